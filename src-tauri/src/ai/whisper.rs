@@ -1,6 +1,9 @@
+use crate::error::ClipForgeError;
 use crate::models::caption::Caption;
+use crate::models::settings::{expand_home, WhisperSettings};
+use crate::platform::process::{self, ProcessClass, ProcessError};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 use tokio::fs;
 
 /// Whisper.cpp configuration
@@ -11,54 +14,45 @@ pub struct WhisperConfig {
     pub model_path: String,
     /// Language code (e.g., "en", "es", "auto" for auto-detect)
     pub language: String,
+    /// Number of threads to use
+    pub threads: u32,
 }
 
 impl Default for WhisperConfig {
     fn default() -> Self {
-        // Try to load config from ~/.clipforge/config.json
-        if let Some(config) = Self::load_from_config() {
-            return config;
-        }
-
-        // Fallback to default values
-        Self {
-            executable_path: "whisper-cli".to_string(),
-            model_path: "~/.clipforge/models/ggml-base.en.bin".to_string(),
-            language: "en".to_string(),
-        }
+        Self::from_settings(&WhisperSettings::default(), None)
     }
 }
 
 impl WhisperConfig {
-    /// Load whisper config from ~/.clipforge/config.json
-    fn load_from_config() -> Option<Self> {
-        use std::fs;
-        use std::path::PathBuf;
-
-        let config_path: PathBuf = dirs::home_dir()?.join(".clipforge").join("config.json");
-
-        if !config_path.exists() {
-            return None;
+    /// Build whisper config from app settings
+    /// `language` overrides the configured default language
+    pub fn from_settings(settings: &WhisperSettings, language: Option<&str>) -> Self {
+        Self {
+            executable_path: settings.executable_path.clone(),
+            model_path: expand_home(&settings.model_path),
+            language: language.unwrap_or(&settings.default_language).to_string(),
+            threads: settings.threads,
         }
+    }
+}
 
-        let content = fs::read_to_string(config_path).ok()?;
-        let config_json: serde_json::Value = serde_json::from_str(&content).ok()?;
-
-        let whisper_config = config_json.get("whisper")?;
-
-        Some(Self {
-            executable_path: whisper_config.get("executable_path")?.as_str()?.to_string(),
-            model_path: whisper_config
-                .get("model_path")?
-                .as_str()?
-                .replace("~", &dirs::home_dir()?.to_string_lossy()),
-            language: whisper_config
-                .get("default_language")
-                .and_then(|v| v.as_str())
-                .unwrap_or("en")
-                .to_string(),
-        })
+/// Check that the whisper.cpp executable can be started
+pub async fn check_executable(executable_path: &str) -> Result<(), ClipForgeError> {
+    let mut whisper_check = Command::new(executable_path);
+    whisper_check.arg("--help");
+    let whisper_check = process::output(whisper_check, ProcessClass::Probe).await;
+
+    if matches!(whisper_check, Err(ProcessError::Spawn { .. })) {
+        return Err(ClipForgeError::MissingDependency {
+            which: "whisper".to_string(),
+            message: format!(
+                "Whisper.cpp not found at '{}'. Please install whisper.cpp and ensure it's in PATH or specify the correct path.",
+                executable_path
+            ),
+        });
     }
+    Ok(())
 }
 
 /// Transcribe audio file using whisper.cpp
@@ -66,22 +60,17 @@ impl WhisperConfig {
 pub async fn transcribe_audio(
     audio_path: &Path,
     config: &WhisperConfig,
-) -> Result<PathBuf, String> {
+) -> Result<PathBuf, ClipForgeError> {
     // Validate input file
     if !audio_path.exists() {
-        return Err(format!("Audio file not found: {}", audio_path.display()));
-    }
-
-    // Check if whisper executable exists
-    let whisper_check = Command::new(&config.executable_path).arg("--help").output();
-
-    if whisper_check.is_err() {
-        return Err(format!(
-            "Whisper.cpp not found at '{}'. Please install whisper.cpp and ensure it's in PATH or specify the correct path.",
-            config.executable_path
+        return Err(ClipForgeError::not_found(
+            "audio_file",
+            audio_path.display().to_string(),
         ));
     }
 
+    check_executable(&config.executable_path).await?;
+
     // Output SRT file path
     // Note: whisper-cli appends .srt to the full filename, so audio.wav becomes audio.wav.srt
     let mut output_srt = audio_path.to_path_buf();
@@ -94,6 +83,7 @@ pub async fn transcribe_audio(
     // -osrt: output SRT subtitle file
     // -l: language (or "auto" for detection)
     // -ml: max line length for captions
+    let threads = config.threads.to_string();
     let args = vec![
         "-m",
         &config.model_path,
@@ -105,35 +95,18 @@ pub async fn transcribe_audio(
         "-ml",
         "50", // Max 50 chars per line
         "-t",
-        "4", // Use 4 threads
+        &threads, // Thread count from settings
     ];
 
-    println!(
-        "[WHISPER] Running command: {} {}",
-        config.executable_path,
-        args.join(" ")
-    );
-    println!("[WHISPER] Model path: {}", config.model_path);
-    println!("[WHISPER] Audio path: {}", audio_path.display());
+    // The supervisor logs the full command line, exit status and stderr
+    let mut cmd = Command::new(&config.executable_path);
+    cmd.args(&args);
+    let output = process::output(cmd, ProcessClass::Transcription).await?;
 
-    let output = Command::new(&config.executable_path)
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("Failed to execute whisper.cpp: {}", e))?;
-
-    println!("[WHISPER] Command exit status: {:?}", output.status);
-
-    // Capture both stdout and stderr for debugging
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
-
     if !stdout.is_empty() {
-        println!("[WHISPER] STDOUT:\n{}", stdout);
-    }
-    if !stderr.is_empty() {
-        println!("[WHISPER] STDERR:\n{}", stderr);
+        log::debug!("whisper stdout:\n{}", stdout);
     }
 
     if !output.status.success() {
@@ -142,7 +115,8 @@ pub async fn transcribe_audio(
             output.status.code(),
             stderr,
             stdout
-        ));
+        )
+        .into());
     }
 
     // Verify SRT file was created
@@ -152,13 +126,11 @@ pub async fn transcribe_audio(
             output_srt.display(),
             stderr,
             stdout
-        ));
+        )
+        .into());
     }
 
-    println!(
-        "[WHISPER] SRT file created successfully: {}",
-        output_srt.display()
-    );
+    log::info!("SRT file created: {}", output_srt.display());
     Ok(output_srt)
 }
 
@@ -271,6 +243,24 @@ pub async fn cleanup_srt_file(srt_path: &Path) -> Result<(), String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_whisper_config_from_settings() {
+        let settings = WhisperSettings {
+            executable_path: "/opt/whisper/main".to_string(),
+            model_path: "/models/ggml-base.en.bin".to_string(),
+            default_language: "de".to_string(),
+            threads: 8,
+        };
+
+        let config = WhisperConfig::from_settings(&settings, None);
+        assert_eq!(config.executable_path, "/opt/whisper/main");
+        assert_eq!(config.language, "de");
+        assert_eq!(config.threads, 8);
+
+        let config = WhisperConfig::from_settings(&settings, Some("fr"));
+        assert_eq!(config.language, "fr");
+    }
+
     #[test]
     fn test_parse_srt_time() {
         assert_eq!(parse_srt_time("00:00:01,500"), Some(1.5));
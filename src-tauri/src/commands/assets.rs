@@ -0,0 +1,94 @@
+// Asset protocol URLs for media files
+// The webview can only load local files through Tauri's asset protocol, and
+// only from directories in the asset scope. Paths handed to the frontend are
+// converted to asset URLs here and their directories added to the scope.
+
+use crate::commands::locks::LockState;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Directories already added to the asset protocol scope
+#[derive(Default)]
+pub struct AssetScope {
+    allowed: Mutex<HashSet<PathBuf>>,
+}
+
+impl AssetScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Add `dir` (recursively) to the webview's asset scope
+pub fn allow_asset_dir(app_handle: &AppHandle, dir: &Path) {
+    if let Some(scope) = app_handle.try_state::<AssetScope>() {
+        if !scope.allowed.lock_recovering().insert(dir.to_path_buf()) {
+            return;
+        }
+    }
+    if let Err(e) = app_handle.asset_protocol_scope().allow_directory(dir, true) {
+        eprintln!(
+            "[Assets] Failed to allow {} in asset scope: {}",
+            dir.display(),
+            e
+        );
+    }
+}
+
+/// Asset URL for `path`, allowing its directory in the asset scope first
+pub fn asset_url_for_webview(app_handle: &AppHandle, path: &str) -> String {
+    if let Some(dir) = Path::new(path).parent() {
+        allow_asset_dir(app_handle, dir);
+    }
+    asset_url(path)
+}
+
+/// Asset URL the webview loads `path` from (same as `convertFileSrc`)
+pub fn asset_url(path: &str) -> String {
+    asset_url_for_platform(path, cfg!(windows))
+}
+
+/// Windows webviews serve custom protocols from `https://<scheme>.localhost`
+fn asset_url_for_platform(path: &str, windows: bool) -> String {
+    let encoded = urlencoding::encode(path);
+    if windows {
+        format!("https://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spaces_and_apostrophes() {
+        assert_eq!(
+            asset_url_for_platform("/Users/test/Videos/Bob's clip 1.mov", false),
+            "asset://localhost/%2FUsers%2Ftest%2FVideos%2FBob%27s%20clip%201.mov"
+        );
+    }
+
+    #[test]
+    fn test_unicode_paths() {
+        assert_eq!(
+            asset_url_for_platform("/動画/テスト.mp4", false),
+            "asset://localhost/%2F%E5%8B%95%E7%94%BB%2F%E3%83%86%E3%82%B9%E3%83%88.mp4"
+        );
+        assert_eq!(
+            asset_url_for_platform("/m/café #1?.mp4", false),
+            "asset://localhost/%2Fm%2Fcaf%C3%A9%20%231%3F.mp4"
+        );
+    }
+
+    #[test]
+    fn test_windows_drive_letters() {
+        assert_eq!(
+            asset_url_for_platform(r"C:\Users\Test\My Videos\clip.mp4", true),
+            "https://asset.localhost/C%3A%5CUsers%5CTest%5CMy%20Videos%5Cclip.mp4"
+        );
+    }
+}
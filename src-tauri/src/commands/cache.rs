@@ -0,0 +1,35 @@
+// Cache database maintenance commands
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::error::ClipForgeError;
+use crate::storage::cache::MaintenanceReport;
+use serde::Serialize;
+use tauri::State;
+
+/// `cache_rebuilt` event payload, emitted when a corrupted cache was replaced at startup
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheRebuiltEvent {
+    /// Where the corrupted database was moved
+    pub corrupt_path: String,
+}
+
+/// Evict cache files past the size cap, then check integrity, vacuum, and
+/// optimize the cache database
+#[tauri::command]
+pub async fn run_cache_maintenance(
+    state: State<'_, AppState>,
+) -> Result<MaintenanceReport, ClipForgeError> {
+    let size_cap = state.settings.lock_state()?.cache_size_cap_bytes();
+    let cache_db = state.cache_db.lock_state()?;
+    let report = cache_db.run_maintenance(size_cap)?;
+
+    println!(
+        "[Cache] Maintenance complete: {} -> {} bytes, {} integrity errors, {} files evicted",
+        report.size_before,
+        report.size_after,
+        report.integrity_errors.len(),
+        report.evicted.files
+    );
+
+    Ok(report)
+}
@@ -1,9 +1,17 @@
 use crate::ai::whisper::{parse_srt_file, transcribe_audio, WhisperConfig};
+use crate::commands::events::ProjectEvents;
+use crate::commands::library::{media_clip, update_media_clip};
+use crate::commands::locks::LockState;
 use crate::commands::media::AppState;
+use crate::commands::progress::ProgressThrottle;
+use crate::commands::project::ModificationScope;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::metadata::probe_full;
+use crate::ffmpeg::subtitles::{extract_subtitle_stream, is_text_subtitle_codec};
 use crate::ffmpeg::{extract_audio_to_wav, get_temp_audio_path};
 use crate::models::caption::Caption;
+use crate::storage::work_dir::{self, WorkArea};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
 use tauri::{Manager, State};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,9 +24,15 @@ pub struct CaptionGenerationRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CaptionGenerationProgress {
     pub job_id: String,
+    /// Increases with every event of the job; older events can be dropped
+    #[serde(default)]
+    pub sequence: u64,
     pub progress: f64,
     pub status: String,
     pub message: Option<String>,
+    /// Set on `caption_generation_error` events
+    #[serde(default, skip_deserializing)]
+    pub error: Option<ClipForgeError>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,61 +48,58 @@ pub async fn generate_captions(
     language: String,
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    println!(
-        "[CAPTIONS] generate_captions called for clip: {}, language: {}",
-        clip_id, language
+) -> Result<String, ClipForgeError> {
+    log::info!(
+        "generate_captions called for clip: {}, language: {}",
+        clip_id,
+        language
     );
 
     let job_id = uuid::Uuid::new_v4().to_string();
 
     // Find the media clip
-    let media_library = state.media_library.lock().unwrap();
-    let clip = media_library
-        .iter()
-        .find(|c| c.id == clip_id)
-        .ok_or_else(|| format!("Media clip not found: {}", clip_id))?;
-
-    let source_path = clip.source_path.clone();
+    let clip = media_clip(&state, &clip_id)?;
+    let source_path = clip.source_path;
+    let audio_stream = clip.audio_stream_index;
     let clip_id_copy = clip_id.clone();
     let has_audio = clip.has_audio;
-    drop(media_library);
 
-    println!(
-        "[CAPTIONS] Clip found: {}, has_audio: {}",
-        source_path, has_audio
-    );
+    log::debug!("Clip found: {}, has_audio: {}", source_path, has_audio);
 
     // Validate clip has audio
     if !has_audio {
-        return Err("Media clip does not contain audio track".to_string());
+        return Err(ClipForgeError::validation(
+            "clip_id",
+            "Media clip does not contain audio track",
+        ));
     }
 
     // Spawn background task for caption generation
     let job_id_clone = job_id.clone();
     let language_clone = language.clone();
-    let state_clone = Arc::new(state.inner().clone());
 
-    tokio::spawn(async move {
-        println!(
-            "[CAPTIONS] Background task started for job: {}",
-            job_id_clone
-        );
+    let label = clip.name;
+    let task = tokio::spawn(async move {
+        log::debug!("Background task started for job: {}", job_id_clone);
+        // The task outlives the command; it reaches the shared state through
+        // the app handle and locks it only for each short read or write
+        let state = app_handle.state::<AppState>();
 
+        let mut progress = CaptionProgress::new(&app_handle, &job_id_clone);
         let result = generate_captions_task(
-            &job_id_clone,
+            &mut progress,
             &clip_id_copy,
             &source_path,
+            audio_stream,
             &language_clone,
-            app_handle.clone(),
-            state_clone.clone(),
+            &state,
         )
         .await;
 
         match result {
             Ok(captions) => {
-                println!(
-                    "[CAPTIONS] Caption generation successful! Generated {} captions",
+                log::info!(
+                    "Caption generation successful, generated {} captions",
                     captions.len()
                 );
 
@@ -102,160 +113,221 @@ pub async fn generate_captions(
                 );
 
                 // Update media clip with captions
-                let mut media_library = state_clone.media_library.lock().unwrap();
-                if let Some(clip) = media_library.iter_mut().find(|c| c.id == clip_id_copy) {
-                    clip.captions = captions;
-                    println!("[CAPTIONS] Updated media clip with captions");
+                if let Err(e) = set_captions(&state, &app_handle, &clip_id_copy, captions) {
+                    log::warn!("Captions not attached to {}: {}", clip_id_copy, e);
                 }
             }
             Err(e) => {
-                println!("[CAPTIONS] Caption generation FAILED: {}", e);
+                log::error!("Caption generation failed: {}", e);
 
                 // Emit error event
-                let _ = app_handle.emit_all(
-                    "caption_generation_error",
-                    CaptionGenerationProgress {
-                        job_id: job_id_clone,
-                        progress: 0.0,
-                        status: "error".to_string(),
-                        message: Some(e),
-                    },
-                );
+                progress.emit_error(e);
             }
         }
+        state.caption_jobs.remove(&job_id_clone);
     });
+    state
+        .caption_jobs
+        .insert(job_id.clone(), label, task.abort_handle());
 
-    println!("[CAPTIONS] Returning job_id: {}", job_id);
+    log::debug!("Returning job_id: {}", job_id);
     Ok(job_id)
 }
 
 /// Background task to generate captions
 async fn generate_captions_task(
-    job_id: &str,
+    progress: &mut CaptionProgress<'_>,
     clip_id: &str,
     source_path: &str,
+    audio_stream: Option<u32>,
     language: &str,
-    app_handle: tauri::AppHandle,
-    _state: Arc<AppState>,
-) -> Result<Vec<Caption>, String> {
-    println!("[CAPTIONS TASK] Starting for clip: {}", clip_id);
+    state: &AppState,
+) -> Result<Vec<Caption>, ClipForgeError> {
+    log::debug!("Caption task starting for clip: {}", clip_id);
 
     // Step 1: Extract audio
-    emit_progress(
-        &app_handle,
-        job_id,
+    progress.emit(
         0.1,
         "extracting_audio",
         Some("Extracting audio from video..."),
     );
-    println!("[CAPTIONS TASK] Step 1: Extracting audio...");
+    log::debug!("Step 1: Extracting audio...");
 
-    let audio_path = get_temp_audio_path(clip_id);
-    println!("[CAPTIONS TASK] Audio path: {:?}", audio_path);
+    let audio_path = get_temp_audio_path(clip_id)?;
+    log::debug!("Audio path: {:?}", audio_path);
 
-    match extract_audio_to_wav(source_path, audio_path.to_str().unwrap()).await {
-        Ok(_) => println!("[CAPTIONS TASK] Audio extracted successfully"),
+    match extract_audio_to_wav(source_path, audio_stream, audio_path.to_str().unwrap()).await {
+        Ok(_) => log::debug!("Audio extracted successfully"),
         Err(e) => {
-            println!("[CAPTIONS TASK] Audio extraction FAILED: {}", e);
-            return Err(e);
+            log::error!("Audio extraction failed: {}", e);
+            return Err(e.into());
         }
     }
 
     // Step 2: Transcribe audio with Whisper
-    emit_progress(
-        &app_handle,
-        job_id,
-        0.3,
-        "transcribing",
-        Some("Transcribing audio with AI..."),
-    );
-    println!("[CAPTIONS TASK] Step 2: Transcribing with Whisper...");
+    progress.emit(0.3, "transcribing", Some("Transcribing audio with AI..."));
+    log::debug!("Step 2: Transcribing with Whisper...");
 
-    let whisper_config = WhisperConfig {
-        language: language.to_string(),
-        ..Default::default()
-    };
+    let whisper_settings = state.settings.lock_state()?.whisper.clone();
+    let whisper_config = WhisperConfig::from_settings(&whisper_settings, Some(language));
 
-    println!(
-        "[CAPTIONS TASK] Whisper config: executable={}, model={}, lang={}",
-        whisper_config.executable_path, whisper_config.model_path, whisper_config.language
+    log::debug!(
+        "Whisper config: executable={}, model={}, lang={}",
+        whisper_config.executable_path,
+        whisper_config.model_path,
+        whisper_config.language
     );
 
     let srt_path = match transcribe_audio(&audio_path, &whisper_config).await {
         Ok(path) => {
-            println!(
-                "[CAPTIONS TASK] Transcription successful! SRT file: {:?}",
-                path
-            );
+            log::info!("Transcription successful, SRT file: {:?}", path);
             path
         }
         Err(e) => {
-            println!("[CAPTIONS TASK] Transcription FAILED: {}", e);
+            log::error!("Transcription failed: {}", e);
             let _ = tokio::fs::remove_file(audio_path).await;
             return Err(e);
         }
     };
 
     // Step 3: Parse SRT file
-    emit_progress(
-        &app_handle,
-        job_id,
-        0.9,
-        "parsing",
-        Some("Parsing captions..."),
-    );
-    println!("[CAPTIONS TASK] Step 3: Parsing SRT file...");
+    progress.emit(0.9, "parsing", Some("Parsing captions..."));
+    log::debug!("Step 3: Parsing SRT file...");
 
     let captions = match parse_srt_file(&srt_path, clip_id.to_string(), language.to_string()).await
     {
         Ok(caps) => {
-            println!("[CAPTIONS TASK] Parsed {} captions", caps.len());
+            log::debug!("Parsed {} captions", caps.len());
             caps
         }
         Err(e) => {
-            println!("[CAPTIONS TASK] Parsing FAILED: {}", e);
+            log::error!("Parsing failed: {}", e);
             let _ = tokio::fs::remove_file(audio_path).await;
             let _ = tokio::fs::remove_file(srt_path).await;
-            return Err(e);
+            return Err(e.into());
         }
     };
 
     // Step 4: Cleanup
-    emit_progress(
-        &app_handle,
-        job_id,
-        1.0,
-        "complete",
-        Some("Caption generation complete!"),
-    );
-    println!("[CAPTIONS TASK] Step 4: Cleanup...");
+    progress.emit(1.0, "complete", Some("Caption generation complete!"));
+    log::debug!("Step 4: Cleanup...");
 
     // Clean up temporary files
     let _ = tokio::fs::remove_file(audio_path).await;
     let _ = tokio::fs::remove_file(srt_path).await;
 
-    println!("[CAPTIONS TASK] Task completed successfully!");
+    log::info!("Caption task completed");
 
     Ok(captions)
 }
 
-/// Emit progress event
-fn emit_progress(
-    app_handle: &tauri::AppHandle,
-    job_id: &str,
-    progress: f64,
-    status: &str,
-    message: Option<&str>,
-) {
-    let _ = app_handle.emit_all(
-        "caption_generation_progress",
+/// Emits a caption job's progress events, each stage change in order
+struct CaptionProgress<'a> {
+    app_handle: &'a tauri::AppHandle,
+    job_id: &'a str,
+    throttle: ProgressThrottle,
+}
+
+impl<'a> CaptionProgress<'a> {
+    fn new(app_handle: &'a tauri::AppHandle, job_id: &'a str) -> Self {
+        Self {
+            app_handle,
+            job_id,
+            throttle: ProgressThrottle::new(),
+        }
+    }
+
+    /// Emit progress event
+    fn emit(&mut self, progress: f64, status: &str, message: Option<&str>) {
+        self.app_handle
+            .state::<AppState>()
+            .caption_jobs
+            .set_progress(self.job_id, progress);
+        self.emit_as("caption_generation_progress", progress, status, message);
+    }
+
+    fn emit_as(&mut self, event: &str, progress: f64, status: &str, message: Option<&str>) {
+        let event_payload = self.payload(progress, status, message.map(|s| s.to_string()));
+        let _ = self.app_handle.emit_all(event, event_payload);
+    }
+
+    /// Emit `caption_generation_error` with the failure
+    fn emit_error(&mut self, error: ClipForgeError) {
+        let mut event_payload = self.payload(0.0, "error", Some(error.to_string()));
+        event_payload.error = Some(error);
+        let _ = self
+            .app_handle
+            .emit_all("caption_generation_error", event_payload);
+    }
+
+    fn payload(
+        &mut self,
+        progress: f64,
+        status: &str,
+        message: Option<String>,
+    ) -> CaptionGenerationProgress {
         CaptionGenerationProgress {
-            job_id: job_id.to_string(),
+            job_id: self.job_id.to_string(),
+            sequence: self.throttle.always(progress),
             progress,
             status: status.to_string(),
-            message: message.map(|s| s.to_string()),
-        },
-    );
+            message,
+            error: None,
+        }
+    }
+}
+
+/// Emit `caption_generation_error` for a job cancelled from the job list
+pub fn emit_cancelled(app_handle: &tauri::AppHandle, job_id: &str) {
+    CaptionProgress::new(app_handle, job_id).emit_error(ClipForgeError::Cancelled);
+}
+
+/// Import an embedded text subtitle stream as the clip's captions
+///
+/// Replaces existing captions, like generating them does. The caption
+/// language is `language`, else the stream's language tag.
+#[tauri::command]
+pub async fn import_subtitle_stream(
+    clip_id: String,
+    stream_index: u32,
+    language: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<Caption>, ClipForgeError> {
+    let source_path = media_clip(&state, &clip_id)?.source_path;
+
+    let probe = probe_full(&source_path).await?;
+    let stream = probe
+        .streams_of_type("subtitle")
+        .find(|s| s.index == stream_index)
+        .ok_or_else(|| {
+            ClipForgeError::validation(
+                "stream_index",
+                format!("Stream {} is not a subtitle stream", stream_index),
+            )
+        })?;
+    let codec = stream.codec.as_deref().unwrap_or("unknown");
+    if !is_text_subtitle_codec(codec) {
+        return Err(ClipForgeError::unsupported(format!(
+            "Subtitle stream {} uses {}, which is an image format and cannot be imported",
+            stream_index, codec
+        )));
+    }
+    let language = language
+        .or_else(|| stream.language.clone())
+        .unwrap_or_else(|| "und".to_string());
+
+    let srt_path = work_dir::area_dir(WorkArea::Captions)?
+        .join(format!("clipforge_subs_{}_{}.srt", clip_id, stream_index));
+    let srt_path_str = srt_path.to_str().ok_or("Invalid subtitle path")?;
+    extract_subtitle_stream(&source_path, stream_index, srt_path_str).await?;
+    let parsed = parse_srt_file(&srt_path, clip_id.clone(), language).await;
+    let _ = tokio::fs::remove_file(&srt_path).await;
+    let captions = parsed?;
+
+    set_captions(&state, &app_handle, &clip_id, captions.clone())?;
+
+    Ok(captions)
 }
 
 /// Update caption text and timing
@@ -267,35 +339,38 @@ pub async fn update_caption(
     start_time: Option<f64>,
     end_time: Option<f64>,
     state: State<'_, AppState>,
-) -> Result<Caption, String> {
-    let mut media_library = state.media_library.lock().unwrap();
-
-    let clip = media_library
-        .iter_mut()
-        .find(|c| c.id == clip_id)
-        .ok_or_else(|| format!("Media clip not found: {}", clip_id))?;
-
-    let caption = clip
-        .captions
-        .iter_mut()
-        .find(|c| c.id == caption_id)
-        .ok_or_else(|| format!("Caption not found: {}", caption_id))?;
-
-    // Update fields if provided
-    if let Some(new_text) = text {
-        caption.text = new_text;
-    }
-    if let Some(new_start) = start_time {
-        caption.start_time = new_start;
-    }
-    if let Some(new_end) = end_time {
-        caption.end_time = new_end;
-    }
-
-    // Validate updated caption
-    caption.validate()?;
+    app_handle: tauri::AppHandle,
+) -> Result<Caption, ClipForgeError> {
+    update_media_clip(
+        &state,
+        &app_handle,
+        &clip_id,
+        ModificationScope::Captions,
+        |clip| {
+            let caption = clip
+                .captions
+                .iter_mut()
+                .find(|c| c.id == caption_id)
+                .ok_or_else(|| ClipForgeError::not_found("caption", caption_id.as_str()))?;
+
+            // Update fields if provided
+            if let Some(new_text) = text {
+                caption.text = new_text;
+            }
+            if let Some(new_start) = start_time {
+                caption.start_time = new_start;
+            }
+            if let Some(new_end) = end_time {
+                caption.end_time = new_end;
+            }
 
-    Ok(caption.clone())
+            // Validate updated caption
+            caption
+                .validate()
+                .map_err(|e| ClipForgeError::validation("caption", e))?;
+            Ok(caption.clone())
+        },
+    )
 }
 
 /// Delete a caption
@@ -304,20 +379,40 @@ pub async fn delete_caption(
     clip_id: String,
     caption_id: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let mut media_library = state.media_library.lock().unwrap();
-
-    let clip = media_library
-        .iter_mut()
-        .find(|c| c.id == clip_id)
-        .ok_or_else(|| format!("Media clip not found: {}", clip_id))?;
-
-    let initial_len = clip.captions.len();
-    clip.captions.retain(|c| c.id != caption_id);
-
-    if clip.captions.len() == initial_len {
-        return Err(format!("Caption not found: {}", caption_id));
-    }
+    app_handle: tauri::AppHandle,
+) -> Result<(), ClipForgeError> {
+    update_media_clip(
+        &state,
+        &app_handle,
+        &clip_id,
+        ModificationScope::Captions,
+        |clip| {
+            let initial_len = clip.captions.len();
+            clip.captions.retain(|c| c.id != caption_id);
+
+            if clip.captions.len() == initial_len {
+                return Err(ClipForgeError::not_found("caption", caption_id));
+            }
+            Ok(())
+        },
+    )
+}
 
-    Ok(())
+/// Replace a clip's captions
+pub(crate) fn set_captions(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    clip_id: &str,
+    captions: Vec<Caption>,
+) -> Result<(), ClipForgeError> {
+    update_media_clip(
+        state,
+        events,
+        clip_id,
+        ModificationScope::Captions,
+        |clip| {
+            clip.captions = captions;
+            Ok(())
+        },
+    )
 }
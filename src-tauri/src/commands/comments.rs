@@ -0,0 +1,167 @@
+// Clip notes and review comments
+// Notes and comments live on the timeline clips, so they save with the
+// project and follow the clips through moves, splits and trims (see
+// `TimelineClip::retarget_comments`). Only the clips of the open timeline are
+// searched; clips inside compound clips are reached by entering the compound.
+
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::error::ClipForgeError;
+use crate::models::project::Project;
+use crate::models::timeline::{Comment, TimelineClip};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+/// A comment with the clip it's on, for the review sidebar
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipComment {
+    pub clip_id: String,
+    pub track_id: String,
+    /// Timeline time the comment points at
+    pub time: f64,
+    pub comment: Comment,
+}
+
+/// Comments across the timeline in timeline order, optionally with the
+/// resolved ones
+pub fn collect_comments(project: &Project, include_resolved: bool) -> Vec<ClipComment> {
+    let mut comments: Vec<ClipComment> = project
+        .tracks
+        .iter()
+        .flat_map(|track| &track.clips)
+        .flat_map(|clip| {
+            clip.comments
+                .iter()
+                .filter(move |c| include_resolved || !c.resolved)
+                .map(move |comment| ClipComment {
+                    clip_id: clip.id.clone(),
+                    track_id: clip.track_id.clone(),
+                    time: clip.start_time + comment.time_offset,
+                    comment: comment.clone(),
+                })
+        })
+        .collect();
+    comments.sort_by(|a, b| {
+        a.time
+            .total_cmp(&b.time)
+            .then_with(|| a.comment.created_at.cmp(&b.comment.created_at))
+    });
+    comments
+}
+
+/// Run `edit` on a timeline clip of the open project and record the change
+fn edit_clip<T>(
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+    clip_id: &str,
+    edit: impl FnOnce(&mut TimelineClip) -> Result<T, ClipForgeError>,
+) -> Result<T, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let clip = project
+        .tracks
+        .iter_mut()
+        .flat_map(|track| track.clips.iter_mut())
+        .find(|c| c.id == clip_id)
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", clip_id))?;
+    let result = edit(clip)?;
+
+    record_modification(
+        app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![clip_id.to_string()],
+    );
+    Ok(result)
+}
+
+/// Set or clear (None or blank) a timeline clip's notes
+#[tauri::command]
+pub async fn set_clip_notes(
+    clip_id: String,
+    notes: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
+    let notes = notes.filter(|n| !n.trim().is_empty());
+    edit_clip(&state, &app_handle, &clip_id, |clip| {
+        clip.notes = notes;
+        Ok(clip.clone())
+    })
+}
+
+/// Comment on a timeline clip `time_offset` seconds from its start
+#[tauri::command]
+pub async fn add_clip_comment(
+    clip_id: String,
+    time_offset: f64,
+    text: String,
+    author: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Comment, ClipForgeError> {
+    if text.trim().is_empty() {
+        return Err(ClipForgeError::validation(
+            "text",
+            "Comment text can't be empty",
+        ));
+    }
+    edit_clip(&state, &app_handle, &clip_id, |clip| {
+        if !time_offset.is_finite() || time_offset < 0.0 || time_offset > clip.duration() {
+            return Err(ClipForgeError::validation(
+                "time_offset",
+                format!(
+                    "Comment time must be within the clip (0-{:.3}s)",
+                    clip.duration()
+                ),
+            ));
+        }
+        let comment = Comment::new(time_offset, text, author);
+        clip.comments.push(comment.clone());
+        Ok(comment)
+    })
+}
+
+/// Mark a comment resolved, or open it again with `resolved: false`
+#[tauri::command]
+pub async fn resolve_clip_comment(
+    clip_id: String,
+    comment_id: String,
+    resolved: Option<bool>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Comment, ClipForgeError> {
+    edit_clip(&state, &app_handle, &clip_id, |clip| {
+        let comment = clip
+            .comments
+            .iter_mut()
+            .find(|c| c.id == comment_id)
+            .ok_or_else(|| ClipForgeError::not_found("comment", comment_id.as_str()))?;
+        comment.resolved = resolved.unwrap_or(true);
+        Ok(comment.clone())
+    })
+}
+
+/// Comments on the timeline in timeline order
+#[tauri::command]
+pub async fn list_comments(
+    include_resolved: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipComment>, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    Ok(collect_comments(project, include_resolved))
+}
+
+/// Unresolved comments across the project, for the review sidebar
+#[tauri::command]
+pub async fn get_all_open_comments(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipComment>, ClipForgeError> {
+    list_comments(false, state).await
+}
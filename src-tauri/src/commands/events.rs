@@ -0,0 +1,88 @@
+// Event sinks
+// Helpers that announce changes emit through an `EventSink` instead of the
+// app handle directly, so tests can capture what a code path emits.
+
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::models::project::Project;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Something events can be emitted to
+pub trait EventSink {
+    fn emit_event<P: Serialize + Clone>(&self, event: &str, payload: P);
+}
+
+/// Emits to every window
+impl EventSink for AppHandle {
+    fn emit_event<P: Serialize + Clone>(&self, event: &str, payload: P) {
+        let _ = self.emit_all(event, payload);
+    }
+}
+
+/// Where changes to the open project are recorded and announced
+pub trait ProjectEvents: EventSink {
+    /// Mark `project` modified (see `record_modification`)
+    fn project_modified(
+        &self,
+        project: &mut Project,
+        scope: ModificationScope,
+        entity_ids: Vec<String>,
+    );
+}
+
+impl ProjectEvents for AppHandle {
+    fn project_modified(
+        &self,
+        project: &mut Project,
+        scope: ModificationScope,
+        entity_ids: Vec<String>,
+    ) {
+        record_modification(self, project, scope, entity_ids);
+    }
+}
+
+/// Records events (name and JSON payload) instead of emitting them
+#[cfg(test)]
+#[derive(Default)]
+pub struct CapturedEvents {
+    events: std::cell::RefCell<Vec<(String, serde_json::Value)>>,
+}
+
+#[cfg(test)]
+impl CapturedEvents {
+    /// Payloads of the captured `event`s, oldest first
+    pub fn named(&self, event: &str) -> Vec<serde_json::Value> {
+        self.events
+            .borrow()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.events.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+impl EventSink for CapturedEvents {
+    fn emit_event<P: Serialize + Clone>(&self, event: &str, payload: P) {
+        self.events
+            .borrow_mut()
+            .push((event.to_string(), serde_json::to_value(payload).unwrap()));
+    }
+}
+
+/// Only marks the project modified: its state and preview events need an app
+#[cfg(test)]
+impl ProjectEvents for CapturedEvents {
+    fn project_modified(
+        &self,
+        project: &mut Project,
+        _scope: ModificationScope,
+        _entity_ids: Vec<String>,
+    ) {
+        project.mark_modified();
+    }
+}
@@ -1,35 +1,269 @@
+use crate::commands::jobs::{ActiveJob, ActiveJobKind};
+use crate::commands::locks::LockState;
+use crate::commands::progress::ProgressThrottle;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::analysis::parse_loudnorm;
+use crate::ffmpeg::capabilities::{self, HardwareEncoders};
+use crate::ffmpeg::caption_sidecar::write_caption_sidecar;
+use crate::ffmpeg::chapters::{map_chapters, write_chapters_file};
+use crate::ffmpeg::clip_export::{plan_clip_exports, source_media, ClipExport, ClipExportFile};
+use crate::ffmpeg::errors::{classify, FfmpegError};
 use crate::ffmpeg::export::{
-    build_export_command, calculate_timeline_duration, generate_concat_file, parse_progress,
-    ExportJob, ExportStatus,
+    audio_render_builder, probe_trim_keyframes, write_concat_file, EncodeParams, ExportJob,
+    ExportPlan, ExportProgress, ExportStatus, ExportTiming, ProgressReader, TrimWarning,
 };
-use crate::models::export::ExportSettings;
+use crate::ffmpeg::frames::{build_still_command, full_frame_clip_at, SourceFrame, StillFormat};
+use crate::ffmpeg::locate::require_ffmpeg;
+use crate::ffmpeg::parallel::{
+    join_parts_builder, plan_parts, prepare_parts, split_ranges, PartProgress, PARTS_PER_WORKER,
+};
+use crate::ffmpeg::preflight::{self, PreflightReport};
+use crate::ffmpeg::retry::{fallback_for, Fallback};
+use crate::ffmpeg::scheduler::{
+    default_limit, path_label, scheduler, JobKind, JobState, QueuedJob, Scheduler,
+};
+use crate::models::export::{
+    ExportHistoryEntry, ExportSettings, GapHandling, VideoCodec, LOUDNESS_TARGET_RANGE,
+};
+use crate::models::project::Project;
+use crate::platform::process::{self, ProcessClass};
+use crate::storage::work_dir::{JobDir, WorkArea};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::{Child, Command};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Manager, State};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as TokioCommand;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 /// Shared state for export jobs
+///
+/// Exports wait in `queue` and render `max_concurrent_exports` at a time, in
+/// the order they were started; each then also takes a slot in the shared
+/// FFmpeg scheduler.
 #[derive(Clone)]
 pub struct ExportState {
     jobs: Arc<Mutex<HashMap<String, ExportJobHandle>>>,
+    queue: Scheduler,
 }
 
 struct ExportJobHandle {
     job: ExportJob,
-    process: Option<Child>,
+    /// Process ids of the FFmpeg processes rendering it (several in a
+    /// parallel export)
+    pids: Vec<u32>,
+    history: ExportHistoryEntry,
+    task: Option<tokio::task::AbortHandle>,
+    /// 0.0 - 1.0, once rendering reports it
+    progress: Option<f64>,
+    /// Id in the export queue, once queued
+    queue_id: Option<u64>,
+}
+
+impl ExportJobHandle {
+    /// Stop the render and remove its partial output; returns the finished history entry
+    ///
+    /// A rendering job's FFmpeg processes are killed and its task, seeing
+    /// the job cancelled, cleans up after them; any other job's task is
+    /// aborted (a queued job never starts FFmpeg).
+    fn cancel(&mut self) -> ExportHistoryEntry {
+        let pids = std::mem::take(&mut self.pids);
+        if pids.is_empty() {
+            if let Some(task) = self.task.take() {
+                task.abort();
+            }
+        }
+        for pid in pids {
+            process::kill_tree(pid);
+        }
+        let _ = std::fs::remove_file(&self.job.output_path);
+
+        self.job.status = ExportStatus::Cancelled;
+        self.history.finish(ExportStatus::Cancelled, None);
+        self.history.clone()
+    }
+}
+
+impl Default for ExportState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ExportState {
     pub fn new() -> Self {
         Self {
             jobs: Arc::new(Mutex::new(HashMap::new())),
+            queue: Scheduler::new(1),
+        }
+    }
+
+    /// Register a new export job
+    pub fn insert_job(&self, job: ExportJob, history: ExportHistoryEntry) {
+        let mut jobs = self.jobs.lock_recovering();
+        jobs.insert(
+            job.id.clone(),
+            ExportJobHandle {
+                job,
+                pids: Vec::new(),
+                history,
+                task: None,
+                progress: None,
+                queue_id: None,
+            },
+        );
+    }
+
+    /// Put a registered job in the export queue, `limit` rendering at once
+    ///
+    /// The job renders once `QueuedJob::started` returns; cancelling it
+    /// before then drops it from the queue without running FFmpeg.
+    pub fn enqueue(&self, job_id: &str, limit: u32) -> QueuedJob {
+        self.queue.set_limit(limit as usize);
+        let queued = self.queue.enqueue(JobKind::Export, job_id);
+        if let Some(handle) = self.jobs.lock_recovering().get_mut(job_id) {
+            handle.queue_id = Some(queued.id());
+        }
+        queued
+    }
+
+    /// Exports that will start before the queued job; None once it renders
+    pub fn queue_position(&self, job_id: &str) -> Option<usize> {
+        let queue_id = self.jobs.lock_recovering().get(job_id)?.queue_id?;
+        self.queue.queue_position(queue_id)
+    }
+
+    /// Every export of this session, oldest first
+    pub fn list_jobs(&self) -> Vec<ExportJobInfo> {
+        let jobs = self.jobs.lock_recovering();
+        let mut listed: Vec<(&ExportJobHandle, ExportJobInfo)> = jobs
+            .values()
+            .map(|handle| {
+                let info = ExportJobInfo {
+                    id: handle.job.id.clone(),
+                    output_path: handle.job.output_path.clone(),
+                    status: handle.job.status,
+                    progress: handle.progress,
+                    queue_position: handle
+                        .queue_id
+                        .filter(|_| handle.job.status == ExportStatus::Queued)
+                        .and_then(|id| self.queue.queue_position(id)),
+                };
+                (handle, info)
+            })
+            .collect();
+        listed.sort_by(|(a, _), (b, _)| {
+            (a.history.started_at, &a.job.id).cmp(&(b.history.started_at, &b.job.id))
+        });
+        listed.into_iter().map(|(_, info)| info).collect()
+    }
+
+    /// Whether a job has finished (or was cancelled or removed)
+    fn is_finished(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock_recovering();
+        jobs.get(job_id)
+            .is_none_or(|handle| handle.job.status.is_finished())
+    }
+
+    fn is_cancelled(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock_recovering();
+        jobs.get(job_id)
+            .is_some_and(|handle| handle.job.status == ExportStatus::Cancelled)
+    }
+
+    /// Record an FFmpeg process rendering a job, so cancelling can kill it
+    ///
+    /// A process started after the job was cancelled is killed right away.
+    fn add_pid(&self, job_id: &str, pid: u32) {
+        match self.jobs.lock_recovering().get_mut(job_id) {
+            Some(handle) if !handle.job.status.is_finished() => handle.pids.push(pid),
+            _ => process::kill_tree(pid),
+        }
+    }
+
+    /// Forget a job's FFmpeg process once it exited
+    fn remove_pid(&self, job_id: &str, pid: u32) {
+        if let Some(handle) = self.jobs.lock_recovering().get_mut(job_id) {
+            handle.pids.retain(|p| *p != pid);
+        }
+    }
+
+    /// Kill every FFmpeg process still rendering a job, e.g. the other parts
+    /// of a parallel export one of which failed
+    fn kill_processes(&self, job_id: &str) {
+        let pids = match self.jobs.lock_recovering().get_mut(job_id) {
+            Some(handle) => std::mem::take(&mut handle.pids),
+            None => return,
+        };
+        for pid in pids {
+            process::kill_tree(pid);
+        }
+    }
+
+    /// Attach the background task running an export job
+    pub fn set_task(&self, job_id: &str, task: tokio::task::AbortHandle) {
+        let mut jobs = self.jobs.lock_recovering();
+        if let Some(handle) = jobs.get_mut(job_id) {
+            handle.task = Some(task);
+        }
+    }
+
+    /// Cancel all unfinished export jobs of a project
+    ///
+    /// Aborts the render tasks (FFmpeg is killed when its task is dropped), removes
+    /// partial output, and returns the cancelled history entries for recording.
+    pub fn cancel_jobs_for_project(&self, project_id: &str) -> Vec<ExportHistoryEntry> {
+        let mut jobs = self.jobs.lock_recovering();
+        let mut cancelled = Vec::new();
+
+        for handle in jobs.values_mut() {
+            if handle.job.status.is_finished()
+                || handle.history.project_id.as_deref() != Some(project_id)
+            {
+                continue;
+            }
+
+            cancelled.push(handle.cancel());
+        }
+
+        cancelled
+    }
+
+    /// Cancel one unfinished export job (see `cancel_jobs_for_project`)
+    pub fn cancel_job(&self, job_id: &str) -> Option<ExportHistoryEntry> {
+        let mut jobs = self.jobs.lock_recovering();
+        let handle = jobs.get_mut(job_id)?;
+        (!handle.job.status.is_finished()).then(|| handle.cancel())
+    }
+
+    fn set_progress(&self, job_id: &str, progress: f64) {
+        if let Some(handle) = self.jobs.lock_recovering().get_mut(job_id) {
+            handle.progress = Some(progress);
         }
     }
+
+    /// Unfinished jobs for the job list, labelled with their output file
+    pub fn active_jobs(&self) -> Vec<ActiveJob> {
+        let jobs = self.jobs.lock_recovering();
+        jobs.values()
+            .filter(|handle| !handle.job.status.is_finished())
+            .map(|handle| ActiveJob {
+                id: handle.job.id.clone(),
+                kind: ActiveJobKind::Export,
+                label: path_label(&handle.job.output_path),
+                state: match handle.job.status {
+                    ExportStatus::Queued => JobState::Queued,
+                    _ => JobState::Running,
+                },
+                progress: handle.progress,
+                cancellable: true,
+            })
+            .collect()
+    }
 }
 
 /// Export timeline request
@@ -43,12 +277,31 @@ pub struct ExportRequest {
 #[derive(Debug, Serialize)]
 pub struct ExportJobResponse {
     pub job_id: String,
+    /// File written, which may differ from the request's in extension (see
+    /// `VideoCodec::fixed_extension`)
+    pub output_path: String,
+    /// Timeline end in seconds, gaps included
+    pub timeline_duration: f64,
+    /// Length of the exported file (what progress is measured against)
+    pub render_duration: f64,
+    /// Clips that will start early because `fast_path` kept them on the
+    /// concat demuxer despite trims between keyframes
+    pub warnings: Vec<TrimWarning>,
+    /// Media clips exported from their source because the proxy is gone
+    pub proxy_fallbacks: Vec<String>,
+    /// Markers left out of the chapters
+    pub marker_warnings: Vec<String>,
+    /// Video parts rendered by separate FFmpeg processes (0 = the export
+    /// renders in one piece)
+    pub parallel_parts: usize,
 }
 
 /// Export progress event payload
 #[derive(Debug, Clone, Serialize)]
 pub struct ExportProgressEvent {
     pub job_id: String,
+    /// Increases with every event of the job; older events can be dropped
+    pub sequence: u64,
     pub progress: f64,
     pub current_frame: u64,
     pub total_frames: u64,
@@ -56,18 +309,70 @@ pub struct ExportProgressEvent {
     pub eta_seconds: u64,
 }
 
+/// An export job as listed by `list_export_jobs`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportJobInfo {
+    pub id: String,
+    pub output_path: String,
+    pub status: ExportStatus,
+    /// 0.0 - 1.0, once rendering reports it
+    pub progress: Option<f64>,
+    /// Exports that will start first, while the job is queued
+    pub queue_position: Option<usize>,
+}
+
+/// `export_queued` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportQueuedEvent {
+    pub job_id: String,
+    /// Exports that will start first (0 = starts next, or right away)
+    pub position: usize,
+}
+
+/// `export_started` event payload: the job left the queue and renders now
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportStartedEvent {
+    pub job_id: String,
+}
+
+/// `export_clip_progress` event payload: progress of one file of a batch
+/// clip export, alongside the job's `export_progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportClipProgressEvent {
+    pub job_id: String,
+    pub clip_id: String,
+    /// Position of the clip on the main track, from 1
+    pub index: usize,
+    /// 0.0 - 1.0 of this clip's file
+    pub progress: f64,
+}
+
+/// What `export_clips_individually` started: the export job and the files
+/// it writes, in timeline order
+#[derive(Debug, Serialize)]
+pub struct ClipExportManifest {
+    pub job_id: String,
+    pub directory: String,
+    pub files: Vec<ClipExportFile>,
+}
+
 /// Export complete event payload
 #[derive(Debug, Clone, Serialize)]
 pub struct ExportCompleteEvent {
     pub job_id: String,
     pub output_path: String,
+    /// Caption file written next to the output (`caption_sidecar` setting)
+    pub caption_sidecar: Option<String>,
 }
 
 /// Export error event payload
 #[derive(Debug, Clone, Serialize)]
 pub struct ExportErrorEvent {
     pub job_id: String,
+    /// User-facing message
     pub error: String,
+    /// Classified failure with the relevant FFmpeg output
+    pub details: ClipForgeError,
 }
 
 /// Export cancelled event payload
@@ -76,6 +381,251 @@ pub struct ExportCancelledEvent {
     pub job_id: String,
 }
 
+/// `export_fallback` event payload: the export is re-run with fallback arguments
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportFallbackEvent {
+    pub job_id: String,
+    pub fallback: &'static Fallback,
+    /// The failure that triggered the retry
+    pub error: FfmpegError,
+}
+
+/// Validate `settings` and run the preflight of exporting the open project
+/// to `output_path`
+///
+/// Compound clips render as the clips they hold. Proxies removed by a
+/// cache cleanup fall back to their sources in the returned project.
+async fn preflight_project_export(
+    app_state: &AppState,
+    settings: &ExportSettings,
+    output_path: Option<&Path>,
+) -> Result<(Project, ExportTiming, PreflightReport), ClipForgeError> {
+    // Get project data directly from the live AppState (not from cached copy)
+    let mut project = app_state
+        .project
+        .lock_state()?
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?
+        .rendered()?;
+
+    if let Some(range) = &settings.range {
+        range
+            .validate()
+            .map_err(|e| ClipForgeError::validation("range", e))?;
+    }
+    if let Some(watermark) = &settings.watermark {
+        watermark
+            .validate()
+            .map_err(|e| ClipForgeError::validation("watermark", e))?;
+    }
+    settings
+        .validate_metadata()
+        .map_err(|e| ClipForgeError::validation("metadata", e))?;
+    if settings.normalize_audio && !LOUDNESS_TARGET_RANGE.contains(&settings.loudness_target) {
+        return Err(ClipForgeError::validation(
+            "loudness_target",
+            format!(
+                "Loudness target must be between {} and {} LUFS",
+                LOUDNESS_TARGET_RANGE.start(),
+                LOUDNESS_TARGET_RANGE.end()
+            ),
+        ));
+    }
+    if settings.hardware_acceleration {
+        capabilities::ensure_probed().await;
+    }
+    let timing = ExportTiming::new(&project.tracks, settings.gap_handling, settings.range);
+    if timing.render_duration <= 0.0 {
+        return Err(ClipForgeError::validation(
+            "range",
+            "Nothing to export in the selected range",
+        ));
+    }
+    if settings.codec == VideoCodec::Gif {
+        let limit = app_state.settings.lock_state()?.gif_max_seconds;
+        if timing.render_duration > f64::from(limit) {
+            return Err(ClipForgeError::validation(
+                "range",
+                format!(
+                    "GIFs can be at most {} seconds long and this one would be {:.0}; \
+                     export a shorter range or raise the limit in settings",
+                    limit, timing.render_duration
+                ),
+            ));
+        }
+    }
+
+    let encode = EncodeParams::new(settings, &project.settings);
+    let report = preflight::preflight_export(
+        &project.tracks,
+        &mut project.media_library,
+        &timing,
+        &encode,
+        output_path,
+        require_ffmpeg().map_err(|e| e.to_string()),
+        encoder_speed_factor(app_state, settings),
+    );
+    Ok((project, timing, report))
+}
+
+/// How fast earlier exports with `settings`' codec rendered, in timeline
+/// seconds per second; None without history
+fn encoder_speed_factor(app_state: &AppState, settings: &ExportSettings) -> Option<f64> {
+    let speed = app_state
+        .cache_db
+        .lock_recovering()
+        .get_encoder_speed_factor(settings.codec.ffmpeg_codec());
+    speed.unwrap_or_else(|e| {
+        log::warn!("No export time estimate: {}", e);
+        None
+    })
+}
+
+/// Plan an export of the open project
+///
+/// Everything is checked before FFmpeg would reach it (see
+/// `preflight_project_export`); any preflight error fails the plan.
+async fn plan_project_export(
+    app_state: &AppState,
+    settings: &ExportSettings,
+    output_path: Option<&Path>,
+) -> Result<(Project, ExportPlan, PreflightReport), ClipForgeError> {
+    let (project, timing, report) =
+        preflight_project_export(app_state, settings, output_path).await?;
+    if !report.passed() {
+        return Err(ClipForgeError::PreflightFailed {
+            message: report.summary(),
+            report: Box::new(report),
+        });
+    }
+
+    // The concat demuxer starts clips trimmed between keyframes at the
+    // keyframe before; the plan reads those through the accurate path
+    // unless the fast path was asked for
+    let keyframes = probe_trim_keyframes(&project.tracks, &project.media_library, &timing).await;
+    let mut settings = settings.clone();
+    settings.fill_default_metadata(&project.name);
+    let mut plan = ExportPlan::build(
+        &project.tracks,
+        &project.media_library,
+        &settings,
+        &project.settings,
+        &keyframes,
+    )?;
+    if settings.chapters && settings.codec.supports_chapters() {
+        plan.add_chapters(&project.markers);
+    }
+    if settings.caption_sidecar.is_some() {
+        plan.add_captions(&project.tracks, &project.media_library);
+    }
+    plan.encode.threads = app_state.settings.lock_state()?.export_thread_cap;
+    Ok((project, plan, report))
+}
+
+/// What exporting the open project with `settings` would render, for debugging
+#[tauri::command]
+pub async fn preview_export_plan(
+    settings: ExportSettings,
+    app_state: State<'_, AppState>,
+) -> Result<ExportPlan, ClipForgeError> {
+    let (_, plan, _) = plan_project_export(&app_state, &settings, None).await?;
+    Ok(plan)
+}
+
+/// Check that exporting the open project with `settings` to `output_path`
+/// can run, without starting it
+///
+/// `export_timeline` runs the same checks and refuses to start on any error.
+#[tauri::command]
+pub async fn preflight_export(
+    settings: ExportSettings,
+    output_path: String,
+    app_state: State<'_, AppState>,
+) -> Result<PreflightReport, ClipForgeError> {
+    let (_, _, report) =
+        preflight_project_export(&app_state, &settings, Some(Path::new(&output_path))).await?;
+    Ok(report)
+}
+
+/// Save the frame under the playhead at `timeline_time` as a PNG or JPEG
+/// (by `output_path`'s extension), at the full resolution of its source
+///
+/// The frame comes from the clip filling the canvas there (see
+/// `full_frame_clip_at`) and is read from the source file, not the proxy.
+#[tauri::command]
+pub async fn export_frame(
+    timeline_time: f64,
+    output_path: String,
+    app_state: State<'_, AppState>,
+) -> Result<SourceFrame, ClipForgeError> {
+    require_ffmpeg()?;
+    let output = PathBuf::from(&output_path);
+    let format = StillFormat::from_path(&output).ok_or_else(|| {
+        ClipForgeError::validation(
+            "output_path",
+            "Frames can be saved as .png, .jpg or .jpeg files",
+        )
+    })?;
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(ClipForgeError::validation(
+                "output_path",
+                format!("Output directory does not exist: {}", parent.display()),
+            ));
+        }
+    }
+    if !timeline_time.is_finite() || timeline_time < 0.0 {
+        return Err(ClipForgeError::validation(
+            "timeline_time",
+            "Timeline time must be a non-negative number of seconds",
+        ));
+    }
+
+    let (frame, project_name) = {
+        let project = app_state
+            .project
+            .lock_state()?
+            .as_ref()
+            .ok_or_else(ClipForgeError::no_project)?
+            .flattened()?;
+        let clip = full_frame_clip_at(&project, timeline_time).ok_or_else(|| {
+            ClipForgeError::validation(
+                "timeline_time",
+                format!(
+                    "There is no clip at {:.2}s to take a frame from",
+                    timeline_time
+                ),
+            )
+        })?;
+        let media = project
+            .media_library
+            .iter()
+            .find(|m| m.id == clip.media_clip_id)
+            .ok_or_else(|| ClipForgeError::not_found("media_clip", clip.media_clip_id.as_str()))?;
+        (
+            SourceFrame::new(clip, media, timeline_time),
+            project.name.clone(),
+        )
+    };
+
+    let _permit = scheduler().acquire(JobKind::Preview, &project_name).await;
+    let result = process::output(
+        build_still_command(&frame, format, &output),
+        ProcessClass::Thumbnail,
+    )
+    .await?;
+    if !result.status.success() || !output.exists() {
+        return Err(classify(&String::from_utf8_lossy(&result.stderr)).into());
+    }
+    log::info!(
+        "Saved the frame at {:.3}s of {} to {}",
+        frame.source_time,
+        frame.path,
+        output_path
+    );
+    Ok(frame)
+}
+
 /// Export timeline to video file
 #[tauri::command]
 pub async fn export_timeline(
@@ -83,65 +633,202 @@ pub async fn export_timeline(
     export_state: State<'_, ExportState>,
     app_state: State<'_, AppState>,
     app_handle: AppHandle,
-) -> Result<ExportJobResponse, String> {
-    // Get project data directly from the live AppState (not from cached copy)
-    let project = app_state
+) -> Result<ExportJobResponse, ClipForgeError> {
+    start_export(request, None, export_state, app_state, app_handle).await
+}
+
+/// Export again a failed or cancelled job from export history, as a new job
+/// linked to it
+///
+/// The export is planned from the current project with the job's stored
+/// settings and output path, so edits made since are included.
+#[tauri::command]
+pub async fn retry_export(
+    job_id: String,
+    export_state: State<'_, ExportState>,
+    app_state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ExportJobResponse, ClipForgeError> {
+    let entry = app_state
+        .cache_db
+        .lock_state()?
+        .get_export_job(&job_id)?
+        .ok_or_else(|| ClipForgeError::not_found("export_job", job_id.as_str()))?;
+    let project_id = app_state
         .project
-        .lock()
-        .unwrap()
-        .clone()
-        .ok_or_else(|| "No project loaded".to_string())?;
-
-    eprintln!("[Export] Project has {} tracks", project.tracks.len());
-    eprintln!(
-        "[Export] Media library has {} clips",
-        project.media_library.len()
-    );
+        .lock_state()?
+        .as_ref()
+        .map(|project| project.id.clone())
+        .ok_or_else(ClipForgeError::no_project)?;
+    let request = retry_request(&entry, &project_id)?;
+    log::info!("Retrying export {} to {}", job_id, request.output_path);
+    start_export(request, Some(job_id), export_state, app_state, app_handle).await
+}
 
-    // Validate output path
-    let output_path = PathBuf::from(&request.output_path);
-    if let Some(parent) = output_path.parent() {
-        if !parent.exists() {
-            return Err(format!(
-                "Output directory does not exist: {}",
-                parent.display()
-            ));
-        }
+/// The request exporting `entry` again, when it failed or was cancelled in
+/// the open project
+fn retry_request(
+    entry: &ExportHistoryEntry,
+    project_id: &str,
+) -> Result<ExportRequest, ClipForgeError> {
+    if Path::new(&entry.output_path).is_dir() {
+        return Err(ClipForgeError::validation(
+            "job_id",
+            "Batch clip exports can't be retried; export the clips again",
+        ));
+    }
+    if !matches!(entry.status, ExportStatus::Failed | ExportStatus::Cancelled) {
+        return Err(ClipForgeError::validation(
+            "job_id",
+            "Only failed or cancelled exports can be retried",
+        ));
+    }
+    if entry.project_id.as_deref() != Some(project_id) {
+        return Err(ClipForgeError::validation(
+            "job_id",
+            "The export was made from another project; open it to retry",
+        ));
+    }
+    Ok(ExportRequest {
+        output_path: entry.output_path.clone(),
+        settings: entry.settings.clone(),
+    })
+}
+
+/// Plan and queue an export job, rendered by a spawned task; `retry_of` is
+/// the job a retry repeats
+async fn start_export(
+    mut request: ExportRequest,
+    retry_of: Option<String>,
+    export_state: State<'_, ExportState>,
+    app_state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ExportJobResponse, ClipForgeError> {
+    // GIF, ProRes and DNxHR get their own extension whatever name was picked
+    let mut output_path = PathBuf::from(&request.output_path);
+    if request.settings.codec.fixed_extension() {
+        output_path.set_extension(request.settings.codec.extension());
+        request.output_path = output_path.to_string_lossy().to_string();
     }
 
-    // Create temporary directory for concat file
-    let temp_dir = std::env::temp_dir().join(format!("clipforge_export_{}", uuid::Uuid::new_v4()));
-    std::fs::create_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    // Until the gap filler lands, the concat export can only collapse gaps
+    if request.settings.gap_handling == GapHandling::Black {
+        return Err(ClipForgeError::unsupported(
+            "Filling gaps with black is not supported by the export yet",
+        ));
+    }
+    let (project, plan, preflight) =
+        plan_project_export(&app_state, &request.settings, Some(&output_path)).await?;
+
+    log::info!(
+        "Exporting project {} ({} tracks, {} media clips) to {}",
+        project.id,
+        project.tracks.len(),
+        project.media_library.len(),
+        request.output_path
+    );
+
+    // Temporary directory for the concat file, removed with the export task
+    let temp_dir = JobDir::create(WorkArea::Export)?;
+    let concat_file = write_concat_file(&plan.segments, temp_dir.path())?;
 
-    // Generate concat file
-    let concat_file = generate_concat_file(&project.tracks, &project.media_library, &temp_dir)?;
+    // A parallel export cuts the video into parts between clips; a GIF's
+    // palette comes from the whole animation, so GIFs render in one piece
+    let workers = app_state
+        .settings
+        .lock_state()?
+        .parallel_export_workers
+        .map_or_else(|| default_limit().max(2), |workers| workers as usize);
+    let ranges = if request.settings.parallel && !plan.encode.palette {
+        split_ranges(&plan.segments, &plan.timing, workers * PARTS_PER_WORKER)
+    } else {
+        Vec::new()
+    };
+    let parallel = if ranges.len() > 1 {
+        let keyframes =
+            probe_trim_keyframes(&project.tracks, &project.media_library, &plan.timing).await;
+        let mut plans = plan_parts(
+            &project.tracks,
+            &project.media_library,
+            &request.settings,
+            &project.settings,
+            &keyframes,
+            &ranges,
+        )?;
+        // The cap is for the whole export, so the parts running at once share it
+        for part in &mut plans {
+            part.encode.threads = plan
+                .encode
+                .threads
+                .map(|threads| (threads / workers as u32).max(1));
+        }
+        log::info!(
+            "Rendering export in {} parts, {} at a time",
+            plans.len(),
+            workers
+        );
+        Some(prepare_parts(&plans, temp_dir.path())?)
+    } else {
+        None
+    };
+    let parallel_parts = parallel.as_ref().map_or(0, |parts| parts.commands.len());
+    let chapters_file = if plan.chapters.is_empty() {
+        None
+    } else {
+        Some(write_chapters_file(&plan.chapters, temp_dir.path())?)
+    };
+    // Normalized audio is measured first; the render command is built from
+    // the measured plan
+    let analysis = plan.analysis_command_builder(&concat_file);
+    let mut render_plan = plan.clone();
 
-    // Build FFmpeg command
-    let cmd = build_export_command(&concat_file, &output_path, &request.settings)?;
+    // Progress is measured against the output length, not the timeline;
+    // the analysis pass fills the start of the bar
+    let progress = ProgressReader::new(plan.timing.render_duration, plan.encode.fps);
+    let render_start = if analysis.is_some() {
+        LOUDNESS_ANALYSIS_SHARE
+    } else {
+        0.0
+    };
+    let (analysis_progress, progress) = match &analysis {
+        Some(_) => (
+            progress.within(0.0, LOUDNESS_ANALYSIS_SHARE),
+            progress.within(LOUDNESS_ANALYSIS_SHARE, 1.0 - LOUDNESS_ANALYSIS_SHARE),
+        ),
+        None => (progress, progress),
+    };
+    let progress = progress.expected_speed(encoder_speed_factor(&app_state, &request.settings));
 
     // Create export job
     let job_id = uuid::Uuid::new_v4().to_string();
     let job = ExportJob {
         id: job_id.clone(),
         output_path: request.output_path.clone(),
-        status: ExportStatus::Preparing,
+        status: ExportStatus::Queued,
     };
-
-    // Store job in state
-    {
-        let mut jobs = export_state.jobs.lock().unwrap();
-        jobs.insert(
+    let history = ExportHistoryEntry {
+        retry_of,
+        ..ExportHistoryEntry::started(
             job_id.clone(),
-            ExportJobHandle {
-                job: job.clone(),
-                process: None,
-            },
-        );
-    }
+            Some(project.id.clone()),
+            request.output_path.clone(),
+            request.settings.clone(),
+            plan.timing.timeline_duration,
+        )
+    };
+    record_export_history(&app_state, &history);
 
-    // Calculate total duration for progress tracking
-    let total_duration = calculate_timeline_duration(&project.tracks);
+    // Store job in state and queue it behind running exports
+    export_state.insert_job(job, history);
+    let limit = app_state.settings.lock_state()?.max_concurrent_exports;
+    let queued = export_state.enqueue(&job_id, limit);
+    let _ = app_handle.emit_all(
+        "export_queued",
+        ExportQueuedEvent {
+            job_id: job_id.clone(),
+            position: export_state.queue_position(&job_id).unwrap_or(0),
+        },
+    );
 
     // Spawn export task
     let job_id_clone = job_id.clone();
@@ -150,146 +837,790 @@ pub async fn export_timeline(
     let export_state_for_complete = export_state_arc.clone();
     let export_state_for_error = export_state_arc.clone();
     let output_path_clone = request.output_path.clone();
+    let job_label = project.name.clone();
+    let caption_format = request.settings.caption_sidecar;
 
-    tokio::spawn(async move {
-        match run_export(
-            cmd,
-            job_id_clone.clone(),
-            total_duration,
-            app_handle_clone.clone(),
-            export_state_arc,
-        )
-        .await
-        {
+    let task = tokio::spawn(async move {
+        // Held through a retry, so the next export waits for both
+        let _slot = queued.started().await;
+        let _ = app_handle_clone.emit_all(
+            "export_started",
+            ExportStartedEvent {
+                job_id: job_id_clone.clone(),
+            },
+        );
+
+        // Shared by the passes and a retry, so its events keep counting up
+        let mut throttle = ProgressThrottle::new();
+        let mut result = Ok(());
+        if let Some(analysis) = analysis {
+            result = run_export(
+                analysis.build(),
+                job_id_clone.clone(),
+                job_label.clone(),
+                analysis_progress,
+                app_handle_clone.clone(),
+                export_state_arc.clone(),
+                &mut throttle,
+            )
+            .await
+            .map(|stderr| {
+                let measured = parse_loudnorm(&stderr);
+                if measured.is_none() {
+                    log::warn!("Export {} audio is silent; not normalizing", job_id_clone);
+                }
+                render_plan.set_loudness(measured);
+            });
+        }
+        let analysed = result.is_ok();
+        let mut command = render_plan.command_builder(&concat_file, &output_path);
+        if let Some(chapters_file) = &chapters_file {
+            command = map_chapters(command, chapters_file);
+        }
+        if analysed {
+            result = match &parallel {
+                // The video parts and the whole audio render at once, then
+                // are joined without re-encoding
+                Some(parts) => {
+                    let audio_path = temp_dir.path().join("audio.mka");
+                    let audio = audio_render_builder(
+                        &concat_file,
+                        &audio_path,
+                        &render_plan.encode,
+                        &render_plan.streams,
+                    );
+                    let mut commands: Vec<(Command, ProgressReader)> = parts
+                        .commands
+                        .iter()
+                        .zip(&parts.durations)
+                        .map(|(command, duration)| {
+                            let reader = ProgressReader::new(*duration, render_plan.encode.fps);
+                            (command.build(), reader)
+                        })
+                        .collect();
+                    let duration = render_plan.timing.render_duration;
+                    if let Some(audio) = &audio {
+                        commands.push((audio.build(), ProgressReader::new(duration, 0.0)));
+                    }
+                    let part_progress = PartProgress::new(
+                        &parts.durations,
+                        audio.as_ref().map(|_| duration),
+                        render_plan.encode.fps,
+                    )
+                    .within(render_start, (1.0 - render_start) * (1.0 - JOIN_SHARE));
+                    let join_start = part_progress.end();
+                    let rendered = run_parallel(
+                        commands,
+                        workers,
+                        part_progress,
+                        job_id_clone.clone(),
+                        job_label.clone(),
+                        app_handle_clone.clone(),
+                        export_state_arc.clone(),
+                        &mut throttle,
+                    )
+                    .await;
+
+                    let mut join = join_parts_builder(
+                        &parts.parts_file,
+                        audio.is_some().then_some(audio_path.as_path()),
+                        &render_plan.encode,
+                        &output_path,
+                    );
+                    if let Some(chapters_file) = &chapters_file {
+                        join = map_chapters(join, chapters_file);
+                    }
+                    match rendered {
+                        Ok(()) => run_export(
+                            join.build(),
+                            job_id_clone.clone(),
+                            job_label.clone(),
+                            ProgressReader::new(duration, render_plan.encode.fps)
+                                .within(join_start, 1.0 - join_start),
+                            app_handle_clone.clone(),
+                            export_state_arc.clone(),
+                            &mut throttle,
+                        )
+                        .await
+                        .map(drop),
+                        Err(error) => Err(error),
+                    }
+                }
+                None => run_export(
+                    command.build(),
+                    job_id_clone.clone(),
+                    job_label.clone(),
+                    progress,
+                    app_handle_clone.clone(),
+                    export_state_arc.clone(),
+                    &mut throttle,
+                )
+                .await
+                .map(drop),
+            };
+        }
+
+        // Known recoverable render failures get one retry with fallback
+        // arguments; a parallel export's failures aren't retried
+        let fallback = match &result {
+            Err(error)
+                if analysed
+                    && parallel.is_none()
+                    && !export_state_arc.is_finished(&job_id_clone) =>
+            {
+                fallback_for(error).map(|fallback| (fallback, error.clone()))
+            }
+            _ => None,
+        };
+        if let Some((fallback, error)) = fallback {
+            log::warn!(
+                "Export {} failed ({}); retrying once with fallback {}: {}",
+                job_id_clone,
+                error.message,
+                fallback.name,
+                fallback.description
+            );
+            let _ = app_handle_clone.emit_all(
+                "export_fallback",
+                ExportFallbackEvent {
+                    job_id: job_id_clone.clone(),
+                    fallback,
+                    error,
+                },
+            );
+            result = run_export(
+                fallback.apply(&command).build(),
+                job_id_clone.clone(),
+                job_label,
+                progress,
+                app_handle_clone.clone(),
+                export_state_arc,
+                &mut throttle,
+            )
+            .await
+            .map(drop);
+        }
+
+        match result {
+            // Killed by `cancel_export`, which reported it; FFmpeg may have
+            // still held the partial file then
+            _ if export_state_for_complete.is_cancelled(&job_id_clone) => {
+                let _ = std::fs::remove_file(&output_path_clone);
+            }
             Ok(_) => {
+                // A caption file that can't be written doesn't fail the
+                // export it goes with
+                let caption_sidecar =
+                    caption_format.and_then(|format| {
+                        match write_caption_sidecar(&render_plan.captions, format, &output_path) {
+                            Ok(path) => Some(path.to_string_lossy().to_string()),
+                            Err(e) => {
+                                log::warn!("Export {}: {}", job_id_clone, e);
+                                None
+                            }
+                        }
+                    });
+
                 // Emit completion event
                 let _ = app_handle_clone.emit_all(
                     "export_complete",
                     ExportCompleteEvent {
                         job_id: job_id_clone.clone(),
                         output_path: output_path_clone,
+                        caption_sidecar,
                     },
                 );
 
                 // Update job status
-                let mut jobs = export_state_for_complete.jobs.lock().unwrap();
-                if let Some(handle) = jobs.get_mut(&job_id_clone) {
-                    handle.job.status = ExportStatus::Complete;
-                }
+                update_job_status(
+                    &export_state_for_complete,
+                    &app_handle_clone,
+                    &job_id_clone,
+                    ExportStatus::Complete,
+                    None,
+                );
             }
             Err(e) => {
+                log::error!(
+                    "Export {} failed ({:?}): {}\n{}",
+                    job_id_clone,
+                    e.kind,
+                    e.message,
+                    e.excerpt
+                );
+
                 // Emit error event
                 let _ = app_handle_clone.emit_all(
                     "export_error",
                     ExportErrorEvent {
                         job_id: job_id_clone.clone(),
-                        error: e.clone(),
+                        error: e.message.clone(),
+                        details: e.clone().into(),
                     },
                 );
 
                 // Update job status
-                let mut jobs = export_state_for_error.jobs.lock().unwrap();
-                if let Some(handle) = jobs.get_mut(&job_id_clone) {
-                    handle.job.status = ExportStatus::Failed;
-                }
+                update_job_status(
+                    &export_state_for_error,
+                    &app_handle_clone,
+                    &job_id_clone,
+                    ExportStatus::Failed,
+                    Some(e),
+                );
 
                 // Clean up partial file
                 let _ = std::fs::remove_file(&output_path_clone);
             }
         }
+        drop(temp_dir);
+    });
+    export_state.set_task(&job_id, task.abort_handle());
+
+    Ok(ExportJobResponse {
+        job_id,
+        output_path: request.output_path,
+        timeline_duration: plan.timing.timeline_duration,
+        render_duration: plan.timing.render_duration,
+        warnings: plan.warnings,
+        proxy_fallbacks: preflight.proxy_fallbacks,
+        marker_warnings: plan.marker_warnings,
+        parallel_parts,
+    })
+}
 
-        // Clean up temp directory
-        let _ = std::fs::remove_dir_all(&temp_dir);
+/// Export every clip of the main track to a file of its own in `directory`,
+/// from the clips' sources, as one export job
+///
+/// The clips render one after another with `settings` (its range ignored);
+/// `export_progress` covers the whole batch and `export_clip_progress` each
+/// file. `cancel_export` stops the batch, keeping the files already written.
+#[tauri::command]
+pub async fn export_clips_individually(
+    directory: String,
+    settings: ExportSettings,
+    export_state: State<'_, ExportState>,
+    app_state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ClipExportManifest, ClipForgeError> {
+    let directory_path = PathBuf::from(&directory);
+    if !directory_path.is_dir() {
+        return Err(ClipForgeError::validation(
+            "directory",
+            format!("{} is not a folder", directory),
+        ));
+    }
+    let mut settings = ExportSettings {
+        range: None,
+        ..settings
+    };
+    // Checked as one export of the whole timeline into the folder
+    let (project, timing, preflight) =
+        preflight_project_export(&app_state, &settings, Some(&directory_path.join("clip"))).await?;
+    if !preflight.passed() {
+        return Err(ClipForgeError::PreflightFailed {
+            message: preflight.summary(),
+            report: Box::new(preflight),
+        });
+    }
+    settings.fill_default_metadata(&project.name);
+    let media_library = source_media(&project.media_library);
+    let keyframes = probe_trim_keyframes(&project.tracks, &media_library, &timing).await;
+    let mut exports = plan_clip_exports(
+        &project.tracks,
+        &media_library,
+        &settings,
+        &project.settings,
+        &keyframes,
+        &directory_path,
+    )?;
+    let thread_cap = app_state.settings.lock_state()?.export_thread_cap;
+    for export in &mut exports {
+        export.plan.encode.threads = thread_cap;
+    }
+    if exports.is_empty() {
+        return Err(ClipForgeError::validation(
+            "directory",
+            "The main track has no clips to export",
+        ));
+    }
+    log::info!(
+        "Exporting {} clips of project {} to {}",
+        exports.len(),
+        project.id,
+        directory
+    );
+
+    // Each clip's concat file goes in a directory of its own
+    let temp_dir = JobDir::create(WorkArea::Export)?;
+    let mut clips = Vec::new();
+    for export in exports {
+        let dir = temp_dir.path().join(format!("clip{}", export.file.index));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create clip directory: {}", e))?;
+        let concat_file = write_concat_file(&export.plan.segments, &dir)?;
+        clips.push((export, concat_file));
+    }
+    let files: Vec<ClipExportFile> = clips.iter().map(|(e, _)| e.file.clone()).collect();
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let job = ExportJob {
+        id: job_id.clone(),
+        output_path: directory.clone(),
+        status: ExportStatus::Queued,
+    };
+    let history = ExportHistoryEntry::started(
+        job_id.clone(),
+        Some(project.id.clone()),
+        directory.clone(),
+        settings,
+        timing.timeline_duration,
+    );
+    record_export_history(&app_state, &history);
+    export_state.insert_job(job, history);
+    let limit = app_state.settings.lock_state()?.max_concurrent_exports;
+    let queued = export_state.enqueue(&job_id, limit);
+    let _ = app_handle.emit_all(
+        "export_queued",
+        ExportQueuedEvent {
+            job_id: job_id.clone(),
+            position: export_state.queue_position(&job_id).unwrap_or(0),
+        },
+    );
+
+    let task_job_id = job_id.clone();
+    let task_directory = directory.clone();
+    let task_app_handle = app_handle.clone();
+    let task_export_state = Arc::new(export_state.inner().clone());
+    let label = project.name.clone();
+    let task = tokio::spawn(async move {
+        let _slot = queued.started().await;
+        let _ = task_app_handle.emit_all(
+            "export_started",
+            ExportStartedEvent {
+                job_id: task_job_id.clone(),
+            },
+        );
+        let result = run_clip_exports(
+            clips,
+            task_job_id.clone(),
+            label,
+            task_app_handle.clone(),
+            task_export_state.clone(),
+        )
+        .await;
+
+        match result {
+            // Reported by `cancel_export`
+            _ if task_export_state.is_cancelled(&task_job_id) => {}
+            Ok(()) => {
+                let _ = task_app_handle.emit_all(
+                    "export_complete",
+                    ExportCompleteEvent {
+                        job_id: task_job_id.clone(),
+                        output_path: task_directory,
+                        caption_sidecar: None,
+                    },
+                );
+                update_job_status(
+                    &task_export_state,
+                    &task_app_handle,
+                    &task_job_id,
+                    ExportStatus::Complete,
+                    None,
+                );
+            }
+            Err(e) => {
+                log::error!(
+                    "Clip export {} failed ({:?}): {}\n{}",
+                    task_job_id,
+                    e.kind,
+                    e.message,
+                    e.excerpt
+                );
+                let _ = task_app_handle.emit_all(
+                    "export_error",
+                    ExportErrorEvent {
+                        job_id: task_job_id.clone(),
+                        error: e.message.clone(),
+                        details: e.clone().into(),
+                    },
+                );
+                update_job_status(
+                    &task_export_state,
+                    &task_app_handle,
+                    &task_job_id,
+                    ExportStatus::Failed,
+                    Some(e),
+                );
+            }
+        }
+        drop(temp_dir);
     });
+    export_state.set_task(&job_id, task.abort_handle());
 
-    Ok(ExportJobResponse { job_id })
+    Ok(ClipExportManifest {
+        job_id,
+        directory,
+        files,
+    })
 }
 
-/// Run export process and emit progress events
+/// Render a batch clip export's files one after another, each taking the
+/// share of the job's progress its length is of the whole
+///
+/// Normalized audio is measured per clip, in the first part of its share.
+/// The first failure, or a cancel, stops the batch and removes the file
+/// being written.
+async fn run_clip_exports(
+    clips: Vec<(ClipExport, PathBuf)>,
+    job_id: String,
+    label: String,
+    app_handle: AppHandle,
+    export_state: Arc<ExportState>,
+) -> Result<(), FfmpegError> {
+    let permit = scheduler().acquire(JobKind::Export, label).await;
+    let total: f64 = clips.iter().map(|(e, _)| e.file.duration).sum();
+    let mut throttle = ProgressThrottle::new();
+    let mut start = 0.0;
+    for (ClipExport { file, mut plan }, concat_file) in clips {
+        if export_state.is_finished(&job_id) {
+            return Err(FfmpegError::from("Clip export stopped".to_string()));
+        }
+        let share = if total > 0.0 {
+            file.duration / total
+        } else {
+            0.0
+        };
+        let output_path = PathBuf::from(&file.output_path);
+        let reader = ProgressReader::new(file.duration, plan.encode.fps);
+        let mut on_progress = |progress: &ExportProgress| {
+            permit.set_progress(progress.progress);
+            export_state.set_progress(&job_id, progress.progress);
+            if let Some(sequence) = throttle.sample(progress.progress) {
+                emit_export_progress(&app_handle, &job_id, sequence, progress);
+                let clip_progress = if share > 0.0 {
+                    ((progress.progress - start) / share).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                emit_clip_progress(&app_handle, &job_id, &file, clip_progress);
+            }
+        };
+
+        let render_start = match plan.analysis_command_builder(&concat_file) {
+            Some(analysis) => {
+                let analysis_share = share * LOUDNESS_ANALYSIS_SHARE;
+                let (stderr, _) = run_ffmpeg(
+                    analysis.build(),
+                    &job_id,
+                    reader.within(start, analysis_share),
+                    &app_handle,
+                    &export_state,
+                    &mut on_progress,
+                )
+                .await?;
+                plan.set_loudness(parse_loudnorm(&stderr));
+                start + analysis_share
+            }
+            None => start,
+        };
+        let rendered = run_ffmpeg(
+            plan.command_builder(&concat_file, &output_path).build(),
+            &job_id,
+            reader.within(render_start, start + share - render_start),
+            &app_handle,
+            &export_state,
+            &mut on_progress,
+        )
+        .await;
+        if let Err(error) = rendered {
+            let _ = std::fs::remove_file(&output_path);
+            return Err(error);
+        }
+        start += share;
+        emit_clip_progress(&app_handle, &job_id, &file, 1.0);
+    }
+    emit_export_progress(
+        &app_handle,
+        &job_id,
+        throttle.always(1.0),
+        &ExportProgress {
+            progress: 1.0,
+            current_frame: 0,
+            total_frames: 0,
+            fps: 0.0,
+            eta_seconds: 0,
+        },
+    );
+    Ok(())
+}
+
+fn emit_clip_progress(app_handle: &AppHandle, job_id: &str, file: &ClipExportFile, progress: f64) {
+    let _ = app_handle.emit_all(
+        "export_clip_progress",
+        ExportClipProgressEvent {
+            job_id: job_id.to_string(),
+            clip_id: file.clip_id.clone(),
+            index: file.index,
+            progress,
+        },
+    );
+}
+
+/// Share of the progress bar taken by the loudness analysis, which only
+/// decodes and mixes the audio
+const LOUDNESS_ANALYSIS_SHARE: f64 = 0.2;
+
+/// Share of the rest taken by joining a parallel export's parts, which
+/// only copies streams
+const JOIN_SHARE: f64 = 0.05;
+
+/// Run export process and emit progress events; FFmpeg's stderr on success
 async fn run_export(
     cmd: Command,
     job_id: String,
-    total_duration: f64,
+    label: String,
+    progress_reader: ProgressReader,
     app_handle: AppHandle,
     export_state: Arc<ExportState>,
-) -> Result<(), String> {
-    // Log the FFmpeg command for debugging
-    eprintln!("[Export] FFmpeg command: {:?}", cmd);
+    throttle: &mut ProgressThrottle,
+) -> Result<String, FfmpegError> {
+    // Wait for a heavy-job slot; exports go ahead of previews, proxies and thumbnails
+    let permit = scheduler().acquire(JobKind::Export, label).await;
+
+    let (all_output, last_progress) = run_ffmpeg(
+        cmd,
+        &job_id,
+        progress_reader,
+        &app_handle,
+        &export_state,
+        |progress| {
+            permit.set_progress(progress.progress);
+            export_state.set_progress(&job_id, progress.progress);
+            if let Some(sequence) = throttle.sample(progress.progress) {
+                emit_export_progress(&app_handle, &job_id, sequence, progress);
+            }
+        },
+    )
+    .await?;
+
+    // The bar reaches the end of this run (100% before `export_complete`),
+    // however the last report was throttled
+    let end = progress_reader.end();
+    let done = ExportProgress {
+        progress: end,
+        current_frame: last_progress
+            .as_ref()
+            .map_or(0, |p| p.total_frames.max(p.current_frame)),
+        total_frames: last_progress.as_ref().map_or(0, |p| p.total_frames),
+        fps: last_progress.as_ref().map_or(0.0, |p| p.fps),
+        eta_seconds: 0,
+    };
+    emit_export_progress(&app_handle, &job_id, throttle.always(end), &done);
 
+    Ok(all_output)
+}
+
+/// Run one FFmpeg process of an export job, handing its progress reports to
+/// `on_progress`; FFmpeg's stderr and last report on success
+async fn run_ffmpeg(
+    mut cmd: Command,
+    job_id: &str,
+    mut progress_reader: ProgressReader,
+    app_handle: &AppHandle,
+    export_state: &ExportState,
+    mut on_progress: impl FnMut(&ExportProgress),
+) -> Result<(String, Option<ExportProgress>), FfmpegError> {
     // Convert to tokio command for async execution
+    process::own_process_group(&mut cmd);
+    log::info!("Running {}", process::command_line(&cmd));
+    let started = std::time::Instant::now();
     let mut tokio_cmd = TokioCommand::from(cmd);
+    // Aborting the export task (e.g. project closed) must not leave FFmpeg running
+    tokio_cmd.kill_on_drop(true);
 
     let mut child = tokio_cmd
         .stderr(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to spawn FFmpeg process: {}", e))?;
+        .map_err(|e| FfmpegError::from(format!("Failed to spawn FFmpeg process: {}", e)))?;
+    let pid = child.id().unwrap_or_default();
+    let _registration = process::register(pid, "ffmpeg");
+    export_state.add_pid(job_id, pid);
 
     // Update job status
-    {
-        let mut jobs = export_state.jobs.lock().unwrap();
-        if let Some(handle) = jobs.get_mut(&job_id) {
-            handle.job.status = ExportStatus::Rendering;
-        }
-    }
+    update_job_status(
+        export_state,
+        app_handle,
+        job_id,
+        ExportStatus::Rendering,
+        None,
+    );
 
-    // Collect all FFmpeg output for error reporting
-    let mut all_output = String::new();
-
-    // Read stderr for progress and errors
-    if let Some(stderr) = child.stderr.take() {
-        let reader = BufReader::new(stderr);
-        let mut lines = reader.lines();
-
-        while let Ok(Some(line)) = lines.next_line().await {
-            // Store all output for error reporting
-            all_output.push_str(&line);
-            all_output.push('\n');
-
-            // Also log to console for debugging
-            eprintln!("[FFmpeg] {}", line);
-
-            // Parse progress
-            if let Some(progress) = parse_progress(&line, total_duration) {
-                // Emit progress event
-                let _ = app_handle.emit_all(
-                    "export_progress",
-                    ExportProgressEvent {
-                        job_id: job_id.clone(),
-                        progress: progress.progress,
-                        current_frame: progress.current_frame,
-                        total_frames: progress.total_frames,
-                        fps: progress.fps,
-                        eta_seconds: progress.eta_seconds,
-                    },
-                );
+    // Progress comes from `-progress pipe:1` on stdout; stderr is kept
+    // for error reporting
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    let collect_output = async {
+        let mut output = String::new();
+        if let Some(stderr) = stderr {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("ffmpeg: {}", line);
+                output.push_str(&line);
+                output.push('\n');
             }
         }
-    }
+        output
+    };
+    let follow_progress = async {
+        let mut last_progress = None;
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Some(progress) = progress_reader.line(&line) else {
+                    continue;
+                };
+                on_progress(&progress);
+                last_progress = Some(progress);
+            }
+        }
+        last_progress
+    };
+    let (all_output, last_progress) = tokio::join!(collect_output, follow_progress);
 
     // Wait for process to complete
-    let status = child
-        .wait()
-        .await
-        .map_err(|e| format!("Failed to wait for FFmpeg process: {}", e))?;
+    let status = child.wait().await;
+    export_state.remove_pid(job_id, pid);
+    let status = status
+        .map_err(|e| FfmpegError::from(format!("Failed to wait for FFmpeg process: {}", e)))?;
+    log::info!(
+        "Export {} ffmpeg exited with {} after {:.2}s",
+        job_id,
+        status,
+        started.elapsed().as_secs_f64()
+    );
 
     if !status.success() {
-        // Return detailed error with FFmpeg output
-        let error_msg = if all_output.is_empty() {
-            format!("FFmpeg export failed with status: {}", status)
-        } else {
-            // Get last 10 lines of output for error message
-            let lines: Vec<&str> = all_output.lines().rev().take(10).collect();
-            let recent_output = lines.into_iter().rev().collect::<Vec<_>>().join("\n");
-            format!(
-                "FFmpeg export failed with status: {}\n\nRecent output:\n{}",
-                status, recent_output
+        if all_output.is_empty() {
+            return Err(format!("FFmpeg export failed with status: {}", status).into());
+        }
+        return Err(classify(&all_output));
+    }
+    Ok((all_output, last_progress))
+}
+
+/// Run a parallel export's processes (see `ffmpeg::parallel`), `workers` at
+/// a time, combining their progress into the job's
+///
+/// The render takes one slot in the FFmpeg scheduler however many workers
+/// it runs. The first process to fail kills the others and fails the render.
+#[allow(clippy::too_many_arguments)]
+async fn run_parallel(
+    commands: Vec<(Command, ProgressReader)>,
+    workers: usize,
+    parts: PartProgress,
+    job_id: String,
+    label: String,
+    app_handle: AppHandle,
+    export_state: Arc<ExportState>,
+    throttle: &mut ProgressThrottle,
+) -> Result<(), FfmpegError> {
+    let permit = Arc::new(scheduler().acquire(JobKind::Export, label).await);
+    let end = parts.end();
+    let shared = Arc::new(Mutex::new((parts, std::mem::take(throttle))));
+    let slots = Arc::new(Semaphore::new(workers));
+
+    let mut tasks = JoinSet::new();
+    for (index, (cmd, reader)) in commands.into_iter().enumerate() {
+        let (permit, shared, slots) = (permit.clone(), shared.clone(), slots.clone());
+        let (job_id, app_handle, export_state) =
+            (job_id.clone(), app_handle.clone(), export_state.clone());
+        tasks.spawn(async move {
+            let _slot = slots
+                .acquire_owned()
+                .await
+                .map_err(|_| FfmpegError::from("Parallel export stopped".to_string()))?;
+            if export_state.is_finished(&job_id) {
+                return Err(FfmpegError::from("Parallel export stopped".to_string()));
+            }
+            run_ffmpeg(
+                cmd,
+                &job_id,
+                reader,
+                &app_handle,
+                &export_state,
+                |progress| {
+                    let (parts, throttle) = &mut *shared.lock_recovering();
+                    let overall = parts.update(index, progress);
+                    permit.set_progress(overall.progress);
+                    export_state.set_progress(&job_id, overall.progress);
+                    if let Some(sequence) = throttle.sample(overall.progress) {
+                        emit_export_progress(&app_handle, &job_id, sequence, &overall);
+                    }
+                },
             )
-        };
-        return Err(error_msg);
+            .await?;
+            shared.lock_recovering().0.finish(index);
+            Ok(())
+        });
     }
 
-    Ok(())
+    let mut result = Ok(());
+    while let Some(joined) = tasks.join_next().await {
+        let outcome = joined
+            .unwrap_or_else(|e| Err(FfmpegError::from(format!("Export worker failed: {}", e))));
+        if let (Err(error), Ok(())) = (outcome, &result) {
+            // No more parts start, and the running ones are killed
+            slots.close();
+            export_state.kill_processes(&job_id);
+            result = Err(error);
+        }
+    }
+
+    let (parts, taken) = &mut *shared.lock_recovering();
+    *throttle = std::mem::take(taken);
+    if result.is_ok() {
+        emit_export_progress(&app_handle, &job_id, throttle.always(end), &parts.report());
+    }
+    result
+}
+
+fn emit_export_progress(
+    app_handle: &AppHandle,
+    job_id: &str,
+    sequence: u64,
+    progress: &ExportProgress,
+) {
+    let _ = app_handle.emit_all(
+        "export_progress",
+        ExportProgressEvent {
+            job_id: job_id.to_string(),
+            sequence,
+            progress: progress.progress,
+            current_frame: progress.current_frame,
+            total_frames: progress.total_frames,
+            fps: progress.fps,
+            eta_seconds: progress.eta_seconds,
+        },
+    );
+}
+
+/// List this session's export jobs: queued, rendering and finished
+#[tauri::command]
+pub async fn list_export_jobs(
+    export_state: State<'_, ExportState>,
+) -> Result<Vec<ExportJobInfo>, ClipForgeError> {
+    Ok(export_state.list_jobs())
+}
+
+/// Hardware H.264 encoders of the located FFmpeg and the one exports use
+/// with hardware acceleration on (probed once, then cached)
+#[tauri::command]
+pub async fn get_hardware_encoders() -> Result<HardwareEncoders, ClipForgeError> {
+    require_ffmpeg()?;
+    capabilities::hardware_encoders()
+        .await
+        .map_err(ClipForgeError::from)
 }
 
 /// Cancel ongoing export
@@ -298,27 +1629,15 @@ pub async fn cancel_export(
     job_id: String,
     export_state: State<'_, ExportState>,
     app_handle: AppHandle,
-) -> Result<(), String> {
-    let mut jobs = export_state.jobs.lock().unwrap();
-
-    let handle = jobs
-        .get_mut(&job_id)
-        .ok_or_else(|| format!("Export job not found: {}", job_id))?;
-
-    // Kill the FFmpeg process
-    if let Some(mut process) = handle.process.take() {
-        process
-            .kill()
-            .map_err(|e| format!("Failed to kill export process: {}", e))?;
-    }
-
-    // Update status
-    handle.job.status = ExportStatus::Cancelled;
-
-    // Clean up partial output file
-    if std::fs::remove_file(&handle.job.output_path).is_ok() {
-        // File deleted successfully
+) -> Result<(), ClipForgeError> {
+    if !export_state.jobs.lock_state()?.contains_key(&job_id) {
+        return Err(ClipForgeError::not_found("export_job", job_id.as_str()));
     }
+    // A job that already finished has nothing to stop, and was reported
+    let Some(entry) = export_state.cancel_job(&job_id) else {
+        return Ok(());
+    };
+    record_export_history(&app_handle.state::<AppState>(), &entry);
 
     // Emit cancelled event
     let _ = app_handle.emit_all(
@@ -330,3 +1649,230 @@ pub async fn cancel_export(
 
     Ok(())
 }
+
+/// Update a job's status and persist the transition to export history,
+/// with the message and stderr tail of the error that failed it
+///
+/// Finished jobs are left untouched, so a render that ends after the user
+/// cancelled it doesn't overwrite the cancelled state.
+fn update_job_status(
+    export_state: &ExportState,
+    app_handle: &AppHandle,
+    job_id: &str,
+    status: ExportStatus,
+    error: Option<FfmpegError>,
+) {
+    let mut jobs = export_state.jobs.lock_recovering();
+    let Some(handle) = jobs.get_mut(job_id) else {
+        return;
+    };
+    if handle.job.status.is_finished() {
+        return;
+    }
+
+    handle.job.status = status;
+    if status.is_finished() {
+        let (error, log) = match error {
+            Some(error) => (Some(error.message), Some(error.tail)),
+            None => (None, None),
+        };
+        handle.history.finish(status, error);
+        handle.history.error_log = log.filter(|tail| !tail.is_empty());
+    } else {
+        handle.history.status = status;
+    }
+    record_export_history(&app_handle.state::<AppState>(), &handle.history);
+}
+
+/// Write an export history row; failures are logged but never fail the export
+pub fn record_export_history(app_state: &AppState, entry: &ExportHistoryEntry) {
+    if let Err(e) = app_state
+        .cache_db
+        .lock_recovering()
+        .upsert_export_job(entry)
+    {
+        log::warn!("Failed to record export job {}: {}", entry.job_id, e);
+    }
+}
+
+/// Get past export jobs (most recent first), optionally for a single project
+#[tauri::command]
+pub async fn get_export_history(
+    limit: Option<u32>,
+    project_id: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ExportHistoryEntry>, ClipForgeError> {
+    let limit = limit.unwrap_or(50) as usize;
+    Ok(app_state
+        .cache_db
+        .lock_state()?
+        .get_export_history(limit, project_id.as_deref())?)
+}
+
+/// Delete all export history, returning the number of removed jobs
+#[tauri::command]
+pub async fn clear_export_history(app_state: State<'_, AppState>) -> Result<usize, ClipForgeError> {
+    Ok(app_state.cache_db.lock_state()?.clear_export_history()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn queue_job(export_state: &ExportState, id: &str) {
+        export_state.insert_job(
+            ExportJob {
+                id: id.to_string(),
+                output_path: format!("/out/{}.mp4", id),
+                status: ExportStatus::Queued,
+            },
+            ExportHistoryEntry::started(
+                id.to_string(),
+                None,
+                format!("/out/{}.mp4", id),
+                ExportSettings::default(),
+                10.0,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_retry_request_repeats_failed_job() {
+        let mut entry = ExportHistoryEntry::started(
+            "job-1".to_string(),
+            Some("project-1".to_string()),
+            "/out/movie.mp4".to_string(),
+            ExportSettings {
+                parallel: true,
+                ..ExportSettings::default()
+            },
+            10.0,
+        );
+        // Still rendering, or done
+        assert!(retry_request(&entry, "project-1").is_err());
+        entry.finish(ExportStatus::Complete, None);
+        assert!(retry_request(&entry, "project-1").is_err());
+
+        entry.status = ExportStatus::Failed;
+        let request = retry_request(&entry, "project-1").unwrap();
+        assert_eq!(request.output_path, "/out/movie.mp4");
+        assert!(request.settings.parallel);
+        // Only from the project it was exported from
+        assert!(retry_request(&entry, "project-2").is_err());
+
+        entry.status = ExportStatus::Cancelled;
+        assert!(retry_request(&entry, "project-1").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_export_queue_runs_jobs_in_order() {
+        let export_state = ExportState::new();
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for id in ["first", "second", "third"] {
+            queue_job(&export_state, id);
+            let queued = export_state.enqueue(id, 1);
+            let started = started.clone();
+            let task = tokio::spawn(async move {
+                let _slot = queued.started().await;
+                started.lock().unwrap().push(id);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            });
+            export_state.set_task(id, task.abort_handle());
+            tasks.push(task);
+        }
+
+        let positions: Vec<_> = ["first", "second", "third"]
+            .iter()
+            .map(|id| export_state.queue_position(id))
+            .collect();
+        assert_eq!(positions, [None, Some(0), Some(1)]);
+        let listed = export_state.list_jobs();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed[2].output_path, "/out/third.mp4");
+        assert_eq!(listed[2].queue_position, Some(1));
+
+        // Cancelling a queued job drops it from the queue before it starts
+        let cancelled = export_state.cancel_job("second").unwrap();
+        assert_eq!(cancelled.status, ExportStatus::Cancelled);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(export_state.queue_position("third"), Some(0));
+
+        for task in tasks {
+            let _ = task.await;
+        }
+        assert_eq!(*started.lock().unwrap(), ["first", "third"]);
+        assert!(export_state.queue.jobs().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_kills_rendering_export() {
+        let export_state = ExportState::new();
+        queue_job(&export_state, "long");
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        process::own_process_group(&mut cmd);
+        let mut child = TokioCommand::from(cmd).spawn().unwrap();
+        export_state.add_pid("long", child.id().unwrap());
+
+        // Like `run_export`, the render task waits on FFmpeg
+        let render = tokio::spawn(async move { child.wait().await.unwrap() });
+        export_state.set_task("long", render.abort_handle());
+
+        let cancelled = export_state.cancel_job("long").unwrap();
+        assert_eq!(cancelled.status, ExportStatus::Cancelled);
+        let status = tokio::time::timeout(Duration::from_secs(1), render)
+            .await
+            .expect("cancelled export should stop within a second")
+            .unwrap();
+        assert!(!status.success());
+        assert!(export_state.is_cancelled("long"));
+
+        // Cancelling again changes nothing
+        assert!(export_state.cancel_job("long").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_kills_every_parallel_worker() {
+        let export_state = ExportState::new();
+        queue_job(&export_state, "parts");
+        let mut workers = Vec::new();
+        for _ in 0..3 {
+            let mut cmd = Command::new("sleep");
+            cmd.arg("30");
+            process::own_process_group(&mut cmd);
+            let child = TokioCommand::from(cmd).spawn().unwrap();
+            export_state.add_pid("parts", child.id().unwrap());
+            workers.push(child);
+        }
+        // One part already finished
+        let mut finished = workers.pop().unwrap();
+        let pid = finished.id().unwrap();
+        finished.kill().await.unwrap();
+        export_state.remove_pid("parts", pid);
+
+        export_state.cancel_job("parts").unwrap();
+        for mut child in workers {
+            let status = tokio::time::timeout(Duration::from_secs(1), child.wait())
+                .await
+                .expect("every worker should stop within a second")
+                .unwrap();
+            assert!(!status.success());
+        }
+
+        // A part starting after the cancel is killed right away
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        process::own_process_group(&mut cmd);
+        let mut late = TokioCommand::from(cmd).spawn().unwrap();
+        export_state.add_pid("parts", late.id().unwrap());
+        let status = tokio::time::timeout(Duration::from_secs(1), late.wait())
+            .await
+            .expect("a late worker should be killed")
+            .unwrap();
+        assert!(!status.success());
+    }
+}
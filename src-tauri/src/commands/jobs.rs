@@ -0,0 +1,607 @@
+// Background job commands
+// Exports, proxies, captions, recordings and the smaller FFmpeg jobs
+// (previews, thumbnails, audio extraction) are tracked by different
+// subsystems. The activity center lists them all through one `JobSource` per
+// subsystem, under ids prefixed with the source ("export:<id>"), so
+// `cancel_job` can hand a cancellation back to the subsystem that owns it.
+// The list is pushed to the UI as `jobs_updated` (at most once a second) and
+// consulted before the window closes.
+
+use crate::commands::export::{record_export_history, ExportCancelledEvent, ExportState};
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::recording::active_sessions;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::scheduler::{path_label, scheduler, BackgroundJob, JobKind, JobState};
+use crate::models::recording::RecordingStatus;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::task::AbortHandle;
+
+/// Interval of `jobs_updated` events
+pub const JOBS_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// List queued and running heavy FFmpeg jobs (running first, then by priority)
+#[tauri::command]
+pub async fn get_background_jobs() -> Result<Vec<BackgroundJob>, ClipForgeError> {
+    Ok(scheduler().jobs())
+}
+
+/// What a background job does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveJobKind {
+    Recording,
+    Export,
+    Captions,
+    Proxy,
+    Preview,
+    Thumbnail,
+    Audio,
+}
+
+impl From<JobKind> for ActiveJobKind {
+    fn from(kind: JobKind) -> Self {
+        match kind {
+            JobKind::Export => ActiveJobKind::Export,
+            JobKind::Preview => ActiveJobKind::Preview,
+            JobKind::Proxy => ActiveJobKind::Proxy,
+            JobKind::Thumbnail => ActiveJobKind::Thumbnail,
+            JobKind::Audio => ActiveJobKind::Audio,
+        }
+    }
+}
+
+/// A background job as listed by `get_active_jobs`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActiveJob {
+    /// `<source>:<id>`, stable for the job's lifetime
+    pub id: String,
+    pub kind: ActiveJobKind,
+    /// Clip, file or project the job works on
+    pub label: String,
+    pub state: JobState,
+    /// 0.0 - 1.0, when the job reports it
+    pub progress: Option<f64>,
+    pub cancellable: bool,
+}
+
+impl ActiveJob {
+    /// Whether quitting now would lose work the user asked for; previews,
+    /// thumbnails and audio extraction are regenerated when needed
+    pub fn blocks_exit(&self) -> bool {
+        matches!(
+            self.kind,
+            ActiveJobKind::Recording
+                | ActiveJobKind::Export
+                | ActiveJobKind::Captions
+                | ActiveJobKind::Proxy
+        )
+    }
+}
+
+/// A subsystem's job list
+pub trait JobSource {
+    /// Prefix of the ids of this source's jobs
+    fn prefix(&self) -> &'static str;
+
+    /// Current jobs, with ids local to the source
+    fn jobs(&self) -> Vec<ActiveJob>;
+
+    /// Cancel a listed, cancellable job by its local id
+    fn cancel(&self, id: &str) -> Result<(), ClipForgeError>;
+}
+
+/// Every source's jobs under prefixed ids, recordings and exports first
+pub fn collect_jobs(sources: &[&dyn JobSource]) -> Vec<ActiveJob> {
+    let mut jobs: Vec<ActiveJob> = sources
+        .iter()
+        .flat_map(|source| {
+            source.jobs().into_iter().map(|job| ActiveJob {
+                id: format!("{}:{}", source.prefix(), job.id),
+                ..job
+            })
+        })
+        .collect();
+    jobs.sort_by(|a, b| {
+        (a.state != JobState::Running, a.kind, &a.label, &a.id).cmp(&(
+            b.state != JobState::Running,
+            b.kind,
+            &b.label,
+            &b.id,
+        ))
+    });
+    jobs
+}
+
+/// Cancel the job listed under `id` by `collect_jobs`
+pub fn cancel_in(sources: &[&dyn JobSource], id: &str) -> Result<(), ClipForgeError> {
+    let not_found = || ClipForgeError::not_found("job", id);
+    let (prefix, local_id) = id.split_once(':').ok_or_else(not_found)?;
+    let source = sources
+        .iter()
+        .find(|source| source.prefix() == prefix)
+        .ok_or_else(not_found)?;
+    let job = source
+        .jobs()
+        .into_iter()
+        .find(|job| job.id == local_id)
+        .ok_or_else(not_found)?;
+    if !job.cancellable {
+        return Err(ClipForgeError::unsupported(format!(
+            "{} can't be cancelled from the job list",
+            job.label
+        )));
+    }
+    source.cancel(local_id)
+}
+
+/// Jobs that keep the app from quitting
+///
+/// With `cancel` set, cancellable ones are cancelled first and only the rest
+/// (active recordings) are returned.
+pub fn exit_blockers(sources: &[&dyn JobSource], cancel: bool) -> Vec<ActiveJob> {
+    let blocking = collect_jobs(sources)
+        .into_iter()
+        .filter(ActiveJob::blocks_exit);
+    if !cancel {
+        return blocking.collect();
+    }
+    blocking
+        .filter(|job| {
+            if !job.cancellable {
+                return true;
+            }
+            match cancel_in(sources, &job.id) {
+                Ok(()) => false,
+                Err(e) => {
+                    log::warn!("Could not cancel {} on exit: {}", job.id, e);
+                    true
+                }
+            }
+        })
+        .collect()
+}
+
+/// Spawned tasks by id, with a label and progress for the job list
+#[derive(Debug, Clone, Default)]
+pub struct TrackedTasks {
+    tasks: Arc<Mutex<HashMap<String, TrackedTask>>>,
+}
+
+#[derive(Debug)]
+struct TrackedTask {
+    label: String,
+    progress: Option<f64>,
+    handle: AbortHandle,
+}
+
+impl TrackedTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a task, forgetting finished ones
+    pub fn insert(&self, id: impl Into<String>, label: impl Into<String>, handle: AbortHandle) {
+        let mut tasks = self.tasks.lock_recovering();
+        tasks.retain(|_, task| !task.handle.is_finished());
+        tasks.insert(
+            id.into(),
+            TrackedTask {
+                label: label.into(),
+                progress: None,
+                handle,
+            },
+        );
+    }
+
+    /// Stop tracking a task; returns its handle (to abort it)
+    pub fn remove(&self, id: &str) -> Option<AbortHandle> {
+        self.tasks
+            .lock_recovering()
+            .remove(id)
+            .map(|task| task.handle)
+    }
+
+    /// Abort and forget a task; false if it wasn't running
+    pub fn abort(&self, id: &str) -> bool {
+        match self.remove(id) {
+            Some(handle) => {
+                let running = !handle.is_finished();
+                handle.abort();
+                running
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.tasks
+            .lock_recovering()
+            .get(id)
+            .is_some_and(|task| !task.handle.is_finished())
+    }
+
+    /// Report progress (0.0 - 1.0) for the job list
+    pub fn set_progress(&self, id: &str, progress: f64) {
+        if let Some(task) = self.tasks.lock_recovering().get_mut(id) {
+            task.progress = Some(progress.clamp(0.0, 1.0));
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.tasks.lock_recovering().len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Running tasks as jobs of `kind`
+    fn jobs(&self, kind: ActiveJobKind) -> Vec<ActiveJob> {
+        self.tasks
+            .lock_recovering()
+            .iter()
+            .filter(|(_, task)| !task.handle.is_finished())
+            .map(|(id, task)| ActiveJob {
+                id: id.clone(),
+                kind,
+                label: task.label.clone(),
+                state: JobState::Running,
+                progress: task.progress,
+                cancellable: true,
+            })
+            .collect()
+    }
+}
+
+struct ExportJobs<'a>(&'a AppHandle);
+
+impl JobSource for ExportJobs<'_> {
+    fn prefix(&self) -> &'static str {
+        "export"
+    }
+
+    fn jobs(&self) -> Vec<ActiveJob> {
+        self.0.state::<ExportState>().active_jobs()
+    }
+
+    fn cancel(&self, id: &str) -> Result<(), ClipForgeError> {
+        let entry = self
+            .0
+            .state::<ExportState>()
+            .cancel_job(id)
+            .ok_or_else(|| ClipForgeError::not_found("export_job", id))?;
+        record_export_history(&self.0.state::<AppState>(), &entry);
+        let _ = self.0.emit_all(
+            "export_cancelled",
+            ExportCancelledEvent {
+                job_id: id.to_string(),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Proxy generation, by clip id
+struct ProxyJobs<'a>(&'a AppState);
+
+impl JobSource for ProxyJobs<'_> {
+    fn prefix(&self) -> &'static str {
+        "proxy"
+    }
+
+    fn jobs(&self) -> Vec<ActiveJob> {
+        self.0.proxy_jobs.jobs(ActiveJobKind::Proxy)
+    }
+
+    fn cancel(&self, id: &str) -> Result<(), ClipForgeError> {
+        // The clip keeps playing its original; a re-import queues the proxy again
+        self.0.proxy_jobs.abort(id);
+        Ok(())
+    }
+}
+
+/// Caption generation, by caption job id
+struct CaptionJobs<'a>(&'a AppHandle);
+
+impl JobSource for CaptionJobs<'_> {
+    fn prefix(&self) -> &'static str {
+        "captions"
+    }
+
+    fn jobs(&self) -> Vec<ActiveJob> {
+        self.0
+            .state::<AppState>()
+            .caption_jobs
+            .jobs(ActiveJobKind::Captions)
+    }
+
+    fn cancel(&self, id: &str) -> Result<(), ClipForgeError> {
+        if self.0.state::<AppState>().caption_jobs.abort(id) {
+            crate::commands::captions::emit_cancelled(self.0, id);
+        }
+        Ok(())
+    }
+}
+
+/// Active recordings; stopped with `stop_recording` so the file is finalized
+struct RecordingJobs;
+
+impl JobSource for RecordingJobs {
+    fn prefix(&self) -> &'static str {
+        "recording"
+    }
+
+    fn jobs(&self) -> Vec<ActiveJob> {
+        active_sessions()
+            .into_iter()
+            .map(|session| ActiveJob {
+                id: session.id,
+                kind: ActiveJobKind::Recording,
+                label: path_label(&session.output_path),
+                state: match session.status {
+                    RecordingStatus::Preparing => JobState::Queued,
+                    _ => JobState::Running,
+                },
+                progress: None,
+                cancellable: false,
+            })
+            .collect()
+    }
+
+    fn cancel(&self, _id: &str) -> Result<(), ClipForgeError> {
+        Err(ClipForgeError::unsupported(
+            "Recordings are stopped, not cancelled",
+        ))
+    }
+}
+
+/// Scheduler jobs no other source lists (previews, thumbnails, audio)
+struct FfmpegJobs;
+
+impl JobSource for FfmpegJobs {
+    fn prefix(&self) -> &'static str {
+        "ffmpeg"
+    }
+
+    fn jobs(&self) -> Vec<ActiveJob> {
+        scheduler()
+            .jobs()
+            .into_iter()
+            .filter(|job| !matches!(job.kind, JobKind::Export | JobKind::Proxy))
+            .map(|job| ActiveJob {
+                id: job.id.to_string(),
+                kind: job.kind.into(),
+                label: job.label,
+                state: job.state,
+                progress: job.progress,
+                cancellable: false,
+            })
+            .collect()
+    }
+
+    fn cancel(&self, _id: &str) -> Result<(), ClipForgeError> {
+        Err(ClipForgeError::unsupported(
+            "Short FFmpeg jobs can't be cancelled",
+        ))
+    }
+}
+
+/// Run `f` over the app's job sources
+fn with_sources<T>(app_handle: &AppHandle, f: impl FnOnce(&[&dyn JobSource]) -> T) -> T {
+    let app_state = app_handle.state::<AppState>();
+    f(&[
+        &RecordingJobs,
+        &ExportJobs(app_handle),
+        &CaptionJobs(app_handle),
+        &ProxyJobs(&app_state),
+        &FfmpegJobs,
+    ])
+}
+
+/// Every background job, running first
+#[tauri::command]
+pub async fn get_active_jobs(app_handle: AppHandle) -> Result<Vec<ActiveJob>, ClipForgeError> {
+    Ok(with_sources(&app_handle, collect_jobs))
+}
+
+/// Cancel a job listed by `get_active_jobs`
+#[tauri::command]
+pub async fn cancel_job(id: String, app_handle: AppHandle) -> Result<(), ClipForgeError> {
+    log::info!("Cancelling job {}", id);
+    with_sources(&app_handle, |sources| cancel_in(sources, &id))
+}
+
+/// Jobs that keep the window from closing; cancels what it can first when
+/// `cancel` is set (the user chose to quit anyway) or the
+/// `cancel_jobs_on_exit` setting is on
+pub fn jobs_blocking_exit(app_handle: &AppHandle, cancel: bool) -> Vec<ActiveJob> {
+    let cancel = cancel
+        || app_handle
+            .state::<AppState>()
+            .settings
+            .lock_recovering()
+            .cancel_jobs_on_exit;
+    with_sources(app_handle, |sources| exit_blockers(sources, cancel))
+}
+
+/// `jobs_updated` and `close_requested_jobs_running` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct JobsUpdatedEvent {
+    pub jobs: Vec<ActiveJob>,
+}
+
+/// Emit `jobs_updated` whenever the job list changed, at most once per interval
+pub async fn emit_job_updates(app_handle: AppHandle) {
+    let mut ticker = tokio::time::interval(JOBS_UPDATE_INTERVAL);
+    let mut last = Vec::new();
+    loop {
+        ticker.tick().await;
+        let jobs = with_sources(&app_handle, collect_jobs);
+        if jobs != last {
+            let _ = app_handle.emit_all("jobs_updated", JobsUpdatedEvent { jobs: jobs.clone() });
+            last = jobs;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// A subsystem with a fixed job list that records cancellations
+    struct MockSource {
+        prefix: &'static str,
+        jobs: RefCell<Vec<ActiveJob>>,
+        cancelled: RefCell<Vec<String>>,
+    }
+
+    impl MockSource {
+        fn new(prefix: &'static str, jobs: Vec<ActiveJob>) -> Self {
+            Self {
+                prefix,
+                jobs: RefCell::new(jobs),
+                cancelled: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl JobSource for MockSource {
+        fn prefix(&self) -> &'static str {
+            self.prefix
+        }
+
+        fn jobs(&self) -> Vec<ActiveJob> {
+            self.jobs.borrow().clone()
+        }
+
+        fn cancel(&self, id: &str) -> Result<(), ClipForgeError> {
+            self.jobs.borrow_mut().retain(|job| job.id != id);
+            self.cancelled.borrow_mut().push(id.to_string());
+            Ok(())
+        }
+    }
+
+    fn job(id: &str, kind: ActiveJobKind, state: JobState, cancellable: bool) -> ActiveJob {
+        ActiveJob {
+            id: id.to_string(),
+            kind,
+            label: format!("{} label", id),
+            state,
+            progress: None,
+            cancellable,
+        }
+    }
+
+    #[test]
+    fn test_collect_and_cancel() {
+        let exports = MockSource::new(
+            "export",
+            vec![
+                job("e1", ActiveJobKind::Export, JobState::Queued, true),
+                job("e2", ActiveJobKind::Export, JobState::Running, true),
+            ],
+        );
+        let ffmpeg = MockSource::new(
+            "ffmpeg",
+            vec![job("7", ActiveJobKind::Thumbnail, JobState::Running, false)],
+        );
+        let recordings = MockSource::new(
+            "recording",
+            vec![job(
+                "r1",
+                ActiveJobKind::Recording,
+                JobState::Running,
+                false,
+            )],
+        );
+        let sources: [&dyn JobSource; 3] = [&exports, &ffmpeg, &recordings];
+
+        let jobs = collect_jobs(&sources);
+        let ids: Vec<&str> = jobs.iter().map(|job| job.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["recording:r1", "export:e2", "ffmpeg:7", "export:e1"],
+            "running first, then by kind"
+        );
+
+        // Dispatched to the owning source with its local id
+        cancel_in(&sources, "export:e1").unwrap();
+        assert_eq!(*exports.cancelled.borrow(), ["e1"]);
+        assert_eq!(collect_jobs(&sources).len(), 3);
+
+        // Unknown ids and jobs that can't be cancelled
+        let error = cancel_in(&sources, "export:e1").unwrap_err();
+        assert_eq!(error.code(), "not_found");
+        assert_eq!(
+            cancel_in(&sources, "captions:c1").unwrap_err().code(),
+            "not_found"
+        );
+        assert_eq!(cancel_in(&sources, "e2").unwrap_err().code(), "not_found");
+        assert_eq!(
+            cancel_in(&sources, "recording:r1").unwrap_err().code(),
+            "unsupported"
+        );
+        assert!(recordings.cancelled.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_exit_blockers() {
+        let exports = MockSource::new(
+            "export",
+            vec![job("e1", ActiveJobKind::Export, JobState::Running, true)],
+        );
+        let ffmpeg = MockSource::new(
+            "ffmpeg",
+            vec![job("3", ActiveJobKind::Preview, JobState::Running, false)],
+        );
+        let recordings = MockSource::new("recording", Vec::new());
+        let sources: [&dyn JobSource; 3] = [&exports, &ffmpeg, &recordings];
+
+        // Previews don't block; exports do until cancelled
+        let blockers = exit_blockers(&sources, false);
+        assert_eq!(blockers.len(), 1);
+        assert_eq!(blockers[0].id, "export:e1");
+        assert!(exports.cancelled.borrow().is_empty());
+
+        assert!(exit_blockers(&sources, true).is_empty());
+        assert_eq!(*exports.cancelled.borrow(), ["e1"]);
+
+        // A recording can't be cancelled and keeps blocking
+        recordings.jobs.borrow_mut().push(job(
+            "r1",
+            ActiveJobKind::Recording,
+            JobState::Running,
+            false,
+        ));
+        let blockers = exit_blockers(&sources, true);
+        assert_eq!(blockers.len(), 1);
+        assert_eq!(blockers[0].id, "recording:r1");
+    }
+
+    #[tokio::test]
+    async fn test_tracked_tasks() {
+        let tasks = TrackedTasks::new();
+        let pending = tokio::spawn(std::future::pending::<()>());
+        tasks.insert("a", "clip.mov", pending.abort_handle());
+        tasks.set_progress("a", 1.5);
+        assert!(tasks.is_running("a"));
+
+        let listed = tasks.jobs(ActiveJobKind::Proxy);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].label, "clip.mov");
+        assert_eq!(listed[0].progress, Some(1.0));
+
+        assert!(tasks.abort("a"));
+        assert!(pending.await.unwrap_err().is_cancelled());
+        assert!(!tasks.abort("a"));
+        assert!(tasks.is_empty());
+    }
+}
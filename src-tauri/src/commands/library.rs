@@ -0,0 +1,510 @@
+// Media library of the current project
+// The loaded `Project` owns its media list; there is no second copy in
+// AppState. Commands read clips through `media_clip`/`media_clips` and change
+// them through `edit_media`, which keeps the cache rows in step, records the
+// project modification and emits `media_library_changed` (plus one
+// `media_clip_updated` per updated clip), so the bin, preview and export
+// always see the same proxies and captions, including changes made by
+// background jobs.
+
+use crate::commands::events::{EventSink, ProjectEvents};
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::project::ModificationScope;
+use crate::error::ClipForgeError;
+use crate::models::clip::MediaClip;
+use crate::models::project::Project;
+use crate::storage::cache::CacheDb;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+/// How a `media_library_changed` event changed the listed clips
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaChange {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// `media_library_changed` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaLibraryChangedEvent {
+    pub project_id: String,
+    pub change: MediaChange,
+    pub clip_ids: Vec<String>,
+    /// Current state of the added or updated clips (empty for removals)
+    pub clips: Vec<MediaClip>,
+}
+
+/// `media_clip_updated` event payload: the clip after the change
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaClipUpdatedEvent {
+    pub project_id: String,
+    pub clip: MediaClip,
+}
+
+/// A clip of the current project's library
+pub fn media_clip(state: &AppState, clip_id: &str) -> Result<MediaClip, ClipForgeError> {
+    state
+        .project
+        .lock_state()?
+        .as_ref()
+        .and_then(|project| project.media_library.iter().find(|c| c.id == clip_id))
+        .cloned()
+        .ok_or_else(|| ClipForgeError::not_found("media_clip", clip_id))
+}
+
+/// The current project's library (empty when no project is open)
+pub fn media_clips(state: &AppState) -> Vec<MediaClip> {
+    state
+        .project
+        .lock_recovering()
+        .as_ref()
+        .map(|project| project.media_library.clone())
+        .unwrap_or_default()
+}
+
+/// Change the current project's media list
+///
+/// `edit` returns the ids of the clips it added, updated or removed along
+/// with its own result. Added and updated clips are written to the cache and
+/// the change is reported to `events` (the app handle outside tests).
+pub fn edit_media<R>(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    change: MediaChange,
+    scope: ModificationScope,
+    edit: impl FnOnce(&mut Vec<MediaClip>) -> Result<(Vec<String>, R), ClipForgeError>,
+) -> Result<R, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let (clip_ids, result) = edit(&mut project.media_library)?;
+    publish_media_change(state, events, project, change, scope, clip_ids);
+    Ok(result)
+}
+
+/// Sync the cache and announce clips already changed in a locked project
+///
+/// For callers that change the media list along with other parts of the
+/// project (e.g. merging another project in); everything else goes through
+/// `edit_media`.
+pub fn publish_media_change(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    project: &mut Project,
+    change: MediaChange,
+    scope: ModificationScope,
+    clip_ids: Vec<String>,
+) {
+    if clip_ids.is_empty() {
+        return;
+    }
+
+    let clips: Vec<MediaClip> = match change {
+        MediaChange::Removed => Vec::new(),
+        MediaChange::Added | MediaChange::Updated => project
+            .media_library
+            .iter()
+            .filter(|c| clip_ids.contains(&c.id))
+            .cloned()
+            .collect(),
+    };
+    {
+        let cache_db = state.cache_db.lock_recovering();
+        for clip in &clips {
+            if let Err(e) = cache_media_clip(&cache_db, clip) {
+                log::warn!("Failed to cache media clip {}: {}", clip.id, e);
+            }
+        }
+    }
+
+    events.project_modified(project, scope, clip_ids.clone());
+    emit_media_events(events, &project.id, change, clip_ids, clips);
+}
+
+/// `media_library_changed`, then `media_clip_updated` for each updated clip
+fn emit_media_events(
+    sink: &impl EventSink,
+    project_id: &str,
+    change: MediaChange,
+    clip_ids: Vec<String>,
+    clips: Vec<MediaClip>,
+) {
+    let updated = match change {
+        MediaChange::Updated => clips.clone(),
+        MediaChange::Added | MediaChange::Removed => Vec::new(),
+    };
+    sink.emit_event(
+        "media_library_changed",
+        MediaLibraryChangedEvent {
+            project_id: project_id.to_string(),
+            change,
+            clip_ids,
+            clips,
+        },
+    );
+    for clip in updated {
+        sink.emit_event(
+            "media_clip_updated",
+            MediaClipUpdatedEvent {
+                project_id: project_id.to_string(),
+                clip,
+            },
+        );
+    }
+}
+
+/// Update a single clip of the current project (see `edit_media`)
+pub fn update_media_clip<R>(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    clip_id: &str,
+    scope: ModificationScope,
+    update: impl FnOnce(&mut MediaClip) -> Result<R, ClipForgeError>,
+) -> Result<R, ClipForgeError> {
+    edit_media(state, events, MediaChange::Updated, scope, |library| {
+        let clip = library
+            .iter_mut()
+            .find(|c| c.id == clip_id)
+            .ok_or_else(|| ClipForgeError::not_found("media_clip", clip_id))?;
+        Ok((vec![clip.id.clone()], update(clip)?))
+    })
+}
+
+/// Write a clip's cache row, inserting it if its source path is new
+///
+/// Rows are keyed by source path and shared between projects, so removing a
+/// clip from a project leaves its row (and the id re-imports reuse) in place.
+fn cache_media_clip(cache_db: &CacheDb, clip: &MediaClip) -> Result<(), String> {
+    match cache_db.get_media_clip_by_source_path(&clip.source_path)? {
+        Some(_) => cache_db.update_media_clip(clip),
+        None => cache_db.insert_media_clip(clip),
+    }
+}
+
+/// Current state of a clip, for refreshing a view that may have missed events
+#[tauri::command]
+pub async fn get_media_clip(
+    clip_id: String,
+    state: State<'_, AppState>,
+) -> Result<MediaClip, ClipForgeError> {
+    media_clip(&state, &clip_id)
+}
+
+/// Remove a clip from the current project's library
+///
+/// Refused while timeline clips still use it.
+#[tauri::command]
+pub async fn remove_media_clip(
+    clip_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), ClipForgeError> {
+    remove_clip(&state, &app_handle, &clip_id)
+}
+
+fn remove_clip(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    clip_id: &str,
+) -> Result<(), ClipForgeError> {
+    {
+        // Checked and removed under one lock, so no edit can place the clip
+        // on the timeline in between
+        let mut project_lock = state.project.lock_state()?;
+        let project = project_lock
+            .as_mut()
+            .ok_or_else(ClipForgeError::no_project)?;
+        if project.uses_media(clip_id) {
+            return Err(ClipForgeError::validation(
+                "clip_id",
+                "Remove the clip from the timeline first",
+            ));
+        }
+        let before = project.media_library.len();
+        project.media_library.retain(|c| c.id != clip_id);
+        if project.media_library.len() == before {
+            return Err(ClipForgeError::not_found("media_clip", clip_id));
+        }
+        publish_media_change(
+            state,
+            events,
+            project,
+            MediaChange::Removed,
+            ModificationScope::MediaLibrary,
+            vec![clip_id.to_string()],
+        );
+    }
+
+    state.proxy_jobs.abort(clip_id);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::commands::captions::set_captions;
+    use crate::commands::events::CapturedEvents;
+    use crate::commands::jobs::TrackedTasks;
+    use crate::commands::media::{apply_thumbnail, finish_proxy};
+    use crate::ffmpeg::thumbnails::{ThumbnailQueue, ThumbnailResult};
+    use crate::models::caption::Caption;
+    use crate::models::timeline::TimelineClip;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    pub(crate) fn mock_state(temp_dir: &TempDir) -> AppState {
+        AppState {
+            cache_db: Arc::new(Mutex::new(
+                CacheDb::new(&temp_dir.path().join("cache.db")).unwrap(),
+            )),
+            project: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(Default::default())),
+            proxy_jobs: TrackedTasks::new(),
+            caption_jobs: TrackedTasks::new(),
+            ffmpeg: Arc::new(Mutex::new(Default::default())),
+            thumbnails: ThumbnailQueue::new(1).0,
+        }
+    }
+
+    pub(crate) fn clip(path: &str) -> MediaClip {
+        MediaClip::new(path.into(), 10.0, 1920, 1080, 30.0, "prores".into(), 1)
+    }
+
+    #[test]
+    fn test_edits_need_a_project_and_sync_the_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let events = CapturedEvents::default();
+        let a = clip("/m/a.mov");
+
+        let add = |state: &AppState, clip: MediaClip| {
+            edit_media(
+                state,
+                &events,
+                MediaChange::Added,
+                ModificationScope::MediaLibrary,
+                |library| {
+                    let id = clip.id.clone();
+                    library.push(clip);
+                    Ok((vec![id], ()))
+                },
+            )
+        };
+        assert!(add(&state, a.clone()).is_err());
+        assert!(media_clips(&state).is_empty());
+
+        *state.project.lock().unwrap() = Some(Project::new("Library".into()));
+        add(&state, a.clone()).unwrap();
+        assert_eq!(media_clip(&state, &a.id).unwrap().source_path, "/m/a.mov");
+        assert!(state.project.lock().unwrap().as_ref().unwrap().dirty);
+
+        update_media_clip(
+            &state,
+            &events,
+            &a.id,
+            ModificationScope::MediaLibrary,
+            |clip| {
+                clip.proxy_path = Some("/cache/a.mp4".into());
+                Ok(())
+            },
+        )
+        .unwrap();
+        let cached = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/m/a.mov")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.id, a.id);
+        assert_eq!(cached.proxy_path.as_deref(), Some("/cache/a.mp4"));
+
+        assert!(update_media_clip(
+            &state,
+            &events,
+            "missing",
+            ModificationScope::MediaLibrary,
+            |_| Ok(())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_remove_clip() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let a = clip("/m/a.mov");
+        let b = clip("/m/b.mov");
+        let mut project = Project::new("Library".into());
+        let track_id = project.tracks[0].id.clone();
+        project.tracks[0]
+            .clips
+            .push(TimelineClip::new(a.id.clone(), track_id, 0.0, 0.0, 5.0));
+        project.media_library = vec![a.clone(), b.clone()];
+        *state.project.lock().unwrap() = Some(project);
+        let events = CapturedEvents::default();
+
+        // Still on the timeline
+        assert!(remove_clip(&state, &events, &a.id).is_err());
+
+        remove_clip(&state, &events, &b.id).unwrap();
+        assert_eq!(
+            media_clips(&state)
+                .iter()
+                .map(|c| c.id.clone())
+                .collect::<Vec<_>>(),
+            [a.id]
+        );
+        assert!(remove_clip(&state, &events, &b.id).is_err());
+    }
+
+    #[test]
+    fn test_background_mutations_emit_clip_updates() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let a = clip("/m/a.mov");
+        let mut project = Project::new("Events".into());
+        project.media_library = vec![a.clone()];
+        *state.project.lock().unwrap() = Some(project);
+        let events = CapturedEvents::default();
+
+        // Each path emits exactly one `media_clip_updated` with the new state
+        let updated_clip = |mutate: &dyn Fn()| {
+            events.clear();
+            mutate();
+            assert_eq!(events.named("media_library_changed").len(), 1);
+            let updates = events.named("media_clip_updated");
+            assert_eq!(updates.len(), 1);
+            serde_json::from_value::<MediaClip>(updates[0]["clip"].clone()).unwrap()
+        };
+
+        let updated =
+            updated_clip(&|| finish_proxy(&state, &events, &a.id, "/cache/a.mp4").unwrap());
+        assert_eq!(updated.proxy_path.as_deref(), Some("/cache/a.mp4"));
+
+        let updated = updated_clip(&|| {
+            let thumbnail = ThumbnailResult {
+                clip_id: a.id.clone(),
+                thumbnail_path: "/cache/a.jpg".into(),
+                thumbnail_2x_path: "/cache/a@2x.jpg".into(),
+            };
+            apply_thumbnail(&state, &events, &thumbnail).unwrap()
+        });
+        assert_eq!(updated.thumbnail_path.as_deref(), Some("/cache/a.jpg"));
+        assert_eq!(updated.proxy_path.as_deref(), Some("/cache/a.mp4"));
+
+        let updated = updated_clip(&|| {
+            let caption = Caption::new(a.id.clone(), "Hi".into(), 0.0, 1.0, "en".into());
+            set_captions(&state, &events, &a.id, vec![caption]).unwrap()
+        });
+        assert_eq!(updated.captions.len(), 1);
+
+        // Additions and removals only change the list
+        events.clear();
+        let b = clip("/m/b.mov");
+        edit_media(
+            &state,
+            &events,
+            MediaChange::Added,
+            ModificationScope::MediaLibrary,
+            |library| {
+                library.push(b.clone());
+                Ok((vec![b.id.clone()], ()))
+            },
+        )
+        .unwrap();
+        remove_clip(&state, &events, &b.id).unwrap();
+        assert_eq!(events.named("media_library_changed").len(), 2);
+        assert!(events.named("media_clip_updated").is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_edits_do_not_deadlock() {
+        use crate::commands::locks::LockState;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let a = clip("/m/a.mov");
+        let mut project = Project::new("Stress".into());
+        project.media_library.push(a.clone());
+        *state.project.lock().unwrap() = Some(project);
+
+        const ROUNDS: usize = 200;
+        let (done_tx, done_rx) = mpsc::channel();
+        let spawn = |work: Box<dyn Fn(&AppState, usize) + Send>| {
+            let (state, done_tx) = (state.clone(), done_tx.clone());
+            std::thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    work(&state, round);
+                }
+                done_tx.send(()).unwrap();
+            });
+        };
+
+        // Timeline edits: copy out, then lock again to commit
+        let media_id = a.id.clone();
+        spawn(Box::new(move |state, round| {
+            let duration = media_clip(state, &media_id).unwrap().duration;
+            let mut project_lock = state.project.lock_state().unwrap();
+            let track = &mut project_lock.as_mut().unwrap().tracks[0];
+            let start = round as f64 * duration;
+            track.clips.push(TimelineClip::new(
+                media_id.clone(),
+                track.id.clone(),
+                start,
+                0.0,
+                duration,
+            ));
+        }));
+        // Caption attachment
+        let media_id = a.id.clone();
+        spawn(Box::new(move |state, round| {
+            let caption = Caption::new(media_id.clone(), round.to_string(), 0.0, 1.0, "en".into());
+            set_captions(state, &CapturedEvents::default(), &media_id, vec![caption]).unwrap();
+        }));
+        // Imports, which also write the cache
+        spawn(Box::new(|state, round| {
+            let imported = clip(&format!("/m/import-{}.mov", round));
+            edit_media(
+                state,
+                &CapturedEvents::default(),
+                MediaChange::Added,
+                ModificationScope::MediaLibrary,
+                |library| {
+                    let id = imported.id.clone();
+                    library.push(imported);
+                    Ok((vec![id], ()))
+                },
+            )
+            .unwrap();
+        }));
+        // Thumbnails arriving from the background queue
+        let media_id = a.id.clone();
+        spawn(Box::new(move |state, round| {
+            let thumbnail = ThumbnailResult {
+                clip_id: media_id.clone(),
+                thumbnail_path: format!("/cache/{}.jpg", round),
+                thumbnail_2x_path: format!("/cache/{}@2x.jpg", round),
+            };
+            apply_thumbnail(state, &CapturedEvents::default(), &thumbnail).unwrap();
+        }));
+
+        for _ in 0..4 {
+            done_rx
+                .recv_timeout(Duration::from_secs(30))
+                .expect("concurrent edits deadlocked");
+        }
+        let project = state.project.lock().unwrap();
+        let project = project.as_ref().unwrap();
+        assert_eq!(project.tracks[0].clips.len(), ROUNDS);
+        assert_eq!(project.media_library.len(), ROUNDS + 1);
+        assert_eq!(project.media_library[0].captions.len(), 1);
+        assert!(!state.project.is_poisoned() && !state.cache_db.is_poisoned());
+    }
+}
@@ -0,0 +1,75 @@
+// Locking shared state
+// `AppState` keeps the project, settings and cache behind `std::sync::Mutex`.
+// Command futures must be `Send`, so a guard can't be held across an
+// `.await`: commands copy what they need out, await, then lock again to
+// commit. Nested locks are taken in one order only — project, then cache
+// database — and a lock is never taken again while held (helpers receive the
+// locked value instead, see `library::publish_media_change`).
+//
+// A panic while a lock is held poisons it. Commands lock with `lock_state`,
+// which reports that as an error instead of panicking in turn, and clears the
+// poison so the next command can run.
+
+use crate::error::ClipForgeError;
+use std::sync::{Mutex, MutexGuard};
+
+/// Poison-aware locking for shared state
+pub trait LockState<T> {
+    /// Lock for a command; a poisoned lock is an error
+    fn lock_state(&self) -> Result<MutexGuard<'_, T>, ClipForgeError>;
+
+    /// Lock where failing isn't an option (bookkeeping, caches); a poisoned
+    /// lock's value is used as-is
+    fn lock_recovering(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockState<T> for Mutex<T> {
+    fn lock_state(&self) -> Result<MutexGuard<'_, T>, ClipForgeError> {
+        self.lock().map_err(|_| {
+            log::error!("State lock poisoned by an earlier failure");
+            self.clear_poison();
+            ClipForgeError::Other {
+                message: "An earlier operation failed while changing the app state; try again"
+                    .to_string(),
+            }
+        })
+    }
+
+    fn lock_recovering(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poisoned| {
+            log::warn!("Using state from a lock poisoned by an earlier failure");
+            self.clear_poison();
+            poisoned.into_inner()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_poisoned_lock_is_an_error_once() {
+        let state = Arc::new(Mutex::new(1));
+        let poisoner = state.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("command failed mid-edit");
+        })
+        .join();
+
+        assert!(state.lock_state().is_err());
+        assert_eq!(*state.lock_state().unwrap(), 1);
+
+        let poisoner = state.clone();
+        let _ = std::thread::spawn(move || {
+            let mut guard = poisoner.lock().unwrap();
+            *guard = 2;
+            panic!("command failed mid-edit");
+        })
+        .join();
+        assert_eq!(*state.lock_recovering(), 2);
+        assert!(!state.is_poisoned());
+    }
+}
@@ -0,0 +1,43 @@
+// Log commands for the support panel
+use crate::error::ClipForgeError;
+use crate::logging;
+use std::process::Command;
+
+/// Lines returned when the caller doesn't ask for a count
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Last `lines` log lines, oldest first
+#[tauri::command]
+pub async fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, ClipForgeError> {
+    let dir = logging::log_dir().ok_or("Failed to get home directory")?;
+    Ok(logging::recent_lines(
+        &dir,
+        lines.unwrap_or(DEFAULT_LOG_LINES),
+    )?)
+}
+
+/// Reveal the log directory in the system file manager
+#[tauri::command]
+pub async fn open_log_folder() -> Result<(), ClipForgeError> {
+    let dir = logging::log_dir().ok_or("Failed to get home directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| ClipForgeError::Io {
+        message: format!("Failed to create log directory: {}", e),
+    })?;
+
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    // The openers hand off to the file manager and exit right away
+    tokio::task::spawn_blocking(move || Command::new(opener).arg(&dir).status())
+        .await
+        .map_err(|e| format!("Failed to open log folder: {}", e))?
+        .map_err(|e| ClipForgeError::MissingDependency {
+            which: opener.to_string(),
+            message: format!("Failed to open log folder: {}", e),
+        })?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+// Timeline markers and the chapters made from them
+// Markers belong to the project's top-level timeline and don't move with
+// clips. Exports write them as chapters when `ExportSettings::chapters` is
+// on; `export_chapters_text` gives the same chapters as text for a video
+// description.
+
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::error::ClipForgeError;
+use crate::ffmpeg::chapters::chapters_text;
+use crate::ffmpeg::export::ExportPlan;
+use crate::models::export::ExportSettings;
+use crate::models::marker::{sort_markers, Marker};
+use crate::models::project::Project;
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Marker properties changed by `update_marker`; None leaves a field as is
+#[derive(serde::Deserialize)]
+pub struct MarkerUpdates {
+    pub time: Option<f64>,
+    pub label: Option<String>,
+    /// "#RRGGBB"; an empty string clears it
+    pub color: Option<String>,
+}
+
+/// Run `edit` on the open project's markers and record the change to
+/// `marker_id`
+fn edit_markers<T>(
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+    marker_id: &str,
+    edit: impl FnOnce(&mut Vec<Marker>) -> Result<T, ClipForgeError>,
+) -> Result<T, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let result = edit(&mut project.markers)?;
+    sort_markers(&mut project.markers);
+
+    record_modification(
+        app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![marker_id.to_string()],
+    );
+    Ok(result)
+}
+
+/// Add a marker at timeline `time`
+#[tauri::command]
+pub async fn add_marker(
+    time: f64,
+    label: String,
+    color: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Marker, ClipForgeError> {
+    let mut marker = Marker::new(time, label, color.filter(|c| !c.is_empty()));
+    marker
+        .validate()
+        .map_err(|e| ClipForgeError::validation("marker", e))?;
+    let id = marker.id.clone();
+    edit_markers(&state, &app_handle, &id, |markers| {
+        markers.push(marker.clone());
+        Ok(marker)
+    })
+}
+
+/// Move, rename or recolor a marker
+#[tauri::command]
+pub async fn update_marker(
+    marker_id: String,
+    updates: MarkerUpdates,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Marker, ClipForgeError> {
+    edit_markers(&state, &app_handle, &marker_id, |markers| {
+        let marker = markers
+            .iter_mut()
+            .find(|m| m.id == marker_id)
+            .ok_or_else(|| ClipForgeError::not_found("marker", marker_id.as_str()))?;
+        let mut updated = marker.clone();
+        if let Some(time) = updates.time {
+            updated.time = time;
+        }
+        if let Some(label) = updates.label {
+            updated.label = label;
+        }
+        if let Some(color) = updates.color {
+            updated.color = Some(color).filter(|c| !c.is_empty());
+        }
+        updated
+            .validate()
+            .map_err(|e| ClipForgeError::validation("marker", e))?;
+        *marker = updated.clone();
+        Ok(updated)
+    })
+}
+
+/// Remove a marker
+#[tauri::command]
+pub async fn delete_marker(
+    marker_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), ClipForgeError> {
+    edit_markers(&state, &app_handle, &marker_id, |markers| {
+        let index = markers
+            .iter()
+            .position(|m| m.id == marker_id)
+            .ok_or_else(|| ClipForgeError::not_found("marker", marker_id.as_str()))?;
+        markers.remove(index);
+        Ok(())
+    })
+}
+
+/// Chapter lines an export with `settings` would get, in the project's
+/// markers' output times
+pub fn project_chapters_text(
+    project: &Project,
+    settings: &ExportSettings,
+) -> Result<String, ClipForgeError> {
+    let project = project.rendered()?;
+    let mut plan = ExportPlan::build(
+        &project.tracks,
+        &project.media_library,
+        settings,
+        &project.settings,
+        &HashMap::new(),
+    )?;
+    plan.add_chapters(&project.markers);
+    for warning in &plan.marker_warnings {
+        log::warn!("{}", warning);
+    }
+    Ok(chapters_text(&plan.chapters))
+}
+
+/// YouTube-style chapter list ("00:00 Intro", one per line) for an export
+/// with `settings`, the project's export settings by default
+#[tauri::command]
+pub async fn export_chapters_text(
+    settings: Option<ExportSettings>,
+    state: State<'_, AppState>,
+) -> Result<String, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let settings = settings.unwrap_or_else(|| project.export_settings.clone());
+    project_chapters_text(project, &settings)
+}
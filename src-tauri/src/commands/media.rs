@@ -1,26 +1,64 @@
 // Media command implementation for import, metadata extraction, and thumbnail generation
 
-use crate::ffmpeg::{extract_metadata, generate_proxy, generate_thumbnail, needs_proxy};
+use crate::commands::assets::{allow_asset_dir, asset_url_for_webview};
+use crate::commands::events::ProjectEvents;
+use crate::commands::jobs::TrackedTasks;
+use crate::commands::library::{
+    edit_media, media_clip, media_clips, update_media_clip, MediaChange,
+};
+use crate::commands::locks::LockState;
+use crate::commands::progress::ProgressThrottle;
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::commands::recording::get_recordings_dir;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::errors::FfmpegError;
+use crate::ffmpeg::locate::{require_ffmpeg, FfmpegStatus};
+use crate::ffmpeg::metadata::{probe_full, FullProbeResult};
+use crate::ffmpeg::scheduler::{path_label, scheduler, JobKind};
+use crate::ffmpeg::thumbnails::{
+    build_hover_sprite_command, needs_thumbnail, HoverSpriteLayout, HoverSpriteQueue,
+    ThumbnailPaths, ThumbnailQueue, ThumbnailRequest, ThumbnailResult as QueuedThumbnail,
+};
+use crate::ffmpeg::{
+    extract_metadata, generate_proxy, generate_thumbnail, needs_proxy, proxy_outdated,
+    PROXY_FORMAT_VERSION,
+};
 use crate::models::clip::MediaClip;
-use crate::models::project::Project;
+use crate::models::project::{Project, ProjectSettings};
+use crate::models::settings::{AppSettings, ProxySettings};
+use crate::platform::process::{self, ProcessClass};
 use crate::storage::cache::CacheDb;
+use crate::storage::cache_location::active_cache_dir;
+use crate::storage::media_download::{DownloadClaim, MediaDownload};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
+/// Shared backend state; the media library lives in `project` (see `commands::library`)
 pub struct AppState {
     pub cache_db: Arc<Mutex<CacheDb>>,
-    pub media_library: Arc<Mutex<Vec<MediaClip>>>,
     pub project: Arc<Mutex<Option<Project>>>,
+    pub settings: Arc<Mutex<AppSettings>>,
+    /// Running proxy generation tasks by clip id (aborted when their project closes)
+    pub proxy_jobs: TrackedTasks,
+    /// Running caption generation tasks by caption job id
+    pub caption_jobs: TrackedTasks,
+    /// FFmpeg installation resolved at startup (or via `set_ffmpeg_path`)
+    pub ffmpeg: Arc<Mutex<FfmpegStatus>>,
+    /// Background thumbnail generation for imports and recordings
+    pub thumbnails: ThumbnailQueue,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ImportResult {
     pub clips: Vec<MediaClip>,
     pub errors: Vec<ImportError>,
+    /// IDs of clips whose source path was already imported (existing row refreshed)
+    pub already_imported: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,13 +72,113 @@ pub struct ImportError {
 pub async fn import_media_files(
     paths: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<ImportResult, String> {
+    app_handle: AppHandle,
+) -> Result<ImportResult, ClipForgeError> {
+    if state.project.lock_state()?.is_none() {
+        return Err(ClipForgeError::no_project());
+    }
+    import_paths(paths, None, &state, &app_handle).await
+}
+
+/// `media_download_progress` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaDownloadProgressEvent {
+    pub url: String,
+    /// Increases with every event of the download; older events can be dropped
+    pub sequence: u64,
+    pub downloaded_bytes: u64,
+    /// None when the server didn't announce the size
+    pub total_bytes: Option<u64>,
+}
+
+/// Download a video from an https:// link and import it
+///
+/// The file is saved to `destination`, or a "Downloads" folder in the
+/// recordings directory, emitting `media_download_progress` as it arrives.
+/// An interrupted download is continued by calling this again with the same
+/// URL. The imported clip records the link in `origin_url`.
+#[tauri::command]
+pub async fn import_media_from_url(
+    url: String,
+    destination: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ImportResult, ClipForgeError> {
+    if state.project.lock_state()?.is_none() {
+        return Err(ClipForgeError::no_project());
+    }
+    let url = url.trim().to_string();
+    let (dest_dir, max_bytes) = {
+        let settings = state.settings.lock_state()?;
+        let dest_dir = match destination {
+            Some(dir) => PathBuf::from(dir),
+            None => get_recordings_dir(settings.recordings_dir.as_deref())?.join("Downloads"),
+        };
+        (
+            dest_dir,
+            settings.max_download_mb.saturating_mul(1024 * 1024),
+        )
+    };
+
+    let _claim = DownloadClaim::acquire(&url)?;
+    let throttle = Mutex::new(ProgressThrottle::new());
+    let path = MediaDownload::new(&url, &dest_dir, max_bytes)
+        .run(|downloaded_bytes, total_bytes| {
+            let progress =
+                total_bytes.map_or(0.0, |total| downloaded_bytes as f64 / total.max(1) as f64);
+            if let Some(sequence) = throttle.lock_recovering().sample(progress) {
+                let _ = app_handle.emit_all(
+                    "media_download_progress",
+                    MediaDownloadProgressEvent {
+                        url: url.clone(),
+                        sequence,
+                        downloaded_bytes,
+                        total_bytes,
+                    },
+                );
+            }
+        })
+        .await?;
+    log::info!("Downloaded {} to {}", url, path.display());
+
+    import_paths(
+        vec![path.to_string_lossy().into_owned()],
+        Some(&url),
+        &state,
+        &app_handle,
+    )
+    .await
+}
+
+/// Import files into the open project's library, marking the clips as
+/// downloaded from `origin_url`
+pub(crate) async fn import_paths(
+    paths: Vec<String>,
+    origin_url: Option<&str>,
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+) -> Result<ImportResult, ClipForgeError> {
     let mut clips = Vec::new();
     let mut errors = Vec::new();
+    let mut already_imported = Vec::new();
+    let mut proxies = Vec::new();
+    let mut thumbnails = Vec::new();
 
     for path in paths {
-        match import_single_file(&path, &state).await {
-            Ok(clip) => clips.push(clip),
+        match import_single_file(&path, state, app_handle).await {
+            Ok((mut clip, existing, proxy, thumbnail)) => {
+                if let Some(url) = origin_url {
+                    clip.origin_url = Some(url.to_string());
+                }
+                if existing {
+                    already_imported.push(clip.id.clone());
+                }
+                if let Some(proxy) = proxy {
+                    proxies.push((clip.id.clone(), proxy));
+                }
+                thumbnails.push(thumbnail);
+                clips.push(clip);
+            }
             Err(e) => errors.push(ImportError {
                 path: path.clone(),
                 error: e,
@@ -48,135 +186,143 @@ pub async fn import_media_files(
         }
     }
 
-    // Add successfully imported clips to BOTH storage locations
     if !clips.is_empty() {
-        // 1. Add to state.media_library (for validation and frontend sync)
-        let mut library = state.media_library.lock().unwrap();
-        library.extend(clips.clone());
-        drop(library);
+        let first_import = edit_media(
+            state,
+            app_handle,
+            MediaChange::Added,
+            ModificationScope::MediaLibrary,
+            |library| {
+                let first_import = library.is_empty();
+                merge_clips(library, &clips);
+                Ok((clips.iter().map(|c| c.id.clone()).collect(), first_import))
+            },
+        )?;
 
-        // 2. Add to project.media_library (for export and persistence)
-        let mut project_lock = state.project.lock().unwrap();
+        // The first import into a fresh project sets the sequence to match the media
+        let mut project_lock = state.project.lock_state()?;
         if let Some(ref mut project) = *project_lock {
-            project.media_library.extend(clips.clone());
-            project.mark_modified();
-            eprintln!(
-                "[Import] Added {} clips to project. Project now has {} clips",
-                clips.len(),
-                project.media_library.len()
-            );
-        } else {
-            eprintln!(
-                "[Import] Warning: No project loaded, clips added to state.media_library only"
-            );
+            if first_import && project.settings == ProjectSettings::default() {
+                if let Some(first_video) = clips.iter().find(|c| c.width > 0 && c.height > 0) {
+                    project.settings = ProjectSettings::match_media(first_video);
+                    log::info!(
+                        "Matched project settings to {}: {}x{} @ {} fps",
+                        first_video.name,
+                        project.settings.width,
+                        project.settings.height,
+                        project.settings.fps
+                    );
+                    record_modification(app_handle, project, ModificationScope::Settings, vec![]);
+                }
+            }
+        }
+    }
+
+    // Proxies and thumbnails start once their clips are in the library, so a
+    // fast job can't finish before there is a clip to attach its result to
+    for (clip_id, proxy) in proxies {
+        spawn_proxy_job(state, app_handle, clip_id, proxy);
+    }
+    for thumbnail in thumbnails {
+        if let Err(e) = state.thumbnails.submit(thumbnail) {
+            log::warn!("{}", e);
+        }
+    }
+
+    Ok(ImportResult {
+        clips,
+        errors,
+        already_imported,
+    })
+}
+
+/// Add clips to a library, replacing entries that share an id (re-imports)
+fn merge_clips(library: &mut Vec<MediaClip>, clips: &[MediaClip]) {
+    for clip in clips {
+        match library.iter_mut().find(|c| c.id == clip.id) {
+            Some(existing) => {
+                // Captions live only in memory/project, keep them on refresh
+                let captions = std::mem::take(&mut existing.captions);
+                *existing = clip.clone();
+                existing.captions = captions;
+            }
+            None => library.push(clip.clone()),
         }
     }
+}
 
-    Ok(ImportResult { clips, errors })
+/// A proxy to generate for an imported clip once it is in the library
+struct PendingProxy {
+    source_path: String,
+    proxy_path: String,
+    source_fps: f64,
+    duration: f64,
+    settings: ProxySettings,
 }
 
-async fn import_single_file(path: &str, state: &State<'_, AppState>) -> Result<MediaClip, String> {
+/// Import a single file, returning the clip, whether its source path was
+/// already known, and the proxy and thumbnail it still needs
+async fn import_single_file(
+    path: &str,
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+) -> Result<(MediaClip, bool, Option<PendingProxy>, ThumbnailRequest), String> {
     // Validate file exists
     let file_path = PathBuf::from(path);
     if !file_path.exists() {
         return Err(format!("File not found: {}", path));
     }
+    if let Some(dir) = file_path.parent() {
+        allow_asset_dir(app_handle, dir);
+    }
 
     // Extract metadata using FFmpeg
     let metadata = extract_metadata(path).await?;
 
+    // Reuse the existing clip id when this file was imported before, so projects
+    // and timelines referencing it stay valid
+    let existing = state
+        .cache_db
+        .lock_state()?
+        .get_media_clip_by_source_path(path)?;
+
     // Generate clip ID and thumbnail path
-    let clip_id = Uuid::new_v4().to_string();
+    let clip_id = existing
+        .as_ref()
+        .map(|c| c.id.clone())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
     let cache_dir = get_cache_dir()?;
-    let thumbnail_dir = cache_dir.join("thumbnails");
-    std::fs::create_dir_all(&thumbnail_dir)
-        .map_err(|e| format!("Failed to create thumbnail directory: {}", e))?;
-    let thumbnail_path = thumbnail_dir.join(format!("{}.jpg", clip_id));
 
-    // Generate thumbnail at 1 second mark (or 0 if video is shorter)
-    let timestamp = if metadata.duration > 1.0 { 1.0 } else { 0.0 };
-    let thumbnail_path_str = thumbnail_path
-        .to_str()
-        .ok_or("Invalid thumbnail path")?
-        .to_string();
-
-    match generate_thumbnail(path, &thumbnail_path_str, timestamp).await {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("Warning: Failed to generate thumbnail: {}", e);
-            // Continue without thumbnail
-        }
-    }
+    // Thumbnail at 1 second mark (or 0 if video is shorter), generated in the background
+    let thumbnail = thumbnail_request(
+        state,
+        &clip_id,
+        path,
+        default_thumbnail_time(metadata.duration),
+    )?;
 
     // Check if we need to generate a proxy for web playback
-    let proxy_path = if needs_proxy(&metadata.codec) {
+    let proxy_settings = state.settings.lock_state()?.proxy.clone();
+    let pending_proxy = if proxy_settings.enabled && needs_proxy(&metadata.codec) {
         let proxy_dir = cache_dir.join("proxies");
         std::fs::create_dir_all(&proxy_dir)
             .map_err(|e| format!("Failed to create proxy directory: {}", e))?;
         let proxy_file = proxy_dir.join(format!("{}.mp4", clip_id));
-        let proxy_path_str = proxy_file.to_str().ok_or("Invalid proxy path")?.to_string();
-
-        // Generate proxy in background (don't block import)
-        let path_clone = path.to_string();
-        let proxy_clone = proxy_path_str.clone();
-        let clip_id_clone = clip_id.clone();
-        let state_clone = state.inner().clone();
-
-        tokio::spawn(async move {
-            match generate_proxy(&path_clone, &proxy_clone).await {
-                Ok(_) => {
-                    println!("✓ Proxy generated for clip {}", clip_id_clone);
-                    println!("  Proxy path: {}", proxy_clone);
-
-                    // Update the clip in the library with the proxy path
-                    let mut library = state_clone.media_library.lock().unwrap();
-                    if let Some(clip) = library.iter_mut().find(|c| c.id == clip_id_clone) {
-                        clip.proxy_path = Some(proxy_clone.clone());
-                        println!("  Updated clip in state.media_library with proxy path");
-
-                        // Also update project.media_library for export consistency
-                        let mut project_lock = state_clone.project.lock().unwrap();
-                        if let Some(ref mut project) = *project_lock {
-                            if let Some(project_clip) = project
-                                .media_library
-                                .iter_mut()
-                                .find(|c| c.id == clip_id_clone)
-                            {
-                                project_clip.proxy_path = Some(proxy_clone.clone());
-                                project.mark_modified();
-                                println!("  Updated clip in project.media_library with proxy path");
-                            }
-                        }
-
-                        // Update cache database
-                        let cache_db = state_clone.cache_db.lock().unwrap();
-                        if let Err(e) = cache_db.insert_media_clip(clip) {
-                            eprintln!("Failed to update clip with proxy path: {}", e);
-                        } else {
-                            println!("  Updated cache database with proxy path");
-                        }
-                    } else {
-                        eprintln!(
-                            "  ERROR: Could not find clip {} in library to update proxy path",
-                            clip_id_clone
-                        );
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: Failed to generate proxy for {}: {}",
-                        clip_id_clone, e
-                    );
-                }
-            }
-        });
-
-        // Return None for now - will be updated when proxy generation completes
-        None
+        Some(PendingProxy {
+            source_path: path.to_string(),
+            proxy_path: proxy_file.to_str().ok_or("Invalid proxy path")?.to_string(),
+            source_fps: metadata.fps,
+            duration: metadata.duration,
+            settings: proxy_settings,
+        })
     } else {
         // No proxy needed for web-compatible formats
         None
     };
+    // Keep a previously generated proxy until the new one completes
+    let proxy_path = pending_proxy
+        .as_ref()
+        .and(existing.as_ref().and_then(|c| c.proxy_path.clone()));
 
     // Get file size
     let file_size = std::fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
@@ -193,12 +339,13 @@ async fn import_single_file(path: &str, state: &State<'_, AppState>) -> Result<M
         id: clip_id,
         name,
         source_path: path.to_string(),
+        proxy_version: proxy_path
+            .as_ref()
+            .and(existing.as_ref().and_then(|c| c.proxy_version)),
         proxy_path,
-        thumbnail_path: if thumbnail_path.exists() {
-            Some(thumbnail_path_str)
-        } else {
-            None
-        },
+        // Filled in by `apply_thumbnail` once the queue has generated them
+        thumbnail_path: None,
+        thumbnail_2x_path: None,
         duration: metadata.duration,
         resolution: metadata.resolution,
         width: metadata.width as i32,
@@ -209,15 +356,221 @@ async fn import_single_file(path: &str, state: &State<'_, AppState>) -> Result<M
         file_size: file_size as i64,
         bitrate: metadata.bitrate.map(|b| b as i32),
         has_audio: metadata.has_audio,
-        imported_at: chrono::Utc::now(),
+        imported_at: existing
+            .as_ref()
+            .map(|c| c.imported_at)
+            .unwrap_or_else(chrono::Utc::now),
         captions: vec![],
+        // Keep a stream choice made before the re-import if the stream still exists
+        audio_stream_index: existing
+            .as_ref()
+            .and_then(|c| c.audio_stream_index)
+            .filter(|index| metadata.audio_streams.iter().any(|s| s.index == *index)),
+        audio_streams: metadata.audio_streams,
+        suggested_trim: None,
+        loudness: None,
+        origin_url: None,
     };
 
-    // Store in cache database
-    let cache_db = state.cache_db.lock().unwrap();
-    cache_db.insert_media_clip(&clip)?;
+    Ok((clip, existing.is_some(), pending_proxy, thumbnail))
+}
+
+/// Generate a proxy in the background (don't block import)
+///
+/// The task is tracked so closing the project can abort it.
+fn spawn_proxy_job(
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+    clip_id: String,
+    proxy: PendingProxy,
+) {
+    let state_clone = state.inner().clone();
+    let app_handle_clone = app_handle.clone();
+    let clip_id_clone = clip_id.clone();
+    let label = path_label(&proxy.source_path);
+
+    let proxy_task = tokio::spawn(async move {
+        let mut throttle = ProgressThrottle::new();
+        let emit_progress = |sequence: u64, progress: f64| {
+            let _ = app_handle_clone.emit_all(
+                "proxy_progress",
+                ProxyProgressEvent {
+                    clip_id: clip_id_clone.clone(),
+                    sequence,
+                    progress,
+                },
+            );
+        };
+        let result = generate_proxy(
+            &proxy.source_path,
+            &proxy.proxy_path,
+            proxy.source_fps,
+            proxy.duration,
+            &proxy.settings,
+            |progress| {
+                state_clone
+                    .proxy_jobs
+                    .set_progress(&clip_id_clone, progress);
+                if let Some(sequence) = throttle.sample(progress) {
+                    emit_progress(sequence, progress);
+                }
+            },
+        )
+        .await;
+        match result {
+            Ok(_) => {
+                emit_progress(throttle.always(1.0), 1.0);
+                log::info!(
+                    "Proxy generated for clip {}: {}",
+                    clip_id_clone,
+                    proxy.proxy_path
+                );
+                if let Err(e) = finish_proxy(
+                    &state_clone,
+                    &app_handle_clone,
+                    &clip_id_clone,
+                    &proxy.proxy_path,
+                ) {
+                    log::error!("Could not attach proxy to clip {}: {}", clip_id_clone, e);
+                }
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to generate proxy for {} ({:?}): {}\n{}",
+                    clip_id_clone,
+                    e.kind,
+                    e,
+                    e.excerpt
+                );
+                let _ = app_handle_clone.emit_all(
+                    "proxy_failed",
+                    ProxyFailedEvent {
+                        clip_id: clip_id_clone.clone(),
+                        error: e,
+                    },
+                );
+            }
+        }
+
+        state_clone.proxy_jobs.remove(&clip_id_clone);
+    });
+
+    state
+        .proxy_jobs
+        .insert(clip_id, label, proxy_task.abort_handle());
+}
+
+/// Point a clip at its newly generated proxy
+pub(crate) fn finish_proxy(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    clip_id: &str,
+    proxy_path: &str,
+) -> Result<(), ClipForgeError> {
+    update_media_clip(
+        state,
+        events,
+        clip_id,
+        ModificationScope::MediaLibrary,
+        |clip| {
+            clip.proxy_path = Some(proxy_path.to_string());
+            clip.proxy_version = Some(PROXY_FORMAT_VERSION);
+            Ok(())
+        },
+    )
+}
 
-    Ok(clip)
+/// `thumbnail_generated` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ThumbnailGeneratedEvent {
+    pub clip_id: String,
+    pub thumbnail_path: String,
+    /// Asset URL of `thumbnail_path` for the webview
+    pub url: String,
+    /// Asset URL of the 2x thumbnail
+    pub url_2x: String,
+}
+
+/// Apply the thumbnail queue's results to the library as they arrive
+pub async fn apply_thumbnail_results(
+    app_handle: AppHandle,
+    mut results: mpsc::UnboundedReceiver<Result<QueuedThumbnail, String>>,
+) {
+    while let Some(result) = results.recv().await {
+        match result {
+            Ok(thumbnail) => {
+                let state = app_handle.state::<AppState>();
+                match apply_thumbnail(&state, &app_handle, &thumbnail) {
+                    Ok(()) => emit_thumbnail_generated(&app_handle, &thumbnail),
+                    Err(e) => {
+                        log::debug!("Thumbnail for {} not applied: {}", thumbnail.clip_id, e)
+                    }
+                }
+            }
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+}
+
+/// Point a clip at its generated thumbnail
+///
+/// A clip outside the current project (e.g. a recording stopped with no
+/// project open) only has its cache row updated, so a later import of the
+/// file picks the thumbnail up.
+pub(crate) fn apply_thumbnail(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    thumbnail: &QueuedThumbnail,
+) -> Result<(), String> {
+    let updated = update_media_clip(
+        state,
+        events,
+        &thumbnail.clip_id,
+        ModificationScope::MediaLibrary,
+        |clip| {
+            clip.thumbnail_path = Some(thumbnail.thumbnail_path.clone());
+            clip.thumbnail_2x_path = Some(thumbnail.thumbnail_2x_path.clone());
+            Ok(())
+        },
+    );
+    if let Err(e) = updated {
+        let cached = state.cache_db.lock_state()?.set_thumbnail_path(
+            &thumbnail.clip_id,
+            &thumbnail.thumbnail_path,
+            Some(&thumbnail.thumbnail_2x_path),
+        )?;
+        if !cached {
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+/// Announce a thumbnail `apply_thumbnail` pointed its clip at
+fn emit_thumbnail_generated(app_handle: &AppHandle, thumbnail: &QueuedThumbnail) {
+    let _ = app_handle.emit_all(
+        "thumbnail_generated",
+        ThumbnailGeneratedEvent {
+            clip_id: thumbnail.clip_id.clone(),
+            thumbnail_path: thumbnail.thumbnail_path.clone(),
+            url: asset_url_for_webview(app_handle, &thumbnail.thumbnail_path),
+            url_2x: asset_url_for_webview(app_handle, &thumbnail.thumbnail_2x_path),
+        },
+    );
+}
+
+/// Clips whose proxy was built with an older encoding recipe
+///
+/// Re-importing a clip through `import_media_files` regenerates its proxy.
+#[tauri::command]
+pub async fn get_outdated_proxy_clips(
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, ClipForgeError> {
+    Ok(media_clips(&state)
+        .iter()
+        .filter(|clip| proxy_outdated(clip))
+        .map(|clip| clip.id.clone())
+        .collect())
 }
 
 /// T028: Get metadata for a specific clip
@@ -225,13 +578,139 @@ async fn import_single_file(path: &str, state: &State<'_, AppState>) -> Result<M
 pub async fn get_media_metadata(
     clip_id: String,
     state: State<'_, AppState>,
-) -> Result<MediaClip, String> {
-    let library = state.media_library.lock().unwrap();
-    library
-        .iter()
-        .find(|c| c.id == clip_id)
-        .cloned()
-        .ok_or_else(|| format!("Media clip not found: {}", clip_id))
+) -> Result<MediaClip, ClipForgeError> {
+    media_clip(&state, &clip_id)
+}
+
+/// Full probe of a clip's source: every stream, chapters and container tags
+#[tauri::command]
+pub async fn get_media_streams(
+    clip_id: String,
+    state: State<'_, AppState>,
+) -> Result<FullProbeResult, ClipForgeError> {
+    let source_path = media_clip(&state, &clip_id)?.source_path;
+    Ok(probe_full(&source_path).await?)
+}
+
+/// Choose which audio stream of a multi-track source is used (None = default)
+///
+/// Export, preview and waveforms map the chosen stream with `-map 0:<index>`.
+#[tauri::command]
+pub async fn select_audio_stream(
+    clip_id: String,
+    stream_index: Option<u32>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<MediaClip, ClipForgeError> {
+    update_media_clip(
+        &state,
+        &app_handle,
+        &clip_id,
+        ModificationScope::MediaLibrary,
+        |clip| {
+            if let Some(index) = stream_index {
+                if !clip.audio_streams.iter().any(|s| s.index == index) {
+                    return Err(ClipForgeError::validation(
+                        "stream_index",
+                        format!("Stream {} is not an audio stream of {}", index, clip.name),
+                    ));
+                }
+            }
+            clip.audio_stream_index = stream_index;
+            Ok(clip.clone())
+        },
+    )
+}
+
+/// `proxy_progress` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyProgressEvent {
+    pub clip_id: String,
+    /// Increases with every event of the proxy; older events can be dropped
+    pub sequence: u64,
+    pub progress: f64,
+}
+
+/// `proxy_failed` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyFailedEvent {
+    pub clip_id: String,
+    pub error: FfmpegError,
+}
+
+/// A generated thumbnail
+#[derive(Debug, Serialize)]
+pub struct ThumbnailResult {
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// The 2x variant, for HiDPI displays
+    pub path_2x: String,
+    pub url_2x: String,
+}
+
+impl ThumbnailResult {
+    fn new(app_handle: &AppHandle, paths: ThumbnailPaths) -> Self {
+        Self {
+            url: asset_url_for_webview(app_handle, &paths.standard),
+            url_2x: asset_url_for_webview(app_handle, &paths.hidpi),
+            path: paths.standard,
+            path_2x: paths.hidpi,
+        }
+    }
+}
+
+/// Where a clip's thumbnail is taken from when none is asked for: 1s in, or
+/// the first frame of shorter clips
+fn default_thumbnail_time(duration: f64) -> f64 {
+    if duration > 1.0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Request for `clip_id`'s thumbnails at the current thumbnail settings
+pub(crate) fn thumbnail_request(
+    state: &AppState,
+    clip_id: &str,
+    source_path: &str,
+    timestamp: f64,
+) -> Result<ThumbnailRequest, String> {
+    let settings = state.settings.lock_state()?.thumbnail;
+    let thumbnail_dir = get_cache_dir()?.join("thumbnails");
+    std::fs::create_dir_all(&thumbnail_dir)
+        .map_err(|e| format!("Failed to create thumbnail directory: {}", e))?;
+    Ok(ThumbnailRequest {
+        clip_id: clip_id.to_string(),
+        source_path: source_path.to_string(),
+        paths: ThumbnailPaths::new(&thumbnail_dir, clip_id, &settings),
+        settings,
+        timestamp,
+    })
+}
+
+/// Generate `request`'s thumbnails now and point the clip at them
+async fn generate_and_apply_thumbnail(
+    state: &AppState,
+    app_handle: &AppHandle,
+    request: ThumbnailRequest,
+) -> Result<ThumbnailPaths, String> {
+    let paths = generate_thumbnail(
+        &request.source_path,
+        &request.paths,
+        request.timestamp,
+        &request.settings,
+    )
+    .await?;
+    let thumbnail = QueuedThumbnail {
+        clip_id: request.clip_id,
+        thumbnail_path: paths.standard.clone(),
+        thumbnail_2x_path: paths.hidpi.clone(),
+    };
+    apply_thumbnail(state, app_handle, &thumbnail)?;
+    emit_thumbnail_generated(app_handle, &thumbnail);
+    Ok(paths)
 }
 
 /// T030: Generate thumbnail for existing clip
@@ -240,34 +719,122 @@ pub async fn generate_thumbnail_for_clip(
     clip_id: String,
     timestamp: f64,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    // Get the source path from the library, then drop the lock before async operation
-    let source_path = {
-        let library = state.media_library.lock().unwrap();
-        let clip = library
-            .iter()
-            .find(|c| c.id == clip_id)
-            .ok_or_else(|| format!("Media clip not found: {}", clip_id))?;
-        clip.source_path.clone()
-    }; // MutexGuard is dropped here
+    app_handle: AppHandle,
+) -> Result<ThumbnailResult, ClipForgeError> {
+    let source_path = media_clip(&state, &clip_id)?.source_path;
+    let request = thumbnail_request(&state, &clip_id, &source_path, timestamp)?;
+    let paths = generate_and_apply_thumbnail(&state, &app_handle, request).await?;
+    Ok(ThumbnailResult::new(&app_handle, paths))
+}
 
-    let cache_dir = get_cache_dir()?;
-    let thumbnail_dir = cache_dir.join("thumbnails");
-    let thumbnail_path = thumbnail_dir.join(format!("{}.jpg", clip_id));
-    let thumbnail_path_str = thumbnail_path
+/// Get a clip's thumbnails at the current thumbnail settings
+///
+/// Thumbnails made at another size or format (or by older versions, as one
+/// full-size JPEG) are regenerated on first request.
+#[tauri::command]
+pub async fn get_clip_thumbnail(
+    clip_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ThumbnailResult, ClipForgeError> {
+    let clip = media_clip(&state, &clip_id)?;
+    let request = thumbnail_request(
+        &state,
+        &clip_id,
+        &clip.source_path,
+        default_thumbnail_time(clip.duration),
+    )?;
+    if !needs_thumbnail(&clip, &request.paths, |p| Path::new(p).exists()) {
+        return Ok(ThumbnailResult::new(&app_handle, request.paths));
+    }
+    let paths = generate_and_apply_thumbnail(&state, &app_handle, request).await?;
+    Ok(ThumbnailResult::new(&app_handle, paths))
+}
+
+/// Hover-scrub sprite of a clip with the timing of each frame
+#[derive(Debug, Serialize)]
+pub struct HoverSprite {
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    #[serde(flatten)]
+    pub layout: HoverSpriteLayout,
+}
+
+/// Get (generating on first use) a sprite of `count` small frames for hover scrubbing
+///
+/// Sprites are cached in `cache/hover_sprites` and recorded in the cache
+/// database; a sprite removed by cache maintenance is regenerated on the next hover.
+#[tauri::command]
+pub async fn get_hover_thumbnails(
+    clip_id: String,
+    count: u32,
+    state: State<'_, AppState>,
+    sprite_queue: State<'_, HoverSpriteQueue>,
+    app_handle: AppHandle,
+) -> Result<HoverSprite, ClipForgeError> {
+    require_ffmpeg()?;
+
+    let clip = media_clip(&state, &clip_id)?;
+    let layout = HoverSpriteLayout::new(clip.duration, clip.width, clip.height, count)?;
+
+    let sprite_path = get_cache_dir()?
+        .join("hover_sprites")
+        .join(format!("{}_{}.jpg", clip.id, layout.frame_count));
+    let sprite_path_str = sprite_path
         .to_str()
-        .ok_or("Invalid thumbnail path")?
+        .ok_or("Invalid sprite path")?
         .to_string();
 
-    generate_thumbnail(&source_path, &thumbnail_path_str, timestamp).await?;
+    let known = state
+        .cache_db
+        .lock_state()?
+        .get_hover_sprite_path(&clip.id)?;
+    if known.as_deref() != Some(sprite_path_str.as_str()) || !sprite_path.exists() {
+        let key = format!("{}_{}", clip.id, layout.frame_count);
+        sprite_queue
+            .run(&key, || async {
+                // An earlier request may have finished while this one waited
+                if sprite_path.exists() {
+                    return Ok(sprite_path_str.clone());
+                }
+                std::fs::create_dir_all(sprite_path.parent().unwrap())
+                    .map_err(|e| format!("Failed to create sprite directory: {}", e))?;
+                let partial = sprite_path.with_extension("part.jpg");
+                let cmd = build_hover_sprite_command(&clip.source_path, &layout, &partial);
+                let _permit = scheduler().acquire(JobKind::Thumbnail, &clip.name).await;
+                let output = process::output(cmd, ProcessClass::Thumbnail).await?;
+                if !output.status.success() {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(format!(
+                        "Hover sprite generation failed: {}",
+                        String::from_utf8_lossy(&output.stderr)
+                            .lines()
+                            .last()
+                            .unwrap_or_default()
+                    ));
+                }
+                std::fs::rename(&partial, &sprite_path)
+                    .map_err(|e| format!("Failed to store hover sprite: {}", e))?;
+                Ok(sprite_path_str.clone())
+            })
+            .await?;
+        state
+            .cache_db
+            .lock_state()?
+            .set_hover_sprite_path(&clip.id, Some(&sprite_path_str))?;
+    }
 
-    Ok(thumbnail_path_str)
+    Ok(HoverSprite {
+        url: asset_url_for_webview(&app_handle, &sprite_path_str),
+        path: sprite_path_str,
+        layout,
+    })
 }
 
-/// Get cache directory path
-fn get_cache_dir() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir().ok_or("Failed to get home directory")?;
-    let cache_dir = home_dir.join(".clipforge").join("cache");
+/// Get cache directory path (configurable, see `storage::cache_location`)
+pub fn get_cache_dir() -> Result<PathBuf, String> {
+    let cache_dir = active_cache_dir().ok_or("Failed to get home directory")?;
     std::fs::create_dir_all(&cache_dir)
         .map_err(|e| format!("Failed to create cache directory: {}", e))?;
     Ok(cache_dir)
@@ -276,6 +843,247 @@ fn get_cache_dir() -> Result<PathBuf, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commands::events::CapturedEvents;
+    use crate::commands::library::tests::mock_state;
+    use crate::ffmpeg::export::{write_concat_file, ExportPlan};
+    use crate::models::export::ExportSettings;
+    use crate::models::timeline::TimelineClip;
+    use std::collections::HashMap;
+
+    fn mock_clip(id: &str, source_path: &str, duration: f64) -> MediaClip {
+        MediaClip {
+            id: id.to_string(),
+            ..MediaClip::new(
+                source_path.to_string(),
+                duration,
+                1920,
+                1080,
+                30.0,
+                "h264".to_string(),
+                1024,
+            )
+        }
+    }
+
+    fn import(state: &AppState, clip: &MediaClip) {
+        edit_media(
+            state,
+            &CapturedEvents::default(),
+            MediaChange::Added,
+            ModificationScope::MediaLibrary,
+            |library| {
+                merge_clips(library, std::slice::from_ref(clip));
+                Ok((vec![clip.id.clone()], ()))
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_reimport_preserves_clip_id() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        *state.project.lock().unwrap() = Some(Project::new("Imports".to_string()));
+
+        // First import
+        let first = mock_clip("clip-1", "/videos/a.mp4", 10.0);
+        import(&state, &first);
+
+        // Re-import of the same path resolves to the existing row
+        let existing = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap();
+        assert_eq!(existing.as_ref().map(|c| c.id.as_str()), Some("clip-1"));
+
+        // Libraries referencing the id are refreshed, not duplicated
+        update_media_clip(
+            &state,
+            &CapturedEvents::default(),
+            "clip-1",
+            ModificationScope::Captions,
+            |clip| {
+                clip.captions.push(crate::models::caption::Caption::new(
+                    "clip-1".to_string(),
+                    "Hello".to_string(),
+                    0.0,
+                    1.0,
+                    "en".to_string(),
+                ));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        // File changed on disk: refreshed metadata keeps the same id
+        let refreshed = mock_clip("clip-1", "/videos/a.mp4", 15.0);
+        import(&state, &refreshed);
+
+        let stored = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.id, "clip-1");
+        assert_eq!(stored.duration, 15.0);
+
+        let library = media_clips(&state);
+        assert_eq!(library.len(), 1);
+        assert_eq!(library[0].duration, 15.0);
+        assert_eq!(library[0].captions.len(), 1);
+    }
+
+    #[test]
+    fn test_proxy_finished_after_load_is_used_by_export() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+
+        // A saved project whose clip had no proxy yet
+        let clip = mock_clip("clip-1", "/videos/a.mov", 10.0);
+        let mut project = Project::new("Proxies".to_string());
+        let track_id = project.tracks[0].id.clone();
+        project.tracks[0]
+            .clips
+            .push(TimelineClip::new(clip.id.clone(), track_id, 0.0, 0.0, 5.0));
+        project.media_library.push(clip);
+        *state.project.lock().unwrap() = Some(project);
+
+        finish_proxy(
+            &state,
+            &CapturedEvents::default(),
+            "clip-1",
+            "/cache/proxies/clip-1.mp4",
+        )
+        .unwrap();
+
+        // What export reads: the current project
+        let project = state.project.lock().unwrap().clone().unwrap();
+        assert!(project.dirty);
+        let plan = ExportPlan::build(
+            &project.tracks,
+            &project.media_library,
+            &ExportSettings::default(),
+            &ProjectSettings::default(),
+            &HashMap::new(),
+        )
+        .unwrap();
+        let concat = write_concat_file(&plan.segments, temp_dir.path()).unwrap();
+        let content = std::fs::read_to_string(concat).unwrap();
+        assert!(content.contains("file '/cache/proxies/clip-1.mp4'"));
+        assert!(!content.contains("/videos/a.mov"));
+
+        // And what the clip cache remembers for the next import
+        let cached = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/videos/a.mov")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.proxy_version, Some(PROXY_FORMAT_VERSION));
+
+        // A proxy for a clip that left the project has nowhere to go
+        assert!(finish_proxy(
+            &state,
+            &CapturedEvents::default(),
+            "gone",
+            "/cache/proxies/gone.mp4"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_thumbnail_result_is_applied() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        *state.project.lock().unwrap() = Some(Project::new("Thumbnails".to_string()));
+
+        // Imported without a thumbnail
+        let clip = MediaClip {
+            thumbnail_path: None,
+            ..mock_clip("clip-1", "/videos/a.mov", 10.0)
+        };
+        import(&state, &clip);
+
+        let generated = QueuedThumbnail {
+            clip_id: "clip-1".to_string(),
+            thumbnail_path: "/cache/thumbnails/clip-1.jpg".to_string(),
+            thumbnail_2x_path: "/cache/thumbnails/clip-1@2x.jpg".to_string(),
+        };
+        apply_thumbnail(&state, &CapturedEvents::default(), &generated).unwrap();
+
+        assert_eq!(
+            media_clip(&state, "clip-1")
+                .unwrap()
+                .thumbnail_path
+                .as_deref(),
+            Some("/cache/thumbnails/clip-1.jpg")
+        );
+        let cached = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/videos/a.mov")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cached.thumbnail_path.as_deref(),
+            Some("/cache/thumbnails/clip-1.jpg")
+        );
+        assert_eq!(
+            cached.thumbnail_2x_path.as_deref(),
+            Some("/cache/thumbnails/clip-1@2x.jpg")
+        );
+
+        // The clip was removed before its thumbnail finished
+        let orphan = QueuedThumbnail {
+            clip_id: "gone".to_string(),
+            thumbnail_path: "/cache/thumbnails/gone.jpg".to_string(),
+            thumbnail_2x_path: "/cache/thumbnails/gone@2x.jpg".to_string(),
+        };
+        assert!(apply_thumbnail(&state, &CapturedEvents::default(), &orphan).is_err());
+    }
+
+    #[test]
+    fn test_thumbnail_for_clip_outside_project_reaches_the_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+
+        // A recording stopped with no project open is only cached
+        let recording = MediaClip {
+            thumbnail_path: None,
+            ..mock_clip("rec-1", "/recordings/recording_1.mp4", 12.0)
+        };
+        state
+            .cache_db
+            .lock()
+            .unwrap()
+            .insert_media_clip(&recording)
+            .unwrap();
+
+        let generated = QueuedThumbnail {
+            clip_id: "rec-1".to_string(),
+            thumbnail_path: "/cache/thumbnails/rec-1.jpg".to_string(),
+            thumbnail_2x_path: "/cache/thumbnails/rec-1@2x.jpg".to_string(),
+        };
+        apply_thumbnail(&state, &CapturedEvents::default(), &generated).unwrap();
+
+        let cached = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_media_clip_by_source_path("/recordings/recording_1.mp4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cached.thumbnail_path.as_deref(),
+            Some("/cache/thumbnails/rec-1.jpg")
+        );
+    }
 
     #[test]
     fn test_get_cache_dir() {
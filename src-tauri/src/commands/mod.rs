@@ -1,8 +1,22 @@
 // Commands module - exports all Tauri command handlers
+pub mod assets;
+pub mod cache;
 pub mod captions;
+pub mod comments;
+pub mod events;
 pub mod export;
+pub mod jobs;
+pub mod library;
+pub mod locks;
+pub mod logs;
+pub mod markers;
 pub mod media;
 pub mod playback;
+pub mod preview;
+pub mod progress;
 pub mod project;
 pub mod recording;
+pub mod settings;
+pub mod startup;
+pub mod system_check;
 pub mod timeline;
@@ -1,6 +1,92 @@
 // Playback control commands
+use crate::commands::assets::{allow_asset_dir, asset_url};
+use crate::commands::library::media_clip;
+use crate::commands::locks::LockState;
 use crate::commands::media::AppState;
-use tauri::State;
+use crate::error::ClipForgeError;
+use crate::models::clip::MediaClip;
+use crate::models::playback::PlaybackSequence;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+/// State of a clip's proxy when loading it for playback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyStatus {
+    /// A proxy exists on disk
+    Ready,
+    /// Proxy generation is running; the source is shown meanwhile
+    Pending,
+    /// No proxy exists or is being generated
+    Unavailable,
+}
+
+/// The file the preview should play for a clip
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaybackSource {
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// Whether `path` is the proxy rather than the original
+    pub is_proxy: bool,
+    pub proxy_status: ProxyStatus,
+    /// Proxy was requested but is still being generated
+    pub proxy_pending: bool,
+}
+
+/// Errors from loading a clip for playback
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum PlaybackError {
+    /// No media clip with this id in the library
+    ClipNotFound(String),
+    /// The chosen file no longer exists on disk
+    MissingFile(String),
+}
+
+impl std::fmt::Display for PlaybackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaybackError::ClipNotFound(id) => write!(f, "Media clip not found: {}", id),
+            PlaybackError::MissingFile(path) => write!(f, "File not found: {}", path),
+        }
+    }
+}
+
+/// Choose the playback file for `clip`
+///
+/// `use_proxy=false` always plays the original. `use_proxy=true` plays the
+/// proxy when it exists and otherwise falls back to the original.
+pub fn resolve_playback_source(
+    clip: &MediaClip,
+    use_proxy: bool,
+    proxy_generating: bool,
+    exists: impl Fn(&str) -> bool,
+) -> Result<PlaybackSource, PlaybackError> {
+    let proxy = clip.proxy_path.as_deref().filter(|p| exists(p));
+    let proxy_status = match (proxy, proxy_generating) {
+        (Some(_), _) => ProxyStatus::Ready,
+        (None, true) => ProxyStatus::Pending,
+        (None, false) => ProxyStatus::Unavailable,
+    };
+
+    let (path, is_proxy) = match proxy {
+        Some(proxy) if use_proxy => (proxy.to_string(), true),
+        _ => (clip.source_path.clone(), false),
+    };
+    if !is_proxy && !exists(&path) {
+        return Err(PlaybackError::MissingFile(path));
+    }
+
+    Ok(PlaybackSource {
+        url: asset_url(&path),
+        path,
+        is_proxy,
+        proxy_status,
+        proxy_pending: use_proxy && proxy_status == ProxyStatus::Pending,
+    })
+}
 
 /// T037: Load clip for playback in video preview
 #[tauri::command]
@@ -8,41 +94,121 @@ pub async fn load_clip_for_playback(
     clip_id: String,
     use_proxy: bool,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let library = state.media_library.lock().unwrap();
-    let clip = library
-        .iter()
-        .find(|c| c.id == clip_id)
-        .ok_or_else(|| format!("Media clip not found: {}", clip_id))?;
-
-    // Prefer proxy if available for better web compatibility
-    // Otherwise fall back to source path
-    let playback_path = if use_proxy && clip.proxy_path.is_some() {
-        clip.proxy_path.as_ref().unwrap().clone()
-    } else if clip.proxy_path.is_some() {
-        // Even if not explicitly requested, use proxy if available (better compatibility)
-        clip.proxy_path.as_ref().unwrap().clone()
-    } else {
-        clip.source_path.clone()
-    };
+    app_handle: AppHandle,
+) -> Result<PlaybackSource, PlaybackError> {
+    let clip =
+        media_clip(&state, &clip_id).map_err(|_| PlaybackError::ClipNotFound(clip_id.clone()))?;
+    let proxy_generating = state.proxy_jobs.is_running(&clip_id);
 
-    println!(
-        "load_clip_for_playback: clip_id={}, use_proxy={}, has_proxy={}, returning: {}",
-        clip_id,
-        use_proxy,
-        clip.proxy_path.is_some(),
-        playback_path
-    );
+    let source = resolve_playback_source(&clip, use_proxy, proxy_generating, |p| {
+        Path::new(p).exists()
+    })?;
+    if let Some(dir) = Path::new(&source.path).parent() {
+        allow_asset_dir(&app_handle, dir);
+    }
+    Ok(source)
+}
 
-    Ok(playback_path)
+/// Ordered playback plan (main track and overlays) with explicit gaps
+#[tauri::command]
+pub async fn get_playback_sequence(
+    use_proxies: bool,
+    state: State<'_, AppState>,
+) -> Result<PlaybackSequence, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    Ok(PlaybackSequence::from_project(project, use_proxies))
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn clip(proxy: Option<&str>) -> MediaClip {
+        let mut clip = MediaClip::new(
+            "/media/source.mov".into(),
+            10.0,
+            1920,
+            1080,
+            30.0,
+            "prores".into(),
+            1,
+        );
+        clip.proxy_path = proxy.map(String::from);
+        clip
+    }
+
+    fn all_exist(_: &str) -> bool {
+        true
+    }
+
     #[test]
     fn test_playback_url_encoding() {
         let path = "/Users/test/Videos/my video.mp4";
         let encoded = urlencoding::encode(path);
         assert!(encoded.contains("%20")); // Space should be encoded
     }
+
+    #[test]
+    fn test_original_requested_with_proxy_available() {
+        let source =
+            resolve_playback_source(&clip(Some("/cache/p.mp4")), false, false, all_exist).unwrap();
+        assert_eq!(source.path, "/media/source.mov");
+        assert!(!source.is_proxy);
+        assert_eq!(source.proxy_status, ProxyStatus::Ready);
+        assert!(!source.proxy_pending);
+    }
+
+    #[test]
+    fn test_original_requested_without_proxy() {
+        let source = resolve_playback_source(&clip(None), false, true, all_exist).unwrap();
+        assert_eq!(source.path, "/media/source.mov");
+        assert!(!source.is_proxy);
+        assert_eq!(source.proxy_status, ProxyStatus::Pending);
+        assert!(!source.proxy_pending);
+    }
+
+    #[test]
+    fn test_proxy_requested_and_available() {
+        let source =
+            resolve_playback_source(&clip(Some("/cache/p.mp4")), true, false, all_exist).unwrap();
+        assert_eq!(source.path, "/cache/p.mp4");
+        assert_eq!(source.url, asset_url("/cache/p.mp4"));
+        assert!(source.is_proxy);
+        assert_eq!(source.proxy_status, ProxyStatus::Ready);
+    }
+
+    #[test]
+    fn test_proxy_requested_but_not_ready() {
+        let source = resolve_playback_source(&clip(None), true, true, all_exist).unwrap();
+        assert_eq!(source.path, "/media/source.mov");
+        assert!(!source.is_proxy);
+        assert!(source.proxy_pending);
+
+        let source = resolve_playback_source(&clip(None), true, false, all_exist).unwrap();
+        assert_eq!(source.proxy_status, ProxyStatus::Unavailable);
+        assert!(!source.proxy_pending);
+    }
+
+    #[test]
+    fn test_missing_files() {
+        // Deleted proxy falls back to the original
+        let source = resolve_playback_source(&clip(Some("/cache/p.mp4")), true, false, |p| {
+            p == "/media/source.mov"
+        })
+        .unwrap();
+        assert!(!source.is_proxy);
+        assert_eq!(source.proxy_status, ProxyStatus::Unavailable);
+
+        let err = resolve_playback_source(&clip(Some("/cache/p.mp4")), false, false, |p| {
+            p == "/cache/p.mp4"
+        })
+        .unwrap_err();
+        assert_eq!(
+            err,
+            PlaybackError::MissingFile("/media/source.mov".to_string())
+        );
+    }
 }
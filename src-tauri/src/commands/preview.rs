@@ -0,0 +1,865 @@
+// Timeline preview commands
+// Renders timeline segments into the cache for smooth playback across edit
+// points, and tells the frontend when edits make rendered segments stale.
+
+use crate::commands::assets::asset_url_for_webview;
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::progress::ProgressThrottle;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::clip_segment::{
+    build_clip_segment_command, choose_cut_mode, clip_segment_file_name, CutMode,
+};
+use crate::ffmpeg::errors::classify;
+use crate::ffmpeg::export::cached_keyframes;
+use crate::ffmpeg::frames::{build_frame_command, resolve_frame_sources};
+use crate::ffmpeg::locate::require_ffmpeg;
+use crate::ffmpeg::mixdown::{build_audio_mix_command, plan_audio_mix};
+use crate::ffmpeg::playback_audio_stream;
+use crate::ffmpeg::preview::{
+    build_preview_command, plan_preview_range, plan_segment, AdaptivePreviewScale, PreviewQuality,
+    PreviewScale, PreviewSegmentPlan,
+};
+use crate::ffmpeg::scheduler::{scheduler, JobKind};
+use crate::models::playback::playback_file;
+use crate::models::project::Project;
+use crate::models::settings::PreviewQualityMode;
+use crate::models::timeline::ClipSourceKind;
+use crate::platform::process::{self, ProcessClass};
+use crate::storage::work_dir::{self, WorkArea};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::OnceCell;
+
+/// Playhead positions within this many seconds share one extracted frame
+const FRAME_BUCKET_SECONDS: f64 = 0.1;
+
+/// How long extracted frames are reused
+const FRAME_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Maximum number of cached frames
+const FRAME_CACHE_CAPACITY: usize = 64;
+
+/// Identifies a rendered segment: (project id, quality, scale, segment index)
+type SegmentSlot = (String, PreviewQuality, PreviewScale, u64);
+
+/// Identifies a rendered audio mixdown: (project id, start bits, end bits)
+type AudioSlot = (String, u64, u64);
+
+/// Segments rendered for the current project, so edits can invalidate them
+#[derive(Clone, Default)]
+pub struct PreviewState {
+    /// Slot -> key of the segment rendered into it
+    rendered: Arc<Mutex<HashMap<SegmentSlot, String>>>,
+    /// Slot -> key of the audio mixdown rendered for that range
+    rendered_audio: Arc<Mutex<HashMap<AudioSlot, String>>>,
+    /// Scale chosen from render speed in `auto` quality mode
+    auto_scale: Arc<Mutex<AdaptivePreviewScale>>,
+    /// Timeline clip id -> file rendered by `render_clip_segment`
+    clip_segments: Arc<Mutex<HashMap<String, PathBuf>>>,
+}
+
+impl PreviewState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, project_id: &str, plan: &PreviewSegmentPlan) {
+        self.rendered.lock_recovering().insert(
+            (project_id.to_string(), plan.quality, plan.scale, plan.index),
+            plan.key.clone(),
+        );
+    }
+
+    /// Scale to render at for the configured quality mode
+    fn scale_for(&self, mode: PreviewQualityMode) -> PreviewScale {
+        match mode {
+            PreviewQualityMode::Full => PreviewScale::Full,
+            PreviewQualityMode::Half => PreviewScale::Half,
+            PreviewQualityMode::Quarter => PreviewScale::Quarter,
+            PreviewQualityMode::Auto => self.auto_scale.lock_recovering().current(),
+        }
+    }
+
+    /// Feed a render timing to the `auto` mode; returns the new scale if it changed
+    fn record_render_time(&self, render: Duration, content_seconds: f64) -> Option<PreviewScale> {
+        self.auto_scale
+            .lock_recovering()
+            .record(render.as_secs_f64(), content_seconds)
+    }
+
+    fn record_audio(&self, project_id: &str, start: f64, end: f64, key: String) {
+        self.rendered_audio.lock_recovering().insert(
+            (project_id.to_string(), start.to_bits(), end.to_bits()),
+            key,
+        );
+    }
+
+    fn record_clip_segment(&self, timeline_clip_id: &str, path: PathBuf) {
+        self.clip_segments
+            .lock_recovering()
+            .insert(timeline_clip_id.to_string(), path);
+    }
+
+    /// Forget clip renders whose clip was retrimmed or removed, deleting
+    /// files no other clip still uses
+    fn take_stale_clip_segments(&self, project: &Project) -> Vec<String> {
+        let mut segments = self.clip_segments.lock_recovering();
+        let mut stale = Vec::new();
+        let mut removed_files = Vec::new();
+        segments.retain(|clip_id, path| {
+            let current = project
+                .tracks
+                .iter()
+                .flat_map(|t| &t.clips)
+                .find(|c| c.id == *clip_id)
+                .map(|c| {
+                    let audio_stream = project
+                        .media_library
+                        .iter()
+                        .find(|m| m.id == c.media_clip_id)
+                        .and_then(|media| {
+                            playback_audio_stream(media, playback_file(media, true).1)
+                        });
+                    clip_segment_file_name(&c.media_clip_id, c.in_point, c.out_point, audio_stream)
+                });
+            if current.is_some_and(|name| path.ends_with(name)) {
+                return true;
+            }
+            stale.push(clip_id.clone());
+            removed_files.push(path.clone());
+            false
+        });
+        for path in removed_files {
+            if !segments.values().any(|p| *p == path) {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+        stale.sort();
+        stale
+    }
+
+    /// Forget rendered audio mixdowns whose content no longer matches `project`
+    fn take_stale_audio(&self, project: &Project) -> Vec<AudioRange> {
+        let mut rendered = self.rendered_audio.lock_recovering();
+        let mut stale = Vec::new();
+        rendered.retain(|(project_id, start, end), key| {
+            if *project_id != project.id {
+                return true;
+            }
+            let (start, end) = (f64::from_bits(*start), f64::from_bits(*end));
+            if plan_audio_mix(project, start, end).key == *key {
+                return true;
+            }
+            stale.push(AudioRange { start, end });
+            false
+        });
+        stale.sort_by(|a, b| a.start.total_cmp(&b.start));
+        stale
+    }
+
+    /// Forget rendered segments whose content no longer matches `project`
+    fn take_stale(&self, project: &Project) -> Vec<InvalidatedSegment> {
+        let mut rendered = self.rendered.lock_recovering();
+        let mut stale = Vec::new();
+        rendered.retain(|(project_id, quality, scale, index), key| {
+            if *project_id != project.id {
+                return true;
+            }
+            let current = plan_segment(project, *index, *quality, *scale);
+            if current.as_ref().is_some_and(|plan| plan.key == *key) {
+                return true;
+            }
+            stale.push(InvalidatedSegment {
+                index: *index,
+                quality: *quality,
+                scale: *scale,
+            });
+            false
+        });
+        stale.sort_by_key(|s| s.index);
+        stale
+    }
+}
+
+/// Identifies an extracted frame: project, revision, time bucket, width, composite
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FrameKey {
+    project_id: String,
+    revision: u64,
+    bucket: i64,
+    max_width: u32,
+    composite: bool,
+}
+
+/// A cached frame; concurrent requests for the same key share one extraction
+struct CachedFrame {
+    created: Instant,
+    result: Arc<OnceCell<Result<PathBuf, String>>>,
+}
+
+/// Recently extracted timeline frames (scrubbing)
+#[derive(Clone, Default)]
+pub struct FrameCache {
+    frames: Arc<Mutex<HashMap<FrameKey, CachedFrame>>>,
+}
+
+impl FrameCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared slot for `key`, evicting expired and excess entries
+    fn slot(&self, key: FrameKey) -> Arc<OnceCell<Result<PathBuf, String>>> {
+        let mut frames = self.frames.lock_recovering();
+        let now = Instant::now();
+        frames.retain(|_, frame| {
+            let fresh = now.duration_since(frame.created) < FRAME_CACHE_TTL;
+            if !fresh {
+                if let Some(Ok(path)) = frame.result.get() {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+            fresh
+        });
+        if frames.len() >= FRAME_CACHE_CAPACITY && !frames.contains_key(&key) {
+            let oldest = frames
+                .iter()
+                .min_by_key(|(_, frame)| frame.created)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest.and_then(|key| frames.remove(&key)) {
+                if let Some(Ok(path)) = oldest.result.get() {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+        }
+
+        frames
+            .entry(key)
+            .or_insert_with(|| CachedFrame {
+                created: now,
+                result: Arc::new(OnceCell::new()),
+            })
+            .result
+            .clone()
+    }
+}
+
+/// A frame of the edited timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineFrame {
+    /// Timeline time the frame was taken at (start of the time bucket)
+    pub time: f64,
+    /// JPEG file in the cache
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// Base64-encoded JPEG, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+}
+
+/// Get the frame under the playhead at timeline `time`
+///
+/// Frames are cached per 100ms bucket and project revision, and concurrent
+/// requests for the same bucket share one FFmpeg run. `composite` draws
+/// visible overlays (slower); otherwise only the main track is shown.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_timeline_frame(
+    time: f64,
+    max_width: Option<u32>,
+    composite: Option<bool>,
+    base64: Option<bool>,
+    state: State<'_, AppState>,
+    preview_state: State<'_, PreviewState>,
+    frame_cache: State<'_, FrameCache>,
+    app_handle: AppHandle,
+) -> Result<TimelineFrame, ClipForgeError> {
+    require_ffmpeg()?;
+
+    if !time.is_finite() || time < 0.0 {
+        return Err(ClipForgeError::validation(
+            "time",
+            format!("Invalid timeline time: {}", time),
+        ));
+    }
+    let mode = state.settings.lock_state()?.preview_quality;
+    let scale = preview_state.scale_for(mode);
+    let max_width = scale.apply((max_width.unwrap_or(640), 2)).0;
+    let composite = composite.unwrap_or(false);
+    let project = state
+        .project
+        .lock_state()?
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?
+        .flattened()?;
+
+    let bucket = (time / FRAME_BUCKET_SECONDS).floor() as i64;
+    let bucket_time = bucket as f64 * FRAME_BUCKET_SECONDS;
+    let key = FrameKey {
+        project_id: project.id.clone(),
+        revision: project.revision,
+        bucket,
+        max_width,
+        composite,
+    };
+
+    let slot = frame_cache.slot(key.clone());
+    let path = slot
+        .get_or_init(|| async {
+            let dir = work_dir::area_dir(WorkArea::Preview)?
+                .join("frames")
+                .join(&project.id);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create frame directory: {}", e))?;
+            let path = dir.join(format!(
+                "r{}_{}_{}{}.jpg",
+                key.revision,
+                key.bucket,
+                key.max_width,
+                if composite { "_c" } else { "" }
+            ));
+
+            let sources = resolve_frame_sources(&project, bucket_time);
+            let cmd = build_frame_command(&sources, &project.settings, max_width, composite, &path);
+            let _permit = scheduler().acquire(JobKind::Preview, &project.name).await;
+            let output = process::output(cmd, ProcessClass::Preview).await?;
+            if !output.status.success() || !path.exists() {
+                return Err(format!(
+                    "Frame extraction failed at {:.2}s: {}",
+                    bucket_time,
+                    String::from_utf8_lossy(&output.stderr)
+                        .lines()
+                        .last()
+                        .unwrap_or_default()
+                ));
+            }
+            Ok(path)
+        })
+        .await
+        .clone()?;
+
+    let base64 = if base64.unwrap_or(false) {
+        use base64::Engine;
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read frame: {}", e))?;
+        Some(base64::engine::general_purpose::STANDARD.encode(bytes))
+    } else {
+        None
+    };
+
+    let path = path.to_string_lossy().to_string();
+    Ok(TimelineFrame {
+        time: bucket_time,
+        url: asset_url_for_webview(&app_handle, &path),
+        path,
+        base64,
+    })
+}
+
+/// A playable preview segment
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewSegment {
+    pub index: u64,
+    /// Timeline start of the segment in seconds
+    pub start: f64,
+    /// Timeline end of the segment in seconds
+    pub end: f64,
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// Whether an existing render was reused
+    pub cached: bool,
+}
+
+/// `preview_progress` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewProgressEvent {
+    pub project_id: String,
+    /// Increases with every event of the render; older events can be dropped
+    pub sequence: u64,
+    pub completed: usize,
+    pub total: usize,
+    pub segment_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidatedSegment {
+    pub index: u64,
+    pub quality: PreviewQuality,
+    pub scale: PreviewScale,
+}
+
+/// `preview_quality_changed` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewQualityChangedEvent {
+    pub scale: PreviewScale,
+    /// Renders were slower than real time (performance mode)
+    pub degraded: bool,
+}
+
+/// Timeline range of an audio mixdown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// `preview_invalidated` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewInvalidatedEvent {
+    pub project_id: String,
+    pub segments: Vec<InvalidatedSegment>,
+    /// Audio mixdowns that must be re-rendered
+    pub audio_ranges: Vec<AudioRange>,
+}
+
+/// A rendered audio mixdown
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioPreview {
+    pub start: f64,
+    pub end: f64,
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// Whether an existing render was reused
+    pub cached: bool,
+}
+
+/// Mix all audible tracks between `start` and `end` into one AAC file
+///
+/// The file is cached by content, so repeated requests for an unchanged
+/// range are free; edits that change the mix trigger `preview_invalidated`.
+#[tauri::command]
+pub async fn render_audio_preview(
+    start: f64,
+    end: f64,
+    state: State<'_, AppState>,
+    preview_state: State<'_, PreviewState>,
+    app_handle: AppHandle,
+) -> Result<AudioPreview, ClipForgeError> {
+    require_ffmpeg()?;
+
+    if start < 0.0 || end <= start {
+        return Err(ClipForgeError::validation(
+            "end",
+            "end must be greater than start",
+        ));
+    }
+    let project = state
+        .project
+        .lock_state()?
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?
+        .flattened()?;
+
+    let preview_dir = work_dir::area_dir(WorkArea::Preview)?.join(&project.id);
+    std::fs::create_dir_all(&preview_dir)
+        .map_err(|e| format!("Failed to create preview directory: {}", e))?;
+
+    let plan = plan_audio_mix(&project, start, end);
+    let output_path = preview_dir.join(format!("audio_{}.m4a", plan.key));
+    let cached = output_path.exists();
+    if !cached {
+        let partial = preview_dir.join(format!("audio_{}.part.m4a", plan.key));
+        let _permit = scheduler().acquire(JobKind::Preview, &project.name).await;
+        let output = process::output(
+            build_audio_mix_command(&plan, &partial),
+            ProcessClass::Preview,
+        )
+        .await?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&partial);
+            return Err(classify(&String::from_utf8_lossy(&output.stderr)).into());
+        }
+        std::fs::rename(&partial, &output_path)
+            .map_err(|e| format!("Failed to store audio preview: {}", e))?;
+    }
+    preview_state.record_audio(&project.id, start, end, plan.key);
+
+    let path = output_path.to_string_lossy().to_string();
+    Ok(AudioPreview {
+        start,
+        end,
+        url: asset_url_for_webview(&app_handle, &path),
+        path,
+        cached,
+    })
+}
+
+/// Render the timeline between `start` and `end` into cached preview segments
+///
+/// Segments whose content is unchanged since a previous render are reused.
+/// Emits `preview_progress` after each segment.
+#[tauri::command]
+pub async fn render_preview_range(
+    start: f64,
+    end: f64,
+    quality: Option<PreviewQuality>,
+    state: State<'_, AppState>,
+    preview_state: State<'_, PreviewState>,
+    app_handle: AppHandle,
+) -> Result<Vec<PreviewSegment>, ClipForgeError> {
+    require_ffmpeg()?;
+
+    if end <= start {
+        return Err(ClipForgeError::validation(
+            "end",
+            "end must be greater than start",
+        ));
+    }
+    let quality = quality.unwrap_or_default();
+    let mode = state.settings.lock_state()?.preview_quality;
+    let scale = preview_state.scale_for(mode);
+    let project = state
+        .project
+        .lock_state()?
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?
+        .flattened()?;
+
+    let preview_dir = work_dir::area_dir(WorkArea::Preview)?.join(&project.id);
+    std::fs::create_dir_all(&preview_dir)
+        .map_err(|e| format!("Failed to create preview directory: {}", e))?;
+
+    let plans = plan_preview_range(&project, start, end, quality, scale);
+    let mut segments = Vec::with_capacity(plans.len());
+
+    let mut throttle = ProgressThrottle::new();
+    for (completed, plan) in plans.iter().enumerate() {
+        let output_path = preview_dir.join(format!("{}.mp4", plan.key));
+        let cached = output_path.exists();
+
+        if !cached {
+            let partial = preview_dir.join(format!("{}.part.mp4", plan.key));
+            let cmd = build_preview_command(plan, &project.settings, &partial);
+
+            let started = Instant::now();
+            let _permit = scheduler().acquire(JobKind::Preview, &project.name).await;
+            let output = process::output(cmd, ProcessClass::Preview).await?;
+            if !output.status.success() {
+                let _ = std::fs::remove_file(&partial);
+                log::warn!("Preview render failed for segment {}", plan.index);
+                return Err(classify(&String::from_utf8_lossy(&output.stderr)).into());
+            }
+            std::fs::rename(&partial, &output_path)
+                .map_err(|e| format!("Failed to store preview segment: {}", e))?;
+
+            if mode == PreviewQualityMode::Auto {
+                let changed =
+                    preview_state.record_render_time(started.elapsed(), plan.end - plan.start);
+                if let Some(new_scale) = changed {
+                    println!(
+                        "[Preview] Auto quality switched to {} scale",
+                        new_scale.as_str()
+                    );
+                    let _ = app_handle.emit_all(
+                        "preview_quality_changed",
+                        PreviewQualityChangedEvent {
+                            scale: new_scale,
+                            degraded: new_scale != PreviewScale::Full,
+                        },
+                    );
+                }
+            }
+        }
+
+        preview_state.record(&project.id, plan);
+        // Reused segments complete in a burst; the last one always reports
+        let fraction = (completed + 1) as f64 / plans.len() as f64;
+        let sequence = if completed + 1 == plans.len() {
+            Some(throttle.always(fraction))
+        } else {
+            throttle.sample(fraction)
+        };
+        if let Some(sequence) = sequence {
+            let _ = app_handle.emit_all(
+                "preview_progress",
+                PreviewProgressEvent {
+                    project_id: project.id.clone(),
+                    sequence,
+                    completed: completed + 1,
+                    total: plans.len(),
+                    segment_index: plan.index,
+                },
+            );
+        }
+
+        segments.push(PreviewSegment {
+            index: plan.index,
+            start: plan.start,
+            end: plan.end,
+            path: output_path.to_string_lossy().to_string(),
+            url: asset_url_for_webview(&app_handle, &output_path.to_string_lossy()),
+            cached,
+        });
+    }
+
+    println!(
+        "[Preview] Rendered {:.2}-{:.2}s: {} segments ({} reused)",
+        start,
+        end,
+        segments.len(),
+        segments.iter().filter(|s| s.cached).count()
+    );
+    Ok(segments)
+}
+
+/// A timeline clip's trimmed range rendered to its own file
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipSegment {
+    pub timeline_clip_id: String,
+    pub path: String,
+    /// Asset URL of `path` for the webview
+    pub url: String,
+    /// Cut without re-encoding (in-point on a keyframe); None when reused
+    pub stream_copy: Option<bool>,
+    /// Whether an existing render was reused
+    pub cached: bool,
+}
+
+/// `clip_segment_invalidated` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipSegmentInvalidatedEvent {
+    pub project_id: String,
+    pub timeline_clip_ids: Vec<String>,
+}
+
+/// Render a timeline clip's `in_point..out_point` into a standalone file
+///
+/// Cuts from the proxy when one exists. Renders are cached per media range,
+/// so clips sharing a trim share the file; retrimming emits
+/// `clip_segment_invalidated`.
+#[tauri::command]
+pub async fn render_clip_segment(
+    timeline_clip_id: String,
+    state: State<'_, AppState>,
+    preview_state: State<'_, PreviewState>,
+    app_handle: AppHandle,
+) -> Result<ClipSegment, ClipForgeError> {
+    require_ffmpeg()?;
+
+    let (clip, media) = {
+        let project_lock = state.project.lock_state()?;
+        let project = project_lock
+            .as_ref()
+            .ok_or_else(ClipForgeError::no_project)?;
+        let clip = project
+            .tracks
+            .iter()
+            .flat_map(|t| &t.clips)
+            .find(|c| c.id == timeline_clip_id)
+            .cloned()
+            .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &timeline_clip_id))?;
+        if clip.source_kind == ClipSourceKind::Compound {
+            return Err(ClipForgeError::unsupported(
+                "Compound clips are previewed through the timeline",
+            ));
+        }
+        let media = project
+            .media_library
+            .iter()
+            .find(|m| m.id == clip.media_clip_id)
+            .cloned()
+            .ok_or_else(|| ClipForgeError::not_found("media_clip", &clip.media_clip_id))?;
+        (clip, media)
+    };
+
+    let dir = work_dir::area_dir(WorkArea::Preview)?.join("clip_segments");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create clip segment directory: {}", e))?;
+    let (source, is_proxy) = playback_file(&media, true);
+    let audio_stream = playback_audio_stream(&media, is_proxy);
+    let output_path = dir.join(clip_segment_file_name(
+        &media.id,
+        clip.in_point,
+        clip.out_point,
+        audio_stream,
+    ));
+
+    let cached = output_path.exists();
+    let mut stream_copy = None;
+    if !cached {
+        let keyframes = cached_keyframes(&source).await.unwrap_or_else(|e| {
+            eprintln!("[Preview] Keyframe probe failed, re-encoding: {}", e);
+            Vec::new()
+        });
+        let mode = choose_cut_mode(&keyframes, clip.in_point, media.fps);
+        stream_copy = Some(matches!(mode, CutMode::StreamCopy { .. }));
+
+        let partial = output_path.with_extension("part.mp4");
+        let _permit = scheduler().acquire(JobKind::Preview, &media.name).await;
+        let output = process::output(
+            build_clip_segment_command(
+                &source,
+                clip.in_point,
+                clip.out_point,
+                mode,
+                audio_stream,
+                &partial,
+            )
+            .build(),
+            ProcessClass::Preview,
+        )
+        .await?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&partial);
+            return Err(classify(&String::from_utf8_lossy(&output.stderr)).into());
+        }
+        std::fs::rename(&partial, &output_path)
+            .map_err(|e| format!("Failed to store clip segment: {}", e))?;
+    }
+    preview_state.record_clip_segment(&timeline_clip_id, output_path.clone());
+
+    let path = output_path.to_string_lossy().to_string();
+    Ok(ClipSegment {
+        timeline_clip_id,
+        url: asset_url_for_webview(&app_handle, &path),
+        path,
+        stream_copy,
+        cached,
+    })
+}
+
+/// Emit `preview_invalidated` for rendered segments made stale by a modification
+pub fn invalidate_previews(app_handle: &AppHandle, project: &Project) {
+    let Some(preview_state) = app_handle.try_state::<PreviewState>() else {
+        return;
+    };
+    let segments = preview_state.take_stale(project);
+    let audio_ranges = preview_state.take_stale_audio(project);
+    if !segments.is_empty() || !audio_ranges.is_empty() {
+        let _ = app_handle.emit_all(
+            "preview_invalidated",
+            PreviewInvalidatedEvent {
+                project_id: project.id.clone(),
+                segments,
+                audio_ranges,
+            },
+        );
+    }
+
+    let timeline_clip_ids = preview_state.take_stale_clip_segments(project);
+    if !timeline_clip_ids.is_empty() {
+        let _ = app_handle.emit_all(
+            "clip_segment_invalidated",
+            ClipSegmentInvalidatedEvent {
+                project_id: project.id.clone(),
+                timeline_clip_ids,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaClip;
+    use crate::models::timeline::TimelineClip;
+
+    #[test]
+    fn test_take_stale_segments() {
+        let mut project = Project::new("Preview".to_string());
+        let media = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        let track_id = project.tracks[0].id.clone();
+        project.tracks[0].clips.push(TimelineClip::new(
+            media.id.clone(),
+            track_id,
+            0.0,
+            0.0,
+            12.0,
+        ));
+        project.media_library.push(media);
+
+        let state = PreviewState::new();
+        for plan in plan_preview_range(
+            &project,
+            0.0,
+            12.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full,
+        ) {
+            state.record(&project.id, &plan);
+        }
+        let other =
+            plan_segment(&project, 0, PreviewQuality::Standard, PreviewScale::Full).unwrap();
+        state.record("other-project", &other);
+
+        // Nothing changed
+        assert!(state.take_stale(&project).is_empty());
+
+        // Trimming the end only touches the last segment
+        project.tracks[0].clips[0].out_point = 11.0;
+        let stale = state.take_stale(&project);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].index, 2);
+
+        // Stale entries are forgotten; other projects are untouched
+        assert!(state.take_stale(&project).is_empty());
+        assert_eq!(state.rendered.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_take_stale_clip_segments() {
+        let mut project = Project::new("Preview".to_string());
+        let media = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        let track_id = project.tracks[0].id.clone();
+        let clip = TimelineClip::new(media.id.clone(), track_id, 0.0, 1.0, 4.0);
+        let clip_id = clip.id.clone();
+        project.tracks[0].clips.push(clip);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join(clip_segment_file_name(&media.id, 1.0, 4.0, None));
+        std::fs::write(&path, b"segment").unwrap();
+
+        let state = PreviewState::new();
+        state.record_clip_segment(&clip_id, path.clone());
+        assert!(state.take_stale_clip_segments(&project).is_empty());
+
+        // Retrimming invalidates the render and removes its file
+        project.tracks[0].clips[0].out_point = 3.5;
+        assert_eq!(state.take_stale_clip_segments(&project), vec![clip_id]);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_take_stale_audio() {
+        let mut project = Project::new("Preview".to_string());
+        let mut media = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        media.has_audio = true;
+        let track_id = project.tracks[0].id.clone();
+        project.tracks[0].clips.push(TimelineClip::new(
+            media.id.clone(),
+            track_id,
+            0.0,
+            0.0,
+            12.0,
+        ));
+        project.media_library.push(media);
+
+        let state = PreviewState::new();
+        state.record_audio(
+            &project.id,
+            0.0,
+            5.0,
+            plan_audio_mix(&project, 0.0, 5.0).key,
+        );
+        assert!(state.take_stale_audio(&project).is_empty());
+
+        project.tracks[0].volume = 0.5;
+        assert_eq!(
+            state.take_stale_audio(&project),
+            vec![AudioRange {
+                start: 0.0,
+                end: 5.0
+            }]
+        );
+        assert!(state.take_stale_audio(&project).is_empty());
+    }
+}
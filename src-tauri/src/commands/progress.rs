@@ -0,0 +1,107 @@
+// Rate limiting for progress events
+// FFmpeg reports progress many times a second; forwarding every report
+// floods the webview's event queue until updates arrive late (even after the
+// job finished). Emitters pass each report through a `ProgressThrottle`,
+// which lets through at most one every 250 ms unless progress moved by at
+// least 1%, and stamps the ones it lets through with an increasing sequence
+// number so the frontend can drop stale or reordered events.
+
+use std::time::{Duration, Instant};
+
+/// Minimum time between two throttled events
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Progress change (0.0 - 1.0) that is emitted regardless of timing
+pub const PROGRESS_STEP: f64 = 0.01;
+
+/// Decides which progress reports of one job are emitted
+#[derive(Debug, Default)]
+pub struct ProgressThrottle {
+    last_emitted: Option<(Instant, f64)>,
+    sequence: u64,
+}
+
+impl ProgressThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sequence number to emit `progress` with, or None to drop the report
+    pub fn sample(&mut self, progress: f64) -> Option<u64> {
+        self.sample_at(progress, Instant::now())
+    }
+
+    /// Sequence number for a report that must not be dropped (stage
+    /// changes, the final 100%)
+    pub fn always(&mut self, progress: f64) -> u64 {
+        self.emit(progress, Instant::now())
+    }
+
+    fn sample_at(&mut self, progress: f64, now: Instant) -> Option<u64> {
+        let due = match self.last_emitted {
+            None => true,
+            Some((at, last)) => {
+                now.duration_since(at) >= PROGRESS_INTERVAL
+                    || (progress - last).abs() >= PROGRESS_STEP
+            }
+        };
+        due.then(|| self.emit(progress, now))
+    }
+
+    fn emit(&mut self, progress: f64, now: Instant) -> u64 {
+        self.last_emitted = Some((now, progress));
+        self.sequence += 1;
+        self.sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_timing() {
+        let mut throttle = ProgressThrottle::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // The first report always goes out
+        assert!(throttle.sample_at(0.0, at(0)).is_some());
+        // Small changes within 250 ms are dropped
+        assert!(throttle.sample_at(0.002, at(40)).is_none());
+        assert!(throttle.sample_at(0.009, at(200)).is_none());
+        // ...until the interval has passed
+        assert!(throttle.sample_at(0.009, at(250)).is_some());
+        assert!(throttle.sample_at(0.010, at(300)).is_none());
+        // A 1% step goes out immediately
+        assert!(throttle.sample_at(0.02, at(310)).is_some());
+        assert!(throttle.sample_at(0.5, at(311)).is_some());
+
+        // A burst of 1000 small reports over one second yields one per interval
+        let mut throttle = ProgressThrottle::new();
+        let emitted = (0..1000)
+            .filter(|i| throttle.sample_at(*i as f64 / 100_000.0, at(*i)).is_some())
+            .count();
+        assert_eq!(emitted, 4);
+    }
+
+    #[test]
+    fn test_sequence_is_monotonic() {
+        let mut throttle = ProgressThrottle::new();
+        let start = Instant::now();
+        let mut sequences = Vec::new();
+        for i in 0..50u64 {
+            if let Some(sequence) = throttle.sample_at(i as f64 / 50.0, start) {
+                sequences.push(sequence);
+            }
+        }
+        sequences.push(throttle.always(1.0));
+        assert_eq!(sequences.len(), 51);
+        assert!(sequences.windows(2).all(|w| w[1] == w[0] + 1));
+        assert_eq!(sequences[0], 1);
+
+        // Forced reports are never dropped, even right after another
+        let last = throttle.always(1.0);
+        assert_eq!(last, 52);
+    }
+}
@@ -1,19 +1,1245 @@
-// Project command stubs - implement these according to tasks.md
+// Project commands: create, save, load, close, and backup restore
 
+use crate::commands::export::{record_export_history, ExportState};
+use crate::commands::jobs::{jobs_blocking_exit, JobsUpdatedEvent};
+use crate::commands::library::{publish_media_change, MediaChange};
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::recording::has_active_recording;
+use crate::error::ClipForgeError;
+use crate::models::export::ExportSettings;
+use crate::models::project::{Project, ProjectSettings};
+use crate::storage::cache::RecentProject;
+use crate::storage::project_archive::{
+    extract_project_archive, write_project_archive, ArchiveError, ArchiveManifest, ArchiveOptions,
+    ArchiveProgress,
+};
+use crate::storage::project_file::{
+    list_project_backups as list_backups, load_project_file, restore_project_file,
+    save_project_file, ProjectBackup,
+};
+use crate::storage::project_merge::{
+    merge_project_section, ImportSectionOptions, ImportSectionSummary,
+};
+use crate::storage::project_paths::find_missing_media;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// How often the auto-save task checks the setting while auto-save is off
+const AUTOSAVE_IDLE_CHECK: Duration = Duration::from_secs(60);
+
+/// Save project response
+#[derive(Debug, Serialize)]
+pub struct SaveProjectResult {
+    pub success: bool,
+    pub path: String,
+}
+
+/// Load project response: the project plus media files that could not be found
+#[derive(Debug, Serialize)]
+pub struct LoadProjectResult {
+    #[serde(flatten)]
+    pub project: Project,
+    /// Resolved source paths that don't exist on disk
+    pub missing_media: Vec<String>,
+}
+
+/// Errors that prevent closing the current project
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CloseProjectError {
+    /// A recording is in progress and would lose its target project
+    RecordingActive(String),
+    /// `save_first` was requested but the save failed
+    SaveFailed(String),
+}
+
+impl std::fmt::Display for CloseProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CloseProjectError::RecordingActive(msg) | CloseProjectError::SaveFailed(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+/// `project_closed` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectClosedEvent {
+    pub project_id: String,
+    /// Where the project was saved before closing, if requested
+    pub saved_to: Option<String>,
+    /// Export jobs of the project that were cancelled
+    pub cancelled_exports: Vec<String>,
+    /// Clips whose pending proxy generation was aborted
+    pub cancelled_proxies: Vec<String>,
+}
+
+/// `project_state_changed` event payload, emitted when dirtiness flips
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectStateEvent {
+    pub project_id: String,
+    pub dirty: bool,
+    pub modified_at: chrono::DateTime<chrono::Utc>,
+    pub last_saved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ProjectStateEvent {
+    pub fn from_project(project: &Project) -> Self {
+        Self {
+            project_id: project.id.clone(),
+            dirty: project.dirty,
+            modified_at: project.modified_at,
+            last_saved_at: project.last_saved_at,
+        }
+    }
+}
+
+/// Part of the project touched by a modification
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModificationScope {
+    Timeline,
+    MediaLibrary,
+    Settings,
+    Captions,
+}
+
+/// `project_modified` event payload, emitted on every modification
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectModifiedEvent {
+    pub project_id: String,
+    pub scope: ModificationScope,
+    /// Ids of the tracks, clips, media, or captions that changed
+    pub entity_ids: Vec<String>,
+    /// Project revision after the change
+    pub revision: u64,
+}
+
+/// Record a modification of `project` and notify the frontend
+///
+/// Emits `project_modified`, plus `project_state_changed` if the project just
+/// became dirty. Every mutating command goes through this.
+pub fn record_modification(
+    app_handle: &AppHandle,
+    project: &mut Project,
+    scope: ModificationScope,
+    entity_ids: Vec<String>,
+) {
+    let became_dirty = project.mark_modified();
+    let _ = app_handle.emit_all(
+        "project_modified",
+        ProjectModifiedEvent {
+            project_id: project.id.clone(),
+            scope,
+            entity_ids,
+            revision: project.revision,
+        },
+    );
+    if became_dirty {
+        emit_project_state(app_handle, project);
+    }
+    if scope != ModificationScope::Captions {
+        crate::commands::preview::invalidate_previews(app_handle, project);
+    }
+}
+
+/// Emit `project_state_changed` for the given project
+pub fn emit_project_state(app_handle: &AppHandle, project: &Project) {
+    let _ = app_handle.emit_all(
+        "project_state_changed",
+        ProjectStateEvent::from_project(project),
+    );
+}
+
+/// Create a new empty project and make it the current project
+#[tauri::command]
+pub async fn create_new_project(
+    name: String,
+    settings: Option<ProjectSettings>,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<Project, ClipForgeError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ClipForgeError::validation(
+            "name",
+            "Project name cannot be empty",
+        ));
+    }
+
+    if let Some(ref settings) = settings {
+        settings
+            .validate()
+            .map_err(|e| ClipForgeError::validation("settings", e))?;
+    }
+
+    close_current_project(false, &state, &export_state, &app_handle)?;
+
+    let mut project = Project::new(name.to_string());
+    if let Some(settings) = settings {
+        project.settings = settings;
+    }
+    *state.project.lock_state()? = Some(project.clone());
+
+    println!(
+        "[Project] Created project '{}' ({})",
+        project.name, project.id
+    );
+    Ok(project)
+}
+
+/// Save the current project to `path`
+///
+/// The write is atomic; the previous file is kept as a rotating backup and
+/// left untouched if the save fails.
+#[tauri::command]
+pub async fn save_project(
+    path: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SaveProjectResult, ClipForgeError> {
+    if save_current_project(&state, &path)? {
+        if let Some(ref project) = *state.project.lock_state()? {
+            emit_project_state(&app_handle, project);
+        }
+    }
+
+    Ok(SaveProjectResult {
+        success: true,
+        path,
+    })
+}
+
+/// Load a project file and make it the current project
+///
+/// The currently open project (if any) is closed without saving first.
+#[tauri::command]
+pub async fn load_project(
+    path: String,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<LoadProjectResult, ClipForgeError> {
+    let project = load_project_file(&PathBuf::from(&path))?;
+    close_current_project(false, &state, &export_state, &app_handle)?;
+
+    let result = set_current_project(project, path, &state);
+    emit_project_state(&app_handle, &result.project);
+    Ok(result)
+}
+
+/// Save the current project under a new path ("Save As")
+///
+/// The project gets a fresh id unless `keep_id` is set; the original file is
+/// left untouched and the live project now points at `new_path`.
+#[tauri::command]
+pub async fn save_project_as(
+    new_path: String,
+    new_name: Option<String>,
+    keep_id: Option<bool>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Project, ClipForgeError> {
+    let project = save_current_project_as(&state, &new_path, new_name, keep_id.unwrap_or(false))?;
+    emit_project_state(&app_handle, &project);
+    Ok(project)
+}
+
+/// Copy a project file (not necessarily loaded) to `new_path` with a fresh id
+#[tauri::command]
+pub async fn duplicate_project(
+    source_path: String,
+    new_path: String,
+    state: State<'_, AppState>,
+) -> Result<Project, ClipForgeError> {
+    duplicate_project_file(&state, &source_path, &new_path)
+}
+
+/// Result of `import_project_section`: what was merged, plus the updated project
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSectionResult {
+    #[serde(flatten)]
+    pub summary: ImportSectionSummary,
+    pub project: Project,
+}
+
+/// Merge another project file's timeline into the current project
+///
+/// All ids from the other project are regenerated, media already in the
+/// library is reused, and settings conflicts are returned as warnings.
 #[tauri::command]
-pub async fn create_new_project(_name: String) -> Result<String, String> {
-    // TODO: T104 - Implement project creation
-    Err("Not implemented yet".to_string())
+pub async fn import_project_section(
+    path: String,
+    options: Option<ImportSectionOptions>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ImportSectionResult, ClipForgeError> {
+    let section = load_project_file(&PathBuf::from(&path))?;
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    if project.file_path.as_deref() == Some(path.as_str()) {
+        return Err(ClipForgeError::validation(
+            "path",
+            "Cannot import a project into itself",
+        ));
+    }
+
+    let summary = merge_project_section(project, section, &options.unwrap_or_default());
+
+    let media_ids = summary.media_added.iter().map(|c| c.id.clone()).collect();
+    publish_media_change(
+        &state,
+        &app_handle,
+        project,
+        MediaChange::Added,
+        ModificationScope::MediaLibrary,
+        media_ids,
+    );
+    let track_ids = summary
+        .tracks_added
+        .iter()
+        .chain(&summary.tracks_extended)
+        .cloned()
+        .collect();
+    record_modification(&app_handle, project, ModificationScope::Timeline, track_ids);
+
+    println!(
+        "[Project] Imported section from {}: {} clips, {} new tracks, {} new media ({} reused), {} warnings",
+        path,
+        summary.clips_imported,
+        summary.tracks_added.len(),
+        summary.media_added.len(),
+        summary.media_reused,
+        summary.warnings.len()
+    );
+
+    Ok(ImportSectionResult {
+        summary,
+        project: project.clone(),
+    })
+}
+
+/// Bundle the current project, its media, and thumbnails into a `.clipforgepkg`
+///
+/// Streams in the background, emitting `archive_progress` events.
+#[tauri::command]
+pub async fn export_project_archive(
+    output_path: String,
+    options: Option<ArchiveOptions>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ArchiveManifest, ClipForgeError> {
+    let project = state
+        .project
+        .lock_state()?
+        .clone()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let options = options.unwrap_or_default();
+
+    let manifest = tokio::task::spawn_blocking(move || {
+        write_project_archive(
+            &project,
+            &PathBuf::from(&output_path),
+            &options,
+            |progress| emit_archive_progress(&app_handle, "export", progress),
+        )
+    })
+    .await
+    .map_err(|e| ArchiveError::Io(e.to_string()))??;
+
+    println!(
+        "[Project] Archived '{}' ({} files)",
+        manifest.project_name,
+        manifest.entries.len()
+    );
+    Ok(manifest)
+}
+
+/// Unpack a `.clipforgepkg` into `destination_dir` and open its project
+///
+/// The archive is fully validated and extracted before the current project is
+/// closed, so a corrupt archive leaves the current state untouched.
+#[tauri::command]
+pub async fn import_project_archive(
+    archive_path: String,
+    destination_dir: String,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<LoadProjectResult, ClipForgeError> {
+    let progress_handle = app_handle.clone();
+    let extraction = tokio::task::spawn_blocking(move || {
+        extract_project_archive(
+            &PathBuf::from(&archive_path),
+            &PathBuf::from(&destination_dir),
+            |progress| emit_archive_progress(&progress_handle, "import", progress),
+        )
+    })
+    .await
+    .map_err(|e| ArchiveError::Io(e.to_string()))??;
+
+    let project = load_project_file(&extraction.project_path)
+        .map_err(|e| ArchiveError::InvalidProject(e.to_string()))?;
+
+    close_current_project(false, &state, &export_state, &app_handle)
+        .map_err(|e| ArchiveError::ProjectBusy(e.to_string()))?;
+
+    {
+        let cache_db = state.cache_db.lock_state()?;
+        for clip in &project.media_library {
+            let registered = cache_db
+                .update_media_clip(clip)
+                .or_else(|_| cache_db.insert_media_clip(clip));
+            if let Err(e) = registered {
+                eprintln!(
+                    "[Project] Failed to cache archived media {}: {}",
+                    clip.id, e
+                );
+            }
+        }
+    }
+
+    println!(
+        "[Project] Imported archive into {} ({} files extracted, {} already present)",
+        extraction.project_path.display(),
+        extraction.files_extracted,
+        extraction.files_skipped
+    );
+
+    let path = extraction.project_path.to_string_lossy().to_string();
+    let result = set_current_project(project, path, &state);
+    emit_project_state(&app_handle, &result.project);
+    Ok(result)
 }
 
+/// Archive progress event payload
+#[derive(Debug, Clone, Serialize)]
+struct ArchiveProgressEvent<'a> {
+    operation: &'static str,
+    #[serde(flatten)]
+    progress: &'a ArchiveProgress,
+}
+
+fn emit_archive_progress(
+    app_handle: &AppHandle,
+    operation: &'static str,
+    progress: &ArchiveProgress,
+) {
+    let _ = app_handle.emit_all(
+        "archive_progress",
+        ArchiveProgressEvent {
+            operation,
+            progress,
+        },
+    );
+}
+
+/// Get recently opened/saved project files
 #[tauri::command]
-pub async fn save_project(_path: String) -> Result<String, String> {
-    // TODO: T102 - Implement project save
-    Err("Not implemented yet".to_string())
+pub async fn get_recent_projects(
+    state: State<'_, AppState>,
+) -> Result<Vec<RecentProject>, ClipForgeError> {
+    Ok(state.cache_db.lock_state()?.get_recent_projects()?)
 }
 
+/// Export settings saved with the current project (pre-populates the export dialog)
 #[tauri::command]
-pub async fn load_project(_path: String) -> Result<String, String> {
-    // TODO: T103 - Implement project load
-    Err("Not implemented yet".to_string())
+pub async fn get_project_export_settings(
+    state: State<'_, AppState>,
+) -> Result<ExportSettings, ClipForgeError> {
+    state
+        .project
+        .lock_state()?
+        .as_ref()
+        .map(|p| p.export_settings.clone())
+        .ok_or_else(ClipForgeError::no_project)
+}
+
+/// Persist export settings chosen in the export dialog to the current project
+#[tauri::command]
+pub async fn set_project_export_settings(
+    settings: ExportSettings,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    project.export_settings = settings;
+    record_modification(&app_handle, project, ModificationScope::Settings, vec![]);
+    Ok(())
+}
+
+/// Update the current project's sequence settings (canvas size, fps, background)
+#[tauri::command]
+pub async fn update_project_settings(
+    settings: ProjectSettings,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Project, ClipForgeError> {
+    settings
+        .validate()
+        .map_err(|e| ClipForgeError::validation("settings", e))?;
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    if project.settings != settings {
+        project.settings = settings;
+        record_modification(&app_handle, project, ModificationScope::Settings, vec![]);
+    }
+
+    Ok(project.clone())
+}
+
+/// Whether the current project has unsaved changes
+#[tauri::command]
+pub async fn is_project_dirty(state: State<'_, AppState>) -> Result<bool, ClipForgeError> {
+    Ok(state
+        .project
+        .lock_state()?
+        .as_ref()
+        .is_some_and(|p| p.dirty))
+}
+
+/// Close the current project
+///
+/// Optionally saves first, cancels the project's background work (exports,
+/// pending proxies), clears project state, and emits `project_closed`.
+/// Refuses while a recording is active.
+#[tauri::command]
+pub async fn close_project(
+    save_first: bool,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<(), ClipForgeError> {
+    close_current_project(save_first, &state, &export_state, &app_handle)?;
+    Ok(())
+}
+
+/// `close_requested_unsaved` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct CloseRequestedUnsavedEvent {
+    pub project_id: String,
+    pub name: String,
+    /// Where the project was last saved (null = never saved)
+    pub file_path: Option<String>,
+}
+
+/// Whether the app has to stay open for now, telling the UI why
+///
+/// Unsaved changes come first (`close_requested_unsaved`), then jobs that
+/// block exit (`close_requested_jobs_running`), cancelled instead with
+/// `cancel_jobs` (active recordings still block). The UI answers the prompt
+/// (saves, closes the project to discard the changes, or chooses to cancel
+/// the jobs) and calls `exit_app`, which checks again.
+pub fn exit_blocked(app_handle: &AppHandle, cancel_jobs: bool) -> bool {
+    let unsaved = app_handle
+        .state::<AppState>()
+        .project
+        .lock_recovering()
+        .as_ref()
+        .filter(|p| p.dirty)
+        .map(|p| CloseRequestedUnsavedEvent {
+            project_id: p.id.clone(),
+            name: p.name.clone(),
+            file_path: p.file_path.clone(),
+        });
+    if let Some(event) = unsaved {
+        let _ = app_handle.emit_all("close_requested_unsaved", event);
+        return true;
+    }
+    let blocking = jobs_blocking_exit(app_handle, cancel_jobs);
+    if !blocking.is_empty() {
+        let _ = app_handle.emit_all(
+            "close_requested_jobs_running",
+            JobsUpdatedEvent { jobs: blocking },
+        );
+        return true;
+    }
+    false
+}
+
+/// Quit the app, unless something still keeps it open (see `exit_blocked`)
+#[tauri::command]
+pub async fn exit_app(cancel_jobs: bool, app_handle: AppHandle) -> Result<(), ClipForgeError> {
+    if !exit_blocked(&app_handle, cancel_jobs) {
+        app_handle.exit(0);
+    }
+    Ok(())
+}
+
+/// Tear down the current project and emit the resulting events
+fn close_current_project(
+    save_first: bool,
+    state: &AppState,
+    export_state: &ExportState,
+    app_handle: &AppHandle,
+) -> Result<Option<ProjectClosedEvent>, CloseProjectError> {
+    let closed = teardown_project(state, export_state, save_first, has_active_recording())?;
+
+    if let Some(ref event) = closed {
+        for job_id in &event.cancelled_exports {
+            let _ = app_handle.emit_all(
+                "export_cancelled",
+                crate::commands::export::ExportCancelledEvent {
+                    job_id: job_id.clone(),
+                },
+            );
+        }
+        let _ = app_handle.emit_all("project_closed", event.clone());
+    }
+
+    Ok(closed)
+}
+
+/// Close the current project, in order:
+/// 1. refuse if a recording is active (nothing is touched)
+/// 2. save, if requested (nothing else is touched if it fails)
+/// 3. cancel the project's export jobs
+/// 4. abort pending proxy jobs for the project's clips
+/// 5. clear the project and media library
+///
+/// Returns None if no project was open.
+fn teardown_project(
+    state: &AppState,
+    export_state: &ExportState,
+    save_first: bool,
+    recording_active: bool,
+) -> Result<Option<ProjectClosedEvent>, CloseProjectError> {
+    let Some(project) = state.project.lock_recovering().clone() else {
+        return Ok(None);
+    };
+
+    if recording_active {
+        return Err(CloseProjectError::RecordingActive(
+            "Stop the active recording before closing the project".to_string(),
+        ));
+    }
+
+    let saved_to = if save_first {
+        let path = project.file_path.clone().ok_or_else(|| {
+            CloseProjectError::SaveFailed(
+                "Project has never been saved; choose a location first".to_string(),
+            )
+        })?;
+        save_current_project(state, &path)
+            .map_err(|e| CloseProjectError::SaveFailed(e.to_string()))?;
+        Some(path)
+    } else {
+        None
+    };
+
+    let cancelled_exports = export_state
+        .cancel_jobs_for_project(&project.id)
+        .into_iter()
+        .map(|entry| {
+            record_export_history(state, &entry);
+            entry.job_id
+        })
+        .collect::<Vec<_>>();
+
+    let cancelled_proxies = project
+        .media_library
+        .iter()
+        .filter(|clip| state.proxy_jobs.abort(&clip.id))
+        .map(|clip| clip.id.clone())
+        .collect::<Vec<_>>();
+
+    *state.project.lock_recovering() = None;
+
+    println!(
+        "[Project] Closed project '{}' ({} exports cancelled, {} proxies aborted)",
+        project.name,
+        cancelled_exports.len(),
+        cancelled_proxies.len()
+    );
+
+    Ok(Some(ProjectClosedEvent {
+        project_id: project.id,
+        saved_to,
+        cancelled_exports,
+        cancelled_proxies,
+    }))
+}
+
+/// Save the current project to `path` and record the save on the live project
+///
+/// Returns true if the live project was dirty before the save (its state flipped).
+fn save_current_project(state: &AppState, path: &str) -> Result<bool, ClipForgeError> {
+    let mut snapshot = state
+        .project
+        .lock_state()?
+        .clone()
+        .ok_or_else(ClipForgeError::no_project)?;
+
+    snapshot.file_path = Some(path.to_string());
+    snapshot.modified_at = chrono::Utc::now();
+
+    save_project_file(&snapshot, &PathBuf::from(path))?;
+
+    // Only record the save if the project wasn't replaced meanwhile
+    let mut was_dirty = false;
+    let mut project_lock = state.project.lock_state()?;
+    if let Some(ref mut project) = *project_lock {
+        if project.id == snapshot.id {
+            was_dirty = project.dirty;
+            project.modified_at = snapshot.modified_at;
+            project.mark_saved(path.to_string());
+        }
+    }
+
+    drop(project_lock);
+
+    register_recent_project(state, path, &snapshot.name);
+    println!("[Project] Saved project to {}", path);
+    Ok(was_dirty)
+}
+
+/// Fork the current project to `new_path` and make the fork the live project
+fn save_current_project_as(
+    state: &AppState,
+    new_path: &str,
+    new_name: Option<String>,
+    keep_id: bool,
+) -> Result<Project, ClipForgeError> {
+    let current = state
+        .project
+        .lock_state()?
+        .clone()
+        .ok_or_else(ClipForgeError::no_project)?;
+
+    let mut fork = current.fork(new_name, keep_id);
+    fork.file_path = Some(new_path.to_string());
+    save_project_file(&fork, &PathBuf::from(new_path))?;
+    fork.mark_saved(new_path.to_string());
+
+    // Replace the live project unless it changed while saving
+    {
+        let mut project_lock = state.project.lock_state()?;
+        match *project_lock {
+            Some(ref project) if project.id == current.id => *project_lock = Some(fork.clone()),
+            _ => return Err(ClipForgeError::busy("Project changed while saving")),
+        }
+    }
+
+    register_recent_project(state, new_path, &fork.name);
+    println!(
+        "[Project] Saved '{}' as '{}' ({})",
+        current.name, fork.name, new_path
+    );
+    Ok(fork)
+}
+
+/// Copy a project file to `new_path` as a new project (media is shared, not copied)
+fn duplicate_project_file(
+    state: &AppState,
+    source_path: &str,
+    new_path: &str,
+) -> Result<Project, ClipForgeError> {
+    if std::path::Path::new(source_path) == std::path::Path::new(new_path) {
+        return Err(ClipForgeError::validation(
+            "new_path",
+            "Duplicate must be saved to a different path",
+        ));
+    }
+
+    let source = load_project_file(&PathBuf::from(source_path))?;
+    let mut duplicate = source.fork(Some(format!("{} (copy)", source.name)), false);
+    duplicate.file_path = Some(new_path.to_string());
+    save_project_file(&duplicate, &PathBuf::from(new_path))?;
+
+    register_recent_project(state, new_path, &duplicate.name);
+    println!("[Project] Duplicated {} to {}", source_path, new_path);
+    Ok(duplicate)
+}
+
+/// Add a project file to the recent projects list; failures are only logged
+fn register_recent_project(state: &AppState, path: &str, name: &str) {
+    if let Err(e) = state
+        .cache_db
+        .lock_recovering()
+        .add_recent_project(path, name)
+    {
+        eprintln!("[Project] Failed to update recent projects: {}", e);
+    }
+}
+
+/// Store an auto-save snapshot of the current project
+///
+/// A snapshot is for crash recovery only: the project stays dirty.
+pub fn autosave_current_project(state: &AppState) -> Result<(), String> {
+    let mut snapshot = match state.project.lock_state()?.clone() {
+        Some(project) => project,
+        None => return Ok(()),
+    };
+    // Would differ between otherwise identical snapshots
+    snapshot.last_auto_save = None;
+
+    let max_mb = state.settings.lock_state()?.autosave_max_mb;
+    let max_total_bytes = (max_mb > 0).then(|| max_mb.saturating_mul(1024 * 1024));
+    state
+        .cache_db
+        .lock_state()?
+        .insert_auto_save(&snapshot, max_total_bytes)?;
+
+    let mut project_lock = state.project.lock_state()?;
+    if let Some(ref mut project) = *project_lock {
+        if project.id == snapshot.id {
+            project.last_auto_save = Some(chrono::Utc::now());
+        }
+    }
+    Ok(())
+}
+
+/// Snapshot the open project every `autosave_interval_secs` while it has
+/// unsaved changes and `auto_save_enabled`; runs for the app's lifetime
+pub async fn run_autosave(app_handle: AppHandle) {
+    loop {
+        let interval = app_handle
+            .state::<AppState>()
+            .settings
+            .lock_recovering()
+            .autosave_interval_secs;
+        if interval == 0 {
+            tokio::time::sleep(AUTOSAVE_IDLE_CHECK).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let state = app_handle.state::<AppState>();
+        let due = state
+            .project
+            .lock_recovering()
+            .as_ref()
+            .is_some_and(|p| p.dirty && p.auto_save_enabled);
+        if due {
+            if let Err(e) = autosave_current_project(&state) {
+                log::warn!("Auto-save failed: {}", e);
+            }
+        }
+    }
+}
+
+/// List available backups of a project file (most recent first)
+#[tauri::command]
+pub async fn list_project_backups(path: String) -> Result<Vec<ProjectBackup>, ClipForgeError> {
+    Ok(list_backups(&PathBuf::from(path)))
+}
+
+/// Restore a project file from one of its backups and load it
+///
+/// `backup_path` defaults to the most recent backup.
+#[tauri::command]
+pub async fn restore_project_backup(
+    path: String,
+    backup_path: Option<String>,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<LoadProjectResult, ClipForgeError> {
+    let project_path = PathBuf::from(&path);
+    let backup = match backup_path {
+        Some(backup) => PathBuf::from(backup),
+        None => list_backups(&project_path)
+            .first()
+            .map(|b| PathBuf::from(&b.path))
+            .ok_or_else(|| ClipForgeError::not_found("project_backup", path.as_str()))?,
+    };
+
+    close_current_project(false, &state, &export_state, &app_handle)?;
+    let project = restore_project_file(&project_path, &backup)?;
+
+    println!(
+        "[Project] Restored {} from backup {}",
+        path,
+        backup.display()
+    );
+    let result = set_current_project(project, path, &state);
+    emit_project_state(&app_handle, &result.project);
+    Ok(result)
+}
+
+/// Load the latest auto-save of a project as the current project
+///
+/// The restored project is dirty: it hasn't been saved to its file yet.
+#[tauri::command]
+pub async fn restore_auto_save(
+    project_id: String,
+    state: State<'_, AppState>,
+    export_state: State<'_, ExportState>,
+    app_handle: AppHandle,
+) -> Result<LoadProjectResult, ClipForgeError> {
+    let mut project = state
+        .cache_db
+        .lock_state()?
+        .get_latest_auto_save(&project_id)?
+        .ok_or_else(|| ClipForgeError::not_found("auto_save", project_id.as_str()))?;
+
+    close_current_project(false, &state, &export_state, &app_handle)?;
+    project.mark_modified();
+
+    let missing_media = find_missing_media(&project);
+    *state.project.lock_state()? = Some(project.clone());
+
+    println!(
+        "[Project] Restored auto-save of '{}' ({})",
+        project.name, project.id
+    );
+    emit_project_state(&app_handle, &project);
+    Ok(LoadProjectResult {
+        project,
+        missing_media,
+    })
+}
+
+/// Replace the current project (and with it the media library) with a loaded one
+fn set_current_project(mut project: Project, path: String, state: &AppState) -> LoadProjectResult {
+    project.mark_saved(path);
+
+    let missing_media = find_missing_media(&project);
+    if !missing_media.is_empty() {
+        eprintln!(
+            "[Project] {} media files are missing: {:?}",
+            missing_media.len(),
+            missing_media
+        );
+    }
+
+    *state.project.lock_recovering() = Some(project.clone());
+    if let Some(ref path) = project.file_path {
+        register_recent_project(state, path, &project.name);
+    }
+
+    println!(
+        "[Project] Loaded project '{}' with {} tracks and {} clips",
+        project.name,
+        project.tracks.len(),
+        project.media_library.len()
+    );
+    LoadProjectResult {
+        project,
+        missing_media,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::library::tests::mock_state;
+    use crate::ffmpeg::export::{ExportJob, ExportStatus};
+    use crate::models::clip::MediaClip;
+    use crate::models::export::{ExportHistoryEntry, ExportSettings};
+    use tempfile::TempDir;
+
+    /// Open a project with one clip, a running proxy job, and a running export
+    fn open_project_with_jobs(
+        state: &AppState,
+        export_state: &ExportState,
+        temp_dir: &TempDir,
+    ) -> (
+        Project,
+        tokio::task::JoinHandle<()>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let mut project = Project::new("Busy".to_string());
+        project.file_path = Some(
+            temp_dir
+                .path()
+                .join("busy.clipforge")
+                .to_string_lossy()
+                .to_string(),
+        );
+        let clip = MediaClip::new(
+            "/videos/a.mov".to_string(),
+            5.0,
+            1920,
+            1080,
+            30.0,
+            "prores".to_string(),
+            10,
+        );
+        project.media_library.push(clip.clone());
+        *state.project.lock().unwrap() = Some(project.clone());
+
+        // Mocked background jobs that never finish on their own
+        let proxy_task = tokio::spawn(std::future::pending::<()>());
+        state
+            .proxy_jobs
+            .insert(clip.id.clone(), "clip.mp4", proxy_task.abort_handle());
+
+        let export_task = tokio::spawn(std::future::pending::<()>());
+        let output_path = temp_dir
+            .path()
+            .join("out.mp4")
+            .to_string_lossy()
+            .to_string();
+        export_state.insert_job(
+            ExportJob {
+                id: "export-1".to_string(),
+                output_path: output_path.clone(),
+                status: ExportStatus::Rendering,
+            },
+            ExportHistoryEntry::started(
+                "export-1".to_string(),
+                Some(project.id.clone()),
+                output_path,
+                ExportSettings::default(),
+                5.0,
+            ),
+        );
+        export_state.set_task("export-1", export_task.abort_handle());
+
+        (project, proxy_task, export_task)
+    }
+
+    #[tokio::test]
+    async fn test_teardown_cancels_project_work_and_clears_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let export_state = ExportState::new();
+        let (project, proxy_task, export_task) =
+            open_project_with_jobs(&state, &export_state, &temp_dir);
+
+        let closed = teardown_project(&state, &export_state, true, false)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(closed.project_id, project.id);
+        assert_eq!(closed.saved_to, project.file_path);
+        assert_eq!(closed.cancelled_exports, vec!["export-1".to_string()]);
+        assert_eq!(
+            closed.cancelled_proxies,
+            vec![project.media_library[0].id.clone()]
+        );
+
+        // Saved before state was cleared
+        let saved = crate::storage::project_file::load_project_file(&PathBuf::from(
+            project.file_path.unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(saved.id, project.id);
+        assert_eq!(saved.media_library.len(), 1);
+
+        // Background jobs were aborted
+        assert!(proxy_task.await.unwrap_err().is_cancelled());
+        assert!(export_task.await.unwrap_err().is_cancelled());
+        assert!(state.proxy_jobs.is_empty());
+
+        // Cancellation was recorded in the export history
+        let history = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_export_history(10, None)
+            .unwrap();
+        assert_eq!(history[0].status, ExportStatus::Cancelled);
+
+        assert!(state.project.lock().unwrap().is_none());
+
+        // Closing again is a no-op
+        assert!(teardown_project(&state, &export_state, false, false)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_dirty_state_through_edit_autosave_and_save() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        *state.project.lock().unwrap() = Some(Project::new("Dirty".to_string()));
+
+        // A timeline edit marks the project dirty
+        {
+            let mut project_lock = state.project.lock().unwrap();
+            let project = project_lock.as_mut().unwrap();
+            let track_id = project.tracks[0].id.clone();
+            project.tracks[0]
+                .clips
+                .push(crate::models::timeline::TimelineClip::new(
+                    "media-1".to_string(),
+                    track_id,
+                    0.0,
+                    0.0,
+                    5.0,
+                ));
+            assert!(project.mark_modified());
+        }
+        assert!(state.project.lock().unwrap().as_ref().unwrap().dirty);
+
+        // Auto-save is only a snapshot
+        autosave_current_project(&state).unwrap();
+        let project = state.project.lock().unwrap().clone().unwrap();
+        assert!(project.dirty);
+        assert!(project.last_auto_save.is_some());
+        assert!(project.last_saved_at.is_none());
+
+        // A real save clears it
+        let path = temp_dir.path().join("dirty.clipforge");
+        let flipped = save_current_project(&state, &path.to_string_lossy()).unwrap();
+        assert!(flipped);
+        let project = state.project.lock().unwrap().clone().unwrap();
+        assert!(!project.dirty);
+        assert!(project.last_saved_at.is_some());
+
+        // Saving a clean project doesn't flip anything
+        assert!(!save_current_project(&state, &path.to_string_lossy()).unwrap());
+    }
+
+    #[test]
+    fn test_save_as_assigns_new_id_and_keeps_original() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let original_path = temp_dir.path().join("rough.clipforge");
+        let new_path = temp_dir.path().join("rough_v2.clipforge");
+
+        *state.project.lock().unwrap() = Some(Project::new("Rough".to_string()));
+        save_current_project(&state, &original_path.to_string_lossy()).unwrap();
+        let original = state.project.lock().unwrap().clone().unwrap();
+        let original_bytes = std::fs::read(&original_path).unwrap();
+
+        let fork = save_current_project_as(
+            &state,
+            &new_path.to_string_lossy(),
+            Some("Rough v2".to_string()),
+            false,
+        )
+        .unwrap();
+
+        assert_ne!(fork.id, original.id);
+        assert_eq!(fork.name, "Rough v2");
+        assert_eq!(fork.tracks[0].id, original.tracks[0].id);
+        assert_eq!(
+            fork.file_path.as_deref(),
+            Some(&*new_path.to_string_lossy())
+        );
+        assert!(!fork.dirty);
+
+        // Live project is the fork; original file untouched
+        assert_eq!(state.project.lock().unwrap().as_ref().unwrap().id, fork.id);
+        assert_eq!(std::fs::read(&original_path).unwrap(), original_bytes);
+
+        // Both paths are in the recent list, newest first
+        let recent = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_recent_projects()
+            .unwrap();
+        assert_eq!(recent[0].path, new_path.to_string_lossy());
+        assert_eq!(recent[0].name, "Rough v2");
+        assert_eq!(recent[1].path, original_path.to_string_lossy());
+
+        // keep_id preserves the project id
+        let kept = save_current_project_as(
+            &state,
+            &temp_dir.path().join("v3.clipforge").to_string_lossy(),
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(kept.id, fork.id);
+    }
+
+    #[test]
+    fn test_duplicate_unloaded_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let source_path = temp_dir.path().join("source.clipforge");
+        let copy_path = temp_dir.path().join("copy.clipforge");
+
+        let source = Project::new("Source".to_string());
+        crate::storage::project_file::save_project_file(&source, &source_path).unwrap();
+
+        let copy = duplicate_project_file(
+            &state,
+            &source_path.to_string_lossy(),
+            &copy_path.to_string_lossy(),
+        )
+        .unwrap();
+
+        assert_ne!(copy.id, source.id);
+        assert_eq!(copy.name, "Source (copy)");
+        let on_disk = load_project_file(&copy_path).unwrap();
+        assert_eq!(on_disk.id, copy.id);
+        assert_eq!(on_disk.tracks[0].id, source.tracks[0].id);
+
+        // Nothing was loaded
+        assert!(state.project.lock().unwrap().is_none());
+        let recent = state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_recent_projects()
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].path, copy_path.to_string_lossy());
+
+        assert!(duplicate_project_file(
+            &state,
+            &source_path.to_string_lossy(),
+            &source_path.to_string_lossy()
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_teardown_refuses_during_recording() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let export_state = ExportState::new();
+        let (_project, proxy_task, export_task) =
+            open_project_with_jobs(&state, &export_state, &temp_dir);
+
+        let result = teardown_project(&state, &export_state, true, true);
+        assert!(matches!(result, Err(CloseProjectError::RecordingActive(_))));
+
+        // Nothing was touched: not saved, jobs still running, project still open
+        assert!(!temp_dir.path().join("busy.clipforge").exists());
+        assert!(!proxy_task.is_finished());
+        assert!(!export_task.is_finished());
+        assert!(state.project.lock().unwrap().is_some());
+        assert_eq!(state.proxy_jobs.len(), 1);
+
+        proxy_task.abort();
+        export_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_teardown_save_failure_keeps_project_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let export_state = ExportState::new();
+        let (_project, proxy_task, export_task) =
+            open_project_with_jobs(&state, &export_state, &temp_dir);
+
+        // Saving into a directory that doesn't exist fails
+        state.project.lock().unwrap().as_mut().unwrap().file_path = Some(
+            temp_dir
+                .path()
+                .join("missing")
+                .join("busy.clipforge")
+                .to_string_lossy()
+                .to_string(),
+        );
+
+        let result = teardown_project(&state, &export_state, true, false);
+        assert!(matches!(result, Err(CloseProjectError::SaveFailed(_))));
+        assert!(state.project.lock().unwrap().is_some());
+        assert!(!export_task.is_finished());
+
+        proxy_task.abort();
+        export_task.abort();
+    }
 }
@@ -1,4 +1,18 @@
+use crate::commands::events::ProjectEvents;
+use crate::commands::library::{edit_media, MediaChange};
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::commands::timeline::add_to_track;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::capabilities;
+use crate::ffmpeg::errors::classify;
+use crate::ffmpeg::locate::require_ffmpeg;
+use crate::ffmpeg::metadata::VideoMetadata;
+use crate::models::clip::MediaClip;
+use crate::models::project::Project;
 use crate::models::recording::*;
+use crate::models::timeline::{TimelineClip, TrackType};
 use crate::platform;
 use serde_json::json;
 use std::collections::HashMap;
@@ -10,36 +24,50 @@ use tokio::time::{interval, Duration};
 lazy_static::lazy_static! {
     static ref RECORDING_SESSIONS: Arc<Mutex<HashMap<String, RecordingSession>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    /// Sources from the last `list_recording_sources`, with their frame rates
+    static ref LISTED_SOURCES: Mutex<Option<RecordingSources>> = Mutex::new(None);
 }
 
 /// Request system permissions for screen/camera/microphone recording
 #[tauri::command]
 pub async fn request_recording_permissions(
     permissions: Vec<String>,
-) -> Result<PermissionResult, String> {
-    platform::request_permissions(permissions)
+) -> Result<PermissionResult, ClipForgeError> {
+    Ok(platform::request_permissions(permissions)?)
 }
 
 /// List available screens, windows, and cameras
 #[tauri::command]
-pub async fn list_recording_sources() -> Result<RecordingSources, String> {
-    platform::list_sources()
+pub async fn list_recording_sources() -> Result<RecordingSources, ClipForgeError> {
+    // Device enumeration runs FFmpeg/system_profiler synchronously
+    let sources = tokio::task::spawn_blocking(platform::list_sources)
+        .await
+        .map_err(|e| format!("Failed to list recording sources: {}", e))??;
+    *LISTED_SOURCES.lock_state()? = Some(sources.clone());
+    Ok(sources)
 }
 
 /// Start a new recording session
 #[tauri::command]
 pub async fn start_recording(
-    config: RecordingConfig,
+    mut config: RecordingConfig,
     app_handle: AppHandle,
-) -> Result<RecordingSession, String> {
+) -> Result<RecordingSession, ClipForgeError> {
+    require_ffmpeg()?;
+
     // Generate output path
-    let output_dir = get_recordings_dir()?;
+    let output_dir = {
+        use crate::commands::media::AppState;
+        let app_state = app_handle.state::<AppState>();
+        let settings = app_state.settings.lock_state()?;
+        get_recordings_dir(settings.recordings_dir.as_deref())?
+    };
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
     let filename = format!("recording_{}.mp4", timestamp);
     let output_path = output_dir.join(&filename);
     let output_path_str = output_path
         .to_str()
-        .ok_or_else(|| "Invalid output path".to_string())?
+        .ok_or_else(|| ClipForgeError::validation("output_path", "Invalid output path"))?
         .to_string();
 
     // Create recording session
@@ -54,46 +82,66 @@ pub async fn start_recording(
     session.screen_source = config.screen_source_id.clone();
     session.camera_device = config.camera_device_id.clone();
     session.audio_sources = config.audio_sources.clone();
+    session.post_process = config.post_process;
+    session.auto_add_to_timeline = config.auto_add_to_timeline.clone();
 
     // Validate configuration
-    session.validate()?;
+    session
+        .validate()
+        .map_err(|e| ClipForgeError::validation("config", e))?;
+    config
+        .camera
+        .validate()
+        .map_err(|e| ClipForgeError::validation("camera", e))?;
+    config
+        .settings
+        .validate()
+        .map_err(|e| ClipForgeError::validation("settings", e))?;
+
+    if config.settings.hardware_encoder {
+        capabilities::ensure_probed().await;
+    }
+
+    // Record at a rate the chosen devices can deliver rather than failing
+    let requested_fps = config.settings.fps;
+    let fps = recording_fps(&config, LISTED_SOURCES.lock_state()?.as_ref());
+    if fps != requested_fps {
+        config.settings.fps = fps;
+        session.fps = fps;
+    }
+
+    remember_recording_config(&app_handle, &config);
 
     // Start platform-specific recording
     let session_id = session.id.clone();
 
-    #[cfg(target_os = "macos")]
-    platform::macos::start_recording(
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    if let Err(e) = platform::start_recording(
         session_id.clone(),
         output_path_str,
         config.screen_source_id,
         config.camera_device_id,
         config.audio_sources,
         config.microphone_device_id,
-        config.settings.resolution,
-        config.settings.fps,
-    )?;
-
-    #[cfg(target_os = "windows")]
-    platform::windows::start_recording(
-        session_id.clone(),
-        output_path_str,
-        config.screen_source_id,
-        config.camera_device_id,
-        config.audio_sources,
-        config.settings.resolution,
-        config.settings.fps,
-    )?;
+        config.settings.clone(),
+        config.camera,
+    ) {
+        return Err(fail_session(&app_handle, session, e.into()));
+    }
 
     #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    return Err("Recording not supported on this platform".to_string());
+    return Err(ClipForgeError::unsupported(
+        "Recording not supported on this platform",
+    ));
 
     // Update session status
     session.start();
+    record_session(&app_handle, &session);
 
     // Store session
     let session_clone = session.clone();
     {
-        let mut sessions = RECORDING_SESSIONS.lock().unwrap();
+        let mut sessions = RECORDING_SESSIONS.lock_state()?;
         sessions.insert(session_id.clone(), session.clone());
     }
 
@@ -107,69 +155,173 @@ pub async fn start_recording(
         )
         .map_err(|e| format!("Failed to emit event: {}", e))?;
 
+    if fps != requested_fps {
+        let _ = app_handle.emit_all(
+            "recording_warning",
+            json!({
+                "session_id": session_id,
+                "message": format!(
+                    "The selected source can't record at {} fps; recording at {} fps instead",
+                    requested_fps, fps
+                ),
+                "requested_fps": requested_fps,
+                "fps": fps
+            }),
+        );
+    }
+    if config.settings.chroma == ChromaMode::HighQuality444 {
+        let _ = app_handle.emit_all(
+            "recording_warning",
+            json!({
+                "session_id": session_id,
+                "message": "4:4:4 recordings use the software encoder, which needs more CPU; \
+                            proxies and exports are converted to 4:2:0"
+            }),
+        );
+    }
+
     // Start duration tracking task
     start_duration_tracking(session_id, app_handle);
 
     Ok(session_clone)
 }
 
+/// Persist `config` as the last-used recording configuration
+fn remember_recording_config(app_handle: &AppHandle, config: &RecordingConfig) {
+    let app_state = app_handle.state::<AppState>();
+    let mut settings = app_state.settings.lock_recovering();
+    if settings.last_recording_config.as_ref() == Some(config) {
+        return;
+    }
+    settings.last_recording_config = Some(config.clone());
+    let saved = app_state
+        .cache_db
+        .lock_recovering()
+        .save_app_settings(&settings);
+    if let Err(e) = saved {
+        log::warn!("Failed to save the recording configuration: {}", e);
+    }
+}
+
+/// Frame rate to record `config` at
+///
+/// The requested rate if the selected screen and camera support it (or their
+/// rates are unknown), otherwise the nearest rate they share. Without any
+/// listed sources, or with no shared rate, the request is left to FFmpeg.
+fn recording_fps(config: &RecordingConfig, sources: Option<&RecordingSources>) -> u32 {
+    let requested = config.settings.fps;
+    let Some(sources) = sources else {
+        return requested;
+    };
+    let mut rates: Vec<&[u32]> = Vec::new();
+    if let Some(id) = &config.screen_source_id {
+        if let Some(screen) = sources.screens.iter().find(|s| &s.id == id) {
+            rates.push(&screen.supported_fps);
+        }
+    }
+    if let Some(id) = &config.camera_device_id {
+        if let Some(camera) = sources.cameras.iter().find(|c| &c.id == id) {
+            rates.push(&camera.supported_fps);
+        }
+    }
+    platform::capabilities::resolve_fps(requested, &rates).unwrap_or(requested)
+}
+
 /// Stop an active recording session
 #[tauri::command]
 pub async fn stop_recording(
     session_id: String,
     app_handle: AppHandle,
-) -> Result<crate::models::clip::MediaClip, String> {
+) -> Result<crate::models::clip::MediaClip, ClipForgeError> {
     use crate::commands::media::AppState;
     use tauri::Manager;
 
     // Get session
     let mut session = {
-        let mut sessions = RECORDING_SESSIONS.lock().unwrap();
+        let mut sessions = RECORDING_SESSIONS.lock_state()?;
         sessions
             .remove(&session_id)
-            .ok_or_else(|| format!("Recording session not found: {}", session_id))?
+            .ok_or_else(|| ClipForgeError::not_found("recording_session", session_id.as_str()))?
     };
 
-    // Stop platform-specific recording
-    #[cfg(target_os = "macos")]
-    platform::macos::stop_recording(session_id.clone())?;
-
-    #[cfg(target_os = "windows")]
-    platform::windows::stop_recording(session_id.clone())?;
+    // Stop platform-specific recording; this waits up to 5s for FFmpeg to
+    // finalize the file, so it runs off the async workers
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        let id = session_id.clone();
+        let stopped = tokio::task::spawn_blocking(move || platform::stop_recording(id))
+            .await
+            .map_err(|e| format!("Failed to stop recording: {}", e))
+            .and_then(|result| result);
+        if let Err(e) = stopped {
+            return Err(fail_session(&app_handle, session, e.into()));
+        }
+    }
 
     #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    return Err("Recording not supported on this platform".to_string());
+    return Err(ClipForgeError::unsupported(
+        "Recording not supported on this platform",
+    ));
 
     // Update session status
     session.stop();
 
     // Create MediaClip from recording
-    let media_clip = create_media_clip_from_recording(&session, &app_handle).await?;
+    let media_clip = match create_media_clip_from_recording(&session, &app_handle).await {
+        Ok(clip) => clip,
+        Err(e) => return Err(fail_session(&app_handle, session, e.into())),
+    };
 
-    // Store the created clip ID
+    // Store the created clip ID and what the capture actually delivered
     session.created_media_clip_id = Some(media_clip.id.clone());
-
-    // Add clip to AppState (so it can be played back)
-    let app_state = app_handle.state::<AppState>();
-
-    // Add to media library
-    {
-        let mut library = app_state.media_library.lock().unwrap();
-        library.push(media_clip.clone());
-    }
-
-    // Add to cache database
-    {
-        let cache_db = app_state.cache_db.lock().unwrap();
-        cache_db.insert_media_clip(&media_clip)?;
+    let delivered = session.record_delivered(
+        media_clip.fps,
+        media_clip.width.max(0) as u32,
+        media_clip.height.max(0) as u32,
+    );
+    record_session(&app_handle, &session);
+    if let Some(message) = delivered {
+        log::warn!("Recording {}: {}", session_id, message);
+        let _ = app_handle.emit_all(
+            "recording_warning",
+            json!({
+                "session_id": session_id,
+                "message": message,
+                "requested_fps": session.fps,
+                "fps": media_clip.fps,
+                "requested_resolution": session.resolution,
+                "resolution": media_clip.resolution
+            }),
+        );
     }
 
-    // Add to project's media library if a project is loaded
-    {
-        let mut project_lock = app_state.project.lock().unwrap();
-        if let Some(ref mut project) = *project_lock {
-            project.media_library.push(media_clip.clone());
+    // Add the clip to the current project's library; without a project it is
+    // only cached so a later import of the file reuses it
+    let app_state = app_handle.state::<AppState>();
+    let timeline_clip_ids = match add_recording_to_project(
+        &app_state,
+        &app_handle,
+        &media_clip,
+        session.auto_add_to_timeline.as_ref(),
+    ) {
+        Ok(ids) => ids,
+        Err(e) => {
+            log::warn!("Recording {} not added to a project: {}", session_id, e);
+            let cache_db = app_state.cache_db.lock_state()?;
+            cache_db.insert_media_clip(&media_clip)?;
+            Vec::new()
         }
+    };
+    if let Err(e) = queue_recording_thumbnail(&app_state, &media_clip) {
+        log::warn!("Failed to queue recording thumbnail: {}", e);
+    }
+    if session.post_process.is_enabled() && media_clip.has_audio {
+        start_recording_analysis(
+            &app_handle,
+            &session,
+            &media_clip,
+            timeline_clip_ids.clone(),
+        );
     }
 
     // Emit recording_stopped event
@@ -178,7 +330,8 @@ pub async fn stop_recording(
             "recording_stopped",
             json!({
                 "session_id": session_id,
-                "media_clip_id": media_clip.id
+                "media_clip_id": media_clip.id,
+                "timeline_clip_ids": timeline_clip_ids
             }),
         )
         .map_err(|e| format!("Failed to emit event: {}", e))?;
@@ -186,18 +339,273 @@ pub async fn stop_recording(
     Ok(media_clip)
 }
 
+/// Add a finished recording to the current project's library and, with an
+/// `auto_add` target, to its timeline
+///
+/// Auto-adding creates a project when none is open, as `create_track` does;
+/// otherwise a missing project is an error. Returns the timeline clip ids.
+/// A screen+webcam recording is composited into one file, so it's always a
+/// single clip.
+fn add_recording_to_project(
+    state: &AppState,
+    events: &impl ProjectEvents,
+    media_clip: &MediaClip,
+    auto_add: Option<&AutoAddTarget>,
+) -> Result<Vec<String>, ClipForgeError> {
+    if auto_add.is_some() {
+        let mut project_lock = state.project.lock_state()?;
+        if project_lock.is_none() {
+            *project_lock = Some(Project::new("Untitled Project".to_string()));
+        }
+    }
+
+    edit_media(
+        state,
+        events,
+        MediaChange::Added,
+        ModificationScope::MediaLibrary,
+        |library| {
+            library.push(media_clip.clone());
+            Ok((vec![media_clip.id.clone()], ()))
+        },
+    )?;
+    let Some(target) = auto_add else {
+        return Ok(Vec::new());
+    };
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let timeline_clip = place_recording(project, media_clip, target)?;
+    let ids = vec![timeline_clip.id.clone()];
+    let track_id = timeline_clip.track_id.clone();
+    add_to_track(project, timeline_clip)?;
+    events.project_modified(
+        project,
+        ModificationScope::Timeline,
+        vec![ids[0].clone(), track_id],
+    );
+    Ok(ids)
+}
+
+/// Timeline clip for a whole recording at `target`
+///
+/// A playhead position that would overlap a clip falls back to the end of
+/// the track.
+fn place_recording(
+    project: &Project,
+    media_clip: &MediaClip,
+    target: &AutoAddTarget,
+) -> Result<TimelineClip, ClipForgeError> {
+    let track = match &target.track_id {
+        Some(id) => project.tracks.iter().find(|t| &t.id == id),
+        None => project
+            .tracks
+            .iter()
+            .filter(|t| matches!(t.track_type, TrackType::Main))
+            .min_by_key(|t| t.order),
+    }
+    .ok_or_else(|| {
+        ClipForgeError::not_found("track", target.track_id.as_deref().unwrap_or("main"))
+    })?;
+    let (in_point, out_point) = media_clip
+        .suggested_trim
+        .map_or((0.0, media_clip.duration), |trim| {
+            (trim.in_point, trim.out_point)
+        });
+    let start_time = match target.at {
+        PlayheadOrEnd::Playhead(time) => track.free_start(time.max(0.0), out_point - in_point),
+        PlayheadOrEnd::End => track.duration(),
+    };
+    Ok(TimelineClip::new(
+        media_clip.id.clone(),
+        track.id.clone(),
+        start_time,
+        in_point,
+        out_point,
+    ))
+}
+
+/// Apply a recording's suggested trim to the timeline clips auto-added for it
+///
+/// Clips the user already trimmed are left alone. Returns the ids changed.
+fn apply_suggested_trim(
+    project: &mut Project,
+    media_clip: &MediaClip,
+    timeline_clip_ids: &[String],
+) -> Vec<String> {
+    let Some(trim) = media_clip.suggested_trim else {
+        return Vec::new();
+    };
+    let mut changed = Vec::new();
+    for clip in project
+        .tracks
+        .iter_mut()
+        .flat_map(|track| track.clips.iter_mut())
+        .filter(|clip| timeline_clip_ids.contains(&clip.id))
+    {
+        if clip.in_point == 0.0 && clip.out_point == media_clip.duration {
+            clip.in_point = trim.in_point;
+            clip.out_point = trim.out_point;
+            changed.push(clip.id.clone());
+        }
+    }
+    changed
+}
+
+/// Analyse a finished recording's audio in the background
+///
+/// The suggested trim and loudness are stored on the media clip (announced
+/// as a `media_library_changed` update) and the trim applied to the clip's
+/// auto-added timeline clips, then `recording_analysis_ready` is emitted.
+/// Failures are logged; the clip stays usable untrimmed.
+fn start_recording_analysis(
+    app_handle: &AppHandle,
+    session: &RecordingSession,
+    media_clip: &MediaClip,
+    timeline_clip_ids: Vec<String>,
+) {
+    use crate::commands::media::AppState;
+    use crate::ffmpeg::analysis::analyze_recording;
+
+    let app_handle = app_handle.clone();
+    let session_id = session.id.clone();
+    let options = session.post_process;
+    let clip_id = media_clip.id.clone();
+    let path = media_clip.source_path.clone();
+    let duration = media_clip.duration;
+    tauri::async_runtime::spawn(async move {
+        let analysis = match analyze_recording(&path, duration, &options).await {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                log::warn!("Failed to analyse recording {}: {}", session_id, e);
+                return;
+            }
+        };
+
+        let app_state = app_handle.state::<AppState>();
+        let stored = edit_media(
+            &app_state,
+            &app_handle,
+            MediaChange::Updated,
+            ModificationScope::MediaLibrary,
+            |library| {
+                let clip = library
+                    .iter_mut()
+                    .find(|clip| clip.id == clip_id)
+                    .ok_or_else(|| ClipForgeError::not_found("media_clip", clip_id.as_str()))?;
+                clip.suggested_trim = analysis.suggested_trim;
+                clip.loudness = analysis.loudness;
+                Ok((vec![clip_id.clone()], clip.clone()))
+            },
+        );
+        match stored {
+            Ok(media_clip) if !timeline_clip_ids.is_empty() => {
+                let mut project_lock = app_state.project.lock_recovering();
+                if let Some(project) = project_lock.as_mut() {
+                    let trimmed = apply_suggested_trim(project, &media_clip, &timeline_clip_ids);
+                    if !trimmed.is_empty() {
+                        record_modification(
+                            &app_handle,
+                            project,
+                            ModificationScope::Timeline,
+                            trimmed,
+                        );
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Analysis of recording {} not stored: {}", session_id, e),
+        }
+
+        let _ = app_handle.emit_all(
+            "recording_analysis_ready",
+            json!({
+                "session_id": session_id,
+                "media_clip_id": clip_id,
+                "suggested_trim": analysis.suggested_trim,
+                "loudness": analysis.loudness
+            }),
+        );
+    });
+}
+
+/// Queue the first frame of a recording as its thumbnail
+fn queue_recording_thumbnail(
+    app_state: &crate::commands::media::AppState,
+    clip: &crate::models::clip::MediaClip,
+) -> Result<(), String> {
+    let request =
+        crate::commands::media::thumbnail_request(app_state, &clip.id, &clip.source_path, 0.0)?;
+    app_state.thumbnails.submit(request)
+}
+
+/// Persist a session to the recording history; failures are logged, not returned
+fn record_session(app_handle: &AppHandle, session: &RecordingSession) {
+    use crate::commands::media::AppState;
+
+    let app_state = app_handle.state::<AppState>();
+    let cache_db = app_state.cache_db.lock_recovering();
+    if let Err(e) = cache_db.upsert_recording_session(session) {
+        log::warn!("Failed to record session {}: {}", session.id, e);
+    }
+}
+
+/// Mark a session as failed, persist it, and return the error for the caller
+fn fail_session(
+    app_handle: &AppHandle,
+    mut session: RecordingSession,
+    error: ClipForgeError,
+) -> ClipForgeError {
+    session.fail(error.to_string());
+    record_session(app_handle, &session);
+    error
+}
+
+/// `recording_failed` event payload
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecordingFailedEvent {
+    pub session_id: String,
+    pub error: ClipForgeError,
+}
+
+/// Close a session whose FFmpeg process died and tell the UI why
+fn handle_recording_failure(app_handle: &AppHandle, session_id: &str, stderr: &str) {
+    let error = classify(stderr);
+    log::error!(
+        "Recording session {} failed ({:?}): {}\n{}",
+        session_id,
+        error.kind,
+        error.message,
+        error.excerpt
+    );
+    let session = RECORDING_SESSIONS.lock_recovering().remove(session_id);
+    if let Some(session) = session {
+        fail_session(app_handle, session, error.clone().into());
+    }
+    let _ = app_handle.emit_all(
+        "recording_failed",
+        RecordingFailedEvent {
+            session_id: session_id.to_string(),
+            error: error.into(),
+        },
+    );
+}
+
 /// Start duration tracking task (runs every second)
+///
+/// Durations are measured from `started_at`, so ticks and resyncs agree.
 fn start_duration_tracking(session_id: String, app_handle: AppHandle) {
     tokio::spawn(async move {
         let mut ticker = interval(Duration::from_secs(1));
-        let mut elapsed = 0.0;
 
         loop {
             ticker.tick().await;
 
             // Check if session still exists
             let session_exists = {
-                let sessions = RECORDING_SESSIONS.lock().unwrap();
+                let sessions = RECORDING_SESSIONS.lock_recovering();
                 sessions.contains_key(&session_id)
             };
 
@@ -206,16 +614,28 @@ fn start_duration_tracking(session_id: String, app_handle: AppHandle) {
                 break;
             }
 
-            // Update elapsed time
-            elapsed += 1.0;
+            // FFmpeg exiting while the session is still open means capture failed
+            #[cfg(target_os = "macos")]
+            let exited = platform::macos::take_exited_recording(&session_id);
+            #[cfg(target_os = "windows")]
+            let exited = platform::windows::take_exited_recording(&session_id);
+            #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+            let exited: Option<String> = None;
+            if let Some(stderr) = exited {
+                handle_recording_failure(&app_handle, &session_id, &stderr);
+                break;
+            }
 
             // Update session duration
-            {
-                let mut sessions = RECORDING_SESSIONS.lock().unwrap();
-                if let Some(session) = sessions.get_mut(&session_id) {
-                    session.update_duration(elapsed);
-                }
-            }
+            let elapsed = {
+                let mut sessions = RECORDING_SESSIONS.lock_recovering();
+                let Some(session) = sessions.get_mut(&session_id) else {
+                    break;
+                };
+                let elapsed = session.elapsed_at(chrono::Utc::now());
+                session.update_duration(elapsed);
+                elapsed
+            };
 
             // Emit progress event
             let _ = app_handle.emit_all(
@@ -235,7 +655,6 @@ async fn create_media_clip_from_recording(
     _app_handle: &AppHandle,
 ) -> Result<crate::models::clip::MediaClip, String> {
     use crate::ffmpeg::metadata::extract_metadata;
-    use crate::models::clip::MediaClip;
 
     // Validate file exists and has content
     let metadata_fs = std::fs::metadata(&session.output_path)
@@ -250,70 +669,64 @@ async fn create_media_clip_from_recording(
         .await
         .map_err(|e| format!("Failed to get metadata from recording: {}", e))?;
 
-    // Generate thumbnail SYNCHRONOUSLY so it's ready immediately
-    let clip_id = uuid::Uuid::new_v4().to_string();
-    let home_dir = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-    let thumbnail_dir = home_dir.join(".clipforge").join("thumbnails");
-
-    // Create thumbnail directory
-    std::fs::create_dir_all(&thumbnail_dir)
-        .map_err(|e| format!("Failed to create thumbnail directory: {}", e))?;
-
-    let thumbnail_path = thumbnail_dir.join(format!("{}.jpg", clip_id));
-    let thumbnail_path_str = thumbnail_path.to_str().unwrap_or("").to_string();
+    Ok(media_clip_from_recording(
+        session,
+        metadata,
+        metadata_fs.len(),
+    ))
+}
 
-    eprintln!(
-        "[Thumbnail] Generating thumbnail synchronously for clip: {}",
-        clip_id
-    );
-    eprintln!("[Thumbnail] Source: {}", session.output_path);
-    eprintln!("[Thumbnail] Output: {}", thumbnail_path_str);
-
-    // Generate thumbnail and wait for it
-    let thumbnail_result = crate::ffmpeg::thumbnails::generate_thumbnail(
-        &session.output_path,
-        &thumbnail_path_str,
-        0.0,
-    )
-    .await;
-
-    let final_thumbnail_path = match thumbnail_result {
-        Ok(_) => {
-            eprintln!("[Thumbnail] Successfully generated thumbnail");
-            Some(thumbnail_path_str)
-        }
-        Err(e) => {
-            eprintln!("[Thumbnail] Failed to generate thumbnail: {}", e);
-            None
-        }
+/// Clip for a finished recording, described by what the file holds rather
+/// than what the session asked for
+fn media_clip_from_recording(
+    session: &RecordingSession,
+    metadata: VideoMetadata,
+    file_size: u64,
+) -> MediaClip {
+    let duration = if metadata.duration > 0.0 {
+        metadata.duration
+    } else {
+        session.duration.unwrap_or(0.0)
     };
 
-    // Create MediaClip with thumbnail already included
-    let clip = MediaClip {
-        id: clip_id,
+    // The thumbnail is queued once the clip is in the library
+    MediaClip {
+        id: uuid::Uuid::new_v4().to_string(),
         name: format!("Recording {}", chrono::Utc::now().format("%Y-%m-%d %H:%M")),
         source_path: session.output_path.clone(),
         proxy_path: None,
-        thumbnail_path: final_thumbnail_path,
-        duration: session.duration.unwrap_or(0.0),
+        proxy_version: None,
+        thumbnail_path: None,
+        thumbnail_2x_path: None,
+        duration,
         resolution: metadata.resolution,
         width: metadata.width as i32,
         height: metadata.height as i32,
         fps: metadata.fps,
         codec: metadata.codec,
         audio_codec: metadata.audio_codec,
-        file_size: metadata_fs.len() as i64,
+        file_size: file_size as i64,
         bitrate: metadata.bitrate.map(|b| b as i32),
         has_audio: metadata.has_audio,
         imported_at: chrono::Utc::now(),
         captions: Vec::new(),
-    };
-
-    Ok(clip)
+        audio_streams: metadata.audio_streams,
+        audio_stream_index: None,
+        suggested_trim: None,
+        loudness: None,
+        origin_url: None,
+    }
 }
 
-/// Get the recordings directory (platform-specific)
-fn get_recordings_dir() -> Result<PathBuf, String> {
+/// Get the recordings directory (configured in settings, or platform-specific default)
+pub fn get_recordings_dir(configured_dir: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(dir) = configured_dir {
+        let recordings_dir = PathBuf::from(dir);
+        std::fs::create_dir_all(&recordings_dir)
+            .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+        return Ok(recordings_dir);
+    }
+
     let home_dir = dirs::home_dir().ok_or_else(|| "Cannot find home directory".to_string())?;
 
     // Use platform-specific default directories
@@ -330,23 +743,361 @@ fn get_recordings_dir() -> Result<PathBuf, String> {
     Ok(recordings_dir)
 }
 
+/// Whether any recording session is currently active
+pub fn has_active_recording() -> bool {
+    !RECORDING_SESSIONS.lock_recovering().is_empty()
+}
+
+/// Active recording sessions, for the job list
+pub fn active_sessions() -> Vec<RecordingSession> {
+    RECORDING_SESSIONS
+        .lock_recovering()
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Sessions still capturing, with their duration brought up to `now`
+///
+/// A session whose FFmpeg process is gone is left out; its duration tracker
+/// reports the failure. Oldest first.
+fn resync_sessions(
+    sessions: Vec<RecordingSession>,
+    is_active: impl Fn(&str) -> bool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<RecordingSession> {
+    let mut active: Vec<RecordingSession> = sessions
+        .into_iter()
+        .filter(|session| is_active(&session.id))
+        .map(|mut session| {
+            let elapsed = session.elapsed_at(now);
+            session.update_duration(elapsed);
+            session
+        })
+        .collect();
+    active.sort_by_key(|session| session.started_at);
+    active
+}
+
+/// Active recordings, for a UI that lost its state (e.g. a webview reload)
+///
+/// Re-emits `recording_started` and `recording_progress` for every session
+/// so listeners mounted after the reload can rebuild their timers. The
+/// sessions' duration trackers keep running; none is started here.
+/// `stop_recording` only needs the session id, so the returned sessions can
+/// be stopped from the new page.
+#[tauri::command]
+pub async fn resync_recording_state(
+    app_handle: AppHandle,
+) -> Result<Vec<RecordingSession>, ClipForgeError> {
+    let sessions = resync_sessions(
+        active_sessions(),
+        platform::is_recording_active,
+        chrono::Utc::now(),
+    );
+    for session in &sessions {
+        let _ = app_handle.emit_all("recording_started", json!({ "session_id": session.id }));
+        let _ = app_handle.emit_all(
+            "recording_progress",
+            json!({
+                "session_id": session.id,
+                "duration": session.duration.unwrap_or(0.0)
+            }),
+        );
+    }
+    Ok(sessions)
+}
+
 /// Get an active recording session (for testing/debugging)
 #[tauri::command]
-pub async fn get_recording_session(session_id: String) -> Result<RecordingSession, String> {
-    let sessions = RECORDING_SESSIONS.lock().unwrap();
+pub async fn get_recording_session(session_id: String) -> Result<RecordingSession, ClipForgeError> {
+    let sessions = RECORDING_SESSIONS.lock_state()?;
     sessions
         .get(&session_id)
         .cloned()
-        .ok_or_else(|| format!("Recording session not found: {}", session_id))
+        .ok_or_else(|| ClipForgeError::not_found("recording_session", session_id))
+}
+
+/// Get past recording sessions (most recent first)
+#[tauri::command]
+pub async fn get_recording_history(
+    limit: Option<u32>,
+    app_handle: AppHandle,
+) -> Result<Vec<RecordingSession>, ClipForgeError> {
+    use crate::commands::media::AppState;
+
+    let app_state = app_handle.state::<AppState>();
+    let cache_db = app_state.cache_db.lock_state()?;
+    Ok(cache_db.get_recording_history(limit.unwrap_or(50) as usize)?)
+}
+
+/// Get recordings a crash cut off, for the frontend to offer at startup
+///
+/// A session still marked as recording whose output file exists was left
+/// behind by an earlier run; sessions of this run are still going.
+#[tauri::command]
+pub async fn get_interrupted_recordings(
+    app_handle: AppHandle,
+) -> Result<Vec<RecordingSession>, ClipForgeError> {
+    let app_state = app_handle.state::<AppState>();
+    let interrupted = app_state
+        .cache_db
+        .lock_state()?
+        .get_interrupted_recordings()?;
+    let active = RECORDING_SESSIONS.lock_state()?;
+    Ok(recovery_candidates(interrupted, &active))
+}
+
+fn recovery_candidates(
+    interrupted: Vec<RecordingSession>,
+    active: &HashMap<String, RecordingSession>,
+) -> Vec<RecordingSession> {
+    interrupted
+        .into_iter()
+        .filter(|session| !active.contains_key(&session.id))
+        .filter(|session| PathBuf::from(&session.output_path).is_file())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commands::events::CapturedEvents;
+    use crate::commands::library::tests::{clip, mock_state};
+    use crate::models::clip::SuggestedTrim;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recovery_candidates_need_a_file_from_an_earlier_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let session = |name: &str| {
+            let path = temp_dir.path().join(name);
+            RecordingSession::new(
+                RecordingType::Screen,
+                path.to_string_lossy().to_string(),
+                "1920x1080".to_string(),
+                30,
+            )
+        };
+        let (crashed, gone, live) = (session("a.mp4"), session("b.mp4"), session("c.mp4"));
+        std::fs::write(&crashed.output_path, b"data").unwrap();
+        std::fs::write(&live.output_path, b"data").unwrap();
+        let active = HashMap::from([(live.id.clone(), live.clone())]);
+
+        let candidates = recovery_candidates(vec![crashed.clone(), gone, live], &active);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].id, crashed.id);
+    }
+
+    #[test]
+    fn test_recording_clip_uses_probed_rate_and_size() {
+        let mut session = RecordingSession::new(
+            RecordingType::Webcam,
+            "/recordings/take1.mp4".to_string(),
+            "1920x1080".to_string(),
+            60,
+        );
+        session.duration = Some(12.4);
+        // What ffprobe reported for a camera that fell back to 30 fps at 720p
+        let probed = VideoMetadata {
+            duration: 12.033,
+            resolution: "1280x720".to_string(),
+            width: 1280,
+            height: 720,
+            fps: 30000.0 / 1001.0,
+            codec: "h264".to_string(),
+            audio_codec: Some("aac".to_string()),
+            bitrate: Some(4_000_000),
+            has_audio: true,
+            audio_streams: Vec::new(),
+        };
+
+        let clip = media_clip_from_recording(&session, probed, 6_000_000);
+        assert_eq!(clip.fps, 30000.0 / 1001.0);
+        assert_eq!((clip.width, clip.height), (1280, 720));
+        assert_eq!(clip.resolution, "1280x720");
+        assert_eq!(clip.duration, 12.033);
+
+        let warning = session
+            .record_delivered(clip.fps, clip.width as u32, clip.height as u32)
+            .unwrap();
+        assert!(warning.contains("29.97 fps instead of 60"));
+        assert!(warning.contains("1280x720 instead of 1920x1080"));
+        // The request stays on the session next to what was delivered
+        assert_eq!(session.fps, 60);
+        assert_eq!(session.actual_resolution.as_deref(), Some("1280x720"));
+    }
+
+    #[test]
+    fn test_auto_add_falls_back_to_track_end_on_collision() {
+        let mut project = Project::new("Demo".to_string());
+        let main_id = project.tracks[0].id.clone();
+        let existing = clip("/media/intro.mp4");
+        project.tracks[0].clips.push(TimelineClip::new(
+            existing.id.clone(),
+            main_id.clone(),
+            0.0,
+            0.0,
+            10.0,
+        ));
+        let recording = clip("/recordings/take1.mp4");
+        let at = |at| AutoAddTarget { track_id: None, at };
+
+        // The playhead inside the existing clip collides
+        let placed =
+            place_recording(&project, &recording, &at(PlayheadOrEnd::Playhead(5.0))).unwrap();
+        assert_eq!(placed.track_id, main_id);
+        assert_eq!(placed.start_time, 10.0);
+        // A free spot is used as-is, and so is the end
+        let placed =
+            place_recording(&project, &recording, &at(PlayheadOrEnd::Playhead(20.0))).unwrap();
+        assert_eq!(placed.start_time, 20.0);
+        let placed = place_recording(&project, &recording, &at(PlayheadOrEnd::End)).unwrap();
+        assert_eq!(
+            (placed.start_time, placed.in_point, placed.out_point),
+            (10.0, 0.0, 10.0)
+        );
+
+        let missing = AutoAddTarget {
+            track_id: Some("nope".to_string()),
+            at: PlayheadOrEnd::End,
+        };
+        assert!(place_recording(&project, &recording, &missing).is_err());
+    }
+
+    #[test]
+    fn test_auto_add_creates_a_project_and_applies_the_suggested_trim() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        let events = CapturedEvents::default();
+        let recording = clip("/recordings/take1.mp4");
+
+        // Without auto-add a missing project is still an error
+        assert!(add_recording_to_project(&state, &events, &recording, None).is_err());
+        assert!(state.project.lock().unwrap().is_none());
+
+        let target = AutoAddTarget {
+            track_id: None,
+            at: PlayheadOrEnd::Playhead(3.0),
+        };
+        let ids = add_recording_to_project(&state, &events, &recording, Some(&target)).unwrap();
+        assert_eq!(ids.len(), 1);
+
+        let mut project_lock = state.project.lock().unwrap();
+        let project = project_lock.as_mut().unwrap();
+        assert_eq!(project.media_library[0].id, recording.id);
+        let placed = &project.tracks[0].clips[0];
+        assert_eq!(
+            (placed.id.as_str(), placed.start_time),
+            (ids[0].as_str(), 3.0)
+        );
+
+        // Analysis finishing later trims the auto-added clip
+        let mut analysed = recording.clone();
+        analysed.suggested_trim = Some(SuggestedTrim {
+            in_point: 2.5,
+            out_point: 9.0,
+        });
+        assert_eq!(apply_suggested_trim(project, &analysed, &ids), ids);
+        let placed = &project.tracks[0].clips[0];
+        assert_eq!((placed.in_point, placed.out_point), (2.5, 9.0));
+        // Once trimmed (by the user or the analysis), it isn't touched again
+        assert!(apply_suggested_trim(project, &analysed, &ids).is_empty());
+    }
+
+    #[test]
+    fn test_recording_fps_falls_back_for_selected_sources() {
+        let sources = RecordingSources {
+            screens: vec![ScreenSource {
+                id: "Capture screen 0".to_string(),
+                name: "Main Display".to_string(),
+                resolution: "1920x1080".to_string(),
+                supported_fps: vec![15, 24, 30, 60],
+            }],
+            windows: vec![],
+            cameras: vec![
+                RecordingSource {
+                    id: "0".to_string(),
+                    name: "FaceTime HD Camera".to_string(),
+                    supported_fps: vec![15, 24, 30],
+                },
+                RecordingSource {
+                    id: "1".to_string(),
+                    name: "Capture Card".to_string(),
+                    supported_fps: vec![],
+                },
+            ],
+            microphones: vec![],
+        };
+        let config = |camera: Option<&str>, fps: u32| RecordingConfig {
+            recording_type: RecordingType::ScreenWebcam,
+            screen_source_id: Some("Capture screen 0".to_string()),
+            camera_device_id: camera.map(str::to_string),
+            audio_sources: vec![],
+            microphone_device_id: None,
+            settings: RecordingSettings {
+                resolution: "1920x1080".to_string(),
+                fps,
+                chroma: ChromaMode::Standard420,
+                hardware_encoder: false,
+            },
+            post_process: RecordingPostProcess::default(),
+            auto_add_to_timeline: None,
+            camera: CameraOptions::default(),
+        };
+
+        assert_eq!(recording_fps(&config(None, 60), Some(&sources)), 60);
+        assert_eq!(recording_fps(&config(Some("0"), 60), Some(&sources)), 30);
+        assert_eq!(recording_fps(&config(Some("0"), 24), Some(&sources)), 24);
+        // Unknown capabilities and unlisted sources don't constrain the rate
+        assert_eq!(recording_fps(&config(Some("1"), 60), Some(&sources)), 60);
+        assert_eq!(recording_fps(&config(Some("9"), 60), Some(&sources)), 60);
+        assert_eq!(recording_fps(&config(Some("0"), 60), None), 60);
+    }
+
+    #[test]
+    fn test_resync_sessions() {
+        let now = chrono::Utc::now();
+        let session = |seconds_ago: i64| {
+            let mut session = RecordingSession::new(
+                RecordingType::Screen,
+                "/tmp/rec.mp4".to_string(),
+                "1920x1080".to_string(),
+                30,
+            );
+            session.start();
+            session.started_at = Some(now - chrono::Duration::seconds(seconds_ago));
+            session.update_duration(1.0);
+            session
+        };
+        let recent = session(5);
+        let old = session(90);
+        let orphaned = session(30);
+        let running = [recent.id.clone(), old.id.clone()];
+
+        let resynced = resync_sessions(
+            vec![recent.clone(), orphaned, old.clone()],
+            |id| running.iter().any(|r| r == id),
+            now,
+        );
+
+        // Durations come from `started_at`, not the last tick
+        let summary: Vec<(&str, Option<f64>)> = resynced
+            .iter()
+            .map(|s| (s.id.as_str(), s.duration))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (old.id.as_str(), Some(90.0)),
+                (recent.id.as_str(), Some(5.0))
+            ]
+        );
+    }
 
     #[test]
     fn test_get_recordings_dir() {
-        let result = get_recordings_dir();
+        let result = get_recordings_dir(None);
 
         // Should either succeed or fail with a known error
         // (on some systems the Videos directory might not exist)
@@ -363,6 +1114,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_recordings_dir_from_settings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let configured = temp_dir.path().join("captures");
+
+        let dir = get_recordings_dir(Some(configured.to_str().unwrap())).unwrap();
+        assert_eq!(dir, configured);
+        assert!(dir.exists());
+    }
+
     #[tokio::test]
     async fn test_request_recording_permissions() {
         let permissions = vec!["screen".to_string()];
@@ -0,0 +1,197 @@
+// Application settings commands
+use crate::commands::assets::allow_asset_dir;
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::startup::{switch_cache_dir, StartupState};
+use crate::error::ClipForgeError;
+use crate::ffmpeg::locate::{self, FfmpegStatus};
+use crate::ffmpeg::managed::{self, DownloadProgress};
+use crate::ffmpeg::scheduler;
+use crate::logging;
+use crate::models::settings::AppSettings;
+use crate::storage::work_dir::{self, WorkArea};
+use std::path::Path;
+use tauri::{AppHandle, Manager, State};
+
+/// Get the current application settings
+#[tauri::command]
+pub async fn get_app_settings(state: State<'_, AppState>) -> Result<AppSettings, ClipForgeError> {
+    let settings = state.settings.lock_state()?;
+    Ok(settings.clone())
+}
+
+/// Apply a partial settings update
+///
+/// `patch` uses JSON merge patch semantics: nested objects are merged and
+/// `null` resets a field to its default. The merged settings are validated
+/// before anything is persisted. A new `cache_dir` is opened right away and
+/// the settings are saved into its database; the old directory is kept.
+#[tauri::command]
+pub async fn update_app_settings(
+    patch: serde_json::Value,
+    state: State<'_, AppState>,
+    startup: State<'_, StartupState>,
+    app_handle: AppHandle,
+) -> Result<AppSettings, ClipForgeError> {
+    let updated = {
+        let mut settings = state.settings.lock_state()?;
+        let updated = settings
+            .apply_patch(&patch)
+            .map_err(|e| ClipForgeError::validation("patch", e))?;
+        updated
+            .validate_update(&settings)
+            .map_err(|e| ClipForgeError::validation("patch", e))?;
+
+        if updated.cache_dir != settings.cache_dir {
+            switch_cache_dir(&state, &startup, updated.cache_dir.as_deref())?;
+        }
+
+        let cache_db = state.cache_db.lock_state()?;
+        cache_db.save_app_settings(&updated)?;
+
+        *settings = updated.clone();
+        updated
+    };
+
+    // Subsystems read settings from AppState on use; the event lets the
+    // frontend (and any long-running task) pick up the change immediately
+    let _ = app_handle.emit_all("settings_changed", &updated);
+
+    if let Some(dir) = &updated.recordings_dir {
+        allow_asset_dir(&app_handle, Path::new(dir));
+    }
+    if let Ok(dir) = crate::commands::media::get_cache_dir() {
+        allow_asset_dir(&app_handle, &dir);
+    }
+    // Running jobs keep the paths they started with
+    work_dir::install(updated.work_directory.as_deref().map(Path::new));
+    if let Ok(dir) = work_dir::area_dir(WorkArea::Preview) {
+        allow_asset_dir(&app_handle, &dir);
+    }
+    scheduler::configure(updated.max_concurrent_jobs);
+    logging::set_debug(updated.debug_logging);
+
+    Ok(updated)
+}
+
+/// Get the located FFmpeg installation and any problems with it
+#[tauri::command]
+pub async fn get_ffmpeg_status(state: State<'_, AppState>) -> Result<FfmpegStatus, ClipForgeError> {
+    Ok(state.ffmpeg.lock_state()?.clone())
+}
+
+/// Use the FFmpeg at `path` (binary or its directory); null searches again
+///
+/// An explicit path is rejected unless ffmpeg and ffprobe exist there. The
+/// resolved binaries are used by every FFmpeg call from then on.
+#[tauri::command]
+pub async fn set_ffmpeg_path(
+    path: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<FfmpegStatus, ClipForgeError> {
+    let resource_dir = app_handle.path_resolver().resource_dir();
+    let explicit = path.clone();
+    let status = tokio::task::spawn_blocking(move || match &explicit {
+        Some(path) => locate::check_explicit(std::path::Path::new(path)),
+        None => locate::locate(None, resource_dir.as_deref()),
+    })
+    .await
+    .map_err(|e| format!("FFmpeg check failed: {}", e))?;
+
+    if path.is_some() && status.paths().is_none() {
+        return Err(ClipForgeError::MissingDependency {
+            which: "ffmpeg".to_string(),
+            message: status
+                .error
+                .unwrap_or_else(|| locate::FFMPEG_NOT_FOUND.to_string()),
+        });
+    }
+
+    let updated = {
+        let mut settings = state.settings.lock_state()?;
+        let mut updated = settings.clone();
+        updated.ffmpeg_path = path;
+        state.cache_db.lock_state()?.save_app_settings(&updated)?;
+        *settings = updated.clone();
+        updated
+    };
+    let _ = app_handle.emit_all("settings_changed", &updated);
+
+    locate::install(&status);
+    *state.ffmpeg.lock_state()? = status.clone();
+    Ok(status)
+}
+
+/// Download the pinned FFmpeg build for this platform into ~/.clipforge/bin
+///
+/// Emits `ffmpeg_download_progress` while running. An interrupted download
+/// resumes on the next call; an already current install is left as is.
+#[tauri::command]
+pub async fn download_ffmpeg(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<FfmpegStatus, ClipForgeError> {
+    install_managed_ffmpeg(&state, &app_handle).await
+}
+
+/// Replace the managed FFmpeg with the manifest's current build, if newer
+#[tauri::command]
+pub async fn update_managed_ffmpeg(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<FfmpegStatus, ClipForgeError> {
+    if locate::managed_version().is_none() {
+        return Err(ClipForgeError::MissingDependency {
+            which: "ffmpeg".to_string(),
+            message: "No managed FFmpeg is installed".to_string(),
+        });
+    }
+    install_managed_ffmpeg(&state, &app_handle).await
+}
+
+async fn install_managed_ffmpeg(
+    state: &AppState,
+    app_handle: &AppHandle,
+) -> Result<FfmpegStatus, ClipForgeError> {
+    let (manifest_url, explicit) = {
+        let settings = state.settings.lock_state()?;
+        (
+            settings.ffmpeg_manifest_url.clone(),
+            settings.ffmpeg_path.clone(),
+        )
+    };
+    let manifest_url = manifest_url.ok_or_else(|| {
+        ClipForgeError::validation(
+            "ffmpeg_manifest_url",
+            "No FFmpeg download source is configured (ffmpeg_manifest_url)",
+        )
+    })?;
+    let dir = managed::managed_bin_dir().ok_or("Failed to get home directory")?;
+
+    let progress_handle = app_handle.clone();
+    let install =
+        managed::download_and_install(&manifest_url, &dir, move |progress: DownloadProgress| {
+            let _ = progress_handle.emit_all("ffmpeg_download_progress", progress);
+        })
+        .await?;
+    println!(
+        "[FFmpeg] Managed build {} installed in {}",
+        install.version,
+        dir.display()
+    );
+
+    // Re-run discovery so the managed build replaces a system one
+    let resource_dir = app_handle.path_resolver().resource_dir();
+    let status = tokio::task::spawn_blocking(move || {
+        locate::locate(
+            explicit.as_deref().map(std::path::Path::new),
+            resource_dir.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("FFmpeg check failed: {}", e))?;
+    locate::install(&status);
+    *state.ffmpeg.lock_state()? = status.clone();
+    Ok(status)
+}
@@ -0,0 +1,502 @@
+// Startup initialization and degraded mode
+// Opening the cache directory and database can fail: no home directory, a
+// read-only or full disk, a database locked by another process. Instead of
+// crashing, the app then starts on an in-memory cache with default settings,
+// reports the failure through `get_startup_status` and an
+// `initialization_error` event, and offers recovery: retry, choose another
+// cache directory (persisted in `location.json`) or rebuild the database.
+
+use crate::commands::assets::allow_asset_dir;
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::scheduler;
+use crate::logging;
+use crate::storage::cache::CacheDb;
+use crate::storage::cache_location::{self, CacheLocation, CACHE_DB_FILE};
+use crate::storage::cache_paths::rebase_clip_paths;
+use crate::storage::settings::{legacy_config_path, load_app_settings};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, State};
+
+/// Step of cache initialization that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitializationStage {
+    /// No home directory to put the default cache directory in
+    HomeDirectory,
+    /// The cache directory could not be created
+    CacheDirectory,
+    /// The cache database could not be opened (locked, read-only, ...)
+    CacheDatabase,
+}
+
+/// What the user can do about an initialization failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    Retry,
+    ChooseCacheDirectory,
+    /// Move the database aside and start an empty one
+    RebuildCache,
+}
+
+/// `initialization_error` event payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InitializationError {
+    pub stage: InitializationStage,
+    pub message: String,
+    /// Directory that was tried, if one could be determined
+    pub cache_dir: Option<String>,
+    pub recovery_actions: Vec<RecoveryAction>,
+}
+
+impl InitializationError {
+    fn new(stage: InitializationStage, message: String, cache_dir: Option<&Path>) -> Self {
+        let recovery_actions = match stage {
+            InitializationStage::HomeDirectory | InitializationStage::CacheDirectory => {
+                vec![RecoveryAction::ChooseCacheDirectory, RecoveryAction::Retry]
+            }
+            InitializationStage::CacheDatabase => vec![
+                RecoveryAction::Retry,
+                RecoveryAction::RebuildCache,
+                RecoveryAction::ChooseCacheDirectory,
+            ],
+        };
+        Self {
+            stage,
+            message,
+            cache_dir: cache_dir.map(|dir| dir.to_string_lossy().to_string()),
+            recovery_actions,
+        }
+    }
+}
+
+/// Result of `get_startup_status` and the recovery commands
+///
+/// Also emitted as `startup_status_changed` after each recovery attempt.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StartupStatus {
+    /// False while running on the in-memory fallback cache
+    pub ready: bool,
+    /// Cache directory in use
+    pub cache_dir: Option<String>,
+    /// Why the cache could not be opened (last attempt)
+    pub error: Option<InitializationError>,
+    /// Where a corrupted or rebuilt database was moved
+    pub corrupt_cache_path: Option<String>,
+}
+
+/// A cache directory with its opened database
+pub struct OpenedCache {
+    pub cache_db: CacheDb,
+    pub cache_dir: PathBuf,
+    pub corrupt_path: Option<PathBuf>,
+}
+
+impl StartupStatus {
+    pub fn ready(opened: &OpenedCache) -> Self {
+        Self {
+            ready: true,
+            cache_dir: Some(opened.cache_dir.to_string_lossy().to_string()),
+            error: None,
+            corrupt_cache_path: opened
+                .corrupt_path
+                .as_ref()
+                .map(|path| path.to_string_lossy().to_string()),
+        }
+    }
+
+    pub fn degraded(error: InitializationError) -> Self {
+        Self {
+            ready: false,
+            cache_dir: None,
+            error: Some(error),
+            corrupt_cache_path: None,
+        }
+    }
+}
+
+/// Startup status and where the chosen cache directory is persisted
+#[derive(Debug, Clone)]
+pub struct StartupState {
+    status: Arc<Mutex<StartupStatus>>,
+    location_file: Option<PathBuf>,
+}
+
+impl StartupState {
+    pub fn new(status: StartupStatus, location_file: Option<PathBuf>) -> Self {
+        Self {
+            status: Arc::new(Mutex::new(status)),
+            location_file,
+        }
+    }
+
+    pub fn status(&self) -> StartupStatus {
+        self.status.lock_recovering().clone()
+    }
+
+    /// The persisted cache location (default when nothing was chosen)
+    pub fn location(&self) -> CacheLocation {
+        self.location_file
+            .as_deref()
+            .map(cache_location::read_location)
+            .unwrap_or_default()
+    }
+
+    fn save_location(&self, location: &CacheLocation) -> Result<(), String> {
+        let file = self
+            .location_file
+            .as_deref()
+            .ok_or("No configuration directory to remember the cache location in")?;
+        cache_location::write_location(file, location)
+    }
+}
+
+/// Create `cache_dir` and open its database with `open`
+///
+/// `cache_dir` is None when there is no home directory for the default one.
+pub fn open_cache(
+    cache_dir: Option<&Path>,
+    open: impl FnOnce(&PathBuf) -> Result<(CacheDb, Option<PathBuf>), String>,
+) -> Result<OpenedCache, InitializationError> {
+    let cache_dir = cache_dir.ok_or_else(|| {
+        InitializationError::new(
+            InitializationStage::HomeDirectory,
+            "Could not determine the home directory for the cache".to_string(),
+            None,
+        )
+    })?;
+
+    std::fs::create_dir_all(cache_dir).map_err(|e| {
+        InitializationError::new(
+            InitializationStage::CacheDirectory,
+            format!("Failed to create cache directory: {}", e),
+            Some(cache_dir),
+        )
+    })?;
+
+    let (cache_db, corrupt_path) = open(&cache_dir.join(CACHE_DB_FILE)).map_err(|e| {
+        InitializationError::new(
+            InitializationStage::CacheDatabase,
+            format!("Failed to open cache database: {}", e),
+            Some(cache_dir),
+        )
+    })?;
+
+    Ok(OpenedCache {
+        cache_db,
+        cache_dir: cache_dir.to_path_buf(),
+        corrupt_path,
+    })
+}
+
+/// A recovery requested by the user
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recovery {
+    Retry,
+    ChooseCacheDirectory(PathBuf),
+    RebuildCache,
+}
+
+/// Attempt `recovery`, switching the app onto the opened cache on success
+fn recover(
+    state: &AppState,
+    startup: &StartupState,
+    app_handle: &AppHandle,
+    recovery: Recovery,
+) -> StartupStatus {
+    // Only a rebuild moves the database aside; a retry may just find it
+    // no longer locked
+    let open = match recovery {
+        Recovery::RebuildCache => CacheDb::rebuild,
+        Recovery::Retry | Recovery::ChooseCacheDirectory(_) => CacheDb::open_existing,
+    };
+    let status = recover_with(state, startup, recovery, open);
+
+    if let Some(dir) = &status.cache_dir {
+        allow_asset_dir(app_handle, Path::new(dir));
+    }
+    let _ = app_handle.emit_all("startup_status_changed", &status);
+    if status.ready {
+        let settings = state.settings.lock_recovering().clone();
+        let _ = app_handle.emit_all("settings_changed", &settings);
+    }
+    status
+}
+
+fn recover_with(
+    state: &AppState,
+    startup: &StartupState,
+    recovery: Recovery,
+    open: impl FnOnce(&PathBuf) -> Result<(CacheDb, Option<PathBuf>), String>,
+) -> StartupStatus {
+    let location = match &recovery {
+        Recovery::ChooseCacheDirectory(dir) => CacheLocation {
+            cache_dir: Some(dir.to_string_lossy().to_string()),
+        },
+        Recovery::Retry | Recovery::RebuildCache => startup.location(),
+    };
+
+    let status = match open_cache(location.resolve().as_deref(), open) {
+        Ok(opened) => {
+            if matches!(recovery, Recovery::ChooseCacheDirectory(_)) {
+                if let Err(e) = startup.save_location(&location) {
+                    log::warn!("Cache directory will not be remembered: {}", e);
+                }
+            }
+            let status = StartupStatus::ready(&opened);
+            activate_cache(state, opened, &location);
+            log::info!("Cache recovered in {:?}", status.cache_dir);
+            status
+        }
+        Err(error) => {
+            log::error!("Cache recovery failed: {}", error.message);
+            // A working cache stays in use when e.g. rebuilding it failed
+            let previous = startup.status();
+            StartupStatus {
+                error: Some(error),
+                ..previous
+            }
+        }
+    };
+
+    *startup.status.lock_recovering() = status.clone();
+    status
+}
+
+/// Replace the (fallback) cache database and reload the settings stored in it
+fn activate_cache(state: &AppState, opened: OpenedCache, location: &CacheLocation) {
+    let previous = cache_location::active_cache_dir();
+    cache_location::install(&opened.cache_dir);
+    revalidate_cache_paths(state, previous.as_deref(), &opened.cache_dir);
+    let mut settings = load_app_settings(&opened.cache_db, legacy_config_path().as_deref());
+    settings.cache_dir = location.cache_dir.clone();
+    *state.cache_db.lock_recovering() = opened.cache_db;
+
+    scheduler::configure(settings.max_concurrent_jobs);
+    logging::set_debug(settings.debug_logging);
+    *state.settings.lock_recovering() = settings;
+}
+
+/// Move a working app onto another cache directory (the `cache_dir` setting)
+///
+/// The current settings are kept and saved by the caller into the new
+/// database; the previous directory and its files are left in place.
+pub fn switch_cache_dir(
+    state: &AppState,
+    startup: &StartupState,
+    cache_dir: Option<&str>,
+) -> Result<(), String> {
+    let location = CacheLocation {
+        cache_dir: cache_dir.map(str::to_string),
+    };
+    let opened = open_cache(location.resolve().as_deref(), CacheDb::open_or_rebuild)
+        .map_err(|e| e.message)?;
+    startup.save_location(&location)?;
+
+    let previous = cache_location::active_cache_dir();
+    cache_location::install(&opened.cache_dir);
+    revalidate_cache_paths(state, previous.as_deref(), &opened.cache_dir);
+    *startup.status.lock_state()? = StartupStatus::ready(&opened);
+    *state.cache_db.lock_state()? = opened.cache_db;
+    Ok(())
+}
+
+/// Point the open project's proxies and thumbnails into the new cache
+/// directory where they were moved along, dropping the ones that are gone
+fn revalidate_cache_paths(state: &AppState, old_dir: Option<&Path>, new_dir: &Path) {
+    let Some(old_dir) = old_dir.filter(|dir| *dir != new_dir) else {
+        return;
+    };
+    let (old_dir, new_dir) = (old_dir.to_string_lossy(), new_dir.to_string_lossy());
+    let mut project_lock = state.project.lock_recovering();
+    let Some(project) = project_lock.as_mut() else {
+        return;
+    };
+    let changed: usize = project
+        .media_library
+        .iter_mut()
+        .map(|clip| rebase_clip_paths(clip, &old_dir, &new_dir, |p| Path::new(p).exists()))
+        .sum();
+    if changed > 0 {
+        log::info!(
+            "Revalidated {} proxy/thumbnail paths after the cache moved to {}",
+            changed,
+            new_dir
+        );
+    }
+}
+
+/// Whether the cache opened at startup, and if not, why and how to recover
+#[tauri::command]
+pub async fn get_startup_status(
+    startup: State<'_, StartupState>,
+) -> Result<StartupStatus, ClipForgeError> {
+    Ok(startup.status())
+}
+
+/// Try opening the configured cache directory again
+#[tauri::command]
+pub async fn retry_startup(
+    state: State<'_, AppState>,
+    startup: State<'_, StartupState>,
+    app_handle: AppHandle,
+) -> Result<StartupStatus, ClipForgeError> {
+    Ok(recover(&state, &startup, &app_handle, Recovery::Retry))
+}
+
+/// Use (and remember) another cache directory
+#[tauri::command]
+pub async fn choose_cache_directory(
+    path: String,
+    state: State<'_, AppState>,
+    startup: State<'_, StartupState>,
+    app_handle: AppHandle,
+) -> Result<StartupStatus, ClipForgeError> {
+    if !Path::new(&path).is_absolute() {
+        return Err(ClipForgeError::validation(
+            "path",
+            format!("Cache directory must be an absolute path: {}", path),
+        ));
+    }
+    Ok(recover(
+        &state,
+        &startup,
+        &app_handle,
+        Recovery::ChooseCacheDirectory(PathBuf::from(path)),
+    ))
+}
+
+/// Move the cache database aside and start with an empty one
+///
+/// Cached metadata, thumbnails and settings are regenerated or reset.
+#[tauri::command]
+pub async fn rebuild_cache(
+    state: State<'_, AppState>,
+    startup: State<'_, StartupState>,
+    app_handle: AppHandle,
+) -> Result<StartupStatus, ClipForgeError> {
+    Ok(recover(
+        &state,
+        &startup,
+        &app_handle,
+        Recovery::RebuildCache,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::library::tests::mock_state;
+    use tempfile::TempDir;
+
+    // Same signature as the real openers, which take `&PathBuf`
+    #[allow(clippy::ptr_arg)]
+    fn failing_open(path: &PathBuf) -> Result<(CacheDb, Option<PathBuf>), String> {
+        Err(format!("database is locked: {}", path.display()))
+    }
+
+    #[test]
+    fn test_open_cache_failures() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let error = open_cache(None, CacheDb::open_or_rebuild).err().unwrap();
+        assert_eq!(error.stage, InitializationStage::HomeDirectory);
+        assert!(error
+            .recovery_actions
+            .contains(&RecoveryAction::ChooseCacheDirectory));
+
+        // A file where the directory should be
+        let blocked = temp_dir.path().join("blocked");
+        std::fs::write(&blocked, b"").unwrap();
+        let error = open_cache(Some(&blocked.join("cache")), CacheDb::open_or_rebuild)
+            .err()
+            .unwrap();
+        assert_eq!(error.stage, InitializationStage::CacheDirectory);
+
+        let cache_dir = temp_dir.path().join("cache");
+        let error = open_cache(Some(&cache_dir), failing_open).err().unwrap();
+        assert_eq!(error.stage, InitializationStage::CacheDatabase);
+        assert!(error.message.contains("database is locked"));
+        assert!(error
+            .recovery_actions
+            .contains(&RecoveryAction::RebuildCache));
+
+        let opened = open_cache(Some(&cache_dir), CacheDb::open_or_rebuild).unwrap();
+        assert_eq!(opened.cache_dir, cache_dir);
+        assert!(cache_dir.join(CACHE_DB_FILE).exists());
+    }
+
+    #[test]
+    fn test_recovery_state_machine() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = mock_state(&temp_dir);
+        *state.cache_db.lock().unwrap() = CacheDb::in_memory().unwrap();
+        let location_file = temp_dir.path().join("config").join("location.json");
+        let default_dir = temp_dir.path().join("default");
+
+        let startup = StartupState::new(
+            StartupStatus::degraded(InitializationError::new(
+                InitializationStage::CacheDatabase,
+                "locked".to_string(),
+                Some(&default_dir),
+            )),
+            Some(location_file.clone()),
+        );
+
+        // Retrying while the database is still locked stays degraded
+        let status = recover_with(&state, &startup, Recovery::Retry, failing_open);
+        assert!(!status.ready);
+        assert_eq!(
+            status.error.as_ref().unwrap().stage,
+            InitializationStage::CacheDatabase
+        );
+
+        // Choosing another directory recovers and is remembered
+        let chosen = temp_dir.path().join("alternate");
+        let status = recover_with(
+            &state,
+            &startup,
+            Recovery::ChooseCacheDirectory(chosen.clone()),
+            CacheDb::open_existing,
+        );
+        assert!(status.ready);
+        assert!(status.error.is_none());
+        assert_eq!(startup.status(), status);
+        assert_eq!(startup.location().resolve(), Some(chosen.clone()));
+        assert_eq!(
+            state.settings.lock().unwrap().cache_dir.as_deref(),
+            chosen.to_str()
+        );
+        // The real database replaced the fallback
+        state
+            .cache_db
+            .lock()
+            .unwrap()
+            .add_recent_project("/p/a.clipforge", "A")
+            .unwrap();
+        let reopened = CacheDb::new(&chosen.join(CACHE_DB_FILE)).unwrap();
+        assert_eq!(reopened.get_recent_projects().unwrap().len(), 1);
+
+        // A failed rebuild keeps the working cache
+        let status = recover_with(&state, &startup, Recovery::RebuildCache, failing_open);
+        assert!(status.ready);
+        assert!(status.error.is_some());
+
+        // Rebuilding moves the database aside
+        let status = recover_with(&state, &startup, Recovery::RebuildCache, CacheDb::rebuild);
+        assert!(status.ready);
+        let moved = PathBuf::from(status.corrupt_cache_path.unwrap());
+        assert!(moved.exists());
+        assert!(state
+            .cache_db
+            .lock()
+            .unwrap()
+            .get_recent_projects()
+            .unwrap()
+            .is_empty());
+    }
+}
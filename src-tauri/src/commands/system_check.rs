@@ -0,0 +1,546 @@
+// First-run system check
+// One report of everything ClipForge needs from outside the app: FFmpeg, a
+// hardware encoder, whisper.cpp and its model, recording permissions, and
+// writable data directories with room to spare. Every item comes from the
+// probe the feature itself uses; the probes only read state and run
+// concurrently, each within `PROBE_TIMEOUT`, so the check can be repeated
+// at any time (e.g. after the user fixed something).
+
+use crate::ai::whisper;
+use crate::commands::locks::LockState;
+use crate::commands::media::AppState;
+use crate::commands::recording::get_recordings_dir;
+use crate::commands::startup::StartupState;
+use crate::error::ClipForgeError;
+use crate::ffmpeg::capabilities::{self, HardwareEncoders};
+use crate::ffmpeg::locate::{self, FfmpegStatus};
+use crate::models::recording::PermissionStatus;
+use crate::models::settings::expand_home;
+use crate::platform;
+use crate::storage::work_dir::{check_writable, free_space, MIN_WORK_DIR_FREE_BYTES};
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+
+/// Longest a single probe may take before it's reported as not responding
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but something is degraded or a feature is unavailable
+    Warn,
+    /// Core features won't work
+    Fail,
+}
+
+/// What the user can do about a check that isn't ok (mapped to buttons)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckAction {
+    DownloadFfmpeg,
+    ConfigureWhisper,
+    OpenPermissionSettings,
+    ChooseCacheDirectory,
+    ChooseRecordingsDirectory,
+    ChooseWorkDirectory,
+}
+
+/// One line of the report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckItem {
+    /// Stable identifier, e.g. "ffmpeg" or "camera_permission"
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub action: Option<CheckAction>,
+}
+
+impl CheckItem {
+    fn new(id: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            status,
+            detail: detail.into(),
+            action: None,
+        }
+    }
+
+    fn action(self, action: CheckAction) -> Self {
+        Self {
+            action: Some(action),
+            ..self
+        }
+    }
+}
+
+/// Result of `run_system_check`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SystemCheckReport {
+    /// Worst status of any item
+    pub status: CheckStatus,
+    pub items: Vec<CheckItem>,
+}
+
+/// Directory ClipForge writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirectory {
+    Cache,
+    Recordings,
+    Work,
+}
+
+impl DataDirectory {
+    fn id(self) -> &'static str {
+        match self {
+            DataDirectory::Cache => "cache_directory",
+            DataDirectory::Recordings => "recordings_directory",
+            DataDirectory::Work => "work_directory",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DataDirectory::Cache => "Cache directory",
+            DataDirectory::Recordings => "Recordings directory",
+            DataDirectory::Work => "Work directory",
+        }
+    }
+
+    fn action(self) -> CheckAction {
+        match self {
+            DataDirectory::Cache => CheckAction::ChooseCacheDirectory,
+            DataDirectory::Recordings => CheckAction::ChooseRecordingsDirectory,
+            DataDirectory::Work => CheckAction::ChooseWorkDirectory,
+        }
+    }
+}
+
+/// A data directory as probed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryProbe {
+    pub directory: DataDirectory,
+    /// The directory, or why there is none
+    pub path: Result<PathBuf, String>,
+    /// Whether a file could be written there
+    pub writable: Result<(), String>,
+    /// Bytes free on its volume (None = unknown)
+    pub free_bytes: Option<u64>,
+}
+
+/// Raw results of every probe, turned into a report by `assemble_report`
+#[derive(Debug, Clone)]
+pub struct ProbeResults {
+    pub ffmpeg: FfmpegStatus,
+    /// Hardware H.264 encoders in the FFmpeg build and which one opened
+    pub hardware_encoders: Result<HardwareEncoders, String>,
+    pub whisper_executable: (String, Result<(), String>),
+    /// Model path and whether the file exists
+    pub whisper_model: (String, bool),
+    pub permissions: Result<PermissionStatus, String>,
+    pub directories: Vec<DirectoryProbe>,
+}
+
+/// Run `probe` within `PROBE_TIMEOUT`
+async fn timed<T>(probe: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    tokio::time::timeout(PROBE_TIMEOUT, probe)
+        .await
+        .unwrap_or_else(|_| {
+            Err(format!(
+                "Didn't respond within {:.1} s",
+                PROBE_TIMEOUT.as_secs_f64()
+            ))
+        })
+}
+
+/// Run a blocking probe on the blocking pool
+async fn blocking<T: Send + 'static>(
+    probe: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(probe)
+        .await
+        .map_err(|e| format!("Check failed: {}", e))?
+}
+
+fn probe_directory(directory: DataDirectory, path: Result<PathBuf, String>) -> DirectoryProbe {
+    let (writable, free_bytes) = match &path {
+        Ok(dir) => (check_writable(directory.label(), dir), free_space(dir)),
+        Err(e) => (Err(e.clone()), None),
+    };
+    DirectoryProbe {
+        directory,
+        path,
+        writable,
+        free_bytes,
+    }
+}
+
+/// Check FFmpeg, encoders, whisper, permissions and data directories
+///
+/// Safe to call repeatedly; nothing is changed except that a missing
+/// recordings directory is created, as recording would.
+#[tauri::command]
+pub async fn run_system_check(
+    state: State<'_, AppState>,
+    startup: State<'_, StartupState>,
+) -> Result<SystemCheckReport, ClipForgeError> {
+    let ffmpeg = state.ffmpeg.lock_state()?.clone();
+    let settings = state.settings.lock_state()?.clone();
+    let cache_dir = {
+        let status = startup.status();
+        match (status.cache_dir, status.error) {
+            (Some(dir), _) if status.ready => Ok(PathBuf::from(dir)),
+            (_, Some(error)) => Err(error.message),
+            _ => Err("The cache could not be opened".to_string()),
+        }
+    };
+    let work_dir = settings
+        .work_directory
+        .as_deref()
+        .map_or_else(std::env::temp_dir, PathBuf::from);
+    let recordings_dir = settings.recordings_dir.clone();
+    let whisper_executable = settings.whisper.executable_path.clone();
+    let whisper_model = expand_home(&settings.whisper.model_path);
+
+    let ffmpeg_found = ffmpeg.paths().is_some();
+    let encoders = async {
+        if ffmpeg_found {
+            timed(capabilities::hardware_encoders()).await
+        } else {
+            Err(locate::FFMPEG_NOT_FOUND.to_string())
+        }
+    };
+    let whisper = timed(async {
+        whisper::check_executable(&whisper_executable)
+            .await
+            .map_err(|e| e.to_string())
+    });
+    let model_path = whisper_model.clone();
+    let model = timed(blocking(move || Ok(Path::new(&model_path).is_file())));
+    let permissions = timed(blocking(|| {
+        platform::request_permissions(
+            ["screen", "camera", "microphone"]
+                .map(str::to_string)
+                .to_vec(),
+        )
+        .map(|result| result.granted)
+    }));
+    let directories = timed(blocking(move || {
+        Ok(vec![
+            probe_directory(DataDirectory::Cache, cache_dir),
+            probe_directory(
+                DataDirectory::Recordings,
+                get_recordings_dir(recordings_dir.as_deref()),
+            ),
+            probe_directory(DataDirectory::Work, Ok(work_dir)),
+        ])
+    }));
+    let (encoders, whisper, model, permissions, directories) =
+        tokio::join!(encoders, whisper, model, permissions, directories);
+
+    let probes = ProbeResults {
+        hardware_encoders: encoders,
+        ffmpeg,
+        whisper_executable: (whisper_executable, whisper),
+        whisper_model: (whisper_model, model.unwrap_or(false)),
+        permissions,
+        directories: directories.map_err(ClipForgeError::from)?,
+    };
+    Ok(assemble_report(&probes))
+}
+
+/// Turn probe results into report items, in a fixed order
+pub fn assemble_report(probes: &ProbeResults) -> SystemCheckReport {
+    let mut items = vec![ffmpeg_item(&probes.ffmpeg)];
+
+    items.push(match &probes.hardware_encoders {
+        Ok(HardwareEncoders {
+            selected: Some(name),
+            ..
+        }) => CheckItem::new(
+            "hardware_encoder",
+            CheckStatus::Ok,
+            format!("{} is available", name),
+        ),
+        Ok(HardwareEncoders { listed, .. }) if listed.is_empty() => CheckItem::new(
+            "hardware_encoder",
+            CheckStatus::Warn,
+            "This FFmpeg build has no hardware H.264 encoder; exports encode in software",
+        ),
+        Ok(HardwareEncoders { listed, .. }) => CheckItem::new(
+            "hardware_encoder",
+            CheckStatus::Warn,
+            format!(
+                "{} can't encode on this machine; exports encode in software",
+                listed.join(", ")
+            ),
+        ),
+        Err(e) => CheckItem::new(
+            "hardware_encoder",
+            CheckStatus::Warn,
+            format!("Couldn't check for hardware encoders: {}", e),
+        ),
+    });
+
+    let (executable, found) = &probes.whisper_executable;
+    items.push(match found {
+        Ok(()) => CheckItem::new(
+            "whisper",
+            CheckStatus::Ok,
+            format!("whisper.cpp found ({})", executable),
+        ),
+        Err(e) => CheckItem::new("whisper", CheckStatus::Warn, e.as_str())
+            .action(CheckAction::ConfigureWhisper),
+    });
+    let (model, exists) = &probes.whisper_model;
+    items.push(if *exists {
+        CheckItem::new("whisper_model", CheckStatus::Ok, model.as_str())
+    } else {
+        CheckItem::new(
+            "whisper_model",
+            CheckStatus::Warn,
+            format!("Whisper model not found: {}", model),
+        )
+        .action(CheckAction::ConfigureWhisper)
+    });
+
+    match &probes.permissions {
+        Ok(granted) => {
+            for (id, label, granted) in [
+                ("screen_permission", "Screen recording", granted.screen),
+                ("camera_permission", "Camera", granted.camera),
+                ("microphone_permission", "Microphone", granted.microphone),
+            ] {
+                items.push(if granted {
+                    CheckItem::new(id, CheckStatus::Ok, format!("{} access granted", label))
+                } else {
+                    CheckItem::new(id, CheckStatus::Fail, format!("{} access denied", label))
+                        .action(CheckAction::OpenPermissionSettings)
+                });
+            }
+        }
+        Err(e) => items.push(CheckItem::new(
+            "recording_permissions",
+            CheckStatus::Warn,
+            e.as_str(),
+        )),
+    }
+
+    items.extend(probes.directories.iter().map(directory_item));
+
+    SystemCheckReport {
+        status: items
+            .iter()
+            .map(|item| item.status)
+            .max()
+            .unwrap_or(CheckStatus::Ok),
+        items,
+    }
+}
+
+fn ffmpeg_item(status: &FfmpegStatus) -> CheckItem {
+    let version = status.version.as_deref().unwrap_or("unknown version");
+    match (&status.ffmpeg_path, &status.ffprobe_path) {
+        (Some(ffmpeg), Some(ffprobe)) if status.available => CheckItem::new(
+            "ffmpeg",
+            CheckStatus::Ok,
+            format!("FFmpeg {} ({}, {})", version, ffmpeg, ffprobe),
+        ),
+        (Some(_), Some(_)) => CheckItem::new(
+            "ffmpeg",
+            CheckStatus::Warn,
+            format!(
+                "FFmpeg {}: {}",
+                version,
+                status
+                    .error
+                    .as_deref()
+                    .unwrap_or("some features won't work")
+            ),
+        )
+        .action(CheckAction::DownloadFfmpeg),
+        _ => CheckItem::new(
+            "ffmpeg",
+            CheckStatus::Fail,
+            status.error.as_deref().unwrap_or(locate::FFMPEG_NOT_FOUND),
+        )
+        .action(CheckAction::DownloadFfmpeg),
+    }
+}
+
+fn directory_item(probe: &DirectoryProbe) -> CheckItem {
+    let directory = probe.directory;
+    let fail = |detail: String| {
+        CheckItem::new(directory.id(), CheckStatus::Fail, detail).action(directory.action())
+    };
+    let path = match &probe.path {
+        Ok(path) => path,
+        Err(e) => return fail(format!("{}: {}", directory.label(), e)),
+    };
+    if let Err(e) = &probe.writable {
+        return fail(e.clone());
+    }
+    let free = probe
+        .free_bytes
+        .map(|bytes| format!(", {} GB free", bytes / (1024 * 1024 * 1024)))
+        .unwrap_or_default();
+    match probe.free_bytes {
+        Some(bytes) if bytes < MIN_WORK_DIR_FREE_BYTES => CheckItem::new(
+            directory.id(),
+            CheckStatus::Warn,
+            format!(
+                "{} is low on space: {} MB free",
+                path.display(),
+                bytes / (1024 * 1024)
+            ),
+        )
+        .action(directory.action()),
+        _ => CheckItem::new(
+            directory.id(),
+            CheckStatus::Ok,
+            format!("{}{}", path.display(), free),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn healthy() -> ProbeResults {
+        ProbeResults {
+            ffmpeg: FfmpegStatus {
+                available: true,
+                ffmpeg_path: Some("/usr/bin/ffmpeg".into()),
+                ffprobe_path: Some("/usr/bin/ffprobe".into()),
+                version: Some("6.1.1".into()),
+                ..Default::default()
+            },
+            hardware_encoders: Ok(HardwareEncoders {
+                listed: vec!["h264_videotoolbox"],
+                usable: vec!["h264_videotoolbox"],
+                selected: Some("h264_videotoolbox"),
+            }),
+            whisper_executable: ("whisper-cli".into(), Ok(())),
+            whisper_model: ("/models/base.bin".into(), true),
+            permissions: Ok(PermissionStatus {
+                screen: true,
+                camera: true,
+                microphone: true,
+            }),
+            directories: [
+                DataDirectory::Cache,
+                DataDirectory::Recordings,
+                DataDirectory::Work,
+            ]
+            .map(|directory| DirectoryProbe {
+                directory,
+                path: Ok(PathBuf::from("/data")),
+                writable: Ok(()),
+                free_bytes: Some(50 * GB),
+            })
+            .to_vec(),
+        }
+    }
+
+    fn item<'a>(report: &'a SystemCheckReport, id: &str) -> &'a CheckItem {
+        report.items.iter().find(|item| item.id == id).unwrap()
+    }
+
+    #[test]
+    fn test_healthy_system() {
+        let report = assemble_report(&healthy());
+        assert_eq!(report.status, CheckStatus::Ok);
+        assert_eq!(report.items.len(), 10);
+        assert!(report.items.iter().all(|item| item.action.is_none()));
+        assert_eq!(
+            item(&report, "ffmpeg").detail,
+            "FFmpeg 6.1.1 (/usr/bin/ffmpeg, /usr/bin/ffprobe)"
+        );
+        assert_eq!(item(&report, "cache_directory").detail, "/data, 50 GB free");
+    }
+
+    #[test]
+    fn test_problems_map_to_actions() {
+        let mut probes = healthy();
+        probes.ffmpeg = FfmpegStatus {
+            error: Some(locate::FFMPEG_NOT_FOUND.into()),
+            ..Default::default()
+        };
+        probes.hardware_encoders = Ok(HardwareEncoders {
+            listed: vec!["h264_nvenc"],
+            ..Default::default()
+        });
+        probes.whisper_executable.1 = Err("Whisper.cpp not found".into());
+        probes.whisper_model.1 = false;
+        probes.permissions = Ok(PermissionStatus {
+            screen: true,
+            camera: false,
+            microphone: true,
+        });
+        probes.directories[0].writable = Err("Cache directory is not writable".into());
+        probes.directories[1].free_bytes = Some(GB / 2);
+        probes.directories[2].path = Err("Cannot find home directory".into());
+
+        let report = assemble_report(&probes);
+        assert_eq!(report.status, CheckStatus::Fail);
+        let summary: Vec<(&str, CheckStatus, Option<CheckAction>)> = report
+            .items
+            .iter()
+            .map(|item| (item.id, item.status, item.action))
+            .collect();
+        use CheckAction::*;
+        use CheckStatus::{Fail, Warn};
+        let ok = CheckStatus::Ok;
+        assert_eq!(
+            summary,
+            [
+                ("ffmpeg", Fail, Some(DownloadFfmpeg)),
+                ("hardware_encoder", Warn, None),
+                ("whisper", Warn, Some(ConfigureWhisper)),
+                ("whisper_model", Warn, Some(ConfigureWhisper)),
+                ("screen_permission", ok, None),
+                ("camera_permission", Fail, Some(OpenPermissionSettings)),
+                ("microphone_permission", ok, None),
+                ("cache_directory", Fail, Some(ChooseCacheDirectory)),
+                (
+                    "recordings_directory",
+                    Warn,
+                    Some(ChooseRecordingsDirectory)
+                ),
+                ("work_directory", Fail, Some(ChooseWorkDirectory)),
+            ]
+        );
+        assert_eq!(
+            item(&report, "recordings_directory").detail,
+            "/data is low on space: 512 MB free"
+        );
+    }
+
+    #[test]
+    fn test_degraded_and_unsupported() {
+        let mut probes = healthy();
+        probes.ffmpeg.available = false;
+        probes.ffmpeg.error = Some("FFmpeg is missing required components: libx264".into());
+        probes.hardware_encoders = Ok(HardwareEncoders::default());
+        probes.permissions = Err("Recording not supported on this platform".into());
+
+        let report = assemble_report(&probes);
+        assert_eq!(report.status, CheckStatus::Warn);
+        assert_eq!(
+            item(&report, "ffmpeg").detail,
+            "FFmpeg 6.1.1: FFmpeg is missing required components: libx264"
+        );
+        assert_eq!(
+            item(&report, "recording_permissions").status,
+            CheckStatus::Warn
+        );
+        assert_eq!(report.items.len(), 8);
+    }
+}
@@ -1,6 +1,18 @@
+use crate::commands::library::media_clip;
+use crate::commands::locks::LockState;
 use crate::commands::media::AppState;
-use crate::models::timeline::{TimelineClip, Track, TrackType};
-use tauri::State;
+use crate::commands::project::{record_modification, ModificationScope};
+use crate::error::ClipForgeError;
+use crate::models::clip::MediaClip;
+use crate::models::compound::CompoundClip;
+use crate::models::project::Project;
+use crate::models::timeline::{
+    move_track, normalize_track_order, validate_clip_speed, validate_effects, ChannelMode,
+    ClipEffect, ClipSourceKind, FreezeEdge, FreezeExtension, Redaction, SlideError, TimelineClip,
+    Track, TrackDucking, TrackType,
+};
+use crate::models::transition::{Transition, TransitionKind};
+use tauri::{AppHandle, State};
 
 // TODO: This struct is used by update_timeline_clip which is not yet fully implemented
 #[allow(dead_code)]
@@ -10,6 +22,182 @@ pub struct TimelineClipUpdates {
     pub in_point: Option<f64>,
     pub out_point: Option<f64>,
     pub track_id: Option<String>,
+    pub audio_only: Option<bool>,
+    pub video_only: Option<bool>,
+    pub channel_mode: Option<ChannelMode>,
+    /// Playback rate, 0.1 to 10.0
+    pub speed: Option<f64>,
+}
+
+/// Track properties changed by `update_track`; None leaves a field as is
+#[derive(serde::Deserialize)]
+pub struct TrackUpdates {
+    /// Clamped to 0.0 ..= 2.0; 0.0 drops the track's audio from exports
+    pub volume: Option<f32>,
+    pub visible: Option<bool>,
+    pub locked: Option<bool>,
+    /// Header color "#RRGGBB"; an empty string clears it
+    pub color: Option<String>,
+}
+
+/// Timeline snapshot returned by `get_timeline_layout`
+#[derive(serde::Serialize)]
+pub struct TimelineLayout {
+    pub project_id: String,
+    /// Project revision the layout reflects; compare with `project_modified`
+    /// events to detect missed updates
+    pub revision: u64,
+    pub duration: f64,
+    pub tracks: Vec<Track>,
+    /// Compound clip whose contents `tracks` shows, if one is being edited
+    pub editing_compound: Option<String>,
+}
+
+impl TimelineLayout {
+    fn of(project: &Project) -> Self {
+        TimelineLayout {
+            project_id: project.id.clone(),
+            revision: project.revision,
+            duration: project.duration(),
+            tracks: project.tracks.clone(),
+            editing_compound: project
+                .editing_compound
+                .as_ref()
+                .map(|edit| edit.compound_id.clone()),
+        }
+    }
+}
+
+// Read-only introspection: `list_tracks`, `get_track` and `get_timeline_clip`
+// are the supported way for scripts to inspect the timeline without pulling
+// the whole project. Each response carries the project revision so callers
+// can tell when a cached answer went stale.
+
+/// A track without its clips, as listed by `list_tracks`
+#[derive(Debug, serde::Serialize)]
+pub struct TrackSummary {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub track_type: TrackType,
+    pub order: u32,
+    pub clip_count: usize,
+    /// End of the track's last clip
+    pub duration: f64,
+    pub visible: bool,
+    pub locked: bool,
+    pub volume: f32,
+    pub color: Option<String>,
+}
+
+impl TrackSummary {
+    fn of(track: &Track) -> Self {
+        TrackSummary {
+            id: track.id.clone(),
+            name: track.name.clone(),
+            track_type: track.track_type.clone(),
+            order: track.order,
+            clip_count: track.clip_count(),
+            duration: track.duration(),
+            visible: track.visible,
+            locked: track.locked,
+            volume: track.volume,
+            color: track.color.clone(),
+        }
+    }
+}
+
+/// Result of `list_tracks`
+#[derive(Debug, serde::Serialize)]
+pub struct TrackList {
+    pub revision: u64,
+    /// Top to bottom (by `order`)
+    pub tracks: Vec<TrackSummary>,
+}
+
+impl TrackList {
+    fn of(project: &Project) -> Self {
+        let mut tracks: Vec<TrackSummary> = project.tracks.iter().map(TrackSummary::of).collect();
+        tracks.sort_by_key(|t| t.order);
+        TrackList {
+            revision: project.revision,
+            tracks,
+        }
+    }
+}
+
+/// Result of `get_track`: the track with all its clips
+#[derive(Debug, serde::Serialize)]
+pub struct TrackDetail {
+    pub revision: u64,
+    pub track: Track,
+}
+
+impl TrackDetail {
+    fn of(project: &Project, track_id: &str) -> Result<Self, ClipForgeError> {
+        let track = project
+            .tracks
+            .iter()
+            .find(|t| t.id == track_id)
+            .ok_or_else(|| ClipForgeError::not_found("track", track_id))?;
+        Ok(TrackDetail {
+            revision: project.revision,
+            track: track.clone(),
+        })
+    }
+}
+
+/// Result of `get_timeline_clip`: the clip with its source resolved
+#[derive(Debug, serde::Serialize)]
+pub struct TimelineClipDetail {
+    pub revision: u64,
+    pub clip: TimelineClip,
+    /// Library clip the clip plays (media clips only; None if it's gone)
+    pub media_clip: Option<MediaClip>,
+    /// Compound clip the clip plays (compound clips only)
+    pub compound: Option<CompoundClip>,
+}
+
+impl TimelineClipDetail {
+    fn of(project: &Project, clip_id: &str) -> Result<Self, ClipForgeError> {
+        let clip = project
+            .tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .find(|c| c.id == clip_id)
+            .ok_or_else(|| ClipForgeError::not_found("timeline_clip", clip_id))?;
+        let (media_clip, compound) = match clip.source_kind {
+            ClipSourceKind::Media => (
+                project
+                    .media_library
+                    .iter()
+                    .find(|m| m.id == clip.media_clip_id)
+                    .cloned(),
+                None,
+            ),
+            ClipSourceKind::Compound => (
+                None,
+                project
+                    .compound_clips
+                    .iter()
+                    .find(|c| c.id == clip.media_clip_id)
+                    .cloned(),
+            ),
+        };
+        Ok(TimelineClipDetail {
+            revision: project.revision,
+            clip: clip.clone(),
+            media_clip,
+            compound,
+        })
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct CompoundClipResult {
+    pub compound: CompoundClip,
+    /// The clip that replaced the selection on the timeline
+    pub clip: TimelineClip,
 }
 
 #[derive(serde::Serialize)]
@@ -18,92 +206,136 @@ pub struct SplitResult {
     pub clip_after: TimelineClip,
 }
 
+/// Get the current timeline (tracks and clips) with the project revision
+#[tauri::command]
+pub async fn get_timeline_layout(
+    state: State<'_, AppState>,
+) -> Result<TimelineLayout, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+
+    Ok(TimelineLayout::of(project))
+}
+
 /// T048: Add clip to timeline
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn add_clip_to_timeline(
     media_clip_id: String,
     track_id: String,
     start_time: f64,
     in_point: f64,
     out_point: f64,
+    speed: Option<f64>,
     state: State<'_, AppState>,
-) -> Result<TimelineClip, String> {
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
     println!(
         "add_clip_to_timeline called: media_clip={}, track={}, start={}",
         media_clip_id, track_id, start_time
     );
 
     // Validate inputs
+    finite_time("start_time", start_time)?;
+    finite_time("in_point", in_point)?;
+    finite_time("out_point", out_point)?;
     if in_point >= out_point {
-        return Err("in_point must be less than out_point".to_string());
+        return Err(ClipForgeError::validation(
+            "in_point",
+            "in_point must be less than out_point",
+        ));
     }
     if start_time < 0.0 {
-        return Err("start_time must be non-negative".to_string());
+        return Err(ClipForgeError::validation(
+            "start_time",
+            "start_time must be non-negative",
+        ));
     }
+    let speed = speed.unwrap_or(1.0);
+    validate_clip_speed(speed).map_err(|e| ClipForgeError::validation("speed", e))?;
 
     // Check if media clip exists
-    let media_library = state
-        .media_library
-        .lock()
-        .expect("Failed to acquire lock on media library");
-    if !media_library.iter().any(|c| c.id == media_clip_id) {
-        return Err(format!("Media clip not found: {}", media_clip_id));
-    }
-    drop(media_library);
+    media_clip(&state, &media_clip_id)?;
 
     // Create timeline clip
-    let timeline_clip = TimelineClip::new(
+    let mut timeline_clip = TimelineClip::new(
         media_clip_id,
         track_id.clone(),
         start_time,
         in_point,
         out_point,
     );
-
-    println!("Created timeline clip: {:?}", timeline_clip);
+    timeline_clip.speed = speed;
 
     // Store in project state
-    let mut project_lock = state
-        .project
-        .lock()
-        .expect("Failed to acquire lock on project");
+    let mut project_lock = state.project.lock_state()?;
     if let Some(ref mut project) = *project_lock {
-        // Find the track and add the clip
-        let track_found = project
-            .tracks
-            .iter_mut()
-            .find(|t| t.id == track_id)
-            .map(|track| {
-                track.clips.push(timeline_clip.clone());
-                track.clips.len()
-            });
-
-        if let Some(clip_count) = track_found {
-            project.mark_modified();
-            println!("Added clip to track. Track now has {} clips", clip_count);
-        } else {
-            return Err(format!("Track not found: {}", track_id));
-        }
+        add_to_track(project, timeline_clip.clone())?;
+        record_modification(
+            &app_handle,
+            project,
+            ModificationScope::Timeline,
+            vec![timeline_clip.id.clone(), track_id],
+        );
     } else {
-        return Err("No project loaded".to_string());
+        return Err(ClipForgeError::no_project());
     }
 
     Ok(timeline_clip)
 }
 
+/// Reject NaN and infinite times; they can't be ordered on a track or cut
+/// by FFmpeg, and a NaN would be saved as `null`
+pub(crate) fn finite_time(field: &str, value: f64) -> Result<(), ClipForgeError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(ClipForgeError::validation(
+            field,
+            format!("{} must be a finite number of seconds", field),
+        ))
+    }
+}
+
+/// Add a clip to the track named by its `track_id`
+pub(crate) fn add_to_track(
+    project: &mut Project,
+    timeline_clip: TimelineClip,
+) -> Result<(), ClipForgeError> {
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.id == timeline_clip.track_id)
+        .ok_or_else(|| ClipForgeError::not_found("track", timeline_clip.track_id.as_str()))?;
+    track.clips.push(timeline_clip);
+    Ok(())
+}
+
 /// T049: Update timeline clip properties
 #[tauri::command]
 pub async fn update_timeline_clip(
     clip_id: String,
     updates: TimelineClipUpdates,
     state: State<'_, AppState>,
-) -> Result<TimelineClip, String> {
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
     println!("update_timeline_clip called: clip={}", clip_id);
+    for (field, value) in [
+        ("start_time", updates.start_time),
+        ("in_point", updates.in_point),
+        ("out_point", updates.out_point),
+    ] {
+        if let Some(value) = value {
+            finite_time(field, value)?;
+        }
+    }
+    if let Some(speed) = updates.speed {
+        validate_clip_speed(speed).map_err(|e| ClipForgeError::validation("speed", e))?;
+    }
 
-    let mut project_lock = state
-        .project
-        .lock()
-        .expect("Failed to acquire lock on project");
+    let mut project_lock = state.project.lock_state()?;
 
     if let Some(ref mut project) = *project_lock {
         // Find the clip across all tracks
@@ -111,11 +343,21 @@ pub async fn update_timeline_clip(
 
         for track in &mut project.tracks {
             if let Some(clip) = track.clips.iter_mut().find(|c| c.id == clip_id) {
+                // Checked before anything changes
+                let audio_only = updates.audio_only.unwrap_or(clip.audio_only);
+                let video_only = updates.video_only.unwrap_or(clip.video_only);
+                if audio_only && video_only {
+                    return Err(ClipForgeError::validation(
+                        "audio_only",
+                        "A clip can't be both audio-only and video-only",
+                    ));
+                }
+
                 // Apply updates
+                let untrimmed = clip.clone();
                 if let Some(start_time) = updates.start_time {
                     if start_time >= 0.0 {
                         clip.start_time = start_time;
-                        println!("✓ Updated clip start_time to {}", start_time);
                     } else {
                         println!("✗ Rejected start_time update: {} (negative)", start_time);
                     }
@@ -123,7 +365,6 @@ pub async fn update_timeline_clip(
                 if let Some(in_point) = updates.in_point {
                     if in_point >= 0.0 && in_point < clip.out_point {
                         clip.in_point = in_point;
-                        println!("✓ Updated clip in_point to {}", in_point);
                     } else {
                         println!(
                             "✗ Rejected in_point update: {} (must be >= 0 and < out_point {})",
@@ -134,7 +375,6 @@ pub async fn update_timeline_clip(
                 if let Some(out_point) = updates.out_point {
                     if out_point > clip.in_point {
                         clip.out_point = out_point;
-                        println!("✓ Updated clip out_point to {}", out_point);
                     } else {
                         println!(
                             "✗ Rejected out_point update: {} (must be > in_point {})",
@@ -142,40 +382,385 @@ pub async fn update_timeline_clip(
                         );
                     }
                 }
+                clip.retarget_comments(&untrimmed);
+                if let Some(speed) = updates.speed {
+                    clip.set_speed(speed)
+                        .map_err(|e| ClipForgeError::validation("speed", e))?;
+                }
                 if let Some(track_id) = updates.track_id {
                     clip.track_id = track_id;
-                    println!("✓ Updated clip track_id");
+                }
+                clip.audio_only = audio_only;
+                clip.video_only = video_only;
+                if let Some(channel_mode) = updates.channel_mode {
+                    clip.channel_mode = channel_mode;
                 }
 
                 updated_clip = Some(clip.clone());
+                // Moved or trimmed away from a neighbor it transitioned with
+                track.prune_transitions();
                 break;
             }
         }
 
         if let Some(clip) = updated_clip {
-            project.mark_modified();
+            record_modification(
+                &app_handle,
+                project,
+                ModificationScope::Timeline,
+                vec![clip.id.clone()],
+            );
             return Ok(clip);
         }
 
-        Err(format!("Clip not found: {}", clip_id))
+        Err(ClipForgeError::not_found("timeline_clip", clip_id))
     } else {
-        Err("No project loaded".to_string())
+        Err(ClipForgeError::no_project())
     }
 }
 
 /// T050: Split timeline clip at specified time
+///
+/// Both halves keep the clip's effects and audio/video flags, and its
+/// transitions in and out.
 #[tauri::command]
 pub async fn split_timeline_clip(
     clip_id: String,
     split_time: f64,
-    _state: State<'_, AppState>,
-) -> Result<SplitResult, String> {
-    // TODO: Implement split logic with project state
-    // For now, return error
-    Err(format!(
-        "Not fully implemented yet: {} at {}",
-        clip_id, split_time
-    ))
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SplitResult, ClipForgeError> {
+    finite_time("split_time", split_time)?;
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+
+    let (track, index) = project
+        .tracks
+        .iter_mut()
+        .find_map(|track| {
+            let index = track.clips.iter().position(|c| c.id == clip_id)?;
+            Some((track, index))
+        })
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &clip_id))?;
+    let (clip_before, clip_after) = track.clips[index].split_at(split_time).ok_or_else(|| {
+        ClipForgeError::validation("split_time", "Split time must be inside the clip")
+    })?;
+    track.split_transitions(&clip_id, &clip_before.id, &clip_after.id);
+    track
+        .clips
+        .splice(index..=index, [clip_before.clone(), clip_after.clone()]);
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![clip_id, clip_before.id.clone(), clip_after.id.clone()],
+    );
+    Ok(SplitResult {
+        clip_before,
+        clip_after,
+    })
+}
+
+/// Result of `slide_clip`: the moved clip and the neighbors trimmed for it
+#[derive(serde::Serialize)]
+pub struct SlideResult {
+    pub previous: TimelineClip,
+    pub clip: TimelineClip,
+    pub next: TimelineClip,
+}
+
+/// Slide a clip `delta` seconds along its track (negative = earlier)
+///
+/// The clip's content stays the same; the previous clip's out point and the
+/// next clip's in point absorb the move, so no other clip changes position.
+/// Fails naming the limiting neighbor when its source has too little room.
+#[tauri::command]
+pub async fn slide_clip(
+    clip_id: String,
+    delta: f64,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SlideResult, ClipForgeError> {
+    if !delta.is_finite() || delta == 0.0 {
+        return Err(ClipForgeError::validation(
+            "delta",
+            "Slide distance must be a non-zero number of seconds",
+        ));
+    }
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+
+    let Project {
+        tracks,
+        media_library,
+        compound_clips,
+        ..
+    } = project;
+    let track = tracks
+        .iter_mut()
+        .find(|t| t.clips.iter().any(|c| c.id == clip_id))
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &clip_id))?;
+    let source_duration = |clip: &TimelineClip| match clip.source_kind {
+        ClipSourceKind::Media => media_library
+            .iter()
+            .find(|m| m.id == clip.media_clip_id)
+            .map(|m| m.duration),
+        ClipSourceKind::Compound => compound_clips
+            .iter()
+            .find(|c| c.id == clip.media_clip_id)
+            .map(|c| c.duration()),
+    };
+    let [previous, clip, next] =
+        track
+            .slide_clip(&clip_id, delta, source_duration)
+            .map_err(|err| match err {
+                SlideError::ClipNotFound => ClipForgeError::not_found("timeline_clip", &clip_id),
+                SlideError::MissingNeighbor { side } => ClipForgeError::validation(
+                    "clip_id",
+                    format!("Sliding needs a {} clip on the track", side),
+                ),
+                SlideError::HandleLimit {
+                    neighbor_id,
+                    max_delta,
+                } => ClipForgeError::validation(
+                    "delta",
+                    format!(
+                        "Clip {} limits the slide to {:+.3}s",
+                        neighbor_id, max_delta
+                    ),
+                ),
+            })?;
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![previous.id.clone(), clip.id.clone(), next.id.clone()],
+    );
+    Ok(SlideResult {
+        previous,
+        clip,
+        next,
+    })
+}
+
+/// Extend a timeline clip past its media by holding its first or last frame
+///
+/// The held frame is silent and counts toward the clip's duration. It
+/// replaces any earlier extension; `extra_seconds` of 0 removes it.
+#[tauri::command]
+pub async fn extend_with_freeze(
+    clip_id: String,
+    extra_seconds: f64,
+    edge: FreezeEdge,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
+    let freeze = (extra_seconds != 0.0).then_some(FreezeExtension {
+        edge,
+        duration: extra_seconds,
+    });
+    if let Some(freeze) = &freeze {
+        freeze
+            .validate()
+            .map_err(|message| ClipForgeError::validation("extra_seconds", message))?;
+    }
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let clip = project
+        .tracks
+        .iter_mut()
+        .flat_map(|track| track.clips.iter_mut())
+        .find(|c| c.id == clip_id)
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &clip_id))?;
+    if clip.source_kind == ClipSourceKind::Compound && freeze.is_some() {
+        return Err(ClipForgeError::unsupported(
+            "Compound clips can't hold a frame; extend a clip inside the compound instead",
+        ));
+    }
+    let unextended = clip.clone();
+    clip.freeze_extension = freeze;
+    clip.retarget_comments(&unextended);
+
+    let clip = clip.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![clip.id.clone()],
+    );
+    Ok(clip)
+}
+
+/// Replace a timeline clip's effects
+#[tauri::command]
+pub async fn set_clip_effects(
+    clip_id: String,
+    effects: Vec<ClipEffect>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
+    validate_effects(&effects).map_err(|message| ClipForgeError::validation("effects", message))?;
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let clip = project
+        .tracks
+        .iter_mut()
+        .flat_map(|track| track.clips.iter_mut())
+        .find(|c| c.id == clip_id)
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &clip_id))?;
+    clip.effects = effects;
+
+    let clip = clip.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![clip.id.clone()],
+    );
+    Ok(clip)
+}
+
+/// Replace a timeline clip's redacted regions
+///
+/// Regions are checked against the clip's media resolution.
+#[tauri::command]
+pub async fn set_clip_redactions(
+    clip_id: String,
+    redactions: Vec<Redaction>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineClip, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let clip = project
+        .tracks
+        .iter_mut()
+        .flat_map(|track| track.clips.iter_mut())
+        .find(|c| c.id == clip_id)
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &clip_id))?;
+    if clip.source_kind == ClipSourceKind::Compound {
+        return Err(ClipForgeError::unsupported(
+            "Redact the clips inside a compound clip instead",
+        ));
+    }
+    let media = project
+        .media_library
+        .iter()
+        .find(|m| m.id == clip.media_clip_id)
+        .ok_or_else(|| ClipForgeError::not_found("media_clip", &clip.media_clip_id))?;
+    let (width, height) = (media.width.max(0) as u32, media.height.max(0) as u32);
+    for redaction in &redactions {
+        redaction
+            .validate(width, height)
+            .map_err(|message| ClipForgeError::validation("redactions", message))?;
+    }
+    clip.redactions = redactions;
+
+    let clip = clip.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![clip.id.clone()],
+    );
+    Ok(clip)
+}
+
+/// Group timeline clips into a compound clip
+///
+/// The clips move into a sub-timeline and a single clip referencing it takes
+/// their place; compound clips can't themselves be grouped.
+#[tauri::command]
+pub async fn create_compound_clip(
+    clip_ids: Vec<String>,
+    name: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<CompoundClipResult, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let (compound, clip) = project
+        .create_compound_clip(&clip_ids, name)
+        .map_err(|message| ClipForgeError::validation("clip_ids", message))?;
+
+    let mut entity_ids = clip_ids;
+    entity_ids.extend([compound.id.clone(), clip.id.clone()]);
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        entity_ids,
+    );
+    Ok(CompoundClipResult { compound, clip })
+}
+
+/// Show a compound clip's contents on the timeline
+///
+/// The timeline commands then edit the compound until `exit_compound`.
+#[tauri::command]
+pub async fn enter_compound(
+    compound_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineLayout, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    if project.compound_clip(&compound_id).is_none() {
+        return Err(ClipForgeError::not_found("compound_clip", compound_id));
+    }
+    project
+        .enter_compound(&compound_id)
+        .map_err(|message| ClipForgeError::validation("compound_id", message))?;
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![compound_id],
+    );
+    Ok(TimelineLayout::of(project))
+}
+
+/// Store the compound clip being edited and show the parent timeline again
+#[tauri::command]
+pub async fn exit_compound(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<TimelineLayout, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let compound_id = project
+        .exit_compound()
+        .map_err(|message| ClipForgeError::validation("compound_id", message))?;
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![compound_id],
+    );
+    Ok(TimelineLayout::of(project))
 }
 
 /// T051: Delete timeline clip
@@ -183,10 +768,13 @@ pub async fn split_timeline_clip(
 pub async fn delete_timeline_clip(
     clip_id: String,
     _state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), ClipForgeError> {
     // TODO: Implement delete logic with project state
     // For now, return error
-    Err(format!("Not fully implemented yet: {}", clip_id))
+    Err(ClipForgeError::unsupported(format!(
+        "Not fully implemented yet: {}",
+        clip_id
+    )))
 }
 
 /// T052: Create new track
@@ -195,31 +783,36 @@ pub async fn create_track(
     name: String,
     track_type: String,
     state: State<'_, AppState>,
-) -> Result<Track, String> {
+    app_handle: AppHandle,
+) -> Result<Track, ClipForgeError> {
     println!("create_track called: name={}, type={}", name, track_type);
 
     // Parse track type
     let parsed_type = match track_type.to_lowercase().as_str() {
         "main" => TrackType::Main,
         "overlay" => TrackType::Overlay,
-        _ => return Err(format!("Invalid track type: {}", track_type)),
+        _ => {
+            return Err(ClipForgeError::validation(
+                "track_type",
+                format!("Invalid track type: {}", track_type),
+            ))
+        }
     };
 
     // Create track
     let mut track = Track::new(name, parsed_type);
 
     // Store in project state
-    let mut project_lock = state
-        .project
-        .lock()
-        .expect("Failed to acquire lock on project");
+    let mut project_lock = state.project.lock_state()?;
     if let Some(ref mut project) = *project_lock {
         track.order = project.tracks.len() as u32;
         project.tracks.push(track.clone());
-        project.mark_modified();
-        println!(
-            "Added track. Project now has {} tracks",
-            project.tracks.len()
+        normalize_track_order(&mut project.tracks);
+        record_modification(
+            &app_handle,
+            project,
+            ModificationScope::Timeline,
+            vec![track.id.clone()],
         );
     } else {
         // Create a new project if none exists
@@ -233,3 +826,454 @@ pub async fn create_track(
 
     Ok(track)
 }
+
+/// Tracks of the timeline shown, top to bottom (by `order`), without clips
+#[tauri::command]
+pub async fn list_tracks(state: State<'_, AppState>) -> Result<TrackList, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    Ok(TrackList::of(project))
+}
+
+/// One track of the timeline shown, with all its clips
+#[tauri::command]
+pub async fn get_track(
+    track_id: String,
+    state: State<'_, AppState>,
+) -> Result<TrackDetail, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    TrackDetail::of(project, &track_id)
+}
+
+/// One clip of the timeline shown, with the media or compound clip it plays
+#[tauri::command]
+pub async fn get_timeline_clip(
+    clip_id: String,
+    state: State<'_, AppState>,
+) -> Result<TimelineClipDetail, ClipForgeError> {
+    let project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_ref()
+        .ok_or_else(ClipForgeError::no_project)?;
+    TimelineClipDetail::of(project, &clip_id)
+}
+
+/// Rename a track (1-64 characters, surrounding whitespace dropped)
+#[tauri::command]
+pub async fn rename_track(
+    track_id: String,
+    name: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Track, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| ClipForgeError::not_found("track", &track_id))?;
+    track
+        .rename(&name)
+        .map_err(|message| ClipForgeError::validation("name", message))?;
+
+    let track = track.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![track.id.clone()],
+    );
+    Ok(track)
+}
+
+/// Move a track to position `order` (0 = top); the other tracks shift so the
+/// order stays 0..n. Returns the tracks by their new order.
+#[tauri::command]
+pub async fn set_track_order(
+    track_id: String,
+    order: u32,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<Track>, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    move_track(&mut project.tracks, &track_id, order)
+        .map_err(|_| ClipForgeError::not_found("track", &track_id))?;
+
+    let mut tracks = project.tracks.clone();
+    tracks.sort_by_key(|t| t.order);
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        tracks.iter().map(|t| t.id.clone()).collect(),
+    );
+    Ok(tracks)
+}
+
+/// Update a track's mix volume, visibility, lock or color
+#[tauri::command]
+pub async fn update_track(
+    track_id: String,
+    updates: TrackUpdates,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Track, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| ClipForgeError::not_found("track", &track_id))?;
+
+    if let Some(volume) = updates.volume {
+        track
+            .set_volume(volume)
+            .map_err(|message| ClipForgeError::validation("volume", message))?;
+    }
+    if let Some(visible) = updates.visible {
+        track.visible = visible;
+    }
+    if let Some(locked) = updates.locked {
+        track.locked = locked;
+    }
+    if let Some(color) = updates.color {
+        track
+            .set_color(Some(color).filter(|c| !c.is_empty()))
+            .map_err(|message| ClipForgeError::validation("color", message))?;
+    }
+
+    let track = track.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![track.id.clone()],
+    );
+    Ok(track)
+}
+
+/// Whether any clip on `track` plays audio
+fn track_has_audio(project: &Project, track: &Track) -> bool {
+    track.clips.iter().filter(|c| c.uses_audio()).any(|clip| {
+        match clip.source_kind {
+            ClipSourceKind::Media => project
+                .media_library
+                .iter()
+                .any(|m| m.id == clip.media_clip_id && m.has_audio),
+            // Checked when the compound is flattened for export
+            ClipSourceKind::Compound => true,
+        }
+    })
+}
+
+/// Duck a track (music) by `amount_db` whenever the `against_track_ids`
+/// tracks (narration) sound; replaces earlier ducking of the track
+///
+/// Both the track and the tracks it ducks under must have audio.
+#[tauri::command]
+pub async fn enable_track_ducking(
+    music_track_id: String,
+    against_track_ids: Vec<String>,
+    amount_db: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Track, ClipForgeError> {
+    let ducking = TrackDucking {
+        against_track_ids,
+        amount_db,
+        attack_ms,
+        release_ms,
+    };
+    ducking
+        .validate()
+        .map_err(|message| ClipForgeError::validation("ducking", message))?;
+
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let find_track = |id: &str| {
+        project
+            .tracks
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| ClipForgeError::not_found("track", id))
+    };
+    let music = find_track(&music_track_id)?;
+    if !track_has_audio(project, music) {
+        return Err(ClipForgeError::validation(
+            "music_track_id",
+            format!("Track '{}' has no audio to duck", music.name),
+        ));
+    }
+    for id in &ducking.against_track_ids {
+        if *id == music_track_id {
+            return Err(ClipForgeError::validation(
+                "against_track_ids",
+                "A track can't duck under itself",
+            ));
+        }
+        let against = find_track(id)?;
+        if !track_has_audio(project, against) {
+            return Err(ClipForgeError::validation(
+                "against_track_ids",
+                format!("Track '{}' has no audio to duck under", against.name),
+            ));
+        }
+    }
+
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.id == music_track_id)
+        .expect("track found above");
+    track.ducking = Some(ducking);
+    let track = track.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![track.id.clone()],
+    );
+    Ok(track)
+}
+
+/// Stop ducking a track
+#[tauri::command]
+pub async fn disable_track_ducking(
+    track_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Track, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| ClipForgeError::not_found("track", &track_id))?;
+    track.ducking = None;
+
+    let track = track.clone();
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![track.id.clone()],
+    );
+    Ok(track)
+}
+
+/// Add a transition (a crossfade by default) where `from_clip_id` ends and
+/// `to_clip_id` starts, replacing any transition already there
+///
+/// Both clips must be on one track and meet, each with `duration` seconds
+/// of footage for the overlap (see `Track::check_transition`).
+#[tauri::command]
+pub async fn add_transition(
+    from_clip_id: String,
+    to_clip_id: String,
+    duration: f64,
+    kind: Option<TransitionKind>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Transition, ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let track = project
+        .tracks
+        .iter_mut()
+        .find(|t| t.clips.iter().any(|c| c.id == from_clip_id))
+        .ok_or_else(|| ClipForgeError::not_found("timeline_clip", &from_clip_id))?;
+
+    let mut transition = Transition::new(
+        kind.unwrap_or_default(),
+        duration,
+        from_clip_id.clone(),
+        to_clip_id.clone(),
+    );
+    if let Some(existing) = track
+        .transitions
+        .iter()
+        .find(|t| t.from_clip_id == from_clip_id && t.to_clip_id == to_clip_id)
+    {
+        transition.id = existing.id.clone();
+    }
+    track
+        .check_transition(&transition)
+        .map_err(|message| ClipForgeError::validation("transition", message))?;
+    track.transitions.retain(|t| t.id != transition.id);
+    track.transitions.push(transition.clone());
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![from_clip_id, to_clip_id],
+    );
+    Ok(transition)
+}
+
+/// Remove a transition; its clips meet with a cut again
+#[tauri::command]
+pub async fn remove_transition(
+    transition_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), ClipForgeError> {
+    let mut project_lock = state.project.lock_state()?;
+    let project = project_lock
+        .as_mut()
+        .ok_or_else(ClipForgeError::no_project)?;
+    let (track, index) = project
+        .tracks
+        .iter_mut()
+        .find_map(|track| {
+            let index = track
+                .transitions
+                .iter()
+                .position(|t| t.id == transition_id)?;
+            Some((track, index))
+        })
+        .ok_or_else(|| ClipForgeError::not_found("transition", &transition_id))?;
+    let transition = track.transitions.remove(index);
+
+    record_modification(
+        &app_handle,
+        project,
+        ModificationScope::Timeline,
+        vec![transition.from_clip_id, transition.to_clip_id],
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_finite_times_rejected() {
+        assert!(finite_time("start_time", 2.5).is_ok());
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                finite_time("start_time", value),
+                Err(ClipForgeError::Validation { field, .. }) if field == "start_time"
+            ));
+        }
+    }
+
+    fn fixture() -> (Project, MediaClip) {
+        let mut project = Project::new("Introspection".into());
+        let media = MediaClip::new(
+            "/tmp/a.mp4".into(),
+            10.0,
+            1920,
+            1080,
+            30.0,
+            "h264".into(),
+            1000,
+        );
+        project.media_library.push(media.clone());
+
+        let mut overlay = Track::new("Overlay".into(), TrackType::Overlay);
+        overlay.order = 0;
+        overlay.locked = true;
+        project.tracks.iter_mut().for_each(|t| t.order += 1);
+        project.tracks.push(overlay);
+
+        let main_id = project.tracks[0].id.clone();
+        for start in [0.0, 4.0] {
+            project.tracks[0].clips.push(TimelineClip::new(
+                media.id.clone(),
+                main_id.clone(),
+                start,
+                1.0,
+                4.0,
+            ));
+        }
+        project.revision = 7;
+        (project, media)
+    }
+
+    #[test]
+    fn test_track_list_summarizes_tracks_in_order() {
+        let (project, _) = fixture();
+        let list = TrackList::of(&project);
+
+        assert_eq!(list.revision, 7);
+        assert_eq!(list.tracks.len(), project.tracks.len());
+        assert_eq!(list.tracks[0].name, "Overlay");
+        assert!(list.tracks[0].locked);
+        assert_eq!(list.tracks[0].clip_count, 0);
+
+        let main = list
+            .tracks
+            .iter()
+            .find(|t| t.id == project.tracks[0].id)
+            .unwrap();
+        assert_eq!(main.clip_count, 2);
+        assert!((main.duration - 7.0).abs() < 1e-9);
+
+        let json = serde_json::to_value(&list).unwrap();
+        assert_eq!(json["tracks"][0]["type"], "overlay");
+        assert!(json["tracks"][0].get("clips").is_none());
+    }
+
+    #[test]
+    fn test_track_detail_includes_clips() {
+        let (project, _) = fixture();
+        let detail = TrackDetail::of(&project, &project.tracks[0].id).unwrap();
+
+        assert_eq!(detail.revision, 7);
+        assert_eq!(detail.track.clips.len(), 2);
+    }
+
+    #[test]
+    fn test_timeline_clip_detail_resolves_media() {
+        let (project, media) = fixture();
+        let clip_id = project.tracks[0].clips[1].id.clone();
+        let detail = TimelineClipDetail::of(&project, &clip_id).unwrap();
+
+        assert_eq!(detail.revision, 7);
+        assert_eq!(detail.clip.start_time, 4.0);
+        assert_eq!(detail.media_clip.unwrap().id, media.id);
+        assert!(detail.compound.is_none());
+    }
+
+    #[test]
+    fn test_unknown_ids_are_not_found() {
+        let (project, _) = fixture();
+
+        match TrackDetail::of(&project, "nope") {
+            Err(ClipForgeError::NotFound { kind, id }) => {
+                assert_eq!(kind, "track");
+                assert_eq!(id, "nope");
+            }
+            other => panic!("expected NotFound, got {:?}", other.map(|d| d.revision)),
+        }
+        match TimelineClipDetail::of(&project, "nope") {
+            Err(ClipForgeError::NotFound { kind, .. }) => assert_eq!(kind, "timeline_clip"),
+            other => panic!("expected NotFound, got {:?}", other.map(|d| d.revision)),
+        }
+    }
+}
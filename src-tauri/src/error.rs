@@ -0,0 +1,472 @@
+// Errors returned to the frontend
+// Commands fail with a `ClipForgeError`, serialized as an object with a
+// stable `code` the UI can branch on, a human-readable `message` it can show
+// as-is, and the variant's fields. Events that report failures embed the same
+// object. Internal helpers may keep returning `String`; the `From` impls
+// below turn their errors (and those of the storage and FFmpeg layers) into
+// the closest variant at the command boundary.
+
+use crate::commands::project::CloseProjectError;
+use crate::ffmpeg::errors::{FfmpegError, FfmpegErrorKind};
+use crate::ffmpeg::preflight::PreflightReport;
+use crate::platform::process::ProcessError;
+use crate::storage::media_download::DownloadError;
+use crate::storage::project_archive::ArchiveError;
+use crate::storage::project_file::ProjectFileError;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+
+/// A command or background job failure
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipForgeError {
+    /// No entity of `kind` (e.g. "media_clip", "track") with this id
+    NotFound {
+        kind: &'static str,
+        id: String,
+    },
+    /// An argument or the state it applies to was rejected
+    Validation {
+        field: String,
+        message: String,
+    },
+    /// FFmpeg ran and failed
+    FfmpegFailure {
+        kind: FfmpegErrorKind,
+        message: String,
+        excerpt: String,
+    },
+    Io {
+        message: String,
+    },
+    /// An external tool (FFmpeg, Whisper) is not installed or not found
+    MissingDependency {
+        which: String,
+        message: String,
+    },
+    /// Not available on this platform or for this input
+    Unsupported {
+        message: String,
+    },
+    /// Conflicts with an operation already in progress
+    Busy {
+        message: String,
+    },
+    /// A download failed: the server refused it (`status`) or the
+    /// connection timed out or dropped
+    Network {
+        message: String,
+        status: Option<u16>,
+    },
+    /// An export's preflight found problems; the report lists every one
+    PreflightFailed {
+        message: String,
+        // Boxed so every other variant doesn't carry the report's size
+        report: Box<PreflightReport>,
+    },
+    Cancelled,
+    /// Anything not classified yet
+    Other {
+        message: String,
+    },
+}
+
+impl ClipForgeError {
+    pub fn not_found(kind: &'static str, id: impl Into<String>) -> Self {
+        ClipForgeError::NotFound {
+            kind,
+            id: id.into(),
+        }
+    }
+
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        ClipForgeError::Validation {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Commands that need an open project
+    pub fn no_project() -> Self {
+        Self::validation("project", "No project loaded")
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        ClipForgeError::Unsupported {
+            message: message.into(),
+        }
+    }
+
+    pub fn busy(message: impl Into<String>) -> Self {
+        ClipForgeError::Busy {
+            message: message.into(),
+        }
+    }
+
+    /// Stable identifier of the variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            ClipForgeError::NotFound { .. } => "not_found",
+            ClipForgeError::Validation { .. } => "validation",
+            ClipForgeError::FfmpegFailure { .. } => "ffmpeg_failure",
+            ClipForgeError::Io { .. } => "io",
+            ClipForgeError::MissingDependency { .. } => "missing_dependency",
+            ClipForgeError::Unsupported { .. } => "unsupported",
+            ClipForgeError::Busy { .. } => "busy",
+            ClipForgeError::Network { .. } => "network",
+            ClipForgeError::PreflightFailed { .. } => "preflight_failed",
+            ClipForgeError::Cancelled => "cancelled",
+            ClipForgeError::Other { .. } => "other",
+        }
+    }
+}
+
+impl fmt::Display for ClipForgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipForgeError::NotFound { kind, id } => {
+                // "media_clip" -> "Media clip not found: <id>"
+                let label = kind.replace('_', " ");
+                let mut chars = label.chars();
+                if let Some(first) = chars.next() {
+                    write!(f, "{}{}", first.to_ascii_uppercase(), chars.as_str())?;
+                }
+                write!(f, " not found: {}", id)
+            }
+            ClipForgeError::Validation { message, .. }
+            | ClipForgeError::FfmpegFailure { message, .. }
+            | ClipForgeError::Io { message }
+            | ClipForgeError::MissingDependency { message, .. }
+            | ClipForgeError::Unsupported { message }
+            | ClipForgeError::Busy { message }
+            | ClipForgeError::Network { message, .. }
+            | ClipForgeError::PreflightFailed { message, .. }
+            | ClipForgeError::Other { message } => f.write_str(message),
+            ClipForgeError::Cancelled => f.write_str("Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ClipForgeError {}
+
+/// `{ "code": ..., "message": ..., <variant fields> }`
+impl Serialize for ClipForgeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            ClipForgeError::NotFound { kind, id } => {
+                map.serialize_entry("kind", kind)?;
+                map.serialize_entry("id", id)?;
+            }
+            ClipForgeError::Validation { field, .. } => {
+                map.serialize_entry("field", field)?;
+            }
+            ClipForgeError::FfmpegFailure { kind, excerpt, .. } => {
+                map.serialize_entry("kind", kind)?;
+                map.serialize_entry("excerpt", excerpt)?;
+            }
+            ClipForgeError::MissingDependency { which, .. } => {
+                map.serialize_entry("which", which)?;
+            }
+            ClipForgeError::Network { status, .. } => {
+                map.serialize_entry("status", status)?;
+            }
+            ClipForgeError::PreflightFailed { report, .. } => {
+                map.serialize_entry("report", report)?;
+            }
+            ClipForgeError::Io { .. }
+            | ClipForgeError::Unsupported { .. }
+            | ClipForgeError::Busy { .. }
+            | ClipForgeError::Cancelled
+            | ClipForgeError::Other { .. } => {}
+        }
+        map.end()
+    }
+}
+
+/// Lets `?` keep working in helpers that still return `String`
+impl From<ClipForgeError> for String {
+    fn from(error: ClipForgeError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<String> for ClipForgeError {
+    fn from(message: String) -> Self {
+        ClipForgeError::Other { message }
+    }
+}
+
+impl From<&str> for ClipForgeError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl From<std::io::Error> for ClipForgeError {
+    fn from(error: std::io::Error) -> Self {
+        ClipForgeError::Io {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<FfmpegError> for ClipForgeError {
+    fn from(error: FfmpegError) -> Self {
+        ClipForgeError::FfmpegFailure {
+            kind: error.kind,
+            message: error.message,
+            excerpt: error.excerpt,
+        }
+    }
+}
+
+impl From<ProcessError> for ClipForgeError {
+    fn from(error: ProcessError) -> Self {
+        match &error {
+            ProcessError::Spawn { program, .. } => ClipForgeError::MissingDependency {
+                which: program.clone(),
+                message: error.to_string(),
+            },
+            ProcessError::Timeout { .. } => FfmpegError::from(error).into(),
+            ProcessError::Io { .. } => ClipForgeError::Io {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<ProjectFileError> for ClipForgeError {
+    fn from(error: ProjectFileError) -> Self {
+        match error {
+            ProjectFileError::NotFound(path) => {
+                ClipForgeError::not_found("project_file", path.display().to_string())
+            }
+            ProjectFileError::InvalidProject(..) => {
+                ClipForgeError::validation("project", error.to_string())
+            }
+            ProjectFileError::Serialize(_) => error.to_string().into(),
+            ProjectFileError::PermissionDenied(_)
+            | ProjectFileError::DiskFull(_)
+            | ProjectFileError::Io(..) => ClipForgeError::Io {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<DownloadError> for ClipForgeError {
+    fn from(error: DownloadError) -> Self {
+        match &error {
+            DownloadError::NotFound(url) => ClipForgeError::not_found("remote_file", url),
+            DownloadError::InvalidUrl(_)
+            | DownloadError::TooLarge { .. }
+            | DownloadError::UnsupportedType(_) => {
+                ClipForgeError::validation("url", error.to_string())
+            }
+            DownloadError::DiskFull(_) => ClipForgeError::Io {
+                message: error.to_string(),
+            },
+            DownloadError::InProgress(_) => ClipForgeError::busy(error.to_string()),
+            DownloadError::HttpStatus(status) => ClipForgeError::Network {
+                message: error.to_string(),
+                status: Some(*status),
+            },
+            DownloadError::Timeout | DownloadError::Failed(_) => ClipForgeError::Network {
+                message: error.to_string(),
+                status: None,
+            },
+        }
+    }
+}
+
+impl From<ArchiveError> for ClipForgeError {
+    fn from(error: ArchiveError) -> Self {
+        match &error {
+            ArchiveError::Io(_) => ClipForgeError::Io {
+                message: error.to_string(),
+            },
+            ArchiveError::MissingMedia(path) => ClipForgeError::not_found("media_file", path),
+            ArchiveError::UnsupportedVersion(_) => ClipForgeError::unsupported(error.to_string()),
+            ArchiveError::ProjectBusy(_) => ClipForgeError::busy(error.to_string()),
+            ArchiveError::NotAnArchive(_)
+            | ArchiveError::MissingManifest
+            | ArchiveError::MissingEntry(_)
+            | ArchiveError::HashMismatch(_)
+            | ArchiveError::InvalidProject(_) => {
+                ClipForgeError::validation("archive", error.to_string())
+            }
+        }
+    }
+}
+
+impl From<CloseProjectError> for ClipForgeError {
+    fn from(error: CloseProjectError) -> Self {
+        match error {
+            CloseProjectError::RecordingActive(message) => ClipForgeError::Busy { message },
+            CloseProjectError::SaveFailed(message) => ClipForgeError::Io { message },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::export::FileIssue;
+    use crate::ffmpeg::preflight::{PreflightCheck, PreflightIssue};
+    use serde_json::json;
+
+    fn snapshot(error: ClipForgeError) -> serde_json::Value {
+        serde_json::to_value(error).unwrap()
+    }
+
+    #[test]
+    fn test_serialization_snapshots() {
+        assert_eq!(
+            snapshot(ClipForgeError::not_found("media_clip", "abc")),
+            json!({
+                "code": "not_found",
+                "message": "Media clip not found: abc",
+                "kind": "media_clip",
+                "id": "abc"
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::validation(
+                "fps",
+                "Frame rate must be positive"
+            )),
+            json!({
+                "code": "validation",
+                "message": "Frame rate must be positive",
+                "field": "fps"
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::FfmpegFailure {
+                kind: FfmpegErrorKind::DiskFull,
+                message: "The disk is full".into(),
+                excerpt: "No space left on device".into(),
+            }),
+            json!({
+                "code": "ffmpeg_failure",
+                "message": "The disk is full",
+                "kind": "disk_full",
+                "excerpt": "No space left on device"
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::Io {
+                message: "Permission denied".into()
+            }),
+            json!({ "code": "io", "message": "Permission denied" })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::MissingDependency {
+                which: "whisper".into(),
+                message: "Whisper is not installed".into(),
+            }),
+            json!({
+                "code": "missing_dependency",
+                "message": "Whisper is not installed",
+                "which": "whisper"
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::unsupported("Recording not supported")),
+            json!({ "code": "unsupported", "message": "Recording not supported" })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::busy("A recording is in progress")),
+            json!({ "code": "busy", "message": "A recording is in progress" })
+        );
+        assert_eq!(
+            snapshot(DownloadError::HttpStatus(403).into()),
+            json!({
+                "code": "network",
+                "message": "The server refused the download (HTTP 403)",
+                "status": 403
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::PreflightFailed {
+                message: "1 problem stops the export".into(),
+                report: Box::new(PreflightReport {
+                    errors: vec![PreflightIssue {
+                        check: PreflightCheck::SourceFile,
+                        clip_id: Some("c1".into()),
+                        media_clip_id: Some("abc".into()),
+                        path: Some("/media/a.mp4".into()),
+                        file_issue: Some(FileIssue::Missing),
+                        message: "a.mp4: /media/a.mp4 is missing".into(),
+                    }],
+                    free_bytes: Some(2048),
+                    ..Default::default()
+                }),
+            }),
+            json!({
+                "code": "preflight_failed",
+                "message": "1 problem stops the export",
+                "report": {
+                    "errors": [{
+                        "check": "source_file",
+                        "clip_id": "c1",
+                        "media_clip_id": "abc",
+                        "path": "/media/a.mp4",
+                        "file_issue": "missing",
+                        "message": "a.mp4: /media/a.mp4 is missing"
+                    }],
+                    "warnings": [],
+                    "estimated_bytes": 0,
+                    "estimated_seconds": null,
+                    "free_bytes": 2048,
+                    "proxy_fallbacks": []
+                }
+            })
+        );
+        assert_eq!(
+            snapshot(ClipForgeError::Cancelled),
+            json!({ "code": "cancelled", "message": "Cancelled" })
+        );
+        assert_eq!(
+            snapshot("Something else".into()),
+            json!({ "code": "other", "message": "Something else" })
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        let error: ClipForgeError =
+            ProjectFileError::NotFound(std::path::PathBuf::from("/p/a.clipforge")).into();
+        assert_eq!(error.to_string(), "Project file not found: /p/a.clipforge");
+        assert_eq!(error.code(), "not_found");
+
+        let error: ClipForgeError = ProcessError::Spawn {
+            program: "ffmpeg".into(),
+            message: "No such file or directory".into(),
+        }
+        .into();
+        assert!(matches!(
+            error,
+            ClipForgeError::MissingDependency { ref which, .. } if which == "ffmpeg"
+        ));
+
+        let error: ClipForgeError = ProcessError::Timeout {
+            program: "ffmpeg".into(),
+            seconds: 30,
+        }
+        .into();
+        assert!(matches!(
+            error,
+            ClipForgeError::FfmpegFailure {
+                kind: FfmpegErrorKind::Timeout,
+                ..
+            }
+        ));
+
+        let error: ClipForgeError = CloseProjectError::RecordingActive("recording".into()).into();
+        assert_eq!(error.code(), "busy");
+        assert_eq!(String::from(error), "recording");
+    }
+}
@@ -0,0 +1,302 @@
+// Post-recording audio analysis
+// One FFmpeg pass over a finished recording's audio runs silencedetect (for
+// the silent lead-in and tail) and/or loudnorm's measurement. The results are
+// suggestions stored on the MediaClip; the recording itself is never
+// rewritten.
+
+use crate::ffmpeg::locate::ffmpeg_path;
+use crate::ffmpeg::scheduler::{path_label, scheduler, JobKind};
+use crate::models::clip::{LoudnessMeasurement, SuggestedTrim};
+use crate::models::recording::RecordingPostProcess;
+use crate::platform::process::{self, ProcessClass};
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Level below which audio counts as silence
+const SILENCE_NOISE_DB: i32 = -45;
+/// Shortest quiet stretch reported as silence (seconds)
+const SILENCE_MIN_DURATION: f64 = 0.5;
+/// How close to the start/end a silence must reach to be trimmed (seconds)
+const EDGE_TOLERANCE: f64 = 0.1;
+/// Silence kept on either side of the speech so cuts don't clip it (seconds)
+const TRIM_HANDLE: f64 = 0.25;
+/// Shortest clip a suggested trim may leave (seconds)
+const MIN_KEPT_DURATION: f64 = 1.0;
+
+/// A quiet stretch reported by silencedetect
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceInterval {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// What the analysis found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingAnalysis {
+    pub suggested_trim: Option<SuggestedTrim>,
+    pub loudness: Option<LoudnessMeasurement>,
+}
+
+/// Silences from silencedetect's stderr
+///
+/// A silence still running when the file ends has no `silence_end` line and
+/// is closed at `duration`.
+pub fn parse_silencedetect(stderr: &str, duration: f64) -> Vec<SilenceInterval> {
+    let value = |line: &str, key: &str| -> Option<f64> {
+        let rest = &line[line.find(key)? + key.len()..];
+        rest.split_whitespace().next()?.parse().ok()
+    };
+    let mut silences = Vec::new();
+    let mut open: Option<f64> = None;
+    for line in stderr.lines().filter(|line| line.contains("silencedetect")) {
+        if let Some(start) = value(line, "silence_start:") {
+            open = Some(start.max(0.0));
+        } else if let Some(end) = value(line, "silence_end:") {
+            if let Some(start) = open.take() {
+                silences.push(SilenceInterval {
+                    start,
+                    end: end.min(duration),
+                });
+            }
+        }
+    }
+    if let Some(start) = open {
+        silences.push(SilenceInterval {
+            start,
+            end: duration,
+        });
+    }
+    silences
+}
+
+/// The JSON block loudnorm prints with `print_format=json`
+///
+/// None when it's missing or the audio was silent (`-inf` loudness).
+pub fn parse_loudnorm(stderr: &str) -> Option<LoudnessMeasurement> {
+    let start = stderr.rfind('{')?;
+    let end = start + stderr[start..].find('}')? + 1;
+    let fields: HashMap<String, String> = serde_json::from_str(&stderr[start..end]).ok()?;
+    let field = |key: &str| -> Option<f64> {
+        fields
+            .get(key)?
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+    };
+    Some(LoudnessMeasurement {
+        integrated: field("input_i")?,
+        true_peak: field("input_tp")?,
+        range: field("input_lra")?,
+        threshold: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+    })
+}
+
+/// In/out points dropping the silence at the start and/or end of a clip
+///
+/// A short handle of the silence is kept next to the sound. None when there
+/// is nothing to trim, or when trimming would leave less than a second
+/// (a recording with no sound at all).
+pub fn suggest_trim(
+    silences: &[SilenceInterval],
+    duration: f64,
+    trim_leading: bool,
+    trim_trailing: bool,
+) -> Option<SuggestedTrim> {
+    let leading = silences
+        .iter()
+        .find(|silence| trim_leading && silence.start <= EDGE_TOLERANCE);
+    let trailing = silences
+        .iter()
+        .rev()
+        .find(|silence| trim_trailing && silence.end >= duration - EDGE_TOLERANCE);
+
+    let in_point = leading.map_or(0.0, |silence| (silence.end - TRIM_HANDLE).max(0.0));
+    let out_point = trailing.map_or(duration, |silence| {
+        (silence.start + TRIM_HANDLE).min(duration)
+    });
+    if (in_point <= 0.0 && out_point >= duration) || out_point - in_point < MIN_KEPT_DURATION {
+        return None;
+    }
+    Some(SuggestedTrim {
+        in_point,
+        out_point,
+    })
+}
+
+/// FFmpeg arguments analysing the first audio stream of `path` (None if
+/// `options` asks for nothing)
+pub fn analysis_args(path: &str, options: &RecordingPostProcess) -> Option<Vec<String>> {
+    let mut filters = Vec::new();
+    if options.trim_leading_silence || options.trim_trailing_silence {
+        filters.push(format!(
+            "silencedetect=noise={}dB:d={}",
+            SILENCE_NOISE_DB, SILENCE_MIN_DURATION
+        ));
+    }
+    if options.normalize_audio {
+        filters.push("loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json".to_string());
+    }
+    if filters.is_empty() {
+        return None;
+    }
+    let mut args: Vec<String> = [
+        "-hide_banner",
+        "-nostats",
+        "-i",
+        path,
+        "-map",
+        "0:a:0",
+        "-af",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    args.push(filters.join(","));
+    args.extend(["-f", "null", "-"].iter().map(|arg| arg.to_string()));
+    Some(args)
+}
+
+/// Analyse a finished recording's audio as a background audio job
+pub async fn analyze_recording(
+    path: &str,
+    duration: f64,
+    options: &RecordingPostProcess,
+) -> Result<RecordingAnalysis, String> {
+    let Some(args) = analysis_args(path, options) else {
+        return Ok(RecordingAnalysis::default());
+    };
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(&args);
+    let _permit = scheduler().acquire(JobKind::Audio, path_label(path)).await;
+    let output = process::output(cmd, ProcessClass::AudioExtraction).await?;
+    if !output.status.success() {
+        return Err(format!(
+            "FFmpeg audio analysis failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(RecordingAnalysis {
+        suggested_trim: suggest_trim(
+            &parse_silencedetect(&stderr, duration),
+            duration,
+            options.trim_leading_silence,
+            options.trim_trailing_silence,
+        ),
+        loudness: options
+            .normalize_audio
+            .then(|| parse_loudnorm(&stderr))
+            .flatten(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANALYSIS_OUTPUT: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'recording_20240301_101500.mp4':
+  Duration: 00:00:42.51, start: 0.000000, bitrate: 2519 kb/s
+[silencedetect @ 0x600003a1c000] silence_start: -0.0213333
+[silencedetect @ 0x600003a1c000] silence_end: 3.61933 | silence_duration: 3.64067
+[silencedetect @ 0x600003a1c000] silence_start: 17.2
+[silencedetect @ 0x600003a1c000] silence_end: 18.1 | silence_duration: 0.9
+[silencedetect @ 0x600003a1c000] silence_start: 39.8021
+size=N/A time=00:00:42.50 bitrate=N/A speed= 312x
+[Parsed_loudnorm_1 @ 0x600003a1c100]
+{
+\t\"input_i\" : \"-27.61\",
+\t\"input_tp\" : \"-4.47\",
+\t\"input_lra\" : \"18.06\",
+\t\"input_thresh\" : \"-39.20\",
+\t\"output_i\" : \"-16.58\",
+\t\"output_tp\" : \"-1.50\",
+\t\"output_lra\" : \"14.78\",
+\t\"output_thresh\" : \"-27.71\",
+\t\"normalization_type\" : \"dynamic\",
+\t\"target_offset\" : \"0.58\"
+}";
+
+    #[test]
+    fn test_parse_silencedetect() {
+        let silences = parse_silencedetect(ANALYSIS_OUTPUT, 42.51);
+        assert_eq!(
+            silences,
+            [
+                SilenceInterval {
+                    start: 0.0,
+                    end: 3.61933
+                },
+                SilenceInterval {
+                    start: 17.2,
+                    end: 18.1
+                },
+                // Still silent at the end of the file
+                SilenceInterval {
+                    start: 39.8021,
+                    end: 42.51
+                },
+            ]
+        );
+        assert!(parse_silencedetect("no silence here", 10.0).is_empty());
+    }
+
+    #[test]
+    fn test_parse_loudnorm() {
+        let loudness = parse_loudnorm(ANALYSIS_OUTPUT).unwrap();
+        assert_eq!(loudness.integrated, -27.61);
+        assert_eq!(loudness.true_peak, -4.47);
+        assert_eq!(loudness.range, 18.06);
+        assert_eq!(loudness.threshold, -39.2);
+        assert_eq!(loudness.target_offset, 0.58);
+
+        let silent =
+            "{ \"input_i\" : \"-inf\", \"input_tp\" : \"-inf\", \"input_lra\" : \"0.00\", \
+                      \"input_thresh\" : \"-70.00\", \"target_offset\" : \"inf\" }";
+        assert_eq!(parse_loudnorm(silent), None);
+    }
+
+    #[test]
+    fn test_suggest_trim() {
+        let silences = parse_silencedetect(ANALYSIS_OUTPUT, 42.51);
+        let trim = suggest_trim(&silences, 42.51, true, true).unwrap();
+        assert!((trim.in_point - 3.36933).abs() < 1e-9);
+        assert!((trim.out_point - 40.0521).abs() < 1e-9);
+
+        // Each end only when asked for; the mid-recording pause is kept
+        let leading = suggest_trim(&silences, 42.51, true, false).unwrap();
+        assert_eq!(leading.out_point, 42.51);
+        let trailing = suggest_trim(&silences, 42.51, false, true).unwrap();
+        assert_eq!(trailing.in_point, 0.0);
+        assert_eq!(suggest_trim(&silences, 42.51, false, false), None);
+
+        // No silence at the edges, or nothing but silence
+        let middle = [SilenceInterval {
+            start: 5.0,
+            end: 6.0,
+        }];
+        assert_eq!(suggest_trim(&middle, 10.0, true, true), None);
+        let silent = [SilenceInterval {
+            start: 0.0,
+            end: 10.0,
+        }];
+        assert_eq!(suggest_trim(&silent, 10.0, true, true), None);
+    }
+
+    #[test]
+    fn test_analysis_args() {
+        let mut options = RecordingPostProcess::default();
+        assert_eq!(analysis_args("rec.mp4", &options), None);
+
+        options.trim_leading_silence = true;
+        options.normalize_audio = true;
+        assert_eq!(
+            analysis_args("rec.mp4", &options).unwrap().join(" "),
+            "-hide_banner -nostats -i rec.mp4 -map 0:a:0 -af \
+             silencedetect=noise=-45dB:d=0.5,loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json \
+             -f null -"
+        );
+    }
+}
@@ -1,9 +1,19 @@
+use crate::ffmpeg::locate::{ffmpeg_path, require_ffmpeg};
+use crate::ffmpeg::scheduler::{path_label, scheduler, JobKind};
+use crate::platform::process::{self, ProcessClass};
+use crate::storage::work_dir::{area_dir, WorkArea};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 use tokio::fs;
 
 /// Extract audio from video file to WAV format for speech recognition
-pub async fn extract_audio_to_wav(video_path: &str, output_path: &str) -> Result<PathBuf, String> {
+///
+/// `audio_stream` picks a source stream index; None lets FFmpeg choose.
+pub async fn extract_audio_to_wav(
+    video_path: &str,
+    audio_stream: Option<u32>,
+    output_path: &str,
+) -> Result<PathBuf, String> {
     // Validate input file exists
     if !Path::new(video_path).exists() {
         return Err(format!("Video file not found: {}", video_path));
@@ -16,29 +26,33 @@ pub async fn extract_audio_to_wav(video_path: &str, output_path: &str) -> Result
             .map_err(|e| format!("Failed to create output directory: {}", e))?;
     }
 
+    require_ffmpeg()?;
+
     // FFmpeg command to extract audio as 16-bit PCM WAV (required by whisper.cpp)
     // -vn: no video
     // -acodec pcm_s16le: 16-bit PCM little-endian
     // -ar 16000: 16kHz sample rate (optimal for speech recognition)
     // -ac 1: mono audio (reduces file size, sufficient for speech)
-    let output = Command::new("ffmpeg")
-        .args([
-            "-i",
-            video_path,
-            "-vn", // No video
-            "-acodec",
-            "pcm_s16le", // 16-bit PCM
-            "-ar",
-            "16000", // 16kHz sample rate
-            "-ac",
-            "1",  // Mono
-            "-y", // Overwrite output file
-            output_path,
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-i", video_path]);
+    if let Some(index) = audio_stream {
+        cmd.args(["-map", &format!("0:{}", index)]);
+    }
+    cmd.args([
+        "-vn", // No video
+        "-acodec",
+        "pcm_s16le", // 16-bit PCM
+        "-ar",
+        "16000", // 16kHz sample rate
+        "-ac",
+        "1",  // Mono
+        "-y", // Overwrite output file
+        output_path,
+    ]);
+    let _permit = scheduler()
+        .acquire(JobKind::Audio, path_label(video_path))
+        .await;
+    let output = process::output(cmd, ProcessClass::AudioExtraction).await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -54,11 +68,9 @@ pub async fn extract_audio_to_wav(video_path: &str, output_path: &str) -> Result
     Ok(output_path_buf)
 }
 
-/// Get temporary audio file path for a clip
-pub fn get_temp_audio_path(clip_id: &str) -> PathBuf {
-    let mut path = std::env::temp_dir();
-    path.push(format!("clipforge_audio_{}.wav", clip_id));
-    path
+/// Get temporary audio file path for a clip (in the work directory)
+pub fn get_temp_audio_path(clip_id: &str) -> Result<PathBuf, String> {
+    Ok(area_dir(WorkArea::Captions)?.join(format!("clipforge_audio_{}.wav", clip_id)))
 }
 
 /// Clean up temporary audio file
@@ -78,7 +90,7 @@ mod tests {
 
     #[test]
     fn test_temp_audio_path() {
-        let path = get_temp_audio_path("test-clip-123");
+        let path = get_temp_audio_path("test-clip-123").unwrap();
         assert!(path
             .to_str()
             .unwrap()
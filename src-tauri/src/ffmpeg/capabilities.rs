@@ -0,0 +1,184 @@
+// Hardware encoder detection
+// FFmpeg builds list encoders for every GPU vendor they were compiled for,
+// whether or not this machine has that GPU (Windows builds ship nvenc, qsv
+// and amf together). The probe lists the hardware H.264 encoders in the
+// build, then opens each one with a single-frame test encode; the first that
+// works, in `HARDWARE_H264_PRIORITY` order, is the one exports and
+// recordings use. The result is cached until the FFmpeg binary changes.
+
+use crate::ffmpeg::locate::{available_encoders, ffmpeg_path};
+use crate::platform::process::{self, ProcessClass};
+use serde::Serialize;
+use std::process::Command;
+use std::sync::RwLock;
+
+/// Hardware H.264 encoders, most preferred first
+pub const HARDWARE_H264_PRIORITY: &[&str] = &[
+    "h264_videotoolbox",
+    "h264_nvenc",
+    "h264_qsv",
+    "h264_amf",
+    "h264_vaapi",
+];
+
+/// Render node VAAPI encoders open
+pub const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+/// Hardware H.264 encoders found by `hardware_encoders`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HardwareEncoders {
+    /// Included in the FFmpeg build, in priority order
+    pub listed: Vec<&'static str>,
+    /// Listed encoders that opened on this machine
+    pub usable: Vec<&'static str>,
+    /// Encoder used when hardware acceleration is on (None = libx264)
+    pub selected: Option<&'static str>,
+}
+
+impl HardwareEncoders {
+    /// Build from the `-encoders` list and a check of whether each opens
+    pub fn detect(encoders: &[String], mut opens: impl FnMut(&'static str) -> bool) -> Self {
+        let listed = hardware_candidates(encoders);
+        let usable: Vec<&'static str> = listed.iter().copied().filter(|e| opens(e)).collect();
+        Self {
+            selected: select_hardware_encoder(&usable),
+            listed,
+            usable,
+        }
+    }
+}
+
+/// Result of the last probe; reset when FFmpeg is located again
+static PROBED: RwLock<Option<HardwareEncoders>> = RwLock::new(None);
+
+/// Hardware encoders in `encoders` (`-encoders` names), in priority order
+pub fn hardware_candidates(encoders: &[String]) -> Vec<&'static str> {
+    HARDWARE_H264_PRIORITY
+        .iter()
+        .copied()
+        .filter(|name| encoders.iter().any(|e| e == name))
+        .collect()
+}
+
+/// The most preferred of `usable`
+pub fn select_hardware_encoder(usable: &[&'static str]) -> Option<&'static str> {
+    HARDWARE_H264_PRIORITY
+        .iter()
+        .copied()
+        .find(|name| usable.contains(name))
+}
+
+/// Whether `encoder` takes frames uploaded to a VAAPI device
+pub fn needs_vaapi_upload(encoder: &str) -> bool {
+    encoder.ends_with("_vaapi")
+}
+
+/// Cached hardware encoder choice (None before the first probe)
+pub fn selected_hardware_encoder() -> Option<&'static str> {
+    PROBED.read().unwrap().as_ref()?.selected
+}
+
+/// Forget the cached probe; the next `hardware_encoders` probes again
+pub fn reset() {
+    *PROBED.write().unwrap() = None;
+}
+
+/// Hardware encoders of the located FFmpeg, probed on first use
+pub async fn hardware_encoders() -> Result<HardwareEncoders, String> {
+    if let Some(probed) = PROBED.read().unwrap().clone() {
+        return Ok(probed);
+    }
+    let encoders = available_encoders().await?;
+    let mut usable = Vec::new();
+    for name in hardware_candidates(&encoders) {
+        if opens(name).await {
+            usable.push(name);
+        } else {
+            log::info!("{} is in the FFmpeg build but can't encode here", name);
+        }
+    }
+    let probed = HardwareEncoders::detect(&encoders, |name| usable.contains(&name));
+    log::info!(
+        "Hardware H.264 encoder: {}",
+        probed.selected.unwrap_or("none, using libx264")
+    );
+    *PROBED.write().unwrap() = Some(probed.clone());
+    Ok(probed)
+}
+
+/// Probe ahead of work that may use a hardware encoder; if the probe fails
+/// that work encodes in software
+pub async fn ensure_probed() {
+    if let Err(e) = hardware_encoders().await {
+        log::warn!("Couldn't detect hardware encoders: {}", e);
+    }
+}
+
+/// Whether `encoder` encodes a single black frame
+async fn opens(encoder: &str) -> bool {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-loglevel", "error"]);
+    if needs_vaapi_upload(encoder) {
+        cmd.args(["-vaapi_device", VAAPI_DEVICE]);
+    }
+    cmd.args(["-f", "lavfi", "-i", "color=c=black:s=256x256:d=0.1"]);
+    if needs_vaapi_upload(encoder) {
+        cmd.args(["-vf", "format=nv12,hwupload"]);
+    }
+    cmd.args(["-frames:v", "1", "-c:v", encoder, "-f", "null", "-"]);
+    matches!(
+        process::output(cmd, ProcessClass::Probe).await,
+        Ok(output) if output.status.success()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::locate::parse_encoders;
+
+    const ENCODERS: &str = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D h264_amf             AMD AMF H.264 Encoder (codec h264)
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V..... h264_qsv             H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (Intel Quick Sync Video acceleration) (codec h264)
+ V....D hevc_nvenc           NVIDIA NVENC hevc encoder (codec hevc)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+    #[test]
+    fn test_hardware_candidates_from_encoders_output() {
+        let encoders = parse_encoders(ENCODERS);
+        assert_eq!(
+            hardware_candidates(&encoders),
+            ["h264_nvenc", "h264_qsv", "h264_amf"]
+        );
+        assert!(hardware_candidates(&parse_encoders("")).is_empty());
+    }
+
+    #[test]
+    fn test_selection_skips_encoders_that_dont_open() {
+        let encoders = parse_encoders(ENCODERS);
+
+        // AMD machine with a build that also ships nvenc and qsv
+        let amd = HardwareEncoders::detect(&encoders, |name| name == "h264_amf");
+        assert_eq!(amd.listed, ["h264_nvenc", "h264_qsv", "h264_amf"]);
+        assert_eq!(amd.usable, ["h264_amf"]);
+        assert_eq!(amd.selected, Some("h264_amf"));
+
+        // NVIDIA wins over Quick Sync when both work
+        let both = HardwareEncoders::detect(&encoders, |name| name != "h264_amf");
+        assert_eq!(both.selected, Some("h264_nvenc"));
+
+        let none = HardwareEncoders::detect(&encoders, |_| false);
+        assert_eq!(none.selected, None);
+
+        assert_eq!(
+            select_hardware_encoder(&["h264_vaapi", "h264_videotoolbox"]),
+            Some("h264_videotoolbox")
+        );
+    }
+}
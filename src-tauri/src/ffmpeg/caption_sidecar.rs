@@ -0,0 +1,414 @@
+// Caption sidecar files
+// Captions belong to media and are timed in the media's own seconds. For a
+// subtitle file next to an export, each caption is cut to the part of the
+// media its clip plays (in to out point), moved to the clip's place on the
+// timeline (after any held first frame, at the clip's speed) and then to
+// where that lands in the output (see `chapters::output_time`). Where
+// captions of clips playing at once overlap (an overlay over the main
+// track, two clips crossfading), one cue holds the lines of all of them,
+// lower tracks first, so the file never has overlapping cues.
+
+use crate::ffmpeg::chapters::output_time;
+use crate::ffmpeg::export::{ExportSegment, ExportTiming};
+use crate::models::clip::MediaClip;
+use crate::models::export::CaptionFormat;
+use crate::models::timeline::{TimelineClip, Track};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cues shorter than this (seconds) are dropped
+const MIN_CUE_DURATION: f64 = 1e-3;
+
+/// A caption as it shows in the output, times in output seconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptionCue {
+    pub start: f64,
+    pub end: f64,
+    /// One or more lines, never a blank one
+    pub text: String,
+}
+
+/// Cues of every caption heard in an export, in time order and never
+/// overlapping
+///
+/// Clips with their audio silenced (`video_only`) are left out, as are
+/// captions outside their clip's trim or the export range.
+pub fn plan_caption_cues(
+    tracks: &[Track],
+    media_library: &[MediaClip],
+    segments: &[ExportSegment],
+    timing: &ExportTiming,
+) -> Vec<CaptionCue> {
+    let mut tracks: Vec<&Track> = tracks.iter().collect();
+    tracks.sort_by_key(|t| t.order);
+
+    // Each caption's output span, in stacking order
+    let mut placed: Vec<CaptionCue> = Vec::new();
+    for track in tracks {
+        let mut clips: Vec<&TimelineClip> = track.clips.iter().collect();
+        clips.sort_by(|a, b| a.timeline_order(b));
+        for clip in clips.into_iter().filter(|c| !c.video_only) {
+            let Some(media) = media_library.iter().find(|m| m.id == clip.media_clip_id) else {
+                continue;
+            };
+            let mut captions: Vec<_> = media.captions.iter().collect();
+            captions.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+            for caption in captions {
+                let text = cue_text(&caption.text);
+                let start = caption.start_time.max(clip.in_point);
+                let end = caption.end_time.min(clip.out_point);
+                if text.is_empty() || end - start < MIN_CUE_DURATION {
+                    continue;
+                }
+                let timeline_time =
+                    |t: f64| clip.media_start_time() + (t - clip.in_point) / clip.speed;
+                let start = timeline_time(start).max(timing.range.start);
+                let end = timeline_time(end).min(timing.range.end);
+                if end - start < MIN_CUE_DURATION {
+                    continue;
+                }
+                let Some(output_start) = output_time(segments, timing, start) else {
+                    continue;
+                };
+                placed.push(CaptionCue {
+                    start: output_start,
+                    end: (output_start + (end - start)).min(timing.render_duration),
+                    text,
+                });
+            }
+        }
+    }
+    stack_cues(&placed)
+}
+
+/// `text` without blank lines, which end a cue in SRT and WebVTT
+fn cue_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cues covering the same time as `placed` without overlapping: between
+/// any two cue edges, the lines of every cue showing, in `placed` order
+fn stack_cues(placed: &[CaptionCue]) -> Vec<CaptionCue> {
+    let mut edges: Vec<f64> = placed.iter().flat_map(|c| [c.start, c.end]).collect();
+    edges.sort_by(f64::total_cmp);
+    edges.dedup_by(|a, b| (*a - *b).abs() < MIN_CUE_DURATION);
+
+    let mut cues: Vec<CaptionCue> = Vec::new();
+    for span in edges.windows(2) {
+        let (start, end) = (span[0], span[1]);
+        let lines: Vec<&str> = placed
+            .iter()
+            .filter(|c| {
+                c.start < end - MIN_CUE_DURATION / 2.0 && c.end > start + MIN_CUE_DURATION / 2.0
+            })
+            .map(|c| c.text.as_str())
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let text = lines.join("\n");
+        match cues.last_mut() {
+            // The same lines carry on past an edge of a cue that isn't shown
+            Some(last) if last.text == text && (start - last.end).abs() < MIN_CUE_DURATION => {
+                last.end = end;
+            }
+            _ => cues.push(CaptionCue { start, end, text }),
+        }
+    }
+    cues
+}
+
+/// `seconds` as `HH:MM:SS` plus milliseconds after `separator` (`,` in SRT,
+/// `.` in WebVTT)
+fn timestamp(seconds: f64, separator: char) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// `cues` as a SubRip file
+pub fn srt(cues: &[CaptionCue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            )
+        })
+        .collect()
+}
+
+/// `cues` as a WebVTT file, text escaped so none reads as markup or timing
+pub fn webvtt(cues: &[CaptionCue]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for cue in cues {
+        let text = cue
+            .text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        vtt.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            text
+        ));
+    }
+    vtt
+}
+
+/// Where the sidecar of `output_path` goes: the same name, with the
+/// format's extension
+pub fn sidecar_path(output_path: &Path, format: CaptionFormat) -> PathBuf {
+    output_path.with_extension(format.extension())
+}
+
+/// Write `cues` next to `output_path` in `format`, returning the file's path
+pub fn write_caption_sidecar(
+    cues: &[CaptionCue],
+    format: CaptionFormat,
+    output_path: &Path,
+) -> Result<PathBuf, String> {
+    let path = sidecar_path(output_path, format);
+    let contents = match format {
+        CaptionFormat::Srt => srt(cues),
+        CaptionFormat::Vtt => webvtt(cues),
+    };
+    fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write captions {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::export::ExportPlan;
+    use crate::models::caption::Caption;
+    use crate::models::export::{ExportRange, ExportSettings};
+    use crate::models::project::ProjectSettings;
+    use crate::models::timeline::TrackType;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// 30s of media with `captions` as (start, end, text)
+    fn media(id: &str, captions: &[(f64, f64, &str)]) -> MediaClip {
+        MediaClip {
+            id: id.to_string(),
+            name: format!("{}.mp4", id),
+            source_path: format!("/media/{}.mp4", id),
+            proxy_path: None,
+            proxy_version: None,
+            thumbnail_path: None,
+            thumbnail_2x_path: None,
+            duration: 30.0,
+            resolution: "1920x1080".to_string(),
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            codec: "h264".to_string(),
+            audio_codec: Some("aac".to_string()),
+            file_size: 1024 * 1024,
+            bitrate: Some(5000),
+            has_audio: true,
+            imported_at: Utc::now(),
+            captions: captions
+                .iter()
+                .map(|(start, end, text)| {
+                    Caption::new(
+                        id.to_string(),
+                        text.to_string(),
+                        *start,
+                        *end,
+                        "en".to_string(),
+                    )
+                })
+                .collect(),
+            audio_streams: vec![],
+            audio_stream_index: None,
+            suggested_trim: None,
+            loudness: None,
+            origin_url: None,
+        }
+    }
+
+    fn track(track_type: TrackType, order: u32, clips: &[(&str, f64, f64, f64)]) -> Track {
+        let mut track = Track::new(format!("{:?}", track_type), track_type);
+        track.order = order;
+        for (media_id, start, in_point, out_point) in clips {
+            track.clips.push(TimelineClip::new(
+                media_id.to_string(),
+                track.id.clone(),
+                *start,
+                *in_point,
+                *out_point,
+            ));
+        }
+        track
+    }
+
+    fn cues(
+        tracks: &[Track],
+        media_library: &[MediaClip],
+        range: Option<ExportRange>,
+    ) -> Vec<CaptionCue> {
+        let settings = ExportSettings {
+            range,
+            caption_sidecar: Some(CaptionFormat::Srt),
+            ..ExportSettings::default()
+        };
+        let mut plan = ExportPlan::build(
+            tracks,
+            media_library,
+            &settings,
+            &ProjectSettings::default(),
+            &HashMap::new(),
+        )
+        .unwrap();
+        plan.add_captions(tracks, media_library);
+        plan.captions
+    }
+
+    fn spans(cues: &[CaptionCue]) -> Vec<(f64, f64, &str)> {
+        cues.iter()
+            .map(|c| {
+                let round = |t: f64| (t * 1000.0).round() / 1000.0;
+                (round(c.start), round(c.end), c.text.as_str())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(timestamp(3.5, ','), "00:00:03,500");
+        assert_eq!(timestamp(61.0004, ','), "00:01:01,000");
+        assert_eq!(timestamp(3723.0456, '.'), "01:02:03.046");
+        assert_eq!(timestamp(59.9996, ','), "00:01:00,000");
+        assert_eq!(timestamp(-1.0, ','), "00:00:00,000");
+
+        let cues = vec![
+            CaptionCue {
+                start: 1.0,
+                end: 2.25,
+                text: "Hello".to_string(),
+            },
+            CaptionCue {
+                start: 2.25,
+                end: 4.0,
+                text: "a <b> & c\nsecond line".to_string(),
+            },
+        ];
+        assert_eq!(
+            srt(&cues),
+            "1\n00:00:01,000 --> 00:00:02,250\nHello\n\n2\n00:00:02,250 --> 00:00:04,000\na <b> & c\nsecond line\n\n"
+        );
+        assert_eq!(
+            webvtt(&cues),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.250\nHello\n\n00:00:02.250 --> 00:00:04.000\na &lt;b&gt; &amp; c\nsecond line\n\n"
+        );
+        assert_eq!(cue_text("  one \n\n two\n"), "one\ntwo");
+    }
+
+    #[test]
+    fn test_captions_follow_trims_into_output_time() {
+        let media_library = vec![
+            media(
+                "a",
+                &[
+                    (0.0, 1.5, "before the trim"),
+                    (1.5, 3.0, "cut at the in point"),
+                    (4.0, 5.0, "inside"),
+                    (5.5, 7.0, "cut at the out point"),
+                ],
+            ),
+            media("b", &[(10.0, 12.0, "double speed")]),
+        ];
+        // a plays 2-6 of its media at 0-4; b plays 10-14 at twice the speed
+        // after a 3s gap, which the export collapses
+        let mut main = track(
+            TrackType::Main,
+            0,
+            &[("a", 0.0, 2.0, 6.0), ("b", 7.0, 10.0, 14.0)],
+        );
+        main.clips[1].speed = 2.0;
+        let tracks = vec![main];
+
+        assert_eq!(
+            spans(&cues(&tracks, &media_library, None)),
+            vec![
+                (0.0, 1.0, "cut at the in point"),
+                (2.0, 3.0, "inside"),
+                (3.5, 4.0, "cut at the out point"),
+                (4.0, 5.0, "double speed"),
+            ]
+        );
+
+        // A range from 2.5 into a's clip moves everything 2.5s earlier
+        let range = Some(ExportRange {
+            start: 2.5,
+            end: 8.0,
+        });
+        assert_eq!(
+            spans(&cues(&tracks, &media_library, range)),
+            vec![
+                (0.0, 0.5, "inside"),
+                (1.0, 1.5, "cut at the out point"),
+                (1.5, 2.5, "double speed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_captions_stack() {
+        let media_library = vec![
+            media("a", &[(0.0, 4.0, "main"), (6.0, 8.0, "main again")]),
+            media(
+                "b",
+                &[(0.0, 2.0, "overlay"), (2.0, 3.0, "overlay  \n\n too")],
+            ),
+            media("c", &[(0.0, 10.0, "silenced")]),
+        ];
+        let mut overlay = track(
+            TrackType::Overlay,
+            1,
+            &[("b", 2.0, 0.0, 3.0), ("c", 5.0, 0.0, 5.0)],
+        );
+        overlay.clips[1].video_only = true;
+        // The overlay track first, to show stacking follows track order
+        let tracks = vec![overlay, track(TrackType::Main, 0, &[("a", 0.0, 0.0, 10.0)])];
+
+        let cues = cues(&tracks, &media_library, None);
+        assert_eq!(
+            spans(&cues),
+            vec![
+                (0.0, 2.0, "main"),
+                (2.0, 4.0, "main\noverlay"),
+                (4.0, 5.0, "overlay\ntoo"),
+                (6.0, 8.0, "main again"),
+            ]
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path =
+            write_caption_sidecar(&cues, CaptionFormat::Vtt, &dir.path().join("out.mp4")).unwrap();
+        assert_eq!(path, dir.path().join("out.vtt"));
+        let vtt = fs::read_to_string(path).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.000\nmain\n\n"));
+    }
+}
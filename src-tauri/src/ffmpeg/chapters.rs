@@ -0,0 +1,267 @@
+// Chapters from timeline markers
+// Markers are placed at timeline times; an export collapses gaps and may
+// cover only a range, so each marker is moved to where its moment lands in
+// the output (a marker in a collapsed gap starts the clip after it). The
+// chapters go to FFmpeg as an ffmetadata file mapped onto the output, and to
+// the user as YouTube-style "00:00 Title" lines.
+
+use crate::ffmpeg::export::{ExportSegment, ExportTiming};
+use crate::ffmpeg::metadata::ChapterInfo;
+use crate::ffmpeg::{FfmpegCommandBuilder, FfmpegInput};
+use crate::models::marker::Marker;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Chapters shorter than this (seconds) are dropped as duplicates
+const MIN_CHAPTER_DURATION: f64 = 1e-3;
+
+/// Output time `time` on the timeline lands at, or None when it isn't
+/// rendered (outside the range, or after the last clip)
+pub fn output_time(segments: &[ExportSegment], timing: &ExportTiming, time: f64) -> Option<f64> {
+    if time < timing.range.start || time > timing.range.end {
+        return None;
+    }
+    segments.iter().find_map(|segment| {
+        let (timeline_start, output_start, duration) = match segment {
+            ExportSegment::Clip {
+                timeline_start,
+                output_start,
+                duration,
+                ..
+            }
+            | ExportSegment::Filler {
+                timeline_start,
+                output_start,
+                duration,
+            } => (*timeline_start, *output_start, *duration),
+        };
+        if time < timeline_start {
+            // In a collapsed gap: the chapter starts with the next clip
+            Some(output_start)
+        } else if time < timeline_start + duration {
+            Some(output_start + (time - timeline_start))
+        } else {
+            None
+        }
+    })
+}
+
+/// Chapters of an export from `markers`, each running to the next, plus a
+/// warning for every marker left out for being past the timeline's end
+///
+/// Markers outside the export range are left out quietly; of several
+/// markers landing on the same output time, the first in time order is kept.
+pub fn plan_chapters(
+    markers: &[Marker],
+    segments: &[ExportSegment],
+    timing: &ExportTiming,
+) -> (Vec<ChapterInfo>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut starts: Vec<(f64, &str)> = Vec::new();
+    for marker in markers {
+        if marker.time > timing.timeline_end {
+            warnings.push(format!(
+                "Marker \"{}\" at {:.2}s is past the end of the timeline ({:.2}s) and was left out of the chapters",
+                marker.label, marker.time, timing.timeline_end
+            ));
+            continue;
+        }
+        let Some(start) = output_time(segments, timing, marker.time) else {
+            continue;
+        };
+        if starts
+            .last()
+            .is_some_and(|(previous, _)| start - previous < MIN_CHAPTER_DURATION)
+        {
+            continue;
+        }
+        starts.push((start, &marker.label));
+    }
+
+    let chapters = starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, label))| ChapterInfo {
+            start: *start,
+            end: starts
+                .get(i + 1)
+                .map_or(timing.render_duration, |(next, _)| *next),
+            title: Some(label.to_string()),
+        })
+        .filter(|chapter| chapter.end - chapter.start >= MIN_CHAPTER_DURATION)
+        .collect();
+    (chapters, warnings)
+}
+
+/// `;FFMETADATA1` file holding `chapters` in milliseconds
+pub fn ffmetadata(chapters: &[ChapterInfo]) -> String {
+    let mut content = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        content.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\n",
+            (chapter.start * 1000.0).round() as u64,
+            (chapter.end * 1000.0).round() as u64
+        ));
+        if let Some(title) = &chapter.title {
+            content.push_str(&format!("title={}\n", escape_ffmetadata(title)));
+        }
+    }
+    content
+}
+
+/// Value escaped for an ffmetadata file: `=`, `;`, `#`, `\` and line breaks
+/// are backslash-escaped
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Write `chapters` to `chapters.txt` in `output_dir`
+pub fn write_chapters_file(chapters: &[ChapterInfo], output_dir: &Path) -> Result<PathBuf, String> {
+    let path = output_dir.join("chapters.txt");
+    fs::write(&path, ffmetadata(chapters))
+        .map_err(|e| format!("Failed to write chapters file: {}", e))?;
+    Ok(path)
+}
+
+/// Add the ffmetadata file as the last input, supplying the output's
+/// chapters and global metadata
+pub fn map_chapters(builder: FfmpegCommandBuilder, metadata_file: &Path) -> FfmpegCommandBuilder {
+    let index = builder.input_count().to_string();
+    builder
+        .input(FfmpegInput::new(metadata_file))
+        .option("-map_metadata", &index)
+        .option("-map_chapters", &index)
+}
+
+/// Chapters as YouTube description lines, e.g. "01:05 Setup"
+///
+/// Times count whole seconds, with hours only when the video is that long.
+pub fn chapters_text(chapters: &[ChapterInfo]) -> String {
+    let with_hours = chapters.last().is_some_and(|c| c.end >= 3600.0);
+    chapters
+        .iter()
+        .map(|chapter| {
+            let seconds = chapter.start.max(0.0).floor() as u64;
+            let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+            let time = if with_hours {
+                format!("{}:{:02}:{:02}", hours, minutes, seconds)
+            } else {
+                format!("{:02}:{:02}", minutes, seconds)
+            };
+            format!("{} {}", time, chapter.title.as_deref().unwrap_or_default())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::export::{ExportRange, GapHandling};
+
+    fn clip(timeline_start: f64, output_start: f64, duration: f64) -> ExportSegment {
+        ExportSegment::Clip {
+            clip_id: format!("clip-{}", timeline_start),
+            media_clip_id: "media".to_string(),
+            path: "/tmp/a.mp4".to_string(),
+            in_point: 0.0,
+            out_point: duration,
+            timeline_start,
+            output_start,
+            duration,
+            transition_in: None,
+        }
+    }
+
+    fn timing(start: f64, end: f64, timeline_duration: f64, render_duration: f64) -> ExportTiming {
+        ExportTiming {
+            gap_handling: GapHandling::Collapse,
+            range: ExportRange { start, end },
+            timeline_duration,
+            timeline_end: timeline_duration,
+            render_duration,
+            frame_accurate: false,
+        }
+    }
+
+    fn marker(time: f64, label: &str) -> Marker {
+        Marker::new(time, label.to_string(), None)
+    }
+
+    #[test]
+    fn test_markers_map_to_output_times() {
+        // Clips at 0-10 and 15-25 with the gap collapsed
+        let segments = vec![clip(0.0, 0.0, 10.0), clip(15.0, 10.0, 10.0)];
+        let timing = timing(0.0, 25.0, 25.0, 20.0);
+        let markers = vec![
+            marker(0.0, "Intro"),
+            marker(12.0, "In the gap"),
+            marker(15.0, "Same start"),
+            marker(20.0, "Demo"),
+            marker(40.0, "Too late"),
+        ];
+
+        let (chapters, warnings) = plan_chapters(&markers, &segments, &timing);
+        let starts: Vec<(f64, &str)> = chapters
+            .iter()
+            .map(|c| (c.start, c.title.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            starts,
+            vec![(0.0, "Intro"), (10.0, "In the gap"), (15.0, "Demo")]
+        );
+        assert_eq!(chapters[0].end, 10.0);
+        assert_eq!(chapters[2].end, 20.0);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Too late"));
+    }
+
+    #[test]
+    fn test_range_export_offsets_chapters() {
+        let segments = vec![clip(5.0, 0.0, 10.0)];
+        let timing = timing(5.0, 15.0, 30.0, 10.0);
+        let markers = vec![marker(2.0, "Before"), marker(8.0, "Inside")];
+
+        let (chapters, warnings) = plan_chapters(&markers, &segments, &timing);
+        assert_eq!(chapters.len(), 1);
+        assert_eq!((chapters[0].start, chapters[0].end), (3.0, 10.0));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_ffmetadata_escapes_titles() {
+        let chapters = vec![ChapterInfo {
+            start: 1.5,
+            end: 62.25,
+            title: Some("Q&A; a=b #1".to_string()),
+        }];
+        assert_eq!(
+            ffmetadata(&chapters),
+            ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=1500\nEND=62250\ntitle=Q&A\\; a\\=b \\#1\n"
+        );
+    }
+
+    #[test]
+    fn test_chapters_text() {
+        let chapter = |start: f64, end: f64, title: &str| ChapterInfo {
+            start,
+            end,
+            title: Some(title.to_string()),
+        };
+        let short = vec![chapter(0.0, 65.4, "Intro"), chapter(65.4, 600.0, "Setup")];
+        assert_eq!(chapters_text(&short), "00:00 Intro\n01:05 Setup");
+
+        let long = vec![
+            chapter(0.0, 3725.0, "Intro"),
+            chapter(3725.0, 4000.0, "Outro"),
+        ];
+        assert_eq!(chapters_text(&long), "0:00:00 Intro\n1:02:05 Outro");
+    }
+}
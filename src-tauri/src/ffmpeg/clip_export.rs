@@ -0,0 +1,317 @@
+// Batch clip export
+// Each clip of the main track renders to a file of its own, as a timeline
+// holding only that clip: its trim, speed, effects and held frames apply,
+// while other tracks (overlays, music) and transitions are left out. Clips
+// read their source media, never the proxy, so the files keep the quality
+// of the recording.
+
+use crate::ffmpeg::export::{export_main_track, ExportPlan};
+use crate::models::clip::MediaClip;
+use crate::models::export::ExportSettings;
+use crate::models::project::ProjectSettings;
+use crate::models::timeline::{TimelineClip, Track};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Longest part of a file name taken from the clip's name, in characters
+const MAX_NAME_LENGTH: usize = 60;
+
+/// A file a batch clip export writes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClipExportFile {
+    pub clip_id: String,
+    /// Position of the clip on the main track, from 1
+    pub index: usize,
+    pub output_path: String,
+    /// Output length in seconds
+    pub duration: f64,
+}
+
+/// One clip of a batch export, ready to render
+pub struct ClipExport {
+    pub file: ClipExportFile,
+    pub plan: ExportPlan,
+}
+
+/// `media_library` without proxies, so clips are exported from their sources
+pub fn source_media(media_library: &[MediaClip]) -> Vec<MediaClip> {
+    media_library
+        .iter()
+        .map(|media| MediaClip {
+            proxy_path: None,
+            ..media.clone()
+        })
+        .collect()
+}
+
+/// Plan rendering each clip of the main track into `directory`, in timeline
+/// order, with `settings` (any range ignored)
+///
+/// Files are named by position and media name, e.g. `03_interview.mp4`. A
+/// name taken, by a file already in `directory` or by another clip, gets a
+/// `-2`, `-3`... suffix, so nothing is overwritten. `media_library` should
+/// come from `source_media`.
+pub fn plan_clip_exports(
+    tracks: &[Track],
+    media_library: &[MediaClip],
+    settings: &ExportSettings,
+    project_settings: &ProjectSettings,
+    keyframes: &HashMap<String, Vec<f64>>,
+    directory: &Path,
+) -> Result<Vec<ClipExport>, String> {
+    let Some(main) = export_main_track(tracks) else {
+        return Ok(Vec::new());
+    };
+    let mut clips: Vec<&TimelineClip> = main.clips.iter().collect();
+    clips.sort_by(|a, b| a.timeline_order(b));
+    let settings = ExportSettings {
+        range: None,
+        chapters: false,
+        parallel: false,
+        caption_sidecar: None,
+        ..settings.clone()
+    };
+
+    let digits = clips.len().to_string().len().max(2);
+    let mut taken = HashSet::new();
+    clips
+        .into_iter()
+        .enumerate()
+        .map(|(i, clip)| {
+            let name = media_library
+                .iter()
+                .find(|m| m.id == clip.media_clip_id)
+                .map_or("clip", |m| m.name.as_str());
+            let stem = format!(
+                "{:0digits$}_{}",
+                i + 1,
+                file_name_part(name),
+                digits = digits
+            );
+            let output_path =
+                available_path(directory, &stem, settings.codec.extension(), &mut taken);
+            let track = Track {
+                clips: vec![TimelineClip {
+                    start_time: 0.0,
+                    ..clip.clone()
+                }],
+                transitions: Vec::new(),
+                ..main.clone()
+            };
+            let plan = ExportPlan::build(
+                &[track],
+                media_library,
+                &settings,
+                project_settings,
+                keyframes,
+            )?;
+            Ok(ClipExport {
+                file: ClipExportFile {
+                    clip_id: clip.id.clone(),
+                    index: i + 1,
+                    output_path: output_path.to_string_lossy().to_string(),
+                    duration: plan.timing.render_duration,
+                },
+                plan,
+            })
+        })
+        .collect()
+}
+
+/// `name` without its extension, as a portable file name part: characters
+/// other than letters, digits, `-`, `_` and spaces become `_`
+fn file_name_part(name: &str) -> String {
+    let stem = Path::new(name)
+        .file_stem()
+        .map_or_else(|| name.to_string(), |s| s.to_string_lossy().to_string());
+    let part: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_NAME_LENGTH)
+        .collect();
+    match part.trim() {
+        "" => "clip".to_string(),
+        part => part.to_string(),
+    }
+}
+
+/// `directory/stem.extension`, or with the first `-N` suffix naming neither
+/// an existing file nor one in `taken`, which the path is added to
+fn available_path(
+    directory: &Path,
+    stem: &str,
+    extension: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let mut path = directory.join(format!("{}.{}", stem, extension));
+    let mut n = 2;
+    while taken.contains(&path) || path.exists() {
+        path = directory.join(format!("{}-{}.{}", stem, n, extension));
+        n += 1;
+    }
+    taken.insert(path.clone());
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::TrackType;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    /// 30s of 1080p media with a proxy
+    fn media(id: &str, name: &str) -> MediaClip {
+        MediaClip {
+            id: id.to_string(),
+            name: name.to_string(),
+            source_path: format!("/media/{}", name),
+            proxy_path: Some(format!("/proxies/{}.mp4", id)),
+            proxy_version: None,
+            thumbnail_path: None,
+            thumbnail_2x_path: None,
+            duration: 30.0,
+            resolution: "1920x1080".to_string(),
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            codec: "h264".to_string(),
+            audio_codec: Some("aac".to_string()),
+            file_size: 1024 * 1024,
+            bitrate: Some(5000),
+            has_audio: true,
+            imported_at: Utc::now(),
+            captions: vec![],
+            audio_streams: vec![],
+            audio_stream_index: None,
+            suggested_trim: None,
+            loudness: None,
+            origin_url: None,
+        }
+    }
+
+    fn main_track(clips: &[(&str, f64, f64, f64)]) -> Track {
+        let mut track = Track::new("Main".to_string(), TrackType::Main);
+        for (media_id, start, in_point, out_point) in clips {
+            let clip = TimelineClip::new(
+                media_id.to_string(),
+                track.id.clone(),
+                *start,
+                *in_point,
+                *out_point,
+            );
+            track.clips.push(clip);
+        }
+        track
+    }
+
+    #[test]
+    fn test_clip_file_names_never_collide() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("01_intro.mp4"), b"earlier export").unwrap();
+        let media_library =
+            source_media(&[media("a", "intro.mov"), media("b", "what's: next?.mkv")]);
+        // The same media twice, and a name with characters file systems reject
+        let tracks = vec![main_track(&[
+            ("a", 0.0, 0.0, 2.0),
+            ("b", 2.0, 0.0, 3.0),
+            ("a", 5.0, 4.0, 5.0),
+        ])];
+
+        let exports = plan_clip_exports(
+            &tracks,
+            &media_library,
+            &ExportSettings::default(),
+            &ProjectSettings::default(),
+            &HashMap::new(),
+            dir.path(),
+        )
+        .unwrap();
+        let names: Vec<String> = exports
+            .iter()
+            .map(|e| {
+                Path::new(&e.file.output_path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec!["01_intro-2.mp4", "02_what_s_ next_.mp4", "03_intro.mp4"]
+        );
+
+        let mut taken = HashSet::new();
+        let first = available_path(dir.path(), "clip", "mp4", &mut taken);
+        let second = available_path(dir.path(), "clip", "mp4", &mut taken);
+        assert_eq!(first, dir.path().join("clip.mp4"));
+        assert_eq!(second, dir.path().join("clip-2.mp4"));
+        assert_eq!(file_name_part(" .mov"), "clip");
+    }
+
+    #[test]
+    fn test_each_clip_renders_alone_from_its_source() {
+        let dir = TempDir::new().unwrap();
+        let media_library = source_media(&[media("a", "intro.mov"), media("b", "demo.mov")]);
+        let mut music = main_track(&[("b", 0.0, 0.0, 10.0)]);
+        music.track_type = TrackType::Overlay;
+        let tracks = vec![
+            main_track(&[("a", 0.0, 1.0, 3.0), ("b", 4.0, 2.5, 6.0)]),
+            music,
+        ];
+        let settings = ExportSettings {
+            range: Some(crate::models::export::ExportRange {
+                start: 0.0,
+                end: 1.0,
+            }),
+            hardware_acceleration: false,
+            ..ExportSettings::default()
+        };
+
+        let exports = plan_clip_exports(
+            &tracks,
+            &media_library,
+            &settings,
+            &ProjectSettings::default(),
+            &HashMap::new(),
+            dir.path(),
+        )
+        .unwrap();
+        let files: Vec<_> = exports
+            .iter()
+            .map(|e| (e.file.index, e.file.duration))
+            .collect();
+        assert_eq!(files, vec![(1, 2.0), (2, 3.5)]);
+
+        // The second clip starts the output, read from the source, with
+        // nothing of the other track laid over it
+        let export = &exports[1];
+        assert_eq!(export.file.clip_id, tracks[0].clips[1].id);
+        assert_eq!(export.plan.timing.range.start, 0.0);
+        let concat =
+            crate::ffmpeg::export::write_concat_file(&export.plan.segments, dir.path()).unwrap();
+        let concat = std::fs::read_to_string(concat).unwrap();
+        assert!(concat.contains("/media/demo.mov"));
+        assert!(concat.contains("inpoint 2.500000"));
+        assert!(!concat.contains("/proxies/"));
+        assert!(export.plan.streams.overlays.is_empty());
+
+        let args = export
+            .plan
+            .command_builder(
+                Path::new("/tmp/concat.txt"),
+                Path::new(&export.file.output_path),
+            )
+            .to_arg_vec();
+        assert_eq!(args.last().unwrap(), &export.file.output_path);
+        assert!(args.iter().any(|a| a == "libx264"));
+    }
+}
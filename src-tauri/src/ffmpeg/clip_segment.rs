@@ -0,0 +1,216 @@
+// Trimmed single-clip renders (double-click playback of a timeline clip)
+// The clip's in..out range is cut into its own file so the player can play it
+// start to end instead of seeking and stopping at fractional times.
+
+use crate::ffmpeg::locate::ffprobe_path;
+use crate::ffmpeg::{FfmpegCommandBuilder, FfmpegInput};
+use crate::platform::process::{self, ProcessClass};
+use std::path::Path;
+use std::process::Command;
+
+/// How the clip range is extracted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CutMode {
+    /// Copy packets starting at the keyframe matching the in-point
+    StreamCopy { keyframe: f64 },
+    /// Fast re-encode for frame-accurate cuts between keyframes
+    Reencode,
+}
+
+/// Parse `ffprobe -show_entries packet=pts_time,flags -of csv=p=0` output
+/// into sorted keyframe timestamps
+pub fn parse_keyframes(output: &str) -> Vec<f64> {
+    let mut keyframes: Vec<f64> = output
+        .lines()
+        .filter_map(|line| {
+            let (pts, flags) = line.trim().split_once(',')?;
+            if !flags.contains('K') {
+                return None;
+            }
+            pts.parse::<f64>().ok()
+        })
+        .collect();
+    keyframes.sort_by(f64::total_cmp);
+    keyframes.dedup();
+    keyframes
+}
+
+/// List keyframe timestamps of the first video stream
+pub async fn probe_keyframes(path: &str) -> Result<Vec<f64>, String> {
+    let mut cmd = Command::new(ffprobe_path());
+    cmd.args([
+        "-v",
+        "error",
+        "-select_streams",
+        "v:0",
+        "-show_entries",
+        "packet=pts_time,flags",
+        "-of",
+        "csv=p=0",
+        path,
+    ]);
+    let output = process::output(cmd, ProcessClass::Probe).await?;
+    if !output.status.success() {
+        return Err(format!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(parse_keyframes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Stream copy when the in-point is within half a frame of a keyframe
+///
+/// Copied output always starts at a keyframe, so any other in-point would
+/// show extra frames. The out-point only needs frame accuracy, which packet
+/// copying gives.
+pub fn choose_cut_mode(keyframes: &[f64], in_point: f64, fps: f64) -> CutMode {
+    let tolerance = if fps > 0.0 { 0.5 / fps } else { 0.02 };
+    keyframes
+        .iter()
+        .find(|keyframe| (*keyframe - in_point).abs() <= tolerance)
+        .map_or(CutMode::Reencode, |keyframe| CutMode::StreamCopy {
+            keyframe: *keyframe,
+        })
+}
+
+/// Cache file name for a media range; trims that change in/out get a new file
+///
+/// A selected audio stream adds an `_a<n>` suffix.
+pub fn clip_segment_file_name(
+    media_clip_id: &str,
+    in_point: f64,
+    out_point: f64,
+    audio_stream: Option<usize>,
+) -> String {
+    let audio = audio_stream.map_or_else(String::new, |n| format!("_a{}", n));
+    format!(
+        "{}_{}_{}{}.mp4",
+        media_clip_id,
+        (in_point * 1000.0).round() as i64,
+        (out_point * 1000.0).round() as i64,
+        audio
+    )
+}
+
+/// Build the FFmpeg command cutting `in_point..out_point` of `source` to `output_path`
+pub fn build_clip_segment_command(
+    source: &str,
+    in_point: f64,
+    out_point: f64,
+    mode: CutMode,
+    audio_stream: Option<usize>,
+    output_path: &Path,
+) -> FfmpegCommandBuilder {
+    // Copying must seek to the keyframe itself; input seeking snaps backwards
+    let start = match mode {
+        CutMode::StreamCopy { keyframe } => keyframe,
+        CutMode::Reencode => in_point,
+    };
+    let builder = FfmpegCommandBuilder::new().hide_banner().overwrite().input(
+        FfmpegInput::new(source)
+            .seek(start)
+            .duration((out_point - in_point).max(0.0)),
+    );
+    let builder = match mode {
+        CutMode::StreamCopy { .. } => builder
+            .option("-c", "copy")
+            .option("-avoid_negative_ts", "make_zero"),
+        CutMode::Reencode => builder
+            .video_codec("libx264")
+            .preset("veryfast")
+            .crf(20)
+            .pixel_format("yuv420p")
+            .audio_codec("aac")
+            .option("-b:a", "160k"),
+    };
+    builder
+        .select_audio_stream(0, audio_stream)
+        .option("-movflags", "+faststart")
+        .output(output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keyframes() {
+        let output = "0.000000,K__\n0.033367,___\n2.002000,K__\nN/A,K__\n1.001000,K_\n";
+        assert_eq!(parse_keyframes(output), vec![0.0, 1.001, 2.002]);
+        assert!(parse_keyframes("").is_empty());
+    }
+
+    #[test]
+    fn test_cut_mode_keyframe_alignment() {
+        let keyframes = [0.0, 1.001, 2.002, 3.003];
+
+        // Exactly on a keyframe, and within half a frame of one at 30fps
+        assert_eq!(
+            choose_cut_mode(&keyframes, 0.0, 30.0),
+            CutMode::StreamCopy { keyframe: 0.0 }
+        );
+        assert_eq!(
+            choose_cut_mode(&keyframes, 1.99, 30.0),
+            CutMode::StreamCopy { keyframe: 2.002 }
+        );
+
+        // Between keyframes
+        assert_eq!(choose_cut_mode(&keyframes, 1.5, 30.0), CutMode::Reencode);
+        // One frame after a keyframe is not aligned
+        assert_eq!(
+            choose_cut_mode(&keyframes, 1.001 + 1.0 / 30.0, 30.0),
+            CutMode::Reencode
+        );
+        // Tolerance shrinks with higher frame rates
+        assert_eq!(choose_cut_mode(&keyframes, 1.99, 120.0), CutMode::Reencode);
+        // Unprobed files are always re-encoded
+        assert_eq!(choose_cut_mode(&[], 0.0, 30.0), CutMode::Reencode);
+    }
+
+    #[test]
+    fn test_segment_commands() {
+        let copy = build_clip_segment_command(
+            "/m/a.mp4",
+            1.995,
+            5.5,
+            CutMode::StreamCopy { keyframe: 2.002 },
+            None,
+            Path::new("seg.mp4"),
+        );
+        let copy_str = copy.to_arg_vec().join(" ");
+        assert!(copy_str.contains("-ss 2.002000"));
+        assert!(copy_str.contains("-t 3.505000 -i /m/a.mp4"));
+        assert!(copy_str.contains("-c copy"));
+        assert!(!copy_str.contains("-map"));
+
+        let encode = build_clip_segment_command(
+            "/m/a.mp4",
+            1.5,
+            3.0,
+            CutMode::Reencode,
+            Some(1),
+            Path::new("seg.mp4"),
+        );
+        let encode_str = encode.to_arg_vec().join(" ");
+        assert!(encode_str.contains("libx264"));
+        assert!(!encode_str.contains("copy"));
+        assert!(encode_str.contains("-map 0:v:0? -map 0:a:1"));
+    }
+
+    #[test]
+    fn test_file_name_tracks_trim() {
+        assert_eq!(
+            clip_segment_file_name("m1", 1.5, 4.25, None),
+            "m1_1500_4250.mp4"
+        );
+        assert_ne!(
+            clip_segment_file_name("m1", 1.5, 4.25, None),
+            clip_segment_file_name("m1", 1.5, 4.3, None)
+        );
+        assert_eq!(
+            clip_segment_file_name("m1", 1.5, 4.25, Some(1)),
+            "m1_1500_4250_a1.mp4"
+        );
+    }
+}
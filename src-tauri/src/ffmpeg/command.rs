@@ -0,0 +1,343 @@
+// Shared FFmpeg command-line builder
+// Collects inputs, one video and one audio filter chain, and output options,
+// and renders them in a fixed order so features adding filters or options
+// can't produce duplicate `-vf` arguments or misplaced input options.
+
+use crate::ffmpeg::locate::ffmpeg_path;
+use std::ffi::{OsStr, OsString};
+use std::process::{Command, Stdio};
+
+/// How the caller follows encoding progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressMode {
+    /// Output is not read
+    #[default]
+    None,
+    /// Machine-readable `key=value` blocks on stdout via `-progress pipe:1`
+    Pipe,
+}
+
+/// One `-i` input with the options that must precede it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FfmpegInput {
+    options: Vec<OsString>,
+    path: OsString,
+}
+
+impl FfmpegInput {
+    pub fn new(path: impl AsRef<OsStr>) -> Self {
+        Self {
+            options: Vec::new(),
+            path: path.as_ref().to_os_string(),
+        }
+    }
+
+    /// Force the input format (`-f`)
+    pub fn format(self, format: &str) -> Self {
+        self.option("-f", format)
+    }
+
+    /// Seek before decoding (`-ss`)
+    pub fn seek(self, seconds: f64) -> Self {
+        self.option("-ss", format!("{:.6}", seconds))
+    }
+
+    /// Limit the read duration (`-t`)
+    pub fn duration(self, seconds: f64) -> Self {
+        self.option("-t", format!("{:.6}", seconds))
+    }
+
+    /// Any other input option, e.g. `("-safe", "0")`
+    pub fn option(mut self, key: &str, value: impl AsRef<OsStr>) -> Self {
+        self.options.push(key.into());
+        self.options.push(value.as_ref().to_os_string());
+        self
+    }
+}
+
+/// Stream specifier for the audio of `input`: `0:a`, or `0:a:<n>` for a selected stream
+pub fn audio_stream_spec(input: usize, audio_stream: Option<usize>) -> String {
+    match audio_stream {
+        Some(n) => format!("{}:a:{}", input, n),
+        None => format!("{}:a", input),
+    }
+}
+
+/// Builder for an FFmpeg invocation with a single output
+///
+/// Arguments are rendered as: global flags, inputs (each preceded by its
+/// options), output options in the order they were added, the filter graph,
+/// the video and audio filter chains, progress flags, then the output path.
+#[derive(Debug, Clone, Default)]
+pub struct FfmpegCommandBuilder {
+    global: Vec<OsString>,
+    inputs: Vec<FfmpegInput>,
+    output_options: Vec<OsString>,
+    filter_complex: Option<String>,
+    video_filters: Vec<String>,
+    audio_filters: Vec<String>,
+    progress: ProgressMode,
+    output: Option<OsString>,
+}
+
+impl FfmpegCommandBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overwrite the output file (`-y`)
+    pub fn overwrite(mut self) -> Self {
+        self.global.push("-y".into());
+        self
+    }
+
+    /// Suppress the version banner on stderr
+    pub fn hide_banner(mut self) -> Self {
+        self.global.push("-hide_banner".into());
+        self
+    }
+
+    /// Option placed before every input (e.g. a hardware device)
+    pub fn global_option(mut self, key: &str, value: impl AsRef<OsStr>) -> Self {
+        self.global.push(key.into());
+        self.global.push(value.as_ref().to_os_string());
+        self
+    }
+
+    pub fn input(mut self, input: FfmpegInput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Inputs added so far; the next input gets this index
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Add an input option (placed before `-i`) to every input added so far
+    pub fn input_option(mut self, key: &str, value: impl AsRef<OsStr>) -> Self {
+        self.inputs = self
+            .inputs
+            .into_iter()
+            .map(|input| input.option(key, value.as_ref()))
+            .collect();
+        self
+    }
+
+    pub fn video_codec(self, codec: &str) -> Self {
+        self.option("-c:v", codec)
+    }
+
+    pub fn audio_codec(self, codec: &str) -> Self {
+        self.option("-c:a", codec)
+    }
+
+    /// Constant rate factor for x264/x265-style encoders
+    pub fn crf(self, crf: u32) -> Self {
+        self.option("-crf", crf.to_string())
+    }
+
+    pub fn preset(self, preset: &str) -> Self {
+        self.option("-preset", preset)
+    }
+
+    /// Target video bitrate, e.g. "5M"
+    pub fn video_bitrate(self, bitrate: &str) -> Self {
+        self.option("-b:v", bitrate)
+    }
+
+    pub fn audio_bitrate_kbps(self, kbps: u32) -> Self {
+        self.option("-b:a", format!("{}k", kbps))
+    }
+
+    /// Output frame rate (`-r`)
+    pub fn frame_rate(self, fps: f64) -> Self {
+        self.option("-r", fps.to_string())
+    }
+
+    pub fn pixel_format(self, format: &str) -> Self {
+        self.option("-pix_fmt", format)
+    }
+
+    /// Append a filter to the video chain (rendered as one `-vf`)
+    pub fn video_filter(mut self, filter: impl Into<String>) -> Self {
+        self.video_filters.push(filter.into());
+        self
+    }
+
+    /// Append a filter to the audio chain (rendered as one `-af`)
+    pub fn audio_filter(mut self, filter: impl Into<String>) -> Self {
+        self.audio_filters.push(filter.into());
+        self
+    }
+
+    /// Filter graph over several inputs (`-filter_complex`)
+    ///
+    /// Its labelled outputs still need `-map`; streams mapped straight from
+    /// an input keep going through the `-vf`/`-af` chains.
+    pub fn filter_complex(mut self, graph: impl Into<String>) -> Self {
+        self.filter_complex = Some(graph.into());
+        self
+    }
+
+    /// Any other output option with a value, e.g. `("-movflags", "+faststart")`
+    pub fn option(mut self, key: &str, value: impl AsRef<OsStr>) -> Self {
+        self.output_options.push(key.into());
+        self.output_options.push(value.as_ref().to_os_string());
+        self
+    }
+
+    /// An output flag without a value, e.g. `-vn`
+    pub fn flag(mut self, flag: &str) -> Self {
+        self.output_options.push(flag.into());
+        self
+    }
+
+    /// Map the first video stream and audio stream `n` of `input`
+    ///
+    /// None keeps FFmpeg's default stream selection.
+    pub fn select_audio_stream(self, input: usize, audio_stream: Option<usize>) -> Self {
+        match audio_stream {
+            Some(n) => self
+                .option("-map", format!("{}:v:0?", input))
+                .option("-map", audio_stream_spec(input, Some(n))),
+            None => self,
+        }
+    }
+
+    pub fn progress(mut self, mode: ProgressMode) -> Self {
+        self.progress = mode;
+        self
+    }
+
+    pub fn output(mut self, path: impl AsRef<OsStr>) -> Self {
+        self.output = Some(path.as_ref().to_os_string());
+        self
+    }
+
+    fn args(&self) -> Vec<OsString> {
+        let mut args = self.global.clone();
+        for input in &self.inputs {
+            args.extend(input.options.iter().cloned());
+            args.push("-i".into());
+            args.push(input.path.clone());
+        }
+        args.extend(self.output_options.iter().cloned());
+        if let Some(graph) = &self.filter_complex {
+            args.push("-filter_complex".into());
+            args.push(graph.into());
+        }
+        if !self.video_filters.is_empty() {
+            args.push("-vf".into());
+            args.push(self.video_filters.join(",").into());
+        }
+        if !self.audio_filters.is_empty() {
+            args.push("-af".into());
+            args.push(self.audio_filters.join(",").into());
+        }
+        if self.progress == ProgressMode::Pipe {
+            args.extend(["-progress", "pipe:1", "-nostats"].map(OsString::from));
+        }
+        if let Some(output) = &self.output {
+            args.push(output.clone());
+        }
+        args
+    }
+
+    /// Arguments (without the program) as strings, for logging and tests
+    pub fn to_arg_vec(&self) -> Vec<String> {
+        self.args()
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// Blocking command using the located ffmpeg binary
+    pub fn build(&self) -> Command {
+        let mut cmd = Command::new(ffmpeg_path());
+        cmd.args(self.args());
+        if self.progress != ProgressMode::None {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argument_order() {
+        let builder = FfmpegCommandBuilder::new()
+            .overwrite()
+            .input(
+                FfmpegInput::new("/tmp/list.txt")
+                    .format("concat")
+                    .option("-safe", "0"),
+            )
+            .input(FfmpegInput::new("/m/logo.png").seek(1.5))
+            .video_filter("scale=1280:720")
+            .video_codec("libx264")
+            .crf(23)
+            .audio_filter("volume=0.5")
+            .video_filter("format=yuv420p")
+            .audio_bitrate_kbps(192)
+            .flag("-shortest")
+            .output("/out/final.mp4");
+
+        assert_eq!(
+            builder.to_arg_vec(),
+            [
+                "-y",
+                "-f",
+                "concat",
+                "-safe",
+                "0",
+                "-i",
+                "/tmp/list.txt",
+                "-ss",
+                "1.500000",
+                "-i",
+                "/m/logo.png",
+                "-c:v",
+                "libx264",
+                "-crf",
+                "23",
+                "-b:a",
+                "192k",
+                "-shortest",
+                "-vf",
+                "scale=1280:720,format=yuv420p",
+                "-af",
+                "volume=0.5",
+                "/out/final.mp4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_progress_modes() {
+        let base = FfmpegCommandBuilder::new()
+            .input(FfmpegInput::new("in.mp4"))
+            .output("out.mp4");
+        assert_eq!(base.to_arg_vec(), ["-i", "in.mp4", "out.mp4"]);
+
+        let piped = base.clone().progress(ProgressMode::Pipe).to_arg_vec();
+        assert_eq!(
+            piped,
+            ["-i", "in.mp4", "-progress", "pipe:1", "-nostats", "out.mp4"]
+        );
+    }
+
+    #[test]
+    fn test_paths_are_single_arguments() {
+        let builder = FfmpegCommandBuilder::new()
+            .input(FfmpegInput::new("/Users/me/My Videos/Bob's clip.mov"))
+            .output("/tmp/out dir/a.mp4");
+        let cmd = builder.build();
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args[1], "/Users/me/My Videos/Bob's clip.mov");
+        assert_eq!(args[2], "/tmp/out dir/a.mp4");
+    }
+}
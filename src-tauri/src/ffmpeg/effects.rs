@@ -0,0 +1,272 @@
+// Clip effect filters
+// Maps timeline clip effects and redactions to FFmpeg video filters. Export
+// and preview renders both build a clip's chain here, so the preview shows
+// what the export will render.
+
+use crate::models::timeline::{ClipEffect, Redaction, RedactionKind};
+use serde::Serialize;
+
+/// FFmpeg filter for one effect
+pub fn effect_filter(effect: &ClipEffect) -> String {
+    match *effect {
+        ClipEffect::Crop {
+            left,
+            right,
+            top,
+            bottom,
+        } => format!(
+            "crop=w=iw*{:.4}:h=ih*{:.4}:x=iw*{:.4}:y=ih*{:.4}",
+            1.0 - left - right,
+            1.0 - top - bottom,
+            left,
+            top
+        ),
+        ClipEffect::FlipH => "hflip".to_string(),
+        ClipEffect::FlipV => "vflip".to_string(),
+        ClipEffect::GaussianBlur { sigma } => format!("gblur=sigma={:.2}", sigma),
+        ClipEffect::Eq {
+            brightness,
+            contrast,
+            saturation,
+        } => format!(
+            "eq=brightness={:.3}:contrast={:.3}:saturation={:.3}",
+            brightness, contrast, saturation
+        ),
+    }
+}
+
+/// Comma-separated chain for a clip's effects in stage order (crop, flips,
+/// eq, blur), or None without effects
+pub fn effects_filter(effects: &[ClipEffect]) -> Option<String> {
+    if effects.is_empty() {
+        return None;
+    }
+    let mut ordered: Vec<&ClipEffect> = effects.iter().collect();
+    ordered.sort_by_key(|effect| effect.stage());
+    Some(
+        ordered
+            .into_iter()
+            .map(effect_filter)
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+/// A redaction resolved for one read of a clip
+///
+/// The rectangle is in fractions of the frame, so it covers the same area of
+/// a proxy; `start..end` are seconds from the start of the read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegionMask {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub start: f64,
+    pub end: f64,
+    pub kind: RedactionKind,
+}
+
+/// Masks for a read starting `offset` seconds after the clip's timeline start
+///
+/// Regions ending before the read starts are dropped.
+pub fn region_masks(
+    redactions: &[Redaction],
+    media_width: i32,
+    media_height: i32,
+    offset: f64,
+) -> Vec<RegionMask> {
+    if media_width <= 0 || media_height <= 0 {
+        return Vec::new();
+    }
+    let (w, h) = (media_width as f64, media_height as f64);
+    redactions
+        .iter()
+        .filter(|r| r.end > offset)
+        .map(|r| RegionMask {
+            x: r.x as f64 / w,
+            y: r.y as f64 / h,
+            width: r.width as f64 / w,
+            height: r.height as f64 / h,
+            start: (r.start - offset).max(0.0),
+            end: r.end - offset,
+            kind: r.kind,
+        })
+        .collect()
+}
+
+/// Filters hiding each mask in turn, from the `input` label to `output`
+///
+/// Blurred regions are cropped from a copy of the frame, box-blurred and
+/// overlaid back; boxes are drawn directly. Each is only enabled during its
+/// time range, and later masks apply on top of earlier ones.
+pub fn redaction_filters(input: &str, output: &str, masks: &[RegionMask]) -> Vec<String> {
+    let mut filters = Vec::new();
+    let mut from = input.to_string();
+    for (i, mask) in masks.iter().enumerate() {
+        let to = if i + 1 == masks.len() {
+            output.to_string()
+        } else {
+            format!("{}m{}", output, i)
+        };
+        let enable = format!("enable='between(t,{:.6},{:.6})'", mask.start, mask.end);
+        filters.push(match mask.kind {
+            RedactionKind::Box => format!(
+                "[{}]drawbox=x=iw*{:.6}:y=ih*{:.6}:w=iw*{:.6}:h=ih*{:.6}:color=black:t=fill:{}[{}]",
+                from, mask.x, mask.y, mask.width, mask.height, enable, to
+            ),
+            RedactionKind::Blur => format!(
+                "[{from}]split[{o}s{i}][{o}c{i}];\
+                 [{o}c{i}]crop=w=iw*{w:.6}:h=ih*{h:.6}:x=iw*{x:.6}:y=ih*{y:.6},\
+                 boxblur=lr='min(w,h)/4':lp=3:cr='min(cw,ch)/4':cp=3[{o}b{i}];\
+                 [{o}s{i}][{o}b{i}]overlay=x=W*{x:.6}:y=H*{y:.6}:{enable}[{to}]",
+                from = from,
+                o = output,
+                i = i,
+                w = mask.width,
+                h = mask.height,
+                x = mask.x,
+                y = mask.y,
+                enable = enable,
+                to = to
+            ),
+        });
+        from = to;
+    }
+    filters
+}
+
+/// Filters taking input `input`'s video through a clip's redactions and
+/// effects, then `tail` (sizing and timing), into `[label]`
+pub fn clip_video_filters(
+    input: usize,
+    masks: &[RegionMask],
+    effects: &[ClipEffect],
+    tail: &str,
+    label: &str,
+) -> Vec<String> {
+    let mut filters = Vec::new();
+    let mut source = format!("{}:v", input);
+    if !masks.is_empty() {
+        let redacted = format!("{}r", label);
+        filters.extend(redaction_filters(&source, &redacted, masks));
+        source = redacted;
+    }
+    let effects = effects_filter(effects).map(|f| f + ",");
+    filters.push(format!(
+        "[{}]{}{}[{}]",
+        source,
+        effects.unwrap_or_default(),
+        tail,
+        label
+    ));
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effect_filters() {
+        let crop = ClipEffect::Crop {
+            left: 0.1,
+            right: 0.2,
+            top: 0.0,
+            bottom: 0.25,
+        };
+        assert_eq!(
+            effect_filter(&crop),
+            "crop=w=iw*0.7000:h=ih*0.7500:x=iw*0.1000:y=ih*0.0000"
+        );
+        assert_eq!(effect_filter(&ClipEffect::FlipH), "hflip");
+        assert_eq!(effect_filter(&ClipEffect::FlipV), "vflip");
+        assert_eq!(
+            effect_filter(&ClipEffect::GaussianBlur { sigma: 6.5 }),
+            "gblur=sigma=6.50"
+        );
+        assert_eq!(
+            effect_filter(&ClipEffect::Eq {
+                brightness: 0.05,
+                contrast: 1.2,
+                saturation: 0.8
+            }),
+            "eq=brightness=0.050:contrast=1.200:saturation=0.800"
+        );
+    }
+
+    #[test]
+    fn test_chain_follows_stage_order() {
+        let effects = [
+            ClipEffect::GaussianBlur { sigma: 2.0 },
+            ClipEffect::Eq {
+                brightness: 0.0,
+                contrast: 1.0,
+                saturation: 1.5,
+            },
+            ClipEffect::FlipV,
+            ClipEffect::Crop {
+                left: 0.0,
+                right: 0.0,
+                top: 0.1,
+                bottom: 0.1,
+            },
+            ClipEffect::FlipH,
+        ];
+        assert_eq!(
+            effects_filter(&effects).unwrap(),
+            "crop=w=iw*1.0000:h=ih*0.8000:x=iw*0.0000:y=ih*0.1000,hflip,vflip,\
+             eq=brightness=0.000:contrast=1.000:saturation=1.500,gblur=sigma=2.00"
+        );
+        assert_eq!(effects_filter(&[]), None);
+    }
+
+    #[test]
+    fn test_overlapping_redactions_compose() {
+        // 1920x1080 source: an email blurred for 2-6s, a token boxed for 4-8s
+        let redactions = [
+            Redaction {
+                x: 192,
+                y: 108,
+                width: 960,
+                height: 54,
+                start: 2.0,
+                end: 6.0,
+                kind: RedactionKind::Blur,
+            },
+            Redaction {
+                x: 0,
+                y: 540,
+                width: 480,
+                height: 108,
+                start: 4.0,
+                end: 8.0,
+                kind: RedactionKind::Box,
+            },
+        ];
+        // Read from 1s into the clip
+        let masks = region_masks(&redactions, 1920, 1080, 1.0);
+        let filters = clip_video_filters(3, &masks, &[ClipEffect::FlipH], "fps=30", "v0");
+
+        assert_eq!(
+            filters,
+            [
+                "[3:v]split[v0rs0][v0rc0];\
+                 [v0rc0]crop=w=iw*0.500000:h=ih*0.050000:x=iw*0.100000:y=ih*0.100000,\
+                 boxblur=lr='min(w,h)/4':lp=3:cr='min(cw,ch)/4':cp=3[v0rb0];\
+                 [v0rs0][v0rb0]overlay=x=W*0.100000:y=H*0.100000:\
+                 enable='between(t,1.000000,5.000000)'[v0rm0]",
+                "[v0rm0]drawbox=x=iw*0.000000:y=ih*0.500000:w=iw*0.250000:h=ih*0.100000:\
+                 color=black:t=fill:enable='between(t,3.000000,7.000000)'[v0r]",
+                "[v0r]hflip,fps=30[v0]",
+            ]
+        );
+
+        // A read starting after a region ends leaves it out
+        assert_eq!(region_masks(&redactions, 1920, 1080, 6.5).len(), 1);
+        assert_eq!(
+            clip_video_filters(0, &[], &[], "fps=30", "v1"),
+            ["[0:v]fps=30[v1]"]
+        );
+    }
+}
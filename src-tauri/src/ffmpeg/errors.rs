@@ -0,0 +1,350 @@
+// Classification of FFmpeg failures
+// FFmpeg reports every failure as free-form stderr; the patterns below map
+// the common ones to a kind and a message the UI can show as-is, with the
+// matching stderr lines kept for the details view.
+
+use crate::platform::process::ProcessError;
+use serde::Serialize;
+use std::fmt;
+
+/// Lines of trailing stderr kept when no known pattern matches
+const UNKNOWN_EXCERPT_LINES: usize = 10;
+
+/// Lines of trailing stderr kept with a failed export job's history
+const LOG_TAIL_LINES: usize = 40;
+
+/// What went wrong, as far as the stderr tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FfmpegErrorKind {
+    FileNotFound,
+    PermissionDenied,
+    /// Input is damaged or not a media file
+    CorruptInput,
+    /// Encoder or decoder not compiled into this FFmpeg
+    MissingCodec,
+    DiskFull,
+    /// Capture device in use by another application
+    DeviceBusy,
+    /// Frame width or height the encoder can't take (odd size for 4:2:0)
+    OddDimensions,
+    /// Pixel format the encoder or filter chain can't convert
+    UnsupportedPixelFormat,
+    /// Killed after running longer than its operation allows
+    Timeout,
+    Unknown,
+}
+
+/// A classified FFmpeg failure
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FfmpegError {
+    pub kind: FfmpegErrorKind,
+    /// User-facing explanation
+    pub message: String,
+    /// Relevant stderr lines
+    pub excerpt: String,
+    /// Last lines of the stderr, whatever they say (empty when FFmpeg
+    /// never ran)
+    #[serde(skip)]
+    pub tail: String,
+}
+
+impl FfmpegError {
+    pub fn new(kind: FfmpegErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            excerpt: String::new(),
+            tail: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for FfmpegError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Failures that never reached FFmpeg (spawn errors, validation)
+impl From<String> for FfmpegError {
+    fn from(message: String) -> Self {
+        Self::new(FfmpegErrorKind::Unknown, message)
+    }
+}
+
+impl From<ProcessError> for FfmpegError {
+    fn from(error: ProcessError) -> Self {
+        let kind = match error {
+            ProcessError::Timeout { .. } => FfmpegErrorKind::Timeout,
+            _ => FfmpegErrorKind::Unknown,
+        };
+        Self::new(kind, error.to_string())
+    }
+}
+
+/// Stderr patterns in priority order (case-insensitive substrings)
+///
+/// Disk-full and permission errors are checked first because FFmpeg often
+/// follows them with generic "Invalid argument"/"Conversion failed" lines.
+const PATTERNS: &[(FfmpegErrorKind, &[&str])] = &[
+    (FfmpegErrorKind::DiskFull, &["no space left on device"]),
+    (
+        FfmpegErrorKind::PermissionDenied,
+        &["permission denied", "operation not permitted"],
+    ),
+    (
+        FfmpegErrorKind::DeviceBusy,
+        &[
+            "device or resource busy",
+            "device is in use",
+            "could not lock device",
+            "could not run graph",
+        ],
+    ),
+    (
+        FfmpegErrorKind::OddDimensions,
+        &["width not divisible by 2", "height not divisible by 2"],
+    ),
+    (
+        FfmpegErrorKind::UnsupportedPixelFormat,
+        &[
+            "incompatible pixel format",
+            "impossible to convert between the formats supported by the filter",
+        ],
+    ),
+    (
+        FfmpegErrorKind::MissingCodec,
+        &[
+            "unknown encoder",
+            "encoder not found",
+            "decoder not found",
+            "unknown decoder",
+        ],
+    ),
+    (
+        FfmpegErrorKind::CorruptInput,
+        &[
+            "invalid data found when processing input",
+            "moov atom not found",
+            "error while decoding stream",
+            "invalid nal unit size",
+        ],
+    ),
+    (
+        FfmpegErrorKind::FileNotFound,
+        &["no such file or directory"],
+    ),
+];
+
+/// Classify FFmpeg stderr output
+pub fn classify(stderr: &str) -> FfmpegError {
+    let lines: Vec<&str> = stderr.lines().map(str::trim).collect();
+    let tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+    for (kind, patterns) in PATTERNS {
+        let matching: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| {
+                let line = line.to_lowercase();
+                patterns.iter().any(|p| line.contains(p))
+            })
+            .collect();
+        if let Some(first) = matching.first() {
+            // Prefer a line naming the file ("<path>: <error>")
+            let line = matching
+                .iter()
+                .find(|line| subject(line).is_some())
+                .unwrap_or(first);
+            return FfmpegError {
+                kind: *kind,
+                message: message_for(*kind, line),
+                excerpt: matching.join("\n"),
+                tail,
+            };
+        }
+    }
+
+    let start = lines.len().saturating_sub(UNKNOWN_EXCERPT_LINES);
+    FfmpegError {
+        kind: FfmpegErrorKind::Unknown,
+        message: "FFmpeg failed unexpectedly".to_string(),
+        excerpt: lines[start..].join("\n"),
+        tail,
+    }
+}
+
+/// Path or codec named before the `: <error>` suffix of an FFmpeg line
+fn subject(line: &str) -> Option<&str> {
+    let (subject, _) = line.rsplit_once(": ")?;
+    // Drop a leading "[component @ 0x...]" tag
+    let subject = match subject.find("] ") {
+        Some(i) if subject.starts_with('[') => &subject[i + 2..],
+        _ => subject,
+    };
+    let subject = subject.trim();
+    (!subject.is_empty()).then_some(subject)
+}
+
+fn message_for(kind: FfmpegErrorKind, line: &str) -> String {
+    match kind {
+        FfmpegErrorKind::DiskFull => "Your disk is full. Free up space and try again.".to_string(),
+        FfmpegErrorKind::PermissionDenied => match subject(line) {
+            Some(path) => format!("Permission denied for {}", path),
+            None => "Permission denied while reading or writing a file".to_string(),
+        },
+        FfmpegErrorKind::DeviceBusy => {
+            "The camera or microphone is being used by another application".to_string()
+        }
+        FfmpegErrorKind::OddDimensions => {
+            "The video size has an odd width or height, which the encoder cannot handle".to_string()
+        }
+        FfmpegErrorKind::UnsupportedPixelFormat => {
+            "The encoder does not support this video's pixel format".to_string()
+        }
+        FfmpegErrorKind::MissingCodec => {
+            let codec = line
+                .split('\'')
+                .nth(1)
+                .or_else(|| line.rsplit_once(' ').map(|(_, codec)| codec));
+            match codec {
+                Some(codec) if !codec.is_empty() => {
+                    format!("This FFmpeg build does not include the '{}' codec", codec)
+                }
+                _ => "This FFmpeg build is missing a required codec".to_string(),
+            }
+        }
+        FfmpegErrorKind::CorruptInput => match subject(line) {
+            Some(path) => format!("{} is damaged or not a supported media file", path),
+            None => "A media file is damaged or not in a supported format".to_string(),
+        },
+        FfmpegErrorKind::FileNotFound => match subject(line) {
+            Some(path) => format!("File not found: {}", path),
+            None => "A file FFmpeg needed could not be found".to_string(),
+        },
+        FfmpegErrorKind::Timeout => "FFmpeg took too long and was stopped".to_string(),
+        FfmpegErrorKind::Unknown => "FFmpeg failed unexpectedly".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use FfmpegErrorKind::*;
+
+    /// (stderr sample, expected kind, expected message)
+    const SAMPLES: &[(&str, FfmpegErrorKind, &str)] = &[
+        (
+            "ffmpeg version 6.1 Copyright\n/Users/me/gone.mov: No such file or directory\n",
+            FileNotFound,
+            "File not found: /Users/me/gone.mov",
+        ),
+        (
+            "[concat @ 0x7f8b] Impossible to open '/tmp/a b.mp4'\n/tmp/list.txt: No such file or directory",
+            FileNotFound,
+            "File not found: /tmp/list.txt",
+        ),
+        (
+            "/Volumes/Locked/out.mp4: Permission denied\n",
+            PermissionDenied,
+            "Permission denied for /Volumes/Locked/out.mp4",
+        ),
+        (
+            "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'clip.mp4':\n[mov,mp4,m4a,3gp,3g2,mj2 @ 0x600] moov atom not found\n/m/clip.mp4: Invalid data found when processing input",
+            CorruptInput,
+            "/m/clip.mp4 is damaged or not a supported media file",
+        ),
+        (
+            "Unknown encoder 'libx264'\n",
+            MissingCodec,
+            "This FFmpeg build does not include the 'libx264' codec",
+        ),
+        (
+            "[vost#0:0 @ 0x55] Unknown encoder 'libfdk_aac'\nError selecting an encoder\n",
+            MissingCodec,
+            "This FFmpeg build does not include the 'libfdk_aac' codec",
+        ),
+        (
+            "frame= 1200 fps= 60 q=28.0 size=  102400kB time=00:00:40.00\n[mp4 @ 0x1] Error writing trailer: No space left on device\nav_interleaved_write_frame(): No space left on device\nConversion failed!",
+            DiskFull,
+            "Your disk is full. Free up space and try again.",
+        ),
+        (
+            "[avfoundation @ 0x7fa] Could not lock device for configuration\n",
+            DeviceBusy,
+            "The camera or microphone is being used by another application",
+        ),
+        (
+            "[dshow @ 000001] Could not run graph (sometimes caused by a device already in use by other application)\nvideo=Integrated Camera: I/O error",
+            DeviceBusy,
+            "The camera or microphone is being used by another application",
+        ),
+        (
+            "[video4linux2,v4l2 @ 0x5] ioctl(VIDIOC_STREAMON): Device or resource busy\n",
+            DeviceBusy,
+            "The camera or microphone is being used by another application",
+        ),
+        (
+            "[libx264 @ 0x6] width not divisible by 2 (1281x720)\n[vost#0:0/libx264 @ 0x5] Error while opening encoder\nConversion failed!",
+            OddDimensions,
+            "The video size has an odd width or height, which the encoder cannot handle",
+        ),
+        (
+            "[graph 0 input from stream 0:0 @ 0x7] Impossible to convert between the formats supported by the filter 'Parsed_null_0' and the filter 'auto_scale_0'\n",
+            UnsupportedPixelFormat,
+            "The encoder does not support this video's pixel format",
+        ),
+        (
+            "Some new failure nobody has seen\nConversion failed!",
+            Unknown,
+            "FFmpeg failed unexpectedly",
+        ),
+    ];
+
+    #[test]
+    fn test_classify_samples() {
+        for (stderr, kind, message) in SAMPLES {
+            let error = classify(stderr);
+            assert_eq!(error.kind, *kind, "kind for {:?}", stderr);
+            assert_eq!(error.message, *message, "message for {:?}", stderr);
+            assert!(!error.excerpt.is_empty(), "excerpt for {:?}", stderr);
+        }
+    }
+
+    #[test]
+    fn test_excerpts() {
+        // Every matching line is kept, unrelated progress lines are not
+        let error = classify(SAMPLES[6].0);
+        assert_eq!(
+            error.excerpt,
+            "[mp4 @ 0x1] Error writing trailer: No space left on device\nav_interleaved_write_frame(): No space left on device"
+        );
+
+        // Unknown failures keep the tail of the output
+        let stderr: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+        let error = classify(&stderr);
+        assert_eq!(error.excerpt.lines().count(), 10);
+        assert!(error.excerpt.ends_with("line 29"));
+
+        // The tail is kept however the failure was classified
+        let stderr = format!("{}{}", stderr.repeat(2), SAMPLES[6].0);
+        let error = classify(&stderr);
+        assert_eq!(error.kind, DiskFull);
+        assert_eq!(error.tail.lines().count(), 40);
+        assert!(error.tail.starts_with("line ") && error.tail.ends_with("Conversion failed!"));
+    }
+
+    #[test]
+    fn test_display_and_conversion() {
+        let error = FfmpegError::from("Failed to spawn FFmpeg process".to_string());
+        assert_eq!(error.kind, Unknown);
+        assert_eq!(error.to_string(), "Failed to spawn FFmpeg process");
+
+        let error = FfmpegError::from(ProcessError::Timeout {
+            program: "ffmpeg".to_string(),
+            seconds: 60,
+        });
+        assert_eq!(error.kind, Timeout);
+        assert_eq!(error.to_string(), "ffmpeg did not finish within 60 seconds");
+    }
+}
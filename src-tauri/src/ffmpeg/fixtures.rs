@@ -0,0 +1,261 @@
+// Generated media fixtures for tests that run the real FFmpeg
+// Clips are synthesized with lavfi (`testsrc2` video, `sine` audio) at test
+// time, so no binary fixtures live in the repo and every run starts from the
+// same bit-exact input. Built for unit tests and for the `ffmpeg-tests`
+// integration suite in tests/.
+
+use crate::ffmpeg::locate;
+use crate::ffmpeg::metadata::{probe_full, FullProbeResult};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A clip to generate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub duration: f64,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    /// FFmpeg encoder name, e.g. "libx264", "mpeg4"
+    pub video_codec: &'static str,
+    /// Stereo 440 Hz tone encoded as AAC; None for a silent (video-only) clip
+    pub audio_channels: Option<u32>,
+    /// Container extension, e.g. "mp4", "mov"
+    pub extension: &'static str,
+}
+
+impl Fixture {
+    /// 320x240 30 fps H.264 with stereo AAC, in MP4
+    pub fn h264(duration: f64) -> Self {
+        Fixture {
+            duration,
+            width: 320,
+            height: 240,
+            fps: 30,
+            video_codec: "libx264",
+            audio_channels: Some(2),
+            extension: "mp4",
+        }
+    }
+
+    /// 320x240 25 fps MPEG-4 Part 2 with stereo AAC, in MOV (needs a proxy)
+    pub fn mpeg4(duration: f64) -> Self {
+        Fixture {
+            fps: 25,
+            video_codec: "mpeg4",
+            extension: "mov",
+            ..Fixture::h264(duration)
+        }
+    }
+
+    pub fn without_audio(self) -> Self {
+        Fixture {
+            audio_channels: None,
+            ..self
+        }
+    }
+
+    /// FFmpeg arguments writing this clip to `output`
+    pub fn ffmpeg_args(&self, output: &Path) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "-y".into(),
+            "-hide_banner".into(),
+            "-loglevel".into(),
+            "error".into(),
+            "-f".into(),
+            "lavfi".into(),
+            "-i".into(),
+            format!(
+                "testsrc2=size={}x{}:rate={}:duration={}",
+                self.width, self.height, self.fps, self.duration
+            ),
+        ];
+        if let Some(channels) = self.audio_channels {
+            args.extend([
+                "-f".into(),
+                "lavfi".into(),
+                "-i".into(),
+                format!(
+                    "sine=frequency=440:sample_rate=48000:duration={}",
+                    self.duration
+                ),
+                "-ac".into(),
+                channels.to_string(),
+                "-c:a".into(),
+                "aac".into(),
+            ]);
+        }
+        args.extend([
+            "-c:v".into(),
+            self.video_codec.into(),
+            "-pix_fmt".into(),
+            "yuv420p".into(),
+            // A keyframe every second keeps trims cheap and predictable
+            "-g".into(),
+            self.fps.to_string(),
+            "-fflags".into(),
+            "+bitexact".into(),
+            "-flags:v".into(),
+            "+bitexact".into(),
+            "-flags:a".into(),
+            "+bitexact".into(),
+        ]);
+        args.push(output.to_string_lossy().into_owned());
+        args
+    }
+
+    /// Write the clip to `<dir>/<name>.<extension>`
+    pub fn generate(&self, dir: &Path, name: &str) -> Result<PathBuf, String> {
+        let output = dir.join(format!("{}.{}", name, self.extension));
+        let result = Command::new(locate::ffmpeg_path())
+            .args(self.ffmpeg_args(&output))
+            .output()
+            .map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+        if !result.status.success() {
+            return Err(format!(
+                "Failed to generate fixture {}: {}",
+                output.display(),
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+        Ok(output)
+    }
+}
+
+/// Locate FFmpeg and make the pipeline use it; false (with a note on stderr)
+/// when it isn't installed, so callers can skip instead of failing
+pub fn use_system_ffmpeg() -> bool {
+    let status = locate::locate(None, None);
+    locate::install(&status);
+    if status.paths().is_none() {
+        eprintln!(
+            "FFmpeg not found, skipping: {}",
+            status.error.as_deref().unwrap_or(locate::FFMPEG_NOT_FOUND)
+        );
+        return false;
+    }
+    true
+}
+
+/// What a media file is expected to contain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedMedia {
+    pub duration: f64,
+    /// Allowed difference from `duration`, in seconds
+    pub tolerance: f64,
+    pub video_codec: Option<&'static str>,
+    pub audio_streams: usize,
+}
+
+impl ExpectedMedia {
+    /// `duration` seconds of video in `video_codec` and one audio stream,
+    /// within two frames at 30 fps plus AAC padding
+    pub fn new(duration: f64, video_codec: &'static str) -> Self {
+        ExpectedMedia {
+            duration,
+            tolerance: 0.1,
+            video_codec: Some(video_codec),
+            audio_streams: 1,
+        }
+    }
+
+    /// Mismatches between `probe` and this expectation (empty if it matches)
+    pub fn mismatches(&self, probe: &FullProbeResult) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        match probe.duration {
+            Some(duration) if (duration - self.duration).abs() <= self.tolerance => {}
+            other => mismatches.push(format!(
+                "duration {:?}, expected {}±{}",
+                other, self.duration, self.tolerance
+            )),
+        }
+        let video_codec = probe
+            .streams_of_type("video")
+            .next()
+            .and_then(|s| s.codec.as_deref());
+        if video_codec != self.video_codec {
+            mismatches.push(format!(
+                "video codec {:?}, expected {:?}",
+                video_codec, self.video_codec
+            ));
+        }
+        let audio_streams = probe.streams_of_type("audio").count();
+        if audio_streams != self.audio_streams {
+            mismatches.push(format!(
+                "{} audio stream(s), expected {}",
+                audio_streams, self.audio_streams
+            ));
+        }
+        mismatches
+    }
+}
+
+/// Probe `path` with ffprobe and fail with every mismatch against `expected`
+pub async fn assert_media(path: &Path, expected: &ExpectedMedia) -> FullProbeResult {
+    let probe = probe_full(&path.to_string_lossy())
+        .await
+        .unwrap_or_else(|e| panic!("ffprobe failed on {}: {}", path.display(), e));
+    let mismatches = expected.mismatches(&probe);
+    assert!(
+        mismatches.is_empty(),
+        "{}: {}",
+        path.display(),
+        mismatches.join("; ")
+    );
+    probe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaStream;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_fixture_args() {
+        let args = Fixture::mpeg4(1.5).ffmpeg_args(Path::new("/tmp/a.mov"));
+        let joined = args.join(" ");
+        assert!(joined.contains("-f lavfi -i testsrc2=size=320x240:rate=25:duration=1.5"));
+        assert!(joined.contains("sine=frequency=440:sample_rate=48000:duration=1.5"));
+        assert!(joined.contains("-c:v mpeg4"));
+        assert!(joined.contains("-g 25"));
+        assert_eq!(args.last().map(String::as_str), Some("/tmp/a.mov"));
+
+        let silent = Fixture::h264(1.0)
+            .without_audio()
+            .ffmpeg_args(Path::new("/tmp/b.mp4"));
+        assert!(!silent.iter().any(|a| a.starts_with("sine")));
+        assert!(!silent.contains(&"aac".to_string()));
+    }
+
+    #[test]
+    fn test_expected_media_mismatches() {
+        let stream = |stream_type: &str, codec: &str| MediaStream {
+            index: 0,
+            stream_type: stream_type.into(),
+            codec: Some(codec.into()),
+            language: None,
+            title: None,
+            channels: None,
+            channel_layout: None,
+        };
+        let probe = FullProbeResult {
+            format_name: None,
+            duration: Some(2.02),
+            bitrate: None,
+            tags: BTreeMap::new(),
+            streams: vec![stream("video", "h264"), stream("audio", "aac")],
+            chapters: Vec::new(),
+        };
+
+        assert!(ExpectedMedia::new(2.0, "h264")
+            .mismatches(&probe)
+            .is_empty());
+        let mismatches = ExpectedMedia {
+            audio_streams: 0,
+            ..ExpectedMedia::new(3.0, "hevc")
+        }
+        .mismatches(&probe);
+        assert_eq!(mismatches.len(), 3, "{:?}", mismatches);
+    }
+}
@@ -0,0 +1,433 @@
+// Single-frame extraction at a timeline position (scrub bar / program monitor)
+
+use crate::ffmpeg::locate::ffmpeg_path;
+use crate::models::clip::MediaClip;
+use crate::models::playback::{main_track, playback_file};
+use crate::models::project::{Project, ProjectSettings};
+use crate::models::timeline::{TimelineClip, Track, TrackType, Transform};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+/// A source frame contributing to the timeline frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameLayer {
+    /// File to read (proxy when available)
+    pub path: String,
+    /// Timestamp within the file
+    pub source_time: f64,
+    /// Overlay placement in project canvas pixels
+    pub transform: Option<Transform>,
+}
+
+/// Everything visible at one timeline position
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameSources {
+    /// Main track clip under the playhead (None = gap)
+    pub main: Option<FrameLayer>,
+    /// Visible overlay clips, bottom to top
+    pub overlays: Vec<FrameLayer>,
+}
+
+/// Resolve the clips under timeline `time`
+pub fn resolve_frame_sources(project: &Project, time: f64) -> FrameSources {
+    let main = main_track(project).and_then(|track| layer_at(project, track, time));
+
+    let mut overlay_tracks: Vec<&Track> = project
+        .tracks
+        .iter()
+        .filter(|t| matches!(t.track_type, TrackType::Overlay) && t.visible)
+        .collect();
+    overlay_tracks.sort_by_key(|t| t.order);
+
+    FrameSources {
+        main,
+        overlays: overlay_tracks
+            .into_iter()
+            .filter_map(|track| layer_at(project, track, time))
+            .collect(),
+    }
+}
+
+/// The clip on `track` covering `time`, the top one where clips overlap
+fn clip_at(track: &Track, time: f64) -> Option<&TimelineClip> {
+    track
+        .clips
+        .iter()
+        .filter(|c| c.start_time <= time && time < c.end_time())
+        .max_by_key(|c| c.layer_order)
+}
+
+/// Source timestamp of the frame `clip` shows at `time`
+fn frame_source_time(clip: &TimelineClip, media: &MediaClip, time: f64) -> f64 {
+    // The last held frame is a frame before `out_point`, which may be the end of the file
+    clip.source_time(time)
+        .min(clip.out_point - 1.0 / media.fps.max(1.0))
+        .max(clip.in_point)
+}
+
+/// The clip on `track` covering `time`, mapped to its source timestamp
+fn layer_at(project: &Project, track: &Track, time: f64) -> Option<FrameLayer> {
+    let clip = clip_at(track, time)?;
+    let media = project
+        .media_library
+        .iter()
+        .find(|m| m.id == clip.media_clip_id)?;
+
+    Some(FrameLayer {
+        path: playback_file(media, true).0,
+        source_time: frame_source_time(clip, media, time),
+        transform: clip.transform.clone(),
+    })
+}
+
+/// The clip whose frame fills the canvas at timeline `time` (None in a gap)
+///
+/// Overlay clips without a transform cover the whole canvas, so the topmost
+/// visible one wins; picture-in-picture overlays are skipped in favour of
+/// the main track clip under them.
+pub fn full_frame_clip_at(project: &Project, time: f64) -> Option<&TimelineClip> {
+    let mut overlay_tracks: Vec<&Track> = project
+        .tracks
+        .iter()
+        .filter(|t| matches!(t.track_type, TrackType::Overlay) && t.visible)
+        .collect();
+    overlay_tracks.sort_by_key(|t| std::cmp::Reverse(t.order));
+
+    overlay_tracks
+        .into_iter()
+        .filter_map(|track| clip_at(track, time))
+        .find(|clip| clip.transform.is_none())
+        .or_else(|| main_track(project).and_then(|track| clip_at(track, time)))
+}
+
+/// A source frame saved by a still export
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceFrame {
+    pub clip_id: String,
+    pub media_clip_id: String,
+    /// The source file, never the proxy
+    pub path: String,
+    /// Timestamp within the file
+    pub source_time: f64,
+}
+
+impl SourceFrame {
+    /// The frame `clip` of `media` shows at timeline `time`
+    pub fn new(clip: &TimelineClip, media: &MediaClip, time: f64) -> Self {
+        Self {
+            clip_id: clip.id.clone(),
+            media_clip_id: media.id.clone(),
+            path: media.source_path.clone(),
+            source_time: frame_source_time(clip, media, time),
+        }
+    }
+}
+
+/// Image format of a still export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StillFormat {
+    Png,
+    Jpeg,
+}
+
+impl StillFormat {
+    /// Format named by `path`'s extension: .png, .jpg or .jpeg, in any case
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(StillFormat::Png),
+            "jpg" | "jpeg" => Some(StillFormat::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+/// Build the FFmpeg command writing `frame` to `output_path` at the
+/// source's own resolution
+pub fn build_still_command(
+    frame: &SourceFrame,
+    format: StillFormat,
+    output_path: &Path,
+) -> Command {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y"])
+        .arg("-ss")
+        .arg(format!("{:.6}", frame.source_time))
+        .arg("-i")
+        .arg(&frame.path)
+        .args(["-frames:v", "1"]);
+    match format {
+        StillFormat::Png => cmd.args(["-c:v", "png"]),
+        StillFormat::Jpeg => cmd.args(["-c:v", "mjpeg", "-q:v", "2"]),
+    };
+    cmd.args(["-update", "1", "-f", "image2"]).arg(output_path);
+    cmd
+}
+
+/// Frame size for a project canvas limited to `max_width` (even dimensions)
+pub fn frame_dimensions(settings: &ProjectSettings, max_width: u32) -> (u32, u32) {
+    let width = settings.width.min(max_width.max(2)) & !1;
+    let height = ((settings.height as f64 * width as f64 / settings.width as f64).round() as u32
+        & !1)
+        .max(2);
+    (width, height)
+}
+
+/// Build the FFmpeg command writing the frame as a JPEG to `output_path`
+///
+/// Gaps render the project background color. Overlays are only drawn when
+/// `composite` is set; skipping them is faster while dragging.
+pub fn build_frame_command(
+    sources: &FrameSources,
+    settings: &ProjectSettings,
+    max_width: u32,
+    composite: bool,
+    output_path: &Path,
+) -> Command {
+    let (width, height) = frame_dimensions(settings, max_width);
+    let scale = width as f64 / settings.width as f64;
+
+    let mut layers: Vec<&FrameLayer> = sources.main.iter().collect();
+    if composite {
+        layers.extend(&sources.overlays);
+    }
+
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y"]);
+
+    let mut filters = vec![format!(
+        "color=c={}:s={}x{}:d=1[base0]",
+        settings.ffmpeg_background_color(),
+        width,
+        height
+    )];
+    for (i, layer) in layers.iter().enumerate() {
+        cmd.arg("-ss")
+            .arg(format!("{:.6}", layer.source_time))
+            .arg("-i")
+            .arg(&layer.path);
+
+        let (fit, position) = match &layer.transform {
+            Some(t) => (
+                format!(
+                    "scale={}:{}",
+                    ((t.width as f64 * scale).round() as u32).max(1),
+                    ((t.height as f64 * scale).round() as u32).max(1)
+                ),
+                format!(
+                    "{}:{}",
+                    (t.x as f64 * scale).round() as i64,
+                    (t.y as f64 * scale).round() as i64
+                ),
+            ),
+            None => (
+                format!(
+                    "scale={}:{}:force_original_aspect_ratio=decrease",
+                    width, height
+                ),
+                "(W-w)/2:(H-h)/2".to_string(),
+            ),
+        };
+        filters.push(format!("[{}:v]{}[layer{}]", i, fit, i));
+        filters.push(format!(
+            "[base{}][layer{}]overlay={}:shortest=1[base{}]",
+            i,
+            i,
+            position,
+            i + 1
+        ));
+    }
+
+    cmd.arg("-filter_complex")
+        .arg(filters.join(";"))
+        .arg("-map")
+        .arg(format!("[base{}]", layers.len()))
+        .args(["-frames:v", "1", "-q:v", "3", "-f", "image2"])
+        .arg(output_path);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaClip;
+
+    /// Main: A (in 10s) at 0-4s, gap 4-6s, B at 6-10s. Overlay: C at 1-3s.
+    fn project() -> Project {
+        let mut project = Project::new("Frames".to_string());
+        let a = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        let mut b = MediaClip::new("/m/b.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        b.proxy_path = Some("/cache/b_proxy.mp4".into());
+        let c = MediaClip::new("/m/c.png".into(), 5.0, 640, 360, 30.0, "png".into(), 1);
+
+        let main_id = project.tracks[0].id.clone();
+        project.tracks[0].clips = vec![
+            TimelineClip::new(a.id.clone(), main_id.clone(), 0.0, 10.0, 14.0),
+            TimelineClip::new(b.id.clone(), main_id, 6.0, 0.5, 4.5),
+        ];
+        let mut overlay = Track::new("Overlay".into(), TrackType::Overlay);
+        overlay.order = 1;
+        let mut logo = TimelineClip::new(c.id.clone(), overlay.id.clone(), 1.0, 0.0, 2.0);
+        logo.transform = Some(Transform {
+            x: 1600,
+            y: 40,
+            width: 280,
+            height: 160,
+            rotation: 0.0,
+        });
+        overlay.clips.push(logo);
+        project.tracks.push(overlay);
+        project.media_library = vec![a, b, c];
+        project
+    }
+
+    #[test]
+    fn test_resolves_trimmed_clip_time() {
+        let project = project();
+
+        let frame = resolve_frame_sources(&project, 2.5);
+        let main = frame.main.unwrap();
+        assert_eq!(main.path, "/m/a.mp4");
+        assert_eq!(main.source_time, 12.5);
+        assert_eq!(frame.overlays.len(), 1);
+        assert_eq!(frame.overlays[0].source_time, 1.5);
+
+        // Later clip uses its proxy and its own in-point
+        let frame = resolve_frame_sources(&project, 7.0);
+        let main = frame.main.unwrap();
+        assert_eq!(main.path, "/cache/b_proxy.mp4");
+        assert_eq!(main.source_time, 1.5);
+        assert!(frame.overlays.is_empty());
+    }
+
+    #[test]
+    fn test_still_maps_to_trimmed_source_frame() {
+        let mut project = project();
+
+        // The logo overlay is picture-in-picture, so the main clip is taken
+        let clip = full_frame_clip_at(&project, 2.5).unwrap();
+        let frame = SourceFrame::new(clip, &project.media_library[0], 2.5);
+        assert_eq!(frame.clip_id, project.tracks[0].clips[0].id);
+        assert_eq!(frame.path, "/m/a.mp4");
+        assert_eq!(frame.source_time, 12.5);
+
+        // The source, not the proxy
+        let clip = full_frame_clip_at(&project, 7.0).unwrap();
+        let frame = SourceFrame::new(clip, &project.media_library[1], 7.0);
+        assert_eq!((frame.path.as_str(), frame.source_time), ("/m/b.mp4", 1.5));
+
+        assert!(full_frame_clip_at(&project, 5.0).is_none());
+
+        // A full-frame overlay covers the main track, until it is hidden
+        let cutaway = project.media_library[1].id.clone();
+        let overlay_id = project.tracks[1].id.clone();
+        project.tracks[1]
+            .clips
+            .push(TimelineClip::new(cutaway, overlay_id, 8.0, 20.0, 21.0));
+        let clip = full_frame_clip_at(&project, 8.25).unwrap();
+        assert_eq!(clip.in_point, 20.0);
+        assert_eq!(
+            SourceFrame::new(clip, &project.media_library[1], 8.25).source_time,
+            20.25
+        );
+        project.tracks[1].visible = false;
+        assert_eq!(full_frame_clip_at(&project, 8.25).unwrap().in_point, 0.5);
+    }
+
+    #[test]
+    fn test_still_command() {
+        assert_eq!(
+            StillFormat::from_path(Path::new("/t/poster.PNG")),
+            Some(StillFormat::Png)
+        );
+        assert_eq!(
+            StillFormat::from_path(Path::new("poster.jpeg")),
+            Some(StillFormat::Jpeg)
+        );
+        assert_eq!(StillFormat::from_path(Path::new("poster.gif")), None);
+        assert_eq!(StillFormat::from_path(Path::new("poster")), None);
+
+        let frame = SourceFrame {
+            clip_id: "c".into(),
+            media_clip_id: "m".into(),
+            path: "/m/a.mov".into(),
+            source_time: 12.5,
+        };
+        let cmd = format!(
+            "{:?}",
+            build_still_command(&frame, StillFormat::Jpeg, Path::new("/t/p.jpg"))
+        );
+        assert!(cmd.contains(r#""-ss" "12.500000" "-i" "/m/a.mov" "-frames:v" "1""#));
+        assert!(cmd.contains(r#""-c:v" "mjpeg""#));
+        assert!(!cmd.contains("scale"));
+    }
+
+    #[test]
+    fn test_clip_boundaries() {
+        let project = project();
+        // Start is inclusive, end exclusive
+        assert_eq!(
+            resolve_frame_sources(&project, 0.0)
+                .main
+                .unwrap()
+                .source_time,
+            10.0
+        );
+        assert!(resolve_frame_sources(&project, 4.0).main.is_none());
+        assert_eq!(
+            resolve_frame_sources(&project, 6.0)
+                .main
+                .unwrap()
+                .source_time,
+            0.5
+        );
+        assert!(resolve_frame_sources(&project, 10.0).main.is_none());
+    }
+
+    #[test]
+    fn test_hidden_overlays_are_skipped() {
+        let mut project = project();
+        project.tracks[1].visible = false;
+        assert!(resolve_frame_sources(&project, 2.0).overlays.is_empty());
+    }
+
+    #[test]
+    fn test_gap_renders_background_only() {
+        let project = project();
+        let sources = resolve_frame_sources(&project, 5.0);
+        assert_eq!(sources, FrameSources::default());
+
+        let cmd = build_frame_command(&sources, &project.settings, 640, true, Path::new("f.jpg"));
+        let cmd_str = format!("{:?}", cmd);
+        assert!(cmd_str.contains("color=c=0x000000:s=640x360:d=1[base0]"));
+        assert!(cmd_str.contains("\"-map\" \"[base0]\""));
+        assert!(!cmd_str.contains("\"-i\""));
+    }
+
+    #[test]
+    fn test_composite_flag_controls_overlays() {
+        let project = project();
+        let sources = resolve_frame_sources(&project, 2.0);
+
+        let fast = build_frame_command(&sources, &project.settings, 960, false, Path::new("f.jpg"));
+        let fast_str = format!("{:?}", fast);
+        assert!(fast_str.contains("\"-map\" \"[base1]\""));
+        assert!(!fast_str.contains("c.png"));
+
+        let full = build_frame_command(&sources, &project.settings, 960, true, Path::new("f.jpg"));
+        let full_str = format!("{:?}", full);
+        assert!(full_str.contains("\"-map\" \"[base2]\""));
+        // Overlay transform scaled from 1920 to 960 wide
+        assert!(full_str.contains("[1:v]scale=140:80[layer1]"));
+        assert!(full_str.contains("overlay=800:20"));
+    }
+
+    #[test]
+    fn test_frame_dimensions() {
+        let settings = ProjectSettings::default();
+        assert_eq!(frame_dimensions(&settings, 640), (640, 360));
+        assert_eq!(frame_dimensions(&settings, 4000), (1920, 1080));
+        assert_eq!(frame_dimensions(&settings, 641), (640, 360));
+    }
+}
@@ -0,0 +1,487 @@
+// FFmpeg/ffprobe discovery and capability check
+// The binaries are resolved once at startup (explicit setting, then PATH, then
+// common install locations and the app's resource dir) and every FFmpeg call
+// site spawns the resolved absolute path instead of relying on PATH.
+
+use crate::error::ClipForgeError;
+use crate::ffmpeg::capabilities;
+use crate::ffmpeg::managed::{installed, managed_bin_dir};
+use crate::platform::process::{self, ProcessClass};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+/// Oldest FFmpeg release whose filters and flags the app relies on
+pub const MIN_FFMPEG_VERSION: (u32, u32) = (4, 4);
+
+/// Shown by features that need FFmpeg when it could not be located
+pub const FFMPEG_NOT_FOUND: &str =
+    "FFmpeg not found. Install FFmpeg or set its location in Settings.";
+
+/// Where the binaries were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocateSource {
+    Setting,
+    /// Downloaded into ~/.clipforge/bin by the app
+    Managed,
+    Path,
+    CommonLocation,
+    Bundled,
+}
+
+/// Resolved binary paths
+#[derive(Debug, Clone, PartialEq)]
+pub struct FfmpegPaths {
+    pub ffmpeg: PathBuf,
+    pub ffprobe: PathBuf,
+}
+
+/// Result of locating and checking FFmpeg
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FfmpegStatus {
+    /// Found, new enough and built with every required component
+    pub available: bool,
+    pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+    /// Version string as reported by `ffmpeg -version`
+    pub version: Option<String>,
+    pub source: Option<LocateSource>,
+    /// Version of the app-managed build, if one is installed (used or not)
+    pub managed_version: Option<String>,
+    /// Required components missing from the build (e.g. "libx264")
+    pub missing_components: Vec<String>,
+    /// Why FFmpeg is unavailable or degraded
+    pub error: Option<String>,
+}
+
+impl FfmpegStatus {
+    /// Binary paths, if both binaries were found (even when degraded)
+    pub fn paths(&self) -> Option<FfmpegPaths> {
+        Some(FfmpegPaths {
+            ffmpeg: PathBuf::from(self.ffmpeg_path.as_ref()?),
+            ffprobe: PathBuf::from(self.ffprobe_path.as_ref()?),
+        })
+    }
+}
+
+/// Paths used by every FFmpeg call site; mirrors the status in `AppState`
+static ACTIVE_PATHS: RwLock<Option<FfmpegPaths>> = RwLock::new(None);
+
+/// Make `status` the binaries every FFmpeg call site spawns
+pub fn install(status: &FfmpegStatus) {
+    *ACTIVE_PATHS.write().unwrap() = status.paths();
+    // Another binary may have other encoders
+    capabilities::reset();
+}
+
+/// Path of the `ffmpeg` binary to spawn (bare name if it was not located)
+pub fn ffmpeg_path() -> PathBuf {
+    ACTIVE_PATHS
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or_else(|| PathBuf::from("ffmpeg"), |paths| paths.ffmpeg.clone())
+}
+
+/// Path of the `ffprobe` binary to spawn (bare name if it was not located)
+pub fn ffprobe_path() -> PathBuf {
+    ACTIVE_PATHS
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or_else(|| PathBuf::from("ffprobe"), |paths| paths.ffprobe.clone())
+}
+
+/// Fail with a clear message when FFmpeg was not located
+pub fn require_ffmpeg() -> Result<(), ClipForgeError> {
+    if ACTIVE_PATHS.read().unwrap().is_some() {
+        Ok(())
+    } else {
+        Err(ClipForgeError::MissingDependency {
+            which: "ffmpeg".to_string(),
+            message: FFMPEG_NOT_FOUND.to_string(),
+        })
+    }
+}
+
+fn binary_name(name: &str, windows: bool) -> String {
+    if windows {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Directories to search in priority order
+///
+/// `explicit` may name the ffmpeg binary itself or the directory containing it.
+pub fn candidate_dirs(
+    explicit: Option<&Path>,
+    managed_dir: Option<&Path>,
+    path_var: Option<OsString>,
+    resource_dir: Option<&Path>,
+    windows: bool,
+) -> Vec<(LocateSource, PathBuf)> {
+    let mut dirs = Vec::new();
+    if let Some(explicit) = explicit {
+        let dir = if explicit.is_file()
+            || explicit.file_name() == Some(binary_name("ffmpeg", windows).as_ref())
+        {
+            explicit.parent().unwrap_or(explicit)
+        } else {
+            explicit
+        };
+        dirs.push((LocateSource::Setting, dir.to_path_buf()));
+    }
+    if let Some(managed_dir) = managed_dir {
+        dirs.push((LocateSource::Managed, managed_dir.to_path_buf()));
+    }
+    if let Some(path_var) = path_var {
+        for dir in std::env::split_paths(&path_var) {
+            if !dir.as_os_str().is_empty() {
+                dirs.push((LocateSource::Path, dir));
+            }
+        }
+    }
+    let common: &[&str] = if windows {
+        &[
+            r"C:\ffmpeg\bin",
+            r"C:\Program Files\ffmpeg\bin",
+            r"C:\ProgramData\chocolatey\bin",
+        ]
+    } else {
+        &[
+            "/opt/homebrew/bin",
+            "/usr/local/bin",
+            "/opt/local/bin",
+            "/usr/bin",
+            "/snap/bin",
+        ]
+    };
+    for dir in common {
+        dirs.push((LocateSource::CommonLocation, PathBuf::from(dir)));
+    }
+    if let Some(resource_dir) = resource_dir {
+        dirs.push((LocateSource::Bundled, resource_dir.to_path_buf()));
+        dirs.push((LocateSource::Bundled, resource_dir.join("bin")));
+    }
+    dirs
+}
+
+/// Parse `(major, minor)` from the first line of `ffmpeg -version`
+///
+/// Git snapshot builds ("N-112345-g...") have no release number and yield None.
+pub fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let version = output
+        .lines()
+        .next()?
+        .strip_prefix("ffmpeg version ")?
+        .split_whitespace()
+        .next()?;
+    let version = version.trim_start_matches('n');
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// Required components disabled in the `ffmpeg -buildconf` output
+pub fn missing_components(buildconf: &str) -> Vec<String> {
+    let flags: Vec<&str> = buildconf.split_whitespace().collect();
+    let has = |flag: &str| flags.contains(&flag);
+    let mut missing = Vec::new();
+    if !has("--enable-libx264") {
+        missing.push("libx264".to_string());
+    }
+    if has("--disable-encoder=aac") || (has("--disable-encoders") && !has("--enable-encoder=aac")) {
+        missing.push("aac".to_string());
+    }
+    if has("--disable-demuxer=concat")
+        || (has("--disable-demuxers") && !has("--enable-demuxer=concat"))
+    {
+        missing.push("concat".to_string());
+    }
+    missing
+}
+
+/// Encoder names in `ffmpeg -encoders` output
+pub fn parse_encoders(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Encoders the located FFmpeg was built with
+pub async fn available_encoders() -> Result<Vec<String>, String> {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-encoders"]);
+    let output = process::output(cmd, ProcessClass::Probe)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err("ffmpeg -encoders failed".to_string());
+    }
+    Ok(parse_encoders(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn run(binary: &Path, arg: &str) -> Result<String, String> {
+    let output = Command::new(binary)
+        .arg(arg)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", binary.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("{} {} failed", binary.display(), arg));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Run the version and build checks against binaries in `dir`
+fn check(source: LocateSource, ffmpeg: PathBuf, ffprobe: PathBuf) -> FfmpegStatus {
+    let mut status = FfmpegStatus {
+        ffmpeg_path: Some(ffmpeg.to_string_lossy().to_string()),
+        ffprobe_path: Some(ffprobe.to_string_lossy().to_string()),
+        source: Some(source),
+        ..Default::default()
+    };
+    let version_output = match run(&ffmpeg, "-version").and_then(|out| {
+        run(&ffprobe, "-version")?;
+        Ok(out)
+    }) {
+        Ok(output) => output,
+        Err(e) => {
+            status.error = Some(e);
+            return status;
+        }
+    };
+    status.version = version_output
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("ffmpeg version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string);
+
+    if let Some(version) = parse_version(&version_output) {
+        if version < MIN_FFMPEG_VERSION {
+            status.error = Some(format!(
+                "FFmpeg {}.{} is too old; version {}.{} or newer is required",
+                version.0, version.1, MIN_FFMPEG_VERSION.0, MIN_FFMPEG_VERSION.1
+            ));
+            return status;
+        }
+    }
+
+    match run(&ffmpeg, "-buildconf") {
+        Ok(buildconf) => status.missing_components = missing_components(&buildconf),
+        Err(e) => {
+            status.error = Some(e);
+            return status;
+        }
+    }
+    if !status.missing_components.is_empty() {
+        status.error = Some(format!(
+            "FFmpeg is missing required components: {}",
+            status.missing_components.join(", ")
+        ));
+        return status;
+    }
+
+    status.available = true;
+    status
+}
+
+/// Locate FFmpeg, preferring the first fully usable installation
+///
+/// When only degraded installations exist (too old, missing components) the
+/// first one is returned so the features it can run still work.
+pub fn locate(explicit: Option<&Path>, resource_dir: Option<&Path>) -> FfmpegStatus {
+    let managed_dir = managed_bin_dir();
+    FfmpegStatus {
+        managed_version: managed_version(),
+        ..locate_in(explicit, managed_dir.as_deref(), resource_dir)
+    }
+}
+
+/// Version of the app-managed build in ~/.clipforge/bin, if installed
+pub fn managed_version() -> Option<String> {
+    installed(&managed_bin_dir()?).map(|install| install.version)
+}
+
+fn locate_in(
+    explicit: Option<&Path>,
+    managed_dir: Option<&Path>,
+    resource_dir: Option<&Path>,
+) -> FfmpegStatus {
+    let windows = cfg!(windows);
+    let mut degraded: Option<FfmpegStatus> = None;
+    let path_var = std::env::var_os("PATH");
+    for (source, dir) in candidate_dirs(explicit, managed_dir, path_var, resource_dir, windows) {
+        let ffmpeg = dir.join(binary_name("ffmpeg", windows));
+        let ffprobe = dir.join(binary_name("ffprobe", windows));
+        if !ffmpeg.is_file() || !ffprobe.is_file() {
+            continue;
+        }
+        let status = check(source, ffmpeg, ffprobe);
+        if status.available {
+            return status;
+        }
+        if degraded.is_none() && status.version.is_some() {
+            degraded = Some(status);
+        }
+    }
+    degraded.unwrap_or_else(|| FfmpegStatus {
+        error: Some(FFMPEG_NOT_FOUND.to_string()),
+        ..Default::default()
+    })
+}
+
+/// Check an explicitly chosen FFmpeg (binary or directory) without searching elsewhere
+pub fn check_explicit(path: &Path) -> FfmpegStatus {
+    let windows = cfg!(windows);
+    let (source, dir) = candidate_dirs(Some(path), None, None, None, windows)
+        .into_iter()
+        .next()
+        .unwrap();
+    let ffmpeg = dir.join(binary_name("ffmpeg", windows));
+    let ffprobe = dir.join(binary_name("ffprobe", windows));
+    if !ffmpeg.is_file() || !ffprobe.is_file() {
+        return FfmpegStatus {
+            error: Some(format!(
+                "ffmpeg and ffprobe were not found in {}",
+                dir.display()
+            )),
+            managed_version: managed_version(),
+            ..Default::default()
+        };
+    }
+    FfmpegStatus {
+        managed_version: managed_version(),
+        ..check(source, ffmpeg, ffprobe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("ffmpeg version 6.1.1 Copyright (c) 2000-2023"),
+            Some((6, 1))
+        );
+        assert_eq!(
+            parse_version("ffmpeg version 4.4.2-0ubuntu0.22.04.1 Copyright"),
+            Some((4, 4))
+        );
+        assert_eq!(parse_version("ffmpeg version n7.0 Copyright"), Some((7, 0)));
+        assert_eq!(parse_version("ffmpeg version 5 Copyright"), Some((5, 0)));
+        assert_eq!(
+            parse_version("ffmpeg version N-112345-g1a2b3c4 Copyright"),
+            None
+        );
+        assert_eq!(parse_version("not ffmpeg"), None);
+        assert!((4, 3) < MIN_FFMPEG_VERSION && (6, 1) > MIN_FFMPEG_VERSION);
+    }
+
+    #[test]
+    fn test_missing_components() {
+        let full = "configuration: --prefix=/usr --enable-gpl --enable-libx264";
+        assert!(missing_components(full).is_empty());
+
+        let minimal = "configuration: --disable-encoders --disable-demuxers --enable-demuxer=mov";
+        assert_eq!(
+            missing_components(minimal),
+            vec!["libx264", "aac", "concat"]
+        );
+
+        let explicit =
+            "--enable-libx264 --disable-encoders --enable-encoder=aac --disable-demuxer=concat";
+        assert_eq!(missing_components(explicit), vec!["concat"]);
+    }
+
+    #[test]
+    fn test_parse_encoders() {
+        let output = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ V....D h264_videotoolbox    VideoToolbox H.264 Encoder (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+        assert_eq!(
+            parse_encoders(output),
+            vec!["libx264", "h264_videotoolbox", "aac"]
+        );
+        assert!(parse_encoders("").is_empty());
+    }
+
+    #[test]
+    fn test_candidate_order() {
+        let path_var = std::env::join_paths(["/usr/local/custom/bin", "/bin"]).unwrap();
+        let dirs = candidate_dirs(
+            Some(Path::new("/opt/ffmpeg/bin/ffmpeg")),
+            Some(Path::new("/home/u/.clipforge/bin")),
+            Some(path_var),
+            Some(Path::new("/app/resources")),
+            false,
+        );
+        assert_eq!(
+            dirs[0],
+            (LocateSource::Setting, PathBuf::from("/opt/ffmpeg/bin"))
+        );
+        assert_eq!(
+            dirs[1],
+            (
+                LocateSource::Managed,
+                PathBuf::from("/home/u/.clipforge/bin")
+            )
+        );
+        assert_eq!(
+            dirs[2],
+            (LocateSource::Path, PathBuf::from("/usr/local/custom/bin"))
+        );
+        assert!(dirs.contains(&(
+            LocateSource::CommonLocation,
+            PathBuf::from("/opt/homebrew/bin")
+        )));
+        assert_eq!(
+            dirs.last().unwrap(),
+            &(LocateSource::Bundled, PathBuf::from("/app/resources/bin"))
+        );
+
+        // A directory setting is used as-is
+        let dirs = candidate_dirs(
+            Some(Path::new("/opt/ffmpeg-7/bin")),
+            None,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(
+            dirs[0],
+            (LocateSource::Setting, PathBuf::from("/opt/ffmpeg-7/bin"))
+        );
+
+        let dirs = candidate_dirs(None, None, None, None, true);
+        assert!(dirs.contains(&(
+            LocateSource::CommonLocation,
+            PathBuf::from(r"C:\ffmpeg\bin")
+        )));
+    }
+
+    #[test]
+    fn test_missing_explicit_path() {
+        let status = check_explicit(Path::new("/nonexistent/ffmpeg-dir"));
+        assert!(!status.available);
+        assert!(status.paths().is_none());
+        assert!(status.error.unwrap().contains("/nonexistent/ffmpeg-dir"));
+    }
+}
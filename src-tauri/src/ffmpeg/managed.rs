@@ -0,0 +1,523 @@
+// Managed FFmpeg installation in ~/.clipforge/bin
+// A static ffmpeg/ffprobe build listed in a manifest (URL + SHA-256 per
+// OS/arch) is downloaded with resume support, verified, unpacked and then
+// found by the locator ahead of any system installation.
+
+use crate::platform::process::{self, ProcessClass};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use zip::ZipArchive;
+
+/// Marker written next to the managed binaries
+const MARKER_FILE: &str = "managed.json";
+
+/// Set while a download is running so concurrent requests don't clobber each other
+static DOWNLOADING: AtomicBool = AtomicBool::new(false);
+
+/// Seconds curl waits for a connection, and for a stalled transfer to move,
+/// before giving up (downloads have no overall timeout)
+const CONNECT_TIMEOUT_SECS: &str = "30";
+const STALL_TIMEOUT_SECS: &str = "60";
+
+/// Clears `DOWNLOADING` however the download ends, cancelled included
+struct DownloadingGuard;
+
+impl Drop for DownloadingGuard {
+    fn drop(&mut self) {
+        DOWNLOADING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// curl fetching `url`, failing on HTTP errors and stalls
+fn curl(url: &str) -> Command {
+    let mut cmd = Command::new("curl");
+    cmd.args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--connect-timeout", CONNECT_TIMEOUT_SECS])
+        .args(["--speed-limit", "1", "--speed-time", STALL_TIMEOUT_SECS])
+        .arg(url);
+    cmd
+}
+
+/// One pinned static build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagedBuild {
+    /// `std::env::consts::OS` value, e.g. "macos", "windows", "linux"
+    pub os: String,
+    /// `std::env::consts::ARCH` value, e.g. "aarch64", "x86_64"
+    pub arch: String,
+    /// Zip archive containing ffmpeg and ffprobe (at any depth)
+    pub url: String,
+    pub sha256: String,
+    /// Archive size in bytes (for progress)
+    pub size: u64,
+}
+
+/// Manifest served from the configured `ffmpeg_manifest_url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfmpegManifest {
+    pub version: String,
+    pub builds: Vec<ManagedBuild>,
+}
+
+impl FfmpegManifest {
+    /// Build for the given platform
+    pub fn build_for(&self, os: &str, arch: &str) -> Option<&ManagedBuild> {
+        self.builds.iter().find(|b| b.os == os && b.arch == arch)
+    }
+}
+
+/// What is installed in the managed directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagedInstall {
+    pub version: String,
+    pub sha256: String,
+}
+
+/// `ffmpeg_download_progress` event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    /// "downloading", "verifying", "unpacking" or "done"
+    pub stage: &'static str,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Directory holding the managed binaries
+pub fn managed_bin_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".clipforge").join("bin"))
+}
+
+/// Version installed in `dir`, if any
+pub fn installed(dir: &Path) -> Option<ManagedInstall> {
+    let marker = std::fs::read_to_string(dir.join(MARKER_FILE)).ok()?;
+    serde_json::from_str(&marker).ok()
+}
+
+/// Fail unless the SHA-256 of `path` matches `expected` (hex, case-insensitive)
+pub fn verify_checksum(path: &Path, expected: &str) -> Result<(), String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    let actual = format!("{:x}", hasher.finalize());
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Checksum mismatch for FFmpeg download (expected {}, got {})",
+            expected, actual
+        ))
+    }
+}
+
+/// Extract ffmpeg and ffprobe from `archive` into `dest_dir` and mark them executable
+///
+/// Binaries may sit anywhere in the archive (builds usually nest them in a
+/// versioned `bin/` folder); everything else is ignored.
+pub fn unpack_binaries(archive: &Path, dest_dir: &Path, windows: bool) -> Result<(), String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Invalid FFmpeg archive: {}", e))?;
+    std::fs::create_dir_all(dest_dir)
+        .map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+
+    let wanted: Vec<String> = ["ffmpeg", "ffprobe"]
+        .iter()
+        .map(|name| binary_file_name(name, windows))
+        .collect();
+    let mut found = Vec::new();
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| format!("Invalid FFmpeg archive: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry
+            .enclosed_name()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        if !wanted.contains(&name) || found.contains(&name) {
+            continue;
+        }
+
+        let target = dest_dir.join(&name);
+        let partial = dest_dir.join(format!("{}.part", name));
+        let mut out = File::create(&partial)
+            .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+        std::io::copy(&mut entry, &mut out)
+            .and_then(|_| out.flush())
+            .map_err(|e| format!("Failed to unpack {}: {}", name, e))?;
+        drop(out);
+        make_executable(&partial)?;
+        std::fs::rename(&partial, &target)
+            .map_err(|e| format!("Failed to install {}: {}", target.display(), e))?;
+        found.push(name);
+    }
+
+    let missing: Vec<&String> = wanted.iter().filter(|w| !found.contains(w)).collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "FFmpeg archive does not contain {}",
+            missing
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn binary_file_name(name: &str, windows: bool) -> String {
+    if windows {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to mark {} executable: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+/// Verify a downloaded archive and install its binaries into `dir`
+///
+/// Binaries are unpacked to a staging directory first so a bad archive never
+/// replaces a working installation.
+pub fn install_archive(
+    archive: &Path,
+    build: &ManagedBuild,
+    version: &str,
+    dir: &Path,
+) -> Result<ManagedInstall, String> {
+    verify_checksum(archive, &build.sha256)?;
+
+    let windows = build.os == "windows";
+    let staging = dir.join(".staging");
+    let _ = std::fs::remove_dir_all(&staging);
+    if let Err(e) = unpack_binaries(archive, &staging, windows) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    for name in ["ffmpeg", "ffprobe"] {
+        let file_name = binary_file_name(name, windows);
+        std::fs::rename(staging.join(&file_name), dir.join(&file_name))
+            .map_err(|e| format!("Failed to install {}: {}", file_name, e))?;
+    }
+    let _ = std::fs::remove_dir_all(&staging);
+
+    let install = ManagedInstall {
+        version: version.to_string(),
+        sha256: build.sha256.to_lowercase(),
+    };
+    let marker = serde_json::to_string_pretty(&install)
+        .map_err(|e| format!("Failed to serialize install marker: {}", e))?;
+    std::fs::write(dir.join(MARKER_FILE), marker)
+        .map_err(|e| format!("Failed to write install marker: {}", e))?;
+    Ok(install)
+}
+
+/// Fetch and parse the manifest
+pub async fn fetch_manifest(url: &str) -> Result<FfmpegManifest, String> {
+    let output = process::output(curl(url), ProcessClass::Download)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to fetch FFmpeg manifest: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid FFmpeg manifest: {}", e))
+}
+
+/// Download `build` into `partial`, continuing an earlier partial download
+///
+/// `on_progress` is called with the downloaded byte count while curl runs.
+pub async fn download_archive(
+    build: &ManagedBuild,
+    partial: &Path,
+    on_progress: impl Fn(u64),
+) -> Result<(), String> {
+    let existing = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    if build.size > 0 && existing >= build.size {
+        return Ok(());
+    }
+    if let Some(parent) = partial.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let mut cmd = curl(&build.url);
+    cmd.args(["--continue-at", "-", "--output"]).arg(partial);
+    let run = process::output(cmd, ProcessClass::Download);
+    tokio::pin!(run);
+
+    let output = loop {
+        tokio::select! {
+            output = &mut run => break output,
+            _ = tokio::time::sleep(Duration::from_millis(250)) => {
+                on_progress(std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0));
+            }
+        }
+    }
+    .map_err(|e| format!("FFmpeg download failed: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "FFmpeg download failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    on_progress(std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0));
+    Ok(())
+}
+
+/// Download, verify and install the manifest's build for this platform
+///
+/// Returns the existing install unchanged when it is already at the manifest
+/// version. A failed checksum discards the partial download; other failures
+/// keep it so the next attempt resumes.
+pub async fn download_and_install(
+    manifest_url: &str,
+    dir: &Path,
+    on_progress: impl Fn(DownloadProgress),
+) -> Result<ManagedInstall, String> {
+    if DOWNLOADING.swap(true, Ordering::SeqCst) {
+        return Err("An FFmpeg download is already in progress".to_string());
+    }
+    let _downloading = DownloadingGuard;
+    download_and_install_inner(manifest_url, dir, on_progress).await
+}
+
+async fn download_and_install_inner(
+    manifest_url: &str,
+    dir: &Path,
+    on_progress: impl Fn(DownloadProgress),
+) -> Result<ManagedInstall, String> {
+    let manifest = fetch_manifest(manifest_url).await?;
+    let build = manifest
+        .build_for(std::env::consts::OS, std::env::consts::ARCH)
+        .ok_or_else(|| {
+            format!(
+                "No FFmpeg build available for {}/{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            )
+        })?
+        .clone();
+
+    if let Some(current) = installed(dir) {
+        if current.version == manifest.version && current.sha256 == build.sha256.to_lowercase() {
+            return Ok(current);
+        }
+    }
+
+    // Named by checksum so a changed manifest never resumes a different file
+    let partial = dir.join("downloads").join(format!(
+        "ffmpeg-{}.zip.part",
+        &build.sha256[..build.sha256.len().min(16)]
+    ));
+    let total = build.size;
+    download_archive(&build, &partial, |downloaded| {
+        on_progress(DownloadProgress {
+            stage: "downloading",
+            downloaded_bytes: downloaded,
+            total_bytes: total,
+        })
+    })
+    .await?;
+
+    on_progress(DownloadProgress {
+        stage: "verifying",
+        downloaded_bytes: total,
+        total_bytes: total,
+    });
+    if let Err(e) = verify_checksum(&partial, &build.sha256) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    on_progress(DownloadProgress {
+        stage: "unpacking",
+        downloaded_bytes: total,
+        total_bytes: total,
+    });
+    let install = {
+        let (partial, build, version, dir) = (
+            partial.clone(),
+            build.clone(),
+            manifest.version.clone(),
+            dir.to_path_buf(),
+        );
+        tokio::task::spawn_blocking(move || install_archive(&partial, &build, &version, &dir))
+            .await
+            .map_err(|e| format!("FFmpeg install failed: {}", e))??
+    };
+    let _ = std::fs::remove_file(&partial);
+
+    on_progress(DownloadProgress {
+        stage: "done",
+        downloaded_bytes: total,
+        total_bytes: total,
+    });
+    Ok(install)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    /// Zip shaped like a typical static build: binaries nested under bin/
+    fn build_archive(dir: &Path, entries: &[(&str, &[u8])]) -> (PathBuf, String) {
+        let path = dir.join("ffmpeg.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (name, data) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(std::fs::read(&path).unwrap()));
+        (path, sha256)
+    }
+
+    fn build(sha256: &str) -> ManagedBuild {
+        ManagedBuild {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            url: "https://example.invalid/ffmpeg.zip".to_string(),
+            sha256: sha256.to_string(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn test_checksum_verification() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        std::fs::write(&path, b"hello").unwrap();
+
+        let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(&path, expected).is_ok());
+        assert!(verify_checksum(&path, &expected.to_uppercase()).is_ok());
+        let err = verify_checksum(&path, &"0".repeat(64)).unwrap_err();
+        assert!(err.contains("Checksum mismatch"));
+    }
+
+    #[test]
+    fn test_install_unpacks_and_marks_executable() {
+        let temp_dir = TempDir::new().unwrap();
+        let (archive, sha256) = build_archive(
+            temp_dir.path(),
+            &[
+                ("ffmpeg-7.0-static/bin/ffmpeg", b"ffmpeg-binary"),
+                ("ffmpeg-7.0-static/bin/ffprobe", b"ffprobe-binary"),
+                ("ffmpeg-7.0-static/README.txt", b"docs"),
+            ],
+        );
+        let dir = temp_dir.path().join("bin");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let install = install_archive(&archive, &build(&sha256), "7.0", &dir).unwrap();
+        assert_eq!(install.version, "7.0");
+        assert_eq!(installed(&dir), Some(install));
+        assert_eq!(std::fs::read(dir.join("ffmpeg")).unwrap(), b"ffmpeg-binary");
+        assert!(dir.join("ffprobe").is_file());
+        assert!(!dir.join("README.txt").exists());
+        assert!(!dir.join(".staging").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("ffmpeg"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[test]
+    fn test_bad_archives_leave_existing_install() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("bin");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ffmpeg"), b"working").unwrap();
+
+        // Tampered download
+        let (archive, _) = build_archive(
+            temp_dir.path(),
+            &[("bin/ffmpeg", b"evil"), ("bin/ffprobe", b"evil")],
+        );
+        assert!(install_archive(&archive, &build(&"a".repeat(64)), "7.0", &dir).is_err());
+        assert_eq!(std::fs::read(dir.join("ffmpeg")).unwrap(), b"working");
+
+        // Verified but incomplete
+        let (archive, sha256) = build_archive(temp_dir.path(), &[("bin/ffmpeg", b"new")]);
+        let err = install_archive(&archive, &build(&sha256), "7.0", &dir).unwrap_err();
+        assert!(err.contains("ffprobe"));
+        assert_eq!(std::fs::read(dir.join("ffmpeg")).unwrap(), b"working");
+        assert_eq!(installed(&dir), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_clears_in_progress_flag() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest = format!("file://{}", temp_dir.path().join("missing.json").display());
+        for _ in 0..2 {
+            let error = download_and_install(&manifest, temp_dir.path(), |_| {})
+                .await
+                .unwrap_err();
+            assert!(
+                error.starts_with("Failed to fetch FFmpeg manifest"),
+                "{}",
+                error
+            );
+        }
+        assert!(!DOWNLOADING.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_manifest_build_selection() {
+        let manifest: FfmpegManifest = serde_json::from_str(
+            r#"{"version":"7.0","builds":[
+                {"os":"macos","arch":"aarch64","url":"u1","sha256":"s1","size":1},
+                {"os":"windows","arch":"x86_64","url":"u2","sha256":"s2","size":2}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.build_for("windows", "x86_64").unwrap().url, "u2");
+        assert!(manifest.build_for("linux", "x86_64").is_none());
+    }
+}
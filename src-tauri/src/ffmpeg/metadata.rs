@@ -1,5 +1,9 @@
 // FFmpeg metadata extraction using ffprobe
+use crate::ffmpeg::locate::{ffprobe_path, require_ffmpeg};
+use crate::models::clip::MediaStream;
+use crate::platform::process::{self, ProcessClass};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::process::Command;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,45 +17,105 @@ pub struct VideoMetadata {
     pub audio_codec: Option<String>,
     pub bitrate: Option<u64>,
     pub has_audio: bool,
+    /// Every audio stream, in file order
+    pub audio_streams: Vec<MediaStream>,
+}
+
+/// A chapter marker from the container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterInfo {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+/// Everything ffprobe reports about a file: all streams, chapters and container tags
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullProbeResult {
+    /// Container format, e.g. "mov,mp4,m4a,3gp,3g2,mj2"
+    pub format_name: Option<String>,
+    pub duration: Option<f64>,
+    pub bitrate: Option<u64>,
+    /// Container metadata (title, creation_time, encoder, ...), keys lowercased
+    pub tags: BTreeMap<String, String>,
+    pub streams: Vec<MediaStream>,
+    pub chapters: Vec<ChapterInfo>,
+}
+
+impl FullProbeResult {
+    pub fn streams_of_type<'a>(
+        &'a self,
+        stream_type: &'a str,
+    ) -> impl Iterator<Item = &'a MediaStream> + 'a {
+        self.streams
+            .iter()
+            .filter(move |s| s.stream_type == stream_type)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct FfprobeStream {
+    index: Option<u32>,
     codec_type: Option<String>,
     codec_name: Option<String>,
     width: Option<u32>,
     height: Option<u32>,
     r_frame_rate: Option<String>,
     bit_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct FfprobeFormat {
+    format_name: Option<String>,
     duration: Option<String>,
     bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct FfprobeOutput {
     streams: Vec<FfprobeStream>,
     format: FfprobeFormat,
+    #[serde(default)]
+    chapters: Vec<FfprobeChapter>,
 }
 
-/// Extract metadata from video file using ffprobe
-pub async fn extract_metadata(file_path: &str) -> Result<VideoMetadata, String> {
-    // Run ffprobe to get JSON output
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "quiet",
-            "-print_format",
-            "json",
-            "-show_format",
-            "-show_streams",
-            file_path,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute ffprobe: {}", e))?;
+/// Tag lookup ignoring case (containers disagree on "title" vs "TITLE")
+fn tag(tags: &HashMap<String, String>, key: &str) -> Option<String> {
+    tags.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.clone())
+}
+
+/// Run ffprobe with streams, format and chapters as JSON
+async fn run_ffprobe(file_path: &str) -> Result<FfprobeOutput, String> {
+    require_ffmpeg()?;
+
+    let mut cmd = Command::new(ffprobe_path());
+    cmd.args([
+        "-v",
+        "quiet",
+        "-print_format",
+        "json",
+        "-show_format",
+        "-show_streams",
+        "-show_chapters",
+        file_path,
+    ]);
+    let output = process::output(cmd, ProcessClass::Probe).await?;
 
     if !output.status.success() {
         return Err(format!(
@@ -60,10 +124,69 @@ pub async fn extract_metadata(file_path: &str) -> Result<VideoMetadata, String>
         ));
     }
 
-    let json_output = String::from_utf8_lossy(&output.stdout);
-    let ffprobe_data: FfprobeOutput = serde_json::from_str(&json_output)
-        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+    parse_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+}
 
+fn parse_ffprobe_json(json: &str) -> Result<FfprobeOutput, String> {
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse ffprobe output: {}", e))
+}
+
+/// Probe every stream, chapter and container tag of a file
+pub async fn probe_full(file_path: &str) -> Result<FullProbeResult, String> {
+    Ok(full_probe_result(&run_ffprobe(file_path).await?))
+}
+
+fn full_probe_result(probe: &FfprobeOutput) -> FullProbeResult {
+    let streams = probe
+        .streams
+        .iter()
+        .enumerate()
+        .map(|(position, s)| MediaStream {
+            index: s.index.unwrap_or(position as u32),
+            stream_type: s
+                .codec_type
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+            codec: s.codec_name.clone(),
+            language: tag(&s.tags, "language").filter(|l| l != "und"),
+            title: tag(&s.tags, "title"),
+            channels: s.channels,
+            channel_layout: s.channel_layout.clone(),
+        })
+        .collect();
+    let chapters = probe
+        .chapters
+        .iter()
+        .filter_map(|c| {
+            Some(ChapterInfo {
+                start: c.start_time.as_ref()?.parse().ok()?,
+                end: c.end_time.as_ref()?.parse().ok()?,
+                title: tag(&c.tags, "title"),
+            })
+        })
+        .collect();
+    FullProbeResult {
+        format_name: probe.format.format_name.clone(),
+        duration: probe.format.duration.as_ref().and_then(|d| d.parse().ok()),
+        bitrate: probe.format.bit_rate.as_ref().and_then(|b| b.parse().ok()),
+        tags: probe
+            .format
+            .tags
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), v.clone()))
+            .collect(),
+        streams,
+        chapters,
+    }
+}
+
+/// Extract metadata from video file using ffprobe
+pub async fn extract_metadata(file_path: &str) -> Result<VideoMetadata, String> {
+    video_metadata(&run_ffprobe(file_path).await?)
+}
+
+/// Summarize the first video and audio stream for import
+fn video_metadata(ffprobe_data: &FfprobeOutput) -> Result<VideoMetadata, String> {
     // Find video and audio streams
     let video_stream = ffprobe_data
         .streams
@@ -113,6 +236,10 @@ pub async fn extract_metadata(file_path: &str) -> Result<VideoMetadata, String>
         audio_codec: audio_stream.and_then(|s| s.codec_name.clone()),
         bitrate,
         has_audio: audio_stream.is_some(),
+        audio_streams: full_probe_result(ffprobe_data)
+            .streams_of_type("audio")
+            .cloned()
+            .collect(),
     })
 }
 
@@ -148,4 +275,100 @@ mod tests {
         // NTSC frame rate
         assert!((parse_frame_rate("30000/1001").unwrap() - 29.97).abs() < 0.01);
     }
+
+    /// Trimmed `ffprobe -show_format -show_streams -show_chapters` output of a
+    /// game capture: video, game + mic audio, an English subtitle track and chapters
+    const MULTI_STREAM_PROBE: &str = r#"{
+        "streams": [
+            {"index": 0, "codec_name": "h264", "codec_type": "video", "width": 2560,
+             "height": 1440, "r_frame_rate": "60/1", "bit_rate": "24000000",
+             "tags": {"language": "und", "handler_name": "VideoHandler"}},
+            {"index": 1, "codec_name": "aac", "codec_type": "audio", "channels": 6,
+             "channel_layout": "5.1", "tags": {"language": "eng", "title": "Game"}},
+            {"index": 2, "codec_name": "opus", "codec_type": "audio", "channels": 1,
+             "channel_layout": "mono", "tags": {"LANGUAGE": "eng", "TITLE": "Mic"}},
+            {"index": 3, "codec_name": "mov_text", "codec_type": "subtitle",
+             "tags": {"language": "eng"}},
+            {"index": 4, "codec_name": "bin_data", "codec_type": "data"}
+        ],
+        "chapters": [
+            {"id": 0, "start_time": "0.000000", "end_time": "95.500000", "tags": {"title": "Intro"}},
+            {"id": 1, "start_time": "95.500000", "end_time": "600.000000", "tags": {"title": "Boss fight"}}
+        ],
+        "format": {"format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "600.000000",
+                   "bit_rate": "25000000",
+                   "tags": {"title": "Session 12", "COMMENT": "recorded live", "encoder": "OBS"}}
+    }"#;
+
+    #[test]
+    fn test_full_probe_of_multi_stream_file() {
+        let probe = full_probe_result(&parse_ffprobe_json(MULTI_STREAM_PROBE).unwrap());
+
+        assert_eq!(
+            probe.format_name.as_deref(),
+            Some("mov,mp4,m4a,3gp,3g2,mj2")
+        );
+        assert_eq!(probe.duration, Some(600.0));
+        assert_eq!(probe.bitrate, Some(25_000_000));
+        assert_eq!(probe.tags["title"], "Session 12");
+        assert_eq!(probe.tags["comment"], "recorded live");
+
+        assert_eq!(probe.streams.len(), 5);
+        let audio: Vec<_> = probe.streams_of_type("audio").collect();
+        assert_eq!(audio.len(), 2);
+        assert_eq!(audio[0].index, 1);
+        assert_eq!(audio[0].title.as_deref(), Some("Game"));
+        assert_eq!(audio[0].channels, Some(6));
+        assert_eq!(audio[0].channel_layout.as_deref(), Some("5.1"));
+        // Upper-case tag keys are found too
+        assert_eq!(audio[1].language.as_deref(), Some("eng"));
+        assert_eq!(audio[1].title.as_deref(), Some("Mic"));
+
+        let subtitles: Vec<_> = probe.streams_of_type("subtitle").collect();
+        assert_eq!(subtitles.len(), 1);
+        assert_eq!(subtitles[0].index, 3);
+        assert_eq!(subtitles[0].codec.as_deref(), Some("mov_text"));
+        // "und" means no language
+        assert_eq!(probe.streams[0].language, None);
+
+        assert_eq!(
+            probe.chapters,
+            vec![
+                ChapterInfo {
+                    start: 0.0,
+                    end: 95.5,
+                    title: Some("Intro".to_string())
+                },
+                ChapterInfo {
+                    start: 95.5,
+                    end: 600.0,
+                    title: Some("Boss fight".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_video_metadata_lists_audio_streams() {
+        let metadata = video_metadata(&parse_ffprobe_json(MULTI_STREAM_PROBE).unwrap()).unwrap();
+        assert_eq!((metadata.width, metadata.height), (2560, 1440));
+        assert_eq!(metadata.fps, 60.0);
+        assert_eq!(metadata.audio_codec.as_deref(), Some("aac"));
+        assert!(metadata.has_audio);
+        assert_eq!(
+            metadata
+                .audio_streams
+                .iter()
+                .map(|s| s.index)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+
+        // Older probes without chapters or tags still parse
+        let minimal = r#"{"streams": [{"codec_type": "video", "codec_name": "vp9",
+            "width": 640, "height": 360}], "format": {"duration": "3.5"}}"#;
+        let metadata = video_metadata(&parse_ffprobe_json(minimal).unwrap()).unwrap();
+        assert!(metadata.audio_streams.is_empty());
+        assert_eq!(metadata.fps, 30.0);
+    }
 }
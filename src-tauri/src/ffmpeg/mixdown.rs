@@ -0,0 +1,663 @@
+// Audio mixdown of a timeline range (program audio preview / waveform)
+// Every audible clip on every track is delayed to its timeline position,
+// scaled by its track volume and mixed over silence of the range length.
+// Sources are brought to stereo first, mono ones on both channels, with the
+// clip's channel mode applied. Ducked tracks are mixed on their own and
+// compressed by the mix of the tracks they duck under (`mix_filters`, shared
+// with export so the preview sounds like the exported file).
+
+use crate::ffmpeg::locate::ffmpeg_path;
+use crate::ffmpeg::{audio_stream_spec, playback_audio_stream};
+use crate::models::playback::{track_segments, PlaybackSegment};
+use crate::models::project::Project;
+use crate::models::timeline::{ChannelMode, Track, TrackDucking};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Bump when the mix recipe changes so old cached mixdowns are not reused
+const MIXDOWN_FORMAT_VERSION: u32 = 4;
+
+/// Durations shorter than this are treated as empty
+const EPSILON: f64 = 1e-3;
+
+/// One clip's audio contribution to a mixdown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioSource {
+    pub path: String,
+    /// Start within the file
+    pub source_start: f64,
+    /// Length read from the file
+    pub duration: f64,
+    /// Playback rate; the source is heard for `duration / speed` seconds
+    pub speed: f64,
+    /// Offset from the start of the mixed range
+    pub delay: f64,
+    pub volume: f32,
+    /// Selected audio stream position (None = default stream)
+    pub audio_stream: Option<usize>,
+    pub channel_mode: ChannelMode,
+    /// Channels of the stream, if probed
+    pub channels: Option<u32>,
+    /// Track the clip is on, for ducking
+    pub track_id: String,
+}
+
+/// A track ducked under others in a mix
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuckedTrack {
+    pub track_id: String,
+    pub ducking: TrackDucking,
+}
+
+/// Ducking of the audible tracks among `tracks`
+pub fn ducked_tracks(tracks: &[Track]) -> Vec<DuckedTrack> {
+    tracks
+        .iter()
+        .filter(|t| t.volume > 0.0)
+        .filter_map(|t| {
+            Some(DuckedTrack {
+                track_id: t.id.clone(),
+                ducking: t.ducking.clone()?,
+            })
+        })
+        .collect()
+}
+
+/// Planned mixdown of `start..end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioMixPlan {
+    pub start: f64,
+    pub end: f64,
+    pub sources: Vec<AudioSource>,
+    pub ducking: Vec<DuckedTrack>,
+    /// Hash of everything that affects the mixed audio
+    pub key: String,
+}
+
+/// Collect the audible clip ranges of all tracks within `start..end`
+///
+/// Tracks at zero volume are muted; video-only clips and clips without an
+/// audio stream are skipped.
+pub fn plan_audio_mix(project: &Project, start: f64, end: f64) -> AudioMixPlan {
+    let mut sources = Vec::new();
+    for track in project.tracks.iter().filter(|t| t.volume > 0.0) {
+        for segment in track_segments(project, track, true, end) {
+            let PlaybackSegment::Clip {
+                clip_id,
+                media_clip_id,
+                file_path,
+                is_proxy,
+                source_in,
+                timeline_start,
+                timeline_end,
+                speed,
+                volume,
+                ..
+            } = segment
+            else {
+                continue;
+            };
+            // Video-only clips are silent
+            if volume <= 0.0 {
+                continue;
+            }
+            let Some(media) = project
+                .media_library
+                .iter()
+                .find(|m| m.id == media_clip_id && m.has_audio)
+            else {
+                continue;
+            };
+            let from = timeline_start.max(start);
+            let to = timeline_end.min(end);
+            if to - from <= EPSILON {
+                continue;
+            }
+            let channel_mode = track
+                .clips
+                .iter()
+                .find(|c| c.id == clip_id)
+                .map_or(ChannelMode::Auto, |c| c.channel_mode);
+            sources.push(AudioSource {
+                path: file_path,
+                source_start: source_in + (from - timeline_start) * speed,
+                duration: (to - from) * speed,
+                speed,
+                delay: from - start,
+                volume,
+                audio_stream: playback_audio_stream(media, is_proxy),
+                channel_mode,
+                channels: media.audio_channels(),
+                track_id: track.id.clone(),
+            });
+        }
+    }
+
+    let ducking = ducked_tracks(&project.tracks);
+    let key = mix_key(start, end, &sources, &ducking);
+    AudioMixPlan {
+        start,
+        end,
+        sources,
+        ducking,
+        key,
+    }
+}
+
+fn mix_key(start: f64, end: f64, sources: &[AudioSource], ducking: &[DuckedTrack]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "v{}|{:.6}-{:.6}\n",
+        MIXDOWN_FORMAT_VERSION, start, end
+    ));
+    for source in sources {
+        hasher.update(format!(
+            "{}|{:.6}|{:.6}|{:.6}|{:.6}|{:.4}|{:?}|{:?}|{:?}|{}\n",
+            source.path,
+            source.source_start,
+            source.duration,
+            source.speed,
+            source.delay,
+            source.volume,
+            source.audio_stream,
+            source.channel_mode,
+            source.channels,
+            source.track_id
+        ));
+    }
+    for ducked in ducking {
+        hasher.update(format!("duck|{}|{:?}\n", ducked.track_id, ducked.ducking));
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// `pan` filter routing a source's channels to stereo for `mode`, or None
+/// when the plain stereo conversion already does
+///
+/// Mono is copied to both channels at full level (a plain conversion would
+/// lower it 3 dB), so every mode plays a mono source the same way.
+pub fn channel_filter(mode: ChannelMode, channels: Option<u32>) -> Option<&'static str> {
+    if channels == Some(1) {
+        return Some("pan=stereo|c0=c0|c1=c0");
+    }
+    match mode {
+        ChannelMode::Auto => None,
+        ChannelMode::MonoToStereo => Some("pan=stereo|c0=0.5*c0+0.5*c1|c1=0.5*c0+0.5*c1"),
+        ChannelMode::LeftOnly => Some("pan=stereo|c0=c0|c1=c0"),
+        ChannelMode::RightOnly => Some("pan=stereo|c0=c1|c1=c1"),
+        ChannelMode::Swap => Some("pan=stereo|c0=c1|c1=c0"),
+    }
+}
+
+/// Filter bringing input `index` to 48kHz stereo at its timeline position
+pub fn audio_source_filter(index: usize, source: &AudioSource, label: &str) -> String {
+    let delay_ms = (source.delay * 1000.0).round() as u64;
+    let pan = channel_filter(source.channel_mode, source.channels)
+        .map_or_else(String::new, |pan| format!("{},", pan));
+    let tempo = tempo_filter(source.speed).map_or_else(String::new, |tempo| format!("{},", tempo));
+    format!(
+        "[{}]aresample=48000,{}aformat=channel_layouts=stereo,atrim=duration={:.6},{}volume={:.4},adelay={}|{}[{}]",
+        audio_stream_spec(index, source.audio_stream),
+        pan, source.duration, tempo, source.volume, delay_ms, delay_ms, label
+    )
+}
+
+/// Slowest and fastest rate a single `atempo` filter takes
+const ATEMPO_RANGE: (f64, f64) = (0.5, 2.0);
+
+/// `atempo` filters playing audio at `speed` without changing its pitch,
+/// or None at normal speed
+///
+/// One `atempo` only goes from half to double speed, so faster and slower
+/// rates chain several: 4x is two doublings, 0.1x three halvings and 0.8x.
+pub fn tempo_filter(speed: f64) -> Option<String> {
+    if (speed - 1.0).abs() <= f64::EPSILON {
+        return None;
+    }
+    let (slowest, fastest) = ATEMPO_RANGE;
+    let mut factors = Vec::new();
+    let mut rest = speed;
+    while rest > fastest {
+        factors.push(fastest);
+        rest /= fastest;
+    }
+    while rest < slowest {
+        factors.push(slowest);
+        rest /= slowest;
+    }
+    if (rest - 1.0).abs() > 1e-9 {
+        factors.push(rest);
+    }
+    Some(
+        factors
+            .iter()
+            .map(|factor| format!("atempo={:.6}", factor))
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+/// `sidechaincompress` threshold: ducking starts once the key mix is above
+/// about -34 dBFS
+const DUCKING_THRESHOLD: f64 = 0.02;
+
+/// `sidechaincompress` options for `ducking`
+///
+/// The strongest ratio pushes the compressed signal far down; blending it
+/// with the dry signal (`mix`) then caps the reduction at `amount_db`.
+pub fn ducking_filter(ducking: &TrackDucking) -> String {
+    let mix = 1.0 - 10f64.powf(-f64::from(ducking.amount_db) / 20.0);
+    format!(
+        "sidechaincompress=threshold={}:ratio=20:attack={:.2}:release={:.2}:mix={:.4}",
+        DUCKING_THRESHOLD, ducking.attack_ms, ducking.release_ms, mix
+    )
+}
+
+/// Filters mixing the labelled `inputs` (label, track id) and the `[bed]`
+/// silence into `[aout]`
+///
+/// A track in `ducking` is mixed over its own bed and compressed by the mix
+/// of the tracks it ducks under, which still play in the final mix as well;
+/// ducking with nothing to duck, or nothing to duck under, is skipped.
+/// Every mixed signal lasts `duration`, so the compressor sees both inputs
+/// to the end.
+pub fn mix_filters(
+    inputs: &[(String, String)],
+    ducking: &[DuckedTrack],
+    duration: f64,
+) -> Vec<String> {
+    let labels_of = |wanted: &dyn Fn(&str) -> bool| -> Vec<&str> {
+        inputs
+            .iter()
+            .filter(|(_, track_id)| wanted(track_id))
+            .map(|(label, _)| label.as_str())
+            .collect()
+    };
+    let active: Vec<(&DuckedTrack, Vec<&str>, Vec<&str>)> = ducking
+        .iter()
+        .filter_map(|ducked| {
+            let ducked_labels = labels_of(&|id| id == ducked.track_id);
+            let key_labels = labels_of(&|id| {
+                id != ducked.track_id && ducked.ducking.against_track_ids.iter().any(|a| a == id)
+            });
+            (!ducked_labels.is_empty() && !key_labels.is_empty()).then_some((
+                ducked,
+                ducked_labels,
+                key_labels,
+            ))
+        })
+        .collect();
+    let direct: Vec<&str> = inputs
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .filter(|label| !active.iter().any(|(_, ducked, _)| ducked.contains(label)))
+        .collect();
+
+    let amix = |mix_inputs: &str, count: usize, output: &str| {
+        format!(
+            "{}amix=inputs={}:duration=first:normalize=0[{}]",
+            mix_inputs, count, output
+        )
+    };
+    if active.is_empty() {
+        let mix_inputs: String = direct.iter().map(|l| format!("[{}]", l)).collect();
+        return vec![amix(
+            &format!("[bed]{}", mix_inputs),
+            direct.len() + 1,
+            "aout",
+        )];
+    }
+
+    // Inputs keying a compressor are also mixed, so they're split
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for label in direct.iter().chain(
+        active
+            .iter()
+            .flat_map(|(_, ducked, keys)| ducked.iter().chain(keys)),
+    ) {
+        *uses.entry(label).or_default() += 1;
+    }
+    let mut filters = Vec::new();
+    for (label, _) in inputs {
+        let count = uses.get(label.as_str()).copied().unwrap_or(0);
+        if count > 1 {
+            let outputs: String = (0..count).map(|i| format!("[{}s{}]", label, i)).collect();
+            filters.push(format!("[{}]asplit={}{}", label, count, outputs));
+        }
+    }
+    let mut taken: HashMap<String, usize> = HashMap::new();
+    let mut take = |label: &str| -> String {
+        if uses.get(label).copied().unwrap_or(0) <= 1 {
+            return format!("[{}]", label);
+        }
+        let next = taken.entry(label.to_string()).or_default();
+        *next += 1;
+        format!("[{}s{}]", label, *next - 1)
+    };
+    let bed = |label: String| {
+        format!(
+            "anullsrc=r=48000:cl=stereo,atrim=duration={:.6}[{}]",
+            duration, label
+        )
+    };
+
+    let mut mix_inputs = String::from("[bed]");
+    for label in &direct {
+        mix_inputs.push_str(&take(label));
+    }
+    for (i, (ducked, ducked_labels, key_labels)) in active.iter().enumerate() {
+        for (name, labels) in [("duck", ducked_labels), ("key", key_labels)] {
+            filters.push(bed(format!("{}bed{}", name, i)));
+            let group: String = labels.iter().map(|l| take(l)).collect();
+            filters.push(amix(
+                &format!("[{}bed{}]{}", name, i, group),
+                labels.len() + 1,
+                &format!("{}{}", name, i),
+            ));
+        }
+        filters.push(format!(
+            "[duck{i}][key{i}]{}[ducked{i}]",
+            ducking_filter(&ducked.ducking),
+            i = i
+        ));
+        mix_inputs.push_str(&format!("[ducked{}]", i));
+    }
+    filters.push(amix(&mix_inputs, direct.len() + active.len() + 1, "aout"));
+    filters
+}
+
+/// Build the FFmpeg command writing the mixdown to `output_path` (AAC in .m4a)
+pub fn build_audio_mix_command(plan: &AudioMixPlan, output_path: &Path) -> Command {
+    let duration = plan.end - plan.start;
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y"]);
+
+    // Silence bed fixes the output length and keeps empty ranges valid
+    let mut filters = vec![format!(
+        "anullsrc=r=48000:cl=stereo,atrim=duration={:.6}[bed]",
+        duration
+    )];
+    let mut mix_inputs = Vec::new();
+    for (i, source) in plan.sources.iter().enumerate() {
+        cmd.arg("-ss")
+            .arg(format!("{:.6}", source.source_start))
+            .arg("-t")
+            .arg(format!("{:.6}", source.duration))
+            .arg("-i")
+            .arg(&source.path);
+        let label = format!("a{}", i);
+        filters.push(audio_source_filter(i, source, &label));
+        mix_inputs.push((label, source.track_id.clone()));
+    }
+    filters.extend(mix_filters(&mix_inputs, &plan.ducking, duration));
+
+    cmd.arg("-filter_complex")
+        .arg(filters.join(";"))
+        .args(["-map", "[aout]", "-vn", "-c:a", "aac", "-b:a", "192k"])
+        .arg(output_path);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaClip;
+    use crate::models::timeline::{TimelineClip, Track, TrackType};
+
+    /// Main: A (source 1-9s) at 0-8s, volume 0.5. Overlay: B (source 0-4s) at 2-6s.
+    fn project() -> Project {
+        let mut project = Project::new("Mix".to_string());
+        let mut a = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        a.has_audio = true;
+        let mut b = MediaClip::new("/m/b.mp4".into(), 30.0, 1280, 720, 30.0, "h264".into(), 1);
+        b.has_audio = true;
+
+        let main_id = project.tracks[0].id.clone();
+        project.tracks[0].volume = 0.5;
+        project.tracks[0]
+            .clips
+            .push(TimelineClip::new(a.id.clone(), main_id, 0.0, 1.0, 9.0));
+        let mut overlay = Track::new("Overlay".into(), TrackType::Overlay);
+        overlay.order = 1;
+        overlay.clips.push(TimelineClip::new(
+            b.id.clone(),
+            overlay.id.clone(),
+            2.0,
+            0.0,
+            4.0,
+        ));
+        project.tracks.push(overlay);
+        project.media_library = vec![a, b];
+        project
+    }
+
+    #[test]
+    fn test_overlapping_sources_are_mixed() {
+        let plan = plan_audio_mix(&project(), 1.0, 5.0);
+        assert_eq!(plan.sources.len(), 2);
+        assert_eq!(plan.sources[0].source_start, 2.0);
+        assert_eq!(plan.sources[0].duration, 4.0);
+        assert_eq!(plan.sources[0].delay, 0.0);
+        assert_eq!(plan.sources[1].source_start, 0.0);
+        assert_eq!(plan.sources[1].duration, 3.0);
+        assert_eq!(plan.sources[1].delay, 1.0);
+
+        let cmd = build_audio_mix_command(&plan, Path::new("mix.m4a"));
+        let cmd_str = format!("{:?}", cmd);
+        assert!(cmd_str.contains("anullsrc=r=48000:cl=stereo,atrim=duration=4.000000[bed]"));
+        assert!(cmd_str.contains("atrim=duration=4.000000,volume=0.5000,adelay=0|0[a0]"));
+        assert!(cmd_str.contains("atrim=duration=3.000000,volume=1.0000,adelay=1000|1000[a1]"));
+        assert!(cmd_str.contains("[bed][a0][a1]amix=inputs=3:duration=first:normalize=0[aout]"));
+        assert!(cmd_str.contains("\"-vn\""));
+    }
+
+    #[test]
+    fn test_ducking_matches_export() {
+        let mut project = project();
+        let ducking = TrackDucking {
+            against_track_ids: vec![project.tracks[0].id.clone()],
+            amount_db: 12.0,
+            attack_ms: 20.0,
+            release_ms: 300.0,
+        };
+        let before = plan_audio_mix(&project, 0.0, 8.0).key;
+        project.tracks[1].ducking = Some(ducking.clone());
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert_ne!(plan.key, before);
+
+        // The same tail export builds from the same labelled inputs
+        let inputs = vec![
+            ("a0".to_string(), project.tracks[0].id.clone()),
+            ("a1".to_string(), project.tracks[1].id.clone()),
+        ];
+        let tail = mix_filters(&inputs, &ducked_tracks(&project.tracks), 8.0).join(";");
+        let cmd_str = format!("{:?}", build_audio_mix_command(&plan, Path::new("mix.m4a")));
+        assert!(cmd_str.contains(&tail), "{}", cmd_str);
+        assert!(tail.starts_with("[a0]asplit=2[a0s0][a0s1];"));
+        assert!(tail.contains(&format!(
+            "[duck0][key0]{}[ducked0]",
+            ducking_filter(&ducking)
+        )));
+        assert!(
+            tail.ends_with("[bed][a0s0][ducked0]amix=inputs=3:duration=first:normalize=0[aout]")
+        );
+    }
+
+    #[test]
+    fn test_muted_and_silent_sources_are_skipped() {
+        let mut project = project();
+        project.tracks[0].volume = 0.0;
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert_eq!(plan.sources.len(), 1);
+        assert_eq!(plan.sources[0].path, "/m/b.mp4");
+
+        project.media_library[1].has_audio = false;
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert!(plan.sources.is_empty());
+        let cmd_str = format!("{:?}", build_audio_mix_command(&plan, Path::new("mix.m4a")));
+        assert!(cmd_str.contains("[bed]amix=inputs=1"));
+    }
+
+    #[test]
+    fn test_tempo_filter_chains_atempo() {
+        assert_eq!(tempo_filter(1.0), None);
+        assert_eq!(tempo_filter(1.5).unwrap(), "atempo=1.500000");
+        assert_eq!(tempo_filter(0.5).unwrap(), "atempo=0.500000");
+        assert_eq!(tempo_filter(2.0).unwrap(), "atempo=2.000000");
+        assert_eq!(
+            tempo_filter(4.0).unwrap(),
+            "atempo=2.000000,atempo=2.000000"
+        );
+        assert_eq!(
+            tempo_filter(3.0).unwrap(),
+            "atempo=2.000000,atempo=1.500000"
+        );
+        assert_eq!(
+            tempo_filter(10.0).unwrap(),
+            "atempo=2.000000,atempo=2.000000,atempo=2.000000,atempo=1.250000"
+        );
+        assert_eq!(
+            tempo_filter(0.25).unwrap(),
+            "atempo=0.500000,atempo=0.500000"
+        );
+        assert_eq!(
+            tempo_filter(0.1).unwrap(),
+            "atempo=0.500000,atempo=0.500000,atempo=0.500000,atempo=0.800000"
+        );
+
+        // Every factor stays in range and the chain multiplies to the speed
+        for speed in [0.1, 0.3, 0.7, 1.9, 2.5, 7.0, 10.0] {
+            let product: f64 = tempo_filter(speed)
+                .unwrap()
+                .split(',')
+                .map(|f| f.trim_start_matches("atempo=").parse::<f64>().unwrap())
+                .inspect(|f| assert!((0.5..=2.0).contains(f), "{} in {}", f, speed))
+                .product();
+            assert!((product - speed).abs() < 1e-5, "{}", speed);
+        }
+    }
+
+    #[test]
+    fn test_sped_up_clip_reads_more_source() {
+        let mut sped = project();
+        sped.tracks[0].clips[0].speed = 2.0;
+        let plan = plan_audio_mix(&sped, 1.0, 3.0);
+        assert_eq!(plan.sources[0].source_start, 3.0);
+        assert_eq!(plan.sources[0].duration, 4.0);
+        assert_eq!(plan.sources[0].speed, 2.0);
+
+        let cmd_str = format!("{:?}", build_audio_mix_command(&plan, Path::new("mix.m4a")));
+        assert!(cmd_str
+            .contains("atrim=duration=4.000000,atempo=2.000000,volume=0.5000,adelay=0|0[a0]"));
+        assert_ne!(plan.key, plan_audio_mix(&project(), 1.0, 3.0).key);
+    }
+
+    #[test]
+    fn test_key_tracks_volume_changes() {
+        let mut project = project();
+        let before = plan_audio_mix(&project, 0.0, 8.0).key;
+        assert_eq!(before, plan_audio_mix(&project, 0.0, 8.0).key);
+        project.tracks[1].volume = 0.7;
+        assert_ne!(before, plan_audio_mix(&project, 0.0, 8.0).key);
+    }
+
+    #[test]
+    fn test_selected_audio_stream_is_mapped() {
+        let mut project = project();
+        let before = plan_audio_mix(&project, 0.0, 8.0);
+        assert!(format!(
+            "{:?}",
+            build_audio_mix_command(&before, Path::new("mix.m4a"))
+        )
+        .contains("[0:a]aresample"));
+
+        // Mic track recorded as the second audio stream (source index 2)
+        let media = &mut project.media_library[0];
+        media.audio_streams = [1, 2]
+            .map(|index| crate::models::clip::MediaStream {
+                index,
+                stream_type: "audio".into(),
+                codec: Some("aac".into()),
+                language: None,
+                title: None,
+                channels: Some(2),
+                channel_layout: None,
+            })
+            .to_vec();
+        media.audio_stream_index = Some(2);
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert_eq!(plan.sources[0].audio_stream, Some(1));
+        assert_ne!(plan.key, before.key);
+        let cmd_str = format!("{:?}", build_audio_mix_command(&plan, Path::new("mix.m4a")));
+        assert!(cmd_str.contains("[0:a:1]aresample"));
+        assert!(cmd_str.contains("[1:a]aresample"));
+    }
+
+    fn audio_stream(channels: u32) -> crate::models::clip::MediaStream {
+        crate::models::clip::MediaStream {
+            index: 1,
+            stream_type: "audio".into(),
+            codec: Some("aac".into()),
+            language: None,
+            title: None,
+            channels: Some(channels),
+            channel_layout: None,
+        }
+    }
+
+    #[test]
+    fn test_mono_sources_play_on_both_channels() {
+        let mut project = project();
+        project.media_library[0].audio_streams = vec![audio_stream(1)];
+        project.media_library[1].audio_streams = vec![audio_stream(2)];
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert_eq!(
+            audio_source_filter(0, &plan.sources[0], "a0"),
+            "[0:a]aresample=48000,pan=stereo|c0=c0|c1=c0,aformat=channel_layouts=stereo,\
+             atrim=duration=8.000000,volume=0.5000,adelay=0|0[a0]"
+        );
+        // Stereo goes straight to the stereo conversion
+        assert!(audio_source_filter(1, &plan.sources[1], "a1")
+            .starts_with("[1:a]aresample=48000,aformat=channel_layouts=stereo,"));
+    }
+
+    #[test]
+    fn test_channel_modes() {
+        let stereo = Some(2);
+        assert_eq!(channel_filter(ChannelMode::Auto, stereo), None);
+        assert_eq!(channel_filter(ChannelMode::Auto, None), None);
+        assert_eq!(
+            channel_filter(ChannelMode::MonoToStereo, stereo),
+            Some("pan=stereo|c0=0.5*c0+0.5*c1|c1=0.5*c0+0.5*c1")
+        );
+        assert_eq!(
+            channel_filter(ChannelMode::LeftOnly, stereo),
+            Some("pan=stereo|c0=c0|c1=c0")
+        );
+        assert_eq!(
+            channel_filter(ChannelMode::RightOnly, stereo),
+            Some("pan=stereo|c0=c1|c1=c1")
+        );
+        assert_eq!(
+            channel_filter(ChannelMode::Swap, stereo),
+            Some("pan=stereo|c0=c1|c1=c0")
+        );
+        // A mono source has no right channel to pick
+        assert_eq!(
+            channel_filter(ChannelMode::RightOnly, Some(1)),
+            Some("pan=stereo|c0=c0|c1=c0")
+        );
+
+        // The clip's mode reaches the preview mix and its cache key
+        let mut project = project();
+        let before = plan_audio_mix(&project, 0.0, 8.0);
+        project.tracks[1].clips[0].channel_mode = ChannelMode::Swap;
+        let plan = plan_audio_mix(&project, 0.0, 8.0);
+        assert_ne!(plan.key, before.key);
+        let cmd_str = format!("{:?}", build_audio_mix_command(&plan, Path::new("mix.m4a")));
+        assert!(cmd_str.contains("[1:a]aresample=48000,pan=stereo|c0=c1|c1=c0,aformat"));
+    }
+}
@@ -1,13 +1,39 @@
 // FFmpeg integration module
 // Provides video processing capabilities: metadata extraction, thumbnails, proxy generation, export
 
+pub mod analysis;
 pub mod audio;
+pub mod capabilities;
+pub mod caption_sidecar;
+pub mod chapters;
+pub mod clip_export;
+pub mod clip_segment;
+pub mod command;
+pub mod effects;
+pub mod errors;
 pub mod export;
+// Used by the `ffmpeg-tests` integration suite, which the binary never sees
+#[cfg(any(test, feature = "ffmpeg-tests"))]
+#[allow(dead_code)]
+pub mod fixtures;
+pub mod frames;
+pub mod locate;
+pub mod managed;
 pub mod metadata;
+pub mod mixdown;
+pub mod parallel;
+pub mod preflight;
+pub mod preview;
 pub mod proxy;
+pub mod retry;
+pub mod scheduler;
+pub mod subtitles;
 pub mod thumbnails;
 
 pub use audio::{extract_audio_to_wav, get_temp_audio_path};
+pub use command::{audio_stream_spec, FfmpegCommandBuilder, FfmpegInput, ProgressMode};
 pub use metadata::extract_metadata;
-pub use proxy::{generate_proxy, needs_proxy};
+pub use proxy::{
+    generate_proxy, needs_proxy, playback_audio_stream, proxy_outdated, PROXY_FORMAT_VERSION,
+};
 pub use thumbnails::generate_thumbnail;
@@ -0,0 +1,484 @@
+// Parallel segment rendering
+// A parallel export splits the timeline where main track clips meet into
+// contiguous parts and renders each part's video with its own FFmpeg
+// process. The mixed audio is rendered once, whole: encoded audio frames
+// don't end on part boundaries, so audio joined from parts would click at
+// every cut. The parts are then joined by the concat demuxer and muxed with
+// the audio without re-encoding.
+
+use crate::ffmpeg::export::{
+    concat_file_directive, output_tags, write_concat_file, EncodeParams, ExportPlan,
+    ExportProgress, ExportSegment, ExportTiming,
+};
+use crate::ffmpeg::{FfmpegCommandBuilder, FfmpegInput, ProgressMode};
+use crate::models::clip::MediaClip;
+use crate::models::export::{ExportRange, ExportSettings};
+use crate::models::project::ProjectSettings;
+use crate::models::timeline::Track;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parts cut per worker, so a worker finishing early picks up another
+/// instead of idling while one long part renders
+pub const PARTS_PER_WORKER: usize = 2;
+
+/// Shortest part worth its own process, in output seconds
+const MIN_PART_SECONDS: f64 = 2.0;
+
+/// Weight of the audio render in the combined progress, per output second
+/// (against 1.0 for a second of video)
+const AUDIO_PART_WEIGHT: f64 = 0.1;
+
+/// Output start and timeline start of a segment
+fn segment_start(segment: &ExportSegment) -> (f64, f64) {
+    match segment {
+        ExportSegment::Clip {
+            timeline_start,
+            output_start,
+            ..
+        }
+        | ExportSegment::Filler {
+            timeline_start,
+            output_start,
+            ..
+        } => (*output_start, *timeline_start),
+    }
+}
+
+/// Timeline ranges of up to `parts` contiguous pieces of an export, about
+/// equally long in the output
+///
+/// Pieces only start where a segment of the main track does, and not at a
+/// transition, so no clip or transition is split between two processes;
+/// parts shorter than `MIN_PART_SECONDS` are not cut. Fewer ranges than `parts` come back for short timelines, one
+/// covering the whole export when it can't be split.
+pub fn split_ranges(
+    segments: &[ExportSegment],
+    timing: &ExportTiming,
+    parts: usize,
+) -> Vec<ExportRange> {
+    let total = timing.render_duration;
+    let cuts: Vec<(f64, f64)> = segments
+        .iter()
+        .skip(1)
+        .filter(|segment| {
+            !matches!(
+                segment,
+                ExportSegment::Clip {
+                    transition_in: Some(_),
+                    ..
+                }
+            )
+        })
+        .map(segment_start)
+        .collect();
+
+    let mut ranges = Vec::new();
+    let (mut start_output, mut start_timeline) = (0.0, timing.range.start);
+    for part in 1..parts {
+        let target = total * part as f64 / parts as f64;
+        let cut = cuts
+            .iter()
+            .filter(|(output, _)| {
+                *output - start_output >= MIN_PART_SECONDS && total - *output >= MIN_PART_SECONDS
+            })
+            .min_by(|(a, _), (b, _)| (a - target).abs().total_cmp(&(b - target).abs()));
+        if let Some(&(output, timeline)) = cut {
+            ranges.push(ExportRange {
+                start: start_timeline,
+                end: timeline,
+            });
+            (start_output, start_timeline) = (output, timeline);
+        }
+    }
+    ranges.push(ExportRange {
+        start: start_timeline,
+        end: timing.range.end,
+    });
+    ranges
+}
+
+/// Plans of the video parts of a parallel export of `ranges` (see
+/// `split_ranges`), each rendered without audio
+pub fn plan_parts(
+    tracks: &[Track],
+    media_library: &[MediaClip],
+    settings: &ExportSettings,
+    project_settings: &ProjectSettings,
+    keyframes: &HashMap<String, Vec<f64>>,
+    ranges: &[ExportRange],
+) -> Result<Vec<ExportPlan>, String> {
+    ranges
+        .iter()
+        .map(|range| {
+            let settings = ExportSettings {
+                range: Some(*range),
+                normalize_audio: false,
+                chapters: false,
+                metadata: HashMap::new(),
+                ..settings.clone()
+            };
+            let mut plan = ExportPlan::build(
+                tracks,
+                media_library,
+                &settings,
+                project_settings,
+                keyframes,
+            )?;
+            plan.encode.audio_codec = None;
+            Ok(plan)
+        })
+        .collect()
+}
+
+/// A parallel export's video parts, written out and ready to render
+pub struct PreparedParts {
+    /// Render command of each part, in order
+    pub commands: Vec<FfmpegCommandBuilder>,
+    /// Output length of each part in seconds
+    pub durations: Vec<f64>,
+    /// Concat list of the rendered parts (see `join_parts_builder`)
+    pub parts_file: PathBuf,
+}
+
+/// Write the concat files of the parts planned by `plan_parts` to
+/// `output_dir`, each part rendering to a Matroska file of its own
+pub fn prepare_parts(plans: &[ExportPlan], output_dir: &Path) -> Result<PreparedParts, String> {
+    let mut commands = Vec::new();
+    let mut outputs = Vec::new();
+    for (index, plan) in plans.iter().enumerate() {
+        let dir = output_dir.join(format!("part{}", index));
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create part directory: {}", e))?;
+        let concat_file = write_concat_file(&plan.segments, &dir)?;
+        let output = dir.join("video.mkv");
+        commands.push(plan.command_builder(&concat_file, &output));
+        outputs.push(output);
+    }
+    Ok(PreparedParts {
+        commands,
+        durations: plans.iter().map(|p| p.timing.render_duration).collect(),
+        parts_file: write_parts_file(&outputs, output_dir)?,
+    })
+}
+
+/// Write the rendered parts, in order, to `parts.txt` in `output_dir`
+fn write_parts_file(parts: &[PathBuf], output_dir: &Path) -> Result<PathBuf, String> {
+    let mut content = String::from("ffconcat version 1.0\n");
+    for part in parts {
+        content.push_str(&concat_file_directive(
+            &part.to_string_lossy(),
+            cfg!(windows),
+        )?);
+        content.push('\n');
+    }
+    let path = output_dir.join("parts.txt");
+    fs::write(&path, content).map_err(|e| format!("Failed to write parts file: {}", e))?;
+    Ok(path)
+}
+
+/// Join the rendered parts listed in `parts_file` and mux in the separately
+/// rendered `audio`, copying every stream
+pub fn join_parts_builder(
+    parts_file: &Path,
+    audio: Option<&Path>,
+    encode: &EncodeParams,
+    output_path: &Path,
+) -> FfmpegCommandBuilder {
+    let mut builder = FfmpegCommandBuilder::new().overwrite().input(
+        FfmpegInput::new(parts_file)
+            .format("concat")
+            .option("-safe", "0"),
+    );
+    builder = builder.option("-map", "0:v:0");
+    if let Some(audio) = audio {
+        builder = builder
+            .input(FfmpegInput::new(audio))
+            .option("-map", "1:a:0");
+    }
+    output_tags(builder.option("-c", "copy"), encode, output_path)
+        .progress(ProgressMode::Pipe)
+        .output(output_path)
+}
+
+/// Progress of a parallel render's processes combined into one report
+///
+/// Each process counts by the output seconds it renders (the audio process
+/// by `AUDIO_PART_WEIGHT` of its seconds), and the combined render reports
+/// as the `share` of the job starting at `offset`, like
+/// `ProgressReader::within`. Rendering speed is that of the processes
+/// running, so the ETA holds however many wait for a worker.
+#[derive(Debug, Clone)]
+pub struct PartProgress {
+    weights: Vec<f64>,
+    part_frames: Vec<u64>,
+    progress: Vec<f64>,
+    frames: Vec<u64>,
+    fps: Vec<f64>,
+    offset: f64,
+    share: f64,
+}
+
+impl PartProgress {
+    /// Parts of `durations` output seconds, followed by an audio render of
+    /// `audio_duration` when there is one
+    pub fn new(durations: &[f64], audio_duration: Option<f64>, output_fps: f64) -> Self {
+        let mut weights = durations.to_vec();
+        let mut part_frames: Vec<u64> = durations
+            .iter()
+            .map(|duration| (duration * output_fps).round() as u64)
+            .collect();
+        if let Some(duration) = audio_duration {
+            weights.push(duration * AUDIO_PART_WEIGHT);
+            part_frames.push(0);
+        }
+        let count = weights.len();
+        Self {
+            weights,
+            part_frames,
+            progress: vec![0.0; count],
+            frames: vec![0; count],
+            fps: vec![0.0; count],
+            offset: 0.0,
+            share: 1.0,
+        }
+    }
+
+    /// Report the render as the `share` of a job starting at `offset`
+    pub fn within(self, offset: f64, share: f64) -> Self {
+        Self {
+            offset,
+            share,
+            ..self
+        }
+    }
+
+    /// Progress once every part has finished
+    pub fn end(&self) -> f64 {
+        self.offset + self.share
+    }
+
+    /// Take part `index`'s latest report (its own 0-1 progress); the whole
+    /// render's
+    pub fn update(&mut self, index: usize, report: &ExportProgress) -> ExportProgress {
+        self.progress[index] = report.progress.clamp(0.0, 1.0);
+        self.frames[index] = report.current_frame.min(self.part_frames[index]);
+        self.fps[index] = report.fps;
+        self.report()
+    }
+
+    /// Mark part `index` rendered; the whole render's progress
+    pub fn finish(&mut self, index: usize) -> ExportProgress {
+        self.progress[index] = 1.0;
+        self.frames[index] = self.part_frames[index];
+        self.fps[index] = 0.0;
+        self.report()
+    }
+
+    /// The whole render's progress so far
+    pub fn report(&self) -> ExportProgress {
+        let total: f64 = self.weights.iter().sum();
+        let done: f64 = self
+            .weights
+            .iter()
+            .zip(&self.progress)
+            .map(|(weight, progress)| weight * progress)
+            .sum();
+        let fraction = if total > 0.0 { done / total } else { 0.0 };
+        let current_frame: u64 = self.frames.iter().sum();
+        let total_frames: u64 = self.part_frames.iter().sum();
+        let fps: f64 = self.fps.iter().sum();
+        let eta_seconds = if fps > 0.0 {
+            (total_frames.saturating_sub(current_frame) as f64 / fps).round() as u64
+        } else {
+            0
+        };
+        ExportProgress {
+            current_frame,
+            total_frames,
+            fps,
+            progress: self.offset + self.share * fraction,
+            eta_seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::export::GapHandling;
+
+    /// Main track segments of clips with these (timeline start, duration),
+    /// gaps collapsed
+    fn clip_segments(clips: &[(f64, f64)]) -> (Vec<ExportSegment>, ExportTiming) {
+        let mut output_start = 0.0;
+        let segments: Vec<ExportSegment> = clips
+            .iter()
+            .enumerate()
+            .map(|(i, &(timeline_start, duration))| {
+                let segment = ExportSegment::Clip {
+                    clip_id: format!("clip{}", i),
+                    media_clip_id: format!("media{}", i),
+                    path: format!("/media/{}.mp4", i),
+                    in_point: 0.0,
+                    out_point: duration,
+                    timeline_start,
+                    output_start,
+                    duration,
+                    transition_in: None,
+                };
+                output_start += duration;
+                segment
+            })
+            .collect();
+        let end = clips
+            .last()
+            .map_or(0.0, |(start, duration)| start + duration);
+        let timing = ExportTiming {
+            gap_handling: GapHandling::Collapse,
+            range: ExportRange { start: 0.0, end },
+            timeline_duration: end,
+            timeline_end: end,
+            render_duration: output_start,
+            frame_accurate: false,
+        };
+        (segments, timing)
+    }
+
+    fn progress(fraction: f64, frame: u64, fps: f64) -> ExportProgress {
+        ExportProgress {
+            current_frame: frame,
+            total_frames: 0,
+            fps,
+            progress: fraction,
+            eta_seconds: 0,
+        }
+    }
+
+    #[test]
+    fn test_split_ranges_cut_between_clips() {
+        // Six 5s clips with a collapsed 3s gap after the second
+        let (segments, timing) = clip_segments(&[
+            (0.0, 5.0),
+            (5.0, 5.0),
+            (13.0, 5.0),
+            (18.0, 5.0),
+            (23.0, 5.0),
+            (28.0, 5.0),
+        ]);
+        let ranges = split_ranges(&segments, &timing, 3);
+        assert_eq!(
+            ranges,
+            [
+                ExportRange {
+                    start: 0.0,
+                    end: 13.0
+                },
+                ExportRange {
+                    start: 13.0,
+                    end: 23.0
+                },
+                ExportRange {
+                    start: 23.0,
+                    end: 33.0
+                },
+            ]
+        );
+
+        // Contiguous, covering the export, and every clip inside one range
+        assert_eq!(ranges[0].start, timing.range.start);
+        assert_eq!(ranges.last().unwrap().end, timing.range.end);
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+        for segment in &segments {
+            let ExportSegment::Clip {
+                timeline_start,
+                duration,
+                ..
+            } = segment
+            else {
+                unreachable!()
+            };
+            let end = timeline_start + duration;
+            assert_eq!(
+                ranges
+                    .iter()
+                    .filter(|r| *timeline_start < r.end && end > r.start)
+                    .count(),
+                1
+            );
+            assert!(ranges
+                .iter()
+                .any(|r| r.start <= *timeline_start && end <= r.end));
+        }
+    }
+
+    #[test]
+    fn test_split_ranges_never_cuts_inside_a_clip() {
+        // One long clip can't be split however many parts are asked for
+        let (segments, timing) = clip_segments(&[(0.0, 60.0)]);
+        assert_eq!(
+            split_ranges(&segments, &timing, 8),
+            [ExportRange {
+                start: 0.0,
+                end: 60.0
+            }]
+        );
+
+        // Uneven clips: cuts land on the clip starts nearest the targets,
+        // and a tiny last clip isn't a part of its own
+        let (segments, timing) =
+            clip_segments(&[(0.0, 2.0), (2.0, 30.0), (32.0, 4.0), (36.0, 1.0)]);
+        assert_eq!(
+            split_ranges(&segments, &timing, 4),
+            [
+                ExportRange {
+                    start: 0.0,
+                    end: 2.0
+                },
+                ExportRange {
+                    start: 2.0,
+                    end: 32.0
+                },
+                ExportRange {
+                    start: 32.0,
+                    end: 37.0
+                },
+            ]
+        );
+
+        assert_eq!(split_ranges(&segments, &timing, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_part_progress_weighs_parts_by_length() {
+        // 30s and 10s of video at 10 fps, 40s of audio, after a 20% analysis
+        let mut parts = PartProgress::new(&[30.0, 10.0], Some(40.0), 10.0).within(0.2, 0.8);
+        assert!((parts.end() - 1.0).abs() < 1e-9);
+
+        // Half of the long part: 15 of 44 weighted seconds
+        let report = parts.update(0, &progress(0.5, 150, 40.0));
+        assert!((report.progress - (0.2 + 0.8 * 15.0 / 44.0)).abs() < 1e-9);
+        assert_eq!(report.current_frame, 150);
+        assert_eq!(report.total_frames, 400);
+        // 250 frames left at 40 fps
+        assert_eq!(report.eta_seconds, 6);
+
+        // A second worker adds its speed
+        let report = parts.update(1, &progress(0.5, 50, 20.0));
+        assert!((report.progress - (0.2 + 0.8 * 20.0 / 44.0)).abs() < 1e-9);
+        assert_eq!(report.fps, 60.0);
+        assert_eq!(report.eta_seconds, 3);
+
+        // Finished parts count whole and stop adding speed; the audio
+        // reports no frames
+        parts.finish(1);
+        let report = parts.update(2, &progress(1.0, 0, 500.0));
+        assert_eq!(report.current_frame, 250);
+        parts.finish(2);
+        let report = parts.finish(0);
+        assert!((report.progress - 1.0).abs() < 1e-9);
+        assert_eq!(report.current_frame, 400);
+        assert_eq!(report.fps, 0.0);
+        assert_eq!(report.eta_seconds, 0);
+    }
+}
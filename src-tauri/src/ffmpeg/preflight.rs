@@ -0,0 +1,430 @@
+// Export preflight
+// Everything an export needs is checked before FFmpeg starts, so a moved
+// source file or a clip whose media was removed shows up as a list of
+// problems per clip instead of an FFmpeg error halfway through the render.
+// Errors stop the export; warnings (proxies replaced by their sources, free
+// space that is only just enough) don't.
+
+use crate::ffmpeg::export::{
+    clips_in_range, preflight_files, EncodeParams, ExportTiming, FileIssue,
+};
+use crate::models::clip::MediaClip;
+use crate::models::timeline::Track;
+use crate::storage::work_dir::{check_writable, free_space};
+use serde::Serialize;
+use std::path::Path;
+
+/// Constant-quality H.264 bits per pixel at CRF 23; each 6 CRF steps halve it
+const BITS_PER_PIXEL_CRF23: f64 = 0.1;
+/// ProRes HQ / DNxHR HQ, about 220 Mbit/s at 1080p30
+const INTERMEDIATE_BITS_PER_PIXEL: f64 = 3.5;
+/// Palette GIF frames compress poorly
+const GIF_BITS_PER_PIXEL: f64 = 1.0;
+/// Free space below this multiple of the estimate is worth a warning, as
+/// the estimate is rough
+const DISK_SPACE_MARGIN: f64 = 2.0;
+
+/// What a preflight issue is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightCheck {
+    /// FFmpeg was not located
+    Ffmpeg,
+    /// A clip's media clip id isn't in the media library
+    MediaReference,
+    /// A source file can't be read
+    SourceFile,
+    /// A proxy can't be read, so the source is exported instead
+    ProxyFallback,
+    /// The output directory is missing or not writable
+    OutputDirectory,
+    /// The output may not fit on the disk
+    DiskSpace,
+}
+
+/// One problem found by the preflight
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightIssue {
+    pub check: PreflightCheck,
+    /// Timeline clip affected; None for problems with the whole export
+    pub clip_id: Option<String>,
+    pub media_clip_id: Option<String>,
+    /// File or directory at fault
+    pub path: Option<String>,
+    /// Why the file can't be used (`SourceFile`)
+    pub file_issue: Option<FileIssue>,
+    pub message: String,
+}
+
+impl PreflightIssue {
+    fn new(check: PreflightCheck, message: String) -> Self {
+        Self {
+            check,
+            clip_id: None,
+            media_clip_id: None,
+            path: None,
+            file_issue: None,
+            message,
+        }
+    }
+}
+
+/// Outcome of `preflight_export`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PreflightReport {
+    /// Problems that stop the export, clips in timeline track order
+    pub errors: Vec<PreflightIssue>,
+    pub warnings: Vec<PreflightIssue>,
+    /// Rough size of the output (see `estimate_output_bytes`)
+    pub estimated_bytes: u64,
+    /// Rough render time in seconds, from how fast earlier exports with the
+    /// codec ran; None until one has finished
+    pub estimated_seconds: Option<u64>,
+    /// Free space where the output goes, when it could be queried
+    pub free_bytes: Option<u64>,
+    /// Media clips whose unreadable proxy was replaced by the source
+    pub proxy_fallbacks: Vec<String>,
+}
+
+impl PreflightReport {
+    /// Whether the export can start
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// "2 problems stop the export: <message>; <message>"
+    pub fn summary(&self) -> String {
+        let count = self.errors.len();
+        format!(
+            "{} problem{} stop{} the export: {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            if count == 1 { "s" } else { "" },
+            self.errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; ")
+        )
+    }
+}
+
+/// Parse an FFmpeg bitrate such as "5M" or "800k" into kbps
+fn bitrate_kbps(bitrate: &str) -> Option<f64> {
+    let (number, scale) = match bitrate.char_indices().last()? {
+        (i, 'M') => (&bitrate[..i], 1000.0),
+        (i, 'k') => (&bitrate[..i], 1.0),
+        _ => (bitrate, 0.001),
+    };
+    number.parse::<f64>().ok().map(|n| n * scale)
+}
+
+/// Rough size in bytes of `duration` seconds encoded with `encode`
+///
+/// Uses the target bitrate where there is one; constant-quality encodes
+/// are estimated from bits per pixel, erring on the large side.
+pub fn estimate_output_bytes(encode: &EncodeParams, duration: f64) -> u64 {
+    let pixel_rate = f64::from(encode.width) * f64::from(encode.height) * encode.fps;
+    let video_kbps = match encode.video_bitrate.and_then(bitrate_kbps) {
+        Some(kbps) => kbps,
+        None => {
+            let bits_per_pixel = if encode.profile.is_some() {
+                INTERMEDIATE_BITS_PER_PIXEL
+            } else if encode.palette {
+                GIF_BITS_PER_PIXEL
+            } else {
+                let crf = f64::from(encode.crf.unwrap_or(23));
+                BITS_PER_PIXEL_CRF23 * 2f64.powf((23.0 - crf) / 6.0)
+            };
+            pixel_rate * bits_per_pixel / 1000.0
+        }
+    };
+    let audio_kbps = match (encode.audio_codec, encode.audio_bitrate) {
+        (None, _) => 0.0,
+        (Some(_), Some(kbps)) => f64::from(kbps),
+        // 16-bit stereo PCM
+        (Some(_), None) => f64::from(encode.audio_sample_rate.unwrap_or(48000)) * 32.0 / 1000.0,
+    };
+    ((video_kbps + audio_kbps) * 1000.0 / 8.0 * duration.max(0.0)) as u64
+}
+
+/// Check that the export described by `timing` and `encode` can run
+///
+/// `ffmpeg` is the outcome of locating FFmpeg, and `speed_factor` how fast
+/// earlier exports with the codec rendered (see
+/// `CacheDb::get_encoder_speed_factor`). The output directory and
+/// disk space are only checked with an `output_path`. As with
+/// `preflight_files`, media clips whose proxy can't be read have it
+/// dropped from `media_library` so the source is exported.
+pub fn preflight_export(
+    tracks: &[Track],
+    media_library: &mut [MediaClip],
+    timing: &ExportTiming,
+    encode: &EncodeParams,
+    output_path: Option<&Path>,
+    ffmpeg: Result<(), String>,
+    speed_factor: Option<f64>,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    if let Err(message) = ffmpeg {
+        report
+            .errors
+            .push(PreflightIssue::new(PreflightCheck::Ffmpeg, message));
+    }
+
+    let files = preflight_files(tracks, media_library, timing);
+    for clip in clips_in_range(tracks, timing.range) {
+        let Some(media) = media_library.iter().find(|m| m.id == clip.media_clip_id) else {
+            report.errors.push(PreflightIssue {
+                clip_id: Some(clip.id.clone()),
+                media_clip_id: Some(clip.media_clip_id.clone()),
+                ..PreflightIssue::new(
+                    PreflightCheck::MediaReference,
+                    format!(
+                        "Clip {} uses media {}, which is not in the media library",
+                        clip.id, clip.media_clip_id
+                    ),
+                )
+            });
+            continue;
+        };
+        if let Some(problem) = files.problems.iter().find(|p| p.media_clip_id == media.id) {
+            report.errors.push(PreflightIssue {
+                clip_id: Some(clip.id.clone()),
+                media_clip_id: Some(media.id.clone()),
+                path: Some(problem.path.clone()),
+                file_issue: Some(problem.issue),
+                ..PreflightIssue::new(
+                    PreflightCheck::SourceFile,
+                    format!(
+                        "{}: {} is {}",
+                        media.name,
+                        problem.path,
+                        problem.issue.label()
+                    ),
+                )
+            });
+        } else if files.proxy_fallbacks.contains(&media.id) {
+            report.warnings.push(PreflightIssue {
+                clip_id: Some(clip.id.clone()),
+                media_clip_id: Some(media.id.clone()),
+                ..PreflightIssue::new(
+                    PreflightCheck::ProxyFallback,
+                    format!(
+                        "{}: the proxy can't be read, so the source is exported",
+                        media.name
+                    ),
+                )
+            });
+        }
+    }
+    report.proxy_fallbacks = files.proxy_fallbacks;
+
+    report.estimated_bytes = estimate_output_bytes(encode, timing.render_duration);
+    report.estimated_seconds = speed_factor
+        .filter(|factor| *factor > 0.0)
+        .map(|factor| (timing.render_duration / factor).round() as u64);
+    let Some(output_path) = output_path else {
+        return report;
+    };
+    let dir = output_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let path = Some(dir.to_string_lossy().to_string());
+    if let Err(message) = check_writable("Output directory", dir) {
+        report.errors.push(PreflightIssue {
+            path,
+            ..PreflightIssue::new(PreflightCheck::OutputDirectory, message)
+        });
+        return report;
+    }
+
+    report.free_bytes = free_space(dir);
+    if let Some(free) = report.free_bytes {
+        let (needed, free_mb) = (report.estimated_bytes / (1024 * 1024), free / (1024 * 1024));
+        if free < report.estimated_bytes {
+            report.errors.push(PreflightIssue {
+                path,
+                ..PreflightIssue::new(
+                    PreflightCheck::DiskSpace,
+                    format!(
+                        "The export needs about {} MB but {} has only {} MB free",
+                        needed,
+                        dir.display(),
+                        free_mb
+                    ),
+                )
+            });
+        } else if (free as f64) < report.estimated_bytes as f64 * DISK_SPACE_MARGIN {
+            report.warnings.push(PreflightIssue {
+                path,
+                ..PreflightIssue::new(
+                    PreflightCheck::DiskSpace,
+                    format!(
+                        "The export needs about {} MB and {} has {} MB free; the estimate is rough",
+                        needed,
+                        dir.display(),
+                        free_mb
+                    ),
+                )
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::export::{ExportSettings, GapHandling};
+    use crate::models::project::ProjectSettings;
+    use crate::models::timeline::{TimelineClip, TrackType};
+    use tempfile::TempDir;
+
+    fn media(path: &Path) -> MediaClip {
+        MediaClip::new(
+            path.to_string_lossy().to_string(),
+            10.0,
+            1920,
+            1080,
+            30.0,
+            "h264".to_string(),
+            4,
+        )
+    }
+
+    fn run(
+        tracks: &[Track],
+        media_library: &mut [MediaClip],
+        output_path: Option<&Path>,
+    ) -> PreflightReport {
+        let timing = ExportTiming::new(tracks, GapHandling::Collapse, None);
+        let encode = EncodeParams::new(&ExportSettings::default(), &ProjectSettings::default());
+        preflight_export(
+            tracks,
+            media_library,
+            &timing,
+            &encode,
+            output_path,
+            Ok(()),
+            None,
+        )
+    }
+
+    fn main_track(clips: Vec<TimelineClip>) -> Track {
+        let mut track = Track::new("Main".to_string(), TrackType::Main);
+        track.clips = clips;
+        track
+    }
+
+    #[test]
+    fn test_missing_file_is_reported_per_clip() {
+        let temp_dir = TempDir::new().unwrap();
+        let present = temp_dir.path().join("a.mp4");
+        std::fs::write(&present, b"data").unwrap();
+        let mut library = vec![media(&present), media(&temp_dir.path().join("moved.mp4"))];
+        let (fine, gone) = (library[0].id.clone(), library[1].id.clone());
+        let clips = vec![
+            TimelineClip::new(fine, "main".to_string(), 0.0, 0.0, 2.0),
+            TimelineClip::new(gone.clone(), "main".to_string(), 2.0, 0.0, 2.0),
+            TimelineClip::new(gone.clone(), "main".to_string(), 4.0, 5.0, 7.0),
+        ];
+        let tracks = vec![main_track(clips.clone())];
+
+        let output = temp_dir.path().join("out.mp4");
+        let report = run(&tracks, &mut library, Some(&output));
+        assert!(!report.passed());
+        let clip_ids: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.check, e.clip_id.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            clip_ids,
+            [
+                (PreflightCheck::SourceFile, clips[1].id.as_str()),
+                (PreflightCheck::SourceFile, clips[2].id.as_str())
+            ]
+        );
+        assert_eq!(
+            report.errors[0].media_clip_id.as_deref(),
+            Some(gone.as_str())
+        );
+        assert_eq!(report.errors[0].file_issue, Some(FileIssue::Missing));
+        assert!(report
+            .summary()
+            .starts_with("2 problems stop the export: moved.mp4"));
+        assert!(report.estimated_bytes > 0);
+        assert_eq!(report.estimated_seconds, None);
+    }
+
+    #[test]
+    fn test_estimated_seconds_from_speed_factor() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.mp4");
+        std::fs::write(&path, b"data").unwrap();
+        let mut library = vec![media(&path)];
+        let clip = TimelineClip::new(library[0].id.clone(), "main".to_string(), 0.0, 0.0, 6.0);
+        let tracks = vec![main_track(vec![clip])];
+        let timing = ExportTiming::new(&tracks, GapHandling::Collapse, None);
+        let encode = EncodeParams::new(&ExportSettings::default(), &ProjectSettings::default());
+
+        // 6s of output at 1.5x
+        let report = preflight_export(
+            &tracks,
+            &mut library,
+            &timing,
+            &encode,
+            None,
+            Ok(()),
+            Some(1.5),
+        );
+        assert_eq!(report.estimated_seconds, Some(4));
+    }
+
+    #[test]
+    fn test_missing_media_id_and_output_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let present = temp_dir.path().join("a.mp4");
+        std::fs::write(&present, b"data").unwrap();
+        let mut library = vec![media(&present)];
+        let orphan = TimelineClip::new("deleted".to_string(), "main".to_string(), 0.0, 0.0, 2.0);
+        let tracks = vec![main_track(vec![orphan.clone()])];
+
+        let output = temp_dir.path().join("missing_dir").join("out.mp4");
+        let report = run(&tracks, &mut library, Some(&output));
+        let checks: Vec<_> = report.errors.iter().map(|e| e.check).collect();
+        assert_eq!(
+            checks,
+            [
+                PreflightCheck::MediaReference,
+                PreflightCheck::OutputDirectory
+            ]
+        );
+        assert_eq!(report.errors[0].clip_id, Some(orphan.id));
+        assert_eq!(report.errors[0].media_clip_id.as_deref(), Some("deleted"));
+        assert_eq!(report.free_bytes, None);
+
+        // Without an output path only the media is checked
+        let report = run(&tracks, &mut library, None);
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_estimate_output_bytes() {
+        let settings = ExportSettings::default();
+        let encode = EncodeParams::new(&settings, &ProjectSettings::default());
+        let minute = estimate_output_bytes(&encode, 60.0);
+        assert_eq!(estimate_output_bytes(&encode, 120.0), minute * 2);
+
+        let hardware = EncodeParams {
+            video_bitrate: Some("5M"),
+            audio_bitrate: Some(192),
+            ..encode
+        };
+        // (5000 + 192) kbps for 10 s
+        assert_eq!(estimate_output_bytes(&hardware, 10.0), 6_490_000);
+        assert_eq!(bitrate_kbps("800k"), Some(800.0));
+    }
+}
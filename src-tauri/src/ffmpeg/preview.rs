@@ -0,0 +1,803 @@
+// Timeline preview rendering
+// The timeline is split into fixed-length segments that are rendered at reduced
+// resolution. Each segment is keyed by a hash of everything that affects its
+// pixels and audio, so edits only invalidate the segments they touch.
+
+use crate::ffmpeg::effects::{clip_video_filters, region_masks, RegionMask};
+use crate::ffmpeg::export::{canvas_filter, freeze_filter};
+use crate::ffmpeg::locate::ffmpeg_path;
+use crate::ffmpeg::{audio_stream_spec, playback_audio_stream};
+use crate::models::playback::{main_track, track_segments, PlaybackSegment};
+use crate::models::project::{Project, ProjectSettings};
+use crate::models::timeline::{ClipEffect, FreezeEdge, FreezeExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::process::Command;
+
+/// Length of a preview segment in seconds
+pub const PREVIEW_SEGMENT_SECONDS: f64 = 5.0;
+
+/// Bump when the render recipe changes so old cached segments are not reused
+const PREVIEW_FORMAT_VERSION: u32 = 1;
+
+/// Durations shorter than this are treated as empty
+const EPSILON: f64 = 1e-3;
+
+/// Preview render quality
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewQuality {
+    /// 640x360 box, fastest
+    Draft,
+    /// 960x540 box
+    #[default]
+    Standard,
+    /// 1280x720 box
+    High,
+}
+
+impl PreviewQuality {
+    /// Bounding box the project canvas is fitted into
+    fn max_dimensions(&self) -> (u32, u32) {
+        match self {
+            PreviewQuality::Draft => (640, 360),
+            PreviewQuality::Standard => (960, 540),
+            PreviewQuality::High => (1280, 720),
+        }
+    }
+
+    /// Preview frame size: the project canvas scaled into the bounding box (even)
+    pub fn dimensions(&self, settings: &ProjectSettings) -> (u32, u32) {
+        let (max_w, max_h) = self.max_dimensions();
+        let scale = (max_w as f64 / settings.width as f64)
+            .min(max_h as f64 / settings.height as f64)
+            .min(1.0);
+        let even = |v: f64| ((v.round() as u32) & !1).max(2);
+        (
+            even(settings.width as f64 * scale),
+            even(settings.height as f64 * scale),
+        )
+    }
+
+    fn preset(&self) -> &'static str {
+        match self {
+            PreviewQuality::Draft => "ultrafast",
+            PreviewQuality::Standard => "veryfast",
+            PreviewQuality::High => "faster",
+        }
+    }
+
+    fn crf(&self) -> u32 {
+        match self {
+            PreviewQuality::Draft => 30,
+            PreviewQuality::Standard => 26,
+            PreviewQuality::High => 23,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreviewQuality::Draft => "draft",
+            PreviewQuality::Standard => "standard",
+            PreviewQuality::High => "high",
+        }
+    }
+}
+
+/// Resolution factor applied on top of the preview quality
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewScale {
+    #[default]
+    Full,
+    Half,
+    Quarter,
+}
+
+impl PreviewScale {
+    pub fn factor(&self) -> f64 {
+        match self {
+            PreviewScale::Full => 1.0,
+            PreviewScale::Half => 0.5,
+            PreviewScale::Quarter => 0.25,
+        }
+    }
+
+    /// Scale `(width, height)` keeping both even
+    pub fn apply(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let even = |v: u32| (((v as f64 * self.factor()).round() as u32) & !1).max(2);
+        (even(width), even(height))
+    }
+
+    fn lower(&self) -> Option<PreviewScale> {
+        match self {
+            PreviewScale::Full => Some(PreviewScale::Half),
+            PreviewScale::Half => Some(PreviewScale::Quarter),
+            PreviewScale::Quarter => None,
+        }
+    }
+
+    fn higher(&self) -> Option<PreviewScale> {
+        match self {
+            PreviewScale::Full => None,
+            PreviewScale::Half => Some(PreviewScale::Full),
+            PreviewScale::Quarter => Some(PreviewScale::Half),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreviewScale::Full => "full",
+            PreviewScale::Half => "half",
+            PreviewScale::Quarter => "quarter",
+        }
+    }
+}
+
+/// Render samples kept for the automatic scale decision
+const AUTO_SCALE_WINDOW: usize = 5;
+
+/// Samples needed before stepping down
+const AUTO_SCALE_MIN_SAMPLES: usize = 3;
+
+/// Step down when rendering takes longer than real time on average
+const AUTO_SCALE_DOWN_RATIO: f64 = 1.0;
+
+/// Step back up when a full window renders this much faster than real time
+const AUTO_SCALE_UP_RATIO: f64 = 0.35;
+
+/// Picks the preview scale in `auto` mode from recent render speed
+///
+/// Each sample is render time divided by rendered timeline duration, so a
+/// ratio above 1 means previews can't keep up with playback.
+#[derive(Debug, Clone, Default)]
+pub struct AdaptivePreviewScale {
+    current: PreviewScale,
+    ratios: VecDeque<f64>,
+}
+
+impl AdaptivePreviewScale {
+    pub fn current(&self) -> PreviewScale {
+        self.current
+    }
+
+    /// Record a render; returns the new scale when it changed
+    pub fn record(&mut self, render_seconds: f64, content_seconds: f64) -> Option<PreviewScale> {
+        if content_seconds <= 0.0 || !render_seconds.is_finite() {
+            return None;
+        }
+        self.ratios.push_back(render_seconds / content_seconds);
+        if self.ratios.len() > AUTO_SCALE_WINDOW {
+            self.ratios.pop_front();
+        }
+
+        let average = self.ratios.iter().sum::<f64>() / self.ratios.len() as f64;
+        let next = if self.ratios.len() >= AUTO_SCALE_MIN_SAMPLES && average > AUTO_SCALE_DOWN_RATIO
+        {
+            self.current.lower()
+        } else if self.ratios.len() == AUTO_SCALE_WINDOW
+            && self.ratios.iter().all(|r| *r < AUTO_SCALE_UP_RATIO)
+        {
+            self.current.higher()
+        } else {
+            None
+        };
+
+        let next = next?;
+        self.current = next;
+        // Samples taken at the old scale say nothing about the new one
+        self.ratios.clear();
+        Some(next)
+    }
+}
+
+/// A piece of a preview segment
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentPart {
+    /// A range of a media file
+    Media {
+        path: String,
+        /// Media file size, so replaced files invalidate the segment
+        file_size: i64,
+        source_start: f64,
+        duration: f64,
+        has_audio: bool,
+        /// Selected audio stream position (None = default stream)
+        audio_stream: Option<usize>,
+        /// The timeline clip's effects and redactions, rendered as at export
+        effects: Vec<ClipEffect>,
+        redactions: Vec<RegionMask>,
+    },
+    /// One frame of a media file held for `duration` in silence (a clip's
+    /// freeze extension)
+    Still {
+        path: String,
+        file_size: i64,
+        source_time: f64,
+        duration: f64,
+        effects: Vec<ClipEffect>,
+        redactions: Vec<RegionMask>,
+    },
+    /// Empty timeline: background color and silence
+    Gap { duration: f64 },
+}
+
+/// What to render for one preview segment
+#[derive(Debug, Clone)]
+pub struct PreviewSegmentPlan {
+    pub index: u64,
+    /// Timeline start of the segment in seconds
+    pub start: f64,
+    /// Timeline end of the segment in seconds
+    pub end: f64,
+    pub quality: PreviewQuality,
+    pub scale: PreviewScale,
+    pub parts: Vec<SegmentPart>,
+    /// Content hash identifying the rendered output
+    pub key: String,
+}
+
+impl PreviewSegmentPlan {
+    /// Output frame size for this plan's quality and scale
+    pub fn dimensions(&self, settings: &ProjectSettings) -> (u32, u32) {
+        self.scale.apply(self.quality.dimensions(settings))
+    }
+}
+
+/// Plan the segments covering `start..end` of the project timeline
+///
+/// The range is clamped to the timeline and widened to segment boundaries.
+pub fn plan_preview_range(
+    project: &Project,
+    start: f64,
+    end: f64,
+    quality: PreviewQuality,
+    scale: PreviewScale,
+) -> Vec<PreviewSegmentPlan> {
+    let duration = project.duration();
+    let start = start.max(0.0);
+    let end = end.min(duration);
+    if end - start < EPSILON {
+        return Vec::new();
+    }
+
+    let first = (start / PREVIEW_SEGMENT_SECONDS).floor() as u64;
+    let last = ((end - EPSILON) / PREVIEW_SEGMENT_SECONDS).floor() as u64;
+    (first..=last)
+        .filter_map(|index| plan_segment(project, index, quality, scale))
+        .collect()
+}
+
+/// Plan a single segment by index, or None if it lies past the timeline end
+pub fn plan_segment(
+    project: &Project,
+    index: u64,
+    quality: PreviewQuality,
+    scale: PreviewScale,
+) -> Option<PreviewSegmentPlan> {
+    let start = index as f64 * PREVIEW_SEGMENT_SECONDS;
+    let end = (start + PREVIEW_SEGMENT_SECONDS).min(project.duration());
+    if end - start < EPSILON {
+        return None;
+    }
+
+    let parts = segment_parts(project, start, end);
+    let key = segment_key(&project.settings, quality, scale, start, end, &parts);
+    Some(PreviewSegmentPlan {
+        index,
+        start,
+        end,
+        quality,
+        scale,
+        parts,
+        key,
+    })
+}
+
+/// Media ranges and gaps of the main track within `start..end`
+fn segment_parts(project: &Project, start: f64, end: f64) -> Vec<SegmentPart> {
+    let Some(track) = main_track(project) else {
+        return vec![SegmentPart::Gap {
+            duration: end - start,
+        }];
+    };
+    let segments = track_segments(project, track, true, end);
+
+    let mut parts = Vec::new();
+    let mut cursor = start;
+    for segment in segments {
+        if segment.timeline_start() >= end - EPSILON {
+            break;
+        }
+        let part_end = segment.timeline_end().min(end);
+        if part_end <= cursor + EPSILON {
+            continue;
+        }
+        match segment {
+            PlaybackSegment::Clip {
+                clip_id,
+                media_clip_id,
+                file_path,
+                is_proxy,
+                source_in,
+                timeline_start,
+                speed,
+                ..
+            } => {
+                let Some(media) = project.media_library.iter().find(|m| m.id == media_clip_id)
+                else {
+                    continue;
+                };
+                let clip = track.clips.iter().find(|c| c.id == clip_id);
+                let effects = clip.map(|c| c.effects.clone()).unwrap_or_default();
+                let redactions = clip
+                    .map(|c| {
+                        let offset = cursor - c.start_time;
+                        region_masks(&c.redactions, media.width, media.height, offset)
+                    })
+                    .unwrap_or_default();
+                parts.push(if speed == 0.0 {
+                    SegmentPart::Still {
+                        path: file_path,
+                        file_size: media.file_size,
+                        source_time: source_in,
+                        duration: part_end - cursor,
+                        effects,
+                        redactions,
+                    }
+                } else {
+                    SegmentPart::Media {
+                        path: file_path,
+                        file_size: media.file_size,
+                        source_start: source_in + (cursor - timeline_start),
+                        duration: part_end - cursor,
+                        has_audio: media.has_audio,
+                        audio_stream: playback_audio_stream(media, is_proxy),
+                        effects,
+                        redactions,
+                    }
+                });
+            }
+            PlaybackSegment::Gap { .. } => parts.push(SegmentPart::Gap {
+                duration: part_end - cursor,
+            }),
+        }
+        cursor = part_end;
+    }
+
+    if end - cursor > EPSILON {
+        parts.push(SegmentPart::Gap {
+            duration: end - cursor,
+        });
+    }
+    parts
+}
+
+/// Hash of everything that affects a segment's output
+fn segment_key(
+    settings: &ProjectSettings,
+    quality: PreviewQuality,
+    scale: PreviewScale,
+    start: f64,
+    end: f64,
+    parts: &[SegmentPart],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "v{}|{}/{}|{}x{}@{}|{}|{:.6}-{:.6}\n",
+        PREVIEW_FORMAT_VERSION,
+        quality.as_str(),
+        scale.as_str(),
+        settings.width,
+        settings.height,
+        settings.fps,
+        settings.background_color,
+        start,
+        end
+    ));
+    for part in parts {
+        match part {
+            SegmentPart::Media {
+                path,
+                file_size,
+                source_start,
+                duration,
+                has_audio,
+                audio_stream,
+                effects,
+                redactions,
+            } => hasher.update(format!(
+                "media|{}|{}|{:.6}|{:.6}|{}|{:?}|{:?}|{:?}\n",
+                path,
+                file_size,
+                source_start,
+                duration,
+                has_audio,
+                audio_stream,
+                effects,
+                redactions
+            )),
+            SegmentPart::Still {
+                path,
+                file_size,
+                source_time,
+                duration,
+                effects,
+                redactions,
+            } => hasher.update(format!(
+                "still|{}|{}|{:.6}|{:.6}|{:?}|{:?}\n",
+                path, file_size, source_time, duration, effects, redactions
+            )),
+            SegmentPart::Gap { duration } => hasher.update(format!("gap|{:.6}\n", duration)),
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Build the FFmpeg command rendering a planned segment to `output_path`
+pub fn build_preview_command(
+    plan: &PreviewSegmentPlan,
+    settings: &ProjectSettings,
+    output_path: &Path,
+) -> Command {
+    let (width, height) = plan.dimensions(settings);
+    let color = settings.ffmpeg_background_color();
+    let silence =
+        |duration: f64| format!("anullsrc=r=48000:cl=stereo,atrim=duration={:.6}", duration);
+
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y"]);
+
+    let mut filters = Vec::new();
+    let mut concat_inputs = String::new();
+    let mut input_index = 0;
+    for (i, part) in plan.parts.iter().enumerate() {
+        match part {
+            SegmentPart::Media {
+                path,
+                source_start,
+                duration,
+                has_audio,
+                audio_stream,
+                effects,
+                redactions,
+                ..
+            } => {
+                cmd.arg("-ss")
+                    .arg(format!("{:.6}", source_start))
+                    .arg("-t")
+                    .arg(format!("{:.6}", duration))
+                    .arg("-i")
+                    .arg(path);
+                filters.extend(clip_video_filters(
+                    input_index,
+                    redactions,
+                    effects,
+                    &format!(
+                        "{},fps={},setsar=1,format=yuv420p",
+                        canvas_filter(width, height, &color),
+                        settings.fps
+                    ),
+                    &format!("v{}", i),
+                ));
+                filters.push(if *has_audio {
+                    format!(
+                        "[{}]aresample=48000,aformat=channel_layouts=stereo,apad,atrim=duration={:.6}[a{}]",
+                        audio_stream_spec(input_index, *audio_stream),
+                        duration,
+                        i
+                    )
+                } else {
+                    format!("{}[a{}]", silence(*duration), i)
+                });
+                input_index += 1;
+            }
+            SegmentPart::Still {
+                path,
+                source_time,
+                duration,
+                effects,
+                redactions,
+                ..
+            } => {
+                // A second of input is plenty to decode the one frame kept
+                cmd.arg("-ss")
+                    .arg(format!("{:.6}", source_time))
+                    .args(["-t", "1"])
+                    .arg("-i")
+                    .arg(path);
+                let hold = FreezeExtension {
+                    edge: FreezeEdge::End,
+                    duration: *duration,
+                };
+                filters.extend(clip_video_filters(
+                    input_index,
+                    redactions,
+                    effects,
+                    &format!(
+                        "trim=end_frame=1,{},{},fps={},setsar=1,format=yuv420p,trim=duration={:.6}",
+                        freeze_filter(hold),
+                        canvas_filter(width, height, &color),
+                        settings.fps,
+                        duration
+                    ),
+                    &format!("v{}", i),
+                ));
+                filters.push(format!("{}[a{}]", silence(*duration), i));
+                input_index += 1;
+            }
+            SegmentPart::Gap { duration } => {
+                filters.push(format!(
+                    "color=c={}:s={}x{}:r={}:d={:.6},setsar=1,format=yuv420p[v{}]",
+                    color, width, height, settings.fps, duration, i
+                ));
+                filters.push(format!("{}[a{}]", silence(*duration), i));
+            }
+        }
+        concat_inputs.push_str(&format!("[v{}][a{}]", i, i));
+    }
+    filters.push(format!(
+        "{}concat=n={}:v=1:a=1[vout][aout]",
+        concat_inputs,
+        plan.parts.len()
+    ));
+
+    cmd.arg("-filter_complex")
+        .arg(filters.join(";"))
+        .args(["-map", "[vout]", "-map", "[aout]"])
+        .args(["-c:v", "libx264", "-preset", plan.quality.preset()])
+        .arg("-crf")
+        .arg(plan.quality.crf().to_string())
+        .args(["-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart"])
+        .arg(output_path);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaClip;
+    use crate::models::timeline::TimelineClip;
+
+    /// Main track: A at 0-4s, gap, B at 6-14s
+    fn project() -> Project {
+        let mut project = Project::new("Preview".to_string());
+        let mut a = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        a.has_audio = true;
+        let b = MediaClip::new("/m/b.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 2);
+        let track_id = project.tracks[0].id.clone();
+        project.tracks[0].clips = vec![
+            TimelineClip::new(a.id.clone(), track_id.clone(), 0.0, 10.0, 14.0),
+            TimelineClip::new(b.id.clone(), track_id, 6.0, 2.0, 10.0),
+        ];
+        project.media_library = vec![a, b];
+        project
+    }
+
+    #[test]
+    fn test_plan_covers_range_with_gaps() {
+        let project = project();
+        let plans = plan_preview_range(
+            &project,
+            1.0,
+            100.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full,
+        );
+
+        assert_eq!(plans.len(), 3);
+        assert_eq!((plans[2].start, plans[2].end), (10.0, 14.0));
+
+        // 0-5s: A for 4s then a 1s gap
+        assert_eq!(plans[0].parts.len(), 2);
+        assert!(matches!(
+            plans[0].parts[0],
+            SegmentPart::Media { source_start, duration, .. }
+                if source_start == 10.0 && duration == 4.0
+        ));
+        assert!(matches!(plans[0].parts[1], SegmentPart::Gap { duration } if duration == 1.0));
+
+        // 5-10s: 1s gap, then B starting at its in-point
+        assert!(matches!(plans[1].parts[0], SegmentPart::Gap { duration } if duration == 1.0));
+        assert!(matches!(
+            plans[1].parts[1],
+            SegmentPart::Media { source_start, duration, .. }
+                if source_start == 2.0 && duration == 4.0
+        ));
+
+        // 10-14s: B continues mid-clip
+        assert!(matches!(
+            plans[2].parts[0],
+            SegmentPart::Media { source_start, .. } if source_start == 6.0
+        ));
+    }
+
+    #[test]
+    fn test_keys_only_change_for_affected_segments() {
+        let mut project = project();
+        let before = plan_preview_range(
+            &project,
+            0.0,
+            14.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full,
+        );
+
+        // Trim the end of B: only the last segment changes
+        project.tracks[0].clips[1].out_point = 9.0;
+        let after = plan_preview_range(
+            &project,
+            0.0,
+            14.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full,
+        );
+        assert_eq!(before[0].key, after[0].key);
+        assert_eq!(before[1].key, after[1].key);
+        assert_ne!(before[2].key, after[2].key);
+
+        // Quality and project settings are part of the key
+        let draft = plan_preview_range(
+            &project,
+            0.0,
+            5.0,
+            PreviewQuality::Draft,
+            PreviewScale::Full,
+        );
+        assert_ne!(draft[0].key, after[0].key);
+        project.settings.background_color = "#FFFFFF".to_string();
+        let recolored = plan_preview_range(
+            &project,
+            0.0,
+            5.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full,
+        );
+        assert_ne!(recolored[0].key, after[0].key);
+    }
+
+    #[test]
+    fn test_preview_dimensions_follow_project_aspect() {
+        let settings = ProjectSettings::default();
+        assert_eq!(PreviewQuality::Standard.dimensions(&settings), (960, 540));
+
+        let vertical = ProjectSettings {
+            width: 1080,
+            height: 1920,
+            ..Default::default()
+        };
+        assert_eq!(PreviewQuality::Standard.dimensions(&vertical), (304, 540));
+
+        // Never upscale small canvases
+        let small = ProjectSettings {
+            width: 320,
+            height: 240,
+            ..Default::default()
+        };
+        assert_eq!(PreviewQuality::High.dimensions(&small), (320, 240));
+    }
+
+    #[test]
+    fn test_build_preview_command() {
+        let project = project();
+        let plan = plan_segment(&project, 0, PreviewQuality::Draft, PreviewScale::Full).unwrap();
+        let cmd = build_preview_command(&plan, &project.settings, Path::new("/tmp/seg.mp4"));
+        let cmd_str = format!("{:?}", cmd);
+
+        assert!(cmd_str.contains("\"-ss\" \"10.000000\""));
+        assert!(cmd_str.contains("scale=640:360:force_original_aspect_ratio=decrease"));
+        assert!(cmd_str.contains("color=c=0x000000:s=640x360"));
+        assert!(cmd_str.contains("concat=n=2:v=1:a=1[vout][aout]"));
+        assert!(cmd_str.contains("ultrafast"));
+    }
+
+    #[test]
+    fn test_effects_render_in_preview() {
+        let mut project = project();
+        let plain = plan_segment(&project, 0, PreviewQuality::Draft, PreviewScale::Full).unwrap();
+        project.tracks[0].clips[0].effects =
+            vec![ClipEffect::GaussianBlur { sigma: 3.0 }, ClipEffect::FlipH];
+        let plan = plan_segment(&project, 0, PreviewQuality::Draft, PreviewScale::Full).unwrap();
+        assert_ne!(plain.key, plan.key);
+
+        let cmd_str = format!(
+            "{:?}",
+            build_preview_command(&plan, &project.settings, Path::new("/tmp/seg.mp4"))
+        );
+        assert!(cmd_str.contains("[0:v]hflip,gblur=sigma=3.00,scale=640:360"));
+    }
+
+    #[test]
+    fn test_redactions_render_in_preview() {
+        use crate::models::timeline::{Redaction, RedactionKind};
+
+        // B starts at 6s; segment 1 (5-10s) reads it from its start
+        let mut project = project();
+        project.tracks[0].clips[1].redactions = vec![Redaction {
+            x: 960,
+            y: 0,
+            width: 960,
+            height: 540,
+            start: 1.0,
+            end: 3.0,
+            kind: RedactionKind::Box,
+        }];
+        let plan = plan_segment(&project, 1, PreviewQuality::Draft, PreviewScale::Full).unwrap();
+        let cmd_str = format!(
+            "{:?}",
+            build_preview_command(&plan, &project.settings, Path::new("/tmp/seg.mp4"))
+        );
+        assert!(cmd_str.contains(
+            "[0:v]drawbox=x=iw*0.500000:y=ih*0.000000:w=iw*0.500000:h=ih*0.500000:\
+             color=black:t=fill:enable='between(t,1.000000,3.000000)'[v1r]"
+        ));
+        assert!(cmd_str.contains("[v1r]scale=640:360"));
+    }
+
+    #[test]
+    fn test_scale_reduces_output_and_changes_key() {
+        let project = project();
+        let full = plan_segment(&project, 0, PreviewQuality::Standard, PreviewScale::Full).unwrap();
+        let half = plan_segment(&project, 0, PreviewQuality::Standard, PreviewScale::Half).unwrap();
+        assert_ne!(full.key, half.key);
+        assert_eq!(half.dimensions(&project.settings), (480, 270));
+        assert_eq!(
+            PreviewScale::Quarter.apply(half.quality.dimensions(&project.settings)),
+            (240, 134)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_scale_steps_down_when_slow() {
+        let mut auto = AdaptivePreviewScale::default();
+        // Faster than real time: stay at full
+        for _ in 0..4 {
+            assert_eq!(auto.record(2.0, 5.0), None);
+        }
+        assert_eq!(auto.current(), PreviewScale::Full);
+
+        // Renders now take longer than the content; needs a few samples to react
+        assert_eq!(auto.record(9.0, 5.0), None);
+        assert_eq!(auto.record(9.0, 5.0), None);
+        assert_eq!(auto.record(9.0, 5.0), Some(PreviewScale::Half));
+        assert_eq!(auto.record(8.0, 5.0), None);
+        assert_eq!(auto.record(8.0, 5.0), None);
+        assert_eq!(auto.record(8.0, 5.0), Some(PreviewScale::Quarter));
+
+        // Already at the lowest scale
+        for _ in 0..5 {
+            assert_eq!(auto.record(8.0, 5.0), None);
+        }
+        assert_eq!(auto.current(), PreviewScale::Quarter);
+    }
+
+    #[test]
+    fn test_adaptive_scale_recovers_after_fast_window() {
+        let mut auto = AdaptivePreviewScale::default();
+        for _ in 0..3 {
+            auto.record(10.0, 5.0);
+        }
+        assert_eq!(auto.current(), PreviewScale::Half);
+
+        for _ in 0..4 {
+            assert_eq!(auto.record(1.0, 5.0), None);
+        }
+        assert_eq!(auto.record(1.0, 5.0), Some(PreviewScale::Full));
+        assert_eq!(auto.record(1.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_empty_range() {
+        let project = Project::new("Empty".to_string());
+        assert!(plan_preview_range(
+            &project,
+            0.0,
+            10.0,
+            PreviewQuality::Standard,
+            PreviewScale::Full
+        )
+        .is_empty());
+    }
+}
@@ -1,7 +1,22 @@
 // FFmpeg proxy video generation for web-compatible playback
 // Converts non-web-compatible formats (MOV, ProRes, etc.) to H.264/MP4
+use crate::ffmpeg::errors::{classify, FfmpegError, FfmpegErrorKind};
+use crate::ffmpeg::export::parse_progress;
+use crate::ffmpeg::locate::require_ffmpeg;
+use crate::ffmpeg::retry::fallback_for;
+use crate::ffmpeg::scheduler::{path_label, scheduler, JobKind};
+use crate::ffmpeg::{FfmpegCommandBuilder, FfmpegInput};
+use crate::models::clip::MediaClip;
+use crate::models::settings::ProxySettings;
+use crate::platform::process::{self, ProcessClass};
 use std::path::Path;
-use std::process::Command;
+
+/// Version of the proxy encoding recipe, stored per clip as `proxy_version`
+///
+/// 1: default x264 GOP (clips without a stored version)
+/// 2: keyframe about every second for scrubbing
+/// 3: every audio stream kept, so a selected stream can be mapped
+pub const PROXY_FORMAT_VERSION: u32 = 3;
 
 /// Check if a video format needs a proxy for web playback
 /// Returns true for codecs that aren't natively supported in browsers
@@ -15,12 +30,49 @@ pub fn needs_proxy(codec: &str) -> bool {
     !web_compatible.iter().any(|c| codec_lower.contains(c))
 }
 
+/// Whether a clip's proxy was built with an older recipe and should be regenerated
+pub fn proxy_outdated(clip: &MediaClip) -> bool {
+    clip.proxy_path.is_some() && clip.proxy_version.unwrap_or(1) < PROXY_FORMAT_VERSION
+}
+
+/// Audio stream position to map when reading `clip` from its proxy or source
+///
+/// Proxies from before version 3 only hold the default audio stream.
+pub fn playback_audio_stream(clip: &MediaClip, is_proxy: bool) -> Option<usize> {
+    if is_proxy && clip.proxy_version.unwrap_or(1) < 3 {
+        return None;
+    }
+    clip.selected_audio_ordinal()
+}
+
+/// Keyframe interval in frames giving about one keyframe per second
+pub fn keyframe_interval(fps: f64) -> u32 {
+    if fps.is_finite() && fps > 0.0 {
+        (fps.round() as u32).clamp(1, 240)
+    } else {
+        30
+    }
+}
+
 /// Generate a web-compatible proxy video (H.264/MP4)
 /// This allows MOV, ProRes, HEVC, and other formats to play in the browser
-pub async fn generate_proxy(source_path: &str, output_path: &str) -> Result<String, String> {
+///
+/// `on_progress` receives the encoded fraction (0.0 - 1.0) of a source
+/// `duration` seconds long as FFmpeg reports it.
+pub async fn generate_proxy(
+    source_path: &str,
+    output_path: &str,
+    source_fps: f64,
+    duration: f64,
+    settings: &ProxySettings,
+    mut on_progress: impl FnMut(f64),
+) -> Result<String, FfmpegError> {
     // Validate input file exists
     if !Path::new(source_path).exists() {
-        return Err(format!("Source file not found: {}", source_path));
+        return Err(FfmpegError::new(
+            FfmpegErrorKind::FileNotFound,
+            format!("Source file not found: {}", source_path),
+        ));
     }
 
     // Create output directory if it doesn't exist
@@ -29,49 +81,90 @@ pub async fn generate_proxy(source_path: &str, output_path: &str) -> Result<Stri
             .map_err(|e| format!("Failed to create output directory: {}", e))?;
     }
 
-    // Generate H.264/AAC proxy at 1080p max resolution
-    // - Fast encoding preset for reasonable generation time
-    // - Scale down to 1080p max (maintains aspect ratio)
-    // - Constant Rate Factor (CRF) 23 for good quality/size balance
-    let output = Command::new("ffmpeg")
-        .args([
-            "-y", // Overwrite output file
-            "-i",
-            source_path, // Input file
-            "-c:v",
-            "libx264", // H.264 video codec
-            "-preset",
-            "fast", // Fast encoding (good speed/quality)
-            "-crf",
-            "23", // Quality level (lower = better)
-            "-vf",
-            "scale='min(1920,iw)':'min(1080,ih)':force_original_aspect_ratio=decrease", // Scale to max 1080p
-            "-c:a",
-            "aac", // AAC audio codec
-            "-b:a",
-            "128k", // Audio bitrate
-            "-movflags",
-            "+faststart", // Enable progressive download
-            "-pix_fmt",
-            "yuv420p", // Ensure compatibility
-            output_path,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    require_ffmpeg().map_err(String::from)?;
+
+    // Generate H.264/AAC proxy capped at the configured resolution
+    // - Preset/CRF from proxy settings (defaults: fast, 23)
+    // - Scale down to the max size (maintains aspect ratio)
+    let _permit = scheduler()
+        .acquire(JobKind::Proxy, path_label(source_path))
+        .await;
+    let command = proxy_command(source_path, output_path, source_fps, settings);
+    let mut report = |line: &str| {
+        if let Some(progress) = parse_progress(line, duration) {
+            on_progress(progress.progress);
+        }
+    };
+    let output =
+        process::output_with_progress(command.build(), ProcessClass::Proxy, &mut report).await?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("ffmpeg proxy generation failed: {}", stderr));
+        let error = classify(&String::from_utf8_lossy(&output.stderr));
+        let Some(fallback) = fallback_for(&error) else {
+            return Err(error);
+        };
+        log::warn!(
+            "Proxy for {} failed ({}); retrying once with fallback {}: {}",
+            source_path,
+            error.message,
+            fallback.name,
+            fallback.description
+        );
+        let output = process::output_with_progress(
+            fallback.apply(&command).build(),
+            ProcessClass::Proxy,
+            &mut report,
+        )
+        .await?;
+        if !output.status.success() {
+            return Err(classify(&String::from_utf8_lossy(&output.stderr)));
+        }
     }
 
     // Verify output file was created
     if !Path::new(output_path).exists() {
-        return Err("Proxy file was not created".to_string());
+        return Err("Proxy file was not created".to_string().into());
     }
 
     Ok(output_path.to_string())
 }
 
+/// Build the ffmpeg command for proxy generation
+fn proxy_command(
+    source_path: &str,
+    output_path: &str,
+    source_fps: f64,
+    settings: &ProxySettings,
+) -> FfmpegCommandBuilder {
+    let mut builder = FfmpegCommandBuilder::new()
+        .overwrite() // Overwrite output file
+        .input(FfmpegInput::new(source_path))
+        // First video stream and every audio stream (multi-track recordings)
+        .option("-map", "0:v:0")
+        .option("-map", "0:a?")
+        .video_codec("libx264") // H.264 video codec
+        .preset(&settings.preset)
+        .crf(settings.crf) // Quality level (lower = better)
+        .video_filter(format!(
+            "scale='min({},iw)':'min({},ih)':force_original_aspect_ratio=decrease",
+            settings.max_width, settings.max_height
+        ));
+    if settings.scrub_optimized {
+        // Fixed ~1s GOP without scene-cut keyframes: fast, predictable seeks
+        let gop = keyframe_interval(source_fps).to_string();
+        builder = builder
+            .option("-g", &gop)
+            .option("-keyint_min", &gop)
+            .option("-sc_threshold", "0");
+    }
+    builder
+        .audio_codec("aac") // AAC audio codec
+        .audio_bitrate_kbps(settings.audio_bitrate)
+        .option("-movflags", "+faststart") // Enable progressive download
+        .pixel_format("yuv420p") // Ensure compatibility (4:4:4 recordings too)
+        .output(output_path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,9 +188,107 @@ mod tests {
 
     #[test]
     fn test_proxy_path_validation() {
-        let result =
-            tokio_test::block_on(generate_proxy("/nonexistent/file.mov", "/tmp/proxy.mp4"));
+        let result = tokio_test::block_on(generate_proxy(
+            "/nonexistent/file.mov",
+            "/tmp/proxy.mp4",
+            30.0,
+            10.0,
+            &ProxySettings::default(),
+            |_| {},
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not found"));
+        let error = result.unwrap_err();
+        assert_eq!(error.kind, FfmpegErrorKind::FileNotFound);
+        assert!(error.message.contains("not found"));
+    }
+
+    #[test]
+    fn test_proxy_args_use_settings() {
+        let settings = ProxySettings {
+            max_width: 1280,
+            max_height: 720,
+            crf: 28,
+            preset: "veryfast".to_string(),
+            ..Default::default()
+        };
+        let args = proxy_command("/in.mov", "/out.mp4", 30.0, &settings)
+            .to_arg_vec()
+            .join(" ");
+
+        assert!(args.contains("-preset veryfast"));
+        assert!(args.contains("-crf 28"));
+        assert!(args.contains("scale='min(1280,iw)':'min(720,ih)'"));
+        assert!(args.contains("-b:a 128k"));
+        assert!(args.contains("-map 0:v:0 -map 0:a?"));
+    }
+
+    #[test]
+    fn test_keyframe_args_scale_with_fps() {
+        let settings = ProxySettings::default();
+        let args = proxy_command("/in.mov", "/out.mp4", 30.0, &settings)
+            .to_arg_vec()
+            .join(" ");
+        assert!(args.contains("-g 30 -keyint_min 30 -sc_threshold 0"));
+
+        let args = proxy_command("/in.mov", "/out.mp4", 59.94, &settings)
+            .to_arg_vec()
+            .join(" ");
+        assert!(args.contains("-g 60 -keyint_min 60"));
+
+        let args = proxy_command("/in.mov", "/out.mp4", 23.976, &settings)
+            .to_arg_vec()
+            .join(" ");
+        assert!(args.contains("-g 24 -keyint_min 24"));
+
+        // Output path stays last
+        assert!(args.ends_with("/out.mp4"));
+
+        let settings = ProxySettings {
+            scrub_optimized: false,
+            ..Default::default()
+        };
+        let args = proxy_command("/in.mov", "/out.mp4", 30.0, &settings)
+            .to_arg_vec()
+            .join(" ");
+        assert!(!args.contains("-g "));
+        assert!(!args.contains("-sc_threshold"));
+    }
+
+    #[test]
+    fn test_proxy_outdated() {
+        let mut clip = MediaClip::new("/in.mov".into(), 5.0, 1920, 1080, 30.0, "prores".into(), 1);
+        assert!(!proxy_outdated(&clip));
+
+        clip.proxy_path = Some("/cache/p.mp4".into());
+        assert!(proxy_outdated(&clip));
+        clip.proxy_version = Some(PROXY_FORMAT_VERSION);
+        assert!(!proxy_outdated(&clip));
+    }
+
+    #[test]
+    fn test_playback_audio_stream() {
+        let mut clip = MediaClip::new("/in.mov".into(), 5.0, 1920, 1080, 30.0, "prores".into(), 1);
+        clip.audio_streams = [1, 2]
+            .map(|index| crate::models::clip::MediaStream {
+                index,
+                stream_type: "audio".into(),
+                codec: Some("aac".into()),
+                language: None,
+                title: None,
+                channels: Some(2),
+                channel_layout: None,
+            })
+            .to_vec();
+        assert_eq!(playback_audio_stream(&clip, false), None);
+
+        // Source stream 2 is the second audio stream
+        clip.audio_stream_index = Some(2);
+        assert_eq!(playback_audio_stream(&clip, false), Some(1));
+
+        // Old proxies only carry the default audio stream
+        clip.proxy_version = Some(2);
+        assert_eq!(playback_audio_stream(&clip, true), None);
+        clip.proxy_version = Some(PROXY_FORMAT_VERSION);
+        assert_eq!(playback_audio_stream(&clip, true), Some(1));
     }
 }
@@ -0,0 +1,172 @@
+// Fallback arguments for recoverable encode failures
+// Some sources fail the default pipelines for known reasons: odd frame sizes
+// break 4:2:0 encoders, damaged MOVs trip the decoder, some pixel formats
+// can't be negotiated. When a classified failure matches an entry below the
+// job is re-run once with that entry's arguments added. Anything else is
+// reported as-is, and a failed retry is never retried again.
+
+use crate::ffmpeg::errors::{FfmpegError, FfmpegErrorKind};
+use crate::ffmpeg::FfmpegCommandBuilder;
+use serde::Serialize;
+
+/// A known failure signature and the arguments that work around it
+#[derive(Debug, Serialize)]
+pub struct Fallback {
+    /// Stable identifier for logs and events
+    pub name: &'static str,
+    /// What the retry changes, for the job log
+    pub description: &'static str,
+    #[serde(skip)]
+    kind: FfmpegErrorKind,
+    /// At least one must appear in the error excerpt (empty = any)
+    #[serde(skip)]
+    requires: &'static [&'static str],
+    /// None may appear in the error excerpt
+    #[serde(skip)]
+    unless: &'static [&'static str],
+    #[serde(skip)]
+    input_options: &'static [(&'static str, &'static str)],
+    #[serde(skip)]
+    video_filters: &'static [&'static str],
+    #[serde(skip)]
+    output_options: &'static [(&'static str, &'static str)],
+}
+
+/// Fallbacks in match order
+pub const FALLBACKS: &[Fallback] = &[
+    Fallback {
+        name: "even_dimensions",
+        description: "padded the frame to an even width and height",
+        kind: FfmpegErrorKind::OddDimensions,
+        requires: &[],
+        unless: &[],
+        input_options: &[],
+        video_filters: &["pad=ceil(iw/2)*2:ceil(ih/2)*2"],
+        output_options: &[],
+    },
+    Fallback {
+        name: "ignore_decode_errors",
+        description: "decoded the input ignoring damaged packets",
+        kind: FfmpegErrorKind::CorruptInput,
+        requires: &["error while decoding stream", "invalid nal unit size"],
+        // A file without an index can't be read at all
+        unless: &["moov atom not found"],
+        input_options: &[("-err_detect", "ignore_err")],
+        video_filters: &[],
+        output_options: &[],
+    },
+    Fallback {
+        name: "yuv420p",
+        description: "converted the video to yuv420p before encoding",
+        kind: FfmpegErrorKind::UnsupportedPixelFormat,
+        requires: &[],
+        unless: &[],
+        input_options: &[],
+        video_filters: &["format=yuv420p"],
+        output_options: &[("-pix_fmt", "yuv420p")],
+    },
+];
+
+impl Fallback {
+    fn matches(&self, error: &FfmpegError) -> bool {
+        let excerpt = error.excerpt.to_lowercase();
+        error.kind == self.kind
+            && (self.requires.is_empty() || self.requires.iter().any(|p| excerpt.contains(p)))
+            && !self.unless.iter().any(|p| excerpt.contains(p))
+    }
+
+    /// The command with this fallback's arguments added
+    pub fn apply(&self, builder: &FfmpegCommandBuilder) -> FfmpegCommandBuilder {
+        let mut builder = builder.clone();
+        for (key, value) in self.input_options {
+            builder = builder.input_option(key, value);
+        }
+        for filter in self.video_filters {
+            builder = builder.video_filter(*filter);
+        }
+        for (key, value) in self.output_options {
+            builder = builder.option(key, value);
+        }
+        builder
+    }
+}
+
+/// The fallback for a classified failure, if it is a known recoverable one
+pub fn fallback_for(error: &FfmpegError) -> Option<&'static Fallback> {
+    FALLBACKS.iter().find(|fallback| fallback.matches(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::errors::classify;
+    use crate::ffmpeg::FfmpegInput;
+
+    fn builder() -> FfmpegCommandBuilder {
+        FfmpegCommandBuilder::new()
+            .overwrite()
+            .input(FfmpegInput::new("/m/in.mov"))
+            .video_codec("libx264")
+            .video_filter("scale=1281:-1")
+            .output("/out.mp4")
+    }
+
+    fn retried_args(stderr: &str) -> Option<String> {
+        let fallback = fallback_for(&classify(stderr))?;
+        Some(fallback.apply(&builder()).to_arg_vec().join(" "))
+    }
+
+    #[test]
+    fn test_fallback_argv() {
+        assert_eq!(
+            retried_args("[libx264 @ 0x6] width not divisible by 2 (1281x720)\nConversion failed!")
+                .unwrap(),
+            "-y -i /m/in.mov -c:v libx264 -vf scale=1281:-1,pad=ceil(iw/2)*2:ceil(ih/2)*2 /out.mp4"
+        );
+        assert_eq!(
+            retried_args(
+                "[h264 @ 0x1] Invalid NAL unit size (1109 > 535).\n[vist#0:0/h264 @ 0x2] Error while decoding stream #0:0: Invalid data found when processing input"
+            )
+            .unwrap(),
+            "-y -err_detect ignore_err -i /m/in.mov -c:v libx264 -vf scale=1281:-1 /out.mp4"
+        );
+        assert_eq!(
+            retried_args(
+                "Impossible to convert between the formats supported by the filter 'Parsed_null_0' and the filter 'auto_scale_0'"
+            )
+            .unwrap(),
+            "-y -i /m/in.mov -c:v libx264 -pix_fmt yuv420p -vf scale=1281:-1,format=yuv420p /out.mp4"
+        );
+    }
+
+    #[test]
+    fn test_other_failures_are_not_retried() {
+        // Same kind as a fallback, but not the recoverable signature
+        assert!(retried_args("/m/in.mov: Invalid data found when processing input").is_none());
+        assert!(retried_args(
+            "[mov @ 0x1] moov atom not found\nError while decoding stream #0:0: Invalid data found when processing input"
+        )
+        .is_none());
+        // Different kinds
+        for stderr in [
+            "Unknown encoder 'libx264'",
+            "/out.mp4: No space left on device",
+            "/m/in.mov: No such file or directory",
+            "Some new failure nobody has seen\nConversion failed!",
+        ] {
+            assert!(retried_args(stderr).is_none(), "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn test_fallback_event_payload() {
+        let json = serde_json::to_value(&FALLBACKS[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "even_dimensions",
+                "description": "padded the frame to an even width and height"
+            })
+        );
+    }
+}
@@ -0,0 +1,371 @@
+// Shared scheduler for heavy FFmpeg work
+// Exports, preview renders, proxies and thumbnails all take a slot here before
+// spawning FFmpeg, so importing a folder during an export can't start a dozen
+// encodes at once. Waiting jobs are started highest priority first, then in
+// the order they were queued.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+lazy_static! {
+    static ref SCHEDULER: Scheduler = Scheduler::new(default_limit());
+}
+
+/// The scheduler shared by every heavy FFmpeg invocation
+pub fn scheduler() -> &'static Scheduler {
+    &SCHEDULER
+}
+
+/// Default concurrent heavy jobs: half the cores, at least one
+///
+/// The standard library only reports logical CPUs; on SMT machines half of
+/// those is roughly one job per physical core pair.
+pub fn default_limit() -> usize {
+    let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+    (cores / 2).max(1)
+}
+
+/// Apply the `max_concurrent_jobs` setting (null = default limit)
+pub fn configure(max_concurrent_jobs: Option<u32>) {
+    scheduler().set_limit(max_concurrent_jobs.map_or_else(default_limit, |jobs| jobs as usize));
+}
+
+/// Job label for work on a file: its name without the directory
+pub fn path_label(path: &str) -> String {
+    std::path::Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+/// Kind of heavy work, in descending priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Export,
+    Preview,
+    Proxy,
+    Thumbnail,
+    /// Audio extraction for captions and waveforms
+    Audio,
+}
+
+impl JobKind {
+    /// Lower runs first; thumbnails and audio analysis share the lowest level
+    fn priority(self) -> u8 {
+        match self {
+            JobKind::Export => 0,
+            JobKind::Preview => 1,
+            JobKind::Proxy => 2,
+            JobKind::Thumbnail | JobKind::Audio => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+}
+
+/// A queued or running job, as listed to the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackgroundJob {
+    pub id: u64,
+    pub kind: JobKind,
+    /// Clip or project the job works on
+    pub label: String,
+    pub state: JobState,
+    /// 0.0 - 1.0, when the job reports it
+    pub progress: Option<f64>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    limit: usize,
+    running: usize,
+    next_id: u64,
+    jobs: BTreeMap<u64, BackgroundJob>,
+    /// Queued jobs waiting for a slot, by id
+    waiters: BTreeMap<u64, oneshot::Sender<()>>,
+}
+
+impl SchedulerState {
+    /// Start waiting jobs while slots are free
+    fn dispatch(&mut self) {
+        while self.running < self.limit {
+            let next = self
+                .waiters
+                .keys()
+                .min_by_key(|id| (self.jobs[id].kind.priority(), **id))
+                .copied();
+            let Some(id) = next else { break };
+            let waiter = self.waiters.remove(&id).unwrap();
+            // The job counts as running from here; if its acquire was
+            // cancelled meanwhile, dropping its permit frees the slot
+            self.running += 1;
+            self.jobs.get_mut(&id).unwrap().state = JobState::Running;
+            let _ = waiter.send(());
+        }
+    }
+
+    fn release(&mut self, id: u64) {
+        if let Some(job) = self.jobs.remove(&id) {
+            if job.state == JobState::Running {
+                self.running -= 1;
+            }
+        }
+        self.waiters.remove(&id);
+        self.dispatch();
+    }
+}
+
+/// Priority semaphore with a job list
+#[derive(Clone)]
+pub struct Scheduler {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl Scheduler {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SchedulerState {
+                limit: limit.max(1),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Change the number of concurrent jobs; running jobs are not interrupted
+    pub fn set_limit(&self, limit: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = limit.max(1);
+        state.dispatch();
+    }
+
+    /// Wait for a slot; the job stays listed until the permit is dropped
+    pub async fn acquire(&self, kind: JobKind, label: impl Into<String>) -> JobPermit {
+        self.enqueue(kind, label).started().await
+    }
+
+    /// Queue a job without waiting for its slot (see `QueuedJob::started`)
+    pub fn enqueue(&self, kind: JobKind, label: impl Into<String>) -> QueuedJob {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.jobs.insert(
+            id,
+            BackgroundJob {
+                id,
+                kind,
+                label: label.into(),
+                state: JobState::Queued,
+                progress: None,
+            },
+        );
+        let (sender, receiver) = oneshot::channel();
+        state.waiters.insert(id, sender);
+        state.dispatch();
+
+        // Created before waiting so a cancelled job leaves the queue
+        QueuedJob {
+            permit: JobPermit {
+                id,
+                scheduler: self.clone(),
+            },
+            receiver,
+        }
+    }
+
+    /// Jobs that will start before the queued job `id`; None once it runs
+    /// (or left the queue)
+    pub fn queue_position(&self, id: u64) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        let job = state
+            .jobs
+            .get(&id)
+            .filter(|j| j.state == JobState::Queued)?;
+        let place = (job.kind.priority(), id);
+        Some(
+            state
+                .waiters
+                .keys()
+                .filter(|other| (state.jobs[*other].kind.priority(), **other) < place)
+                .count(),
+        )
+    }
+
+    /// Queued and running jobs, running first
+    pub fn jobs(&self) -> Vec<BackgroundJob> {
+        let state = self.state.lock().unwrap();
+        let mut jobs: Vec<BackgroundJob> = state.jobs.values().cloned().collect();
+        jobs.sort_by_key(|job| (job.state != JobState::Running, job.kind.priority(), job.id));
+        jobs
+    }
+}
+
+/// A job waiting for a slot; dropping it leaves the queue
+pub struct QueuedJob {
+    permit: JobPermit,
+    receiver: oneshot::Receiver<()>,
+}
+
+impl QueuedJob {
+    /// Id in the job list
+    pub fn id(&self) -> u64 {
+        self.permit.id
+    }
+
+    /// Wait until the job may run
+    pub async fn started(self) -> JobPermit {
+        let _ = self.receiver.await;
+        self.permit
+    }
+}
+
+/// A slot in the scheduler, released on drop
+pub struct JobPermit {
+    id: u64,
+    scheduler: Scheduler,
+}
+
+impl JobPermit {
+    /// Report progress (0.0 - 1.0) for the job list
+    pub fn set_progress(&self, progress: f64) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        if let Some(job) = state.jobs.get_mut(&self.id) {
+            job.progress = Some(progress.clamp(0.0, 1.0));
+        }
+    }
+}
+
+impl Drop for JobPermit {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().release(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_priority_ordering() {
+        let scheduler = Scheduler::new(1);
+        let blocker = scheduler.acquire(JobKind::Thumbnail, "busy").await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut tasks = Vec::new();
+        for (kind, label) in [
+            (JobKind::Thumbnail, "thumb"),
+            (JobKind::Proxy, "proxy"),
+            (JobKind::Export, "export"),
+            (JobKind::Preview, "preview"),
+            (JobKind::Export, "export 2"),
+        ] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(kind, label).await;
+                order.lock().unwrap().push(label);
+            }));
+            // Queue in a known order
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let listed = scheduler.jobs();
+        assert_eq!(listed.len(), 6);
+        assert_eq!(listed[0].label, "busy");
+        assert_eq!(listed[0].state, JobState::Running);
+        assert_eq!(listed[1].label, "export");
+        assert!(listed[1..].iter().all(|job| job.state == JobState::Queued));
+
+        drop(blocker);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            ["export", "export 2", "preview", "proxy", "thumb"]
+        );
+        assert!(scheduler.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let scheduler = Scheduler::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let scheduler = scheduler.clone();
+                let running = running.clone();
+                let peak = peak.clone();
+                tokio::spawn(async move {
+                    let permit = scheduler
+                        .acquire(JobKind::Proxy, format!("clip {}", i))
+                        .await;
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    permit.set_progress(0.5);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        // Raising the limit starts queued jobs right away
+        scheduler.set_limit(1);
+        let first = scheduler.acquire(JobKind::Proxy, "a").await;
+        let second = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(JobKind::Proxy, "b").await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(scheduler.jobs()[1].state, JobState::Queued);
+        scheduler.set_limit(2);
+        let second = tokio::time::timeout(Duration::from_secs(1), second)
+            .await
+            .expect("queued job should start after raising the limit")
+            .unwrap();
+        drop((first, second));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_leaves_queue() {
+        let scheduler = Scheduler::new(1);
+        let blocker = scheduler.acquire(JobKind::Export, "export").await;
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move {
+                let _permit = scheduler.acquire(JobKind::Proxy, "proxy").await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(scheduler.jobs().len(), 2);
+
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(scheduler.jobs().len(), 1);
+
+        // The slot is still usable once the blocker finishes
+        drop(blocker);
+        let _permit = tokio::time::timeout(
+            Duration::from_secs(1),
+            scheduler.acquire(JobKind::Thumbnail, "thumb"),
+        )
+        .await
+        .unwrap();
+    }
+}
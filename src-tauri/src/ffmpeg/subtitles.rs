@@ -0,0 +1,66 @@
+// Embedded subtitle extraction
+// Text subtitle streams (mov_text in MP4, subrip/ass in MKV, WebVTT) are
+// converted to SRT so they can be imported as captions; bitmap subtitles
+// (PGS, DVD) would need OCR and are rejected up front.
+
+use crate::ffmpeg::locate::{ffmpeg_path, require_ffmpeg};
+use crate::platform::process::{self, ProcessClass};
+use std::process::Command;
+
+/// Subtitle codecs FFmpeg can convert to SRT
+const TEXT_SUBTITLE_CODECS: &[&str] =
+    &["mov_text", "subrip", "srt", "ass", "ssa", "webvtt", "text"];
+
+pub fn is_text_subtitle_codec(codec: &str) -> bool {
+    TEXT_SUBTITLE_CODECS.contains(&codec)
+}
+
+/// FFmpeg arguments converting subtitle stream `stream_index` of `source` to an SRT file
+pub fn build_subtitle_extract_args(source: &str, stream_index: u32, output: &str) -> Vec<String> {
+    vec![
+        "-i".to_string(),
+        source.to_string(),
+        "-map".to_string(),
+        format!("0:{}", stream_index),
+        "-f".to_string(),
+        "srt".to_string(),
+        "-y".to_string(),
+        output.to_string(),
+    ]
+}
+
+/// Convert an embedded subtitle stream to an SRT file
+pub async fn extract_subtitle_stream(
+    source: &str,
+    stream_index: u32,
+    output: &str,
+) -> Result<(), String> {
+    require_ffmpeg()?;
+
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(build_subtitle_extract_args(source, stream_index, output));
+    let output = process::output(cmd, ProcessClass::AudioExtraction).await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Subtitle extraction failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtitle_extract_args() {
+        assert_eq!(
+            build_subtitle_extract_args("/m/talk.mkv", 3, "/tmp/out.srt").join(" "),
+            "-i /m/talk.mkv -map 0:3 -f srt -y /tmp/out.srt"
+        );
+        assert!(is_text_subtitle_codec("mov_text"));
+        assert!(!is_text_subtitle_codec("hdmv_pgs_subtitle"));
+    }
+}
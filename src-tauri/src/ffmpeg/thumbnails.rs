@@ -1,120 +1,376 @@
 // FFmpeg thumbnail generation with async task queue
+use crate::ffmpeg::locate::{ffmpeg_path, require_ffmpeg};
+use crate::ffmpeg::scheduler::{path_label, scheduler, JobKind};
+use crate::models::clip::MediaClip;
+use crate::models::settings::{ThumbnailFormat, ThumbnailSettings};
+use crate::platform::process::{self, ProcessClass};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
+use std::pin::Pin;
 use std::process::Command;
-use tokio::sync::mpsc;
-use tokio::task;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, OnceCell, Semaphore};
+
+/// Width of each hover-scrub frame in pixels
+pub const HOVER_FRAME_WIDTH: u32 = 160;
+
+/// Maximum frames per sprite row
+const HOVER_SPRITE_COLUMNS: u32 = 10;
+
+/// Files a clip's thumbnail is written to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailPaths {
+    /// At the configured height
+    pub standard: String,
+    /// At twice the height, for HiDPI displays
+    pub hidpi: String,
+}
+
+impl ThumbnailPaths {
+    /// `<dir>/<clip>_<height>.<ext>` and `<dir>/<clip>_<height>@2x.<ext>`, so
+    /// each size and format has its own file
+    pub fn new(dir: &Path, clip_id: &str, settings: &ThumbnailSettings) -> Self {
+        let ext = settings.format.extension();
+        let path = |suffix: &str| {
+            dir.join(format!("{}_{}{}.{}", clip_id, settings.height, suffix, ext))
+                .to_string_lossy()
+                .into_owned()
+        };
+        Self {
+            standard: path(""),
+            hidpi: path("@2x"),
+        }
+    }
+}
+
+/// Whether `clip` needs new thumbnails at `paths`: it has none, they were
+/// made at another size or format, or a file is gone
+pub fn needs_thumbnail(
+    clip: &MediaClip,
+    paths: &ThumbnailPaths,
+    exists: impl Fn(&str) -> bool,
+) -> bool {
+    clip.thumbnail_path.as_deref() != Some(paths.standard.as_str())
+        || clip.thumbnail_2x_path.as_deref() != Some(paths.hidpi.as_str())
+        || !exists(&paths.standard)
+        || !exists(&paths.hidpi)
+}
 
 /// Request to generate a thumbnail
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct ThumbnailRequest {
     pub clip_id: String,
     pub source_path: String,
-    pub output_path: String,
+    pub paths: ThumbnailPaths,
+    pub settings: ThumbnailSettings,
     pub timestamp: f64,
 }
 
 /// Result of thumbnail generation
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct ThumbnailResult {
     pub clip_id: String,
     pub thumbnail_path: String,
+    pub thumbnail_2x_path: String,
 }
 
+/// Thumbnail generation run by the queue workers
+type Generator = Arc<
+    dyn Fn(ThumbnailRequest) -> Pin<Box<dyn Future<Output = Result<ThumbnailPaths, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
 /// Async queue for thumbnail generation
-#[allow(dead_code)]
+///
+/// Up to `workers` thumbnails are generated at once (the scheduler still
+/// bounds FFmpeg overall). A request for a clip that is already waiting
+/// replaces the waiting one in place, so repeated imports of a file don't
+/// pile up work. The channel carries clip ids; the requests themselves wait
+/// in `pending`.
+#[derive(Debug, Clone)]
 pub struct ThumbnailQueue {
-    tx: mpsc::UnboundedSender<ThumbnailRequest>,
+    tx: mpsc::UnboundedSender<String>,
+    pending: Arc<Mutex<HashMap<String, ThumbnailRequest>>>,
 }
 
-#[allow(dead_code)]
 impl ThumbnailQueue {
-    /// Create a new thumbnail queue and spawn worker task
-    pub fn new() -> (
+    /// Create a new thumbnail queue and spawn its worker tasks
+    pub fn new(
+        workers: usize,
+    ) -> (
         Self,
         mpsc::UnboundedReceiver<Result<ThumbnailResult, String>>,
     ) {
-        let (req_tx, mut req_rx) = mpsc::unbounded_channel::<ThumbnailRequest>();
+        Self::with_generator(
+            workers,
+            Arc::new(|request: ThumbnailRequest| {
+                Box::pin(async move {
+                    generate_thumbnail_internal(
+                        &request.source_path,
+                        &request.paths,
+                        request.timestamp,
+                        &request.settings,
+                    )
+                    .await
+                })
+            }),
+        )
+    }
+
+    fn with_generator(
+        workers: usize,
+        generate: Generator,
+    ) -> (
+        Self,
+        mpsc::UnboundedReceiver<Result<ThumbnailResult, String>>,
+    ) {
+        let (req_tx, req_rx) = mpsc::unbounded_channel::<String>();
         let (result_tx, result_rx) = mpsc::unbounded_channel::<Result<ThumbnailResult, String>>();
+        let req_rx = Arc::new(tokio::sync::Mutex::new(req_rx));
+        let pending: Arc<Mutex<HashMap<String, ThumbnailRequest>>> = Arc::default();
 
-        // Spawn worker task
-        task::spawn(async move {
-            while let Some(request) = req_rx.recv().await {
-                let result = generate_thumbnail_internal(
-                    &request.source_path,
-                    &request.output_path,
-                    request.timestamp,
-                )
-                .await
-                .map(|path| ThumbnailResult {
-                    clip_id: request.clip_id.clone(),
-                    thumbnail_path: path,
-                });
-
-                let _ = result_tx.send(result);
-            }
-        });
+        // Workers run on Tauri's runtime so the queue can be created before it
+        // starts; they exit once every queue handle is dropped
+        for _ in 0..workers.max(1) {
+            let req_rx = req_rx.clone();
+            let result_tx = result_tx.clone();
+            let pending = pending.clone();
+            let generate = generate.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let Some(clip_id) = req_rx.lock().await.recv().await else {
+                        break;
+                    };
+                    let Some(request) = pending.lock().unwrap().remove(&clip_id) else {
+                        continue;
+                    };
+                    let result = generate(request.clone())
+                        .await
+                        .map(|paths| ThumbnailResult {
+                            clip_id: request.clip_id.clone(),
+                            thumbnail_path: paths.standard,
+                            thumbnail_2x_path: paths.hidpi,
+                        })
+                        .map_err(|e| format!("Thumbnail for {} failed: {}", request.clip_id, e));
 
-        (Self { tx: req_tx }, result_rx)
+                    let _ = result_tx.send(result);
+                }
+            });
+        }
+
+        (
+            Self {
+                tx: req_tx,
+                pending,
+            },
+            result_rx,
+        )
     }
 
     /// Submit a thumbnail generation request
+    ///
+    /// Replaces a request for the same clip that hasn't started yet.
     pub fn submit(&self, request: ThumbnailRequest) -> Result<(), String> {
+        let clip_id = request.clip_id.clone();
+        let replaced = self
+            .pending
+            .lock()
+            .unwrap()
+            .insert(clip_id.clone(), request)
+            .is_some();
+        if replaced {
+            return Ok(());
+        }
         self.tx
-            .send(request)
+            .send(clip_id)
             .map_err(|e| format!("Failed to submit thumbnail request: {}", e))
     }
 }
 
-/// Generate thumbnail image from video at specified timestamp
+/// Grid layout and frame timing of a hover-scrub sprite
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HoverSpriteLayout {
+    pub frame_count: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Seconds between frames
+    pub interval: f64,
+    /// Source time of each frame, in sprite order (left to right, top to bottom)
+    pub timestamps: Vec<f64>,
+}
+
+impl HoverSpriteLayout {
+    /// Layout for `count` frames evenly spread over a `width`x`height` clip
+    pub fn new(duration: f64, width: i32, height: i32, count: u32) -> Result<Self, String> {
+        if width <= 0 || height <= 0 {
+            return Err("Clip has no video to preview".to_string());
+        }
+        if duration <= 0.0 {
+            return Err("Clip has no duration".to_string());
+        }
+        let count = count.clamp(1, 100);
+        let columns = count.min(HOVER_SPRITE_COLUMNS);
+        let rows = count.div_ceil(columns);
+        let frame_height =
+            ((HOVER_FRAME_WIDTH as f64 * height as f64 / width as f64).round() as u32 & !1).max(2);
+        let interval = duration / count as f64;
+        Ok(Self {
+            frame_count: count,
+            columns,
+            rows,
+            frame_width: HOVER_FRAME_WIDTH,
+            frame_height,
+            interval,
+            timestamps: (0..count).map(|i| i as f64 * interval).collect(),
+        })
+    }
+}
+
+/// Build the FFmpeg command tiling the layout's frames into one JPEG sprite
+pub fn build_hover_sprite_command(
+    source_path: &str,
+    layout: &HoverSpriteLayout,
+    output_path: &Path,
+) -> Command {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y", "-i", source_path])
+        .arg("-vf")
+        .arg(format!(
+            "fps=1/{:.6},scale={}:{},tile={}x{}",
+            layout.interval, layout.frame_width, layout.frame_height, layout.columns, layout.rows
+        ))
+        .args(["-frames:v", "1", "-q:v", "5", "-f", "image2"])
+        .arg(output_path);
+    cmd
+}
+
+/// Shared result of one sprite generation
+type SpriteSlot = Arc<OnceCell<Result<String, String>>>;
+
+/// Lazily generates hover sprites one at a time in the background
+///
+/// Concurrent requests for the same clip share a single generation, and the
+/// single permit keeps sprites from competing with imports and exports.
+#[derive(Clone)]
+pub struct HoverSpriteQueue {
+    in_flight: Arc<Mutex<HashMap<String, SpriteSlot>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl Default for HoverSpriteQueue {
+    fn default() -> Self {
+        Self {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(1)),
+        }
+    }
+}
+
+impl HoverSpriteQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `generate` for `key` unless a generation is already running, in
+    /// which case wait for and return its result
+    pub async fn run<F, Fut>(&self, key: &str, generate: F) -> Result<String, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let slot = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        let result = slot
+            .get_or_init(|| async {
+                let _permit = self.permits.acquire().await;
+                generate().await
+            })
+            .await
+            .clone();
+
+        // Finished generations are served from the cache from now on
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(key).is_some_and(|s| Arc::ptr_eq(s, &slot)) {
+            in_flight.remove(key);
+        }
+        result
+    }
+}
+
+/// Generate a clip's 1x and 2x thumbnails from the frame at `timestamp`
 pub async fn generate_thumbnail(
     source_path: &str,
-    output_path: &str,
+    paths: &ThumbnailPaths,
+    timestamp: f64,
+    settings: &ThumbnailSettings,
+) -> Result<ThumbnailPaths, String> {
+    generate_thumbnail_internal(source_path, paths, timestamp, settings).await
+}
+
+/// Build the FFmpeg command writing both thumbnail sizes from one decoded frame
+pub fn build_thumbnail_command(
+    source_path: &str,
+    paths: &ThumbnailPaths,
     timestamp: f64,
-) -> Result<String, String> {
-    generate_thumbnail_internal(source_path, output_path, timestamp).await
+    settings: &ThumbnailSettings,
+) -> Command {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args(["-hide_banner", "-y", "-ss"])
+        .arg(timestamp.to_string())
+        .args(["-i", source_path])
+        .arg("-filter_complex")
+        .arg(format!(
+            "[0:v]split=2[sd][hd];[sd]scale=-2:{}[sdo];[hd]scale=-2:{}[hdo]",
+            settings.height,
+            settings.height * 2
+        ));
+    for (label, output) in [("[sdo]", &paths.standard), ("[hdo]", &paths.hidpi)] {
+        cmd.args(["-map", label, "-frames:v", "1"]);
+        match settings.format {
+            // 2 is near-lossless JPEG
+            ThumbnailFormat::Jpeg => cmd.args(["-q:v", "2"]),
+            ThumbnailFormat::Webp => cmd.args(["-c:v", "libwebp", "-quality", "80"]),
+        };
+        cmd.args(["-f", "image2"]).arg(output);
+    }
+    cmd
 }
 
 async fn generate_thumbnail_internal(
     source_path: &str,
-    output_path: &str,
+    paths: &ThumbnailPaths,
     timestamp: f64,
-) -> Result<String, String> {
+    settings: &ThumbnailSettings,
+) -> Result<ThumbnailPaths, String> {
     // Validate input file exists
     if !Path::new(source_path).exists() {
         return Err(format!("Source file not found: {}", source_path));
     }
 
     // Create output directory if it doesn't exist
-    if let Some(parent) = Path::new(output_path).parent() {
+    if let Some(parent) = Path::new(&paths.standard).parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create output directory: {}", e))?;
     }
 
-    // Run ffmpeg to extract frame as JPEG
-    // -ss: seek to timestamp
-    // -i: input file
-    // -vframes 1: extract one frame
-    // -q:v 2: JPEG quality (2 is high quality)
-    // -f image2: force image format
-    let output = Command::new("ffmpeg")
-        .args([
-            "-y", // Overwrite output file
-            "-ss",
-            &timestamp.to_string(),
-            "-i",
-            source_path,
-            "-vframes",
-            "1",
-            "-q:v",
-            "2",
-            "-f",
-            "image2",
-            output_path,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    require_ffmpeg()?;
+
+    let cmd = build_thumbnail_command(source_path, paths, timestamp, settings);
+    let _permit = scheduler()
+        .acquire(JobKind::Thumbnail, path_label(source_path))
+        .await;
+    let output = process::output(cmd, ProcessClass::Thumbnail).await?;
 
     if !output.status.success() {
         return Err(format!(
@@ -123,21 +379,147 @@ async fn generate_thumbnail_internal(
         ));
     }
 
-    // Verify output file was created
-    if !Path::new(output_path).exists() {
+    // Verify output files were created
+    if !Path::new(&paths.standard).exists() || !Path::new(&paths.hidpi).exists() {
         return Err("Thumbnail file was not created".to_string());
     }
 
-    Ok(output_path.to_string())
+    Ok(paths.clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_hover_sprite_layout() {
+        let layout = HoverSpriteLayout::new(30.0, 1920, 1080, 15).unwrap();
+        assert_eq!((layout.columns, layout.rows), (10, 2));
+        assert_eq!((layout.frame_width, layout.frame_height), (160, 90));
+        assert_eq!(layout.interval, 2.0);
+        assert_eq!(layout.timestamps.len(), 15);
+        assert_eq!(layout.timestamps[14], 28.0);
+
+        assert!(HoverSpriteLayout::new(30.0, 0, 0, 10).is_err());
+
+        let cmd = build_hover_sprite_command("/m/a.mp4", &layout, Path::new("s.jpg"));
+        assert!(format!("{:?}", cmd).contains("fps=1/2.000000,scale=160:90,tile=10x2"));
+    }
+
+    #[test]
+    fn test_thumbnail_command() {
+        let settings = ThumbnailSettings {
+            height: 180,
+            format: ThumbnailFormat::Webp,
+        };
+        let paths = ThumbnailPaths::new(Path::new("/cache/thumbnails"), "clip-1", &settings);
+        assert_eq!(paths.standard, "/cache/thumbnails/clip-1_180.webp");
+        assert_eq!(paths.hidpi, "/cache/thumbnails/clip-1_180@2x.webp");
+
+        let cmd = format!(
+            "{:?}",
+            build_thumbnail_command("/m/a.mov", &paths, 1.0, &settings)
+        );
+        assert!(cmd.contains(
+            "\"-ss\" \"1\" \"-i\" \"/m/a.mov\" \"-filter_complex\" \
+             \"[0:v]split=2[sd][hd];[sd]scale=-2:180[sdo];[hd]scale=-2:360[hdo]\""
+        ));
+        assert!(cmd.contains(
+            "\"-map\" \"[sdo]\" \"-frames:v\" \"1\" \"-c:v\" \"libwebp\" \"-quality\" \"80\" \
+             \"-f\" \"image2\" \"/cache/thumbnails/clip-1_180.webp\""
+        ));
+        assert!(cmd.ends_with("\"/cache/thumbnails/clip-1_180@2x.webp\""));
+
+        let jpeg = ThumbnailSettings::default();
+        let paths = ThumbnailPaths::new(Path::new("/t"), "clip-1", &jpeg);
+        let cmd = format!(
+            "{:?}",
+            build_thumbnail_command("/m/a.mov", &paths, 0.0, &jpeg)
+        );
+        assert!(cmd.contains("\"-q:v\" \"2\" \"-f\" \"image2\" \"/t/clip-1_180.jpg\""));
+        assert!(!cmd.contains("libwebp"));
+    }
+
+    #[test]
+    fn test_thumbnails_regenerate_for_new_settings() {
+        let settings = ThumbnailSettings::default();
+        let paths = ThumbnailPaths::new(Path::new("/t"), "clip-1", &settings);
+        let mut clip = MediaClip::new("/m/a.mov".into(), 10.0, 1920, 1080, 30.0, "h264".into(), 1);
+        let all_exist = |_: &str| true;
+
+        // Thumbnails from before sized variants: a single full-size JPEG
+        clip.thumbnail_path = Some("/t/clip-1.jpg".into());
+        assert!(needs_thumbnail(&clip, &paths, all_exist));
+
+        clip.thumbnail_path = Some(paths.standard.clone());
+        clip.thumbnail_2x_path = Some(paths.hidpi.clone());
+        assert!(!needs_thumbnail(&clip, &paths, all_exist));
+        // A missing 2x file is regenerated
+        assert!(needs_thumbnail(&clip, &paths, |p: &str| p == paths.standard));
+
+        // Another size or format has its own files
+        let larger = ThumbnailSettings {
+            height: 360,
+            ..settings
+        };
+        assert!(needs_thumbnail(
+            &clip,
+            &ThumbnailPaths::new(Path::new("/t"), "clip-1", &larger),
+            all_exist
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hover_sprites_are_generated_lazily_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let queue = HoverSpriteQueue::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let generate = || {
+            let runs = runs.clone();
+            move || async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                Ok("/cache/hover/a.jpg".to_string())
+            }
+        };
+
+        // Nothing is generated until requested
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+        // Concurrent hovers over the same clip share one generation
+        let (a, b, c) = tokio::join!(
+            queue.run("clip-a", generate()),
+            queue.run("clip-a", generate()),
+            queue.run("clip-a", generate()),
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap(), "/cache/hover/a.jpg");
+        assert_eq!(b, c);
+
+        // A different clip runs its own generation
+        queue.run("clip-b", generate()).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(queue.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_sprite_generation_can_retry() {
+        let queue = HoverSpriteQueue::new();
+        let result = queue
+            .run("clip-a", || async { Err("ffmpeg failed".to_string()) })
+            .await;
+        assert!(result.is_err());
+
+        let result = queue
+            .run("clip-a", || async { Ok("/cache/hover/a.jpg".to_string()) })
+            .await;
+        assert!(result.is_ok());
+    }
+
     #[tokio::test]
     async fn test_thumbnail_queue() {
-        let (queue, mut results) = ThumbnailQueue::new();
+        let (queue, mut results) = ThumbnailQueue::new(2);
 
         // This test would need a real video file to work
         // For now, just verify the queue accepts requests
@@ -145,7 +527,12 @@ mod tests {
             .submit(ThumbnailRequest {
                 clip_id: "test-1".to_string(),
                 source_path: "/nonexistent.mp4".to_string(),
-                output_path: "/tmp/thumb.jpg".to_string(),
+                paths: ThumbnailPaths::new(
+                    Path::new("/tmp"),
+                    "test-1",
+                    &ThumbnailSettings::default()
+                ),
+                settings: ThumbnailSettings::default(),
                 timestamp: 0.0,
             })
             .is_ok());
@@ -158,4 +545,61 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    #[tokio::test]
+    async fn test_duplicate_requests_are_coalesced() {
+        let (started_tx, mut started) = mpsc::unbounded_channel::<String>();
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let generate: Generator = {
+            let gate = gate.clone();
+            Arc::new(move |request: ThumbnailRequest| {
+                let started_tx = started_tx.clone();
+                let gate = gate.clone();
+                Box::pin(async move {
+                    let _ = started_tx.send(request.paths.standard.clone());
+                    if request.clip_id == "busy" {
+                        gate.notified().await;
+                    }
+                    Ok(request.paths)
+                })
+            })
+        };
+        let (queue, mut results) = ThumbnailQueue::with_generator(1, generate);
+        let request = |clip_id: &str, output_path: &str| ThumbnailRequest {
+            clip_id: clip_id.to_string(),
+            source_path: "/m/a.mov".to_string(),
+            paths: ThumbnailPaths {
+                standard: output_path.to_string(),
+                hidpi: output_path.replace(".jpg", "@2x.jpg"),
+            },
+            settings: ThumbnailSettings::default(),
+            timestamp: 1.0,
+        };
+
+        // Occupy the only worker, then queue the same clip three times
+        queue.submit(request("busy", "/t/busy.jpg")).unwrap();
+        assert_eq!(started.recv().await.unwrap(), "/t/busy.jpg");
+        queue.submit(request("clip-a", "/t/a1.jpg")).unwrap();
+        queue.submit(request("clip-b", "/t/b.jpg")).unwrap();
+        queue.submit(request("clip-a", "/t/a2.jpg")).unwrap();
+        queue.submit(request("clip-a", "/t/a3.jpg")).unwrap();
+        assert_eq!(queue.pending.lock().unwrap().len(), 2);
+        gate.notify_one();
+        drop(queue);
+
+        let mut generated = Vec::new();
+        while let Some(result) = results.recv().await {
+            let result = result.unwrap();
+            generated.push((result.clip_id, result.thumbnail_path));
+        }
+        // Only the latest request for clip-a ran, in its original queue position
+        assert_eq!(
+            generated,
+            [
+                ("busy".to_string(), "/t/busy.jpg".to_string()),
+                ("clip-a".to_string(), "/t/a3.jpg".to_string()),
+                ("clip-b".to_string(), "/t/b.jpg".to_string()),
+            ]
+        );
+    }
 }
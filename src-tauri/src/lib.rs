@@ -0,0 +1,14 @@
+// Clipforge library target
+// Every module of the app. main.rs only wires them into Tauri, and the
+// integration tests in tests/ drive the pipeline functions directly.
+
+pub mod ai;
+pub mod commands;
+pub mod error;
+pub mod ffmpeg;
+pub mod logging;
+pub mod models;
+pub mod platform;
+pub mod storage;
+
+pub use commands::media::AppState;
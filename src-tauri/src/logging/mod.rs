@@ -0,0 +1,141 @@
+// Application log
+// Records from the `log` macros go to ~/.clipforge/logs/clipforge.log (rotated
+// by size) and to stderr, so packaged builds keep the diagnostics support needs.
+
+mod rotation;
+
+pub use rotation::{rotated_path, RotatingFile};
+
+use log::{Level, LevelFilter, Metadata, Record};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+pub const LOG_FILE_NAME: &str = "clipforge.log";
+/// Size cap of each log file
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Live file plus rotated files kept
+const MAX_LOG_FILES: usize = 5;
+
+/// Debug records (full FFmpeg stderr) are dropped unless enabled in settings
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+static LOGGER: FileLogger = FileLogger {
+    file: Mutex::new(None),
+};
+
+struct FileLogger {
+    file: Mutex<Option<RotatingFile>>,
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies only contribute warnings and errors
+        if !metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return metadata.level() <= Level::Warn;
+        }
+        metadata.level() <= Level::Info || DEBUG.load(Ordering::Relaxed)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_record(record);
+        eprintln!("{}", line);
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn format_record(record: &Record) -> String {
+    let target = record
+        .target()
+        .strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::"))
+        .unwrap_or(record.target());
+    format!(
+        "{} {:<5} [{}] {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        record.level(),
+        target,
+        record.args()
+    )
+}
+
+/// Directory holding the log files
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".clipforge").join("logs"))
+}
+
+/// Install the logger; without a writable log directory records only go to stderr
+pub fn init(debug: bool) {
+    set_debug(debug);
+    if let Some(dir) = log_dir() {
+        match RotatingFile::open(&dir.join(LOG_FILE_NAME), MAX_LOG_BYTES, MAX_LOG_FILES) {
+            Ok(file) => *LOGGER.file.lock().unwrap() = Some(file),
+            Err(e) => eprintln!("[Log] Failed to open log file: {}", e),
+        }
+    }
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Debug);
+    }
+}
+
+/// Enable or disable debug records (settings toggle)
+pub fn set_debug(enabled: bool) {
+    DEBUG.store(enabled, Ordering::Relaxed);
+}
+
+/// Last `count` lines across the live and rotated log files, oldest first
+pub fn recent_lines(dir: &Path, count: usize) -> Result<Vec<String>, String> {
+    let live = dir.join(LOG_FILE_NAME);
+    let mut lines = Vec::new();
+    for index in 0..MAX_LOG_FILES {
+        if lines.len() >= count {
+            break;
+        }
+        let path = if index == 0 {
+            live.clone()
+        } else {
+            rotated_path(&live, index)
+        };
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        // Newer files are read first, so prepend
+        let mut older: Vec<String> = content.lines().map(str::to_string).collect();
+        older.append(&mut lines);
+        lines = older;
+    }
+    let start = lines.len().saturating_sub(count);
+    Ok(lines.split_off(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_lines_span_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(&dir.path().join(LOG_FILE_NAME), 20, 3).unwrap();
+        for i in 0..5 {
+            file.write_line(&format!("line {}", i)).unwrap();
+        }
+
+        assert_eq!(
+            recent_lines(dir.path(), 3).unwrap(),
+            ["line 2", "line 3", "line 4"]
+        );
+        // Asking for more than exists returns everything kept
+        assert_eq!(recent_lines(dir.path(), 100).unwrap().len(), 5);
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(recent_lines(empty.path(), 10).unwrap().is_empty());
+    }
+}
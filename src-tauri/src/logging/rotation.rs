@@ -0,0 +1,134 @@
+// Size-based log file rotation
+// clipforge.log is the live file; when it would grow past the size cap it is
+// renamed to clipforge.log.1, older files shift up by one, and the oldest
+// beyond the file limit is deleted.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Append-only log file that rotates by size
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Live file plus rotated files kept
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files: max_files.max(1),
+            file,
+            size,
+        })
+    }
+
+    /// Append one line, rotating first if it would exceed the size cap
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files > 1 {
+            let _ = fs::remove_file(rotated_path(&self.path, self.max_files - 1));
+            for index in (1..self.max_files - 1).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Path of the `index`-th rotated file (`clipforge.log.1` is the newest)
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clipforge.log");
+        // 10-byte lines (9 chars + newline), 30-byte cap: three lines per file
+        let mut log = RotatingFile::open(&path, 30, 3).unwrap();
+        for i in 0..10 {
+            log.write_line(&format!("line {:04}", i)).unwrap();
+        }
+
+        let mut names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["clipforge.log", "clipforge.log.1", "clipforge.log.2"]
+        );
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 0009\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "line 0006\nline 0007\nline 0008\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "line 0003\nline 0004\nline 0005\n"
+        );
+        for file in [path.clone(), rotated_path(&path, 1)] {
+            assert!(fs::metadata(file).unwrap().len() <= 30);
+        }
+    }
+
+    #[test]
+    fn test_reopen_continues_size_and_single_file_truncates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        RotatingFile::open(&path, 25, 1)
+            .unwrap()
+            .write_line("line 0001")
+            .unwrap();
+
+        // The existing size counts toward the cap after a restart
+        let mut log = RotatingFile::open(&path, 25, 1).unwrap();
+        log.write_line("line 0002").unwrap();
+        log.write_line("line 0003").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 0003\n");
+        assert!(!rotated_path(&path, 1).exists());
+
+        // A line longer than the cap is still written
+        let long = "x".repeat(40);
+        log.write_line(&long).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{}\n", long));
+    }
+}
@@ -6,75 +6,320 @@
     windows_subsystem = "windows"
 )]
 
-mod ai;
-mod commands;
-mod ffmpeg;
-mod models;
-mod platform;
-mod storage;
-
-use commands::media::AppState;
-use commands::{captions, export, media, playback, project, recording, timeline};
+use clipforge::commands::locks::LockState;
+use clipforge::commands::media::AppState;
+use clipforge::commands::{
+    assets, cache, captions, comments, export, jobs, library, logs, markers, media, playback,
+    preview, project, recording, settings, startup, system_check, timeline,
+};
+use clipforge::{ffmpeg, logging, platform, storage, storage::CacheDb};
 use std::sync::{Arc, Mutex};
-use storage::CacheDb;
+use tauri::Manager;
 
 fn main() {
-    // Initialize cache database
-    let cache_path = dirs::home_dir()
-        .expect("Failed to get home directory")
-        .join(".clipforge")
-        .join("cache")
-        .join("clipforge.db");
+    // Open the cache (which also stores the settings); failures leave the app
+    // running on an in-memory cache until the user recovers
+    let location_file = storage::cache_location::location_file();
+    let location = location_file
+        .as_deref()
+        .map(storage::cache_location::read_location)
+        .unwrap_or_default();
+    // A corrupted database is moved aside and recreated instead of crashing
+    let opened = startup::open_cache(location.resolve().as_deref(), CacheDb::open_or_rebuild);
+    let (cache_db, startup_status) = match opened {
+        Ok(opened) => {
+            let status = startup::StartupStatus::ready(&opened);
+            storage::cache_location::install(&opened.cache_dir);
+            (opened.cache_db, status)
+        }
+        Err(error) => {
+            eprintln!(
+                "[Cache] {}; starting without a persistent cache",
+                error.message
+            );
+            let cache_db = CacheDb::in_memory().expect("Failed to create in-memory cache database");
+            (cache_db, startup::StartupStatus::degraded(error))
+        }
+    };
+    let cache_rebuilt = Mutex::new(
+        startup_status
+            .corrupt_cache_path
+            .clone()
+            .map(|path| cache::CacheRebuiltEvent { corrupt_path: path }),
+    );
+    let startup_state = startup::StartupState::new(startup_status, location_file);
 
-    std::fs::create_dir_all(cache_path.parent().unwrap())
-        .expect("Failed to create cache directory");
+    // Load app settings (migrates the legacy whisper config.json on first run)
+    let mut app_settings = storage::settings::load_app_settings(
+        &cache_db,
+        storage::settings::legacy_config_path().as_deref(),
+    );
+    app_settings.cache_dir = location.cache_dir;
+    let work_directory = app_settings
+        .work_directory
+        .as_deref()
+        .map(std::path::Path::new);
+    storage::work_dir::install(work_directory);
+    ffmpeg::scheduler::configure(app_settings.max_concurrent_jobs);
+    logging::init(app_settings.debug_logging);
+    log::info!("ClipForge {} starting", env!("CARGO_PKG_VERSION"));
+    // Nothing runs yet, so anything left in the work directory is an orphan;
+    // the system temp is swept too in case the setting changed mid-job
+    let started = std::time::SystemTime::now();
+    let mut work_roots = vec![storage::work_dir::work_root(work_directory)];
+    if work_directory.is_some() {
+        work_roots.push(storage::work_dir::work_root(None));
+    }
+    for root in work_roots {
+        let removed = storage::work_dir::sweep(&root, started);
+        if removed > 0 {
+            log::info!(
+                "Removed {} leftover work files from {}",
+                removed,
+                root.display()
+            );
+        }
+    }
+    match cache_db.evict_to_size_cap(app_settings.cache_size_cap_bytes()) {
+        Ok(evicted) if evicted.files > 0 => log::info!(
+            "Evicted {} cache files ({} bytes) over the size cap",
+            evicted.files,
+            evicted.bytes
+        ),
+        Ok(_) => {}
+        Err(e) => log::warn!("Cache eviction failed: {}", e),
+    }
+    if let Some(error) = startup_state.status().error {
+        log::error!("Cache unavailable ({:?}): {}", error.stage, error.message);
+    }
 
-    let cache_db = CacheDb::new(&cache_path).expect("Failed to initialize cache database");
+    let (thumbnail_queue, thumbnail_results) =
+        ffmpeg::thumbnails::ThumbnailQueue::new(app_settings.thumbnail_workers as usize);
 
     // Initialize app state with empty project
     let app_state = AppState {
         cache_db: Arc::new(Mutex::new(cache_db)),
-        media_library: Arc::new(Mutex::new(Vec::new())),
         project: Arc::new(Mutex::new(None)),
+        settings: Arc::new(Mutex::new(app_settings)),
+        proxy_jobs: jobs::TrackedTasks::new(),
+        caption_jobs: jobs::TrackedTasks::new(),
+        ffmpeg: Arc::new(Mutex::new(Default::default())),
+        thumbnails: thumbnail_queue,
     };
 
     // Initialize export state
     let export_state = export::ExportState::new();
 
+    // Initialize preview render state
+    let preview_state = preview::PreviewState::new();
+    let frame_cache = preview::FrameCache::new();
+    let asset_scope = assets::AssetScope::new();
+    let hover_sprite_queue = ffmpeg::thumbnails::HoverSpriteQueue::new();
+
     tauri::Builder::default()
         .manage(app_state)
         .manage(export_state)
+        .manage(preview_state)
+        .manage(frame_cache)
+        .manage(asset_scope)
+        .manage(hover_sprite_queue)
+        .manage(startup_state)
+        .setup(|app| {
+            // Thumbnails queued by imports and recordings land in the library
+            tauri::async_runtime::spawn(media::apply_thumbnail_results(
+                app.handle(),
+                thumbnail_results,
+            ));
+            tauri::async_runtime::spawn(jobs::emit_job_updates(app.handle()));
+            tauri::async_runtime::spawn(project::run_autosave(app.handle()));
+
+            // Media the webview plays lives outside the static asset scope:
+            // recordings, the cache (proxies, previews) and imported folders
+            let handle = app.handle();
+
+            // Missing FFmpeg must not keep the app from opening; features
+            // that need it report "FFmpeg not found" instead
+            let app_state = app.state::<AppState>();
+            let ffmpeg_setting = app_state.settings.lock_recovering().ffmpeg_path.clone();
+            let status = ffmpeg::locate::locate(
+                ffmpeg_setting.as_deref().map(std::path::Path::new),
+                app.path_resolver().resource_dir().as_deref(),
+            );
+            match (&status.ffmpeg_path, &status.error) {
+                (Some(path), None) => println!("[FFmpeg] Using {}", path),
+                (_, Some(error)) => eprintln!("[FFmpeg] {}", error),
+                (None, None) => {}
+            }
+            ffmpeg::locate::install(&status);
+            if status.available {
+                tauri::async_runtime::spawn(ffmpeg::capabilities::ensure_probed());
+            }
+            *app_state.ffmpeg.lock_recovering() = status;
+
+            if let Ok(cache_dir) = media::get_cache_dir() {
+                assets::allow_asset_dir(&handle, &cache_dir);
+            }
+            if let Ok(dir) = storage::work_dir::area_dir(storage::work_dir::WorkArea::Preview) {
+                assets::allow_asset_dir(&handle, &dir);
+            }
+            let recordings_dir = app
+                .state::<AppState>()
+                .settings
+                .lock_recovering()
+                .recordings_dir
+                .clone();
+            if let Ok(dir) = recording::get_recordings_dir(recordings_dir.as_deref()) {
+                assets::allow_asset_dir(&handle, &dir);
+            }
+            Ok(())
+        })
+        .on_window_event(|event| {
+            // Unsaved changes and running jobs keep the window open while the
+            // UI prompts; it quits with `exit_app` once they're dealt with
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event.event() {
+                if project::exit_blocked(&event.window().app_handle(), false) {
+                    api.prevent_close();
+                }
+            }
+        })
+        .on_page_load(move |window, _payload| {
+            // Tell the UI (once) that thumbnails/metadata will be regenerated lazily
+            if let Some(event) = cache_rebuilt.lock_recovering().take() {
+                let _ = window.emit("cache_rebuilt", event);
+            }
+            // Degraded until the user recovers; repeated on reload
+            if let Some(error) = window.state::<startup::StartupState>().status().error {
+                let _ = window.emit("initialization_error", error);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Media commands
             media::import_media_files,
+            media::import_media_from_url,
             media::get_media_metadata,
+            media::get_media_streams,
+            media::select_audio_stream,
+            library::get_media_clip,
+            library::remove_media_clip,
+            media::get_outdated_proxy_clips,
             media::generate_thumbnail_for_clip,
+            media::get_clip_thumbnail,
+            media::get_hover_thumbnails,
             // Playback commands
             playback::load_clip_for_playback,
+            playback::get_playback_sequence,
+            preview::render_preview_range,
+            preview::get_timeline_frame,
+            preview::render_audio_preview,
+            preview::render_clip_segment,
             // Project commands
             project::create_new_project,
             project::save_project,
             project::load_project,
+            project::close_project,
+            project::exit_app,
+            project::save_project_as,
+            project::duplicate_project,
+            project::get_recent_projects,
+            project::import_project_section,
+            project::export_project_archive,
+            project::import_project_archive,
+            project::is_project_dirty,
+            project::update_project_settings,
+            project::get_project_export_settings,
+            project::set_project_export_settings,
+            project::list_project_backups,
+            project::restore_project_backup,
+            project::restore_auto_save,
             // Timeline commands
+            timeline::get_timeline_layout,
             timeline::add_clip_to_timeline,
             timeline::update_timeline_clip,
             timeline::split_timeline_clip,
+            timeline::slide_clip,
+            timeline::extend_with_freeze,
+            timeline::create_compound_clip,
+            timeline::enter_compound,
+            timeline::exit_compound,
+            timeline::set_clip_effects,
+            timeline::set_clip_redactions,
+            // Notes and review comments
+            comments::set_clip_notes,
+            comments::add_clip_comment,
+            comments::resolve_clip_comment,
+            comments::list_comments,
+            comments::get_all_open_comments,
+            markers::add_marker,
+            markers::update_marker,
+            markers::delete_marker,
+            markers::export_chapters_text,
             timeline::delete_timeline_clip,
             timeline::create_track,
+            timeline::update_track,
+            timeline::enable_track_ducking,
+            timeline::disable_track_ducking,
+            timeline::add_transition,
+            timeline::remove_transition,
+            timeline::list_tracks,
+            timeline::get_track,
+            timeline::get_timeline_clip,
+            timeline::rename_track,
+            timeline::set_track_order,
             // Export commands
             export::export_timeline,
+            export::export_clips_individually,
+            export::preview_export_plan,
+            export::preflight_export,
+            export::export_frame,
+            export::list_export_jobs,
+            export::get_hardware_encoders,
             export::cancel_export,
+            export::get_export_history,
+            export::clear_export_history,
+            export::retry_export,
             // Recording commands
             recording::request_recording_permissions,
             recording::list_recording_sources,
             recording::start_recording,
             recording::stop_recording,
             recording::get_recording_session,
+            recording::resync_recording_state,
+            recording::get_recording_history,
+            recording::get_interrupted_recordings,
             // Caption commands
             captions::generate_captions,
             captions::update_caption,
             captions::delete_caption,
+            captions::import_subtitle_stream,
+            // Cache commands
+            cache::run_cache_maintenance,
+            // Background job commands
+            jobs::get_background_jobs,
+            jobs::get_active_jobs,
+            jobs::cancel_job,
+            // Log commands
+            logs::get_recent_logs,
+            logs::open_log_folder,
+            // Settings commands
+            settings::get_app_settings,
+            settings::update_app_settings,
+            settings::get_ffmpeg_status,
+            settings::set_ffmpeg_path,
+            settings::download_ffmpeg,
+            settings::update_managed_ffmpeg,
+            // Startup recovery commands
+            startup::get_startup_status,
+            startup::retry_startup,
+            startup::choose_cache_directory,
+            startup::rebuild_cache,
+            system_check::run_system_check,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app_handle, event| {
+            // Recorders and exports must not keep running headless after quit
+            if let tauri::RunEvent::Exit = event {
+                platform::process::drain_registry();
+            }
+        });
 }
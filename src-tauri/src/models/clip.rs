@@ -8,7 +8,13 @@ pub struct MediaClip {
     pub name: String,
     pub source_path: String,
     pub proxy_path: Option<String>,
+    /// Proxy recipe version the proxy was generated with (None = before versioning)
+    #[serde(default)]
+    pub proxy_version: Option<u32>,
     pub thumbnail_path: Option<String>,
+    /// Thumbnail at twice `thumbnail_path`'s size, for HiDPI displays
+    #[serde(default)]
+    pub thumbnail_2x_path: Option<String>,
     pub duration: f64,
     pub resolution: String,
     pub width: i32,
@@ -21,6 +27,59 @@ pub struct MediaClip {
     pub has_audio: bool,
     pub imported_at: DateTime<Utc>,
     pub captions: Vec<Caption>,
+    /// Audio streams found at import, in file order
+    #[serde(default)]
+    pub audio_streams: Vec<MediaStream>,
+    /// Source stream index used for audio (None = FFmpeg's default pick)
+    #[serde(default)]
+    pub audio_stream_index: Option<u32>,
+    /// Trim suggested by post-recording analysis (None = keep it all)
+    #[serde(default)]
+    pub suggested_trim: Option<SuggestedTrim>,
+    /// Loudness measured by post-recording analysis
+    #[serde(default)]
+    pub loudness: Option<LoudnessMeasurement>,
+    /// Link the file was downloaded from (`import_media_from_url`)
+    #[serde(default)]
+    pub origin_url: Option<String>,
+}
+
+/// Source range worth keeping, with silent lead-in/tail cut off
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SuggestedTrim {
+    pub in_point: f64,
+    pub out_point: f64,
+}
+
+/// EBU R128 loudness of a clip's audio, as measured by FFmpeg's loudnorm
+///
+/// Enough for a second, linear loudnorm pass at export.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    /// Integrated loudness (LUFS)
+    pub integrated: f64,
+    /// True peak (dBTP)
+    pub true_peak: f64,
+    /// Loudness range (LU)
+    pub range: f64,
+    /// Gating threshold (LUFS)
+    pub threshold: f64,
+    /// Offset loudnorm's first pass suggests for the target (LU)
+    pub target_offset: f64,
+}
+
+/// One stream of a media file, as reported by ffprobe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaStream {
+    /// Stream index in the source file (`-map 0:<index>`)
+    pub index: u32,
+    /// "video", "audio", "subtitle", "data", ...
+    pub stream_type: String,
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -45,7 +104,9 @@ impl MediaClip {
             name,
             source_path,
             proxy_path: None,
+            proxy_version: None,
             thumbnail_path: None,
+            thumbnail_2x_path: None,
             duration,
             resolution: format!("{}x{}", width, height),
             width,
@@ -58,9 +119,33 @@ impl MediaClip {
             has_audio: false,
             imported_at: Utc::now(),
             captions: vec![],
+            audio_streams: vec![],
+            audio_stream_index: None,
+            suggested_trim: None,
+            loudness: None,
+            origin_url: None,
         }
     }
 
+    /// Position of the selected stream among the audio streams (`0:a:<n>`)
+    ///
+    /// Proxies keep every audio stream in order, so the position is valid for
+    /// both the source and its proxy, unlike the source stream index.
+    pub fn selected_audio_ordinal(&self) -> Option<usize> {
+        let index = self.audio_stream_index?;
+        self.audio_streams.iter().position(|s| s.index == index)
+    }
+
+    /// Channel count of the audio stream playback uses (the selected one,
+    /// else the first), if probed
+    pub fn audio_channels(&self) -> Option<u32> {
+        let stream = match self.audio_stream_index {
+            Some(index) => self.audio_streams.iter().find(|s| s.index == index),
+            None => self.audio_streams.first(),
+        };
+        stream.and_then(|s| s.channels)
+    }
+
     pub fn aspect_ratio(&self) -> f64 {
         if self.height == 0 {
             return 0.0;
@@ -0,0 +1,474 @@
+// Compound clips
+// A compound clip is a group of timeline clips stored as a sub-timeline and
+// placed on the parent timeline as a single clip. Editing one swaps its
+// tracks into `Project::tracks`, so the existing timeline commands work on
+// its contents; renders expand compound clips back into the clips they hold.
+
+use super::project::Project;
+use super::timeline::{normalize_track_order, ClipSourceKind, TimelineClip, Track};
+use serde::{Deserialize, Serialize};
+
+/// Timeline clips grouped into a sub-timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompoundClip {
+    pub id: String,
+    pub name: String,
+    /// The parent tracks the clips came from (same ids, names and types),
+    /// with clip times relative to the start of the compound
+    pub tracks: Vec<Track>,
+}
+
+/// A compound clip open for editing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompoundEdit {
+    pub compound_id: String,
+    /// The parent timeline's tracks, restored by `exit_compound`
+    pub parent_tracks: Vec<Track>,
+}
+
+impl CompoundClip {
+    /// End of the last clip in the compound
+    pub fn duration(&self) -> f64 {
+        self.tracks.iter().map(|t| t.duration()).fold(0.0, f64::max)
+    }
+}
+
+/// `inner`, timed within a compound, as it plays through `outer`, the
+/// compound's clip on the parent timeline
+///
+/// The clip is cut to the part of the compound `outer` shows; None if its
+/// trim hides all of it.
+fn place_in_parent(outer: &TimelineClip, inner: &TimelineClip) -> Option<TimelineClip> {
+    let mut clip = inner.clone();
+    if outer.in_point > clip.start_time {
+        clip = clip.split_at(outer.in_point)?.1;
+    }
+    if outer.out_point < clip.end_time() {
+        clip = clip.split_at(outer.out_point)?.0;
+    }
+    // Ids stay unique when a compound is placed more than once
+    clip.id = format!("{}/{}", outer.id, inner.id);
+    clip.start_time += outer.start_time - outer.in_point;
+    Some(clip)
+}
+
+impl Project {
+    pub fn compound_clip(&self, compound_id: &str) -> Option<&CompoundClip> {
+        self.compound_clips.iter().find(|c| c.id == compound_id)
+    }
+
+    /// Whether a timeline clip anywhere in the project (the timeline shown,
+    /// the parent timeline of an edited compound, or inside a compound)
+    /// plays `media_clip_id`
+    pub fn uses_media(&self, media_clip_id: &str) -> bool {
+        let parent = self.editing_compound.iter().map(|e| &e.parent_tracks);
+        let compounds = self.compound_clips.iter().map(|c| &c.tracks);
+        std::iter::once(&self.tracks)
+            .chain(parent)
+            .chain(compounds)
+            .flatten()
+            .flat_map(|t| &t.clips)
+            .any(|c| c.source_kind == ClipSourceKind::Media && c.media_clip_id == media_clip_id)
+    }
+
+    /// Move `clip_ids` into a new compound clip and put a single clip
+    /// referencing it where they were
+    ///
+    /// The compound clip goes on the track of the earliest selected clip,
+    /// starting where that clip did and ending where the last one ended.
+    /// Returns the compound and its clip on the timeline.
+    pub fn create_compound_clip(
+        &mut self,
+        clip_ids: &[String],
+        name: String,
+    ) -> Result<(CompoundClip, TimelineClip), String> {
+        if self.editing_compound.is_some() {
+            return Err("Compound clips can't be nested: exit the compound clip first".to_string());
+        }
+        if clip_ids.is_empty() {
+            return Err("Select at least one clip".to_string());
+        }
+
+        let mut selected = Vec::new();
+        for id in clip_ids {
+            let (track, clip) = self
+                .tracks
+                .iter()
+                .flat_map(|t| t.clips.iter().map(move |c| (t, c)))
+                .find(|(_, c)| &c.id == id)
+                .ok_or_else(|| format!("Timeline clip not found: {}", id))?;
+            if clip.source_kind == ClipSourceKind::Compound {
+                return Err("Compound clips can't be nested".to_string());
+            }
+            selected.push((track.order, clip.start_time, clip.end_time(), track));
+        }
+        let start = selected.iter().map(|s| s.1).fold(f64::INFINITY, f64::min);
+        let end = selected.iter().map(|s| s.2).fold(0.0, f64::max);
+        // Earliest clip; the upper track on a tie
+        let host = selected
+            .iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|s| s.3)
+            .expect("selection is not empty");
+        if host
+            .clips
+            .iter()
+            .any(|c| !clip_ids.contains(&c.id) && c.start_time < end && start < c.end_time())
+        {
+            return Err(format!(
+                "The compound clip would overlap other clips on {}",
+                host.name
+            ));
+        }
+        let host_id = host.id.clone();
+
+        let mut tracks = Vec::new();
+        for track in &mut self.tracks {
+            let (moved, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut track.clips)
+                .into_iter()
+                .partition(|c| clip_ids.contains(&c.id));
+            track.clips = kept;
+            if moved.is_empty() {
+                continue;
+            }
+            track.prune_transitions();
+            let mut sub_track = track.clone();
+            sub_track.locked = false;
+            sub_track.clips = moved
+                .into_iter()
+                .map(|mut clip| {
+                    clip.start_time -= start;
+                    clip
+                })
+                .collect();
+            sub_track.prune_transitions();
+            tracks.push(sub_track);
+        }
+        normalize_track_order(&mut tracks);
+        let compound = CompoundClip {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            tracks,
+        };
+
+        let mut clip = TimelineClip::new(compound.id.clone(), host_id, start, 0.0, end - start);
+        clip.source_kind = ClipSourceKind::Compound;
+        self.compound_clips.push(compound.clone());
+        if let Some(track) = self.tracks.iter_mut().find(|t| t.id == clip.track_id) {
+            track.clips.push(clip.clone());
+        }
+        Ok((compound, clip))
+    }
+
+    /// Show a compound clip's tracks on the timeline so they can be edited
+    pub fn enter_compound(&mut self, compound_id: &str) -> Result<(), String> {
+        if let Some(edit) = &self.editing_compound {
+            return Err(format!(
+                "Compound clip {} is already being edited",
+                edit.compound_id
+            ));
+        }
+        let tracks = self
+            .compound_clip(compound_id)
+            .ok_or_else(|| format!("Compound clip not found: {}", compound_id))?
+            .tracks
+            .clone();
+        self.editing_compound = Some(CompoundEdit {
+            compound_id: compound_id.to_string(),
+            parent_tracks: std::mem::replace(&mut self.tracks, tracks),
+        });
+        Ok(())
+    }
+
+    /// Store the edited tracks back into the compound and show the parent
+    /// timeline again; returns the compound's id
+    ///
+    /// Clips of a compound that got shorter are trimmed to its new end, and
+    /// clips of one left empty are removed.
+    pub fn exit_compound(&mut self) -> Result<String, String> {
+        let edit = self
+            .editing_compound
+            .take()
+            .ok_or("No compound clip is being edited")?;
+        let contents = std::mem::replace(&mut self.tracks, edit.parent_tracks);
+        let duration = match self
+            .compound_clips
+            .iter_mut()
+            .find(|c| c.id == edit.compound_id)
+        {
+            Some(compound) => {
+                compound.tracks = contents;
+                compound.duration()
+            }
+            None => 0.0,
+        };
+
+        for track in &mut self.tracks {
+            track.clips.retain(|c| {
+                !(c.source_kind == ClipSourceKind::Compound
+                    && c.media_clip_id == edit.compound_id
+                    && duration <= 0.0)
+            });
+            for clip in &mut track.clips {
+                if clip.source_kind != ClipSourceKind::Compound
+                    || clip.media_clip_id != edit.compound_id
+                {
+                    continue;
+                }
+                clip.out_point = clip.out_point.min(duration);
+                if clip.in_point >= clip.out_point {
+                    clip.in_point = 0.0;
+                    clip.out_point = duration;
+                }
+            }
+            track.prune_transitions();
+        }
+        Ok(edit.compound_id)
+    }
+
+    /// Copy of the project with every compound clip on the timeline shown
+    /// replaced by the clips it holds
+    ///
+    /// Contents land on the parent tracks they came from (tracks since
+    /// removed are added back). The compound clip's own flags and effects
+    /// aren't applied to its contents.
+    pub fn flattened(&self) -> Result<Project, String> {
+        let mut project = self.clone();
+        project.tracks = self.flatten_tracks(&self.tracks, &mut Vec::new())?;
+        Ok(project)
+    }
+
+    /// The whole project as an export renders it: the compound clip being
+    /// edited is closed and the top-level timeline flattened
+    pub fn rendered(&self) -> Result<Project, String> {
+        if self.editing_compound.is_none() {
+            return self.flattened();
+        }
+        let mut project = self.clone();
+        project.exit_compound()?;
+        project.flattened()
+    }
+
+    /// `tracks` with compound clips expanded; `open` holds the compounds
+    /// being expanded, so one containing itself is an error, not a hang
+    fn flatten_tracks(
+        &self,
+        tracks: &[Track],
+        open: &mut Vec<String>,
+    ) -> Result<Vec<Track>, String> {
+        let mut flat: Vec<Track> = tracks
+            .iter()
+            .map(|t| Track {
+                clips: Vec::new(),
+                ..t.clone()
+            })
+            .collect();
+        for (index, track) in tracks.iter().enumerate() {
+            for clip in &track.clips {
+                if clip.source_kind == ClipSourceKind::Media {
+                    flat[index].clips.push(clip.clone());
+                    continue;
+                }
+                let id = &clip.media_clip_id;
+                if open.contains(id) {
+                    return Err(format!("Compound clip {} contains itself", id));
+                }
+                let compound = self
+                    .compound_clip(id)
+                    .ok_or_else(|| format!("Compound clip not found: {}", id))?;
+                open.push(id.clone());
+                let contents = self.flatten_tracks(&compound.tracks, open)?;
+                open.pop();
+
+                for inner_track in contents {
+                    let target = match flat.iter().position(|t| t.id == inner_track.id) {
+                        Some(target) => target,
+                        None => {
+                            flat.push(Track {
+                                clips: Vec::new(),
+                                ..inner_track.clone()
+                            });
+                            flat.len() - 1
+                        }
+                    };
+                    for inner in &inner_track.clips {
+                        if let Some(mut placed) = place_in_parent(clip, inner) {
+                            placed.track_id = flat[target].id.clone();
+                            flat[target].clips.push(placed);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(flat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::TrackType;
+
+    /// Main track: a 0-5, b 5-9 (from 2s into its media); overlay: c 7-10
+    fn project() -> Project {
+        let mut project = Project::new("Compound".to_string());
+        let mut overlay = Track::new("Overlay".to_string(), TrackType::Overlay);
+        overlay.order = 1;
+        project.tracks.push(overlay);
+        let (main, overlay) = (project.tracks[0].id.clone(), project.tracks[1].id.clone());
+        for (id, track, start, in_point, out_point) in [
+            ("a", &main, 0.0, 0.0, 5.0),
+            ("b", &main, 5.0, 2.0, 6.0),
+            ("c", &overlay, 7.0, 0.0, 3.0),
+        ] {
+            let mut clip = TimelineClip::new(
+                format!("media-{}", id),
+                track.clone(),
+                start,
+                in_point,
+                out_point,
+            );
+            clip.id = id.to_string();
+            let track = project.tracks.iter_mut().find(|t| &t.id == track).unwrap();
+            track.clips.push(clip);
+        }
+        project
+    }
+
+    fn ids(clips: &[TimelineClip]) -> Vec<&str> {
+        clips.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_create_compound_clip_replaces_selection() {
+        let mut project = project();
+        let (compound, clip) = project
+            .create_compound_clip(&["c".to_string(), "b".to_string()], "Outro".to_string())
+            .unwrap();
+
+        // Spans b's start to c's end, on b's track
+        assert_eq!(clip.source_kind, ClipSourceKind::Compound);
+        assert_eq!(clip.media_clip_id, compound.id);
+        assert_eq!(clip.track_id, project.tracks[0].id);
+        assert_eq!(
+            (clip.start_time, clip.in_point, clip.out_point),
+            (5.0, 0.0, 5.0)
+        );
+        assert_eq!(ids(&project.tracks[0].clips), ["a", clip.id.as_str()]);
+        assert!(project.tracks[1].clips.is_empty());
+
+        // Contents keep their tracks, shifted to the compound's start
+        assert_eq!(compound.tracks.len(), 2);
+        assert_eq!(compound.tracks[0].id, project.tracks[0].id);
+        let b = &compound.tracks[0].clips[0];
+        assert_eq!((b.start_time, b.in_point, b.out_point), (0.0, 2.0, 6.0));
+        let c = &compound.tracks[1].clips[0];
+        assert_eq!((c.start_time, c.in_point, c.out_point), (2.0, 0.0, 3.0));
+        assert_eq!(compound.duration(), 5.0);
+        assert_eq!(project.duration(), 10.0);
+        assert!(project.uses_media("media-c"));
+        assert!(!project.uses_media(&compound.id));
+        assert_eq!(
+            serde_json::to_value(&clip).unwrap()["source_kind"],
+            "compound"
+        );
+    }
+
+    #[test]
+    fn test_create_compound_clip_rejects_bad_selections() {
+        let mut project = project();
+        // a..c would cover b, which stays on the main track
+        assert!(project
+            .create_compound_clip(&["a".to_string(), "c".to_string()], "x".to_string())
+            .unwrap_err()
+            .contains("overlap"));
+        assert!(project.create_compound_clip(&[], "x".to_string()).is_err());
+        assert!(project
+            .create_compound_clip(&["missing".to_string()], "x".to_string())
+            .is_err());
+
+        // One level of nesting
+        let (_, clip) = project
+            .create_compound_clip(&["b".to_string(), "c".to_string()], "x".to_string())
+            .unwrap();
+        assert!(project
+            .create_compound_clip(&[clip.id], "y".to_string())
+            .unwrap_err()
+            .contains("nested"));
+        // Nothing changed by the failed attempts
+        assert_eq!(project.compound_clips.len(), 1);
+        assert_eq!(project.tracks[0].clips.len(), 2);
+    }
+
+    #[test]
+    fn test_flattened_renders_trimmed_compound() {
+        let mut project = project();
+        let (_, clip) = project
+            .create_compound_clip(&["b".to_string(), "c".to_string()], "Outro".to_string())
+            .unwrap();
+        // Show compound time 1-4, starting at 20s
+        let placed = &mut project.tracks[0].clips[1];
+        placed.start_time = 20.0;
+        placed.in_point = 1.0;
+        placed.out_point = 4.0;
+
+        let flat = project.flattened().unwrap();
+        assert_eq!(
+            ids(&flat.tracks[0].clips),
+            ["a", format!("{}/b", clip.id).as_str()]
+        );
+        let b = &flat.tracks[0].clips[1];
+        assert_eq!(b.track_id, flat.tracks[0].id);
+        assert_eq!((b.start_time, b.in_point, b.out_point), (20.0, 3.0, 6.0));
+        // c (compound 2-5) is cut at the compound's out point
+        let c = &flat.tracks[1].clips[0];
+        assert_eq!(c.track_id, flat.tracks[1].id);
+        assert_eq!((c.start_time, c.in_point, c.out_point), (21.0, 0.0, 2.0));
+        assert_eq!(flat.duration(), 23.0);
+
+        // A trim that shows nothing of a clip leaves it out
+        project.tracks[0].clips[1].out_point = 1.5;
+        let flat = project.flattened().unwrap();
+        assert_eq!(flat.tracks[0].clips.len(), 2);
+        assert!(flat.tracks[1].clips.is_empty());
+    }
+
+    #[test]
+    fn test_enter_and_exit_compound() {
+        let mut project = project();
+        let (compound, _) = project
+            .create_compound_clip(&["b".to_string(), "c".to_string()], "Outro".to_string())
+            .unwrap();
+        project.enter_compound(&compound.id).unwrap();
+        assert_eq!(ids(&project.tracks[0].clips), ["b"]);
+        assert!(project.enter_compound(&compound.id).is_err());
+
+        // The export still renders the whole project while editing
+        let rendered = project.rendered().unwrap();
+        assert_eq!(rendered.tracks[0].clips.len(), 2);
+        assert_eq!(rendered.duration(), 10.0);
+
+        // Shorten the contents to 3s: the parent clip is trimmed to match
+        project.tracks[1].clips.clear();
+        project.tracks[0].clips[0].out_point = 5.0;
+        assert_eq!(project.exit_compound().unwrap(), compound.id);
+        assert!(project.editing_compound.is_none());
+        let placed = &project.tracks[0].clips[1];
+        assert_eq!((placed.in_point, placed.out_point), (0.0, 3.0));
+        assert_eq!(project.compound_clip(&compound.id).unwrap().duration(), 3.0);
+        assert!(project.exit_compound().is_err());
+    }
+
+    #[test]
+    fn test_flattened_rejects_cycles() {
+        let mut project = project();
+        let (compound, _) = project
+            .create_compound_clip(&["b".to_string(), "c".to_string()], "Outro".to_string())
+            .unwrap();
+        // A hand-edited file referencing the compound from inside itself
+        let mut inner = TimelineClip::new(compound.id.clone(), String::new(), 0.0, 0.0, 1.0);
+        inner.source_kind = ClipSourceKind::Compound;
+        project.compound_clips[0].tracks[0].clips.push(inner);
+        assert!(project.flattened().unwrap_err().contains("contains itself"));
+    }
+}
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Export settings for rendering timeline to video file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,7 +11,7 @@ pub struct ExportSettings {
     pub codec: VideoCodec,
     /// Encoding quality
     pub quality: ExportQuality,
-    /// Override frame rate (null = use source fps)
+    /// Override frame rate (null = use project fps)
     pub fps: Option<u32>,
     /// Audio codec
     pub audio_codec: AudioCodec,
@@ -17,6 +19,222 @@ pub struct ExportSettings {
     pub audio_bitrate: u32,
     /// Enable hardware encoding
     pub hardware_acceleration: bool,
+    /// What happens to empty stretches of the main track
+    #[serde(default)]
+    pub gap_handling: GapHandling,
+    /// Export only this part of the timeline (null = all of it)
+    #[serde(default)]
+    pub range: Option<ExportRange>,
+    /// How media of another shape fills the output frame
+    #[serde(default)]
+    pub scaling_mode: ScalingMode,
+    /// Shape of the output frame (null = the resolution preset's, or the
+    /// project canvas's for `Source`); the preset sets its short side
+    #[serde(default)]
+    pub aspect_ratio: Option<AspectRatio>,
+    /// Keep every clip on the concat demuxer, even clips trimmed between
+    /// keyframes, which then start a little early (reported as warnings).
+    /// Off, such trims switch the export to frame-accurate per-clip reads.
+    #[serde(default)]
+    pub fast_path: bool,
+    /// Widest a GIF export gets; narrower resolutions are kept
+    #[serde(default = "default_gif_max_width")]
+    pub gif_max_width: u32,
+    /// Logo composited into a corner of every frame
+    #[serde(default)]
+    pub watermark: Option<WatermarkSettings>,
+    /// Quality tier of ProRes and DNxHR exports; ignored by other codecs
+    #[serde(default)]
+    pub intermediate_profile: IntermediateProfile,
+    /// Bring the mixed audio to `loudness_target` (EBU R128)
+    #[serde(default)]
+    pub normalize_audio: bool,
+    /// Integrated loudness normalized audio gets (LUFS)
+    #[serde(default = "default_loudness_target")]
+    pub loudness_target: f64,
+    /// Write the project's markers as chapters, where the container has them
+    #[serde(default)]
+    pub chapters: bool,
+    /// Container tags (`title`, `artist`, `comment`, ...) written with
+    /// `-metadata`; an empty value removes the tag
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Render the video in parts at once, one FFmpeg process per part, and
+    /// join them (see `ffmpeg::parallel`); GIFs always render in one piece
+    #[serde(default)]
+    pub parallel: bool,
+    /// Also write the timeline's captions next to the output, as
+    /// `<output>.srt` or `<output>.vtt` (null = no caption file)
+    #[serde(default)]
+    pub caption_sidecar: Option<CaptionFormat>,
+}
+
+/// Longest metadata tag name, in characters
+pub const MAX_METADATA_KEY_LENGTH: usize = 64;
+/// Longest metadata tag value, in characters
+pub const MAX_METADATA_VALUE_LENGTH: usize = 1024;
+
+/// Loudness targets an export can normalize to (LUFS)
+pub const LOUDNESS_TARGET_RANGE: std::ops::RangeInclusive<f64> = -70.0..=-5.0;
+
+fn default_loudness_target() -> f64 {
+    -16.0
+}
+
+/// GIF frame rate when the settings don't override it
+pub const GIF_DEFAULT_FPS: u32 = 15;
+
+fn default_gif_max_width() -> u32 {
+    640
+}
+
+/// Image laid over a corner of the exported video
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatermarkSettings {
+    pub image_path: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// Distance from the frame edges in output pixels
+    #[serde(default = "default_watermark_margin")]
+    pub margin: u32,
+    /// 0 (invisible) to 1 (as the image is)
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32,
+    /// Watermark width as a percentage of the output width
+    #[serde(default = "default_watermark_scale")]
+    pub scale_percent: f32,
+}
+
+/// Image formats a watermark can be read from
+pub const WATERMARK_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp"];
+
+fn default_watermark_margin() -> u32 {
+    24
+}
+
+fn default_watermark_opacity() -> f32 {
+    0.8
+}
+
+fn default_watermark_scale() -> f32 {
+    15.0
+}
+
+impl WatermarkSettings {
+    /// The image must exist and be a format in `WATERMARK_IMAGE_EXTENSIONS`
+    pub fn validate(&self) -> Result<(), String> {
+        let path = std::path::Path::new(&self.image_path);
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        if !WATERMARK_IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(format!(
+                "Watermark must be a {} image",
+                WATERMARK_IMAGE_EXTENSIONS.join(", ")
+            ));
+        }
+        if !path.is_file() {
+            return Err(format!("Watermark image not found: {}", self.image_path));
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err("Watermark opacity must be between 0 and 1".to_string());
+        }
+        if !(1.0..=100.0).contains(&self.scale_percent) {
+            return Err("Watermark size must be between 1% and 100% of the width".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Corner a watermark sits in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Fitting video into the output dimensions; every mode outputs exactly the
+/// target size
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingMode {
+    /// Scale to fit and pad the rest with the project background color
+    #[default]
+    FitPad,
+    /// Scale to cover the frame and crop the overflow, centered
+    FillCrop,
+    /// Scale to the frame, ignoring the aspect ratio
+    Stretch,
+    /// Scale to fit over a blurred copy of the video filling the frame
+    FitBlur,
+}
+
+/// Output frame shape an export can be reshaped to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AspectRatio {
+    /// Landscape, as most screens
+    #[serde(rename = "16:9")]
+    Widescreen,
+    /// Portrait, for phone video (Shorts, Reels, TikTok)
+    #[serde(rename = "9:16")]
+    Vertical,
+    #[serde(rename = "1:1")]
+    Square,
+}
+
+/// Subtitle file format of a caption sidecar
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionFormat {
+    /// SubRip, read by nearly every player and upload form
+    Srt,
+    /// WebVTT, for HTML5 `<track>` elements
+    Vtt,
+}
+
+impl CaptionFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CaptionFormat::Srt => "srt",
+            CaptionFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// Gap handling on the main track
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GapHandling {
+    /// Clips play back to back
+    #[default]
+    Collapse,
+    /// Gaps are rendered as black and silence (not supported by the concat export yet)
+    Black,
+}
+
+/// Timeline range in seconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ExportRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl ExportRange {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.start.is_finite() || !self.end.is_finite() || self.start < 0.0 {
+            return Err(format!("Invalid export range {}-{}", self.start, self.end));
+        }
+        if self.end <= self.start {
+            return Err("Export range must end after it starts".to_string());
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -25,15 +243,15 @@ pub struct ExportSettings {
 pub enum ExportResolution {
     #[serde(rename = "source")]
     Source,
-    #[serde(rename = "2160p")]
+    #[serde(rename = "2160p", alias = "uhd4k", alias = "4k")]
     UHD4K, // 3840x2160
-    #[serde(rename = "1440p")]
+    #[serde(rename = "1440p", alias = "qhd")]
     QHD, // 2560x1440
-    #[serde(rename = "1080p")]
+    #[serde(rename = "1080p", alias = "fullhd")]
     FullHD, // 1920x1080
-    #[serde(rename = "720p")]
+    #[serde(rename = "720p", alias = "hd")]
     HD, // 1280x720
-    #[serde(rename = "480p")]
+    #[serde(rename = "480p", alias = "sd")]
     SD, // 854x480
 }
 
@@ -41,12 +259,82 @@ pub enum ExportResolution {
 #[serde(rename_all = "lowercase")]
 #[allow(clippy::upper_case_acronyms)]
 pub enum VideoCodec {
-    #[serde(rename = "h264")]
+    #[serde(rename = "h264", alias = "H264")]
     H264,
-    #[serde(rename = "hevc")]
+    #[serde(rename = "hevc", alias = "HEVC", alias = "h265")]
     HEVC,
-    #[serde(rename = "vp9")]
+    #[serde(rename = "vp9", alias = "VP9")]
     VP9,
+    /// Looping animated GIF, without audio
+    #[serde(rename = "gif", alias = "GIF")]
+    Gif,
+    /// Apple ProRes in a MOV, for finishing in another editor
+    #[serde(rename = "prores", alias = "ProRes")]
+    ProRes,
+    /// Avid DNxHR in an MXF, for finishing in another editor
+    #[serde(rename = "dnxhr", alias = "DNxHR")]
+    DNxHR,
+}
+
+/// Quality tier of an intermediate codec, lowest bitrate first
+///
+/// Named after the ProRes profiles; DNxHR maps them to LB, SQ, HQ and HQX.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntermediateProfile {
+    Proxy,
+    Lt,
+    /// ProRes 422
+    #[serde(alias = "422")]
+    Standard,
+    #[default]
+    Hq,
+}
+
+/// Export job lifecycle status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    /// Waiting in the export queue (see `ExportState`)
+    #[serde(alias = "preparing")]
+    Queued,
+    Rendering,
+    Complete,
+    Cancelled,
+    Failed,
+}
+
+/// Persisted record of an export job (one row per job, updated on each transition)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportHistoryEntry {
+    /// Export job identifier
+    pub job_id: String,
+    /// Project the export was made from
+    pub project_id: Option<String>,
+    /// Output file path
+    pub output_path: String,
+    /// Settings used for the export
+    pub settings: ExportSettings,
+    /// Current or final job status
+    pub status: ExportStatus,
+    /// When the job started
+    pub started_at: DateTime<Utc>,
+    /// When the job finished, failed, or was cancelled
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Wall-clock render time in seconds
+    pub duration: Option<f64>,
+    /// Duration of the exported timeline in seconds
+    pub timeline_duration: f64,
+    /// Output file size in bytes
+    pub output_size: Option<i64>,
+    /// Error description if status = Failed
+    pub error: Option<String>,
+    /// Last lines FFmpeg wrote to stderr before failing
+    #[serde(default)]
+    pub error_log: Option<String>,
+    /// Job this one retries (see `retry_export`)
+    #[serde(default)]
+    pub retry_of: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -83,6 +371,20 @@ impl ExportResolution {
     }
 }
 
+impl AspectRatio {
+    /// Even-sized frame of this shape whose short side is the short side of
+    /// `width`x`height`, e.g. 1080x1920 for a vertical 1920x1080 export
+    pub fn dimensions(self, width: u32, height: u32) -> (u32, u32) {
+        let short = width.min(height).max(2) & !1;
+        let long = ((short as f64 * 16.0 / 9.0 / 2.0).round() as u32) * 2;
+        match self {
+            AspectRatio::Widescreen => (long, short),
+            AspectRatio::Vertical => (short, long),
+            AspectRatio::Square => (short, short),
+        }
+    }
+}
+
 impl VideoCodec {
     /// Get FFmpeg codec name
     pub fn ffmpeg_codec(&self) -> &'static str {
@@ -90,20 +392,177 @@ impl VideoCodec {
             VideoCodec::H264 => "libx264",
             VideoCodec::HEVC => "libx265",
             VideoCodec::VP9 => "libvpx-vp9",
+            VideoCodec::Gif => "gif",
+            VideoCodec::ProRes => "prores_ks",
+            VideoCodec::DNxHR => "dnxhd",
         }
     }
 
     /// Get output file extension
-    #[allow(dead_code)]
     pub fn extension(&self) -> &'static str {
         match self {
             VideoCodec::H264 => "mp4",
             VideoCodec::HEVC => "mp4",
             VideoCodec::VP9 => "webm",
+            VideoCodec::Gif => "gif",
+            VideoCodec::ProRes => "mov",
+            VideoCodec::DNxHR => "mxf",
+        }
+    }
+
+    /// Whether the output container holds chapters (GIF and MXF don't)
+    pub fn supports_chapters(&self) -> bool {
+        !matches!(self, VideoCodec::Gif | VideoCodec::DNxHR)
+    }
+
+    /// Output only readable under `extension()`; other names are rewritten
+    pub fn fixed_extension(&self) -> bool {
+        matches!(
+            self,
+            VideoCodec::Gif | VideoCodec::ProRes | VideoCodec::DNxHR
+        )
+    }
+
+    /// `-profile:v` and pixel format of an intermediate codec (None for
+    /// delivery codecs)
+    pub fn intermediate_format(
+        &self,
+        profile: IntermediateProfile,
+    ) -> Option<(&'static str, &'static str)> {
+        use IntermediateProfile::*;
+        match (self, profile) {
+            (VideoCodec::ProRes, Proxy) => Some(("proxy", "yuv422p10le")),
+            (VideoCodec::ProRes, Lt) => Some(("lt", "yuv422p10le")),
+            (VideoCodec::ProRes, Standard) => Some(("standard", "yuv422p10le")),
+            (VideoCodec::ProRes, Hq) => Some(("hq", "yuv422p10le")),
+            // Only HQX is 10-bit
+            (VideoCodec::DNxHR, Proxy) => Some(("dnxhr_lb", "yuv422p")),
+            (VideoCodec::DNxHR, Lt) => Some(("dnxhr_sq", "yuv422p")),
+            (VideoCodec::DNxHR, Standard) => Some(("dnxhr_hq", "yuv422p")),
+            (VideoCodec::DNxHR, Hq) => Some(("dnxhr_hqx", "yuv422p10le")),
+            _ => None,
         }
     }
 }
 
+impl ExportStatus {
+    /// Status name as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportStatus::Queued => "queued",
+            ExportStatus::Rendering => "rendering",
+            ExportStatus::Complete => "complete",
+            ExportStatus::Cancelled => "cancelled",
+            ExportStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse a stored status name
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            // Jobs were "preparing" until they were queued
+            "queued" | "preparing" => Some(ExportStatus::Queued),
+            "rendering" => Some(ExportStatus::Rendering),
+            "complete" => Some(ExportStatus::Complete),
+            "cancelled" => Some(ExportStatus::Cancelled),
+            "failed" => Some(ExportStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether the job has reached a final state
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            ExportStatus::Complete | ExportStatus::Cancelled | ExportStatus::Failed
+        )
+    }
+}
+
+impl ExportHistoryEntry {
+    /// Create a history entry for a job that is starting now
+    pub fn started(
+        job_id: String,
+        project_id: Option<String>,
+        output_path: String,
+        settings: ExportSettings,
+        timeline_duration: f64,
+    ) -> Self {
+        Self {
+            job_id,
+            project_id,
+            output_path,
+            settings,
+            status: ExportStatus::Queued,
+            started_at: Utc::now(),
+            finished_at: None,
+            duration: None,
+            timeline_duration,
+            output_size: None,
+            error: None,
+            error_log: None,
+            retry_of: None,
+        }
+    }
+
+    /// Record a final status, computing render duration
+    pub fn finish(&mut self, status: ExportStatus, error: Option<String>) {
+        let finished_at = Utc::now();
+        self.status = status;
+        self.error = error;
+        self.duration = Some((finished_at - self.started_at).num_milliseconds() as f64 / 1000.0);
+        self.finished_at = Some(finished_at);
+        if status == ExportStatus::Complete {
+            self.output_size = std::fs::metadata(&self.output_path)
+                .ok()
+                .filter(|m| m.is_file())
+                .map(|m| m.len() as i64);
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Tag names are letters, digits, `_` and `-` (so no line breaks or
+    /// `=`), both names and values of bounded length
+    pub fn validate_metadata(&self) -> Result<(), String> {
+        for (key, value) in &self.metadata {
+            if key.is_empty()
+                || key.chars().count() > MAX_METADATA_KEY_LENGTH
+                || !key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(format!(
+                    "Metadata tag \"{}\" must be 1 to {} letters, digits, _ or -",
+                    key.escape_debug(),
+                    MAX_METADATA_KEY_LENGTH
+                ));
+            }
+            if value.chars().count() > MAX_METADATA_VALUE_LENGTH {
+                return Err(format!(
+                    "Metadata tag {} can't be longer than {} characters",
+                    key, MAX_METADATA_VALUE_LENGTH
+                ));
+            }
+            if value.contains('\0') {
+                return Err(format!("Metadata tag {} contains a NUL character", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the tags an export gets unless the settings set them: the
+    /// project name as `title` and the ClipForge version as `comment`
+    pub fn fill_default_metadata(&mut self, project_name: &str) {
+        self.metadata
+            .entry("title".to_string())
+            .or_insert_with(|| project_name.to_string());
+        self.metadata
+            .entry("comment".to_string())
+            .or_insert_with(|| format!("Made with ClipForge {}", env!("CARGO_PKG_VERSION")));
+    }
+}
+
 impl ExportQuality {
     /// Get CRF value for quality (lower = better quality)
     pub fn crf_value(&self) -> u32 {
@@ -136,6 +595,20 @@ impl Default for ExportSettings {
             audio_codec: AudioCodec::AAC,
             audio_bitrate: 192,
             hardware_acceleration: true,
+            gap_handling: GapHandling::Collapse,
+            range: None,
+            scaling_mode: ScalingMode::FitPad,
+            aspect_ratio: None,
+            fast_path: false,
+            gif_max_width: default_gif_max_width(),
+            watermark: None,
+            intermediate_profile: IntermediateProfile::Hq,
+            normalize_audio: false,
+            loudness_target: default_loudness_target(),
+            chapters: false,
+            metadata: HashMap::new(),
+            parallel: false,
+            caption_sidecar: None,
         }
     }
 }
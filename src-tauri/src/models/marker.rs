@@ -0,0 +1,89 @@
+use super::project::is_hex_color;
+use serde::{Deserialize, Serialize};
+
+/// Longest marker label, in characters
+pub const MAX_MARKER_LABEL_LENGTH: usize = 100;
+
+/// A named point on the project timeline; exports can write markers as
+/// chapters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub id: String,
+    /// Timeline time in seconds
+    pub time: f64,
+    pub label: String,
+    /// "#RRGGBB" shown on the ruler (None = the default marker color)
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl Marker {
+    pub fn new(time: f64, label: String, color: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            time,
+            label,
+            color,
+        }
+    }
+
+    /// Check the time, label and color; the label is trimmed first
+    pub fn validate(&mut self) -> Result<(), String> {
+        if !self.time.is_finite() || self.time < 0.0 {
+            return Err("Marker time must be a non-negative number of seconds".to_string());
+        }
+        self.label = self.label.trim().to_string();
+        if self.label.is_empty() {
+            return Err("Marker label can't be empty".to_string());
+        }
+        if self.label.chars().count() > MAX_MARKER_LABEL_LENGTH {
+            return Err(format!(
+                "Marker label can't be longer than {} characters",
+                MAX_MARKER_LABEL_LENGTH
+            ));
+        }
+        if let Some(color) = &self.color {
+            if !is_hex_color(color) {
+                return Err(format!(
+                    "Marker color must be written #RRGGBB, not {}",
+                    color
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sort markers by time, then id, so markers at the same time keep a stable order
+pub fn sort_markers(markers: &mut [Marker]) {
+    markers.sort_by(|a, b| a.time.total_cmp(&b.time).then_with(|| a.id.cmp(&b.id)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_validation() {
+        let mut marker = Marker::new(4.0, "  Intro ".to_string(), Some("#FF8800".to_string()));
+        assert!(marker.validate().is_ok());
+        assert_eq!(marker.label, "Intro");
+
+        for (time, label, color) in [
+            (-1.0, "Intro", None),
+            (f64::NAN, "Intro", None),
+            (1.0, "   ", None),
+            (1.0, &"x".repeat(MAX_MARKER_LABEL_LENGTH + 1) as &str, None),
+            (1.0, "Intro", Some("orange")),
+        ] {
+            let mut marker = Marker::new(time, label.to_string(), color.map(str::to_string));
+            assert!(
+                marker.validate().is_err(),
+                "{} {:?} {:?}",
+                time,
+                label,
+                color
+            );
+        }
+    }
+}
@@ -1,6 +1,11 @@
 pub mod caption;
 pub mod clip;
+pub mod compound;
 pub mod export;
+pub mod marker;
+pub mod playback;
 pub mod project;
 pub mod recording;
+pub mod settings;
 pub mod timeline;
+pub mod transition;
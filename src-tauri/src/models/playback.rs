@@ -0,0 +1,387 @@
+// Playback sequence (EDL) derived from the project timeline
+use crate::models::clip::MediaClip;
+use crate::models::project::Project;
+use crate::models::timeline::{Track, TrackType};
+use serde::Serialize;
+
+/// Gaps and overlaps shorter than this are ignored
+const EPSILON: f64 = 1e-6;
+
+/// One entry of a track's playback sequence
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlaybackSegment {
+    /// Play `source_in..source_out` of `file_path` over `timeline_start..timeline_end`
+    ///
+    /// A `speed` of 0 holds the frame at `source_in` (a freeze extension),
+    /// silently.
+    Clip {
+        clip_id: String,
+        media_clip_id: String,
+        file_path: String,
+        is_proxy: bool,
+        source_in: f64,
+        source_out: f64,
+        timeline_start: f64,
+        timeline_end: f64,
+        speed: f64,
+        volume: f32,
+    },
+    /// Nothing on the track (black / silence for the main track)
+    Gap {
+        timeline_start: f64,
+        timeline_end: f64,
+    },
+}
+
+impl PlaybackSegment {
+    pub fn timeline_start(&self) -> f64 {
+        match self {
+            PlaybackSegment::Clip { timeline_start, .. }
+            | PlaybackSegment::Gap { timeline_start, .. } => *timeline_start,
+        }
+    }
+
+    pub fn timeline_end(&self) -> f64 {
+        match self {
+            PlaybackSegment::Clip { timeline_end, .. }
+            | PlaybackSegment::Gap { timeline_end, .. } => *timeline_end,
+        }
+    }
+}
+
+/// Playback sequence of one overlay track
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlaySequence {
+    pub track_id: String,
+    pub order: u32,
+    pub visible: bool,
+    pub segments: Vec<PlaybackSegment>,
+}
+
+/// Ordered, gap-filled playback plan for the whole timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackSequence {
+    pub project_id: String,
+    /// Project revision the sequence was built from
+    pub revision: u64,
+    pub duration: f64,
+    pub main: Vec<PlaybackSegment>,
+    /// Overlay tracks, bottom to top
+    pub overlays: Vec<OverlaySequence>,
+}
+
+impl PlaybackSequence {
+    /// Build the sequence; `use_proxies` plays proxies where they exist
+    pub fn from_project(project: &Project, use_proxies: bool) -> Self {
+        let duration = project.duration();
+        let main = main_track(project)
+            .map(|track| track_segments(project, track, use_proxies, duration))
+            .unwrap_or_else(|| gap(0.0, duration).into_iter().collect());
+
+        let mut overlay_tracks: Vec<&Track> = project
+            .tracks
+            .iter()
+            .filter(|t| matches!(t.track_type, TrackType::Overlay))
+            .collect();
+        overlay_tracks.sort_by_key(|t| t.order);
+
+        PlaybackSequence {
+            project_id: project.id.clone(),
+            revision: project.revision,
+            duration,
+            main,
+            overlays: overlay_tracks
+                .into_iter()
+                .map(|track| OverlaySequence {
+                    track_id: track.id.clone(),
+                    order: track.order,
+                    visible: track.visible,
+                    segments: track_segments(project, track, use_proxies, duration),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The main track played and exported (the one holding the most clips)
+pub fn main_track(project: &Project) -> Option<&Track> {
+    project
+        .tracks
+        .iter()
+        .filter(|t| matches!(t.track_type, TrackType::Main))
+        .max_by_key(|t| t.clips.len())
+}
+
+/// Segments of `track` in timeline order, padded with gaps up to `duration`
+///
+/// Overlapping clips are cut so the later clip starts where the earlier one
+/// ends. Clips whose media is missing from the library become gaps. A clip's
+/// held frames are segments of their own around its media.
+pub fn track_segments(
+    project: &Project,
+    track: &Track,
+    use_proxies: bool,
+    duration: f64,
+) -> Vec<PlaybackSegment> {
+    let mut clips: Vec<_> = track.clips.iter().collect();
+    clips.sort_by(|a, b| a.timeline_order(b));
+
+    let mut segments = Vec::new();
+    let mut cursor = 0.0;
+    for clip in clips {
+        let clip_end = clip.end_time();
+        if clip_end <= cursor + EPSILON {
+            continue;
+        }
+        let Some(media) = project
+            .media_library
+            .iter()
+            .find(|m| m.id == clip.media_clip_id)
+        else {
+            continue;
+        };
+
+        segments.extend(gap(cursor, clip.start_time));
+        let (file_path, is_proxy) = playback_file(media, use_proxies);
+        let volume = if clip.uses_audio() { track.volume } else { 0.0 };
+        let (media_start, media_end) = (clip.media_start_time(), clip.media_end_time());
+        // (timeline start, timeline end, source in, source out, speed, volume)
+        let last_frame = (clip.out_point - 1.0 / media.fps.max(1.0)).max(clip.in_point);
+        let pieces = [
+            (
+                clip.start_time,
+                media_start,
+                clip.in_point,
+                clip.in_point,
+                0.0,
+                0.0,
+            ),
+            (
+                media_start,
+                media_end,
+                clip.in_point,
+                clip.out_point,
+                clip.speed,
+                volume,
+            ),
+            (media_end, clip_end, last_frame, last_frame, 0.0, 0.0),
+        ];
+        for (start, end, source_in, source_out, speed, volume) in pieces {
+            let from = start.max(cursor);
+            if end - from <= EPSILON {
+                continue;
+            }
+            segments.push(PlaybackSegment::Clip {
+                clip_id: clip.id.clone(),
+                media_clip_id: media.id.clone(),
+                file_path: file_path.clone(),
+                is_proxy,
+                source_in: (source_in + (from - start) * speed).min(source_out),
+                source_out,
+                timeline_start: from,
+                timeline_end: end,
+                speed,
+                volume,
+            });
+        }
+        cursor = clip_end;
+    }
+    segments.extend(gap(cursor, duration));
+    segments
+}
+
+/// File to play for `media`: the proxy when requested and available
+pub fn playback_file(media: &MediaClip, use_proxies: bool) -> (String, bool) {
+    match &media.proxy_path {
+        Some(proxy) if use_proxies => (proxy.clone(), true),
+        _ => (media.source_path.clone(), false),
+    }
+}
+
+fn gap(start: f64, end: f64) -> Option<PlaybackSegment> {
+    (end - start > EPSILON).then_some(PlaybackSegment::Gap {
+        timeline_start: start,
+        timeline_end: end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::TimelineClip;
+
+    /// Main: A (source 2-6s) at 0-4s, gap 4-5s, B (source 1-4s) at 5-8s.
+    /// Overlay: C (source 0-2s) at 3-5s.
+    fn project() -> Project {
+        let mut project = Project::new("Sequence".to_string());
+        let a = MediaClip::new("/m/a.mp4".into(), 30.0, 1920, 1080, 30.0, "h264".into(), 1);
+        let mut b = MediaClip::new(
+            "/m/b.mov".into(),
+            30.0,
+            1920,
+            1080,
+            30.0,
+            "prores".into(),
+            1,
+        );
+        b.proxy_path = Some("/cache/b_proxy.mp4".into());
+        let c = MediaClip::new("/m/c.png".into(), 5.0, 640, 360, 30.0, "png".into(), 1);
+
+        let main_id = project.tracks[0].id.clone();
+        project.tracks[0].volume = 0.8;
+        project.tracks[0].clips = vec![
+            TimelineClip::new(b.id.clone(), main_id.clone(), 5.0, 1.0, 4.0),
+            TimelineClip::new(a.id.clone(), main_id, 0.0, 2.0, 6.0),
+        ];
+        let mut overlay = Track::new("Overlay".into(), TrackType::Overlay);
+        overlay.order = 1;
+        overlay.clips.push(TimelineClip::new(
+            c.id.clone(),
+            overlay.id.clone(),
+            3.0,
+            0.0,
+            2.0,
+        ));
+        project.tracks.push(overlay);
+        project.media_library = vec![a, b, c];
+        project
+    }
+
+    /// (file_path, is_proxy, source_in, source_out, timeline_start, timeline_end, volume)
+    type ClipFields = (String, bool, f64, f64, f64, f64, f32);
+
+    fn clip(
+        file_path: &str,
+        is_proxy: bool,
+        source: (f64, f64),
+        timeline: (f64, f64),
+        volume: f32,
+    ) -> ClipFields {
+        (
+            file_path.to_string(),
+            is_proxy,
+            source.0,
+            source.1,
+            timeline.0,
+            timeline.1,
+            volume,
+        )
+    }
+
+    fn as_clip(segment: &PlaybackSegment) -> ClipFields {
+        match segment {
+            PlaybackSegment::Clip {
+                file_path,
+                is_proxy,
+                source_in,
+                source_out,
+                timeline_start,
+                timeline_end,
+                volume,
+                ..
+            } => (
+                file_path.clone(),
+                *is_proxy,
+                *source_in,
+                *source_out,
+                *timeline_start,
+                *timeline_end,
+                *volume,
+            ),
+            PlaybackSegment::Gap { .. } => panic!("expected a clip, got {:?}", segment),
+        }
+    }
+
+    #[test]
+    fn test_held_frame_is_a_silent_still_segment() {
+        use crate::models::timeline::{FreezeEdge, FreezeExtension};
+
+        let mut project = project();
+        // A (source 2-6s) holds its last frame until B starts at 5s
+        project.tracks[0].clips[1].freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::End,
+            duration: 1.0,
+        });
+        let sequence = PlaybackSequence::from_project(&project, true);
+        assert_eq!(sequence.main.len(), 3);
+        assert_eq!(
+            as_clip(&sequence.main[0]),
+            clip("/m/a.mp4", false, (2.0, 6.0), (0.0, 4.0), 0.8)
+        );
+        let last_frame = 6.0 - 1.0 / 30.0;
+        assert_eq!(
+            as_clip(&sequence.main[1]),
+            clip("/m/a.mp4", false, (last_frame, last_frame), (4.0, 5.0), 0.0)
+        );
+        assert!(matches!(sequence.main[1], PlaybackSegment::Clip { speed, .. } if speed == 0.0));
+        assert_eq!(sequence.main[2].timeline_start(), 5.0);
+    }
+
+    #[test]
+    fn test_main_track_with_trims_and_gap() {
+        let sequence = PlaybackSequence::from_project(&project(), true);
+        assert_eq!(sequence.duration, 8.0);
+        assert_eq!(sequence.main.len(), 3);
+        assert_eq!(
+            as_clip(&sequence.main[0]),
+            clip("/m/a.mp4", false, (2.0, 6.0), (0.0, 4.0), 0.8)
+        );
+        assert_eq!(
+            sequence.main[1],
+            PlaybackSegment::Gap {
+                timeline_start: 4.0,
+                timeline_end: 5.0
+            }
+        );
+        assert_eq!(
+            as_clip(&sequence.main[2]),
+            clip("/cache/b_proxy.mp4", true, (1.0, 4.0), (5.0, 8.0), 0.8)
+        );
+    }
+
+    #[test]
+    fn test_sources_without_proxies() {
+        let sequence = PlaybackSequence::from_project(&project(), false);
+        assert_eq!(
+            as_clip(&sequence.main[2]),
+            clip("/m/b.mov", false, (1.0, 4.0), (5.0, 8.0), 0.8)
+        );
+    }
+
+    #[test]
+    fn test_overlay_padded_with_gaps() {
+        let sequence = PlaybackSequence::from_project(&project(), true);
+        assert_eq!(sequence.overlays.len(), 1);
+        let overlay = &sequence.overlays[0].segments;
+        assert_eq!(overlay.len(), 3);
+        assert_eq!(overlay[0].timeline_end(), 3.0);
+        assert_eq!(
+            as_clip(&overlay[1]),
+            clip("/m/c.png", false, (0.0, 2.0), (3.0, 5.0), 1.0)
+        );
+        assert_eq!(overlay[2].timeline_start(), 5.0);
+        assert_eq!(overlay[2].timeline_end(), 8.0);
+    }
+
+    #[test]
+    fn test_overlapping_clip_is_cut() {
+        let mut project = project();
+        // B now starts at 3s, overlapping A by 1s
+        project.tracks[0].clips[0].start_time = 3.0;
+        let sequence = PlaybackSequence::from_project(&project, false);
+        assert_eq!(sequence.main.len(), 2);
+        assert_eq!(
+            as_clip(&sequence.main[1]),
+            clip("/m/b.mov", false, (2.0, 4.0), (4.0, 6.0), 0.8)
+        );
+    }
+
+    #[test]
+    fn test_empty_project_has_no_segments() {
+        let project = Project::new("Empty".to_string());
+        let sequence = PlaybackSequence::from_project(&project, true);
+        assert!(sequence.main.is_empty());
+        assert!(sequence.overlays.is_empty());
+    }
+}
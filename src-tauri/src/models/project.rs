@@ -1,7 +1,11 @@
 use super::clip::MediaClip;
-use super::timeline::{Track, TrackType};
+use super::compound::{CompoundClip, CompoundEdit};
+use super::export::ExportSettings;
+use super::marker::Marker;
+use super::timeline::{TimelineClip, Track, TrackType};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,81 +16,123 @@ pub struct Project {
     pub modified_at: DateTime<Utc>,
     pub file_path: Option<String>,
     pub version: String,
+    /// Tracks shown on the timeline; the compound clip's tracks while one is
+    /// being edited
     pub tracks: Vec<Track>,
+    /// Sub-timelines referenced by compound timeline clips
+    #[serde(default)]
+    pub compound_clips: Vec<CompoundClip>,
+    /// Compound clip open for editing, holding the parent timeline's tracks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editing_compound: Option<CompoundEdit>,
     pub media_library: Vec<MediaClip>,
+    /// Timeline markers in time order (see `sort_markers`)
+    #[serde(default)]
+    pub markers: Vec<Marker>,
     pub export_settings: ExportSettings,
+    /// Sequence settings (canvas size, frame rate, background)
+    #[serde(default)]
+    pub settings: ProjectSettings,
     pub auto_save_enabled: bool,
     pub last_auto_save: Option<DateTime<Utc>>,
+    /// Unsaved changes since the last save/load (runtime only)
+    #[serde(skip)]
+    pub dirty: bool,
+    /// Last successful save or load (runtime only)
+    #[serde(skip)]
+    pub last_saved_at: Option<DateTime<Utc>>,
+    /// Modification counter for this session (clients detect missed events with it)
+    #[serde(skip)]
+    pub revision: u64,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExportSettings {
-    pub resolution: Resolution,
-    pub codec: Codec,
-    pub quality: Quality,
-    pub fps: Option<u32>,
-    pub audio_codec: AudioCodec,
-    pub audio_bitrate: u32,
-    pub hardware_acceleration: bool,
+/// Project-level sequence settings: the canvas everything is rendered into
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// Canvas width in pixels (even)
+    pub width: u32,
+    /// Canvas height in pixels (even)
+    pub height: u32,
+    /// Timeline frame rate
+    pub fps: f64,
+    /// Background color shown behind letterboxed media and in gaps ("#RRGGBB")
+    pub background_color: String,
 }
 
-#[allow(dead_code, clippy::upper_case_acronyms)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Resolution {
-    Source,
-    #[serde(rename = "2160p")]
-    UHD4K,
-    #[serde(rename = "1440p")]
-    QHD,
-    #[serde(rename = "1080p")]
-    FullHD,
-    #[serde(rename = "720p")]
-    HD,
-    #[serde(rename = "480p")]
-    SD,
+/// Whether `color` is written "#RRGGBB"
+pub fn is_hex_color(color: &str) -> bool {
+    let hex = color.strip_prefix('#').unwrap_or("");
+    hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Codec {
-    H264,
-    Hevc,
-    Vp9,
-}
+/// Frame rates accepted for projects
+pub const SUPPORTED_PROJECT_FPS: [f64; 8] = [23.976, 24.0, 25.0, 29.97, 30.0, 50.0, 59.94, 60.0];
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Quality {
-    High,
-    Medium,
-    Low,
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            background_color: "#000000".to_string(),
+        }
+    }
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum AudioCodec {
-    Aac,
-    Mp3,
-    Opus,
-}
+impl ProjectSettings {
+    /// Settings matching a media clip ("match media"): its size rounded down to
+    /// even dimensions and its frame rate snapped to the nearest supported rate
+    pub fn match_media(clip: &MediaClip) -> Self {
+        let defaults = Self::default();
+        if clip.width < 2 || clip.height < 2 {
+            return defaults;
+        }
 
-impl Default for ExportSettings {
-    fn default() -> Self {
-        ExportSettings {
-            resolution: Resolution::FullHD,
-            codec: Codec::H264,
-            quality: Quality::High,
-            fps: None,
-            audio_codec: AudioCodec::Aac,
-            audio_bitrate: 192,
-            hardware_acceleration: true,
+        let fps = SUPPORTED_PROJECT_FPS
+            .iter()
+            .copied()
+            .min_by(|a, b| (a - clip.fps).abs().total_cmp(&(b - clip.fps).abs()))
+            .unwrap_or(defaults.fps);
+
+        Self {
+            width: (clip.width as u32) & !1,
+            height: (clip.height as u32) & !1,
+            fps,
+            ..defaults
         }
     }
+
+    /// Validate canvas size, frame rate, and color
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.width.is_multiple_of(2) || !self.height.is_multiple_of(2) {
+            return Err("Project width and height must be even".to_string());
+        }
+        if !(16..=7680).contains(&self.width) || !(16..=4320).contains(&self.height) {
+            return Err("Project size must be between 16x16 and 7680x4320".to_string());
+        }
+        if !SUPPORTED_PROJECT_FPS
+            .iter()
+            .any(|fps| (fps - self.fps).abs() < 0.001)
+        {
+            return Err(format!(
+                "Unsupported frame rate: {}. Must be one of {:?}",
+                self.fps, SUPPORTED_PROJECT_FPS
+            ));
+        }
+        if !is_hex_color(&self.background_color) {
+            return Err(format!(
+                "Invalid background color: {} (expected #RRGGBB)",
+                self.background_color
+            ));
+        }
+        Ok(())
+    }
+
+    /// Background color in FFmpeg syntax (0xRRGGBB)
+    pub fn ffmpeg_background_color(&self) -> String {
+        format!("0x{}", self.background_color.trim_start_matches('#'))
+    }
 }
 
 #[allow(dead_code)]
@@ -100,10 +146,17 @@ impl Project {
             file_path: None,
             version: "1.0.0".to_string(),
             tracks: Vec::new(),
+            compound_clips: Vec::new(),
+            editing_compound: None,
             media_library: Vec::new(),
+            markers: Vec::new(),
             export_settings: ExportSettings::default(),
+            settings: ProjectSettings::default(),
             auto_save_enabled: true,
             last_auto_save: None,
+            dirty: false,
+            last_saved_at: None,
+            revision: 0,
         };
 
         // Create default main track
@@ -114,7 +167,311 @@ impl Project {
         project
     }
 
-    pub fn mark_modified(&mut self) {
+    /// Copy of this project for "Save As"/duplicate
+    ///
+    /// Gets a fresh id unless `keep_id`; tracks, clips, and media references
+    /// keep their ids so versions can be compared.
+    pub fn fork(&self, name: Option<String>, keep_id: bool) -> Project {
+        let now = Utc::now();
+        let mut fork = self.clone();
+        if !keep_id {
+            fork.id = uuid::Uuid::new_v4().to_string();
+            fork.created_at = now;
+        }
+        if let Some(name) = name {
+            fork.name = name;
+        }
+        fork.modified_at = now;
+        fork.file_path = None;
+        fork.last_auto_save = None;
+        fork.last_saved_at = None;
+        fork
+    }
+
+    /// Timeline length: the end of the last clip on any track
+    pub fn duration(&self) -> f64 {
+        self.tracks.iter().map(|t| t.duration()).fold(0.0, f64::max)
+    }
+
+    /// Repair clip times the editor never writes but a file can hold: NaN
+    /// (saved as `null`) and infinities
+    ///
+    /// A bad in point becomes 0, a bad out point the end of the clip's
+    /// source and a bad start time the end of its track; clips whose range
+    /// can't be recovered are removed. Returns a message per clip touched.
+    pub fn repair_clip_times(&mut self) -> Vec<String> {
+        let mut repairs = Vec::new();
+        let media: HashMap<&str, f64> = self
+            .media_library
+            .iter()
+            .map(|m| (m.id.as_str(), m.duration))
+            .collect();
+        for compound in &mut self.compound_clips {
+            for track in &mut compound.tracks {
+                repair_track_times(
+                    track,
+                    |clip| media.get(clip.media_clip_id.as_str()).copied(),
+                    &mut repairs,
+                );
+            }
+        }
+
+        let sources: HashMap<&str, f64> = self
+            .compound_clips
+            .iter()
+            .map(|c| (c.id.as_str(), c.duration()))
+            .chain(media)
+            .collect();
+        let parent_tracks = self
+            .editing_compound
+            .iter_mut()
+            .flat_map(|edit| edit.parent_tracks.iter_mut());
+        for track in self.tracks.iter_mut().chain(parent_tracks) {
+            repair_track_times(
+                track,
+                |clip| sources.get(clip.media_clip_id.as_str()).copied(),
+                &mut repairs,
+            );
+        }
+        repairs
+    }
+
+    /// Record a modification; returns true if the project just became dirty
+    pub fn mark_modified(&mut self) -> bool {
         self.modified_at = Utc::now();
+        self.revision += 1;
+        let became_dirty = !self.dirty;
+        self.dirty = true;
+        became_dirty
+    }
+
+    /// Record a successful save to (or load from) `path`
+    pub fn mark_saved(&mut self, path: String) {
+        self.file_path = Some(path);
+        self.dirty = false;
+        self.last_saved_at = Some(Utc::now());
+    }
+}
+
+/// `Project::repair_clip_times` for one track; `source_duration` is the
+/// length of a clip's media or compound
+fn repair_track_times(
+    track: &mut Track,
+    source_duration: impl Fn(&TimelineClip) -> Option<f64>,
+    repairs: &mut Vec<String>,
+) {
+    let track_name = &track.name;
+    let mut repaired = Vec::new();
+    track.clips.retain_mut(|clip| {
+        if clip.has_finite_times() {
+            return true;
+        }
+        if !clip.in_point.is_finite() {
+            clip.in_point = 0.0;
+        }
+        if !clip.out_point.is_finite() {
+            match source_duration(clip).filter(|end| *end > clip.in_point) {
+                Some(end) => clip.out_point = end,
+                None => {
+                    repairs.push(format!(
+                        "Removed clip {} from '{}': its out point was invalid",
+                        clip.id, track_name
+                    ));
+                    return false;
+                }
+            }
+        }
+        repaired.push(clip.id.clone());
+        true
+    });
+
+    // Clips without a usable start go after everything else on the track
+    let mut track_end = track
+        .clips
+        .iter()
+        .filter(|clip| clip.start_time.is_finite())
+        .map(|clip| clip.end_time())
+        .fold(0.0, f64::max);
+    for clip in track.clips.iter_mut() {
+        if !clip.start_time.is_finite() {
+            clip.start_time = track_end;
+            track_end = clip.end_time();
+        }
+    }
+    repairs.extend(
+        repaired
+            .into_iter()
+            .map(|id| format!("Repaired invalid times of clip {} on '{}'", id, track.name)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_tracking() {
+        let mut project = Project::new("Demo".to_string());
+        assert!(!project.dirty);
+        assert!(project.last_saved_at.is_none());
+
+        // Only the first modification flips the state
+        assert!(project.mark_modified());
+        assert!(!project.mark_modified());
+        assert!(project.dirty);
+        // Every modification bumps the revision
+        assert_eq!(project.revision, 2);
+
+        project.mark_saved("/tmp/demo.clipforge".to_string());
+        assert!(!project.dirty);
+        assert!(project.last_saved_at.is_some());
+        assert_eq!(project.revision, 2);
+
+        // Runtime state is never written to the project file
+        let json = serde_json::to_value(&project).unwrap();
+        assert!(json.get("dirty").is_none());
+        assert!(json.get("last_saved_at").is_none());
+        assert!(json.get("revision").is_none());
+    }
+
+    #[test]
+    fn test_legacy_export_settings_deserialize() {
+        use crate::models::export::{AudioCodec, ExportQuality, ExportResolution, VideoCodec};
+
+        // Project file written before export settings were unified
+        let json = r#"{
+            "id": "legacy",
+            "name": "Legacy",
+            "created_at": "2025-01-01T00:00:00Z",
+            "modified_at": "2025-01-01T00:00:00Z",
+            "file_path": null,
+            "version": "1.0.0",
+            "tracks": [],
+            "media_library": [],
+            "export_settings": {
+                "resolution": "720p",
+                "codec": "hevc",
+                "quality": "medium",
+                "fps": 24,
+                "audio_codec": "opus",
+                "audio_bitrate": 128,
+                "hardware_acceleration": false
+            },
+            "auto_save_enabled": true,
+            "last_auto_save": null
+        }"#;
+
+        let project: Project = serde_json::from_str(json).unwrap();
+        let export = &project.export_settings;
+        assert_eq!(export.resolution, ExportResolution::HD);
+        assert_eq!(export.codec, VideoCodec::HEVC);
+        assert_eq!(export.quality, ExportQuality::Medium);
+        assert_eq!(export.fps, Some(24));
+        assert_eq!(export.audio_codec, AudioCodec::Opus);
+        assert_eq!(export.audio_bitrate, 128);
+        assert!(!export.hardware_acceleration);
+
+        // Variant-name spellings are accepted too
+        let resolution: ExportResolution = serde_json::from_str(r#""fullhd""#).unwrap();
+        assert_eq!(resolution, ExportResolution::FullHD);
+
+        // Round trip writes the canonical names
+        let saved = serde_json::to_value(&project).unwrap();
+        assert_eq!(saved["export_settings"]["resolution"], "720p");
+        assert_eq!(saved["export_settings"]["codec"], "hevc");
+    }
+
+    #[test]
+    fn test_old_project_files_get_default_settings() {
+        let project = Project::new("Old".to_string());
+        let mut json = serde_json::to_value(&project).unwrap();
+        json.as_object_mut().unwrap().remove("settings");
+
+        let loaded: Project = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.settings, ProjectSettings::default());
+
+        // Partial settings fill in the missing fields
+        let settings: ProjectSettings = serde_json::from_str(r#"{ "width": 1280 }"#).unwrap();
+        assert_eq!(settings.width, 1280);
+        assert_eq!(settings.height, 1080);
+        assert_eq!(settings.background_color, "#000000");
+    }
+
+    #[test]
+    fn test_match_media_inference() {
+        let mut clip = MediaClip::new(
+            "/videos/phone.mov".to_string(),
+            10.0,
+            1081,
+            1921,
+            29.97,
+            "hevc".to_string(),
+            100,
+        );
+        let settings = ProjectSettings::match_media(&clip);
+        assert_eq!((settings.width, settings.height), (1080, 1920));
+        assert_eq!(settings.fps, 29.97);
+        assert!(settings.validate().is_ok());
+
+        // Odd source rates snap to the nearest supported rate
+        clip.fps = 59.8;
+        assert_eq!(ProjectSettings::match_media(&clip).fps, 59.94);
+        clip.fps = 23.98;
+        assert_eq!(ProjectSettings::match_media(&clip).fps, 23.976);
+
+        // Audio-only media has no size: keep defaults
+        clip.width = 0;
+        clip.height = 0;
+        assert_eq!(
+            ProjectSettings::match_media(&clip),
+            ProjectSettings::default()
+        );
+    }
+
+    #[test]
+    fn test_project_settings_validation() {
+        assert!(ProjectSettings::default().validate().is_ok());
+
+        let odd = ProjectSettings {
+            width: 1921,
+            ..Default::default()
+        };
+        assert!(odd.validate().is_err());
+
+        let bad_fps = ProjectSettings {
+            fps: 31.0,
+            ..Default::default()
+        };
+        assert!(bad_fps.validate().is_err());
+
+        let bad_color = ProjectSettings {
+            background_color: "black".to_string(),
+            ..Default::default()
+        };
+        assert!(bad_color.validate().is_err());
+
+        let white = ProjectSettings {
+            background_color: "#FFFFFF".to_string(),
+            ..Default::default()
+        };
+        assert!(white.validate().is_ok());
+        assert_eq!(white.ffmpeg_background_color(), "0xFFFFFF");
+    }
+
+    #[test]
+    fn test_fork_regenerates_project_id_only() {
+        let mut project = Project::new("Rough cut".to_string());
+        project.file_path = Some("/projects/rough.clipforge".to_string());
+
+        let fork = project.fork(Some("Rough cut v2".to_string()), false);
+        assert_ne!(fork.id, project.id);
+        assert_eq!(fork.name, "Rough cut v2");
+        assert!(fork.file_path.is_none());
+        // Track ids are preserved so versions stay comparable
+        assert_eq!(fork.tracks[0].id, project.tracks[0].id);
+
+        let same = project.fork(None, true);
+        assert_eq!(same.id, project.id);
+        assert_eq!(same.name, "Rough cut");
     }
 }
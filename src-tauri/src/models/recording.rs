@@ -1,6 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Frame rates a recording can be made at
+pub const RECORDING_FPS: [u32; 4] = [15, 24, 30, 60];
+
+/// Difference between requested and delivered frame rates worth telling the
+/// user about (60 fps delivered as 59.94 is)
+const FPS_TOLERANCE: f64 = 0.01;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum RecordingType {
@@ -20,6 +27,52 @@ pub enum RecordingStatus {
     Failed,
 }
 
+impl RecordingType {
+    /// Serialized name (matches the serde representation)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordingType::Screen => "screen",
+            RecordingType::Webcam => "webcam",
+            RecordingType::ScreenWebcam => "screen_webcam",
+        }
+    }
+
+    /// Parse a serialized recording type
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "screen" => Some(RecordingType::Screen),
+            "webcam" => Some(RecordingType::Webcam),
+            "screen_webcam" => Some(RecordingType::ScreenWebcam),
+            _ => None,
+        }
+    }
+}
+
+impl RecordingStatus {
+    /// Serialized name (matches the serde representation)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordingStatus::Preparing => "preparing",
+            RecordingStatus::Recording => "recording",
+            RecordingStatus::Paused => "paused",
+            RecordingStatus::Stopped => "stopped",
+            RecordingStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse a serialized recording status
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "preparing" => Some(RecordingStatus::Preparing),
+            "recording" => Some(RecordingStatus::Recording),
+            "paused" => Some(RecordingStatus::Paused),
+            "stopped" => Some(RecordingStatus::Stopped),
+            "failed" => Some(RecordingStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
 /// Represents an active or completed screen/webcam recording session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingSession {
@@ -65,6 +118,15 @@ pub struct RecordingSession {
     /// Recording frame rate (default: 30)
     pub fps: u32,
 
+    /// Frame rate of the finished file, which the capture device may not
+    /// have matched to `fps`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_fps: Option<f64>,
+
+    /// Resolution of the finished file ("WIDTHxHEIGHT")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_resolution: Option<String>,
+
     /// Error description if status = Failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
@@ -72,6 +134,14 @@ pub struct RecordingSession {
     /// MediaClip ID created from recording
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_media_clip_id: Option<String>,
+
+    /// Analysis to run on the finished file
+    #[serde(default)]
+    pub post_process: RecordingPostProcess,
+
+    /// Where to put the finished recording on the timeline, if anywhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_add_to_timeline: Option<AutoAddTarget>,
 }
 
 impl RecordingSession {
@@ -95,8 +165,12 @@ impl RecordingSession {
             audio_sources: Vec::new(),
             resolution,
             fps,
+            actual_fps: None,
+            actual_resolution: None,
             error_message: None,
             created_media_clip_id: None,
+            post_process: RecordingPostProcess::default(),
+            auto_add_to_timeline: None,
         }
     }
 
@@ -119,7 +193,6 @@ impl RecordingSession {
     }
 
     /// Mark recording as failed with error message
-    #[allow(dead_code)]
     pub fn fail(&mut self, error: String) {
         self.status = RecordingStatus::Failed;
         self.error_message = Some(error);
@@ -131,6 +204,41 @@ impl RecordingSession {
         self.duration = Some(duration_seconds);
     }
 
+    /// Store the frame rate and size the finished file was probed at
+    ///
+    /// Returns a warning when they aren't what was requested. Screen+webcam
+    /// recordings are made at the screen's size, so only their frame rate
+    /// is compared.
+    pub fn record_delivered(&mut self, fps: f64, width: u32, height: u32) -> Option<String> {
+        let resolution = format!("{}x{}", width, height);
+        self.actual_fps = Some(fps);
+        self.actual_resolution = Some(resolution.clone());
+
+        let mut differences = Vec::new();
+        if fps > 0.0 && (fps - self.fps as f64).abs() > FPS_TOLERANCE {
+            differences.push(format!("{:.2} fps instead of {}", fps, self.fps));
+        }
+        let size_requested =
+            self.resolution != "source" && self.recording_type != RecordingType::ScreenWebcam;
+        if size_requested && width > 0 && resolution != self.resolution {
+            differences.push(format!("{} instead of {}", resolution, self.resolution));
+        }
+        if differences.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "The capture device delivered {}",
+            differences.join(" and ")
+        ))
+    }
+
+    /// Seconds recorded by `now` (0 before the session starts)
+    pub fn elapsed_at(&self, now: DateTime<Utc>) -> f64 {
+        self.started_at.map_or(0.0, |start| {
+            ((now - start).num_milliseconds() as f64 / 1000.0).max(0.0)
+        })
+    }
+
     /// Validate recording configuration
     pub fn validate(&self) -> Result<(), String> {
         // Validate resolution format
@@ -139,7 +247,7 @@ impl RecordingSession {
         }
 
         // Validate FPS
-        if ![15, 24, 30, 60].contains(&self.fps) {
+        if !RECORDING_FPS.contains(&self.fps) {
             return Err(format!(
                 "Invalid FPS: {}. Must be 15, 24, 30, or 60",
                 self.fps
@@ -160,7 +268,7 @@ impl RecordingSession {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecordingConfig {
     #[serde(rename = "type")]
     pub recording_type: RecordingType,
@@ -171,24 +279,142 @@ pub struct RecordingConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub camera_device_id: Option<String>,
 
+    /// Mirroring and cropping of the camera picture
+    #[serde(default)]
+    pub camera: CameraOptions,
+
     pub audio_sources: Vec<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub microphone_device_id: Option<String>, // Add specific microphone selection
 
     pub settings: RecordingSettings,
+
+    #[serde(default)]
+    pub post_process: RecordingPostProcess,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_add_to_timeline: Option<AutoAddTarget>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Crop aspect ratios offered for webcams
+pub const CAMERA_CROP_ASPECTS: [&str; 3] = ["16:9", "1:1", "4:3"];
+
+/// Adjustments to the webcam picture, applied while recording
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraOptions {
+    /// Flip horizontally, so presenters see themselves as in a mirror
+    pub mirror: bool,
+    /// Center crop to one of `CAMERA_CROP_ASPECTS` (None = as captured)
+    pub crop_aspect: Option<String>,
+}
+
+impl CameraOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.crop_aspect {
+            Some(aspect) if !CAMERA_CROP_ASPECTS.contains(&aspect.as_str()) => Err(format!(
+                "Unsupported camera crop '{}' (expected one of {})",
+                aspect,
+                CAMERA_CROP_ASPECTS.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Width and height of the crop aspect, e.g. (16, 9)
+    pub fn crop_ratio(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.crop_aspect.as_deref()?.split_once(':')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    }
+}
+
+/// Timeline placement for a finished recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoAddTarget {
+    /// Track to add to (None = the Main track)
+    #[serde(default)]
+    pub track_id: Option<String>,
+    pub at: PlayheadOrEnd,
+}
+
+/// Where on the track a recording goes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayheadOrEnd {
+    /// At this time (the playhead when recording started), or after the
+    /// track's last clip if that would overlap a clip
+    Playhead(f64),
+    /// After the track's last clip
+    End,
+}
+
+/// Analysis of a finished recording
+///
+/// Nothing is rewritten: trims become a suggested in/out point on the
+/// MediaClip, and the measured loudness is stored for the export normalizer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingPostProcess {
+    pub trim_leading_silence: bool,
+    pub trim_trailing_silence: bool,
+    pub normalize_audio: bool,
+}
+
+impl RecordingPostProcess {
+    /// Whether any analysis was asked for
+    pub fn is_enabled(&self) -> bool {
+        self.trim_leading_silence || self.trim_trailing_silence || self.normalize_audio
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecordingSettings {
     pub resolution: String,
     pub fps: u32,
+    /// Chroma subsampling of the recorded picture
+    #[serde(default)]
+    pub chroma: ChromaMode,
+    /// Encode with the platform's hardware H.264 encoder (less CPU while
+    /// recording, larger files)
+    #[serde(default)]
+    pub hardware_encoder: bool,
+}
+
+impl RecordingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chroma == ChromaMode::HighQuality444 && self.hardware_encoder {
+            return Err(
+                "4:4:4 recordings need the software encoder; turn off hardware encoding"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Chroma subsampling of a recording
+///
+/// 4:2:0 halves color resolution, which smears the edges of red and blue
+/// text in screen recordings. 4:4:4 keeps them sharp but only the software
+/// encoder writes it and few players open it, so proxies and exports are
+/// converted to 4:2:0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChromaMode {
+    #[default]
+    #[serde(rename = "420")]
+    Standard420,
+    #[serde(rename = "444")]
+    HighQuality444,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingSource {
     pub id: String,
     pub name: String,
+    /// Recording frame rates the device can capture at (empty if unknown)
+    #[serde(default)]
+    pub supported_fps: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -196,6 +422,9 @@ pub struct ScreenSource {
     pub id: String,
     pub name: String,
     pub resolution: String,
+    /// Recording frame rates the screen can be captured at (empty if unknown)
+    #[serde(default)]
+    pub supported_fps: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -260,6 +489,65 @@ mod tests {
         assert!(session.duration.is_some());
     }
 
+    #[test]
+    fn test_record_delivered() {
+        let mut session = RecordingSession::new(
+            RecordingType::Screen,
+            "/tmp/test.mp4".to_string(),
+            "1920x1080".to_string(),
+            60,
+        );
+        assert_eq!(session.record_delivered(60.0, 1920, 1080), None);
+        assert_eq!(session.actual_fps, Some(60.0));
+        assert_eq!(
+            session.record_delivered(60000.0 / 1001.0, 1920, 1080),
+            Some("The capture device delivered 59.94 fps instead of 60".to_string())
+        );
+        assert_eq!(
+            session.record_delivered(30.0, 1280, 720),
+            Some(
+                "The capture device delivered 30.00 fps instead of 60 \
+                 and 1280x720 instead of 1920x1080"
+                    .to_string()
+            )
+        );
+        assert_eq!(session.actual_resolution.as_deref(), Some("1280x720"));
+
+        // Picture-in-picture recordings keep the screen's size
+        session.recording_type = RecordingType::ScreenWebcam;
+        assert_eq!(session.record_delivered(60.0, 2880, 1800), None);
+    }
+
+    #[test]
+    fn test_enum_names_match_serde() {
+        for recording_type in [
+            RecordingType::Screen,
+            RecordingType::Webcam,
+            RecordingType::ScreenWebcam,
+        ] {
+            let json = serde_json::to_string(&recording_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", recording_type.as_str()));
+            assert_eq!(
+                RecordingType::parse(recording_type.as_str()),
+                Some(recording_type)
+            );
+        }
+
+        for status in [
+            RecordingStatus::Preparing,
+            RecordingStatus::Recording,
+            RecordingStatus::Paused,
+            RecordingStatus::Stopped,
+            RecordingStatus::Failed,
+        ] {
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", status.as_str()));
+            assert_eq!(RecordingStatus::parse(status.as_str()), Some(status));
+        }
+
+        assert_eq!(RecordingStatus::parse("unknown"), None);
+    }
+
     #[test]
     fn test_recording_session_validation() {
         let mut session = RecordingSession::new(
@@ -302,4 +590,31 @@ mod tests {
         session.camera_device = Some("camera-1".to_string());
         assert!(session.validate().is_ok());
     }
+
+    #[test]
+    fn test_chroma_settings() {
+        let settings: RecordingSettings =
+            serde_json::from_str(r#"{"resolution": "1920x1080", "fps": 30}"#).unwrap();
+        assert_eq!(settings.chroma, ChromaMode::Standard420);
+        assert!(!settings.hardware_encoder);
+
+        let settings: RecordingSettings = serde_json::from_str(
+            r#"{"resolution": "1920x1080", "fps": 30, "chroma": "444", "hardware_encoder": true}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.chroma, ChromaMode::HighQuality444);
+        assert!(settings.validate().is_err());
+        assert!(RecordingSettings {
+            hardware_encoder: false,
+            ..settings.clone()
+        }
+        .validate()
+        .is_ok());
+        assert!(RecordingSettings {
+            chroma: ChromaMode::Standard420,
+            ..settings
+        }
+        .validate()
+        .is_ok());
+    }
 }
@@ -0,0 +1,580 @@
+use crate::models::recording::RecordingConfig;
+use crate::storage::work_dir::{validate_work_directory, MIN_WORK_DIR_FREE_BYTES};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Application-wide settings persisted in the cache database
+///
+/// Every field has a serde default so settings written by older (or newer)
+/// versions of the app always deserialize.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Directory for new recordings (null = platform default)
+    pub recordings_dir: Option<String>,
+    /// Proxy generation settings
+    pub proxy: ProxySettings,
+    /// Directory of the cache database, proxies and thumbnails (null =
+    /// ~/.clipforge/cache). Mirrored in `location.json`, which is what
+    /// startup reads (see `storage::cache_location`)
+    pub cache_dir: Option<String>,
+    /// Directory for export temp files, caption audio and preview renders
+    /// (null = system temp); see `storage::work_dir`
+    pub work_directory: Option<String>,
+    /// Maximum size of the thumbnail/proxy cache in megabytes; the oldest
+    /// files are evicted past it
+    pub cache_size_cap_mb: u64,
+    /// Maximum encoder threads used by export (null = let FFmpeg decide)
+    pub export_thread_cap: Option<u32>,
+    /// Heavy FFmpeg jobs (exports, previews, proxies, thumbnails) run at once (null = half the cores)
+    pub max_concurrent_jobs: Option<u32>,
+    /// Exports rendered at once; later exports wait in the queue
+    pub max_concurrent_exports: u32,
+    /// FFmpeg processes a parallel export renders with at once (null = half
+    /// the cores, at least 2)
+    pub parallel_export_workers: Option<u32>,
+    /// Longest GIF export in seconds; GIFs grow large quickly
+    pub gif_max_seconds: u32,
+    /// Thumbnails generated at once in the background (applied on restart)
+    pub thumbnail_workers: u32,
+    /// Size and format of media thumbnails
+    pub thumbnail: ThumbnailSettings,
+    /// Auto-save interval in seconds (0 = disabled)
+    pub autosave_interval_secs: u64,
+    /// Space one project's auto-saves may take in the cache before older
+    /// snapshots are dropped early, in megabytes (0 = only the count limit)
+    pub autosave_max_mb: u64,
+    /// Whisper.cpp speech-to-text options
+    pub whisper: WhisperSettings,
+    /// Keyboard shortcuts (action id -> accelerator, e.g. "split_clip" -> "S")
+    pub hotkeys: HashMap<String, String>,
+    /// Resolution of preview renders and timeline frames
+    pub preview_quality: PreviewQualityMode,
+    /// FFmpeg binary or the directory containing it (null = search PATH and common locations)
+    pub ffmpeg_path: Option<String>,
+    /// HTTPS URL of the manifest listing managed FFmpeg builds (null = downloads disabled)
+    pub ffmpeg_manifest_url: Option<String>,
+    /// Largest file `import_media_from_url` downloads, in megabytes
+    pub max_download_mb: u64,
+    /// Write debug records (full FFmpeg stderr) to the log file
+    pub debug_logging: bool,
+    /// Cancel exports, proxies and caption jobs when the window closes
+    /// instead of asking first (active recordings always ask)
+    pub cancel_jobs_on_exit: bool,
+    /// Configuration of the most recently started recording, restored as
+    /// the recording panel's defaults
+    pub last_recording_config: Option<RecordingConfig>,
+}
+
+/// Preview resolution setting
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewQualityMode {
+    Full,
+    Half,
+    Quarter,
+    /// Start at full and step down while renders can't keep up with playback
+    #[default]
+    Auto,
+}
+
+/// Media thumbnail settings
+///
+/// Every thumbnail is written at `height` and at twice that for HiDPI
+/// displays; thumbnails made with other settings are regenerated when next
+/// requested.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThumbnailSettings {
+    /// Height of the 1x thumbnail in pixels (width follows the aspect)
+    pub height: u32,
+    pub format: ThumbnailFormat,
+}
+
+/// Image format of thumbnails
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "jpg",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+}
+
+impl Default for ThumbnailSettings {
+    fn default() -> Self {
+        Self {
+            height: 180,
+            format: ThumbnailFormat::Jpeg,
+        }
+    }
+}
+
+/// Proxy generation settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ProxySettings {
+    /// Generate proxies for non web-compatible codecs
+    pub enabled: bool,
+    /// Maximum proxy width in pixels
+    pub max_width: u32,
+    /// Maximum proxy height in pixels
+    pub max_height: u32,
+    /// x264 CRF value (lower = better quality)
+    pub crf: u32,
+    /// x264 preset
+    pub preset: String,
+    /// Proxy audio bitrate in kbps
+    pub audio_bitrate: u32,
+    /// Encode a keyframe about every second so seeking while scrubbing is
+    /// instant. Proxies get roughly 10-20% larger at the same CRF.
+    pub scrub_optimized: bool,
+}
+
+/// Whisper.cpp settings (migrated from the legacy ~/.clipforge/config.json)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WhisperSettings {
+    /// Path to whisper.cpp executable (or a name on PATH)
+    pub executable_path: String,
+    /// Path to the model file (e.g., ggml-base.en.bin)
+    pub model_path: String,
+    /// Default language code ("auto" for auto-detect)
+    pub default_language: String,
+    /// Number of threads passed to whisper.cpp
+    pub threads: u32,
+}
+
+/// x264 presets accepted for proxy generation
+const X264_PRESETS: [&str; 9] = [
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            recordings_dir: None,
+            proxy: ProxySettings::default(),
+            cache_dir: None,
+            work_directory: None,
+            cache_size_cap_mb: 10 * 1024,
+            export_thread_cap: None,
+            max_concurrent_jobs: None,
+            max_concurrent_exports: 1,
+            parallel_export_workers: None,
+            gif_max_seconds: 60,
+            thumbnail_workers: 2,
+            thumbnail: ThumbnailSettings::default(),
+            autosave_interval_secs: 120,
+            autosave_max_mb: 50,
+            whisper: WhisperSettings::default(),
+            hotkeys: HashMap::new(),
+            preview_quality: PreviewQualityMode::default(),
+            ffmpeg_path: None,
+            ffmpeg_manifest_url: None,
+            max_download_mb: 8 * 1024,
+            debug_logging: false,
+            cancel_jobs_on_exit: false,
+            last_recording_config: None,
+        }
+    }
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_width: 1920,
+            max_height: 1080,
+            crf: 23,
+            preset: "fast".to_string(),
+            audio_bitrate: 128,
+            scrub_optimized: true,
+        }
+    }
+}
+
+impl Default for WhisperSettings {
+    fn default() -> Self {
+        Self {
+            executable_path: "whisper-cli".to_string(),
+            model_path: "~/.clipforge/models/ggml-base.en.bin".to_string(),
+            default_language: "en".to_string(),
+            threads: 4,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Apply a partial update (JSON merge patch semantics)
+    ///
+    /// Objects are merged recursively, other values replace the current value,
+    /// and `null` resets a field to its default.
+    pub fn apply_patch(&self, patch: &serde_json::Value) -> Result<AppSettings, String> {
+        if !patch.is_object() {
+            return Err("Settings patch must be a JSON object".to_string());
+        }
+
+        let mut merged = serde_json::to_value(self)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        merge_json(&mut merged, patch);
+
+        serde_json::from_value(merged).map_err(|e| format!("Invalid settings value: {}", e))
+    }
+
+    /// `cache_size_cap_mb` in bytes
+    pub fn cache_size_cap_bytes(&self) -> u64 {
+        self.cache_size_cap_mb.saturating_mul(1024 * 1024)
+    }
+
+    /// Validate numeric ranges and value formats
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cache_size_cap_mb < 256 {
+            return Err("Cache size cap must be at least 256 MB".to_string());
+        }
+        if let Some(threads) = self.export_thread_cap {
+            if !(1..=64).contains(&threads) {
+                return Err("Export thread cap must be between 1 and 64".to_string());
+            }
+        }
+        if let Some(jobs) = self.max_concurrent_jobs {
+            if !(1..=32).contains(&jobs) {
+                return Err("Concurrent job limit must be between 1 and 32".to_string());
+            }
+        }
+        if !(1..=4).contains(&self.max_concurrent_exports) {
+            return Err("Concurrent export limit must be between 1 and 4".to_string());
+        }
+        if let Some(workers) = self.parallel_export_workers {
+            if !(2..=16).contains(&workers) {
+                return Err("Parallel export workers must be between 2 and 16".to_string());
+            }
+        }
+        if !(1..=600).contains(&self.gif_max_seconds) {
+            return Err("GIF length limit must be between 1 and 600 seconds".to_string());
+        }
+        if !(1..=8).contains(&self.thumbnail_workers) {
+            return Err("Thumbnail workers must be between 1 and 8".to_string());
+        }
+        if !(90..=720).contains(&self.thumbnail.height) {
+            return Err("Thumbnail height must be between 90 and 720 pixels".to_string());
+        }
+        if self.autosave_interval_secs != 0 && !(10..=3600).contains(&self.autosave_interval_secs) {
+            return Err(
+                "Auto-save interval must be 0 (disabled) or between 10 and 3600 seconds"
+                    .to_string(),
+            );
+        }
+        if self.max_download_mb == 0 {
+            return Err("Download size limit must be at least 1 MB".to_string());
+        }
+        if let Some(url) = &self.ffmpeg_manifest_url {
+            if !url.starts_with("https://") {
+                return Err("FFmpeg manifest URL must use https://".to_string());
+            }
+        }
+        self.proxy.validate()?;
+        self.whisper.validate()?;
+        for (action, accelerator) in &self.hotkeys {
+            if action.trim().is_empty() || accelerator.trim().is_empty() {
+                return Err("Hotkey actions and accelerators cannot be empty".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate an updated settings value against the previous one
+    ///
+    /// Ranges are always checked; paths are only checked when they changed, so an
+    /// unrelated update doesn't fail because e.g. the default whisper model isn't installed.
+    pub fn validate_update(&self, previous: &AppSettings) -> Result<(), String> {
+        self.validate()?;
+
+        if self.recordings_dir != previous.recordings_dir {
+            if let Some(dir) = &self.recordings_dir {
+                if !Path::new(dir).is_dir() {
+                    return Err(format!("Recordings directory does not exist: {}", dir));
+                }
+            }
+        }
+
+        if self.cache_dir != previous.cache_dir {
+            if let Some(dir) = &self.cache_dir {
+                if !Path::new(dir).is_absolute() {
+                    return Err(format!("Cache directory must be an absolute path: {}", dir));
+                }
+            }
+        }
+
+        if self.work_directory != previous.work_directory {
+            if let Some(dir) = &self.work_directory {
+                if !Path::new(dir).is_absolute() {
+                    return Err(format!("Work directory must be an absolute path: {}", dir));
+                }
+                validate_work_directory(Path::new(dir), MIN_WORK_DIR_FREE_BYTES)?;
+            }
+        }
+
+        if self.whisper.model_path != previous.whisper.model_path {
+            let model_path = expand_home(&self.whisper.model_path);
+            if !Path::new(&model_path).is_file() {
+                return Err(format!("Whisper model not found: {}", model_path));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ProxySettings {
+    /// Validate proxy settings
+    pub fn validate(&self) -> Result<(), String> {
+        if !(320..=7680).contains(&self.max_width) || !(240..=4320).contains(&self.max_height) {
+            return Err("Proxy size must be between 320x240 and 7680x4320".to_string());
+        }
+        if self.crf > 51 {
+            return Err("Proxy CRF must be between 0 and 51".to_string());
+        }
+        if !X264_PRESETS.contains(&self.preset.as_str()) {
+            return Err(format!("Invalid proxy preset: {}", self.preset));
+        }
+        if !(32..=320).contains(&self.audio_bitrate) {
+            return Err("Proxy audio bitrate must be between 32 and 320 kbps".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl WhisperSettings {
+    /// Validate whisper settings
+    pub fn validate(&self) -> Result<(), String> {
+        if self.executable_path.trim().is_empty() {
+            return Err("Whisper executable path cannot be empty".to_string());
+        }
+        if self.default_language.trim().is_empty() {
+            return Err("Whisper language cannot be empty".to_string());
+        }
+        if !(1..=32).contains(&self.threads) {
+            return Err("Whisper threads must be between 1 and 32".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix('~') {
+        if let Some(home) = dirs::home_dir() {
+            return format!("{}{}", home.to_string_lossy(), rest);
+        }
+    }
+    path.to_string()
+}
+
+/// Recursively merge `patch` into `target` (RFC 7396 JSON merge patch)
+fn merge_json(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target_map), serde_json::Value::Object(patch_map)) => {
+            for (key, value) in patch_map {
+                if value.is_null() {
+                    target_map.remove(key);
+                } else {
+                    merge_json(
+                        target_map
+                            .entry(key.clone())
+                            .or_insert(serde_json::Value::Null),
+                        value,
+                    );
+                }
+            }
+        }
+        (target, serde_json::Value::Object(_)) => {
+            // Merging an object into a non-object starts from an empty object
+            *target = serde_json::Value::Object(serde_json::Map::new());
+            merge_json(target, patch);
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deserialize_empty_uses_defaults() {
+        let settings: AppSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, AppSettings::default());
+
+        // Unknown keys from newer versions are ignored
+        let settings: AppSettings =
+            serde_json::from_value(json!({ "future_option": true, "cache_size_cap_mb": 2048 }))
+                .unwrap();
+        assert_eq!(settings.cache_size_cap_mb, 2048);
+    }
+
+    #[test]
+    fn test_patch_merges_nested_fields() {
+        let settings = AppSettings::default();
+        let patched = settings
+            .apply_patch(&json!({ "proxy": { "crf": 28 }, "autosave_interval_secs": 60 }))
+            .unwrap();
+
+        assert_eq!(patched.proxy.crf, 28);
+        // Sibling fields of the nested object are preserved
+        assert_eq!(patched.proxy.preset, "fast");
+        assert_eq!(patched.proxy.max_height, 1080);
+        assert_eq!(patched.autosave_interval_secs, 60);
+        assert_eq!(patched.whisper, settings.whisper);
+    }
+
+    #[test]
+    fn test_patch_null_resets_to_default() {
+        let settings = AppSettings {
+            recordings_dir: Some("/tmp".to_string()),
+            proxy: ProxySettings {
+                crf: 30,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let patched = settings
+            .apply_patch(&json!({ "recordings_dir": null, "proxy": null }))
+            .unwrap();
+
+        assert_eq!(patched.recordings_dir, None);
+        assert_eq!(patched.proxy, ProxySettings::default());
+    }
+
+    #[test]
+    fn test_patch_replaces_maps() {
+        let settings = AppSettings::default();
+        let patched = settings
+            .apply_patch(&json!({ "hotkeys": { "split_clip": "S" } }))
+            .unwrap();
+        assert_eq!(patched.hotkeys.get("split_clip"), Some(&"S".to_string()));
+
+        // Removing a single hotkey via null
+        let patched = patched
+            .apply_patch(&json!({ "hotkeys": { "split_clip": null } }))
+            .unwrap();
+        assert!(patched.hotkeys.is_empty());
+    }
+
+    #[test]
+    fn test_patch_rejects_wrong_types() {
+        let settings = AppSettings::default();
+        assert!(settings
+            .apply_patch(&json!({ "autosave_interval_secs": "soon" }))
+            .is_err());
+        assert!(settings.apply_patch(&json!([1, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn test_validation_ranges() {
+        assert!(AppSettings::default().validate().is_ok());
+
+        let settings = AppSettings {
+            export_thread_cap: Some(0),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            max_concurrent_jobs: Some(0),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            max_concurrent_exports: 0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            parallel_export_workers: Some(1),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            gif_max_seconds: 0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            thumbnail_workers: 0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let mut settings = AppSettings::default();
+        settings.thumbnail.height = 4000;
+        assert!(settings.validate().is_err());
+
+        let mut settings = AppSettings {
+            autosave_interval_secs: 5,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        settings.autosave_interval_secs = 0;
+        assert!(settings.validate().is_ok());
+
+        let mut settings = AppSettings::default();
+        settings.proxy.crf = 60;
+        assert!(settings.validate().is_err());
+
+        let mut settings = AppSettings::default();
+        settings.proxy.preset = "warp".to_string();
+        assert!(settings.validate().is_err());
+
+        let settings = AppSettings {
+            cache_size_cap_mb: 10,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_update_checks_changed_paths() {
+        let previous = AppSettings::default();
+
+        let mut updated = previous.clone();
+        updated.recordings_dir = Some("/nonexistent/clipforge/recordings".to_string());
+        assert!(updated.validate_update(&previous).is_err());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        updated.recordings_dir = Some(temp_dir.path().to_string_lossy().to_string());
+        assert!(updated.validate_update(&previous).is_ok());
+
+        // Unchanged (possibly missing) whisper model path is not re-validated
+        let mut unrelated = previous.clone();
+        unrelated.proxy.crf = 20;
+        assert!(unrelated.validate_update(&previous).is_ok());
+
+        let mut updated = previous.clone();
+        updated.whisper.model_path = "/nonexistent/model.bin".to_string();
+        assert!(updated.validate_update(&previous).is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use serde::{Deserialize, Serialize};
+use super::project::is_hex_color;
+use super::transition::Transition;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cmp::Ordering;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +16,57 @@ pub struct Track {
     pub visible: bool,
     pub locked: bool,
     pub volume: f32,
+    /// Header swatch color ("#RRGGBB")
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Automatic gain reduction while other tracks sound
+    #[serde(default)]
+    pub ducking: Option<TrackDucking>,
+    /// Transitions between adjacent clips of the track
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+}
+
+/// Ducking of a track (music) whenever other tracks (narration) sound
+///
+/// Rendered with FFmpeg's `sidechaincompress`, keyed by the mix of the
+/// `against_track_ids` tracks, in both the export and the preview mixdown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackDucking {
+    /// Tracks whose audio pushes this one down
+    pub against_track_ids: Vec<String>,
+    /// Reduction while they sound, in dB (positive)
+    pub amount_db: f32,
+    /// Time to duck once they start, in milliseconds
+    pub attack_ms: f32,
+    /// Time to recover once they stop, in milliseconds
+    pub release_ms: f32,
+}
+
+/// Deepest ducking, in dB
+pub const MAX_DUCKING_DB: f32 = 40.0;
+
+impl TrackDucking {
+    /// Check the amounts against what `sidechaincompress` accepts; track ids
+    /// are checked against the project by the caller
+    pub fn validate(&self) -> Result<(), String> {
+        if self.against_track_ids.is_empty() {
+            return Err("Ducking needs at least one track to duck under".to_string());
+        }
+        if !self.amount_db.is_finite() || self.amount_db <= 0.0 || self.amount_db > MAX_DUCKING_DB {
+            return Err(format!(
+                "Ducking amount must be between 0 and {} dB",
+                MAX_DUCKING_DB
+            ));
+        }
+        if !(0.01..=2000.0).contains(&self.attack_ms) {
+            return Err("Ducking attack must be between 0.01 and 2000 ms".to_string());
+        }
+        if !(0.01..=9000.0).contains(&self.release_ms) {
+            return Err("Ducking release must be between 0.01 and 9000 ms".to_string());
+        }
+        Ok(())
+    }
 }
 
 #[allow(dead_code)]
@@ -28,15 +83,317 @@ pub struct TimelineClip {
     pub id: String,
     pub media_clip_id: String,
     pub track_id: String,
+    #[serde(deserialize_with = "time_or_nan")]
     pub start_time: f64,
+    #[serde(deserialize_with = "time_or_nan")]
     pub in_point: f64,
+    #[serde(deserialize_with = "time_or_nan")]
     pub out_point: f64,
     pub layer_order: u32,
     pub transform: Option<Transform>,
+    /// Use only the clip's audio; exports show black for its duration
+    #[serde(default)]
+    pub audio_only: bool,
+    /// Use only the clip's video; its audio is replaced by silence
+    #[serde(default)]
+    pub video_only: bool,
+    /// Visual effects, applied in `ClipEffect` stage order
+    #[serde(default)]
+    pub effects: Vec<ClipEffect>,
+    /// Regions hidden before any effect is applied
+    #[serde(default)]
+    pub redactions: Vec<Redaction>,
+    /// What `media_clip_id` refers to
+    #[serde(default)]
+    pub source_kind: ClipSourceKind,
+    /// How the clip's audio channels reach the stereo output
+    #[serde(default)]
+    pub channel_mode: ChannelMode,
+    /// First or last frame held beyond the media, silent
+    #[serde(default)]
+    pub freeze_extension: Option<FreezeExtension>,
+    /// Free-form note from the editor to themselves
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Review comments anchored at points in the clip
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    /// Playback rate: 2.0 plays the media in half the time, 0.5 in slow
+    /// motion (`MIN_CLIP_SPEED` to `MAX_CLIP_SPEED`)
+    #[serde(default = "default_clip_speed")]
+    pub speed: f64,
+}
+
+fn default_clip_speed() -> f64 {
+    1.0
+}
+
+/// A review comment on a timeline clip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: String,
+    /// Seconds from the clip start; follows the source frame when the clip
+    /// is trimmed
+    pub time_offset: f64,
+    pub text: String,
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub resolved: bool,
+    /// The commented frame was trimmed out of the clip; the comment comes
+    /// back if the trim is undone
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+impl Comment {
+    pub fn new(time_offset: f64, text: String, author: Option<String>) -> Self {
+        Comment {
+            id: uuid::Uuid::new_v4().to_string(),
+            time_offset,
+            text,
+            author,
+            created_at: Utc::now(),
+            resolved: false,
+            orphaned: false,
+        }
+    }
+}
+
+/// Longest freeze-frame extension of a clip, in seconds
+pub const MAX_FREEZE_SECONDS: f64 = 10.0;
+
+/// Slowest clip playback rate (a tenth of real time)
+pub const MIN_CLIP_SPEED: f64 = 0.1;
+
+/// Fastest clip playback rate
+pub const MAX_CLIP_SPEED: f64 = 10.0;
+
+/// Check a clip playback rate is within `MIN_CLIP_SPEED..=MAX_CLIP_SPEED`
+pub fn validate_clip_speed(speed: f64) -> Result<(), String> {
+    if !(MIN_CLIP_SPEED..=MAX_CLIP_SPEED).contains(&speed) {
+        return Err(format!(
+            "Clip speed must be between {} and {}",
+            MIN_CLIP_SPEED, MAX_CLIP_SPEED
+        ));
+    }
+    Ok(())
+}
+
+/// End of a clip whose frame a freeze extension holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FreezeEdge {
+    /// The first frame, shown before the media plays
+    Start,
+    /// The last frame, shown after the media ends
+    End,
+}
+
+/// Time a clip holds its first or last frame beyond its media
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FreezeExtension {
+    pub edge: FreezeEdge,
+    /// Seconds, up to `MAX_FREEZE_SECONDS`
+    pub duration: f64,
+}
+
+impl FreezeExtension {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.duration.is_finite() || self.duration <= 0.0 {
+            return Err("Freeze duration must be a positive number of seconds".to_string());
+        }
+        if self.duration > MAX_FREEZE_SECONDS {
+            return Err(format!(
+                "Freeze duration can't exceed {} seconds",
+                MAX_FREEZE_SECONDS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Routing of a clip's audio channels into the stereo mix
+///
+/// Mono sources always play in both ears, whatever the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelMode {
+    /// Stereo as-is, mono on both channels
+    #[default]
+    Auto,
+    /// Downmix to mono and play it on both channels
+    MonoToStereo,
+    /// The left channel on both sides (interviews with a speaker per channel)
+    LeftOnly,
+    /// The right channel on both sides
+    RightOnly,
+    /// Left and right exchanged
+    Swap,
+}
+
+/// Source a timeline clip plays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipSourceKind {
+    /// A media library clip
+    #[default]
+    Media,
+    /// A compound clip on the project; `media_clip_id` holds its id and the
+    /// in/out points trim its contents' timeline
+    Compound,
+}
+
+/// How a redacted region is hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionKind {
+    Blur,
+    /// Solid black box
+    Box,
+}
+
+/// Region of a clip hidden for part of its duration
+///
+/// The rectangle is in source pixels; `start..end` are seconds from the
+/// clip's start on the timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub start: f64,
+    pub end: f64,
+    pub kind: RedactionKind,
+}
+
+impl Redaction {
+    /// Check the rectangle lies inside a `media_width`x`media_height` frame
+    pub fn validate(&self, media_width: u32, media_height: u32) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err("Redaction region is empty".to_string());
+        }
+        if self.x.saturating_add(self.width) > media_width
+            || self.y.saturating_add(self.height) > media_height
+        {
+            return Err(format!(
+                "Redaction region {}x{} at ({}, {}) extends past the {}x{} frame",
+                self.width, self.height, self.x, self.y, media_width, media_height
+            ));
+        }
+        if !self.start.is_finite() || !self.end.is_finite() || self.start < 0.0 {
+            return Err("Redaction times must be non-negative numbers".to_string());
+        }
+        if self.end <= self.start {
+            return Err("Redaction must end after it starts".to_string());
+        }
+        Ok(())
+    }
+}
+/// Parameterized visual effect on a timeline clip
+///
+/// Crop edges are fractions of the frame, so they cut the same picture from a
+/// proxy as from the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClipEffect {
+    Crop {
+        left: f64,
+        right: f64,
+        top: f64,
+        bottom: f64,
+    },
+    FlipH,
+    FlipV,
+    /// Sigma in pixels of the rendered frame
+    GaussianBlur {
+        sigma: f64,
+    },
+    /// Brightness -1..1 (0 = unchanged), contrast 0..2 and saturation 0..3 (1 = unchanged)
+    Eq {
+        brightness: f64,
+        contrast: f64,
+        saturation: f64,
+    },
+}
+
+/// Largest Gaussian blur sigma
+pub const MAX_BLUR_SIGMA: f64 = 100.0;
+
+impl ClipEffect {
+    /// Position in the filter chain: crop, flips, eq, then blur
+    pub fn stage(&self) -> u8 {
+        match self {
+            ClipEffect::Crop { .. } => 0,
+            ClipEffect::FlipH => 1,
+            ClipEffect::FlipV => 2,
+            ClipEffect::Eq { .. } => 3,
+            ClipEffect::GaussianBlur { .. } => 4,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let in_range = |name: &str, value: f64, min: f64, max: f64| {
+            if value.is_finite() && (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} must be between {} and {}, got {}",
+                    name, min, max, value
+                ))
+            }
+        };
+        match *self {
+            ClipEffect::Crop {
+                left,
+                right,
+                top,
+                bottom,
+            } => {
+                in_range("Crop left", left, 0.0, 1.0)?;
+                in_range("Crop right", right, 0.0, 1.0)?;
+                in_range("Crop top", top, 0.0, 1.0)?;
+                in_range("Crop bottom", bottom, 0.0, 1.0)?;
+                if left + right >= 1.0 || top + bottom >= 1.0 {
+                    return Err("Crop removes the whole frame".to_string());
+                }
+                Ok(())
+            }
+            ClipEffect::FlipH | ClipEffect::FlipV => Ok(()),
+            ClipEffect::GaussianBlur { sigma } => {
+                in_range("Blur sigma", sigma, 0.0, MAX_BLUR_SIGMA)?;
+                if sigma == 0.0 {
+                    return Err("Blur sigma must be greater than 0".to_string());
+                }
+                Ok(())
+            }
+            ClipEffect::Eq {
+                brightness,
+                contrast,
+                saturation,
+            } => {
+                in_range("Brightness", brightness, -1.0, 1.0)?;
+                in_range("Contrast", contrast, 0.0, 2.0)?;
+                in_range("Saturation", saturation, 0.0, 3.0)
+            }
+        }
+    }
+}
+
+/// Check every effect's parameters; each effect may appear once
+pub fn validate_effects(effects: &[ClipEffect]) -> Result<(), String> {
+    for (i, effect) in effects.iter().enumerate() {
+        effect.validate()?;
+        if effects[..i].iter().any(|e| e.stage() == effect.stage()) {
+            return Err(format!("Effect {:?} is applied more than once", effect));
+        }
+    }
+    Ok(())
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
     pub x: i32,
     pub y: i32,
@@ -57,20 +414,220 @@ impl Track {
             visible: true,
             locked: false,
             volume: 1.0,
+            color: None,
+            ducking: None,
+            transitions: Vec::new(),
         }
     }
 
     pub fn duration(&self) -> f64 {
-        self.clips
-            .iter()
-            .map(|c| c.end_time())
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(0.0)
+        self.clips.iter().map(|c| c.end_time()).fold(0.0, f64::max)
     }
 
     pub fn clip_count(&self) -> usize {
         self.clips.len()
     }
+
+    /// Start for a clip of `duration` wanted at `start`: `start` itself if
+    /// nothing on the track overlaps it, otherwise the end of the track
+    pub fn free_start(&self, start: f64, duration: f64) -> f64 {
+        let end = start + duration;
+        let collides = self
+            .clips
+            .iter()
+            .any(|clip| clip.start_time < end && start < clip.end_time());
+        if collides {
+            self.duration()
+        } else {
+            start
+        }
+    }
+
+    /// Set the mix volume, clamped to 0.0 (muted) ..= `MAX_TRACK_VOLUME`
+    pub fn set_volume(&mut self, volume: f32) -> Result<(), String> {
+        if !volume.is_finite() {
+            return Err(format!("Track volume must be a number, got {}", volume));
+        }
+        self.volume = volume.clamp(0.0, MAX_TRACK_VOLUME);
+        Ok(())
+    }
+}
+
+/// Loudest track volume (+6 dB)
+pub const MAX_TRACK_VOLUME: f32 = 2.0;
+
+/// Longest track name, in characters
+pub const MAX_TRACK_NAME_LENGTH: usize = 64;
+
+impl Track {
+    /// Rename; surrounding whitespace is dropped
+    pub fn rename(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Track name can't be empty".to_string());
+        }
+        if name.chars().count() > MAX_TRACK_NAME_LENGTH {
+            return Err(format!(
+                "Track name must be at most {} characters",
+                MAX_TRACK_NAME_LENGTH
+            ));
+        }
+        self.name = name.to_string();
+        Ok(())
+    }
+
+    /// Set the header color ("#RRGGBB"), or clear it with None
+    pub fn set_color(&mut self, color: Option<String>) -> Result<(), String> {
+        if let Some(color) = &color {
+            if !is_hex_color(color) {
+                return Err(format!("Invalid track color: {} (expected #RRGGBB)", color));
+            }
+        }
+        self.color = color;
+        Ok(())
+    }
+}
+
+/// Shortest a slide may leave a neighboring clip, in seconds
+pub const MIN_SLIDE_NEIGHBOR_DURATION: f64 = 0.1;
+
+/// Why `Track::slide_clip` refused a slide
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlideError {
+    ClipNotFound,
+    /// The clip is first or last on its track ("previous" / "next")
+    MissingNeighbor {
+        side: &'static str,
+    },
+    /// `neighbor_id` can't absorb the slide; the clip can move at most
+    /// `max_delta` seconds in that direction (signed like the request)
+    HandleLimit {
+        neighbor_id: String,
+        max_delta: f64,
+    },
+}
+
+impl Track {
+    /// Move a clip by `delta` seconds without changing its content, trimming
+    /// the previous clip's out point and the next clip's in point (and start)
+    /// to match, so every other clip stays where it is
+    ///
+    /// `source_duration` gives the length of a clip's source (None if
+    /// unknown, which leaves it no handle past its out point). Returns the
+    /// previous, slid and next clips.
+    pub fn slide_clip(
+        &mut self,
+        clip_id: &str,
+        delta: f64,
+        source_duration: impl Fn(&TimelineClip) -> Option<f64>,
+    ) -> Result<[TimelineClip; 3], SlideError> {
+        let mut by_start: Vec<usize> = (0..self.clips.len()).collect();
+        by_start.sort_by(|&a, &b| self.clips[a].timeline_order(&self.clips[b]));
+        let position = by_start
+            .iter()
+            .position(|&i| self.clips[i].id == clip_id)
+            .ok_or(SlideError::ClipNotFound)?;
+        let previous = match position.checked_sub(1) {
+            Some(p) => by_start[p],
+            None => return Err(SlideError::MissingNeighbor { side: "previous" }),
+        };
+        let next = *by_start
+            .get(position + 1)
+            .ok_or(SlideError::MissingNeighbor { side: "next" })?;
+        let current = by_start[position];
+
+        let (prev_clip, next_clip) = (&self.clips[previous], &self.clips[next]);
+        let (prev_room, next_room) = if delta > 0.0 {
+            // Previous clip extends into its source, next one loses its head
+            let prev_source = source_duration(prev_clip).unwrap_or(prev_clip.out_point);
+            (
+                (prev_source - prev_clip.out_point) / prev_clip.speed,
+                next_clip.media_duration() - MIN_SLIDE_NEIGHBOR_DURATION,
+            )
+        } else {
+            // Previous clip loses its tail, next one reveals earlier source.
+            // Held frames stay, so only the media can be trimmed
+            (
+                prev_clip.media_duration() - MIN_SLIDE_NEIGHBOR_DURATION,
+                next_clip.in_point / next_clip.speed,
+            )
+        };
+        let (limiting, room) = if prev_room <= next_room {
+            (prev_clip, prev_room)
+        } else {
+            (next_clip, next_room)
+        };
+        if delta.abs() > room.max(0.0) + 1e-9 {
+            return Err(SlideError::HandleLimit {
+                neighbor_id: limiting.id.clone(),
+                max_delta: room.max(0.0).copysign(delta),
+            });
+        }
+
+        self.clips[current].start_time += delta;
+
+        let prev_clip = &mut self.clips[previous];
+        let untrimmed = prev_clip.clone();
+        prev_clip.out_point += delta * prev_clip.speed;
+        let prev_duration = prev_clip.duration();
+        prev_clip.redactions.retain(|r| r.start < prev_duration);
+        prev_clip.retarget_comments(&untrimmed);
+
+        let next_clip = &mut self.clips[next];
+        let untrimmed = next_clip.clone();
+        next_clip.start_time += delta;
+        next_clip.in_point += delta * next_clip.speed;
+        next_clip.retarget_comments(&untrimmed);
+        // Redaction times are relative to the clip start, which moved
+        next_clip.redactions = next_clip
+            .redactions
+            .iter()
+            .filter(|r| r.end > delta)
+            .map(|r| Redaction {
+                start: (r.start - delta).max(0.0),
+                end: r.end - delta,
+                ..r.clone()
+            })
+            .collect();
+
+        Ok([
+            self.clips[previous].clone(),
+            self.clips[current].clone(),
+            self.clips[next].clone(),
+        ])
+    }
+}
+
+/// Renumber `tracks`' `order` to 0..n, keeping their relative order (ties
+/// keep list order)
+pub fn normalize_track_order(tracks: &mut [Track]) {
+    let mut ranked: Vec<usize> = (0..tracks.len()).collect();
+    ranked.sort_by_key(|&index| tracks[index].order);
+    for (order, index) in ranked.into_iter().enumerate() {
+        tracks[index].order = order as u32;
+    }
+}
+
+/// Move a track to position `order` (clamped to the last), shifting the
+/// tracks in between; the order is normalized first
+pub fn move_track(tracks: &mut [Track], track_id: &str, order: u32) -> Result<(), String> {
+    normalize_track_order(tracks);
+    let from = tracks
+        .iter()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| format!("Track not found: {}", track_id))?
+        .order;
+    let to = order.min(tracks.len() as u32 - 1);
+    for track in tracks.iter_mut() {
+        if track.order == from {
+            track.order = to;
+        } else if from < to && (from + 1..=to).contains(&track.order) {
+            track.order -= 1;
+        } else if to < from && (to..from).contains(&track.order) {
+            track.order += 1;
+        }
+    }
+    Ok(())
 }
 
 #[allow(dead_code)]
@@ -91,14 +648,704 @@ impl TimelineClip {
             out_point,
             layer_order: 0,
             transform: None,
+            audio_only: false,
+            video_only: false,
+            effects: Vec::new(),
+            redactions: Vec::new(),
+            source_kind: ClipSourceKind::Media,
+            channel_mode: ChannelMode::Auto,
+            freeze_extension: None,
+            notes: None,
+            comments: Vec::new(),
+            speed: 1.0,
         }
     }
 
-    pub fn duration(&self) -> f64 {
+    /// Split at timeline `time` into two clips with fresh ids
+    ///
+    /// Both halves keep every other property (effects, flags, transform);
+    /// redactions go to the halves they overlap, comments to the half they
+    /// point into and a freeze extension to the half at its edge.
+    /// None when `time` isn't strictly inside the clip's media, so held
+    /// frames can't be split.
+    pub fn split_at(&self, time: f64) -> Option<(TimelineClip, TimelineClip)> {
+        if time <= self.media_start_time() || time >= self.media_end_time() {
+            return None;
+        }
+        let split_point = self.source_time(time);
+        let mut before = self.clone();
+        before.id = uuid::Uuid::new_v4().to_string();
+        before.out_point = split_point;
+        before.freeze_extension = self
+            .freeze_extension
+            .filter(|f| f.edge == FreezeEdge::Start);
+        before
+            .redactions
+            .retain(|r| r.start < time - self.start_time);
+        before
+            .comments
+            .retain(|c| c.time_offset < time - self.start_time);
+        before.retarget_comments(self);
+        let mut after = self.clone();
+        after.id = uuid::Uuid::new_v4().to_string();
+        after.start_time = time;
+        after.in_point = split_point;
+        after.freeze_extension = self.freeze_extension.filter(|f| f.edge == FreezeEdge::End);
+        // Redaction times are relative to the clip start, which moved
+        let shift = time - self.start_time;
+        after.redactions = self
+            .redactions
+            .iter()
+            .filter(|r| r.end > shift)
+            .map(|r| Redaction {
+                start: (r.start - shift).max(0.0),
+                end: r.end - shift,
+                ..r.clone()
+            })
+            .collect();
+        after.comments.retain(|c| c.time_offset >= shift);
+        after.retarget_comments(self);
+        Some((before, after))
+    }
+
+    /// Keep comments on the source frames they were left on after the
+    /// clip's in point, held lead or speed changed from `previous`
+    ///
+    /// Comments whose frame is no longer inside the clip are flagged
+    /// orphaned instead of removed, and recover if a later trim brings the
+    /// frame back.
+    pub fn retarget_comments(&mut self, previous: &TimelineClip) {
+        let (duration, lead) = (self.duration(), self.freeze_lead());
+        for comment in &mut self.comments {
+            let source =
+                previous.in_point + (comment.time_offset - previous.freeze_lead()) * previous.speed;
+            comment.time_offset = lead + (source - self.in_point) / self.speed;
+            comment.orphaned = comment.time_offset < -1e-9 || comment.time_offset > duration + 1e-9;
+        }
+    }
+
+    /// Change the playback rate, keeping redactions and comments on the
+    /// frames they were placed on
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), String> {
+        validate_clip_speed(speed)?;
+        let previous = self.clone();
+        let lead = self.freeze_lead();
+        let retime = |time: f64| lead + (time - lead) * previous.speed / speed;
+        self.speed = speed;
+        for redaction in &mut self.redactions {
+            redaction.start = retime(redaction.start);
+            redaction.end = retime(redaction.end);
+        }
+        self.retarget_comments(&previous);
+        Ok(())
+    }
+
+    /// Whether the clip's audio is heard (it may still have none)
+    pub fn uses_audio(&self) -> bool {
+        !self.video_only
+    }
+
+    /// Seconds the first frame is held before the media plays
+    pub fn freeze_lead(&self) -> f64 {
+        match self.freeze_extension {
+            Some(FreezeExtension {
+                edge: FreezeEdge::Start,
+                duration,
+            }) => duration,
+            _ => 0.0,
+        }
+    }
+
+    /// Seconds the last frame is held after the media ends
+    pub fn freeze_tail(&self) -> f64 {
+        match self.freeze_extension {
+            Some(FreezeExtension {
+                edge: FreezeEdge::End,
+                duration,
+            }) => duration,
+            _ => 0.0,
+        }
+    }
+
+    /// Seconds of source between `in_point` and `out_point`
+    pub fn source_duration(&self) -> f64 {
         (self.out_point - self.in_point).max(0.0)
     }
 
+    /// Timeline length of the media range at the clip's speed, without held
+    /// frames
+    pub fn media_duration(&self) -> f64 {
+        self.source_duration() / self.speed
+    }
+
+    /// Whether the media plays faster or slower than real time
+    pub fn changes_speed(&self) -> bool {
+        (self.speed - 1.0).abs() > f64::EPSILON
+    }
+
+    /// Timeline time `in_point` plays at
+    pub fn media_start_time(&self) -> f64 {
+        self.start_time + self.freeze_lead()
+    }
+
+    /// Timeline time `out_point` plays at
+    pub fn media_end_time(&self) -> f64 {
+        self.media_start_time() + self.media_duration()
+    }
+
+    /// Source timestamp showing at timeline `time`; held frames map to the
+    /// edges of the media range
+    pub fn source_time(&self, time: f64) -> f64 {
+        self.in_point
+            + ((time - self.media_start_time()) * self.speed).clamp(0.0, self.source_duration())
+    }
+
+    /// Timeline length, held frames included
+    pub fn duration(&self) -> f64 {
+        self.media_duration() + self.freeze_lead() + self.freeze_tail()
+    }
+
     pub fn end_time(&self) -> f64 {
         self.start_time + self.duration()
     }
+
+    /// Order on a track: by start time, then id, so clips starting together
+    /// sort the same way every time (and NaN can't panic a sort)
+    pub fn timeline_order(&self, other: &Self) -> Ordering {
+        self.start_time
+            .total_cmp(&other.start_time)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+
+    /// Whether the clip's times are all finite
+    pub fn has_finite_times(&self) -> bool {
+        self.start_time.is_finite() && self.in_point.is_finite() && self.out_point.is_finite()
+    }
+}
+
+/// Clip times as saved: a NaN is written as `null`, which is read back as
+/// NaN for `Project::repair_clip_times` instead of failing the whole load
+fn time_or_nan<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_volume_clamps() {
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        track.set_volume(3.5).unwrap();
+        assert_eq!(track.volume, MAX_TRACK_VOLUME);
+        track.set_volume(-1.0).unwrap();
+        assert_eq!(track.volume, 0.0);
+        track.set_volume(0.75).unwrap();
+        assert_eq!(track.volume, 0.75);
+        assert!(track.set_volume(f32::NAN).is_err());
+        assert_eq!(track.volume, 0.75);
+    }
+
+    #[test]
+    fn test_rename_and_color() {
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        track.rename("  B-roll  ").unwrap();
+        assert_eq!(track.name, "B-roll");
+        assert!(track.rename("   ").is_err());
+        assert!(track.rename(&"é".repeat(65)).is_err());
+        track.rename(&"é".repeat(64)).unwrap();
+
+        track.set_color(Some("#1a2B3c".into())).unwrap();
+        assert_eq!(track.color.as_deref(), Some("#1a2B3c"));
+        assert!(track.set_color(Some("red".into())).is_err());
+        assert!(track.set_color(Some("#12345".into())).is_err());
+        assert_eq!(track.color.as_deref(), Some("#1a2B3c"));
+        track.set_color(None).unwrap();
+        assert!(track.color.is_none());
+
+        // Tracks saved before colors existed load without one
+        let mut json = serde_json::to_value(&track).unwrap();
+        json.as_object_mut().unwrap().remove("color");
+        let loaded: Track = serde_json::from_value(json).unwrap();
+        assert!(loaded.color.is_none());
+    }
+
+    #[test]
+    fn test_track_order_stays_contiguous() {
+        let mut tracks: Vec<Track> = [0, 3, 3, 7]
+            .into_iter()
+            .enumerate()
+            .map(|(i, order)| {
+                let mut track = Track::new(format!("T{}", i), TrackType::Overlay);
+                track.id = format!("t{}", i);
+                track.order = order;
+                track
+            })
+            .collect();
+        let orders = |tracks: &[Track]| tracks.iter().map(|t| t.order).collect::<Vec<_>>();
+
+        normalize_track_order(&mut tracks);
+        assert_eq!(orders(&tracks), [0, 1, 2, 3]);
+
+        move_track(&mut tracks, "t0", 2).unwrap();
+        assert_eq!(orders(&tracks), [2, 0, 1, 3]);
+        move_track(&mut tracks, "t3", 0).unwrap();
+        assert_eq!(orders(&tracks), [3, 1, 2, 0]);
+        // Past the end means last
+        move_track(&mut tracks, "t1", 99).unwrap();
+        assert_eq!(orders(&tracks), [2, 3, 1, 0]);
+        assert!(move_track(&mut tracks, "missing", 0).is_err());
+    }
+
+    #[test]
+    fn test_effect_validation() {
+        let crop = |left, right| ClipEffect::Crop {
+            left,
+            right,
+            top: 0.0,
+            bottom: 0.0,
+        };
+        assert!(validate_effects(&[crop(0.1, 0.2), ClipEffect::FlipH]).is_ok());
+        assert!(crop(0.6, 0.4).validate().is_err());
+        assert!(crop(-0.1, 0.0).validate().is_err());
+        assert!(ClipEffect::GaussianBlur { sigma: 0.0 }.validate().is_err());
+        assert!(ClipEffect::GaussianBlur { sigma: f64::NAN }
+            .validate()
+            .is_err());
+        let eq = ClipEffect::Eq {
+            brightness: 0.1,
+            contrast: 2.5,
+            saturation: 1.0,
+        };
+        assert!(eq.validate().unwrap_err().contains("Contrast"));
+        assert!(validate_effects(&[ClipEffect::FlipV, ClipEffect::FlipV]).is_err());
+    }
+
+    #[test]
+    fn test_freeze_extension_counts_toward_duration() {
+        let mut clip = TimelineClip::new("m".into(), "t".into(), 10.0, 2.0, 6.0);
+        clip.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::End,
+            duration: 3.0,
+        });
+        assert_eq!(clip.duration(), 7.0);
+        assert_eq!(clip.end_time(), 17.0);
+        assert_eq!(clip.media_end_time(), 14.0);
+        assert_eq!(clip.source_time(16.0), 6.0);
+
+        clip.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::Start,
+            duration: 3.0,
+        });
+        assert_eq!(clip.media_start_time(), 13.0);
+        assert_eq!(clip.source_time(11.0), 2.0);
+        assert_eq!(clip.source_time(14.0), 3.0);
+
+        // Only the media splits; the held frame stays with its edge
+        assert!(clip.split_at(12.0).is_none());
+        let (before, after) = clip.split_at(15.0).unwrap();
+        assert_eq!((before.duration(), before.freeze_lead()), (5.0, 3.0));
+        assert_eq!((after.start_time, after.in_point), (15.0, 4.0));
+        assert!(after.freeze_extension.is_none());
+
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        track.clips.push(clip);
+        assert_eq!(track.duration(), 17.0);
+
+        let too_long = FreezeExtension {
+            edge: FreezeEdge::End,
+            duration: MAX_FREEZE_SECONDS + 0.5,
+        };
+        assert!(too_long.validate().is_err());
+        assert!(FreezeExtension {
+            duration: -1.0,
+            ..too_long
+        }
+        .validate()
+        .is_err());
+
+        // Clips saved before freeze extensions load without one
+        let mut json =
+            serde_json::to_value(TimelineClip::new("m".into(), "t".into(), 0.0, 0.0, 1.0)).unwrap();
+        json.as_object_mut().unwrap().remove("freeze_extension");
+        let loaded: TimelineClip = serde_json::from_value(json).unwrap();
+        assert!(loaded.freeze_extension.is_none());
+    }
+
+    #[test]
+    fn test_speed_scales_duration() {
+        // 8s of source at double speed plays in 4s
+        let mut clip = TimelineClip::new("m".into(), "t".into(), 10.0, 2.0, 10.0);
+        clip.speed = 2.0;
+        assert_eq!(clip.source_duration(), 8.0);
+        assert_eq!(clip.duration(), 4.0);
+        assert_eq!(clip.end_time(), 14.0);
+        assert_eq!(clip.source_time(11.0), 4.0);
+        assert_eq!(clip.source_time(20.0), 10.0);
+
+        // Half speed doubles it; held frames don't change with the rate
+        clip.speed = 0.5;
+        clip.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::End,
+            duration: 1.0,
+        });
+        assert_eq!(clip.duration(), 17.0);
+        assert_eq!(clip.media_end_time(), 26.0);
+        assert_eq!(clip.source_time(14.0), 4.0);
+
+        // Splits land on the source frame showing at the split time
+        let (before, after) = clip.split_at(18.0).unwrap();
+        assert_eq!((before.in_point, before.out_point), (2.0, 6.0));
+        assert_eq!(before.duration(), 8.0);
+        assert_eq!((after.in_point, after.speed), (6.0, 0.5));
+        assert_eq!(after.end_time(), 27.0);
+
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        track.clips.push(clip);
+        assert_eq!(track.duration(), 27.0);
+
+        // Clips saved before speeds load at normal speed
+        let mut json =
+            serde_json::to_value(TimelineClip::new("m".into(), "t".into(), 0.0, 0.0, 1.0)).unwrap();
+        json.as_object_mut().unwrap().remove("speed");
+        let loaded: TimelineClip = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.speed, 1.0);
+        assert!(!loaded.changes_speed());
+    }
+
+    #[test]
+    fn test_set_speed_keeps_marks_on_their_frames() {
+        let mut clip = TimelineClip::new("m".into(), "t".into(), 0.0, 0.0, 8.0);
+        clip.comments = vec![Comment::new(2.0, "a".into(), None)];
+        clip.redactions = vec![Redaction {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+            start: 4.0,
+            end: 6.0,
+            kind: RedactionKind::Box,
+        }];
+        clip.set_speed(4.0).unwrap();
+        assert_eq!(clip.duration(), 2.0);
+        assert_eq!(clip.comments[0].time_offset, 0.5);
+        assert_eq!(
+            (clip.redactions[0].start, clip.redactions[0].end),
+            (1.0, 1.5)
+        );
+
+        assert!(clip.set_speed(MIN_CLIP_SPEED).is_ok());
+        assert!(clip.set_speed(MAX_CLIP_SPEED).is_ok());
+        for speed in [0.05, 10.5, 0.0, -1.0, f64::NAN] {
+            assert!(clip.set_speed(speed).is_err(), "{}", speed);
+        }
+        assert_eq!(clip.speed, MAX_CLIP_SPEED);
+    }
+
+    #[test]
+    fn test_comments_follow_trims() {
+        // Source 2-8s at timeline 10s, comments on source frames 3s and 7s
+        let mut clip = TimelineClip::new("m".into(), "t".into(), 10.0, 2.0, 8.0);
+        clip.comments = vec![
+            Comment::new(1.0, "re-record this".into(), None),
+            Comment::new(5.0, "logo bigger".into(), Some("client".into())),
+        ];
+        let offsets = |clip: &TimelineClip| -> Vec<(f64, bool)> {
+            clip.comments
+                .iter()
+                .map(|c| (c.time_offset, c.orphaned))
+                .collect()
+        };
+
+        // Trimming the head moves both comments earlier in the clip
+        let untrimmed = clip.clone();
+        clip.in_point = 2.5;
+        clip.retarget_comments(&untrimmed);
+        assert_eq!(offsets(&clip), vec![(0.5, false), (4.5, false)]);
+
+        // Trimming past a comment orphans it instead of deleting it
+        let untrimmed = clip.clone();
+        clip.in_point = 4.0;
+        clip.out_point = 6.0;
+        clip.retarget_comments(&untrimmed);
+        assert_eq!(offsets(&clip), vec![(-1.0, true), (3.0, true)]);
+
+        // Undoing the trim brings them back on their frames
+        let trimmed = clip.clone();
+        clip.in_point = 2.0;
+        clip.out_point = 8.0;
+        clip.retarget_comments(&trimmed);
+        assert_eq!(offsets(&clip), vec![(1.0, false), (5.0, false)]);
+
+        // A held lead pushes the media, and so the comments, later
+        let unextended = clip.clone();
+        clip.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::Start,
+            duration: 2.0,
+        });
+        clip.retarget_comments(&unextended);
+        assert_eq!(offsets(&clip), vec![(3.0, false), (7.0, false)]);
+    }
+
+    #[test]
+    fn test_split_and_slide_retarget_comments() {
+        let mut clip = TimelineClip::new("m".into(), "t".into(), 10.0, 2.0, 8.0);
+        clip.comments = vec![
+            Comment::new(1.0, "a".into(), None),
+            Comment::new(5.0, "b".into(), None),
+        ];
+        let (before, after) = clip.split_at(13.0).unwrap();
+        assert_eq!(before.comments.len(), 1);
+        assert_eq!(before.comments[0].time_offset, 1.0);
+        assert_eq!(after.comments.len(), 1);
+        assert_eq!(
+            (
+                after.comments[0].text.as_str(),
+                after.comments[0].time_offset
+            ),
+            ("b", 2.0)
+        );
+
+        // Sliding the middle clip later trims the next clip's head
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        let previous = TimelineClip::new("p".into(), track.id.clone(), 0.0, 0.0, 10.0);
+        let middle = TimelineClip::new("m".into(), track.id.clone(), 10.0, 0.0, 3.0);
+        let mut next = TimelineClip::new("n".into(), track.id.clone(), 13.0, 0.0, 5.0);
+        next.comments = vec![
+            Comment::new(0.5, "cut".into(), None),
+            Comment::new(2.0, "kept".into(), None),
+        ];
+        track.clips = vec![previous, middle.clone(), next];
+        let [_, _, next] = track.slide_clip(&middle.id, 1.0, |_| Some(20.0)).unwrap();
+        let offsets: Vec<(f64, bool)> = next
+            .comments
+            .iter()
+            .map(|c| (c.time_offset, c.orphaned))
+            .collect();
+        assert_eq!(offsets, vec![(-0.5, true), (1.0, false)]);
+
+        // Clips saved before comments load without any
+        let mut json =
+            serde_json::to_value(TimelineClip::new("m".into(), "t".into(), 0.0, 0.0, 1.0)).unwrap();
+        json.as_object_mut().unwrap().remove("comments");
+        json.as_object_mut().unwrap().remove("notes");
+        let loaded: TimelineClip = serde_json::from_value(json).unwrap();
+        assert!(loaded.comments.is_empty() && loaded.notes.is_none());
+    }
+
+    #[test]
+    fn test_split_copies_effects() {
+        let mut clip = TimelineClip::new("media".into(), "track".into(), 10.0, 2.0, 8.0);
+        clip.effects = vec![ClipEffect::FlipH, ClipEffect::GaussianBlur { sigma: 4.0 }];
+        let (before, after) = clip.split_at(12.5).unwrap();
+
+        assert_eq!(
+            (before.start_time, before.in_point, before.out_point),
+            (10.0, 2.0, 4.5)
+        );
+        assert_eq!(
+            (after.start_time, after.in_point, after.out_point),
+            (12.5, 4.5, 8.0)
+        );
+        assert_eq!(before.effects, clip.effects);
+        assert_eq!(after.effects, clip.effects);
+        assert_ne!(before.id, after.id);
+        assert!(clip.split_at(10.0).is_none());
+        assert!(clip.split_at(16.0).is_none());
+    }
+
+    /// Clips back to back on one track, each with a 20s source
+    fn slide_fixture() -> Track {
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        for (i, (start, in_point, out_point)) in
+            [(0.0, 5.0, 10.0), (5.0, 2.0, 6.0), (9.0, 3.0, 8.0)]
+                .into_iter()
+                .enumerate()
+        {
+            let mut clip =
+                TimelineClip::new("media".into(), track.id.clone(), start, in_point, out_point);
+            clip.id = format!("c{}", i);
+            track.clips.push(clip);
+        }
+        track
+    }
+
+    fn points(clip: &TimelineClip) -> (f64, f64, f64) {
+        (clip.start_time, clip.in_point, clip.out_point)
+    }
+
+    #[test]
+    fn test_slide_later() {
+        let mut track = slide_fixture();
+        let [previous, clip, next] = track.slide_clip("c1", 1.5, |_| Some(20.0)).unwrap();
+
+        assert_eq!(points(&previous), (0.0, 5.0, 11.5));
+        assert_eq!(points(&clip), (6.5, 2.0, 6.0));
+        assert_eq!(points(&next), (10.5, 4.5, 8.0));
+        // The track still ends where it did
+        assert_eq!(track.duration(), 14.0);
+    }
+
+    #[test]
+    fn test_slide_earlier() {
+        let mut track = slide_fixture();
+        let [previous, clip, next] = track.slide_clip("c1", -2.0, |_| Some(20.0)).unwrap();
+
+        assert_eq!(points(&previous), (0.0, 5.0, 8.0));
+        assert_eq!(points(&clip), (3.0, 2.0, 6.0));
+        assert_eq!(points(&next), (7.0, 1.0, 8.0));
+        assert_eq!(track.duration(), 14.0);
+    }
+
+    #[test]
+    fn test_slide_keeps_held_frames() {
+        let mut track = Track::new("Main".into(), TrackType::Main);
+        // 1s of media holding its last frame 4s, then a 3s clip and a next
+        // clip with 5s of source before its in point
+        let mut previous = TimelineClip::new("media".into(), track.id.clone(), 0.0, 0.0, 1.0);
+        previous.id = "p".into();
+        previous.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::End,
+            duration: 4.0,
+        });
+        let mut middle = TimelineClip::new("media".into(), track.id.clone(), 5.0, 0.0, 3.0);
+        middle.id = "m".into();
+        let mut next = TimelineClip::new("media".into(), track.id.clone(), 8.0, 5.0, 6.0);
+        next.id = "n".into();
+        next.freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::Start,
+            duration: 2.0,
+        });
+        track.clips = vec![previous, middle, next];
+
+        // The hold doesn't count as room to trim either neighbor
+        for (delta, neighbor) in [(-4.5, "p"), (2.5, "n")] {
+            let SlideError::HandleLimit {
+                neighbor_id,
+                max_delta,
+            } = track.slide_clip("m", delta, |_| Some(20.0)).unwrap_err()
+            else {
+                panic!("expected a handle limit");
+            };
+            assert_eq!(neighbor_id, neighbor);
+            assert!((max_delta.abs() - (1.0 - MIN_SLIDE_NEIGHBOR_DURATION)).abs() < 1e-9);
+        }
+
+        let [previous, _, next] = track.slide_clip("m", -0.5, |_| Some(20.0)).unwrap();
+        assert_eq!(points(&previous), (0.0, 0.0, 0.5));
+        assert_eq!(previous.end_time(), 4.5);
+        assert_eq!(points(&next), (7.5, 4.5, 6.0));
+    }
+
+    #[test]
+    fn test_slide_handle_limits() {
+        // Later: the previous clip has 2s of source left after its out point
+        let mut track = slide_fixture();
+        let err = track.slide_clip("c1", 3.0, |_| Some(12.0)).unwrap_err();
+        assert_eq!(
+            err,
+            SlideError::HandleLimit {
+                neighbor_id: "c0".into(),
+                max_delta: 2.0
+            }
+        );
+        // Unchanged after a refused slide
+        assert_eq!(points(&track.clips[1]), (5.0, 2.0, 6.0));
+
+        // Earlier: the next clip has 3s of source before its in point
+        let err = track.slide_clip("c1", -3.5, |_| Some(20.0)).unwrap_err();
+        assert_eq!(
+            err,
+            SlideError::HandleLimit {
+                neighbor_id: "c2".into(),
+                max_delta: -3.0
+            }
+        );
+        // Later with plenty of source: the next clip keeps a minimum length
+        let SlideError::HandleLimit {
+            neighbor_id,
+            max_delta,
+        } = track.slide_clip("c1", 6.0, |_| Some(20.0)).unwrap_err()
+        else {
+            panic!("expected a handle limit");
+        };
+        assert_eq!(neighbor_id, "c2");
+        assert!((max_delta - (5.0 - MIN_SLIDE_NEIGHBOR_DURATION)).abs() < 1e-9);
+
+        assert_eq!(
+            track.slide_clip("c0", 1.0, |_| Some(20.0)).unwrap_err(),
+            SlideError::MissingNeighbor { side: "previous" }
+        );
+        assert_eq!(
+            track.slide_clip("c2", 1.0, |_| Some(20.0)).unwrap_err(),
+            SlideError::MissingNeighbor { side: "next" }
+        );
+    }
+
+    #[test]
+    fn test_split_moves_redactions() {
+        let redaction = |start, end| Redaction {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 50,
+            start,
+            end,
+            kind: RedactionKind::Blur,
+        };
+        let mut clip = TimelineClip::new("media".into(), "track".into(), 10.0, 0.0, 10.0);
+        clip.redactions = vec![
+            redaction(1.0, 2.0),
+            redaction(3.0, 6.0),
+            redaction(7.0, 9.0),
+        ];
+        let (before, after) = clip.split_at(14.0).unwrap();
+
+        assert_eq!(
+            before.redactions,
+            [redaction(1.0, 2.0), redaction(3.0, 6.0)]
+        );
+        assert_eq!(after.redactions, [redaction(0.0, 2.0), redaction(3.0, 5.0)]);
+    }
+
+    #[test]
+    fn test_redaction_validation() {
+        let region = |x, width| Redaction {
+            x,
+            y: 10,
+            width,
+            height: 20,
+            start: 0.0,
+            end: 2.0,
+            kind: RedactionKind::Box,
+        };
+        assert!(region(1800, 120).validate(1920, 1080).is_ok());
+        assert!(region(1800, 121).validate(1920, 1080).is_err());
+        assert!(region(0, 0).validate(1920, 1080).is_err());
+        let backwards = Redaction {
+            start: 3.0,
+            ..region(0, 10)
+        };
+        assert!(backwards.validate(1920, 1080).is_err());
+    }
+
+    #[test]
+    fn test_effects_round_trip() {
+        let mut clip = TimelineClip::new("media".into(), "track".into(), 0.0, 0.0, 5.0);
+        clip.effects = vec![ClipEffect::Eq {
+            brightness: -0.2,
+            contrast: 1.1,
+            saturation: 0.5,
+        }];
+        let json = serde_json::to_value(&clip).unwrap();
+        assert_eq!(json["effects"][0]["type"], "eq");
+        let loaded: TimelineClip = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.effects, clip.effects);
+
+        // Projects saved before effects existed load without any
+        let mut old = serde_json::to_value(&clip).unwrap();
+        old.as_object_mut().unwrap().remove("effects");
+        let loaded: TimelineClip = serde_json::from_value(old).unwrap();
+        assert!(loaded.effects.is_empty());
+    }
 }
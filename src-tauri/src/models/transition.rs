@@ -0,0 +1,297 @@
+use super::timeline::{TimelineClip, Track};
+use serde::{Deserialize, Serialize};
+
+/// Shortest transition, in seconds
+pub const MIN_TRANSITION_DURATION: f64 = 0.1;
+
+/// Longest transition, in seconds
+pub const MAX_TRANSITION_DURATION: f64 = 10.0;
+
+/// Clips this close (seconds) meet
+const ADJACENT_EPSILON: f64 = 1e-3;
+
+/// How one clip turns into the next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionKind {
+    /// The picture dissolves and the sound fades across
+    #[default]
+    Crossfade,
+}
+
+impl TransitionKind {
+    /// FFmpeg `xfade` transition rendering it
+    pub fn xfade_name(self) -> &'static str {
+        match self {
+            TransitionKind::Crossfade => "fade",
+        }
+    }
+}
+
+/// A transition where two adjacent clips of a track meet
+///
+/// The clips overlap by `duration`: the later one starts that much earlier
+/// in the output, its first `duration` seconds playing over the last of
+/// the earlier one, so the track plays `duration` shorter than its clips
+/// add up to. The clips keep their places on the timeline; a transition
+/// whose clips no longer meet is dropped (see `Track::prune_transitions`).
+/// Export ignores one a change left behind anyway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transition {
+    pub id: String,
+    #[serde(default)]
+    pub kind: TransitionKind,
+    /// Overlap in seconds
+    pub duration: f64,
+    /// Clip ending where the transition is
+    pub from_clip_id: String,
+    /// Clip starting there
+    pub to_clip_id: String,
+}
+
+impl Transition {
+    pub fn new(
+        kind: TransitionKind,
+        duration: f64,
+        from_clip_id: String,
+        to_clip_id: String,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            duration,
+            from_clip_id,
+            to_clip_id,
+        }
+    }
+}
+
+impl Track {
+    /// The transition into each of `clips` from the clip before it, None at
+    /// a cut
+    ///
+    /// `clips` are the track's clips in timeline order, possibly trimmed
+    /// (an export range). A transition counts where its clips meet and
+    /// each still has the footage to overlap, after any transition at its
+    /// other end.
+    pub fn transitions_between(&self, clips: &[TimelineClip]) -> Vec<Option<&Transition>> {
+        let mut transitions: Vec<Option<&Transition>> = vec![None; clips.len()];
+        for i in 1..clips.len() {
+            let (from, to) = (&clips[i - 1], &clips[i]);
+            let Some(transition) = self
+                .transitions
+                .iter()
+                .find(|t| t.from_clip_id == from.id && t.to_clip_id == to.id)
+            else {
+                continue;
+            };
+            let used = transitions[i - 1].map_or(0.0, |t| t.duration);
+            let fits = (to.start_time - from.end_time()).abs() < ADJACENT_EPSILON
+                && overlap_footage(from, used) >= transition.duration - ADJACENT_EPSILON
+                && overlap_footage(to, 0.0) >= transition.duration - ADJACENT_EPSILON;
+            if fits {
+                transitions[i] = Some(transition);
+            }
+        }
+        transitions
+    }
+
+    /// Seconds the track's transitions take off its length
+    pub fn transition_overlap(&self) -> f64 {
+        let mut clips = self.clips.clone();
+        clips.sort_by(|a, b| a.timeline_order(b));
+        self.transitions_between(&clips)
+            .into_iter()
+            .flatten()
+            .map(|t| t.duration)
+            .sum()
+    }
+
+    /// Drop transitions whose clips left the track or no longer meet
+    pub fn prune_transitions(&mut self) {
+        let clips = &self.clips;
+        let meet = |t: &Transition| {
+            let from = clips.iter().find(|c| c.id == t.from_clip_id);
+            let to = clips.iter().find(|c| c.id == t.to_clip_id);
+            matches!((from, to), (Some(from), Some(to))
+                if (to.start_time - from.end_time()).abs() < ADJACENT_EPSILON)
+        };
+        self.transitions.retain(meet);
+    }
+
+    /// Hand a split clip's transitions to its halves: the one into it goes
+    /// into `before`, the one out of it out of `after`
+    pub fn split_transitions(&mut self, clip_id: &str, before: &str, after: &str) {
+        for transition in &mut self.transitions {
+            if transition.to_clip_id == clip_id {
+                transition.to_clip_id = before.to_string();
+            }
+            if transition.from_clip_id == clip_id {
+                transition.from_clip_id = after.to_string();
+            }
+        }
+    }
+
+    /// Check a transition from one clip of the track into the next
+    ///
+    /// The clips must meet, with no frame held where they do, and have the
+    /// footage for the overlap: the earlier clip's last `duration` seconds
+    /// can't also be part of a transition into it.
+    pub fn check_transition(&self, transition: &Transition) -> Result<(), String> {
+        if !transition.duration.is_finite()
+            || !(MIN_TRANSITION_DURATION..=MAX_TRANSITION_DURATION).contains(&transition.duration)
+        {
+            return Err(format!(
+                "Transitions must be between {} and {} seconds long",
+                MIN_TRANSITION_DURATION, MAX_TRANSITION_DURATION
+            ));
+        }
+        let clip = |id: &str| {
+            self.clips
+                .iter()
+                .find(|c| c.id == id)
+                .ok_or_else(|| format!("Clip {} is not on track {}", id, self.name))
+        };
+        let (from, to) = (
+            clip(&transition.from_clip_id)?,
+            clip(&transition.to_clip_id)?,
+        );
+        if (to.start_time - from.end_time()).abs() >= ADJACENT_EPSILON {
+            return Err("Transitions go where one clip ends and the next starts".to_string());
+        }
+        if from.freeze_tail() > 0.0 || to.freeze_lead() > 0.0 {
+            return Err(
+                "Clips holding a frame where they meet can't have a transition".to_string(),
+            );
+        }
+
+        // A transition at the earlier clip's start uses its first seconds
+        let used = self
+            .transitions
+            .iter()
+            .filter(|t| t.to_clip_id == from.id && t.id != transition.id)
+            .map(|t| t.duration)
+            .sum();
+        for (clip, used) in [(from, used), (to, 0.0)] {
+            let available = overlap_footage(clip, used);
+            if available < transition.duration - ADJACENT_EPSILON {
+                return Err(format!(
+                    "Clip {} has {:.2}s of footage for the transition, not {:.2}s; trim it longer or shorten the transition",
+                    clip.id, available, transition.duration
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Seconds of `clip`'s media a transition can overlap, after `used`
+/// seconds taken by a transition at its other end
+fn overlap_footage(clip: &TimelineClip, used: f64) -> f64 {
+    (clip.media_duration() - used).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::{FreezeEdge, FreezeExtension, TrackType};
+
+    /// Main track of three 4s clips, a 0-4, b 4-8 and c 8-12
+    fn track() -> Track {
+        let mut track = Track::new("Main".to_string(), TrackType::Main);
+        for (id, start) in [("a", 0.0), ("b", 4.0), ("c", 8.0)] {
+            let mut clip =
+                TimelineClip::new("media".to_string(), track.id.clone(), start, 10.0, 14.0);
+            clip.id = id.to_string();
+            track.clips.push(clip);
+        }
+        track
+    }
+
+    fn crossfade(duration: f64, from: &str, to: &str) -> Transition {
+        Transition::new(
+            TransitionKind::Crossfade,
+            duration,
+            from.to_string(),
+            to.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_check_transition() {
+        let mut track = track();
+        assert!(track.check_transition(&crossfade(1.0, "a", "b")).is_ok());
+        // Not adjacent, out of range, or not on the track
+        assert!(track.check_transition(&crossfade(1.0, "a", "c")).is_err());
+        assert!(track.check_transition(&crossfade(0.0, "a", "b")).is_err());
+        assert!(track
+            .check_transition(&crossfade(f64::NAN, "a", "b"))
+            .is_err());
+        assert!(track.check_transition(&crossfade(1.0, "a", "x")).is_err());
+        // More than a clip's footage
+        assert!(track.check_transition(&crossfade(4.5, "a", "b")).is_err());
+
+        // b's first 3s go to the transition from a, leaving 1s for c
+        track.transitions.push(crossfade(3.0, "a", "b"));
+        assert!(track.check_transition(&crossfade(1.0, "b", "c")).is_ok());
+        assert!(track.check_transition(&crossfade(1.5, "b", "c")).is_err());
+
+        // A held frame where the clips meet
+        track.clips[2].freeze_extension = Some(FreezeExtension {
+            edge: FreezeEdge::Start,
+            duration: 1.0,
+        });
+        track.clips[2].start_time = 8.0;
+        assert!(track.check_transition(&crossfade(0.5, "b", "c")).is_err());
+    }
+
+    #[test]
+    fn test_transition_overlap() {
+        let mut track = track();
+        assert_eq!(track.transition_overlap(), 0.0);
+        track.transitions.push(crossfade(1.0, "a", "b"));
+        track.transitions.push(crossfade(0.5, "b", "c"));
+        assert!((track.transition_overlap() - 1.5).abs() < 1e-9);
+
+        // Clips moved apart no longer transition
+        track.clips[2].start_time = 9.0;
+        assert!((track.transition_overlap() - 1.0).abs() < 1e-9);
+        let transitions = track.transitions_between(&track.clips);
+        assert_eq!(transitions[0], None);
+        assert_eq!(transitions[1].map(|t| t.duration), Some(1.0));
+        assert_eq!(transitions[2], None);
+    }
+
+    #[test]
+    fn test_prune_transitions() {
+        let mut track = track();
+        track.transitions.push(crossfade(1.0, "a", "b"));
+        track.transitions.push(crossfade(0.5, "b", "c"));
+        track.transitions.push(crossfade(0.5, "c", "gone"));
+
+        // c moved away from b
+        track.clips[2].start_time = 9.0;
+        track.prune_transitions();
+        let kept: Vec<_> = track.transitions.iter().map(|t| &t.to_clip_id).collect();
+        assert_eq!(kept, ["b"]);
+    }
+
+    #[test]
+    fn test_split_transitions() {
+        let mut track = track();
+        track.transitions.push(crossfade(1.0, "a", "b"));
+        track.transitions.push(crossfade(0.5, "b", "c"));
+
+        // b split at 6s into b1 (4-6) and b2 (6-8)
+        let (b1, b2) = track.clips[1].split_at(6.0).unwrap();
+        track.split_transitions("b", &b1.id, &b2.id);
+        track.clips.splice(1..=1, [b1.clone(), b2.clone()]);
+        track.prune_transitions();
+        let pairs: Vec<_> = track
+            .transitions
+            .iter()
+            .map(|t| (t.from_clip_id.as_str(), t.to_clip_id.as_str()))
+            .collect();
+        assert_eq!(pairs, [("a", b1.id.as_str()), (b2.id.as_str(), "c")]);
+    }
+}
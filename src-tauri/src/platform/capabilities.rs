@@ -0,0 +1,152 @@
+// Capture device frame rate capabilities
+// Parses what FFmpeg reports about a device's modes (avfoundation on macOS,
+// DirectShow on Windows) into the recording frame rates the device can
+// deliver, and picks a fallback when the requested rate isn't one of them.
+
+use crate::models::recording::RECORDING_FPS;
+
+/// Frame rate range of one device mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FpsRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FpsRange {
+    fn contains(&self, fps: u32) -> bool {
+        // Devices report rates like 29.97 or 30.0003 for 30
+        let fps = fps as f64;
+        fps >= self.min - 0.5 && fps <= self.max + 0.5
+    }
+}
+
+/// Modes avfoundation lists after rejecting a frame rate:
+///
+/// `[avfoundation @ 0x...]   1280x720@[1.000000 30.000000]fps`
+pub fn parse_avfoundation_modes(stderr: &str) -> Vec<FpsRange> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let start = line.find("@[")? + 2;
+            let end = start + line[start..].find("]fps")?;
+            let mut bounds = line[start..end].split_whitespace();
+            let min = bounds.next()?.parse().ok()?;
+            let max = bounds.next().map_or(Some(min), |max| max.parse().ok())?;
+            Some(FpsRange { min, max })
+        })
+        .collect()
+}
+
+/// Modes from `-f dshow -list_options true`:
+///
+/// `[dshow @ ...]   vcodec=mjpeg  min s=1280x720 fps=5 max s=1280x720 fps=60`
+pub fn parse_dshow_options(stderr: &str) -> Vec<FpsRange> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let (min_part, max_part) = line.split_once(" max ")?;
+            let fps = |part: &str| -> Option<f64> {
+                part.split_whitespace()
+                    .find_map(|token| token.strip_prefix("fps="))?
+                    .parse()
+                    .ok()
+            };
+            Some(FpsRange {
+                min: fps(min_part.rsplit_once(" min ")?.1)?,
+                max: fps(max_part)?,
+            })
+        })
+        .collect()
+}
+
+/// Recording frame rates covered by any of `modes`
+///
+/// Empty when nothing could be parsed, which means "unknown".
+pub fn supported_rates(modes: &[FpsRange]) -> Vec<u32> {
+    RECORDING_FPS
+        .into_iter()
+        .filter(|fps| modes.iter().any(|mode| mode.contains(*fps)))
+        .collect()
+}
+
+/// Frame rate to record at given what each selected source supports
+///
+/// A source with an empty list is unknown and accepts any rate. When the
+/// request isn't supported by all sources, the nearest common rate is used
+/// (the lower one on a tie); None if the sources share no rate.
+pub fn resolve_fps(requested: u32, sources: &[&[u32]]) -> Option<u32> {
+    let supports = |fps: u32| sources.iter().all(|s| s.is_empty() || s.contains(&fps));
+    if supports(requested) {
+        return Some(requested);
+    }
+    RECORDING_FPS
+        .into_iter()
+        .filter(|fps| supports(*fps))
+        .min_by_key(|fps| (fps.abs_diff(requested), *fps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// FaceTime camera asked for 60fps
+    const AVFOUNDATION_OUTPUT: &str = "\
+[avfoundation @ 0x7f8b1c004a00] Selected framerate (60.000000) is not supported by the device.
+[avfoundation @ 0x7f8b1c004a00] Supported modes:
+[avfoundation @ 0x7f8b1c004a00]   1280x720@[1.000000 30.000000]fps
+[avfoundation @ 0x7f8b1c004a00]   640x480@[1.000000 30.000000]fps
+[avfoundation @ 0x7f8b1c004a00]   1920x1080@[1.000000 24.000000]fps
+0: Input/output error";
+
+    const DSHOW_OUTPUT: &str = "\
+[dshow @ 000001c8e5a1e340] DirectShow video device options (from video devices)
+[dshow @ 000001c8e5a1e340]  Pin \"Capture\" (alternative pin name \"0\")
+[dshow @ 000001c8e5a1e340]   pixel_format=yuyv422  min s=640x480 fps=5 max s=640x480 fps=30.0003
+[dshow @ 000001c8e5a1e340]   pixel_format=yuyv422  min s=1280x720 fps=5 max s=1280x720 fps=10
+[dshow @ 000001c8e5a1e340]   vcodec=mjpeg  min s=1280x720 fps=5 max s=1280x720 fps=30
+video=Integrated Camera: Immediate exit requested";
+
+    #[test]
+    fn test_parse_avfoundation_modes() {
+        let modes = parse_avfoundation_modes(AVFOUNDATION_OUTPUT);
+        assert_eq!(modes.len(), 3);
+        assert_eq!(
+            modes[0],
+            FpsRange {
+                min: 1.0,
+                max: 30.0
+            }
+        );
+        assert_eq!(supported_rates(&modes), [15, 24, 30]);
+    }
+
+    #[test]
+    fn test_parse_dshow_options() {
+        let modes = parse_dshow_options(DSHOW_OUTPUT);
+        assert_eq!(modes.len(), 3);
+        assert_eq!(
+            modes[0],
+            FpsRange {
+                min: 5.0,
+                max: 30.0003
+            }
+        );
+        assert_eq!(supported_rates(&modes), [15, 24, 30]);
+
+        let high_speed = "[dshow @ 0]   vcodec=mjpeg  min s=1280x720 fps=30 max s=1280x720 fps=60";
+        assert_eq!(supported_rates(&parse_dshow_options(high_speed)), [30, 60]);
+        assert!(supported_rates(&parse_dshow_options("no modes")).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_fps_falls_back_to_nearest_common_rate() {
+        let camera: &[u32] = &[15, 24, 30];
+        let screen: &[u32] = &[15, 24, 30, 60];
+        assert_eq!(resolve_fps(30, &[camera, screen]), Some(30));
+        assert_eq!(resolve_fps(60, &[camera, screen]), Some(30));
+        assert_eq!(resolve_fps(60, &[screen, &[]]), Some(60));
+        // 24 and 30 are both one step from 27; ties pick the lower rate
+        assert_eq!(resolve_fps(27, &[&[24, 30]]), Some(24));
+        assert_eq!(resolve_fps(30, &[&[15], &[60]]), None);
+    }
+}
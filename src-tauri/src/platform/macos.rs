@@ -1,15 +1,19 @@
 // macOS-specific screen recording implementation using AVFoundation and ScreenCaptureKit
 
+use crate::ffmpeg::locate::ffmpeg_path;
 use crate::models::recording::{
-    PermissionResult, PermissionStatus, RecordingSource, RecordingSources, ScreenSource,
-    WindowSource,
+    CameraOptions, PermissionResult, PermissionStatus, RecordingSettings, RecordingSource,
+    RecordingSources, ScreenSource, WindowSource,
 };
+use crate::platform::capabilities::{parse_avfoundation_modes, supported_rates};
+use crate::platform::process::{self, ProcessClass, SupervisedChild};
+use crate::platform::recorder::{CaptureBackend, RecordingCommandPlan, RecordingRequest};
 use std::collections::HashMap;
-use std::process::{Child, Command};
+use std::process::Command;
 use std::sync::{Arc, Mutex};
 
 lazy_static::lazy_static! {
-    static ref ACTIVE_RECORDINGS: Arc<Mutex<HashMap<String, Child>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref ACTIVE_RECORDINGS: Arc<Mutex<HashMap<String, SupervisedChild>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
 /// Request screen recording permissions on macOS
@@ -83,8 +87,10 @@ fn list_screens() -> Result<Vec<ScreenSource>, String> {
     // For MVP, return screen capture device
     // Note: The actual device index depends on how many cameras are connected
     // We use "Capture screen 0" which is more reliable than numeric index
+    let id = "Capture screen 0".to_string(); // Use explicit screen capture name
     Ok(vec![ScreenSource {
-        id: "Capture screen 0".to_string(), // Use explicit screen capture name
+        supported_fps: probe_supported_fps(&id),
+        id,
         name: "Main Display".to_string(),
         resolution: "1920x1080".to_string(), // Would be parsed from system_profiler output
     }])
@@ -114,6 +120,7 @@ fn list_cameras() -> Result<Vec<RecordingSource>, String> {
         Ok(vec![RecordingSource {
             id: "0".to_string(),
             name: "FaceTime HD Camera".to_string(),
+            supported_fps: probe_supported_fps("0"),
         }])
     } else {
         Ok(vec![])
@@ -122,7 +129,7 @@ fn list_cameras() -> Result<Vec<RecordingSource>, String> {
 
 fn list_microphones() -> Result<Vec<RecordingSource>, String> {
     // Use FFmpeg to list audio devices
-    let output = Command::new("ffmpeg")
+    let output = Command::new(ffmpeg_path())
         .args(["-f", "avfoundation", "-list_devices", "true", "-i", ""])
         .output()
         .map_err(|e| format!("Failed to list microphones: {}", e))?;
@@ -155,6 +162,7 @@ fn list_microphones() -> Result<Vec<RecordingSource>, String> {
                     microphones.push(RecordingSource {
                         id: device_id.to_string(),
                         name: device_name.to_string(),
+                        supported_fps: Vec::new(),
                     });
                 }
             }
@@ -166,12 +174,40 @@ fn list_microphones() -> Result<Vec<RecordingSource>, String> {
         microphones.push(RecordingSource {
             id: "0".to_string(),
             name: "Default Microphone".to_string(),
+            supported_fps: Vec::new(),
         });
     }
 
     Ok(microphones)
 }
 
+/// Frame rates an avfoundation video device supports
+///
+/// Opening the device at a rate no device offers makes avfoundation exit
+/// listing its supported modes, which are parsed from stderr. A device
+/// that hangs is killed at the probe timeout and its rates left unknown.
+fn probe_supported_fps(device: &str) -> Vec<u32> {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args([
+        "-hide_banner",
+        "-f",
+        "avfoundation",
+        "-framerate",
+        "10000",
+        "-i",
+        &format!("{}:none", device),
+    ]);
+    match tauri::async_runtime::block_on(process::output(cmd, ProcessClass::Probe)) {
+        Ok(output) => supported_rates(&parse_avfoundation_modes(&String::from_utf8_lossy(
+            &output.stderr,
+        ))),
+        Err(e) => {
+            log::warn!("Could not probe frame rates of device {}: {}", device, e);
+            Vec::new()
+        }
+    }
+}
+
 /// Start recording using FFmpeg with avfoundation input on macOS
 #[allow(clippy::too_many_arguments)]
 pub fn start_recording(
@@ -181,133 +217,25 @@ pub fn start_recording(
     camera_source: Option<String>,
     audio_sources: Vec<String>,
     microphone_device_id: Option<String>,
-    resolution: String,
-    fps: u32,
+    settings: RecordingSettings,
+    camera_options: CameraOptions,
 ) -> Result<(), String> {
-    let mut ffmpeg_args = vec!["-y".to_string()]; // Overwrite output file
-
-    // Determine input sources
-    let has_screen = screen_source.is_some();
-    let has_camera = camera_source.is_some();
-    let has_microphone =
-        !audio_sources.is_empty() && audio_sources.contains(&"microphone".to_string());
-
-    // Add thread queue size globally to prevent drops
-    if has_microphone {
-        ffmpeg_args.extend_from_slice(&["-thread_queue_size".to_string(), "1024".to_string()]);
-    }
-
-    if has_screen {
-        // Screen capture using avfoundation
-        let screen_idx = screen_source.unwrap_or_else(|| "Capture screen 0".to_string());
-
-        // Build input: screen with or without microphone audio
-        let input = if has_microphone {
-            // Screen + microphone: "screenName:audioIndex"
-            let audio_idx = microphone_device_id.as_deref().unwrap_or("0");
-            format!("{}:{}", screen_idx, audio_idx)
-        } else {
-            // Screen only (no audio)
-            screen_idx.clone()
-        };
-
-        ffmpeg_args.extend_from_slice(&[
-            "-f".to_string(),
-            "avfoundation".to_string(),
-            "-capture_cursor".to_string(),
-            "1".to_string(),
-            "-r".to_string(),
-            fps.to_string(),
-            "-i".to_string(),
-            input,
-        ]);
-    }
-
-    if has_camera {
-        // Camera capture using avfoundation
-        let camera_idx = camera_source.unwrap_or_else(|| "0".to_string());
-
-        // Build input: camera with or without microphone audio
-        let camera_input = if has_microphone && !has_screen {
-            // Camera + microphone (only if not already captured with screen)
-            let audio_idx = microphone_device_id.as_deref().unwrap_or("0");
-            format!("{}:{}", camera_idx, audio_idx)
-        } else {
-            // Camera only (audio already captured with screen, or no audio)
-            camera_idx.clone()
-        };
-
-        ffmpeg_args.extend_from_slice(&[
-            "-f".to_string(),
-            "avfoundation".to_string(),
-            "-r".to_string(),
-            fps.to_string(),
-            "-i".to_string(),
-            camera_input,
-        ]);
-    }
-
-    // If we have both screen and camera, create picture-in-picture overlay
-    if has_screen && has_camera {
-        // Picture-in-picture overlay: webcam in bottom-left corner
-        // [1:v] is the camera input (second input), [0:v] is the screen (first input)
-        // Scale webcam to 30% of screen size, adjust brightness/contrast, and overlay in bottom-left with 20px padding
-        // eq filter: brightness=0.06 (slightly brighter), contrast=1.1 (slightly more contrast)
-        let filter = "[1:v]scale=iw*0.30:ih*0.30,eq=brightness=0.06:contrast=1.1[cam];[0:v][cam]overlay=20:main_h-overlay_h-20";
-
-        ffmpeg_args.extend_from_slice(&["-filter_complex".to_string(), filter.to_string()]);
-    }
-
-    // Video codec settings - web-compatible H.264
-    ffmpeg_args.extend_from_slice(&[
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "ultrafast".to_string(),
-        "-crf".to_string(),
-        "23".to_string(),
-        "-pix_fmt".to_string(),
-        "yuv420p".to_string(), // Critical: ensures web/QuickTime compatibility
-    ]);
-
-    // Audio codec settings (if microphone is enabled)
-    if has_microphone {
-        ffmpeg_args.extend_from_slice(&[
-            "-c:a".to_string(),
-            "aac".to_string(),
-            "-b:a".to_string(),
-            "192k".to_string(),
-            "-ar".to_string(),
-            "48000".to_string(), // 48kHz sample rate
-        ]);
-    }
-
-    // Output resolution (if specified and different from source)
-    // Note: Don't apply this when using filter_complex as it's handled in the filter
-    if resolution != "source" && !(has_screen && has_camera) {
-        let parts: Vec<&str> = resolution.split('x').collect();
-        if parts.len() == 2 {
-            ffmpeg_args.extend_from_slice(&["-s".to_string(), resolution.clone()]);
-        }
-    }
-
-    // MP4-specific flags for proper file structure
-    ffmpeg_args.extend_from_slice(&[
-        "-movflags".to_string(),
-        "+faststart".to_string(), // Enable progressive playback and proper MP4 structure
-    ]);
-
-    // Output file
-    ffmpeg_args.push(output_path.clone());
+    let request = RecordingRequest::new(
+        screen_source,
+        camera_source,
+        &audio_sources,
+        microphone_device_id,
+        settings,
+        camera_options,
+    );
+    let plan = RecordingCommandPlan::new(CaptureBackend::AvFoundation, &request);
 
     // Start FFmpeg process with stdin pipe for graceful shutdown
-    let child = Command::new("ffmpeg")
-        .args(&ffmpeg_args)
-        .stdin(std::process::Stdio::piped()) // Enable stdin for 'q' command
+    let mut cmd = plan.command(&output_path).build();
+    cmd.stdin(std::process::Stdio::piped()) // Enable stdin for 'q' command
         .stdout(std::process::Stdio::null()) // Suppress stdout
-        .stderr(std::process::Stdio::piped()) // Capture progress/errors
-        .spawn()
-        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+        .stderr(std::process::Stdio::piped()); // Capture progress/errors
+    let child = process::spawn(cmd)?;
 
     // Store the process handle
     let mut recordings = ACTIVE_RECORDINGS.lock().unwrap();
@@ -345,7 +273,7 @@ pub fn stop_recording(session_id: String) -> Result<(), String> {
                     // Still running
                     if start.elapsed() > timeout {
                         // Timeout - force kill as last resort
-                        let _ = child.kill();
+                        process::kill_tree(child.id());
                         child
                             .wait()
                             .map_err(|e| format!("Failed to wait for FFmpeg: {}", e))?;
@@ -365,12 +293,27 @@ pub fn stop_recording(session_id: String) -> Result<(), String> {
 }
 
 /// Check if a recording is still active
-#[allow(dead_code)]
 pub fn is_recording_active(session_id: &str) -> bool {
     let recordings = ACTIVE_RECORDINGS.lock().unwrap();
     recordings.contains_key(session_id)
 }
 
+/// If the recording's FFmpeg process exited on its own, forget it and return its stderr
+pub fn take_exited_recording(session_id: &str) -> Option<String> {
+    let mut recordings = ACTIVE_RECORDINGS.lock().unwrap();
+    let exited = matches!(recordings.get_mut(session_id)?.try_wait(), Ok(Some(_)));
+    if !exited {
+        return None;
+    }
+    let mut child = recordings.remove(session_id)?;
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        use std::io::Read;
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    Some(stderr)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,9 @@
 // Platform-specific recording implementations
 
+pub mod capabilities;
+pub mod process;
+pub mod recorder;
+
 #[cfg(target_os = "macos")]
 pub mod macos;
 
@@ -21,7 +25,9 @@ mod platform_impl {
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 mod platform_impl {
-    use crate::models::recording::{PermissionResult, PermissionStatus, RecordingSources};
+    use crate::models::recording::{
+        CameraOptions, PermissionResult, PermissionStatus, RecordingSettings, RecordingSources,
+    };
 
     pub fn request_permissions(_permissions: Vec<String>) -> Result<PermissionResult, String> {
         Err("Recording not supported on this platform".to_string())
@@ -31,14 +37,16 @@ mod platform_impl {
         Err("Recording not supported on this platform".to_string())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn start_recording(
         _session_id: String,
         _output_path: String,
         _screen_source: Option<String>,
         _camera_source: Option<String>,
         _audio_sources: Vec<String>,
-        _resolution: String,
-        _fps: u32,
+        _microphone_device_id: Option<String>,
+        _settings: RecordingSettings,
+        _camera_options: CameraOptions,
     ) -> Result<(), String> {
         Err("Recording not supported on this platform".to_string())
     }
@@ -46,4 +54,8 @@ mod platform_impl {
     pub fn stop_recording(_session_id: String) -> Result<(), String> {
         Err("Recording not supported on this platform".to_string())
     }
+
+    pub fn is_recording_active(_session_id: &str) -> bool {
+        false
+    }
 }
@@ -0,0 +1,493 @@
+// Supervision of spawned child processes
+// Every external tool (ffmpeg, ffprobe, whisper) runs in its own process group
+// and is recorded in a global registry, so it can be killed together with its
+// descendants on timeout, on cancellation, or when the app exits.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    /// Live supervised children: pid -> program name
+    static ref REGISTRY: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+}
+
+/// Kind of work a child process does, which decides how long it may run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessClass {
+    /// ffprobe metadata and keyframe queries
+    Probe,
+    /// Single frames and sprite sheets
+    Thumbnail,
+    /// WAV extraction for transcription
+    AudioExtraction,
+    /// Preview frames, segments and audio mixes
+    Preview,
+    Proxy,
+    Transcription,
+    /// curl transfers, which curl itself ends when they stall
+    Download,
+}
+
+impl ProcessClass {
+    /// Wall-clock limit after which the child is killed, if any
+    pub fn timeout(self) -> Option<Duration> {
+        let seconds = match self {
+            ProcessClass::Probe => 30,
+            ProcessClass::Thumbnail => 60,
+            ProcessClass::AudioExtraction | ProcessClass::Preview => 10 * 60,
+            ProcessClass::Proxy => 3 * 60 * 60,
+            ProcessClass::Transcription => 2 * 60 * 60,
+            ProcessClass::Download => return None,
+        };
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+/// Failure to run a supervised child to completion
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessError {
+    Spawn {
+        program: String,
+        message: String,
+    },
+    /// Killed after exceeding its class timeout
+    Timeout {
+        program: String,
+        seconds: u64,
+    },
+    Io {
+        program: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Spawn { program, message } => {
+                write!(f, "Failed to execute {}: {}", program, message)
+            }
+            ProcessError::Timeout { program, seconds } => {
+                write!(f, "{} did not finish within {} seconds", program, seconds)
+            }
+            ProcessError::Io { program, message } => {
+                write!(f, "Failed to wait for {}: {}", program, message)
+            }
+        }
+    }
+}
+
+impl From<ProcessError> for String {
+    fn from(error: ProcessError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Registry entry removed when dropped (after the child has been reaped)
+pub struct Registration {
+    pid: u32,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        REGISTRY.lock().unwrap().remove(&self.pid);
+    }
+}
+
+/// Record a spawned child so it is killed when the app exits
+pub fn register(pid: u32, program: &str) -> Registration {
+    REGISTRY.lock().unwrap().insert(pid, program.to_string());
+    Registration { pid }
+}
+
+/// Number of supervised children still running
+#[cfg(test)]
+fn registered_count() -> usize {
+    REGISTRY.lock().unwrap().len()
+}
+
+/// Kill every registered child and its descendants; returns how many were killed
+pub fn drain_registry() -> usize {
+    let children: Vec<(u32, String)> = REGISTRY.lock().unwrap().drain().collect();
+    for (pid, program) in &children {
+        log::info!("Killing {} (pid {}) on exit", program, pid);
+        kill_tree(*pid);
+    }
+    children.len()
+}
+
+/// Start the child in its own process group so `kill_tree` reaches its descendants
+pub fn own_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Forcefully kill a supervised child and everything it spawned
+pub fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        // Children started by `own_process_group` lead a group with their pid
+        // SAFETY: kill(2) has no memory-safety preconditions
+        unsafe {
+            if libc::kill(-(pid as libc::pid_t), libc::SIGKILL) != 0 {
+                libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// A long-running child (recorder) that stays registered until dropped
+pub struct SupervisedChild {
+    child: Child,
+    _registration: Registration,
+}
+
+impl Deref for SupervisedChild {
+    type Target = Child;
+
+    fn deref(&self) -> &Child {
+        &self.child
+    }
+}
+
+impl DerefMut for SupervisedChild {
+    fn deref_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+}
+
+/// Spawn a child in its own process group and register it
+pub fn spawn(mut cmd: Command) -> Result<SupervisedChild, ProcessError> {
+    let program = program_name(&cmd);
+    own_process_group(&mut cmd);
+    log::info!("Starting {}", command_line(&cmd));
+    let child = cmd.spawn().map_err(|e| spawn_error(&program, e))?;
+    let registration = register(child.id(), &program);
+    Ok(SupervisedChild {
+        child,
+        _registration: registration,
+    })
+}
+
+/// Run a command to completion, capturing output, within its class timeout
+pub async fn output(cmd: Command, class: ProcessClass) -> Result<Output, ProcessError> {
+    output_with_timeout(cmd, class.timeout()).await
+}
+
+/// Run a command to completion, capturing output, killing it after `timeout`
+pub async fn output_with_timeout(
+    cmd: Command,
+    timeout: Option<Duration>,
+) -> Result<Output, ProcessError> {
+    run_to_completion(cmd, timeout, |_| {}).await
+}
+
+/// Like `output`, also passing each stderr line to `on_line` as it is printed
+///
+/// FFmpeg ends its stats lines with a carriage return, so both `\r` and
+/// `\n` end a line. The full stderr is still returned.
+pub async fn output_with_progress(
+    cmd: Command,
+    class: ProcessClass,
+    on_line: impl FnMut(&str),
+) -> Result<Output, ProcessError> {
+    run_to_completion(cmd, class.timeout(), on_line).await
+}
+
+async fn run_to_completion(
+    mut cmd: Command,
+    timeout: Option<Duration>,
+    mut on_line: impl FnMut(&str),
+) -> Result<Output, ProcessError> {
+    use tokio::io::AsyncReadExt;
+
+    let program = program_name(&cmd);
+    own_process_group(&mut cmd);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    log::info!("Running {}", command_line(&cmd));
+
+    let mut cmd = tokio::process::Command::from(cmd);
+    cmd.kill_on_drop(true);
+    let started = Instant::now();
+    let mut child = cmd.spawn().map_err(|e| spawn_error(&program, e))?;
+    let pid = child.id().unwrap_or_default();
+    let _registration = register(pid, &program);
+
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let wait = async {
+        let read_stdout = async {
+            let mut buf = Vec::new();
+            stdout.read_to_end(&mut buf).await.map(|_| buf)
+        };
+        let read_stderr = async {
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let mut line_start = 0;
+            loop {
+                let n = stderr.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                let scanned = buf.len();
+                buf.extend_from_slice(&chunk[..n]);
+                for end in scanned..buf.len() {
+                    if buf[end] == b'\n' || buf[end] == b'\r' {
+                        if end > line_start {
+                            on_line(&String::from_utf8_lossy(&buf[line_start..end]));
+                        }
+                        line_start = end + 1;
+                    }
+                }
+            }
+            if line_start < buf.len() {
+                on_line(&String::from_utf8_lossy(&buf[line_start..]));
+            }
+            Ok::<_, std::io::Error>(buf)
+        };
+        let (stdout, stderr) = tokio::try_join!(read_stdout, read_stderr)?;
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    };
+    let result = match timeout {
+        Some(limit) => match tokio::time::timeout(limit, wait).await {
+            Ok(result) => result,
+            Err(_) => {
+                kill_tree(pid);
+                let error = ProcessError::Timeout {
+                    program,
+                    seconds: limit.as_secs(),
+                };
+                log::error!("{}", error);
+                return Err(error);
+            }
+        },
+        None => wait.await,
+    };
+    let output = result.map_err(|e| ProcessError::Io {
+        program: program.clone(),
+        message: e.to_string(),
+    })?;
+    log_exit(&program, &output, started.elapsed());
+    Ok(output)
+}
+
+/// Log how a finished child exited; full stderr only at debug level
+pub fn log_exit(program: &str, output: &Output, elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    if output.status.success() {
+        log::info!(
+            "{} exited with {} after {:.2}s",
+            program,
+            output.status,
+            seconds
+        );
+    } else {
+        log::warn!(
+            "{} exited with {} after {:.2}s",
+            program,
+            output.status,
+            seconds
+        );
+    }
+    if !output.stderr.is_empty() {
+        log::debug!(
+            "{} stderr:\n{}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+}
+
+fn spawn_error(program: &str, error: std::io::Error) -> ProcessError {
+    let error = ProcessError::Spawn {
+        program: program.to_string(),
+        message: error.to_string(),
+    };
+    log::error!("{}", error);
+    error
+}
+
+/// Full argv for the log, each argument quoted
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| format!("{:?}", arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// File name of the program, for logs and error messages
+pub fn program_name(cmd: &Command) -> String {
+    std::path::Path::new(cmd.get_program())
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| cmd.get_program().to_string_lossy().to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    // Draining the registry would kill the other tests' children
+    static SERIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    fn is_alive(pid: u32) -> bool {
+        // Signal 0 only checks existence; killed-but-unreaped zombies count as dead
+        let exists = unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+        let zombie = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| stat.contains(") Z "))
+            .unwrap_or(false);
+        exists && !zombie
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_child_and_descendants() {
+        let _serial = SERIAL.lock().await;
+        // The shell forks a grandchild sleep and reports its pid
+        let pid_file = tempfile::NamedTempFile::new().unwrap();
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "sleep 30 & echo $! > \"$1\"; wait",
+            "sh",
+            pid_file.path().to_str().unwrap(),
+        ]);
+
+        let started = Instant::now();
+        let result = output_with_timeout(cmd, Some(Duration::from_millis(300))).await;
+        assert_eq!(
+            result.unwrap_err(),
+            ProcessError::Timeout {
+                program: "sh".to_string(),
+                seconds: 0
+            }
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let grandchild: u32 = std::fs::read_to_string(pid_file.path())
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while is_alive(grandchild) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!is_alive(grandchild), "descendant survived the timeout");
+    }
+
+    #[tokio::test]
+    async fn test_fast_child_completes() {
+        let _serial = SERIAL.lock().await;
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo done"]);
+        let output = output(cmd, ProcessClass::Probe).await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "done");
+
+        let missing = output_with_timeout(Command::new("/nonexistent/ffprobe"), None).await;
+        assert!(matches!(missing, Err(ProcessError::Spawn { .. })));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_children_do_not_block_the_runtime() {
+        let _serial = SERIAL.lock().await;
+        // Four 300ms children on a single-threaded runtime finish together
+        let started = Instant::now();
+        let mut runs = tokio::task::JoinSet::new();
+        for _ in 0..4 {
+            let mut cmd = Command::new("sleep");
+            cmd.arg("0.3");
+            runs.spawn(output(cmd, ProcessClass::Probe));
+        }
+        while let Some(result) = runs.join_next().await {
+            assert!(result.unwrap().unwrap().status.success());
+        }
+        assert!(started.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn test_large_output_is_drained() {
+        let _serial = SERIAL.lock().await;
+        // More than a pipe buffer on both streams must not deadlock the wait
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "head -c 1000000 /dev/zero >&2; head -c 1000000 /dev/zero",
+        ]);
+        let output = output_with_timeout(cmd, Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        assert_eq!(output.stdout.len(), 1_000_000);
+        assert_eq!(output.stderr.len(), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_stderr_lines_are_streamed() {
+        let _serial = SERIAL.lock().await;
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "printf 'frame=1\\rframe=2\\r\\nerror\\nlast' >&2"]);
+        let mut lines = Vec::new();
+        let output = output_with_progress(cmd, ProcessClass::Probe, |line| {
+            lines.push(line.to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(lines, ["frame=1", "frame=2", "error", "last"]);
+        assert_eq!(output.stderr, b"frame=1\rframe=2\r\nerror\nlast");
+    }
+
+    #[test]
+    fn test_drain_registry_kills_children() {
+        let _serial = SERIAL.blocking_lock();
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let mut child = spawn(cmd).unwrap();
+        assert_eq!(registered_count(), 1);
+
+        assert_eq!(drain_registry(), 1);
+        assert_eq!(registered_count(), 0);
+        let started = Instant::now();
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Dropping the child after a drain is harmless
+        drop(child);
+        assert_eq!(registered_count(), 0);
+    }
+
+    #[test]
+    fn test_class_timeouts() {
+        assert_eq!(ProcessClass::Probe.timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(ProcessClass::Download.timeout(), None);
+        assert!(ProcessClass::Proxy.timeout() > ProcessClass::Thumbnail.timeout());
+    }
+}
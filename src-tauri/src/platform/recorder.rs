@@ -0,0 +1,488 @@
+// Recorder command plan
+// Both platform recorders describe their capture devices as inputs for the
+// platform's capture API; everything after the inputs (picture-in-picture,
+// encoding, audio quality, output flags) is decided here once, so a
+// screen+webcam recording comes out the same on macOS and Windows.
+
+use crate::ffmpeg::capabilities::needs_vaapi_upload;
+use crate::ffmpeg::export::hardware_h264_encoder;
+use crate::ffmpeg::{FfmpegCommandBuilder, FfmpegInput};
+use crate::models::recording::{CameraOptions, ChromaMode, RecordingSettings};
+
+/// Webcam overlay for screen+webcam recordings: 30% size, slightly brightened,
+/// bottom-left with 20px padding
+const PIP_CAMERA_FILTER: &str = "scale=iw*0.30:ih*0.30,eq=brightness=0.06:contrast=1.1";
+const PIP_OVERLAY: &str = "overlay=20:main_h-overlay_h-20";
+
+/// Recording audio: AAC at 192 kb/s, 48 kHz
+const AUDIO_BITRATE_KBPS: u32 = 192;
+const AUDIO_SAMPLE_RATE: &str = "48000";
+
+/// Input queue deep enough that live devices don't drop packets while the
+/// encoder catches up
+const THREAD_QUEUE_SIZE: &str = "1024";
+
+/// Recording video: software H.264 at CRF 23, or 18 for 4:4:4 text
+/// recordings; hardware encoders get a bitrate instead
+const RECORDING_CRF: u32 = 23;
+const RECORDING_CRF_444: u32 = 18;
+const HARDWARE_BITRATE: &str = "12M";
+
+/// Capture API the devices are read through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureBackend {
+    /// macOS: screens and cameras through avfoundation, with the microphone
+    /// captured as the audio half of the first video device
+    AvFoundation,
+    /// Windows: the desktop through gdigrab, cameras and the microphone
+    /// through DirectShow
+    GdiGrabDshow,
+}
+
+/// What to record
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingRequest {
+    pub screen: Option<String>,
+    pub camera: Option<String>,
+    /// Microphone to capture (None = no audio); an empty id means the default
+    pub microphone: Option<String>,
+    /// "WIDTHxHEIGHT" or "source"
+    pub resolution: String,
+    pub fps: u32,
+    pub chroma: ChromaMode,
+    /// Use the platform's hardware H.264 encoder when there is one
+    pub hardware_encoder: bool,
+    /// Mirroring and cropping of the camera picture
+    pub camera_options: CameraOptions,
+}
+
+/// A device input and the streams it carries
+#[derive(Debug, Clone, PartialEq)]
+struct CaptureInput {
+    input: FfmpegInput,
+    video: Option<VideoRole>,
+    audio: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VideoRole {
+    Screen,
+    Camera,
+}
+
+impl RecordingRequest {
+    /// Request from `start_recording`'s arguments; the microphone is captured
+    /// when `audio_sources` includes "microphone"
+    pub fn new(
+        screen: Option<String>,
+        camera: Option<String>,
+        audio_sources: &[String],
+        microphone_device_id: Option<String>,
+        settings: RecordingSettings,
+        camera_options: CameraOptions,
+    ) -> Self {
+        let microphone = audio_sources
+            .iter()
+            .any(|source| source == "microphone")
+            .then(|| microphone_device_id.unwrap_or_default());
+        Self {
+            screen,
+            camera,
+            microphone,
+            resolution: settings.resolution,
+            fps: settings.fps,
+            chroma: settings.chroma,
+            hardware_encoder: settings.hardware_encoder,
+            camera_options,
+        }
+    }
+}
+
+/// Filters applied to the camera picture before anything else: a center
+/// crop to the chosen aspect, then the mirror flip
+fn camera_filters(options: &CameraOptions) -> Vec<String> {
+    let mut filters = Vec::new();
+    if let Some((width, height)) = options.crop_ratio() {
+        filters.push(format!("crop=ih*{}/{}:ih", width, height));
+    }
+    if options.mirror {
+        filters.push("hflip".to_string());
+    }
+    filters
+}
+
+impl CaptureBackend {
+    /// Inputs capturing `request`'s devices, screen first
+    fn inputs(self, request: &RecordingRequest) -> Vec<CaptureInput> {
+        let fps = request.fps.to_string();
+        let mut inputs = Vec::new();
+        match self {
+            CaptureBackend::AvFoundation => {
+                // The microphone rides along with the first video device
+                let mut microphone =
+                    request
+                        .microphone
+                        .as_deref()
+                        .map(|id| if id.is_empty() { "0" } else { id });
+                let mut device = |id: &str, role: VideoRole| {
+                    let audio = microphone.take();
+                    let url = match audio {
+                        Some(audio) => format!("{}:{}", id, audio),
+                        None => id.to_string(),
+                    };
+                    let mut input = FfmpegInput::new(url).format("avfoundation");
+                    if role == VideoRole::Screen {
+                        input = input.option("-capture_cursor", "1");
+                    }
+                    CaptureInput {
+                        input: input.option("-r", &fps),
+                        video: Some(role),
+                        audio: audio.is_some(),
+                    }
+                };
+                if let Some(screen) = &request.screen {
+                    inputs.push(device(screen, VideoRole::Screen));
+                }
+                if let Some(camera) = &request.camera {
+                    inputs.push(device(camera, VideoRole::Camera));
+                }
+            }
+            CaptureBackend::GdiGrabDshow => {
+                if request.screen.is_some() {
+                    inputs.push(CaptureInput {
+                        input: FfmpegInput::new("desktop")
+                            .format("gdigrab")
+                            .option("-framerate", &fps),
+                        video: Some(VideoRole::Screen),
+                        audio: false,
+                    });
+                }
+                if let Some(camera) = &request.camera {
+                    inputs.push(CaptureInput {
+                        input: FfmpegInput::new(format!("video={}", camera))
+                            .format("dshow")
+                            .option("-framerate", &fps),
+                        video: Some(VideoRole::Camera),
+                        audio: false,
+                    });
+                }
+                if let Some(microphone) = &request.microphone {
+                    let name = if microphone.is_empty() {
+                        "Microphone"
+                    } else {
+                        microphone
+                    };
+                    inputs.push(CaptureInput {
+                        input: FfmpegInput::new(format!("audio={}", name)).format("dshow"),
+                        video: None,
+                        audio: true,
+                    });
+                }
+            }
+        }
+        inputs
+    }
+}
+
+/// Everything a recorder passes to FFmpeg, independent of the platform
+/// beyond its inputs
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingCommandPlan {
+    inputs: Vec<CaptureInput>,
+    resolution: String,
+    camera_filters: Vec<String>,
+    chroma: ChromaMode,
+    hardware_encoder: Option<&'static str>,
+}
+
+impl RecordingCommandPlan {
+    pub fn new(backend: CaptureBackend, request: &RecordingRequest) -> Self {
+        Self {
+            inputs: backend.inputs(request),
+            resolution: request.resolution.clone(),
+            camera_filters: camera_filters(&request.camera_options),
+            chroma: request.chroma,
+            // 4:4:4 is software-only (settings validation rejects the pair)
+            // Capture doesn't upload frames to a VAAPI device
+            hardware_encoder: (request.hardware_encoder
+                && request.chroma == ChromaMode::Standard420)
+                .then(hardware_h264_encoder)
+                .flatten()
+                .filter(|encoder| !needs_vaapi_upload(encoder)),
+        }
+    }
+
+    fn video_input(&self, role: VideoRole) -> Option<usize> {
+        self.inputs.iter().position(|i| i.video == Some(role))
+    }
+
+    /// Builder writing the recording to `output_path`
+    ///
+    /// Screen+webcam recordings composite the webcam over the screen at the
+    /// screen's size; otherwise the single video input is scaled to the
+    /// requested resolution. The camera's crop and mirror run first, ahead
+    /// of the picture-in-picture sizing.
+    pub fn command(&self, output_path: &str) -> FfmpegCommandBuilder {
+        let has_audio = self.inputs.iter().any(|i| i.audio);
+        let mut builder = FfmpegCommandBuilder::new().overwrite();
+        for capture in &self.inputs {
+            let mut input = capture.input.clone();
+            if has_audio {
+                input = input.option("-thread_queue_size", THREAD_QUEUE_SIZE);
+            }
+            builder = builder.input(input);
+        }
+
+        let screen = self.video_input(VideoRole::Screen);
+        let camera = self.video_input(VideoRole::Camera);
+        builder = match (screen, camera) {
+            (Some(screen), Some(camera)) => {
+                let mut camera_chain = self.camera_filters.clone();
+                camera_chain.push(PIP_CAMERA_FILTER.to_string());
+                builder
+                    .filter_complex(format!(
+                        "[{camera}:v]{}[cam];[{screen}:v][cam]{}[vout]",
+                        camera_chain.join(","),
+                        PIP_OVERLAY,
+                        camera = camera,
+                        screen = screen
+                    ))
+                    .option("-map", "[vout]")
+            }
+            (None, Some(camera)) if !self.camera_filters.is_empty() => builder
+                .option("-map", format!("{}:v", camera))
+                .video_filter(self.camera_filters.join(",")),
+            (Some(video), None) | (None, Some(video)) => {
+                builder.option("-map", format!("{}:v", video))
+            }
+            (None, None) => builder,
+        };
+        if let Some(audio) = self.inputs.iter().position(|i| i.audio) {
+            builder = builder.option("-map", format!("{}:a", audio));
+        }
+
+        // H.264 fast enough to encode live: web/QuickTime-compatible 4:2:0,
+        // or 4:4:4 (High 4:4:4 profile) for sharp colored text
+        builder = match (self.hardware_encoder, self.chroma) {
+            (Some(encoder), _) => builder
+                .video_codec(encoder)
+                .video_bitrate(HARDWARE_BITRATE)
+                .pixel_format("yuv420p"),
+            (None, ChromaMode::Standard420) => builder
+                .video_codec("libx264")
+                .preset("ultrafast")
+                .crf(RECORDING_CRF)
+                .pixel_format("yuv420p"),
+            (None, ChromaMode::HighQuality444) => builder
+                .video_codec("libx264")
+                .preset("ultrafast")
+                .crf(RECORDING_CRF_444)
+                .pixel_format("yuv444p"),
+        };
+        if has_audio {
+            builder = builder
+                .audio_codec("aac")
+                .audio_bitrate_kbps(AUDIO_BITRATE_KBPS)
+                .option("-ar", AUDIO_SAMPLE_RATE);
+        }
+        if self.resolution != "source" && !(screen.is_some() && camera.is_some()) {
+            builder = builder.option("-s", &self.resolution);
+        }
+        builder
+            .option("-movflags", "+faststart")
+            .output(output_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(screen: bool, camera: bool) -> RecordingRequest {
+        RecordingRequest::new(
+            screen.then(|| "Capture screen 0".to_string()),
+            camera.then(|| "FaceTime HD Camera".to_string()),
+            &["system".to_string(), "microphone".to_string()],
+            None,
+            RecordingSettings {
+                resolution: "1280x720".to_string(),
+                fps: 30,
+                chroma: ChromaMode::Standard420,
+                hardware_encoder: false,
+            },
+            CameraOptions::default(),
+        )
+    }
+
+    fn argv(backend: CaptureBackend, screen: bool, camera: bool) -> String {
+        RecordingCommandPlan::new(backend, &request(screen, camera))
+            .command("/rec/out.mp4")
+            .to_arg_vec()
+            .join(" ")
+    }
+
+    const ENCODE: &str = "-c:v libx264 -preset ultrafast -crf 23 -pix_fmt yuv420p \
+                          -c:a aac -b:a 192k -ar 48000";
+
+    #[test]
+    fn test_avfoundation_argv() {
+        assert_eq!(
+            argv(CaptureBackend::AvFoundation, true, false),
+            format!(
+                "-y -f avfoundation -capture_cursor 1 -r 30 -thread_queue_size 1024 \
+                 -i Capture screen 0:0 -map 0:v -map 0:a {} -s 1280x720 \
+                 -movflags +faststart /rec/out.mp4",
+                ENCODE
+            )
+        );
+        assert_eq!(
+            argv(CaptureBackend::AvFoundation, false, true),
+            format!(
+                "-y -f avfoundation -r 30 -thread_queue_size 1024 -i FaceTime HD Camera:0 \
+                 -map 0:v -map 0:a {} -s 1280x720 -movflags +faststart /rec/out.mp4",
+                ENCODE
+            )
+        );
+        assert_eq!(
+            argv(CaptureBackend::AvFoundation, true, true),
+            format!(
+                "-y -f avfoundation -capture_cursor 1 -r 30 -thread_queue_size 1024 \
+                 -i Capture screen 0:0 \
+                 -f avfoundation -r 30 -thread_queue_size 1024 -i FaceTime HD Camera \
+                 -map [vout] -map 0:a {} -movflags +faststart \
+                 -filter_complex [1:v]{}[cam];[0:v][cam]{}[vout] /rec/out.mp4",
+                ENCODE, PIP_CAMERA_FILTER, PIP_OVERLAY
+            )
+        );
+    }
+
+    #[test]
+    fn test_gdigrab_dshow_argv() {
+        assert_eq!(
+            argv(CaptureBackend::GdiGrabDshow, true, false),
+            format!(
+                "-y -f gdigrab -framerate 30 -thread_queue_size 1024 -i desktop \
+                 -f dshow -thread_queue_size 1024 -i audio=Microphone \
+                 -map 0:v -map 1:a {} -s 1280x720 -movflags +faststart /rec/out.mp4",
+                ENCODE
+            )
+        );
+        assert_eq!(
+            argv(CaptureBackend::GdiGrabDshow, false, true),
+            format!(
+                "-y -f dshow -framerate 30 -thread_queue_size 1024 -i video=FaceTime HD Camera \
+                 -f dshow -thread_queue_size 1024 -i audio=Microphone \
+                 -map 0:v -map 1:a {} -s 1280x720 -movflags +faststart /rec/out.mp4",
+                ENCODE
+            )
+        );
+        // Same overlay as macOS; the microphone is its own input
+        assert_eq!(
+            argv(CaptureBackend::GdiGrabDshow, true, true),
+            format!(
+                "-y -f gdigrab -framerate 30 -thread_queue_size 1024 -i desktop \
+                 -f dshow -framerate 30 -thread_queue_size 1024 -i video=FaceTime HD Camera \
+                 -f dshow -thread_queue_size 1024 -i audio=Microphone \
+                 -map [vout] -map 2:a {} -movflags +faststart \
+                 -filter_complex [1:v]{}[cam];[0:v][cam]{}[vout] /rec/out.mp4",
+                ENCODE, PIP_CAMERA_FILTER, PIP_OVERLAY
+            )
+        );
+    }
+
+    #[test]
+    fn test_mirrored_square_camera() {
+        let camera = CameraOptions {
+            mirror: true,
+            crop_aspect: Some("1:1".to_string()),
+        };
+        let command = |screen: bool| {
+            let request = RecordingRequest {
+                camera_options: camera.clone(),
+                ..request(screen, true)
+            };
+            RecordingCommandPlan::new(CaptureBackend::AvFoundation, &request)
+                .command("/rec/out.mp4")
+                .to_arg_vec()
+                .join(" ")
+        };
+
+        // Webcam only: the camera filters are the whole chain
+        let webcam = command(false);
+        assert!(webcam.contains("-map 0:v -map 0:a"), "{}", webcam);
+        assert!(webcam.contains("-vf crop=ih*1/1:ih,hflip "), "{}", webcam);
+        assert!(!webcam.contains("-filter_complex"));
+        assert!(webcam.contains("-s 1280x720"));
+
+        // Screen+webcam: cropped and mirrored before the overlay is sized
+        let pip = command(true);
+        assert!(
+            pip.contains(&format!(
+                "-filter_complex [1:v]crop=ih*1/1:ih,hflip,{}[cam];[0:v][cam]{}[vout]",
+                PIP_CAMERA_FILTER, PIP_OVERLAY
+            )),
+            "{}",
+            pip
+        );
+        assert!(!pip.contains("-vf"));
+    }
+
+    #[test]
+    fn test_silent_recording_at_source_resolution() {
+        let request = RecordingRequest {
+            microphone: None,
+            resolution: "source".to_string(),
+            ..request(true, false)
+        };
+        assert_eq!(
+            RecordingCommandPlan::new(CaptureBackend::GdiGrabDshow, &request)
+                .command("/rec/out.mp4")
+                .to_arg_vec()
+                .join(" "),
+            "-y -f gdigrab -framerate 30 -i desktop -map 0:v \
+             -c:v libx264 -preset ultrafast -crf 23 -pix_fmt yuv420p \
+             -movflags +faststart /rec/out.mp4"
+        );
+    }
+
+    #[test]
+    fn test_encoder_choice() {
+        let encode = |chroma: ChromaMode, hardware_encoder: bool| {
+            let request = RecordingRequest {
+                microphone: None,
+                chroma,
+                hardware_encoder,
+                ..request(true, false)
+            };
+            let argv = RecordingCommandPlan::new(CaptureBackend::AvFoundation, &request)
+                .command("/rec/out.mp4")
+                .to_arg_vec()
+                .join(" ");
+            let start = argv.find("-c:v").unwrap();
+            let end = argv.find(" -s ").unwrap();
+            argv[start..end].to_string()
+        };
+
+        assert_eq!(
+            encode(ChromaMode::Standard420, false),
+            "-c:v libx264 -preset ultrafast -crf 23 -pix_fmt yuv420p"
+        );
+        assert_eq!(
+            encode(ChromaMode::HighQuality444, false),
+            "-c:v libx264 -preset ultrafast -crf 18 -pix_fmt yuv444p"
+        );
+        // Hardware encoders can't write 4:4:4
+        assert_eq!(
+            encode(ChromaMode::HighQuality444, true),
+            encode(ChromaMode::HighQuality444, false)
+        );
+        let hardware = encode(ChromaMode::Standard420, true);
+        match hardware_h264_encoder() {
+            Some(encoder) => assert_eq!(
+                hardware,
+                format!("-c:v {} -b:v 12M -pix_fmt yuv420p", encoder)
+            ),
+            None => assert_eq!(hardware, encode(ChromaMode::Standard420, false)),
+        }
+    }
+}
@@ -1,15 +1,19 @@
 // Windows-specific screen recording implementation using FFmpeg with gdigrab
 
+use crate::ffmpeg::locate::ffmpeg_path;
 use crate::models::recording::{
-    PermissionResult, PermissionStatus, RecordingSource, RecordingSources, ScreenSource,
-    WindowSource,
+    CameraOptions, PermissionResult, PermissionStatus, RecordingSettings, RecordingSource,
+    RecordingSources, ScreenSource, WindowSource, RECORDING_FPS,
 };
+use crate::platform::capabilities::{parse_dshow_options, supported_rates};
+use crate::platform::process::{self, ProcessClass, SupervisedChild};
+use crate::platform::recorder::{CaptureBackend, RecordingCommandPlan, RecordingRequest};
 use std::collections::HashMap;
-use std::process::{Child, Command};
+use std::process::Command;
 use std::sync::{Arc, Mutex};
 
 lazy_static::lazy_static! {
-    static ref ACTIVE_RECORDINGS: Arc<Mutex<HashMap<String, Child>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref ACTIVE_RECORDINGS: Arc<Mutex<HashMap<String, SupervisedChild>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
 /// Request recording permissions on Windows
@@ -67,6 +71,8 @@ fn list_screens() -> Result<Vec<ScreenSource>, String> {
         id: "desktop".to_string(),
         name: "Primary Display".to_string(),
         resolution: "1920x1080".to_string(), // Would be detected from system
+        // gdigrab captures the desktop at whatever rate it's asked for
+        supported_fps: RECORDING_FPS.to_vec(),
     }])
 }
 
@@ -78,7 +84,7 @@ fn list_windows() -> Result<Vec<WindowSource>, String> {
 
 fn list_cameras() -> Result<Vec<RecordingSource>, String> {
     // Use FFmpeg to list DirectShow video devices
-    let output = Command::new("ffmpeg")
+    let output = Command::new(ffmpeg_path())
         .args(&["-list_devices", "true", "-f", "dshow", "-i", "dummy"])
         .output()
         .map_err(|e| format!("Failed to list cameras: {}", e))?;
@@ -99,6 +105,7 @@ fn list_cameras() -> Result<Vec<RecordingSource>, String> {
                     cameras.push(RecordingSource {
                         id: name.to_string(),
                         name: name.to_string(),
+                        supported_fps: probe_supported_fps(name),
                     });
                 }
             }
@@ -108,103 +115,60 @@ fn list_cameras() -> Result<Vec<RecordingSource>, String> {
     Ok(cameras)
 }
 
+/// Frame rates a DirectShow camera supports, from its listed options
+///
+/// A camera that hangs is killed at the probe timeout and its rates left
+/// unknown.
+fn probe_supported_fps(device: &str) -> Vec<u32> {
+    let mut cmd = Command::new(ffmpeg_path());
+    cmd.args([
+        "-hide_banner",
+        "-list_options",
+        "true",
+        "-f",
+        "dshow",
+        "-i",
+        &format!("video={}", device),
+    ]);
+    match tauri::async_runtime::block_on(process::output(cmd, ProcessClass::Probe)) {
+        Ok(output) => supported_rates(&parse_dshow_options(&String::from_utf8_lossy(
+            &output.stderr,
+        ))),
+        Err(e) => {
+            log::warn!("Could not probe frame rates of camera {}: {}", device, e);
+            Vec::new()
+        }
+    }
+}
+
 /// Start recording using FFmpeg with gdigrab (screen) and dshow (camera) on Windows
+#[allow(clippy::too_many_arguments)]
 pub fn start_recording(
     session_id: String,
     output_path: String,
     screen_source: Option<String>,
     camera_source: Option<String>,
     audio_sources: Vec<String>,
-    resolution: String,
-    fps: u32,
+    microphone_device_id: Option<String>,
+    settings: RecordingSettings,
+    camera_options: CameraOptions,
 ) -> Result<(), String> {
-    let mut ffmpeg_args = vec!["-y".to_string()]; // Overwrite output file
-
-    let has_screen = screen_source.is_some();
-    let has_camera = camera_source.is_some();
-    let has_audio = !audio_sources.is_empty();
-
-    if has_screen {
-        // Screen capture using gdigrab (Windows GDI-based screen capture)
-        ffmpeg_args.extend_from_slice(&[
-            "-f".to_string(),
-            "gdigrab".to_string(),
-            "-framerate".to_string(),
-            fps.to_string(),
-            "-i".to_string(),
-            "desktop".to_string(), // Capture entire desktop
-        ]);
-    }
-
-    if has_camera {
-        // Camera capture using DirectShow
-        let camera_name = camera_source.unwrap_or_else(|| "video=0".to_string());
-
-        ffmpeg_args.extend_from_slice(&[
-            "-f".to_string(),
-            "dshow".to_string(),
-            "-i".to_string(),
-            format!("video={}", camera_name),
-        ]);
-    }
-
-    if has_audio {
-        // Audio capture using DirectShow
-        if audio_sources.contains(&"microphone".to_string()) {
-            // Default microphone
-            ffmpeg_args.extend_from_slice(&[
-                "-f".to_string(),
-                "dshow".to_string(),
-                "-i".to_string(),
-                "audio=Microphone".to_string(),
-            ]);
-        }
-    }
-
-    // Video codec settings - web-compatible H.264
-    ffmpeg_args.extend_from_slice(&[
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "ultrafast".to_string(),
-        "-crf".to_string(),
-        "23".to_string(),
-        "-pix_fmt".to_string(),
-        "yuv420p".to_string(), // Critical: ensures web/QuickTime compatibility
-    ]);
-
-    // Audio codec settings
-    if has_audio {
-        ffmpeg_args.extend_from_slice(&[
-            "-c:a".to_string(),
-            "aac".to_string(),
-            "-b:a".to_string(),
-            "192k".to_string(),
-        ]);
-    }
-
-    // Output resolution
-    if resolution != "source" {
-        ffmpeg_args.extend_from_slice(&["-s".to_string(), resolution.clone()]);
-    }
-
-    // MP4-specific flags for proper file structure
-    ffmpeg_args.extend_from_slice(&[
-        "-movflags".to_string(),
-        "+faststart".to_string(), // Enable progressive playback and proper MP4 structure
-    ]);
-
-    // Output file
-    ffmpeg_args.push(output_path.clone());
+    let request = RecordingRequest::new(
+        screen_source,
+        camera_source,
+        &audio_sources,
+        microphone_device_id,
+        settings,
+        camera_options,
+    );
+    let plan = RecordingCommandPlan::new(CaptureBackend::GdiGrabDshow, &request);
 
     // Start FFmpeg process with stdin pipe for graceful shutdown
-    let child = Command::new("ffmpeg")
-        .args(&ffmpeg_args)
-        .stdin(std::process::Stdio::piped()) // Enable stdin for 'q' command
+    let mut cmd = plan.command(&output_path).build();
+    cmd.stdin(std::process::Stdio::piped()) // Enable stdin for 'q' command
         .stdout(std::process::Stdio::null()) // Suppress stdout
-        .stderr(std::process::Stdio::piped()) // Capture progress/errors
-        .spawn()
-        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+        .stderr(std::process::Stdio::piped()); // Capture progress/errors
+    let child = process::spawn(cmd)?;
 
     // Store the process handle
     let mut recordings = ACTIVE_RECORDINGS.lock().unwrap();
@@ -242,7 +206,7 @@ pub fn stop_recording(session_id: String) -> Result<(), String> {
                     // Still running
                     if start.elapsed() > timeout {
                         // Timeout - force kill as last resort
-                        let _ = child.kill();
+                        process::kill_tree(child.id());
                         child
                             .wait()
                             .map_err(|e| format!("Failed to wait for FFmpeg: {}", e))?;
@@ -267,6 +231,22 @@ pub fn is_recording_active(session_id: &str) -> bool {
     recordings.contains_key(session_id)
 }
 
+/// If the recording's FFmpeg process exited on its own, forget it and return its stderr
+pub fn take_exited_recording(session_id: &str) -> Option<String> {
+    let mut recordings = ACTIVE_RECORDINGS.lock().unwrap();
+    let exited = matches!(recordings.get_mut(session_id)?.try_wait(), Ok(Some(_)));
+    if !exited {
+        return None;
+    }
+    let mut child = recordings.remove(session_id)?;
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        use std::io::Read;
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    Some(stderr)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
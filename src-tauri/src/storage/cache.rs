@@ -2,31 +2,292 @@
 // Provides fast lookups and persistence for app state
 
 use crate::models::clip::MediaClip;
-use rusqlite::{Connection, Result as SqliteResult};
-use std::path::PathBuf;
+use crate::models::export::{ExportHistoryEntry, ExportStatus};
+use crate::models::project::Project;
+use crate::models::recording::{RecordingSession, RecordingStatus, RecordingType};
+use crate::models::settings::AppSettings;
+use crate::storage::cache_paths::{
+    resolve_cache_path, resolve_clip_paths, resolve_project_paths, store_cache_path,
+    store_clip_paths, store_project_paths,
+};
+use rusqlite::{Connection, OptionalExtension, Result as SqliteResult};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Number of auto-save snapshots kept per project
+pub const AUTO_SAVE_RETENTION: usize = 10;
+
+/// zstd level for auto-save snapshots: fast, and project JSON still shrinks
+/// several times over
+const AUTO_SAVE_ZSTD_LEVEL: i32 = 3;
+
+/// Number of entries kept in the recent projects list
+pub const RECENT_PROJECTS_LIMIT: usize = 20;
+
+/// Number of export jobs kept in the history table
+pub const EXPORT_HISTORY_RETENTION: usize = 200;
+
+/// Cache directory subfolders of files regenerated when missing, evicted
+/// oldest first past the size cap
+const EVICTABLE_DIRS: [&str; 3] = ["proxies", "thumbnails", "hover_sprites"];
+
+/// `user_version` from which cache file paths are stored cache-relative
+const CACHE_RELATIVE_PATHS_VERSION: i64 = 1;
+
+/// Result of a cache maintenance run
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    /// Database size in bytes before maintenance
+    pub size_before: u64,
+    /// Database size in bytes after VACUUM
+    pub size_after: u64,
+    /// Problems reported by `PRAGMA integrity_check` (empty = healthy)
+    pub integrity_errors: Vec<String>,
+    /// Proxies, thumbnails and sprites deleted to get under the size cap
+    pub evicted: Eviction,
+}
+
+/// Cache files `CacheDb::evict_to_size_cap` deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Eviction {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Entry in the recent projects list
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentProject {
+    pub path: String,
+    pub name: String,
+    pub opened_at: chrono::DateTime<chrono::Utc>,
+}
 
 /// Thread-safe wrapper for cache database
+///
+/// Proxy, thumbnail and hover sprite paths under the database's directory
+/// are stored relative to it (see `cache_paths`) and resolved when read.
 #[derive(Debug, Clone)]
 pub struct CacheDb {
     conn: Arc<Mutex<Connection>>,
+    /// Directory holding the database file (None in memory)
+    cache_dir: Option<String>,
 }
 
 impl CacheDb {
     pub fn new(cache_path: &PathBuf) -> SqliteResult<Self> {
         let conn = initialize_cache(cache_path)?;
+        let cache_dir = cache_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| dir.to_string_lossy().to_string());
+        if let Some(dir) = &cache_dir {
+            migrate_cache_relative_paths(&conn, dir)?;
+        }
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            cache_dir,
         })
     }
 
+    /// `path` as stored in this database
+    fn stored_path(&self, path: &str) -> String {
+        match &self.cache_dir {
+            Some(dir) => store_cache_path(path, dir),
+            None => path.to_string(),
+        }
+    }
+
+    /// A stored path as the absolute path it refers to
+    fn resolved_path(&self, stored: String) -> String {
+        match &self.cache_dir {
+            Some(dir) => resolve_cache_path(&stored, dir),
+            None => stored,
+        }
+    }
+
+    /// `clip` with its cache files as stored in this database
+    fn stored_clip(&self, clip: &MediaClip) -> MediaClip {
+        let mut clip = clip.clone();
+        if let Some(dir) = &self.cache_dir {
+            store_clip_paths(&mut clip, dir);
+        }
+        clip
+    }
+
+    /// Open the cache database, moving a corrupted file aside and starting fresh
+    ///
+    /// Returns the database and, if it had to be rebuilt, the path the corrupted
+    /// file was renamed to. Cached metadata is regenerated lazily after a rebuild.
+    /// Only corruption rebuilds: a database that can't be opened for another
+    /// reason (locked by another instance, no permission, disk full) is
+    /// reported and left untouched.
+    pub fn open_or_rebuild(cache_path: &PathBuf) -> Result<(Self, Option<PathBuf>), String> {
+        let problem = match Self::new(cache_path) {
+            Ok(db) => match db.quick_check() {
+                Ok(None) => return Ok((db, None)),
+                Ok(Some(problem)) => problem,
+                Err(e) if is_corruption(&e) => e.to_string(),
+                Err(e) => return Err(e.to_string()),
+            },
+            Err(e) if is_corruption(&e) => e.to_string(),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        log::warn!("Cache database is corrupted ({}), rebuilding", problem);
+        Self::rebuild(cache_path)
+    }
+
+    /// Open the cache database as it is, failing if it's corrupted
+    ///
+    /// For recoveries the user asks for, e.g. a retry once another instance
+    /// let go of the database: nothing is moved aside, that's `rebuild`. The
+    /// second value, kept for the same shape as `open_or_rebuild`, is None.
+    pub fn open_existing(cache_path: &PathBuf) -> Result<(Self, Option<PathBuf>), String> {
+        let db = Self::new(cache_path).map_err(|e| e.to_string())?;
+        match db.quick_check().map_err(|e| e.to_string())? {
+            None => Ok((db, None)),
+            Some(problem) => Err(format!(
+                "the database is corrupted ({}); rebuild it to start fresh",
+                problem
+            )),
+        }
+    }
+
+    /// Move the database file aside (if any) and create an empty one
+    ///
+    /// Returns the new database and where the old file was moved, if there
+    /// was one. Used for corrupted databases and, on request, for ones that
+    /// can't be opened.
+    pub fn rebuild(cache_path: &PathBuf) -> Result<(Self, Option<PathBuf>), String> {
+        let corrupt_path = PathBuf::from(format!(
+            "{}.corrupt-{}",
+            cache_path.display(),
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        let moved = cache_path.exists();
+        if moved {
+            std::fs::rename(cache_path, &corrupt_path)
+                .map_err(|e| format!("Failed to move corrupted cache database aside: {}", e))?;
+        }
+
+        // Stale journal files belong to the corrupted database
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let _ = std::fs::remove_file(format!("{}{}", cache_path.display(), suffix));
+        }
+
+        let db =
+            Self::new(cache_path).map_err(|e| format!("Failed to create cache database: {}", e))?;
+        Ok((db, moved.then_some(corrupt_path)))
+    }
+
+    /// Database that lives only as long as the process
+    ///
+    /// Stands in for the real cache when it can't be opened at startup, so
+    /// the app still runs (without persistence) until the user recovers.
+    pub fn in_memory() -> SqliteResult<Self> {
+        Self::new(&PathBuf::from(":memory:"))
+    }
+
+    /// Fast corruption check used at startup: the problem found, None when
+    /// the database is healthy
+    fn quick_check(&self) -> SqliteResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let result: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        Ok((result != "ok").then_some(result))
+    }
+
+    /// Delete the least recently written proxies, thumbnails and hover
+    /// sprites until together they take at most `cap_bytes`
+    ///
+    /// Clips pointing at a deleted file lose the path, so it's regenerated
+    /// when next needed. Nothing is evicted from an in-memory database.
+    pub fn evict_to_size_cap(&self, cap_bytes: u64) -> Result<Eviction, String> {
+        let Some(cache_dir) = &self.cache_dir else {
+            return Ok(Eviction::default());
+        };
+        let mut files = Vec::new();
+        for dir in EVICTABLE_DIRS {
+            collect_cache_files(&Path::new(cache_dir).join(dir), &mut files);
+        }
+        let mut total: u64 = files.iter().map(|f| f.1).sum();
+        files.sort_by_key(|f| f.2);
+
+        let mut eviction = Eviction::default();
+        let conn = self.conn.lock().unwrap();
+        for (path, size, _) in files {
+            if total <= cap_bytes {
+                break;
+            }
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("Failed to evict {}: {}", path.display(), e);
+                continue;
+            }
+            total -= size;
+            eviction.files += 1;
+            eviction.bytes += size;
+            conn.execute(
+                "UPDATE media_clips SET
+                    proxy_path = NULLIF(proxy_path, ?1),
+                    thumbnail_path = NULLIF(thumbnail_path, ?1),
+                    thumbnail_2x_path = NULLIF(thumbnail_2x_path, ?1),
+                    hover_sprite_path = NULLIF(hover_sprite_path, ?1)
+                 WHERE ?1 IN (proxy_path, thumbnail_path, thumbnail_2x_path, hover_sprite_path)",
+                rusqlite::params![self.stored_path(&path.to_string_lossy())],
+            )
+            .map_err(|e| format!("Failed to forget evicted cache file: {}", e))?;
+        }
+        Ok(eviction)
+    }
+
+    /// Evict cache files past `size_cap_bytes` (see `evict_to_size_cap`),
+    /// then run integrity check, VACUUM and optimize, reporting the size change
+    pub fn run_maintenance(&self, size_cap_bytes: u64) -> Result<MaintenanceReport, String> {
+        let evicted = self.evict_to_size_cap(size_cap_bytes)?;
+        let conn = self.conn.lock().unwrap();
+        let size_before = database_size(&conn)?;
+
+        let integrity_errors = {
+            let mut stmt = conn
+                .prepare("PRAGMA integrity_check")
+                .map_err(|e| format!("Failed to run integrity check: {}", e))?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(|e| format!("Failed to run integrity check: {}", e))?
+                .collect::<SqliteResult<Vec<_>>>()
+                .map_err(|e| format!("Failed to run integrity check: {}", e))?;
+            rows.into_iter().filter(|r| r != "ok").collect::<Vec<_>>()
+        };
+
+        conn.execute_batch("VACUUM; PRAGMA optimize;")
+            .map_err(|e| format!("Failed to vacuum cache database: {}", e))?;
+
+        let size_after = database_size(&conn)?;
+
+        Ok(MaintenanceReport {
+            size_before,
+            size_after,
+            integrity_errors,
+            evicted,
+        })
+    }
+
+    /// Insert a newly imported clip
+    ///
+    /// Fails if the source path is already known; re-imports go through
+    /// `get_media_clip_by_source_path` + `update_media_clip` so the clip id is kept.
     pub fn insert_media_clip(&self, clip: &MediaClip) -> Result<(), String> {
+        let clip = &self.stored_clip(clip);
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO media_clips 
+            "INSERT INTO media_clips 
              (id, name, source_path, proxy_path, thumbnail_path, duration, resolution, 
-              width, height, fps, codec, audio_codec, file_size, bitrate, has_audio, imported_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+              width, height, fps, codec, audio_codec, file_size, bitrate, has_audio, imported_at,
+              proxy_version, audio_streams, audio_stream_index, thumbnail_2x_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                     ?18, ?19, ?20)",
             rusqlite::params![
                 clip.id,
                 clip.name,
@@ -44,12 +305,651 @@ impl CacheDb {
                 clip.bitrate,
                 clip.has_audio,
                 clip.imported_at.to_rfc3339(),
+                clip.proxy_version,
+                audio_streams_json(clip)?,
+                clip.audio_stream_index,
+                clip.thumbnail_2x_path,
             ],
         )
         .map_err(|e| format!("Failed to insert media clip: {}", e))?;
 
         Ok(())
     }
+
+    /// Update an existing clip's metadata in place (matched by id)
+    pub fn update_media_clip(&self, clip: &MediaClip) -> Result<(), String> {
+        let clip = &self.stored_clip(clip);
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE media_clips SET
+                    name = ?2, source_path = ?3, proxy_path = ?4, thumbnail_path = ?5,
+                    duration = ?6, resolution = ?7, width = ?8, height = ?9, fps = ?10,
+                    codec = ?11, audio_codec = ?12, file_size = ?13, bitrate = ?14,
+                    has_audio = ?15, proxy_version = ?16, audio_streams = ?17,
+                    audio_stream_index = ?18, thumbnail_2x_path = ?19
+                 WHERE id = ?1",
+                rusqlite::params![
+                    clip.id,
+                    clip.name,
+                    clip.source_path,
+                    clip.proxy_path,
+                    clip.thumbnail_path,
+                    clip.duration,
+                    clip.resolution,
+                    clip.width,
+                    clip.height,
+                    clip.fps,
+                    clip.codec,
+                    clip.audio_codec,
+                    clip.file_size,
+                    clip.bitrate,
+                    clip.has_audio,
+                    clip.proxy_version,
+                    audio_streams_json(clip)?,
+                    clip.audio_stream_index,
+                    clip.thumbnail_2x_path,
+                ],
+            )
+            .map_err(|e| format!("Failed to update media clip: {}", e))?;
+
+        if updated == 0 {
+            return Err(format!("Media clip not found in cache: {}", clip.id));
+        }
+
+        Ok(())
+    }
+
+    /// Point a cached clip at its thumbnails (1x and 2x); false if no row
+    /// has that id
+    pub fn set_thumbnail_path(
+        &self,
+        clip_id: &str,
+        path: &str,
+        path_2x: Option<&str>,
+    ) -> Result<bool, String> {
+        let path = self.stored_path(path);
+        let path_2x = path_2x.map(|p| self.stored_path(p));
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE media_clips SET thumbnail_path = ?2, thumbnail_2x_path = ?3 WHERE id = ?1",
+                rusqlite::params![clip_id, path, path_2x],
+            )
+            .map_err(|e| format!("Failed to store thumbnail path: {}", e))?;
+        Ok(updated > 0)
+    }
+
+    /// Path of the clip's cached hover-scrub sprite, if one was generated
+    pub fn get_hover_sprite_path(&self, clip_id: &str) -> Result<Option<String>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT hover_sprite_path FROM media_clips WHERE id = ?1",
+            rusqlite::params![clip_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map(Option::flatten)
+        .map(|path| path.map(|p| self.resolved_path(p)))
+        .map_err(|e| format!("Failed to query hover sprite: {}", e))
+    }
+
+    /// Remember (or with None, forget) the clip's hover-scrub sprite
+    pub fn set_hover_sprite_path(&self, clip_id: &str, path: Option<&str>) -> Result<(), String> {
+        let path = path.map(|p| self.stored_path(p));
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE media_clips SET hover_sprite_path = ?2 WHERE id = ?1",
+            rusqlite::params![clip_id, path],
+        )
+        .map_err(|e| format!("Failed to store hover sprite: {}", e))?;
+        Ok(())
+    }
+
+    /// Find a previously imported clip by its source file path
+    pub fn get_media_clip_by_source_path(&self, path: &str) -> Result<Option<MediaClip>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, source_path, proxy_path, thumbnail_path, duration, resolution,
+                    width, height, fps, codec, audio_codec, file_size, bitrate, has_audio,
+                    imported_at, proxy_version, audio_streams, audio_stream_index,
+                    thumbnail_2x_path
+             FROM media_clips WHERE source_path = ?1",
+            rusqlite::params![path],
+            media_clip_from_row,
+        )
+        .optional()
+        .map(|clip| {
+            clip.map(|mut clip| {
+                if let Some(dir) = &self.cache_dir {
+                    resolve_clip_paths(&mut clip, dir);
+                }
+                clip
+            })
+        })
+        .map_err(|e| format!("Failed to query media clip: {}", e))
+    }
+
+    /// Load app settings (None if settings have never been saved)
+    pub fn load_app_settings(&self) -> Result<Option<AppSettings>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings")
+            .map_err(|e| format!("Failed to query settings: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| format!("Failed to query settings: {}", e))?;
+
+        let mut map = serde_json::Map::new();
+        for row in rows {
+            let (key, value) = row.map_err(|e| format!("Failed to read setting: {}", e))?;
+            match serde_json::from_str(&value) {
+                Ok(json) => {
+                    map.insert(key, json);
+                }
+                Err(e) => log::warn!("Ignoring unreadable setting '{}': {}", key, e),
+            }
+        }
+
+        if map.is_empty() {
+            return Ok(None);
+        }
+
+        // Fields that fail to deserialize fall back to defaults rather than
+        // discarding every other setting
+        match serde_json::from_value(serde_json::Value::Object(map.clone())) {
+            Ok(settings) => Ok(Some(settings)),
+            Err(e) => {
+                log::warn!("Failed to parse stored settings: {}", e);
+                let mut settings = serde_json::to_value(AppSettings::default())
+                    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+                for (key, value) in map {
+                    let mut candidate = settings.clone();
+                    candidate[&key] = value;
+                    if serde_json::from_value::<AppSettings>(candidate.clone()).is_ok() {
+                        settings = candidate;
+                    }
+                }
+                serde_json::from_value(settings)
+                    .map(Some)
+                    .map_err(|e| format!("Failed to parse settings: {}", e))
+            }
+        }
+    }
+
+    /// Persist app settings (one row per top-level field)
+    pub fn save_app_settings(&self, settings: &AppSettings) -> Result<(), String> {
+        let value = serde_json::to_value(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        let map = value
+            .as_object()
+            .ok_or_else(|| "Settings must serialize to an object".to_string())?;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for (key, value) in map {
+            tx.execute(
+                "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                rusqlite::params![key, value.to_string(), now],
+            )
+            .map_err(|e| format!("Failed to save setting '{}': {}", key, e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit settings: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Add (or move to the top) a project file in the recent projects list
+    pub fn add_recent_project(&self, path: &str, name: &str) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO recent_projects (path, name, opened_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(path) DO UPDATE SET name = excluded.name, opened_at = excluded.opened_at",
+            rusqlite::params![path, name, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| format!("Failed to record recent project: {}", e))?;
+
+        conn.execute(
+            "DELETE FROM recent_projects WHERE path NOT IN (
+                SELECT path FROM recent_projects ORDER BY opened_at DESC LIMIT ?1
+             )",
+            rusqlite::params![RECENT_PROJECTS_LIMIT],
+        )
+        .map_err(|e| format!("Failed to prune recent projects: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Get recently opened/saved projects, most recent first
+    pub fn get_recent_projects(&self) -> Result<Vec<RecentProject>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT path, name, opened_at FROM recent_projects ORDER BY opened_at DESC")
+            .map_err(|e| format!("Failed to query recent projects: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to query recent projects: {}", e))?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("Failed to read recent project: {}", e))?;
+
+        rows.into_iter()
+            .map(|(path, name, opened_at)| {
+                Ok(RecentProject {
+                    path,
+                    name,
+                    opened_at: parse_timestamp(&opened_at)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Store an auto-save snapshot of a project (crash recovery, not a real save)
+    ///
+    /// The JSON is stored zstd-compressed. Nothing is stored when it's
+    /// identical to the project's latest snapshot; returns whether a snapshot
+    /// was written. Beyond the retention count, the oldest snapshots are
+    /// dropped while the project's snapshots take more than
+    /// `max_total_bytes` (the newest is always kept).
+    pub fn insert_auto_save(
+        &self,
+        project: &Project,
+        max_total_bytes: Option<u64>,
+    ) -> Result<bool, String> {
+        let mut stored = project.clone();
+        if let Some(dir) = &self.cache_dir {
+            store_project_paths(&mut stored, dir);
+        }
+        let project_json = serde_json::to_vec(&stored)
+            .map_err(|e| format!("Failed to serialize project: {}", e))?;
+        let content_hash = format!("{:x}", Sha256::digest(&project_json));
+
+        let conn = self.conn.lock().unwrap();
+        let latest_hash: Option<Option<String>> = conn
+            .query_row(
+                "SELECT content_hash FROM auto_saves WHERE project_id = ?1
+                 ORDER BY saved_at DESC, id DESC LIMIT 1",
+                rusqlite::params![project.id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read auto-saves: {}", e))?;
+        if latest_hash.flatten().as_deref() == Some(content_hash.as_str()) {
+            return Ok(false);
+        }
+
+        let compressed = zstd::encode_all(project_json.as_slice(), AUTO_SAVE_ZSTD_LEVEL)
+            .map_err(|e| format!("Failed to compress auto-save: {}", e))?;
+        conn.execute(
+            "INSERT INTO auto_saves
+             (project_id, project_name, saved_at, project_json, file_size, compression, content_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'zstd', ?6)",
+            rusqlite::params![
+                project.id,
+                project.name,
+                chrono::Utc::now().to_rfc3339(),
+                compressed,
+                compressed.len() as i64,
+                content_hash,
+            ],
+        )
+        .map_err(|e| format!("Failed to store auto-save: {}", e))?;
+
+        cleanup_old_autosaves(&conn, &project.id, AUTO_SAVE_RETENTION)
+            .map_err(|e| format!("Failed to prune auto-saves: {}", e))?;
+        if let Some(max_total_bytes) = max_total_bytes {
+            cleanup_oversized_autosaves(&conn, &project.id, max_total_bytes)
+                .map_err(|e| format!("Failed to prune auto-saves: {}", e))?;
+        }
+
+        Ok(true)
+    }
+
+    /// The latest auto-save snapshot of a project
+    ///
+    /// Reads compressed snapshots and the plain JSON ones older versions wrote.
+    pub fn get_latest_auto_save(&self, project_id: &str) -> Result<Option<Project>, String> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT project_json, compression FROM auto_saves WHERE project_id = ?1
+                 ORDER BY saved_at DESC, id DESC LIMIT 1",
+                rusqlite::params![project_id],
+                |row| {
+                    let data = match row.get_ref(0)? {
+                        rusqlite::types::ValueRef::Text(text) => text.to_vec(),
+                        rusqlite::types::ValueRef::Blob(blob) => blob.to_vec(),
+                        _ => Vec::new(),
+                    };
+                    Ok((data, row.get::<_, Option<String>>(1)?))
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read auto-save: {}", e))?;
+        let Some((data, compression)) = row else {
+            return Ok(None);
+        };
+
+        let project_json = match compression.as_deref() {
+            None => data,
+            Some("zstd") => zstd::decode_all(data.as_slice())
+                .map_err(|e| format!("Failed to decompress auto-save: {}", e))?,
+            Some(other) => return Err(format!("Unknown auto-save compression '{}'", other)),
+        };
+        let mut project: Project = serde_json::from_slice(&project_json)
+            .map_err(|e| format!("Failed to parse auto-save: {}", e))?;
+        if let Some(dir) = &self.cache_dir {
+            resolve_project_paths(&mut project, dir);
+        }
+        Ok(Some(project))
+    }
+
+    /// Insert or update a recording session row
+    /// Written when recording starts and updated when it stops or fails
+    pub fn upsert_recording_session(&self, session: &RecordingSession) -> Result<(), String> {
+        let audio_sources = serde_json::to_string(&session.audio_sources)
+            .map_err(|e| format!("Failed to serialize audio sources: {}", e))?;
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO recording_sessions
+             (id, recording_type, status, output_path, started_at, stopped_at, duration,
+              screen_source, camera_device, audio_sources, resolution, fps, error_message,
+              created_media_clip_id, actual_fps, actual_resolution)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+             ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                started_at = excluded.started_at,
+                stopped_at = excluded.stopped_at,
+                duration = excluded.duration,
+                error_message = excluded.error_message,
+                created_media_clip_id = excluded.created_media_clip_id,
+                actual_fps = excluded.actual_fps,
+                actual_resolution = excluded.actual_resolution",
+            rusqlite::params![
+                session.id,
+                session.recording_type.as_str(),
+                session.status.as_str(),
+                session.output_path,
+                session.started_at.map(|t| t.to_rfc3339()),
+                session.stopped_at.map(|t| t.to_rfc3339()),
+                session.duration,
+                session.screen_source,
+                session.camera_device,
+                audio_sources,
+                session.resolution,
+                session.fps,
+                session.error_message,
+                session.created_media_clip_id,
+                session.actual_fps,
+                session.actual_resolution,
+            ],
+        )
+        .map_err(|e| format!("Failed to record recording session: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Get recording sessions, most recent first
+    pub fn get_recording_history(&self, limit: usize) -> Result<Vec<RecordingSession>, String> {
+        self.query_recording_sessions(
+            "SELECT id, recording_type, status, output_path, started_at, stopped_at, duration,
+                    screen_source, camera_device, audio_sources, resolution, fps, error_message,
+                    created_media_clip_id, actual_fps, actual_resolution
+             FROM recording_sessions
+             ORDER BY started_at DESC
+             LIMIT ?1",
+            rusqlite::params![limit],
+        )
+    }
+
+    /// Get sessions that were still recording when the app last exited
+    ///
+    /// These are crash-recovery candidates when their output file exists on disk.
+    pub fn get_interrupted_recordings(&self) -> Result<Vec<RecordingSession>, String> {
+        self.query_recording_sessions(
+            "SELECT id, recording_type, status, output_path, started_at, stopped_at, duration,
+                    screen_source, camera_device, audio_sources, resolution, fps, error_message,
+                    created_media_clip_id, actual_fps, actual_resolution
+             FROM recording_sessions
+             WHERE status = 'recording' AND stopped_at IS NULL
+             ORDER BY started_at DESC",
+            [],
+        )
+    }
+
+    fn query_recording_sessions<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<RecordingSession>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to query recording sessions: {}", e))?;
+
+        let sessions = stmt
+            .query_map(params, recording_session_from_row)
+            .map_err(|e| format!("Failed to query recording sessions: {}", e))?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("Failed to read recording session: {}", e))?;
+
+        Ok(sessions)
+    }
+
+    /// Insert or update an export job history row
+    /// Called on every job transition (start, complete, fail, cancel)
+    pub fn upsert_export_job(&self, entry: &ExportHistoryEntry) -> Result<(), String> {
+        let settings_json = serde_json::to_string(&entry.settings)
+            .map_err(|e| format!("Failed to serialize export settings: {}", e))?;
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO export_jobs
+             (job_id, project_id, output_path, settings_json, codec, status, started_at,
+              finished_at, duration, timeline_duration, output_size, error, error_log,
+              retry_of)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+             ON CONFLICT(job_id) DO UPDATE SET
+                status = excluded.status,
+                finished_at = excluded.finished_at,
+                duration = excluded.duration,
+                output_size = excluded.output_size,
+                error = excluded.error,
+                error_log = excluded.error_log",
+            rusqlite::params![
+                entry.job_id,
+                entry.project_id,
+                entry.output_path,
+                settings_json,
+                entry.settings.codec.ffmpeg_codec(),
+                entry.status.as_str(),
+                entry.started_at.to_rfc3339(),
+                entry.finished_at.map(|t| t.to_rfc3339()),
+                entry.duration,
+                entry.timeline_duration,
+                entry.output_size,
+                entry.error,
+                entry.error_log,
+                entry.retry_of,
+            ],
+        )
+        .map_err(|e| format!("Failed to record export job: {}", e))?;
+
+        cleanup_old_export_jobs(&conn, EXPORT_HISTORY_RETENTION)
+            .map_err(|e| format!("Failed to prune export history: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Get export history (most recent first), optionally filtered by project
+    pub fn get_export_history(
+        &self,
+        limit: usize,
+        project_id: Option<&str>,
+    ) -> Result<Vec<ExportHistoryEntry>, String> {
+        self.query_export_jobs(
+            "SELECT job_id, project_id, output_path, settings_json, status, started_at,
+                    finished_at, duration, timeline_duration, output_size, error, error_log,
+                    retry_of
+             FROM export_jobs
+             WHERE ?1 IS NULL OR project_id = ?1
+             ORDER BY started_at DESC
+             LIMIT ?2",
+            rusqlite::params![project_id, limit],
+        )
+    }
+
+    /// Get one export job's history row
+    pub fn get_export_job(&self, job_id: &str) -> Result<Option<ExportHistoryEntry>, String> {
+        let mut entries = self.query_export_jobs(
+            "SELECT job_id, project_id, output_path, settings_json, status, started_at,
+                    finished_at, duration, timeline_duration, output_size, error, error_log,
+                    retry_of
+             FROM export_jobs
+             WHERE job_id = ?1",
+            rusqlite::params![job_id],
+        )?;
+        Ok(entries.pop())
+    }
+
+    /// Export jobs selected by `sql`, skipping rows whose settings no longer
+    /// parse
+    fn query_export_jobs<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<ExportHistoryEntry>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to query export history: {}", e))?;
+
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<f64>>(7)?,
+                    row.get::<_, f64>(8)?,
+                    row.get::<_, Option<i64>>(9)?,
+                    row.get::<_, Option<String>>(10)?,
+                    row.get::<_, Option<String>>(11)?,
+                    row.get::<_, Option<String>>(12)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to query export history: {}", e))?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (
+                job_id,
+                project_id,
+                output_path,
+                settings_json,
+                status,
+                started_at,
+                finished_at,
+                duration,
+                timeline_duration,
+                output_size,
+                error,
+                error_log,
+                retry_of,
+            ) = row.map_err(|e| format!("Failed to read export job: {}", e))?;
+
+            let settings = match serde_json::from_str(&settings_json) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    log::warn!("Skipping export history job {}: {}", job_id, e);
+                    continue;
+                }
+            };
+
+            entries.push(ExportHistoryEntry {
+                job_id,
+                project_id,
+                output_path,
+                settings,
+                status: ExportStatus::parse(&status).unwrap_or(ExportStatus::Failed),
+                started_at: parse_timestamp(&started_at)?,
+                finished_at: finished_at.as_deref().map(parse_timestamp).transpose()?,
+                duration,
+                timeline_duration,
+                output_size,
+                error,
+                error_log,
+                retry_of,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Delete all export history rows, returning the number removed
+    pub fn clear_export_history(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM export_jobs", [])
+            .map_err(|e| format!("Failed to clear export history: {}", e))
+    }
+
+    /// Average encoder speed factor (timeline seconds rendered per wall-clock second)
+    /// for completed exports with the given FFmpeg video codec
+    ///
+    /// Backs the export time estimate; None until at least one export has completed.
+    pub fn get_encoder_speed_factor(&self, ffmpeg_codec: &str) -> Result<Option<f64>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT AVG(timeline_duration / duration) FROM (
+                SELECT timeline_duration, duration FROM export_jobs
+                WHERE codec = ?1 AND status = 'complete' AND duration > 0
+                ORDER BY started_at DESC
+                LIMIT 20
+             )",
+            rusqlite::params![ffmpeg_codec],
+            |row| row.get::<_, Option<f64>>(0),
+        )
+        .map_err(|e| format!("Failed to compute encoder speed: {}", e))
+    }
+
+    /// Get a single raw setting value (JSON-encoded)
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            rusqlite::params![key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read setting '{}': {}", key, e))
+    }
+}
+
+/// Whether `error` means the database file is damaged or not a database,
+/// rather than unreachable for now
+fn is_corruption(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase)
+    )
 }
 
 /// Initialize the SQLite cache database
@@ -91,6 +991,11 @@ fn create_schema(conn: &Connection) -> SqliteResult<()> {
         )",
         [],
     )?;
+    add_column_if_missing(conn, "media_clips", "proxy_version", "INTEGER")?;
+    add_column_if_missing(conn, "media_clips", "hover_sprite_path", "TEXT")?;
+    add_column_if_missing(conn, "media_clips", "audio_streams", "TEXT")?;
+    add_column_if_missing(conn, "media_clips", "audio_stream_index", "INTEGER")?;
+    add_column_if_missing(conn, "media_clips", "thumbnail_2x_path", "TEXT")?;
 
     // Auto-saves table
     // Stores periodic snapshots of project state for crash recovery
@@ -106,6 +1011,10 @@ fn create_schema(conn: &Connection) -> SqliteResult<()> {
         [],
     )?;
 
+    // NULL compression marks the plain JSON snapshots of older versions
+    add_column_if_missing(conn, "auto_saves", "compression", "TEXT")?;
+    add_column_if_missing(conn, "auto_saves", "content_hash", "TEXT")?;
+
     // Index for fast auto-save queries (most recent first)
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_auto_saves_project_id 
@@ -113,6 +1022,146 @@ fn create_schema(conn: &Connection) -> SqliteResult<()> {
         [],
     )?;
 
+    // Recording session history
+    // One row per recording, written at start and updated at stop/fail
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recording_sessions (
+            id TEXT PRIMARY KEY,
+            recording_type TEXT NOT NULL,
+            status TEXT NOT NULL,
+            output_path TEXT NOT NULL,
+            started_at TEXT,
+            stopped_at TEXT,
+            duration REAL,
+            screen_source TEXT,
+            camera_device TEXT,
+            audio_sources TEXT NOT NULL,
+            resolution TEXT NOT NULL,
+            fps INTEGER NOT NULL,
+            error_message TEXT,
+            created_media_clip_id TEXT
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "recording_sessions", "actual_fps", "REAL")?;
+    add_column_if_missing(conn, "recording_sessions", "actual_resolution", "TEXT")?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_recording_sessions_started_at
+         ON recording_sessions(started_at DESC)",
+        [],
+    )?;
+
+    // Export job history
+    // One row per export job, updated on each status transition
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS export_jobs (
+            job_id TEXT PRIMARY KEY,
+            project_id TEXT,
+            output_path TEXT NOT NULL,
+            settings_json TEXT NOT NULL,
+            codec TEXT NOT NULL,
+            status TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            duration REAL,
+            timeline_duration REAL NOT NULL,
+            output_size INTEGER,
+            error TEXT
+        )",
+        [],
+    )?;
+    // FFmpeg's last stderr lines for a failed job, and the job a retry repeats
+    add_column_if_missing(conn, "export_jobs", "error_log", "TEXT")?;
+    add_column_if_missing(conn, "export_jobs", "retry_of", "TEXT")?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_export_jobs_started_at
+         ON export_jobs(project_id, started_at DESC)",
+        [],
+    )?;
+
+    // Recently opened/saved project files
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recent_projects (
+            path TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            opened_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Application settings
+    // One row per top-level AppSettings field, value stored as JSON
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Rewrite absolute cache file paths under `cache_dir` as cache-relative
+///
+/// Runs once per database (tracked with `PRAGMA user_version`); paths
+/// outside the directory stay absolute.
+fn migrate_cache_relative_paths(conn: &Connection, cache_dir: &str) -> SqliteResult<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= CACHE_RELATIVE_PATHS_VERSION {
+        return Ok(());
+    }
+
+    let rows = {
+        let mut stmt = conn
+            .prepare("SELECT id, proxy_path, thumbnail_path, hover_sprite_path FROM media_clips")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        rows
+    };
+    let relative = |path: &Option<String>| path.as_deref().map(|p| store_cache_path(p, cache_dir));
+    for (id, proxy, thumbnail, sprite) in &rows {
+        conn.execute(
+            "UPDATE media_clips SET proxy_path = ?2, thumbnail_path = ?3, hover_sprite_path = ?4
+             WHERE id = ?1",
+            rusqlite::params![id, relative(proxy), relative(thumbnail), relative(sprite)],
+        )?;
+    }
+    conn.execute_batch(&format!(
+        "PRAGMA user_version = {}",
+        CACHE_RELATIVE_PATHS_VERSION
+    ))
+}
+
+/// Add a nullable column to a table created by an older version of the app
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> SqliteResult<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
     Ok(())
 }
 
@@ -136,6 +1185,155 @@ pub fn cleanup_old_autosaves(
     )
 }
 
+/// Drop a project's oldest auto-saves while its snapshots together take
+/// more than `max_total_bytes`; the newest one is kept whatever its size
+fn cleanup_oversized_autosaves(
+    conn: &Connection,
+    project_id: &str,
+    max_total_bytes: u64,
+) -> SqliteResult<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT id, file_size FROM auto_saves WHERE project_id = ?1
+         ORDER BY saved_at DESC, id DESC",
+    )?;
+    let sizes = stmt
+        .query_map(rusqlite::params![project_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut total = 0u64;
+    let mut deleted = 0;
+    for (i, (id, size)) in sizes.into_iter().enumerate() {
+        total += size.max(0) as u64;
+        if i > 0 && total > max_total_bytes {
+            deleted += conn.execute("DELETE FROM auto_saves WHERE id = ?1", [id])?;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Clean up old export jobs (keep only the N most recent)
+fn cleanup_old_export_jobs(conn: &Connection, keep_count: usize) -> SqliteResult<usize> {
+    conn.execute(
+        "DELETE FROM export_jobs
+         WHERE job_id NOT IN (
+             SELECT job_id FROM export_jobs
+             ORDER BY started_at DESC
+             LIMIT ?1
+         )",
+        rusqlite::params![keep_count],
+    )
+}
+
+/// Database size in bytes (page_count * page_size)
+/// Files under `dir` with their size and modification time
+fn collect_cache_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_cache_files(&entry.path(), files);
+        } else {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), metadata.len(), modified));
+        }
+    }
+}
+
+fn database_size(conn: &Connection) -> Result<u64, String> {
+    let page_count: i64 = conn
+        .query_row("PRAGMA page_count", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read database size: {}", e))?;
+    let page_size: i64 = conn
+        .query_row("PRAGMA page_size", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read database size: {}", e))?;
+    Ok((page_count * page_size) as u64)
+}
+
+/// Audio stream list as stored in the `audio_streams` column
+fn audio_streams_json(clip: &MediaClip) -> Result<String, String> {
+    serde_json::to_string(&clip.audio_streams)
+        .map_err(|e| format!("Failed to serialize audio streams: {}", e))
+}
+
+/// Build a MediaClip from a `media_clips` row (captions are not cached)
+fn media_clip_from_row(row: &rusqlite::Row) -> SqliteResult<MediaClip> {
+    let imported_at: String = row.get(15)?;
+    // Rows written before stream probing have no list
+    let audio_streams: Option<String> = row.get(17)?;
+    Ok(MediaClip {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source_path: row.get(2)?,
+        proxy_path: row.get(3)?,
+        thumbnail_path: row.get(4)?,
+        thumbnail_2x_path: row.get(19)?,
+        duration: row.get(5)?,
+        resolution: row.get(6)?,
+        width: row.get(7)?,
+        height: row.get(8)?,
+        fps: row.get(9)?,
+        codec: row.get(10)?,
+        audio_codec: row.get(11)?,
+        file_size: row.get(12)?,
+        bitrate: row.get(13)?,
+        has_audio: row.get(14)?,
+        imported_at: parse_timestamp(&imported_at).unwrap_or_else(|_| chrono::Utc::now()),
+        captions: vec![],
+        proxy_version: row.get(16)?,
+        audio_streams: audio_streams
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        audio_stream_index: row.get(18)?,
+        // Analysis results live in the project file
+        suggested_trim: None,
+        loudness: None,
+        origin_url: None,
+    })
+}
+
+/// Build a RecordingSession from a `recording_sessions` row
+fn recording_session_from_row(row: &rusqlite::Row) -> SqliteResult<RecordingSession> {
+    let recording_type: String = row.get(1)?;
+    let status: String = row.get(2)?;
+    let started_at: Option<String> = row.get(4)?;
+    let stopped_at: Option<String> = row.get(5)?;
+    let audio_sources: String = row.get(9)?;
+
+    Ok(RecordingSession {
+        id: row.get(0)?,
+        recording_type: RecordingType::parse(&recording_type).unwrap_or(RecordingType::Screen),
+        status: RecordingStatus::parse(&status).unwrap_or(RecordingStatus::Failed),
+        output_path: row.get(3)?,
+        started_at: started_at.and_then(|t| parse_timestamp(&t).ok()),
+        stopped_at: stopped_at.and_then(|t| parse_timestamp(&t).ok()),
+        duration: row.get(6)?,
+        screen_source: row.get(7)?,
+        camera_device: row.get(8)?,
+        audio_sources: serde_json::from_str(&audio_sources).unwrap_or_default(),
+        resolution: row.get(10)?,
+        fps: row.get(11)?,
+        error_message: row.get(12)?,
+        created_media_clip_id: row.get(13)?,
+        actual_fps: row.get(14)?,
+        actual_resolution: row.get(15)?,
+        post_process: Default::default(),
+        auto_add_to_timeline: None,
+    })
+}
+
+/// Parse an RFC 3339 timestamp stored in the database
+fn parse_timestamp(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .map_err(|e| format!("Invalid timestamp '{}': {}", value, e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +1379,589 @@ mod tests {
         assert!(table_count >= 2, "Tables should still exist");
     }
 
+    #[test]
+    fn test_schema_adds_missing_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("test_cache.db");
+
+        // media_clips as created before proxy versions were tracked
+        Connection::open(&cache_path)
+            .unwrap()
+            .execute(
+                "CREATE TABLE media_clips (
+                    id TEXT PRIMARY KEY, name TEXT NOT NULL, source_path TEXT NOT NULL,
+                    proxy_path TEXT, thumbnail_path TEXT, duration REAL NOT NULL,
+                    resolution TEXT NOT NULL, width INTEGER NOT NULL, height INTEGER NOT NULL,
+                    fps REAL NOT NULL, codec TEXT NOT NULL, audio_codec TEXT,
+                    file_size INTEGER NOT NULL, bitrate INTEGER, has_audio INTEGER NOT NULL,
+                    imported_at TEXT NOT NULL, UNIQUE(source_path)
+                )",
+                [],
+            )
+            .unwrap();
+
+        let conn = initialize_cache(&cache_path).unwrap();
+        let columns: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('media_clips') WHERE name = 'proxy_version'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(columns, 1);
+    }
+
+    #[test]
+    fn test_cache_paths_migrate_to_relative() {
+        let temp_dir = TempDir::new().unwrap();
+        let old_root = temp_dir.path().join("old");
+        std::fs::create_dir_all(&old_root).unwrap();
+        let cache_path = old_root.join("clipforge.db");
+        let root = old_root.to_string_lossy().to_string();
+
+        // Rows written by a version that stored absolute paths
+        let conn = initialize_cache(&cache_path).unwrap();
+        conn.execute(
+            "INSERT INTO media_clips (id, name, source_path, proxy_path, thumbnail_path,
+                duration, resolution, width, height, fps, codec, file_size, has_audio,
+                imported_at, hover_sprite_path)
+             VALUES ('clip-1', 'a', '/media/a.mov', ?1, '/elsewhere/a.jpg', 10.0,
+                '1920x1080', 1920, 1080, 30.0, 'prores', 1, 1, '2024-01-01T00:00:00Z', ?2)",
+            rusqlite::params![
+                format!("{}/proxies/clip-1.mp4", root),
+                format!("{}/sprites/clip-1.jpg", root)
+            ],
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = CacheDb::new(&cache_path).unwrap();
+        let stored: (String, String, String) = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT proxy_path, thumbnail_path, hover_sprite_path FROM media_clips",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            stored,
+            (
+                "$CACHE/proxies/clip-1.mp4".to_string(),
+                "/elsewhere/a.jpg".to_string(),
+                "$CACHE/sprites/clip-1.jpg".to_string()
+            )
+        );
+        drop(db);
+
+        // The cache directory moved: paths follow it
+        let new_root = temp_dir.path().join("new");
+        std::fs::rename(&old_root, &new_root).unwrap();
+        let db = CacheDb::new(&new_root.join("clipforge.db")).unwrap();
+        let clip = db
+            .get_media_clip_by_source_path("/media/a.mov")
+            .unwrap()
+            .unwrap();
+        let expected = new_root.join("proxies").join("clip-1.mp4");
+        assert_eq!(clip.proxy_path.map(PathBuf::from), Some(expected));
+        assert_eq!(clip.thumbnail_path.as_deref(), Some("/elsewhere/a.jpg"));
+        assert_eq!(
+            db.get_hover_sprite_path("clip-1")
+                .unwrap()
+                .map(PathBuf::from),
+            Some(new_root.join("sprites").join("clip-1.jpg"))
+        );
+
+        // New writes are stored relative too
+        db.set_thumbnail_path(
+            "clip-1",
+            &new_root.join("thumbnails").join("a.jpg").to_string_lossy(),
+            None,
+        )
+        .unwrap();
+        let thumbnail: String = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT thumbnail_path FROM media_clips", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(thumbnail, "$CACHE/thumbnails/a.jpg");
+    }
+
+    #[test]
+    fn test_open_or_rebuild_healthy_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("clipforge.db");
+
+        let (db, corrupt) = CacheDb::open_or_rebuild(&db_path).unwrap();
+        assert!(corrupt.is_none());
+        db.insert_media_clip(&mock_media_clip("clip-1", "/videos/a.mp4"))
+            .unwrap();
+        drop(db);
+
+        // Reopening keeps the data
+        let (db, corrupt) = CacheDb::open_or_rebuild(&db_path).unwrap();
+        assert!(corrupt.is_none());
+        assert!(db
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_open_or_rebuild_leaves_locked_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("clipforge.db");
+        {
+            let db = CacheDb::new(&db_path).unwrap();
+            db.insert_media_clip(&mock_media_clip("clip-1", "/videos/a.mp4"))
+                .unwrap();
+        }
+
+        // Another instance holding the database
+        let other = Connection::open(&db_path).unwrap();
+        other
+            .execute_batch("PRAGMA locking_mode = EXCLUSIVE; BEGIN EXCLUSIVE;")
+            .unwrap();
+        assert!(CacheDb::open_or_rebuild(&db_path).is_err());
+        drop(other);
+
+        // Nothing was moved aside
+        let files: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert!(files.iter().all(|name| !name.contains(".corrupt-")));
+        let (db, corrupt) = CacheDb::open_or_rebuild(&db_path).unwrap();
+        assert!(corrupt.is_none());
+        assert!(db
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_open_or_rebuild_truncated_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("clipforge.db");
+
+        {
+            let db = CacheDb::new(&db_path).unwrap();
+            for i in 0..50 {
+                db.insert_media_clip(&mock_media_clip(
+                    &format!("clip-{}", i),
+                    &format!("/videos/{}.mp4", i),
+                ))
+                .unwrap();
+            }
+        }
+
+        // Simulate a power loss mid-write: keep only part of the file
+        let bytes = std::fs::read(&db_path).unwrap();
+        std::fs::write(&db_path, &bytes[..bytes.len() / 3]).unwrap();
+
+        // Opening as it is reports the damage and moves nothing
+        assert!(CacheDb::open_existing(&db_path).is_err());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let (db, corrupt) = CacheDb::open_or_rebuild(&db_path).unwrap();
+        let corrupt = corrupt.expect("Truncated database should be rebuilt");
+
+        assert!(corrupt.exists());
+        assert!(corrupt
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("clipforge.db.corrupt-"));
+
+        // Fresh database is usable and empty
+        assert!(db
+            .get_media_clip_by_source_path("/videos/1.mp4")
+            .unwrap()
+            .is_none());
+        db.insert_media_clip(&mock_media_clip("clip-new", "/videos/new.mp4"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_run_maintenance_reports_sizes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        for i in 0..200 {
+            db.insert_media_clip(&mock_media_clip(
+                &format!("clip-{}", i),
+                &format!("/videos/{}.mp4", i),
+            ))
+            .unwrap();
+        }
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM media_clips", [])
+            .unwrap();
+
+        let report = db.run_maintenance(u64::MAX).unwrap();
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.evicted, Eviction::default());
+        assert!(report.size_before > 0);
+        assert!(report.size_after < report.size_before);
+    }
+
+    #[test]
+    fn test_evict_to_size_cap_removes_oldest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+        let write = |dir: &str, name: &str, age_secs: u64| {
+            let dir = temp_dir.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_len(1000).unwrap();
+            file.set_modified(SystemTime::now() - std::time::Duration::from_secs(age_secs))
+                .unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let proxy = write("proxies", "clip-1.mp4", 300);
+        let sprite = write("hover_sprites", "clip-1_10.jpg", 200);
+        let thumbnail = write("thumbnails", "clip-1.jpg", 100);
+        let mut clip = mock_media_clip("clip-1", "/videos/1.mp4");
+        clip.proxy_path = Some(proxy.clone());
+        clip.thumbnail_path = Some(thumbnail.clone());
+        db.insert_media_clip(&clip).unwrap();
+
+        // Under the cap: nothing goes
+        assert_eq!(db.evict_to_size_cap(3000).unwrap(), Eviction::default());
+
+        let evicted = db.evict_to_size_cap(1500).unwrap();
+        assert_eq!(
+            evicted,
+            Eviction {
+                files: 2,
+                bytes: 2000
+            }
+        );
+        assert!(!Path::new(&proxy).exists() && !Path::new(&sprite).exists());
+        assert!(Path::new(&thumbnail).exists());
+        let clip = db
+            .get_media_clip_by_source_path("/videos/1.mp4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(clip.proxy_path, None);
+        assert_eq!(clip.thumbnail_path, Some(thumbnail));
+    }
+
+    fn mock_media_clip(id: &str, source_path: &str) -> MediaClip {
+        MediaClip {
+            id: id.to_string(),
+            ..MediaClip::new(
+                source_path.to_string(),
+                10.0,
+                1920,
+                1080,
+                30.0,
+                "h264".to_string(),
+                1024,
+            )
+        }
+    }
+
+    #[test]
+    fn test_media_clip_lookup_and_update_in_place() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        assert!(db
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .is_none());
+
+        let clip = mock_media_clip("clip-1", "/videos/a.mp4");
+        db.insert_media_clip(&clip).unwrap();
+
+        // Inserting the same source path again is rejected instead of replacing the row
+        assert!(db
+            .insert_media_clip(&mock_media_clip("clip-2", "/videos/a.mp4"))
+            .is_err());
+
+        let mut updated = db
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.id, "clip-1");
+        assert_eq!(updated.width, 1920);
+
+        updated.duration = 12.5;
+        updated.proxy_path = Some("/cache/proxies/clip-1.mp4".to_string());
+        updated.proxy_version = Some(2);
+        updated.audio_streams = vec![crate::models::clip::MediaStream {
+            index: 2,
+            stream_type: "audio".to_string(),
+            codec: Some("opus".to_string()),
+            language: Some("eng".to_string()),
+            title: Some("Mic".to_string()),
+            channels: Some(1),
+            channel_layout: Some("mono".to_string()),
+        }];
+        updated.audio_stream_index = Some(2);
+        db.update_media_clip(&updated).unwrap();
+
+        let reloaded = db
+            .get_media_clip_by_source_path("/videos/a.mp4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reloaded.id, "clip-1");
+        assert_eq!(reloaded.duration, 12.5);
+        assert_eq!(reloaded.proxy_path, updated.proxy_path);
+        assert_eq!(reloaded.proxy_version, Some(2));
+        assert_eq!(reloaded.audio_streams, updated.audio_streams);
+        assert_eq!(reloaded.audio_stream_index, Some(2));
+
+        assert_eq!(db.get_hover_sprite_path("clip-1").unwrap(), None);
+        db.set_hover_sprite_path("clip-1", Some("/cache/hover_sprites/clip-1_20.jpg"))
+            .unwrap();
+        assert_eq!(
+            db.get_hover_sprite_path("clip-1").unwrap().as_deref(),
+            Some("/cache/hover_sprites/clip-1_20.jpg")
+        );
+        // Updating clip metadata keeps the sprite
+        db.update_media_clip(&reloaded).unwrap();
+        assert!(db.get_hover_sprite_path("clip-1").unwrap().is_some());
+
+        // Updating an unknown id is an error
+        assert!(db
+            .update_media_clip(&mock_media_clip("missing", "/videos/b.mp4"))
+            .is_err());
+    }
+
+    fn mock_recording_session(output_path: &str) -> RecordingSession {
+        let mut session = RecordingSession::new(
+            RecordingType::ScreenWebcam,
+            output_path.to_string(),
+            "1920x1080".to_string(),
+            30,
+        );
+        session.screen_source = Some("screen-1".to_string());
+        session.camera_device = Some("camera-1".to_string());
+        session.audio_sources = vec!["mic-1".to_string(), "system".to_string()];
+        session
+    }
+
+    #[test]
+    fn test_recording_session_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        // Written at start
+        let mut session = mock_recording_session("/tmp/recording_1.mp4");
+        session.start();
+        db.upsert_recording_session(&session).unwrap();
+
+        let history = db.get_recording_history(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, session.id);
+        assert_eq!(history[0].recording_type, RecordingType::ScreenWebcam);
+        assert_eq!(history[0].status, RecordingStatus::Recording);
+        assert_eq!(history[0].audio_sources, session.audio_sources);
+        assert_eq!(history[0].camera_device.as_deref(), Some("camera-1"));
+        assert_eq!(history[0].fps, 30);
+        assert!(history[0].stopped_at.is_none());
+
+        // Still recording with no stop timestamp: recovery candidate
+        assert_eq!(db.get_interrupted_recordings().unwrap().len(), 1);
+
+        // Updated at stop
+        session.stop();
+        session.created_media_clip_id = Some("clip-1".to_string());
+        session.record_delivered(29.97, 1920, 1080);
+        db.upsert_recording_session(&session).unwrap();
+
+        let history = db.get_recording_history(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, RecordingStatus::Stopped);
+        assert!(history[0].stopped_at.is_some());
+        assert_eq!(history[0].duration, session.duration);
+        assert_eq!(history[0].created_media_clip_id.as_deref(), Some("clip-1"));
+        assert_eq!(history[0].actual_fps, Some(29.97));
+        assert_eq!(history[0].actual_resolution.as_deref(), Some("1920x1080"));
+        assert!(db.get_interrupted_recordings().unwrap().is_empty());
+
+        // Failed session keeps its error message
+        let mut failed = mock_recording_session("/tmp/recording_2.mp4");
+        failed.start();
+        failed.fail("Capture device disconnected".to_string());
+        db.upsert_recording_session(&failed).unwrap();
+
+        let history = db.get_recording_history(1).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, RecordingStatus::Failed);
+        assert_eq!(
+            history[0].error_message.as_deref(),
+            Some("Capture device disconnected")
+        );
+    }
+
+    fn mock_export_entry(job_id: &str, project_id: &str) -> ExportHistoryEntry {
+        ExportHistoryEntry::started(
+            job_id.to_string(),
+            Some(project_id.to_string()),
+            format!("/tmp/{}.mp4", job_id),
+            crate::models::export::ExportSettings::default(),
+            60.0,
+        )
+    }
+
+    #[test]
+    fn test_export_history_records_each_transition() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        // Start
+        let mut entry = mock_export_entry("job-1", "project-1");
+        db.upsert_export_job(&entry).unwrap();
+        let history = db.get_export_history(10, None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, ExportStatus::Queued);
+        assert!(history[0].finished_at.is_none());
+
+        // Rendering
+        entry.status = ExportStatus::Rendering;
+        db.upsert_export_job(&entry).unwrap();
+        assert_eq!(
+            db.get_export_history(10, None).unwrap()[0].status,
+            ExportStatus::Rendering
+        );
+
+        // Fail
+        entry.finish(ExportStatus::Failed, Some("Encoder error".to_string()));
+        db.upsert_export_job(&entry).unwrap();
+        let history = db.get_export_history(10, None).unwrap();
+        assert_eq!(history.len(), 1, "Transitions update the same row");
+        assert_eq!(history[0].status, ExportStatus::Failed);
+        assert_eq!(history[0].error.as_deref(), Some("Encoder error"));
+        assert!(history[0].finished_at.is_some());
+        assert!(history[0].duration.is_some());
+
+        // A second job that gets cancelled
+        let mut cancelled = mock_export_entry("job-2", "project-2");
+        db.upsert_export_job(&cancelled).unwrap();
+        cancelled.finish(ExportStatus::Cancelled, None);
+        db.upsert_export_job(&cancelled).unwrap();
+
+        let history = db.get_export_history(10, None).unwrap();
+        assert_eq!(history.len(), 2);
+        let project_history = db.get_export_history(10, Some("project-2")).unwrap();
+        assert_eq!(project_history.len(), 1);
+        assert_eq!(project_history[0].status, ExportStatus::Cancelled);
+
+        assert_eq!(db.clear_export_history().unwrap(), 2);
+        assert!(db.get_export_history(10, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_history_keeps_error_log_and_retry_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        let mut failed = mock_export_entry("job-1", "project-1");
+        db.upsert_export_job(&failed).unwrap();
+        failed.finish(ExportStatus::Failed, Some("Your disk is full".to_string()));
+        failed.error_log = Some("frame= 1200\nConversion failed!".to_string());
+        db.upsert_export_job(&failed).unwrap();
+
+        let mut retry = ExportHistoryEntry {
+            retry_of: Some("job-1".to_string()),
+            ..mock_export_entry("job-2", "project-1")
+        };
+        retry.started_at += chrono::Duration::seconds(1);
+        db.upsert_export_job(&retry).unwrap();
+        retry.finish(ExportStatus::Complete, None);
+        db.upsert_export_job(&retry).unwrap();
+
+        let stored = db.get_export_job("job-1").unwrap().unwrap();
+        assert_eq!(stored.status, ExportStatus::Failed);
+        assert_eq!(stored.error.as_deref(), Some("Your disk is full"));
+        assert_eq!(
+            stored.error_log.as_deref(),
+            Some("frame= 1200\nConversion failed!")
+        );
+        assert_eq!(stored.retry_of, None);
+
+        let history = db.get_export_history(10, Some("project-1")).unwrap();
+        assert_eq!(history[0].job_id, "job-2");
+        assert_eq!(history[0].retry_of.as_deref(), Some("job-1"));
+        assert_eq!(history[0].error_log, None);
+        assert!(db.get_export_job("job-3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recent_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        db.add_recent_project("/projects/a.clipforge", "A").unwrap();
+        db.add_recent_project("/projects/b.clipforge", "B").unwrap();
+        // Re-adding moves the entry to the top and refreshes its name
+        db.add_recent_project("/projects/a.clipforge", "A v2")
+            .unwrap();
+
+        let recent = db.get_recent_projects().unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].path, "/projects/a.clipforge");
+        assert_eq!(recent[0].name, "A v2");
+
+        for i in 0..RECENT_PROJECTS_LIMIT + 3 {
+            db.add_recent_project(&format!("/projects/{}.clipforge", i), "P")
+                .unwrap();
+        }
+        assert_eq!(
+            db.get_recent_projects().unwrap().len(),
+            RECENT_PROJECTS_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_export_history_retention() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        for i in 0..(EXPORT_HISTORY_RETENTION + 5) {
+            let mut entry = mock_export_entry(&format!("job-{}", i), "project-1");
+            entry.started_at += chrono::Duration::seconds(i as i64);
+            db.upsert_export_job(&entry).unwrap();
+        }
+
+        let history = db.get_export_history(1000, None).unwrap();
+        assert_eq!(history.len(), EXPORT_HISTORY_RETENTION);
+        // Oldest jobs were pruned
+        assert!(!history.iter().any(|e| e.job_id == "job-0"));
+        assert_eq!(
+            history[0].job_id,
+            format!("job-{}", EXPORT_HISTORY_RETENTION + 4)
+        );
+    }
+
+    #[test]
+    fn test_encoder_speed_factor_from_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("test_cache.db")).unwrap();
+
+        assert_eq!(db.get_encoder_speed_factor("libx264").unwrap(), None);
+
+        let mut entry = mock_export_entry("job-1", "project-1");
+        entry.status = ExportStatus::Complete;
+        entry.finished_at = Some(entry.started_at);
+        entry.duration = Some(30.0); // 60s timeline rendered in 30s
+        db.upsert_export_job(&entry).unwrap();
+
+        let factor = db.get_encoder_speed_factor("libx264").unwrap().unwrap();
+        assert!((factor - 2.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_cleanup_old_autosaves() {
         let temp_dir = TempDir::new().unwrap();
@@ -219,4 +2000,111 @@ mod tests {
 
         assert_eq!(remaining, 3, "Should have 3 auto-saves remaining");
     }
+
+    fn auto_save_count(db: &CacheDb, project_id: &str) -> i64 {
+        db.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM auto_saves WHERE project_id = ?1",
+                rusqlite::params![project_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_auto_save_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+        let project = Project::new("Round Trip".to_string());
+
+        assert!(db.get_latest_auto_save(&project.id).unwrap().is_none());
+        assert!(db.insert_auto_save(&project, None).unwrap());
+
+        let (compression, stored): (Option<String>, Vec<u8>) = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT compression, project_json FROM auto_saves WHERE project_id = ?1",
+                rusqlite::params![project.id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(compression.as_deref(), Some("zstd"));
+        assert!(serde_json::from_slice::<Project>(&stored).is_err());
+
+        let restored = db.get_latest_auto_save(&project.id).unwrap().unwrap();
+        assert_eq!(restored.id, project.id);
+        assert_eq!(restored.name, "Round Trip");
+
+        // Rows written before compression existed hold plain JSON text
+        let mut legacy = project.clone();
+        legacy.name = "Legacy".to_string();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO auto_saves (project_id, project_name, saved_at, project_json, file_size)
+                 VALUES (?1, ?2, ?3, ?4, 0)",
+                rusqlite::params![
+                    legacy.id,
+                    legacy.name,
+                    (chrono::Utc::now() + chrono::Duration::seconds(1)).to_rfc3339(),
+                    serde_json::to_string(&legacy).unwrap(),
+                ],
+            )
+            .unwrap();
+        let restored = db.get_latest_auto_save(&project.id).unwrap().unwrap();
+        assert_eq!(restored.name, "Legacy");
+    }
+
+    #[test]
+    fn test_auto_save_skips_identical_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+        let mut project = Project::new("Unchanged".to_string());
+
+        assert!(db.insert_auto_save(&project, None).unwrap());
+        assert!(!db.insert_auto_save(&project, None).unwrap());
+        assert_eq!(auto_save_count(&db, &project.id), 1);
+
+        project.name = "Changed".to_string();
+        assert!(db.insert_auto_save(&project, None).unwrap());
+        assert_eq!(auto_save_count(&db, &project.id), 2);
+    }
+
+    #[test]
+    fn test_auto_save_size_cap_evicts_oldest() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+        let mut project = Project::new("Capped".to_string());
+
+        project.name = "Capped 0".to_string();
+        db.insert_auto_save(&project, None).unwrap();
+        let snapshot_size: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT file_size FROM auto_saves", [], |row| row.get(0))
+            .unwrap();
+
+        // Room for about two snapshots, well under the retention count
+        let cap = snapshot_size as u64 * 2 + snapshot_size as u64 / 2;
+        for i in 1..5 {
+            project.name = format!("Capped {}", i);
+            db.insert_auto_save(&project, Some(cap)).unwrap();
+        }
+        assert_eq!(auto_save_count(&db, &project.id), 2);
+        let latest = db.get_latest_auto_save(&project.id).unwrap().unwrap();
+        assert_eq!(latest.name, "Capped 4");
+
+        // The newest snapshot survives even when it alone exceeds the cap
+        project.name = "Capped 5".to_string();
+        db.insert_auto_save(&project, Some(1)).unwrap();
+        assert_eq!(auto_save_count(&db, &project.id), 1);
+        let latest = db.get_latest_auto_save(&project.id).unwrap().unwrap();
+        assert_eq!(latest.name, "Capped 5");
+    }
 }
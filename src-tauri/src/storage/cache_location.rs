@@ -0,0 +1,104 @@
+// Location of the cache directory
+// The cache directory holds the cache database (which also stores the app
+// settings), proxies, thumbnails and previews. Because the settings live
+// inside it, its location is kept outside: in a small `location.json` in the
+// OS config directory, read before anything else at startup. The resolved
+// directory is installed once opened, and every cache path is built from it.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File name of the cache database inside the cache directory
+pub const CACHE_DB_FILE: &str = "clipforge.db";
+
+/// Cache directory in use (None until startup opened one)
+static ACTIVE_CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Contents of `location.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheLocation {
+    /// Chosen cache directory (null = `~/.clipforge/cache`)
+    pub cache_dir: Option<String>,
+}
+
+impl CacheLocation {
+    /// The chosen directory, else the default one (None without a home directory)
+    pub fn resolve(&self) -> Option<PathBuf> {
+        match &self.cache_dir {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => default_cache_dir(),
+        }
+    }
+}
+
+/// `~/.clipforge/cache`
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".clipforge").join("cache"))
+}
+
+/// Where `location.json` is kept
+pub fn location_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("clipforge").join("location.json"))
+}
+
+/// Read `location.json`; a missing or unreadable file means the default location
+pub fn read_location(file: &Path) -> CacheLocation {
+    let Ok(content) = std::fs::read_to_string(file) else {
+        return CacheLocation::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        log::warn!("Ignoring invalid {}: {}", file.display(), e);
+        CacheLocation::default()
+    })
+}
+
+pub fn write_location(file: &Path, location: &CacheLocation) -> Result<(), String> {
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(location)
+        .map_err(|e| format!("Failed to serialize cache location: {}", e))?;
+    std::fs::write(file, content).map_err(|e| format!("Failed to write {}: {}", file.display(), e))
+}
+
+/// Use `dir` for every cache path from now on
+pub fn install(dir: &Path) {
+    *ACTIVE_CACHE_DIR.write().unwrap() = Some(dir.to_path_buf());
+}
+
+/// The installed cache directory, else the default one
+pub fn active_cache_dir() -> Option<PathBuf> {
+    ACTIVE_CACHE_DIR
+        .read()
+        .unwrap()
+        .clone()
+        .or_else(default_cache_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_location_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config").join("location.json");
+
+        // Missing and invalid files fall back to the default location
+        assert_eq!(read_location(&file), CacheLocation::default());
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "not json").unwrap();
+        assert_eq!(read_location(&file), CacheLocation::default());
+
+        let location = CacheLocation {
+            cache_dir: Some("/data/clipforge".to_string()),
+        };
+        write_location(&file, &location).unwrap();
+        assert_eq!(read_location(&file), location);
+        assert_eq!(location.resolve(), Some(PathBuf::from("/data/clipforge")));
+    }
+}
@@ -0,0 +1,194 @@
+// Cache-relative proxy and thumbnail paths
+// Proxies, thumbnails (1x and 2x) and hover sprites live in the cache directory, which
+// moves with a new cache location, another user name or a backup restored
+// on another machine. They are stored as `$CACHE/...` in the cache database,
+// project files and auto-saves, and resolved against the cache directory
+// when read, so the rest of the app only sees absolute paths.
+//
+// Like project-relative paths, these are handled as strings so Windows
+// paths behave the same on every platform.
+
+use crate::models::clip::MediaClip;
+use crate::models::project::Project;
+use crate::storage::project_paths::{resolve_prefixed_path, to_prefixed_relative};
+
+/// Prefix marking a path as relative to the cache directory
+pub const CACHE_DIR_PREFIX: &str = "$CACHE/";
+
+/// Convert an absolute path to `$CACHE/...` if it lives under `cache_dir`
+pub fn to_cache_relative(path: &str, cache_dir: &str) -> Option<String> {
+    to_prefixed_relative(path, cache_dir, CACHE_DIR_PREFIX)
+}
+
+/// Resolve a stored path; absolute paths are returned unchanged
+pub fn resolve_cache_path(stored: &str, cache_dir: &str) -> String {
+    resolve_prefixed_path(stored, cache_dir, CACHE_DIR_PREFIX)
+}
+
+/// `path` as stored: cache-relative when it lives under `cache_dir`
+pub fn store_cache_path(path: &str, cache_dir: &str) -> String {
+    to_cache_relative(path, cache_dir).unwrap_or_else(|| path.to_string())
+}
+
+/// The clip's cache files: proxy and both thumbnails
+fn clip_cache_files(clip: &mut MediaClip) -> [&mut Option<String>; 3] {
+    [
+        &mut clip.proxy_path,
+        &mut clip.thumbnail_path,
+        &mut clip.thumbnail_2x_path,
+    ]
+}
+
+/// The clip's cache files as stored
+pub fn store_clip_paths(clip: &mut MediaClip, cache_dir: &str) {
+    for path in clip_cache_files(clip).into_iter().flatten() {
+        *path = store_cache_path(path, cache_dir);
+    }
+}
+
+/// The clip's stored cache files resolved against `cache_dir`
+pub fn resolve_clip_paths(clip: &mut MediaClip, cache_dir: &str) {
+    for path in clip_cache_files(clip).into_iter().flatten() {
+        *path = resolve_cache_path(path, cache_dir);
+    }
+}
+
+/// Cache files of every media clip as stored (used on save)
+pub fn store_project_paths(project: &mut Project, cache_dir: &str) {
+    for clip in &mut project.media_library {
+        store_clip_paths(clip, cache_dir);
+    }
+}
+
+/// Resolve every media clip's cache files (used on load)
+pub fn resolve_project_paths(project: &mut Project, cache_dir: &str) {
+    for clip in &mut project.media_library {
+        resolve_clip_paths(clip, cache_dir);
+    }
+}
+
+/// Point the clip's cache files at `new_dir` after the cache moved there
+///
+/// A file under `old_dir` is taken from `new_dir` when it exists there and
+/// kept where it was otherwise; files that exist in neither place are
+/// dropped, so the proxy or thumbnail is regenerated. Returns the number of
+/// paths changed.
+pub fn rebase_clip_paths(
+    clip: &mut MediaClip,
+    old_dir: &str,
+    new_dir: &str,
+    exists: impl Fn(&str) -> bool,
+) -> usize {
+    let mut changed = 0;
+    for path in clip_cache_files(clip) {
+        let Some(current) = path.as_deref() else {
+            continue;
+        };
+        let moved = to_cache_relative(current, old_dir)
+            .map(|relative| resolve_cache_path(&relative, new_dir))
+            .filter(|moved| exists(moved));
+        let rebased = match moved {
+            Some(moved) => Some(moved),
+            None if exists(current) => Some(current.to_string()),
+            None => None,
+        };
+        if rebased.as_deref() != Some(current) {
+            *path = rebased;
+            changed += 1;
+        }
+    }
+    if clip.proxy_path.is_none() {
+        clip.proxy_version = None;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip() -> MediaClip {
+        MediaClip::new(
+            "/media/a.mov".into(),
+            10.0,
+            1920,
+            1080,
+            30.0,
+            "prores".into(),
+            1,
+        )
+    }
+
+    #[test]
+    fn test_unix_cache_paths() {
+        let cache = "/home/me/.clipforge/cache";
+        assert_eq!(
+            to_cache_relative("/home/me/.clipforge/cache/proxies/a.mp4", cache),
+            Some("$CACHE/proxies/a.mp4".to_string())
+        );
+        assert_eq!(to_cache_relative("/media/a.mov", cache), None);
+        assert_eq!(
+            resolve_cache_path("$CACHE/proxies/a.mp4", "/home/you/.clipforge/cache/"),
+            "/home/you/.clipforge/cache/proxies/a.mp4"
+        );
+        assert_eq!(resolve_cache_path("/media/a.jpg", cache), "/media/a.jpg");
+    }
+
+    #[test]
+    fn test_windows_cache_paths() {
+        let cache = r"C:\Users\me\.clipforge\cache";
+        assert_eq!(
+            to_cache_relative(r"c:\users\ME\.clipforge\cache\thumbnails\a.jpg", cache),
+            Some("$CACHE/thumbnails/a.jpg".to_string())
+        );
+        assert_eq!(
+            resolve_cache_path("$CACHE/thumbnails/a.jpg", r"D:\ClipForge\cache"),
+            r"D:\ClipForge\cache\thumbnails\a.jpg"
+        );
+        // Stored on Linux, opened on Windows
+        assert_eq!(
+            resolve_cache_path(
+                &store_cache_path("/home/me/cache/proxies/a.mp4", "/home/me/cache"),
+                r"\\nas\share\cache"
+            ),
+            r"\\nas\share\cache\proxies\a.mp4"
+        );
+    }
+
+    #[test]
+    fn test_clip_paths_round_trip() {
+        let mut clip = clip();
+        clip.proxy_path = Some("/old/cache/proxies/a.mp4".into());
+        clip.thumbnail_path = Some("/elsewhere/a.jpg".into());
+
+        store_clip_paths(&mut clip, "/old/cache");
+        assert_eq!(clip.proxy_path.as_deref(), Some("$CACHE/proxies/a.mp4"));
+        assert_eq!(clip.thumbnail_path.as_deref(), Some("/elsewhere/a.jpg"));
+        assert_eq!(clip.source_path, "/media/a.mov");
+
+        resolve_clip_paths(&mut clip, "/new/cache");
+        assert_eq!(clip.proxy_path.as_deref(), Some("/new/cache/proxies/a.mp4"));
+    }
+
+    #[test]
+    fn test_rebase_prefers_moved_files() {
+        let mut clip = clip();
+        clip.proxy_path = Some("/old/proxies/a.mp4".into());
+        clip.proxy_version = Some(2);
+        clip.thumbnail_path = Some("/old/thumbnails/a.jpg".into());
+
+        // The proxy moved along, the thumbnail only exists in the old cache
+        let exists = |p: &str| p == "/new/proxies/a.mp4" || p == "/old/thumbnails/a.jpg";
+        assert_eq!(rebase_clip_paths(&mut clip, "/old", "/new", exists), 1);
+        assert_eq!(clip.proxy_path.as_deref(), Some("/new/proxies/a.mp4"));
+        assert_eq!(
+            clip.thumbnail_path.as_deref(),
+            Some("/old/thumbnails/a.jpg")
+        );
+
+        // Gone everywhere: regenerated later
+        assert_eq!(rebase_clip_paths(&mut clip, "/new", "/other", |_| false), 2);
+        assert!(clip.proxy_path.is_none() && clip.thumbnail_path.is_none());
+        assert!(clip.proxy_version.is_none());
+    }
+}
@@ -0,0 +1,949 @@
+// Remote media downloads (`import_media_from_url`)
+// Footage shared by link is fetched with curl, as the managed FFmpeg build
+// is, into a hidden `.part` file in the destination named after the URL. A
+// failed or interrupted download keeps the partial file and the next attempt
+// continues it with an HTTP range request, as long as the server's ETag or
+// Last-Modified still matches; the finished file is renamed into place and
+// then imported like any local file.
+
+use crate::platform::process::{self, ProcessClass};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// File extensions accepted for download, as in the import dialog
+pub const MEDIA_EXTENSIONS: [&str; 6] = ["mp4", "mov", "m4v", "avi", "webm", "mkv"];
+
+/// Seconds allowed to connect, and for a transfer to stall, before giving up
+const CONNECT_TIMEOUT_SECS: u32 = 30;
+const STALL_TIMEOUT_SECS: u32 = 60;
+
+lazy_static::lazy_static! {
+    /// URLs being downloaded, so two imports of one link can't share a partial file
+    static ref IN_FLIGHT: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Why a download failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadError {
+    /// Not an https:// URL
+    InvalidUrl(String),
+    /// The server answered 404 or 410
+    NotFound(String),
+    /// Any other HTTP error status
+    HttpStatus(u16),
+    /// Connecting took too long or the transfer stalled
+    Timeout,
+    /// The file couldn't be written (usually a full disk)
+    DiskFull(PathBuf),
+    /// Over the `max_download_mb` limit (`size` is None when the server
+    /// didn't announce it)
+    TooLarge { size: Option<u64>, limit: u64 },
+    /// The link serves something other than video, e.g. a sign-in page
+    UnsupportedType(String),
+    /// The same URL is already being downloaded
+    InProgress(String),
+    /// curl failed otherwise
+    Failed(String),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::InvalidUrl(url) => {
+                write!(f, "Only https:// links can be imported: {}", url)
+            }
+            DownloadError::NotFound(url) => write!(f, "Nothing found at {}", url),
+            DownloadError::HttpStatus(status) => {
+                write!(f, "The server refused the download (HTTP {})", status)
+            }
+            DownloadError::Timeout => write!(f, "The download timed out"),
+            DownloadError::DiskFull(path) => {
+                write!(f, "Not enough disk space to save {}", path.display())
+            }
+            DownloadError::TooLarge { size, limit } => match size {
+                Some(size) => write!(
+                    f,
+                    "The file is {} MB, over the {} MB download limit",
+                    size.div_ceil(1024 * 1024),
+                    limit / (1024 * 1024)
+                ),
+                None => write!(
+                    f,
+                    "The file is over the {} MB download limit",
+                    limit / (1024 * 1024)
+                ),
+            },
+            DownloadError::UnsupportedType(kind) => {
+                write!(f, "The link doesn't point to a video file ({})", kind)
+            }
+            DownloadError::InProgress(url) => write!(f, "{} is already being downloaded", url),
+            DownloadError::Failed(e) => write!(f, "Download failed: {}", e),
+        }
+    }
+}
+
+/// The final response to a HEAD request, after redirects
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemoteFile {
+    pub status: u16,
+    /// MIME type without parameters, lowercased
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl RemoteFile {
+    /// What identifies this version of the file in `If-Range`: a strong
+    /// ETag, otherwise Last-Modified (weak ETags can't be used for ranges)
+    pub fn validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// Parse `curl --head --location` output: one header block per response,
+/// of which the last is the file's
+pub fn parse_head_response(headers: &str) -> Option<RemoteFile> {
+    let block = headers
+        .split("\r\n\r\n")
+        .flat_map(|block| block.split("\n\n"))
+        .filter(|block| block.trim_start().starts_with("HTTP/"))
+        .last()?;
+    let mut lines = block.trim_start().lines();
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let mut remote = RemoteFile {
+        status,
+        ..Default::default()
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-type" => {
+                let mime = value.split(';').next().unwrap_or("").trim();
+                remote.content_type = Some(mime.to_ascii_lowercase());
+            }
+            "content-length" => remote.content_length = value.parse().ok(),
+            "etag" => remote.etag = Some(value.to_string()),
+            "last-modified" => remote.last_modified = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(remote)
+}
+
+/// Extension for a video MIME type
+fn extension_for(mime: &str) -> Option<&'static str> {
+    match mime {
+        "video/mp4" | "application/mp4" => Some("mp4"),
+        "video/quicktime" => Some("mov"),
+        "video/x-m4v" => Some("m4v"),
+        "video/x-msvideo" | "video/avi" => Some("avi"),
+        "video/webm" => Some("webm"),
+        "video/x-matroska" | "application/x-matroska" => Some("mkv"),
+        _ => None,
+    }
+}
+
+/// Name to save a download as
+///
+/// The URL's last path segment when it has a media extension, otherwise one
+/// named after the content type. Types other than video or generic binary
+/// data (a share page's HTML, say) are rejected.
+pub fn download_file_name(url: &str, content_type: Option<&str>) -> Result<String, DownloadError> {
+    let generic = matches!(
+        content_type,
+        None | Some("application/octet-stream") | Some("binary/octet-stream")
+    );
+    let typed_extension = content_type.and_then(extension_for);
+    if !generic && typed_extension.is_none() {
+        return Err(DownloadError::UnsupportedType(
+            content_type.unwrap_or_default().to_string(),
+        ));
+    }
+
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('/');
+    let segment = path.rsplit('/').next().unwrap_or("");
+    let decoded = urlencoding::decode(segment)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| segment.to_string());
+    let name: String = decoded
+        .chars()
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, ext.to_ascii_lowercase()),
+        _ => (name, String::new()),
+    };
+    if MEDIA_EXTENSIONS.contains(&extension.as_str()) {
+        return Ok(format!("{}.{}", stem, extension));
+    }
+    let extension = typed_extension.ok_or_else(|| {
+        DownloadError::UnsupportedType(if extension.is_empty() {
+            "unknown file type".to_string()
+        } else {
+            format!(".{} file", extension)
+        })
+    })?;
+    let stem = if name.is_empty() { "download" } else { name };
+    Ok(format!("{}.{}", stem, extension))
+}
+
+/// `path`, or `name (2).ext`, `name (3).ext`... if it exists
+fn unused_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered name is free")
+}
+
+/// Marks a URL as being downloaded until dropped
+pub struct DownloadClaim(String);
+
+impl DownloadClaim {
+    pub fn acquire(url: &str) -> Result<Self, DownloadError> {
+        if !IN_FLIGHT.lock().unwrap().insert(url.to_string()) {
+            return Err(DownloadError::InProgress(url.to_string()));
+        }
+        Ok(Self(url.to_string()))
+    }
+}
+
+impl Drop for DownloadClaim {
+    fn drop(&mut self) {
+        IN_FLIGHT.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// What was downloaded from a URL into a directory, kept in a hidden file
+/// beside the downloads
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct DownloadRecord {
+    url: String,
+    /// `RemoteFile::validator` of the version downloaded
+    validator: Option<String>,
+    /// File name the finished download was saved as
+    saved_as: Option<String>,
+}
+
+/// How a curl transfer ended, when it failed
+struct CurlFailure {
+    code: Option<i32>,
+    /// Last HTTP status curl saw (0 if none)
+    http_status: u16,
+    stderr: String,
+}
+
+/// curl exit code for "server doesn't support byte ranges"
+const CURL_RANGE_ERROR: i32 = 33;
+
+/// One file to download into a directory
+pub struct MediaDownload<'a> {
+    url: &'a str,
+    dest_dir: &'a Path,
+    max_bytes: u64,
+    /// Accept plain http:// too (tests against a local server)
+    allow_http: bool,
+}
+
+impl<'a> MediaDownload<'a> {
+    /// Download of `url` into `dest_dir`, refusing files over `max_bytes`
+    pub fn new(url: &'a str, dest_dir: &'a Path, max_bytes: u64) -> Self {
+        Self {
+            url,
+            dest_dir,
+            max_bytes,
+            allow_http: false,
+        }
+    }
+
+    fn protocols(&self) -> &'static str {
+        if self.allow_http {
+            "=http,https"
+        } else {
+            "=https"
+        }
+    }
+
+    fn common_args(&self) -> Vec<String> {
+        let connect_timeout = CONNECT_TIMEOUT_SECS.to_string();
+        [
+            "--silent",
+            "--show-error",
+            "--location",
+            "--proto",
+            self.protocols(),
+            "--proto-redir",
+            self.protocols(),
+            "--connect-timeout",
+            &connect_timeout,
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+    }
+
+    /// Hidden file in the destination for the URL's partial download
+    fn part_path(&self) -> PathBuf {
+        self.state_path("part")
+    }
+
+    fn record_path(&self) -> PathBuf {
+        self.state_path("json")
+    }
+
+    fn state_path(&self, extension: &str) -> PathBuf {
+        let hash = format!("{:x}", Sha256::digest(self.url.as_bytes()));
+        self.dest_dir
+            .join(format!(".clipforge-download-{}.{}", &hash[..16], extension))
+    }
+
+    fn load_record(&self) -> Option<DownloadRecord> {
+        let record: DownloadRecord =
+            serde_json::from_slice(&std::fs::read(self.record_path()).ok()?).ok()?;
+        (record.url == self.url).then_some(record)
+    }
+
+    fn save_record(&self, record: &DownloadRecord) -> Result<(), String> {
+        let path = self.record_path();
+        let json = serde_json::to_vec(record)
+            .map_err(|e| format!("Failed to serialize download record: {}", e))?;
+        std::fs::write(&path, json)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// curl arguments fetching the file into `part`, continuing from its
+    /// current size if it is still the version `if_range` identifies
+    fn fetch_args(&self, part: &Path, if_range: Option<&str>) -> Vec<String> {
+        let mut args = self.common_args();
+        args.extend(
+            [
+                "--fail",
+                "--speed-limit",
+                "1",
+                "--speed-time",
+                &STALL_TIMEOUT_SECS.to_string(),
+                "--max-filesize",
+                &self.max_bytes.to_string(),
+                "--write-out",
+                "%{http_code}",
+            ]
+            .iter()
+            .map(|arg| arg.to_string()),
+        );
+        if let Some(validator) = if_range {
+            args.extend([
+                "--continue-at".to_string(),
+                "-".to_string(),
+                "--header".to_string(),
+                format!("If-Range: {}", validator),
+            ]);
+        }
+        args.extend([
+            "--output".to_string(),
+            part.to_string_lossy().into_owned(),
+            self.url.to_string(),
+        ]);
+        args
+    }
+
+    /// Status, type and size the server reports for the URL
+    async fn head(&self) -> Result<RemoteFile, DownloadError> {
+        let mut cmd = Command::new("curl");
+        cmd.args(self.common_args())
+            .args(["--head", "--max-time", &STALL_TIMEOUT_SECS.to_string()])
+            .arg(self.url);
+        let output = process::output(cmd, ProcessClass::Download)
+            .await
+            .map_err(|e| DownloadError::Failed(e.to_string()))?;
+        if !output.status.success() {
+            return Err(self.classify(CurlFailure {
+                code: output.status.code(),
+                http_status: 0,
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }));
+        }
+        parse_head_response(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| DownloadError::Failed("No response from the server".to_string()))
+    }
+
+    /// Run curl into `part`, reporting its size as it grows
+    async fn fetch(
+        &self,
+        part: &Path,
+        if_range: Option<&str>,
+        total: Option<u64>,
+        on_progress: &impl Fn(u64, Option<u64>),
+    ) -> Result<(), CurlFailure> {
+        let mut cmd = Command::new("curl");
+        cmd.args(self.fetch_args(part, if_range));
+        let run = process::output(cmd, ProcessClass::Download);
+        tokio::pin!(run);
+
+        let size = || std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        let output = loop {
+            tokio::select! {
+                output = &mut run => break output,
+                _ = tokio::time::sleep(Duration::from_millis(250)) => on_progress(size(), total),
+            }
+        }
+        .map_err(|e| CurlFailure {
+            code: None,
+            http_status: 0,
+            stderr: e.to_string(),
+        })?;
+        on_progress(size(), total);
+        if output.status.success() {
+            return Ok(());
+        }
+        Err(CurlFailure {
+            code: output.status.code(),
+            http_status: String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse()
+                .unwrap_or(0),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    fn classify(&self, failure: CurlFailure) -> DownloadError {
+        match (failure.code, failure.http_status) {
+            (_, 404 | 410) => DownloadError::NotFound(self.url.to_string()),
+            (Some(22), status) if status >= 400 => DownloadError::HttpStatus(status),
+            (Some(1), _) => DownloadError::InvalidUrl(self.url.to_string()),
+            (Some(6 | 7), _) => DownloadError::Failed(failure.stderr.trim().to_string()),
+            (Some(28), _) => DownloadError::Timeout,
+            (Some(23), _) => DownloadError::DiskFull(self.dest_dir.to_path_buf()),
+            (Some(63), _) => DownloadError::TooLarge {
+                size: None,
+                limit: self.max_bytes,
+            },
+            _ if failure.stderr.contains("No space left") => {
+                DownloadError::DiskFull(self.dest_dir.to_path_buf())
+            }
+            _ => DownloadError::Failed(failure.stderr.trim().to_string()),
+        }
+    }
+
+    /// Download the file, continuing a partial download of it, and return
+    /// where it was saved
+    ///
+    /// `on_progress` gets the bytes downloaded so far and the total, if the
+    /// server announced it. A file this downloaded in full before is returned
+    /// without fetching it again while the server still has that version.
+    pub async fn run(
+        &self,
+        on_progress: impl Fn(u64, Option<u64>),
+    ) -> Result<PathBuf, DownloadError> {
+        let https = self.url.starts_with("https://");
+        if !(https || (self.allow_http && self.url.starts_with("http://"))) {
+            return Err(DownloadError::InvalidUrl(self.url.to_string()));
+        }
+
+        let remote = self.head().await?;
+        match remote.status {
+            404 | 410 => return Err(DownloadError::NotFound(self.url.to_string())),
+            // HEAD not allowed: learn the size while downloading instead
+            405 | 501 => {}
+            status if status >= 400 => return Err(DownloadError::HttpStatus(status)),
+            _ => {}
+        }
+        let total = remote.content_length;
+        if let Some(size) = total.filter(|size| *size > self.max_bytes) {
+            return Err(DownloadError::TooLarge {
+                size: Some(size),
+                limit: self.max_bytes,
+            });
+        }
+
+        let name = download_file_name(self.url, remote.content_type.as_deref())?;
+        let file_size = |path: &Path| std::fs::metadata(path).ok().map(|m| m.len());
+        // Without a validator there is no telling whether a file or partial
+        // download from before is of the version the server has now
+        let validator = remote.validator().map(str::to_string);
+        let record = self
+            .load_record()
+            .filter(|record| validator.is_some() && record.validator == validator);
+        if let Some(saved) = record
+            .as_ref()
+            .and_then(|record| record.saved_as.as_ref())
+            .map(|name| self.dest_dir.join(name))
+        {
+            if file_size(&saved).is_some_and(|size| total.is_none_or(|total| size == total)) {
+                return Ok(saved);
+            }
+        }
+        std::fs::create_dir_all(self.dest_dir).map_err(|e| {
+            DownloadError::Failed(format!(
+                "Failed to create {}: {}",
+                self.dest_dir.display(),
+                e
+            ))
+        })?;
+
+        let part = self.part_path();
+        let existing = file_size(&part).unwrap_or(0);
+        if record.is_none() || total.is_some_and(|total| existing > total) {
+            let _ = std::fs::remove_file(&part);
+        }
+        self.save_record(&DownloadRecord {
+            url: self.url.to_string(),
+            validator: validator.clone(),
+            saved_as: None,
+        })
+        .map_err(DownloadError::Failed)?;
+
+        let complete = total.is_some() && file_size(&part) == total;
+        if !complete {
+            let if_range = validator
+                .as_deref()
+                .filter(|_| file_size(&part).unwrap_or(0) > 0);
+            let mut result = self.fetch(&part, if_range, total, &on_progress).await;
+            if if_range.is_some()
+                && matches!(&result, Err(failure) if failure.code == Some(CURL_RANGE_ERROR))
+            {
+                // The server can't continue it, or the file changed since
+                // the HEAD request: start over
+                let _ = std::fs::remove_file(&part);
+                result = self.fetch(&part, None, total, &on_progress).await;
+            }
+            result.map_err(|failure| self.classify(failure))?;
+        }
+
+        if let (Some(total), Some(size)) = (total, file_size(&part)) {
+            if size != total {
+                return Err(DownloadError::Failed(format!(
+                    "The download stopped after {} of {} bytes",
+                    size, total
+                )));
+            }
+        }
+        let target = unused_path(self.dest_dir.join(&name));
+        std::fs::rename(&part, &target).map_err(|e| {
+            DownloadError::Failed(format!("Failed to save {}: {}", target.display(), e))
+        })?;
+        let saved = DownloadRecord {
+            url: self.url.to_string(),
+            validator,
+            saved_as: target
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+        };
+        if let Err(e) = self.save_record(&saved) {
+            log::warn!("{}", e);
+        }
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    /// How the mock server answers
+    #[derive(Clone)]
+    struct Behavior {
+        status: u16,
+        content_type: &'static str,
+        /// Honour `Range` requests with 206
+        ranges: bool,
+        /// Close the first GET after this many body bytes
+        cut_first_get_at: Option<usize>,
+        etag: &'static str,
+        /// ETag once the file is replaced after the first GET
+        changed_etag: Option<&'static str>,
+        /// HEAD keeps reporting the first ETag after the file is replaced
+        stale_head: bool,
+    }
+
+    impl Default for Behavior {
+        fn default() -> Self {
+            Self {
+                status: 200,
+                content_type: "video/mp4",
+                ranges: true,
+                cut_first_get_at: None,
+                etag: "\"v1\"",
+                changed_etag: None,
+                stale_head: false,
+            }
+        }
+    }
+
+    /// HTTP server on localhost serving `body`; returns its URL for
+    /// `/clips/take 1.mp4` and the `Range` header of every GET
+    fn serve(body: Vec<u8>, behavior: Behavior) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+        std::thread::spawn(move || {
+            let mut gets = 0;
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut range = None;
+                let mut if_range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("Range: ") {
+                        range = Some(value.trim().to_string());
+                    }
+                    if let Some(value) = line.strip_prefix("If-Range: ") {
+                        if_range = Some(value.trim().to_string());
+                    }
+                }
+
+                let head = request.starts_with("HEAD");
+                let etag = match behavior.changed_etag {
+                    Some(changed) if gets > 0 && !(head && behavior.stale_head) => changed,
+                    _ => behavior.etag,
+                };
+                // A range of another version of the file gets all of it
+                let current = if_range.as_ref().is_none_or(|if_range| if_range == etag);
+                let start = match (&range, behavior.ranges && current) {
+                    (Some(range), true) => range
+                        .trim_start_matches("bytes=")
+                        .trim_end_matches('-')
+                        .parse()
+                        .unwrap(),
+                    _ => 0,
+                };
+                let mut response = if behavior.status != 200 {
+                    format!(
+                        "HTTP/1.1 {} Error\r\nContent-Length: 0\r\n",
+                        behavior.status
+                    )
+                } else if start > 0 {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                         Content-Length: {}\r\n",
+                        start,
+                        body.len() - 1,
+                        body.len(),
+                        body.len() - start
+                    )
+                } else {
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", body.len())
+                };
+                response.push_str(&format!(
+                    "Content-Type: {}\r\nETag: {}\r\nConnection: close\r\n\r\n",
+                    behavior.content_type, etag
+                ));
+                stream.write_all(response.as_bytes()).unwrap();
+                if head || behavior.status != 200 {
+                    continue;
+                }
+
+                seen.lock().unwrap().push(range);
+                gets += 1;
+                let mut rest = &body[start..];
+                if gets == 1 {
+                    if let Some(cut) = behavior.cut_first_get_at {
+                        rest = &rest[..cut];
+                    }
+                }
+                let _ = stream.write_all(rest);
+            }
+        });
+        (
+            format!("http://127.0.0.1:{}/clips/take%201.mp4?sig=abc", port),
+            ranges,
+        )
+    }
+
+    fn download<'a>(url: &'a str, dir: &'a Path) -> MediaDownload<'a> {
+        MediaDownload {
+            allow_http: true,
+            ..MediaDownload::new(url, dir, 1024 * 1024)
+        }
+    }
+
+    fn body() -> Vec<u8> {
+        (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_with_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, ranges) = serve(
+            body(),
+            Behavior {
+                cut_first_get_at: Some(10_000),
+                ..Default::default()
+            },
+        );
+
+        // The connection drops part way: the partial file is kept
+        assert!(download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .is_err());
+        let part = download(&url, temp_dir.path()).part_path();
+        assert_eq!(std::fs::metadata(&part).unwrap().len(), 10_000);
+
+        let path = download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(path, temp_dir.path().join("take 1.mp4"));
+        assert_eq!(std::fs::read(&path).unwrap(), body());
+        assert!(!part.exists());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            [None, Some("bytes=10000-".to_string())]
+        );
+
+        // Downloaded already: nothing is fetched again
+        let again = download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(again, path);
+        assert_eq!(ranges.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restarts_when_server_ignores_ranges() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, ranges) = serve(
+            body(),
+            Behavior {
+                ranges: false,
+                cut_first_get_at: Some(1000),
+                ..Default::default()
+            },
+        );
+        assert!(download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .is_err());
+
+        let progress = Mutex::new(Vec::new());
+        let path = download(&url, temp_dir.path())
+            .run(|downloaded, total| progress.lock().unwrap().push((downloaded, total)))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), body());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            [None, Some("bytes=1000-".to_string()), None]
+        );
+        assert_eq!(
+            progress.lock().unwrap().last(),
+            Some(&(64 * 1024, Some(64 * 1024)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partial_download_of_changed_file_is_discarded() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, ranges) = serve(
+            body(),
+            Behavior {
+                cut_first_get_at: Some(10_000),
+                changed_etag: Some("\"v2\""),
+                ..Default::default()
+            },
+        );
+        assert!(download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .is_err());
+
+        // HEAD reports the new version: not resumed
+        let path = download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), body());
+        assert_eq!(*ranges.lock().unwrap(), [None, None]);
+    }
+
+    #[tokio::test]
+    async fn test_if_range_restarts_when_file_changes_after_head() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, ranges) = serve(
+            body(),
+            Behavior {
+                cut_first_get_at: Some(10_000),
+                changed_etag: Some("\"v2\""),
+                stale_head: true,
+                ..Default::default()
+            },
+        );
+        assert!(download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .is_err());
+
+        // The range request names the old version, so the server sends it all
+        let path = download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), body());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            [None, Some("bytes=10000-".to_string()), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_existing_file_of_same_name_is_not_returned() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, ranges) = serve(body(), Behavior::default());
+        let unrelated = temp_dir.path().join("take 1.mp4");
+        std::fs::write(&unrelated, vec![0u8; body().len()]).unwrap();
+
+        let path = download(&url, temp_dir.path())
+            .run(|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(path, temp_dir.path().join("take 1 (2).mp4"));
+        assert_eq!(std::fs::read(path).unwrap(), body());
+        assert_eq!(std::fs::read(unrelated).unwrap(), vec![0u8; body().len()]);
+        assert_eq!(ranges.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let (url, _) = serve(
+            body(),
+            Behavior {
+                status: 404,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            download(&url, temp_dir.path()).run(|_, _| {}).await,
+            Err(DownloadError::NotFound(url.clone()))
+        );
+
+        // A share page instead of the file
+        let (url, _) = serve(
+            body(),
+            Behavior {
+                content_type: "text/html; charset=utf-8",
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            download(&url, temp_dir.path()).run(|_, _| {}).await,
+            Err(DownloadError::UnsupportedType("text/html".to_string()))
+        );
+
+        let (url, ranges) = serve(body(), Behavior::default());
+        let small = MediaDownload {
+            max_bytes: 1000,
+            ..download(&url, temp_dir.path())
+        };
+        assert_eq!(
+            small.run(|_, _| {}).await,
+            Err(DownloadError::TooLarge {
+                size: Some(64 * 1024),
+                limit: 1000
+            })
+        );
+        assert!(ranges.lock().unwrap().is_empty());
+
+        // Plain http is refused outside tests
+        let err = MediaDownload::new(&url, temp_dir.path(), 1000)
+            .run(|_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err, DownloadError::InvalidUrl(url.clone()));
+        assert!(std::fs::read_dir(temp_dir.path()).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_parse_head_response_follows_redirects() {
+        let headers = "HTTP/2 302 \r\nlocation: https://cdn.example.com/a.mov\r\n\r\n\
+                       HTTP/2 200 \r\ncontent-type: video/quicktime\r\ncontent-length: 52428800\r\n\
+                       accept-ranges: bytes\r\netag: W/\"3f-1\"\r\n\
+                       last-modified: Tue, 06 Oct 2026 09:00:00 GMT\r\n\r\n";
+        let remote = parse_head_response(headers).unwrap();
+        assert_eq!(
+            remote,
+            RemoteFile {
+                status: 200,
+                content_type: Some("video/quicktime".to_string()),
+                content_length: Some(52_428_800),
+                etag: Some("W/\"3f-1\"".to_string()),
+                last_modified: Some("Tue, 06 Oct 2026 09:00:00 GMT".to_string()),
+            }
+        );
+        // Weak ETags can't validate a range
+        assert_eq!(remote.validator(), Some("Tue, 06 Oct 2026 09:00:00 GMT"));
+        assert_eq!(parse_head_response(""), None);
+    }
+
+    #[test]
+    fn test_download_file_name() {
+        let name = |url: &str, content_type| download_file_name(url, content_type);
+        assert_eq!(
+            name(
+                "https://x.io/a/Interview%20B.MOV?dl=1",
+                Some("video/quicktime")
+            )
+            .unwrap(),
+            "Interview B.mov"
+        );
+        // Named after the type when the URL has no extension
+        assert_eq!(
+            name("https://x.io/share/9f2c", Some("video/webm")).unwrap(),
+            "9f2c.webm"
+        );
+        assert_eq!(
+            name("https://x.io/f.mkv", Some("application/octet-stream")).unwrap(),
+            "f.mkv"
+        );
+        assert_eq!(
+            name("https://x.io/report.pdf", Some("application/octet-stream")),
+            Err(DownloadError::UnsupportedType(".pdf file".to_string()))
+        );
+        assert_eq!(
+            name("https://x.io/..%2F..%2Fetc%2Fclip.mp4", None).unwrap(),
+            "etcclip.mp4"
+        );
+    }
+}
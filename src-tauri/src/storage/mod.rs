@@ -2,5 +2,14 @@
 // Handles persistence: SQLite cache, project files, and media storage
 
 pub mod cache;
+pub mod cache_location;
+pub mod cache_paths;
+pub mod media_download;
+pub mod project_archive;
+pub mod project_file;
+pub mod project_merge;
+pub mod project_paths;
+pub mod settings;
+pub mod work_dir;
 
 pub use cache::CacheDb;
@@ -0,0 +1,664 @@
+// Project archives (.clipforgepkg)
+// A zip holding the project file, its media (originals or proxies), and
+// thumbnails, plus a manifest with SHA-256 hashes for integrity checks.
+// Media is stored uncompressed and streamed, since archives can be huge.
+
+use crate::models::project::Project;
+use crate::models::timeline::normalize_track_order;
+use crate::storage::project_file::save_project_file;
+use crate::storage::project_paths::{resolve_project_path, PROJECT_DIR_PREFIX};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Archive layout version written to the manifest
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Manifest entry name inside the archive
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Project file name inside the archive (and after extraction)
+pub const ARCHIVE_PROJECT_FILE: &str = "project.clipforge";
+
+/// Read/write buffer size for streaming media
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Minimum bytes between progress callbacks
+const PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+
+/// Which media files go into the archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveMediaMode {
+    /// Original source files
+    #[default]
+    Originals,
+    /// Proxies where available (much smaller; originals for clips without one)
+    ProxiesOnly,
+}
+
+/// Options for `export_project_archive`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveOptions {
+    pub media: ArchiveMediaMode,
+}
+
+/// Kind of file stored in an archive
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveEntryKind {
+    Project,
+    Media,
+    Thumbnail,
+}
+
+/// One file in an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path inside the archive (and relative to the extraction directory)
+    pub path: String,
+    pub kind: ArchiveEntryKind,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the content
+    pub sha256: String,
+}
+
+/// Archive table of contents, written as `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub project_id: String,
+    pub project_name: String,
+    pub created_at: DateTime<Utc>,
+    pub media_mode: ArchiveMediaMode,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// Progress of an archive export or import
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveProgress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Archive path of the file being processed
+    pub current_file: String,
+}
+
+/// Outcome of extracting an archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveExtraction {
+    /// Path of the extracted project file
+    pub project_path: PathBuf,
+    /// Files written to the destination
+    pub files_extracted: usize,
+    /// Files already present with matching content
+    pub files_skipped: usize,
+}
+
+/// Errors raised while writing or reading archives
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum ArchiveError {
+    /// Reading or writing a file failed
+    Io(String),
+    /// The file is not a readable zip archive (truncated or corrupt)
+    NotAnArchive(String),
+    /// The archive has no manifest
+    MissingManifest,
+    /// The manifest was written by a newer version of ClipForge
+    UnsupportedVersion(u32),
+    /// A file listed in the manifest is absent or truncated
+    MissingEntry(String),
+    /// A file's content doesn't match its manifest hash
+    HashMismatch(String),
+    /// The project file in the archive (or being archived) is invalid
+    InvalidProject(String),
+    /// A media file referenced by the project doesn't exist
+    MissingMedia(String),
+    /// The current project couldn't be closed to open the archive
+    ProjectBusy(String),
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "Archive I/O error: {}", e),
+            ArchiveError::NotAnArchive(e) => write!(f, "Not a valid project archive: {}", e),
+            ArchiveError::MissingManifest => write!(f, "Project archive has no manifest"),
+            ArchiveError::UnsupportedVersion(v) => {
+                write!(f, "Project archive format {} is not supported", v)
+            }
+            ArchiveError::MissingEntry(path) => write!(f, "Archive is incomplete: {}", path),
+            ArchiveError::HashMismatch(path) => {
+                write!(f, "Archive content is corrupt: {}", path)
+            }
+            ArchiveError::InvalidProject(e) => write!(f, "Invalid project in archive: {}", e),
+            ArchiveError::MissingMedia(path) => write!(f, "Media file not found: {}", path),
+            ArchiveError::ProjectBusy(e) => write!(f, "Cannot open the archived project: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl ArchiveError {
+    fn io(path: &Path, error: impl std::fmt::Display) -> Self {
+        ArchiveError::Io(format!("{}: {}", path.display(), error))
+    }
+}
+
+impl From<zip::result::ZipError> for ArchiveError {
+    fn from(error: zip::result::ZipError) -> Self {
+        match error {
+            zip::result::ZipError::FileNotFound => ArchiveError::MissingManifest,
+            other => ArchiveError::NotAnArchive(other.to_string()),
+        }
+    }
+}
+
+/// A file on disk planned for the archive
+struct PlannedFile {
+    source: PathBuf,
+    entry_path: String,
+    kind: ArchiveEntryKind,
+    size: u64,
+}
+
+/// Write `project` and its media to a zip at `output`
+///
+/// The archive is written next to `output` and renamed into place once
+/// complete, so a failed export never leaves a partial archive behind.
+pub fn write_project_archive(
+    project: &Project,
+    output: &Path,
+    options: &ArchiveOptions,
+    mut on_progress: impl FnMut(&ArchiveProgress),
+) -> Result<ArchiveManifest, ArchiveError> {
+    let (archived_project, files) = plan_archive(project, options)?;
+    let project_json = serde_json::to_vec_pretty(&archived_project)
+        .map_err(|e| ArchiveError::InvalidProject(e.to_string()))?;
+
+    let bytes_total = files.iter().map(|f| f.size).sum::<u64>() + project_json.len() as u64;
+    let mut progress = ArchiveProgress {
+        bytes_done: 0,
+        bytes_total,
+        current_file: String::new(),
+    };
+
+    let partial = output.with_extension("clipforgepkg.part");
+    let file = File::create(&partial).map_err(|e| ArchiveError::io(&partial, e))?;
+    let result = (|| {
+        let mut zip = ZipWriter::new(file);
+        let stored = FileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(true);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut entries = Vec::with_capacity(files.len() + 1);
+        for planned in &files {
+            progress.current_file = planned.entry_path.clone();
+            zip.start_file(planned.entry_path.as_str(), stored)?;
+            let mut source =
+                File::open(&planned.source).map_err(|e| ArchiveError::io(&planned.source, e))?;
+            let (size, sha256) =
+                copy_hashed(&mut source, &mut zip, &mut progress, &mut on_progress)
+                    .map_err(|e| ArchiveError::io(&planned.source, e))?;
+            entries.push(ArchiveEntry {
+                path: planned.entry_path.clone(),
+                kind: planned.kind,
+                size,
+                sha256,
+            });
+        }
+
+        progress.current_file = ARCHIVE_PROJECT_FILE.to_string();
+        zip.start_file(ARCHIVE_PROJECT_FILE, deflated)?;
+        zip.write_all(&project_json)
+            .map_err(|e| ArchiveError::io(output, e))?;
+        entries.push(ArchiveEntry {
+            path: ARCHIVE_PROJECT_FILE.to_string(),
+            kind: ArchiveEntryKind::Project,
+            size: project_json.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&project_json)),
+        });
+        progress.bytes_done += project_json.len() as u64;
+        on_progress(&progress);
+
+        let manifest = ArchiveManifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            project_id: project.id.clone(),
+            project_name: project.name.clone(),
+            created_at: Utc::now(),
+            media_mode: options.media,
+            entries,
+        };
+        zip.start_file(MANIFEST_ENTRY, deflated)?;
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| ArchiveError::InvalidProject(e.to_string()))?;
+        zip.write_all(&manifest_json)
+            .map_err(|e| ArchiveError::io(output, e))?;
+
+        let file = zip.finish()?;
+        file.sync_all().map_err(|e| ArchiveError::io(&partial, e))?;
+        Ok(manifest)
+    })();
+
+    match result {
+        Ok(manifest) => {
+            std::fs::rename(&partial, output).map_err(|e| ArchiveError::io(output, e))?;
+            Ok(manifest)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// Choose the files to archive and rewrite the project to point at them
+fn plan_archive(
+    project: &Project,
+    options: &ArchiveOptions,
+) -> Result<(Project, Vec<PlannedFile>), ArchiveError> {
+    let mut archived = project.clone();
+    archived.file_path = None;
+    let mut files = Vec::new();
+
+    for clip in &mut archived.media_library {
+        let proxy = clip
+            .proxy_path
+            .as_ref()
+            .filter(|p| Path::new(p).exists())
+            .filter(|_| options.media == ArchiveMediaMode::ProxiesOnly);
+        let source = PathBuf::from(proxy.unwrap_or(&clip.source_path));
+        let size = std::fs::metadata(&source)
+            .map_err(|_| ArchiveError::MissingMedia(source.display().to_string()))?
+            .len();
+
+        let entry_path = format!("media/{}/{}", clip.id, file_name(&source));
+        clip.source_path = format!("{}{}", PROJECT_DIR_PREFIX, entry_path);
+        // Proxies are regenerated after import (or already are the source)
+        clip.proxy_path = None;
+        files.push(PlannedFile {
+            source,
+            entry_path,
+            kind: ArchiveEntryKind::Media,
+            size,
+        });
+
+        let thumbnail = clip.thumbnail_path.take().map(PathBuf::from);
+        if let Some(thumbnail) = thumbnail.filter(|t| t.exists()) {
+            let entry_path = format!("thumbnails/{}/{}", clip.id, file_name(&thumbnail));
+            clip.thumbnail_path = Some(format!("{}{}", PROJECT_DIR_PREFIX, entry_path));
+            files.push(PlannedFile {
+                size: std::fs::metadata(&thumbnail)
+                    .map_err(|e| ArchiveError::io(&thumbnail, e))?
+                    .len(),
+                source: thumbnail,
+                entry_path,
+                kind: ArchiveEntryKind::Thumbnail,
+            });
+        }
+    }
+
+    Ok((archived, files))
+}
+
+/// Read and validate an archive's manifest
+pub fn read_archive_manifest(
+    archive: &mut ZipArchive<File>,
+) -> Result<ArchiveManifest, ArchiveError> {
+    let mut contents = Vec::new();
+    archive
+        .by_name(MANIFEST_ENTRY)?
+        .read_to_end(&mut contents)
+        .map_err(|e| ArchiveError::NotAnArchive(e.to_string()))?;
+    let manifest: ArchiveManifest = serde_json::from_slice(&contents)
+        .map_err(|e| ArchiveError::NotAnArchive(format!("invalid manifest: {}", e)))?;
+
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(manifest.format_version));
+    }
+    Ok(manifest)
+}
+
+/// Extract an archive into `dest_dir` and write its project file there
+///
+/// The manifest, entry list, and project are validated before anything is
+/// written. Files already present with matching content are skipped, and every
+/// extracted file is hash-checked before being moved into place.
+pub fn extract_project_archive(
+    archive_path: &Path,
+    dest_dir: &Path,
+    mut on_progress: impl FnMut(&ArchiveProgress),
+) -> Result<ArchiveExtraction, ArchiveError> {
+    let file = File::open(archive_path).map_err(|e| ArchiveError::io(archive_path, e))?;
+    let mut archive = ZipArchive::new(file)?;
+    let manifest = read_archive_manifest(&mut archive)?;
+
+    // Validate the entry list and the project before writing anything
+    for entry in &manifest.entries {
+        if !is_safe_entry_path(&entry.path) {
+            return Err(ArchiveError::NotAnArchive(format!(
+                "unsafe path in manifest: {}",
+                entry.path
+            )));
+        }
+        match archive.by_name(&entry.path) {
+            Ok(file) if file.size() == entry.size => {}
+            _ => return Err(ArchiveError::MissingEntry(entry.path.clone())),
+        }
+    }
+    let project_entry = manifest
+        .entries
+        .iter()
+        .find(|e| e.kind == ArchiveEntryKind::Project)
+        .ok_or_else(|| ArchiveError::MissingEntry(ARCHIVE_PROJECT_FILE.to_string()))?;
+    let mut project_json = Vec::new();
+    archive
+        .by_name(&project_entry.path)?
+        .read_to_end(&mut project_json)
+        .map_err(|e| ArchiveError::NotAnArchive(e.to_string()))?;
+    if format!("{:x}", Sha256::digest(&project_json)) != project_entry.sha256 {
+        return Err(ArchiveError::HashMismatch(project_entry.path.clone()));
+    }
+    let mut project: Project = serde_json::from_slice(&project_json)
+        .map_err(|e| ArchiveError::InvalidProject(e.to_string()))?;
+    // Files from before the order was kept contiguous
+    normalize_track_order(&mut project.tracks);
+    for repair in project.repair_clip_times() {
+        log::warn!("[Project] {}", repair);
+    }
+
+    std::fs::create_dir_all(dest_dir).map_err(|e| ArchiveError::io(dest_dir, e))?;
+
+    let files: Vec<&ArchiveEntry> = manifest
+        .entries
+        .iter()
+        .filter(|e| e.kind != ArchiveEntryKind::Project)
+        .collect();
+    let mut progress = ArchiveProgress {
+        bytes_done: 0,
+        bytes_total: files.iter().map(|e| e.size).sum(),
+        current_file: String::new(),
+    };
+    let mut files_extracted = 0;
+    let mut files_skipped = 0;
+
+    for entry in files {
+        progress.current_file = entry.path.clone();
+        let target = dest_dir.join(&entry.path);
+
+        if is_already_present(&target, entry) {
+            files_skipped += 1;
+            progress.bytes_done += entry.size;
+            on_progress(&progress);
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ArchiveError::io(parent, e))?;
+        }
+        let partial = target.with_extension("part");
+        let mut output = File::create(&partial).map_err(|e| ArchiveError::io(&partial, e))?;
+        let copied = copy_hashed(
+            &mut archive.by_name(&entry.path)?,
+            &mut output,
+            &mut progress,
+            &mut on_progress,
+        );
+
+        match copied {
+            Ok((_, sha256)) if sha256 == entry.sha256 => {
+                drop(output);
+                std::fs::rename(&partial, &target).map_err(|e| ArchiveError::io(&target, e))?;
+                files_extracted += 1;
+            }
+            Ok(_) => {
+                let _ = std::fs::remove_file(&partial);
+                return Err(ArchiveError::HashMismatch(entry.path.clone()));
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                // Read errors here are zip CRC/format failures
+                return Err(ArchiveError::NotAnArchive(format!("{}: {}", entry.path, e)));
+            }
+        }
+    }
+
+    // Point the project at the extracted files
+    let dest = dest_dir.to_string_lossy().to_string();
+    for clip in &mut project.media_library {
+        clip.source_path = resolve_project_path(&clip.source_path, &dest);
+        clip.thumbnail_path = clip
+            .thumbnail_path
+            .as_ref()
+            .map(|t| resolve_project_path(t, &dest));
+    }
+
+    let project_path = dest_dir.join(ARCHIVE_PROJECT_FILE);
+    save_project_file(&project, &project_path).map_err(|e| ArchiveError::io(&project_path, e))?;
+
+    Ok(ArchiveExtraction {
+        project_path,
+        files_extracted,
+        files_skipped,
+    })
+}
+
+/// Stream `reader` into `writer`, returning (bytes, sha256) and reporting progress
+fn copy_hashed(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    progress: &mut ArchiveProgress,
+    on_progress: &mut impl FnMut(&ArchiveProgress),
+) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut total = 0u64;
+    let mut since_report = 0u64;
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        total += read as u64;
+        progress.bytes_done += read as u64;
+        since_report += read as u64;
+        if since_report >= PROGRESS_INTERVAL {
+            since_report = 0;
+            on_progress(progress);
+        }
+    }
+
+    on_progress(progress);
+    Ok((total, format!("{:x}", hasher.finalize())))
+}
+
+/// Whether `target` already holds the entry's content
+fn is_already_present(target: &Path, entry: &ArchiveEntry) -> bool {
+    let matches_size = std::fs::metadata(target).is_ok_and(|m| m.len() == entry.size);
+    matches_size
+        && File::open(target)
+            .and_then(|mut file| {
+                let mut hasher = Sha256::new();
+                io::copy(&mut file, &mut hasher)?;
+                Ok(format!("{:x}", hasher.finalize()))
+            })
+            .is_ok_and(|sha256| sha256 == entry.sha256)
+}
+
+/// Reject absolute paths and parent references (zip-slip)
+fn is_safe_entry_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "media".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clip::MediaClip;
+    use tempfile::TempDir;
+
+    /// Small fixture: a project with one media file and one thumbnail
+    fn fixture(dir: &Path) -> Project {
+        let media = dir.join("source").join("clip.mp4");
+        let thumbnail = dir.join("source").join("clip.jpg");
+        std::fs::create_dir_all(media.parent().unwrap()).unwrap();
+        std::fs::write(&media, vec![42u8; 2 * 1024 * 1024 + 17]).unwrap();
+        std::fs::write(&thumbnail, b"jpeg").unwrap();
+
+        let mut clip = MediaClip::new(
+            media.to_string_lossy().to_string(),
+            3.0,
+            1280,
+            720,
+            30.0,
+            "h264".to_string(),
+            2 * 1024 * 1024 + 17,
+        );
+        clip.thumbnail_path = Some(thumbnail.to_string_lossy().to_string());
+
+        let mut project = Project::new("Archived".to_string());
+        project.media_library.push(clip);
+        project
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = fixture(temp_dir.path());
+        let archive = temp_dir.path().join("out.clipforgepkg");
+
+        let mut reports = Vec::new();
+        let manifest = write_project_archive(&project, &archive, &Default::default(), |p| {
+            reports.push(p.bytes_done)
+        })
+        .unwrap();
+        assert_eq!(manifest.entries.len(), 3);
+        assert_eq!(
+            reports.last(),
+            Some(&(2 * 1024 * 1024 + 17 + 4 + manifest.entries[2].size))
+        );
+        assert!(!archive.with_extension("clipforgepkg.part").exists());
+
+        let dest = temp_dir.path().join("extracted");
+        let extraction = extract_project_archive(&archive, &dest, |_| {}).unwrap();
+        assert_eq!(extraction.files_extracted, 2);
+        assert_eq!(extraction.files_skipped, 0);
+
+        let loaded =
+            crate::storage::project_file::load_project_file(&extraction.project_path).unwrap();
+        let clip = &loaded.media_library[0];
+        assert_eq!(loaded.id, project.id);
+        assert!(Path::new(&clip.source_path).starts_with(&dest));
+        assert_eq!(
+            std::fs::read(&clip.source_path).unwrap().len(),
+            2 * 1024 * 1024 + 17
+        );
+        assert!(clip.proxy_path.is_none());
+
+        // Importing again skips files that are already there
+        let again = extract_project_archive(&archive, &dest, |_| {}).unwrap();
+        assert_eq!(again.files_extracted, 0);
+        assert_eq!(again.files_skipped, 2);
+    }
+
+    #[test]
+    fn test_missing_media_fails_export() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut project = fixture(temp_dir.path());
+        project.media_library[0].source_path = "/nonexistent/clip.mp4".to_string();
+        let archive = temp_dir.path().join("out.clipforgepkg");
+
+        let result = write_project_archive(&project, &archive, &Default::default(), |_| {});
+        assert!(matches!(result, Err(ArchiveError::MissingMedia(_))));
+        assert!(!archive.exists());
+    }
+
+    #[test]
+    fn test_truncated_archive_fails_before_extracting() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = fixture(temp_dir.path());
+        let archive = temp_dir.path().join("out.clipforgepkg");
+        write_project_archive(&project, &archive, &Default::default(), |_| {}).unwrap();
+
+        let bytes = std::fs::read(&archive).unwrap();
+        let truncated = temp_dir.path().join("truncated.clipforgepkg");
+        std::fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+
+        let dest = temp_dir.path().join("extracted");
+        let result = extract_project_archive(&truncated, &dest, |_| {});
+        assert!(matches!(result, Err(ArchiveError::NotAnArchive(_))));
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_corrupt_content_is_detected() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = fixture(temp_dir.path());
+        let archive = temp_dir.path().join("out.clipforgepkg");
+        write_project_archive(&project, &archive, &Default::default(), |_| {}).unwrap();
+
+        // Flip a byte inside the stored (uncompressed) media
+        let mut bytes = std::fs::read(&archive).unwrap();
+        let offset = bytes
+            .windows(4)
+            .position(|w| w == [42, 42, 42, 42])
+            .unwrap()
+            + 1024;
+        bytes[offset] = 0;
+        std::fs::write(&archive, &bytes).unwrap();
+
+        let dest = temp_dir.path().join("extracted");
+        let result = extract_project_archive(&archive, &dest, |_| {});
+        assert!(matches!(
+            result,
+            Err(ArchiveError::NotAnArchive(_)) | Err(ArchiveError::HashMismatch(_))
+        ));
+        assert!(!dest.join(ARCHIVE_PROJECT_FILE).exists());
+    }
+
+    #[test]
+    fn test_archive_without_manifest_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = temp_dir.path().join("plain.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("readme.txt", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"not a project").unwrap();
+        zip.finish().unwrap();
+
+        let result = extract_project_archive(&archive, &temp_dir.path().join("x"), |_| {});
+        assert!(matches!(result, Err(ArchiveError::MissingManifest)));
+    }
+
+    #[test]
+    fn test_unsafe_entry_paths() {
+        assert!(is_safe_entry_path("media/abc/clip.mp4"));
+        assert!(!is_safe_entry_path("../evil.mp4"));
+        assert!(!is_safe_entry_path("/etc/passwd"));
+        assert!(!is_safe_entry_path("media\\..\\evil"));
+        assert!(!is_safe_entry_path(""));
+    }
+}
@@ -0,0 +1,437 @@
+// Project file persistence
+// Saves are atomic (temp file + fsync + rename) and keep rotating backups
+
+use crate::models::project::Project;
+use crate::models::timeline::normalize_track_order;
+use crate::storage::cache_location::active_cache_dir;
+use crate::storage::cache_paths::{resolve_project_paths, store_project_paths};
+use crate::storage::project_paths::{make_media_paths_relative, resolve_media_paths};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Number of backups kept next to a project file
+pub const MAX_PROJECT_BACKUPS: usize = 3;
+
+/// Errors raised while reading or writing project files
+#[derive(Debug)]
+pub enum ProjectFileError {
+    /// The file or its directory is not writable/readable
+    PermissionDenied(PathBuf),
+    /// No space left on the target device
+    DiskFull(PathBuf),
+    /// The file does not exist
+    NotFound(PathBuf),
+    /// The file exists but is not a valid project
+    InvalidProject(PathBuf, String),
+    /// The project could not be serialized
+    Serialize(String),
+    /// Any other I/O failure
+    Io(PathBuf, io::Error),
+}
+
+impl std::fmt::Display for ProjectFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectFileError::PermissionDenied(path) => {
+                write!(f, "Permission denied: {}", path.display())
+            }
+            ProjectFileError::DiskFull(path) => {
+                write!(f, "Not enough disk space to save {}", path.display())
+            }
+            ProjectFileError::NotFound(path) => {
+                write!(f, "Project file not found: {}", path.display())
+            }
+            ProjectFileError::InvalidProject(path, e) => {
+                write!(f, "Invalid project file {}: {}", path.display(), e)
+            }
+            ProjectFileError::Serialize(e) => write!(f, "Failed to serialize project: {}", e),
+            ProjectFileError::Io(path, e) => write!(f, "I/O error on {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ProjectFileError {}
+
+impl ProjectFileError {
+    fn from_io(path: &Path, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => {
+                ProjectFileError::PermissionDenied(path.to_path_buf())
+            }
+            io::ErrorKind::NotFound => ProjectFileError::NotFound(path.to_path_buf()),
+            io::ErrorKind::StorageFull => ProjectFileError::DiskFull(path.to_path_buf()),
+            _ => ProjectFileError::Io(path.to_path_buf(), error),
+        }
+    }
+}
+
+/// A backup of a project file available for restore
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectBackup {
+    /// Backup file path
+    pub path: String,
+    /// Last modification time of the backup
+    pub modified_at: DateTime<Utc>,
+    /// Backup size in bytes
+    pub size: u64,
+}
+
+/// Load a project from disk, resolving project- and cache-relative paths
+pub fn load_project_file(path: &Path) -> Result<Project, ProjectFileError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| ProjectFileError::from_io(path, e))?;
+    let mut project: Project = serde_json::from_str(&contents)
+        .map_err(|e| ProjectFileError::InvalidProject(path.to_path_buf(), e.to_string()))?;
+    // Files from before the order was kept contiguous
+    normalize_track_order(&mut project.tracks);
+    for repair in project.repair_clip_times() {
+        log::warn!("[Project] {}", repair);
+    }
+
+    if let Some(dir) = project_dir(path) {
+        resolve_media_paths(&mut project, &dir);
+    }
+    if let Some(cache_dir) = active_cache_dir() {
+        resolve_project_paths(&mut project, &cache_dir.to_string_lossy());
+    }
+    Ok(project)
+}
+
+/// Save a project atomically, rotating the previous version into the backups
+///
+/// Media under the project file's directory is stored relative to it, and
+/// proxies and thumbnails relative to the cache directory.
+/// On failure the existing file at `path` is left untouched.
+pub fn save_project_file(project: &Project, path: &Path) -> Result<(), ProjectFileError> {
+    let mut portable = project.clone();
+    if let Some(dir) = project_dir(path) {
+        make_media_paths_relative(&mut portable, &dir);
+    }
+    if let Some(cache_dir) = active_cache_dir() {
+        store_project_paths(&mut portable, &cache_dir.to_string_lossy());
+    }
+
+    let json = serde_json::to_vec_pretty(&portable)
+        .map_err(|e| ProjectFileError::Serialize(e.to_string()))?;
+    write_atomic(path, |file| file.write_all(&json))
+}
+
+/// Directory containing the project file (None for a bare file name)
+fn project_dir(path: &Path) -> Option<String> {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// Backup path for the given slot (0 = most recent)
+///
+/// `project.clipforge` -> `project.clipforge.bak`, `project.clipforge.bak.1`, ...
+pub fn backup_path(path: &Path, slot: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    if slot == 0 {
+        name.push(".bak");
+    } else {
+        name.push(format!(".bak.{}", slot));
+    }
+    PathBuf::from(name)
+}
+
+/// List existing backups of a project file, most recent first
+pub fn list_project_backups(path: &Path) -> Vec<ProjectBackup> {
+    (0..MAX_PROJECT_BACKUPS)
+        .map(|slot| backup_path(path, slot))
+        .filter_map(|backup| {
+            let metadata = std::fs::metadata(&backup).ok()?;
+            Some(ProjectBackup {
+                path: backup.to_string_lossy().to_string(),
+                modified_at: metadata.modified().ok()?.into(),
+                size: metadata.len(),
+            })
+        })
+        .collect()
+}
+
+/// Restore a backup over the project file
+///
+/// The backup must be a valid project. The current file is rotated into the
+/// backups first, so a restore can itself be undone.
+pub fn restore_project_file(path: &Path, backup: &Path) -> Result<Project, ProjectFileError> {
+    let project = load_project_file(backup)?;
+    let bytes = std::fs::read(backup).map_err(|e| ProjectFileError::from_io(backup, e))?;
+    write_atomic(path, |file| file.write_all(&bytes))?;
+    Ok(project)
+}
+
+/// Write a file via temp file + fsync + rename, keeping rotating backups
+fn write_atomic<F>(path: &Path, write: F) -> Result<(), ProjectFileError>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| ProjectFileError::NotFound(path.to_path_buf()))?
+        .to_string_lossy()
+        .to_string();
+    let temp_path = dir.join(format!(".{}.tmp-{}", file_name, uuid::Uuid::new_v4()));
+
+    // Write and fsync the temp file; remove it on any failure
+    let written = File::create(&temp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(ProjectFileError::from_io(path, e));
+    }
+
+    // Keep the previous version before replacing it
+    if path.exists() {
+        if let Err(e) = rotate_backups(path) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(ProjectFileError::from_io(path, e));
+        }
+    }
+
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(ProjectFileError::from_io(path, e));
+    }
+
+    // Persist the rename itself (not supported on Windows)
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(&dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Shift backups down one slot and copy the current file into slot 0
+fn rotate_backups(path: &Path) -> io::Result<()> {
+    for slot in (0..MAX_PROJECT_BACKUPS - 1).rev() {
+        let from = backup_path(path, slot);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, slot + 1))?;
+        }
+    }
+    // Copy (not rename) so the project file stays in place until the atomic rename
+    std::fs::copy(path, backup_path(path, 0))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::timeline::TrackDucking;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+
+        let mut project = Project::new("Demo".to_string());
+        let ducking = TrackDucking {
+            against_track_ids: vec!["narration".to_string()],
+            amount_db: 12.0,
+            attack_ms: 20.0,
+            release_ms: 300.0,
+        };
+        project.tracks[0].ducking = Some(ducking.clone());
+        save_project_file(&project, &path).unwrap();
+
+        let loaded = load_project_file(&path).unwrap();
+        assert_eq!(loaded.id, project.id);
+        assert_eq!(loaded.name, "Demo");
+        assert_eq!(loaded.tracks[0].ducking, Some(ducking));
+        // First save has nothing to back up
+        assert!(list_project_backups(&path).is_empty());
+
+        // No temp files left behind
+        let entries: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_load_repairs_non_finite_clip_times() {
+        use crate::models::clip::MediaClip;
+        use crate::models::timeline::TimelineClip;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+        let mut project = Project::new("Demo".to_string());
+        let media = MediaClip::new(
+            "/media/a.mp4".into(),
+            8.0,
+            1920,
+            1080,
+            30.0,
+            "h264".into(),
+            1,
+        );
+        let track_id = project.tracks[0].id.clone();
+        let clip = |media_clip_id: &str, start, in_point, out_point| {
+            TimelineClip::new(
+                media_clip_id.into(),
+                track_id.clone(),
+                start,
+                in_point,
+                out_point,
+            )
+        };
+        project.tracks[0].clips = vec![
+            clip(&media.id, 0.0, 0.0, 4.0),
+            clip(&media.id, f64::NAN, 1.0, 3.0),
+            clip(&media.id, 4.0, 2.0, f64::INFINITY),
+            clip("unknown", 9.0, 0.0, f64::NAN),
+        ];
+        project.media_library.push(media);
+
+        // NaN and infinity are written as null
+        save_project_file(&project, &path).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(json.matches("\"start_time\": null").count(), 1);
+        assert_eq!(json.matches("\"out_point\": null").count(), 2);
+
+        let loaded = load_project_file(&path).unwrap();
+        let times: Vec<(f64, f64, f64)> = loaded.tracks[0]
+            .clips
+            .iter()
+            .map(|c| (c.start_time, c.in_point, c.out_point))
+            .collect();
+        // The unplaceable clip moves to the track end, the open-ended one
+        // plays to the end of its media, and the one with no media is dropped
+        assert_eq!(times, [(0.0, 0.0, 4.0), (10.0, 1.0, 3.0), (4.0, 2.0, 8.0)]);
+    }
+
+    #[test]
+    fn test_media_under_project_dir_saved_relative() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+        let media_path = temp_dir.path().join("media").join("a.mp4");
+
+        let mut project = Project::new("Demo".to_string());
+        project
+            .media_library
+            .push(crate::models::clip::MediaClip::new(
+                media_path.to_string_lossy().to_string(),
+                1.0,
+                1920,
+                1080,
+                30.0,
+                "h264".to_string(),
+                5,
+            ));
+        save_project_file(&project, &path).unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(raw.contains("$PROJECT/media/a.mp4"));
+        // The in-memory project keeps absolute paths
+        assert_eq!(
+            project.media_library[0].source_path,
+            media_path.to_string_lossy()
+        );
+
+        let loaded = load_project_file(&path).unwrap();
+        assert_eq!(
+            Path::new(&loaded.media_library[0].source_path),
+            media_path.as_path()
+        );
+    }
+
+    #[test]
+    fn test_backups_rotate_up_to_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+
+        let mut project = Project::new("v0".to_string());
+        for version in 0..5 {
+            project.name = format!("v{}", version);
+            save_project_file(&project, &path).unwrap();
+        }
+
+        let backups = list_project_backups(&path);
+        assert_eq!(backups.len(), MAX_PROJECT_BACKUPS);
+        assert!(!backup_path(&path, MAX_PROJECT_BACKUPS).exists());
+
+        // Most recent backup is the version before the current one
+        assert_eq!(load_project_file(&path).unwrap().name, "v4");
+        assert_eq!(
+            load_project_file(&backup_path(&path, 0)).unwrap().name,
+            "v3"
+        );
+        assert_eq!(
+            load_project_file(&backup_path(&path, 2)).unwrap().name,
+            "v1"
+        );
+    }
+
+    #[test]
+    fn test_write_failure_leaves_original_intact() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+
+        let project = Project::new("Original".to_string());
+        save_project_file(&project, &path).unwrap();
+        let original = std::fs::read(&path).unwrap();
+
+        // Simulate the disk filling up halfway through the write
+        let result = write_atomic(&path, |file| {
+            file.write_all(b"{\"id\": \"trunc")?;
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        });
+
+        assert!(matches!(result, Err(ProjectFileError::DiskFull(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        assert_eq!(load_project_file(&path).unwrap().name, "Original");
+        // Failed write neither rotated backups nor left a temp file
+        assert!(list_project_backups(&path).is_empty());
+        let entries: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_save_to_missing_directory_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing").join("demo.clipforge");
+
+        let result = save_project_file(&Project::new("Demo".to_string()), &path);
+        assert!(matches!(result, Err(ProjectFileError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.clipforge");
+
+        let mut project = Project::new("Good".to_string());
+        save_project_file(&project, &path).unwrap();
+        project.name = "Bad edit".to_string();
+        save_project_file(&project, &path).unwrap();
+
+        let restored = restore_project_file(&path, &backup_path(&path, 0)).unwrap();
+        assert_eq!(restored.name, "Good");
+        assert_eq!(load_project_file(&path).unwrap().name, "Good");
+
+        // The replaced version is kept, so the restore can be undone
+        assert_eq!(
+            load_project_file(&backup_path(&path, 0)).unwrap().name,
+            "Bad edit"
+        );
+
+        // Invalid backups are rejected without touching the project file
+        let bogus = temp_dir.path().join("bogus.bak");
+        std::fs::write(&bogus, "not json").unwrap();
+        assert!(matches!(
+            restore_project_file(&path, &bogus),
+            Err(ProjectFileError::InvalidProject(..))
+        ));
+        assert_eq!(load_project_file(&path).unwrap().name, "Good");
+    }
+}
@@ -0,0 +1,443 @@
+// Merging a section of another project into the current one
+// Every id coming from the other project is replaced with a fresh one so the
+// two graphs can't collide; media already in the current library is reused
+// instead of duplicated.
+
+use crate::models::clip::MediaClip;
+use crate::models::project::Project;
+use crate::models::timeline::{normalize_track_order, Track, TrackType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Bytes hashed from each end of a file for the content fingerprint
+const FINGERPRINT_CHUNK: u64 = 64 * 1024;
+
+/// Where imported tracks go in the current timeline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionPlacement {
+    /// Add every imported track as a new track
+    #[default]
+    NewTracks,
+    /// Concatenate imported tracks onto the first existing track of the same type
+    AppendToMatching,
+}
+
+/// Options for `import_project_section`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportSectionOptions {
+    pub placement: SectionPlacement,
+}
+
+/// Summary of a merged project section
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSectionSummary {
+    /// Ids of tracks created for the section
+    pub tracks_added: Vec<String>,
+    /// Ids of existing tracks the section was appended to
+    pub tracks_extended: Vec<String>,
+    /// Number of timeline clips imported
+    pub clips_imported: usize,
+    /// Media clips added to the library (with their new ids)
+    pub media_added: Vec<MediaClip>,
+    /// Number of media clips matched to existing library entries
+    pub media_reused: usize,
+    /// Timeline position (seconds) the section was placed at
+    pub offset: f64,
+    /// Conflicts that were reported rather than resolved (e.g. fps mismatch)
+    pub warnings: Vec<String>,
+}
+
+/// Merge `section` into `target`, placing its tracks at the current timeline end
+///
+/// The timeline has a single main track, so the section's main track is always
+/// concatenated onto the existing one.
+pub fn merge_project_section(
+    target: &mut Project,
+    section: Project,
+    options: &ImportSectionOptions,
+) -> ImportSectionSummary {
+    let mut summary = ImportSectionSummary {
+        offset: target.duration(),
+        warnings: settings_conflicts(target, &section),
+        ..Default::default()
+    };
+
+    // Media: reuse matching library entries, otherwise import under a fresh id
+    let mut media_ids: HashMap<String, String> = HashMap::new();
+    let mut fingerprints: HashMap<String, Option<u64>> = HashMap::new();
+    for mut clip in section.media_library {
+        if let Some(existing) = find_duplicate(&target.media_library, &clip, &mut fingerprints) {
+            media_ids.insert(clip.id, existing);
+            summary.media_reused += 1;
+            continue;
+        }
+
+        let new_id = uuid::Uuid::new_v4().to_string();
+        media_ids.insert(clip.id.clone(), new_id.clone());
+        clip.id = new_id;
+        for caption in &mut clip.captions {
+            caption.id = uuid::Uuid::new_v4().to_string();
+            caption.media_clip_id = clip.id.clone();
+        }
+        target.media_library.push(clip.clone());
+        summary.media_added.push(clip);
+    }
+
+    // Tracks: remap ids, shift clips to the offset, and place them
+    let mut next_order = target.tracks.iter().map(|t| t.order + 1).max().unwrap_or(0);
+    let mut sorted_tracks = section.tracks;
+    sorted_tracks.sort_by_key(|t| t.order);
+
+    for source_track in sorted_tracks {
+        let append_to = match (options.placement, &source_track.track_type) {
+            (_, TrackType::Main) | (SectionPlacement::AppendToMatching, _) => target
+                .tracks
+                .iter()
+                .position(|t| same_type(&t.track_type, &source_track.track_type)),
+            (SectionPlacement::NewTracks, TrackType::Overlay) => None,
+        };
+
+        // Clips get new ids, which the transitions don't follow
+        let mut new_track = Track {
+            id: uuid::Uuid::new_v4().to_string(),
+            order: next_order,
+            clips: Vec::new(),
+            transitions: Vec::new(),
+            ..source_track.clone()
+        };
+        let track_id = append_to
+            .map(|index| target.tracks[index].id.clone())
+            .unwrap_or_else(|| new_track.id.clone());
+
+        let mut clips = Vec::new();
+        for mut clip in source_track.clips {
+            let Some(media_id) = media_ids.get(&clip.media_clip_id) else {
+                summary.warnings.push(format!(
+                    "Skipped a clip on track '{}': its media is missing from the imported project",
+                    source_track.name
+                ));
+                continue;
+            };
+            clip.id = uuid::Uuid::new_v4().to_string();
+            clip.media_clip_id = media_id.clone();
+            clip.track_id = track_id.clone();
+            clip.start_time += summary.offset;
+            clips.push(clip);
+        }
+        summary.clips_imported += clips.len();
+
+        match append_to {
+            Some(index) => {
+                let track = &mut target.tracks[index];
+                track.clips.extend(clips);
+                if !summary.tracks_extended.contains(&track.id) {
+                    summary.tracks_extended.push(track.id.clone());
+                }
+            }
+            None => {
+                new_track.clips = clips;
+                summary.tracks_added.push(new_track.id.clone());
+                target.tracks.push(new_track);
+                next_order += 1;
+            }
+        }
+    }
+    normalize_track_order(&mut target.tracks);
+
+    summary
+}
+
+/// Warnings for sequence settings that differ between the two projects
+fn settings_conflicts(target: &Project, section: &Project) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if (target.settings.fps - section.settings.fps).abs() > 0.001 {
+        warnings.push(format!(
+            "Frame rate mismatch: '{}' is {} fps but this project is {} fps; clips were not converted",
+            section.name, section.settings.fps, target.settings.fps
+        ));
+    }
+    if (target.settings.width, target.settings.height)
+        != (section.settings.width, section.settings.height)
+    {
+        warnings.push(format!(
+            "Resolution mismatch: '{}' is {}x{} but this project is {}x{}",
+            section.name,
+            section.settings.width,
+            section.settings.height,
+            target.settings.width,
+            target.settings.height
+        ));
+    }
+    warnings
+}
+
+/// Id of a library clip that is the same media as `clip`: same source path, or
+/// same size and content fingerprint (a copy of the file elsewhere)
+fn find_duplicate(
+    library: &[MediaClip],
+    clip: &MediaClip,
+    fingerprints: &mut HashMap<String, Option<u64>>,
+) -> Option<String> {
+    if let Some(existing) = library.iter().find(|c| c.source_path == clip.source_path) {
+        return Some(existing.id.clone());
+    }
+
+    let mut fingerprint = |path: &str| {
+        *fingerprints
+            .entry(path.to_string())
+            .or_insert_with(|| content_fingerprint(path))
+    };
+
+    library
+        .iter()
+        .filter(|c| c.file_size == clip.file_size && c.file_size > 0)
+        .find(|c| {
+            let ours = fingerprint(&clip.source_path);
+            ours.is_some() && ours == fingerprint(&c.source_path)
+        })
+        .map(|c| c.id.clone())
+}
+
+/// Cheap content hash: file length plus the first and last 64 KiB
+fn content_fingerprint(path: &str) -> Option<u64> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(len);
+
+    let mut buffer = Vec::with_capacity(FINGERPRINT_CHUNK as usize);
+    file.by_ref()
+        .take(FINGERPRINT_CHUNK)
+        .read_to_end(&mut buffer)
+        .ok()?;
+    hasher.write(&buffer);
+
+    if len > FINGERPRINT_CHUNK * 2 {
+        buffer.clear();
+        file.seek(SeekFrom::End(-(FINGERPRINT_CHUNK as i64))).ok()?;
+        file.read_to_end(&mut buffer).ok()?;
+        hasher.write(&buffer);
+    }
+
+    Some(hasher.finish())
+}
+
+fn same_type(a: &TrackType, b: &TrackType) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::caption::Caption;
+    use crate::models::timeline::TimelineClip;
+    use std::collections::HashSet;
+
+    fn media(path: &str, size: i64) -> MediaClip {
+        MediaClip::new(
+            path.to_string(),
+            10.0,
+            1920,
+            1080,
+            30.0,
+            "h264".into(),
+            size,
+        )
+    }
+
+    fn with_overlay(name: &str) -> Project {
+        let mut project = Project::new(name.into());
+        let mut overlay = Track::new("Overlay".into(), TrackType::Overlay);
+        overlay.order = 1;
+        project.tracks.push(overlay);
+        project
+    }
+
+    fn place(project: &mut Project, track_index: usize, media_id: &str, start: f64, len: f64) {
+        let track_id = project.tracks[track_index].id.clone();
+        project.tracks[track_index].clips.push(TimelineClip::new(
+            media_id.to_string(),
+            track_id,
+            start,
+            0.0,
+            len,
+        ));
+    }
+
+    /// Current project: one 8s clip on main. Section: shared + new media on main
+    /// and overlay, with a caption on the new media.
+    fn fixture() -> (Project, Project) {
+        let mut current = with_overlay("Current");
+        let shared = media("/media/shared.mp4", 100);
+        current.media_library.push(shared.clone());
+        place(&mut current, 0, &shared.id, 0.0, 8.0);
+
+        let mut section = with_overlay("Section");
+        let shared_copy = media("/media/shared.mp4", 100);
+        let mut fresh = media("/media/fresh.mp4", 200);
+        fresh.captions.push(Caption::new(
+            fresh.id.clone(),
+            "hi".into(),
+            0.0,
+            1.0,
+            "en".into(),
+        ));
+        section.media_library.push(shared_copy.clone());
+        section.media_library.push(fresh.clone());
+        place(&mut section, 0, &shared_copy.id, 0.0, 4.0);
+        place(&mut section, 1, &fresh.id, 1.0, 2.0);
+
+        (current, section)
+    }
+
+    #[test]
+    fn test_all_ids_are_remapped() {
+        let (mut current, section) = fixture();
+        let section_ids: HashSet<String> = section
+            .tracks
+            .iter()
+            .flat_map(|t| std::iter::once(t.id.clone()).chain(t.clips.iter().map(|c| c.id.clone())))
+            .chain(section.media_library.iter().flat_map(|m| {
+                std::iter::once(m.id.clone()).chain(m.captions.iter().map(|c| c.id.clone()))
+            }))
+            .collect();
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+
+        for track in &current.tracks {
+            assert!(!section_ids.contains(&track.id));
+            for clip in &track.clips {
+                assert!(!section_ids.contains(&clip.id));
+                assert_eq!(clip.track_id, track.id);
+                assert!(current
+                    .media_library
+                    .iter()
+                    .any(|m| m.id == clip.media_clip_id));
+            }
+        }
+        for clip in &current.media_library {
+            assert!(!section_ids.contains(&clip.id));
+            for caption in &clip.captions {
+                assert!(!section_ids.contains(&caption.id));
+                assert_eq!(caption.media_clip_id, clip.id);
+            }
+        }
+        assert_eq!(summary.clips_imported, 2);
+    }
+
+    #[test]
+    fn test_media_deduplicated_by_source_path() {
+        let (mut current, section) = fixture();
+        let shared_id = current.media_library[0].id.clone();
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+
+        assert_eq!(summary.media_reused, 1);
+        assert_eq!(summary.media_added.len(), 1);
+        assert_eq!(current.media_library.len(), 2);
+        // The section's main clip now points at the existing library entry
+        assert_eq!(current.tracks[0].clips[1].media_clip_id, shared_id);
+    }
+
+    #[test]
+    fn test_media_deduplicated_by_content() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = temp_dir.path().join("a.mp4");
+        let copy = temp_dir.path().join("copy-of-a.mp4");
+        let other = temp_dir.path().join("b.mp4");
+        let bytes = vec![7u8; 300 * 1024];
+        std::fs::write(&original, &bytes).unwrap();
+        std::fs::write(&copy, &bytes).unwrap();
+        let mut different = bytes.clone();
+        different[150 * 1024] = 1;
+        different[0] = 1;
+        std::fs::write(&other, &different).unwrap();
+
+        let size = bytes.len() as i64;
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+
+        let mut current = Project::new("Current".into());
+        current.media_library.push(media(&path(&original), size));
+
+        let mut section = Project::new("Section".into());
+        section.media_library.push(media(&path(&copy), size));
+        section.media_library.push(media(&path(&other), size));
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+        assert_eq!(summary.media_reused, 1);
+        assert_eq!(summary.media_added.len(), 1);
+        assert_eq!(summary.media_added[0].source_path, path(&other));
+    }
+
+    #[test]
+    fn test_new_tracks_placement() {
+        let (mut current, section) = fixture();
+        let main_id = current.tracks[0].id.clone();
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+
+        assert_eq!(summary.offset, 8.0);
+        // Main is always shared; the overlay becomes a new track
+        assert_eq!(summary.tracks_extended, vec![main_id]);
+        assert_eq!(summary.tracks_added.len(), 1);
+        assert_eq!(current.tracks.len(), 3);
+
+        let added = current.tracks.last().unwrap();
+        assert_eq!(added.id, summary.tracks_added[0]);
+        assert_eq!(added.order, 2);
+        assert_eq!(added.clips[0].start_time, 9.0);
+        assert_eq!(current.tracks[0].clips[1].start_time, 8.0);
+    }
+
+    #[test]
+    fn test_append_to_matching_placement() {
+        let (mut current, section) = fixture();
+        let overlay_id = current.tracks[1].id.clone();
+        let options = ImportSectionOptions {
+            placement: SectionPlacement::AppendToMatching,
+        };
+
+        let summary = merge_project_section(&mut current, section, &options);
+
+        assert!(summary.tracks_added.is_empty());
+        assert_eq!(summary.tracks_extended.len(), 2);
+        assert_eq!(current.tracks.len(), 2);
+        assert_eq!(current.tracks[1].id, overlay_id);
+        assert_eq!(current.tracks[1].clips[0].track_id, overlay_id);
+        assert_eq!(current.tracks[1].clips[0].start_time, 9.0);
+    }
+
+    #[test]
+    fn test_settings_mismatch_is_reported_not_converted() {
+        let (mut current, mut section) = fixture();
+        section.settings.fps = 25.0;
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+
+        assert_eq!(summary.warnings.len(), 1);
+        assert!(summary.warnings[0].contains("Frame rate mismatch"));
+        assert_eq!(current.settings.fps, 30.0);
+    }
+
+    #[test]
+    fn test_clips_with_unknown_media_are_skipped() {
+        let (mut current, mut section) = fixture();
+        place(&mut section, 1, "missing-media", 0.0, 1.0);
+
+        let summary =
+            merge_project_section(&mut current, section, &ImportSectionOptions::default());
+
+        assert_eq!(summary.clips_imported, 2);
+        assert!(summary.warnings.iter().any(|w| w.contains("missing")));
+    }
+}
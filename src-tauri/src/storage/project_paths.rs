@@ -0,0 +1,230 @@
+// Project-relative media paths
+// Media stored under the project file's directory is saved relative to it, so a
+// project folder can be moved to another drive or machine without breaking clips.
+//
+// Paths are handled as strings (not std::path) so Windows paths behave the same
+// regardless of the platform the project is opened on.
+
+use crate::models::project::Project;
+
+/// Prefix marking a source path as relative to the project file's directory
+pub const PROJECT_DIR_PREFIX: &str = "$PROJECT/";
+
+/// Rewrite media paths under `project_dir` as project-relative (used on save)
+pub fn make_media_paths_relative(project: &mut Project, project_dir: &str) {
+    for clip in &mut project.media_library {
+        if let Some(relative) = to_project_relative(&clip.source_path, project_dir) {
+            clip.source_path = relative;
+        }
+    }
+}
+
+/// Resolve project-relative media paths against `project_dir` (used on load)
+pub fn resolve_media_paths(project: &mut Project, project_dir: &str) {
+    for clip in &mut project.media_library {
+        clip.source_path = resolve_project_path(&clip.source_path, project_dir);
+    }
+}
+
+/// Source paths of media files that don't exist on disk
+pub fn find_missing_media(project: &Project) -> Vec<String> {
+    project
+        .media_library
+        .iter()
+        .filter(|clip| !std::path::Path::new(&clip.source_path).exists())
+        .map(|clip| clip.source_path.clone())
+        .collect()
+}
+
+/// Convert an absolute path to `$PROJECT/...` if it lives under `project_dir`
+pub fn to_project_relative(path: &str, project_dir: &str) -> Option<String> {
+    to_prefixed_relative(path, project_dir, PROJECT_DIR_PREFIX)
+}
+
+/// Resolve a stored path; absolute paths are returned unchanged
+pub fn resolve_project_path(stored: &str, project_dir: &str) -> String {
+    resolve_prefixed_path(stored, project_dir, PROJECT_DIR_PREFIX)
+}
+
+/// `<prefix>a/b` for a path under `dir`, with `/` separators
+pub(crate) fn to_prefixed_relative(path: &str, dir: &str, prefix: &str) -> Option<String> {
+    let path_parts = split_path(path);
+    let dir_parts = split_path(dir);
+
+    if dir_parts.is_empty() || path_parts.len() <= dir_parts.len() {
+        return None;
+    }
+
+    let windows = is_windows_path(dir);
+    let under_dir = dir_parts.iter().zip(&path_parts).all(|(d, p)| {
+        if windows {
+            d.eq_ignore_ascii_case(p)
+        } else {
+            d == p
+        }
+    });
+
+    if !under_dir || path_parts[dir_parts.len()..].contains(&"..") {
+        return None;
+    }
+
+    Some(format!(
+        "{}{}",
+        prefix,
+        path_parts[dir_parts.len()..].join("/")
+    ))
+}
+
+/// Resolve `<prefix>a/b` against `dir` with its separator; other paths are
+/// returned unchanged
+pub(crate) fn resolve_prefixed_path(stored: &str, dir: &str, prefix: &str) -> String {
+    let Some(relative) = stored.strip_prefix(prefix) else {
+        return stored.to_string();
+    };
+
+    let separator = if is_windows_path(dir) { '\\' } else { '/' };
+    let dir = dir.trim_end_matches(['/', '\\']);
+    let relative = relative.replace('/', &separator.to_string());
+
+    format!("{}{}{}", dir, separator, relative)
+}
+
+/// Whether a path uses Windows conventions (drive letter or UNC prefix)
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    drive || path.starts_with("\\\\")
+}
+
+/// Split a path into components, accepting both separators
+fn split_path(path: &str) -> Vec<&str> {
+    path.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_paths() {
+        assert_eq!(
+            to_project_relative("/Users/me/Film/media/a.mp4", "/Users/me/Film"),
+            Some("$PROJECT/media/a.mp4".to_string())
+        );
+        assert_eq!(
+            to_project_relative("/Users/me/Film/a.mp4", "/Users/me/Film/"),
+            Some("$PROJECT/a.mp4".to_string())
+        );
+        // Outside the project directory stays absolute
+        assert_eq!(
+            to_project_relative("/Users/me/Other/a.mp4", "/Users/me/Film"),
+            None
+        );
+        // Sibling directory sharing a name prefix is not "under" the project
+        assert_eq!(
+            to_project_relative("/Users/me/Film2/a.mp4", "/Users/me/Film"),
+            None
+        );
+        // Case matters on Unix
+        assert_eq!(
+            to_project_relative("/users/me/film/a.mp4", "/Users/me/Film"),
+            None
+        );
+
+        assert_eq!(
+            resolve_project_path("$PROJECT/media/a.mp4", "/Volumes/USB/Film"),
+            "/Volumes/USB/Film/media/a.mp4"
+        );
+        assert_eq!(
+            resolve_project_path("/elsewhere/a.mp4", "/Volumes/USB/Film"),
+            "/elsewhere/a.mp4"
+        );
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        assert_eq!(
+            to_project_relative(r"C:\Users\me\Film\media\a.mp4", r"C:\Users\me\Film"),
+            Some("$PROJECT/media/a.mp4".to_string())
+        );
+        // Drive letters and folders are case-insensitive
+        assert_eq!(
+            to_project_relative(r"c:\users\ME\film\a.mp4", r"C:\Users\me\Film"),
+            Some("$PROJECT/a.mp4".to_string())
+        );
+        // Mixed separators
+        assert_eq!(
+            to_project_relative("C:/Users/me/Film/media/a.mp4", r"C:\Users\me\Film"),
+            Some("$PROJECT/media/a.mp4".to_string())
+        );
+        // Different drive stays absolute
+        assert_eq!(
+            to_project_relative(r"D:\Users\me\Film\a.mp4", r"C:\Users\me\Film"),
+            None
+        );
+        // UNC share
+        assert_eq!(
+            to_project_relative(r"\\nas\share\Film\a.mp4", r"\\nas\share\Film"),
+            Some("$PROJECT/a.mp4".to_string())
+        );
+
+        // Project moved to another drive letter
+        assert_eq!(
+            resolve_project_path("$PROJECT/media/a.mp4", r"E:\Backup\Film\"),
+            r"E:\Backup\Film\media\a.mp4"
+        );
+        assert_eq!(
+            resolve_project_path("$PROJECT/a.mp4", r"\\nas\share\Film"),
+            r"\\nas\share\Film\a.mp4"
+        );
+    }
+
+    #[test]
+    fn test_parent_references_stay_absolute() {
+        assert_eq!(
+            to_project_relative("/Users/me/Film/../Other/a.mp4", "/Users/me/Film"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_project_round_trip_and_missing_media() {
+        use crate::models::clip::MediaClip;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_string_lossy().to_string();
+        std::fs::create_dir_all(temp_dir.path().join("media")).unwrap();
+        let inside = temp_dir.path().join("media").join("a.mp4");
+        std::fs::write(&inside, b"video").unwrap();
+
+        let clip =
+            |path: String| MediaClip::new(path, 1.0, 1920, 1080, 30.0, "h264".to_string(), 5);
+
+        let mut project = Project::new("Portable".to_string());
+        project
+            .media_library
+            .push(clip(inside.to_string_lossy().to_string()));
+        project
+            .media_library
+            .push(clip("/nonexistent/clipforge/b.mp4".to_string()));
+
+        make_media_paths_relative(&mut project, &project_dir);
+        assert_eq!(project.media_library[0].source_path, "$PROJECT/media/a.mp4");
+        assert_eq!(
+            project.media_library[1].source_path,
+            "/nonexistent/clipforge/b.mp4"
+        );
+
+        resolve_media_paths(&mut project, &project_dir);
+        assert_eq!(
+            std::path::Path::new(&project.media_library[0].source_path),
+            inside.as_path()
+        );
+        assert_eq!(
+            find_missing_media(&project),
+            vec!["/nonexistent/clipforge/b.mp4".to_string()]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+// App settings loading and first-run migration
+// Settings live in the cache database; older installs kept whisper options in ~/.clipforge/config.json
+
+use crate::models::settings::{AppSettings, WhisperSettings};
+use crate::storage::cache::CacheDb;
+use std::path::{Path, PathBuf};
+
+/// Path of the legacy config file that predates the settings table
+pub fn legacy_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".clipforge").join("config.json"))
+}
+
+/// Load app settings, migrating the legacy whisper config on first run
+///
+/// Never fails: unreadable settings fall back to defaults so startup can continue.
+pub fn load_app_settings(cache_db: &CacheDb, legacy_config: Option<&Path>) -> AppSettings {
+    let whisper_migrated = matches!(cache_db.get_setting("whisper"), Ok(Some(_)));
+
+    let mut settings = match cache_db.load_app_settings() {
+        Ok(Some(settings)) => settings,
+        Ok(None) => AppSettings::default(),
+        Err(e) => {
+            eprintln!("[Settings] Failed to load settings, using defaults: {}", e);
+            AppSettings::default()
+        }
+    };
+
+    if !whisper_migrated {
+        if let Some(whisper) = legacy_config.and_then(read_legacy_whisper_config) {
+            println!("[Settings] Migrated whisper options from legacy config.json");
+            settings.whisper = whisper;
+        }
+        if let Err(e) = cache_db.save_app_settings(&settings) {
+            eprintln!("[Settings] Failed to persist settings: {}", e);
+        }
+    }
+
+    settings
+}
+
+/// Read the `whisper` section of the legacy config.json
+fn read_legacy_whisper_config(path: &Path) -> Option<WhisperSettings> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let config_json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let whisper = config_json.get("whisper")?.clone();
+
+    match serde_json::from_value::<WhisperSettings>(whisper) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            eprintln!("[Settings] Ignoring invalid legacy whisper config: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrates_legacy_whisper_config_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+        let legacy = temp_dir.path().join("config.json");
+        std::fs::write(
+            &legacy,
+            r#"{"whisper": {"executable_path": "/opt/whisper/main", "model_path": "/models/base.bin", "default_language": "de"}}"#,
+        )
+        .unwrap();
+
+        let settings = load_app_settings(&cache_db, Some(&legacy));
+        assert_eq!(settings.whisper.executable_path, "/opt/whisper/main");
+        assert_eq!(settings.whisper.default_language, "de");
+        // Fields missing from the legacy file keep their defaults
+        assert_eq!(settings.whisper.threads, 4);
+
+        // Later edits to the legacy file are no longer picked up
+        std::fs::write(
+            &legacy,
+            r#"{"whisper": {"executable_path": "/other", "model_path": "/m.bin"}}"#,
+        )
+        .unwrap();
+        let reloaded = load_app_settings(&cache_db, Some(&legacy));
+        assert_eq!(reloaded.whisper.executable_path, "/opt/whisper/main");
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+
+        assert!(cache_db.load_app_settings().unwrap().is_none());
+
+        let mut settings = AppSettings::default();
+        settings.proxy.crf = 30;
+        settings.export_thread_cap = Some(8);
+        cache_db.save_app_settings(&settings).unwrap();
+
+        let loaded = load_app_settings(&cache_db, None);
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_corrupt_setting_falls_back_per_field() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_db = CacheDb::new(&temp_dir.path().join("cache.db")).unwrap();
+
+        let settings = AppSettings {
+            autosave_interval_secs: 300,
+            ..Default::default()
+        };
+        cache_db.save_app_settings(&settings).unwrap();
+
+        // Simulate a value written with an incompatible type
+        let conn =
+            crate::storage::cache::initialize_cache(&temp_dir.path().join("cache.db")).unwrap();
+        conn.execute(
+            "UPDATE settings SET value = '\"fast\"' WHERE key = 'proxy'",
+            [],
+        )
+        .unwrap();
+
+        let loaded = cache_db.load_app_settings().unwrap().unwrap();
+        assert_eq!(loaded.autosave_interval_secs, 300);
+        assert_eq!(loaded.proxy, Default::default());
+    }
+}
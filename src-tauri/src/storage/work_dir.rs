@@ -0,0 +1,269 @@
+// Work directory for large temporary files
+// Export temp dirs, caption audio/subtitle extraction and preview renders go
+// under `<work_directory>/clipforge/<area>`. The setting defaults to the
+// system temp dir, which is often a small tmpfs, so users with long timelines
+// can point it at a bigger drive. Jobs resolve their paths when they start
+// and clean up after themselves, so changing the setting never moves files
+// out from under a running job.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// Free space required of a new work directory
+pub const MIN_WORK_DIR_FREE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Configured work directory (None = system temp)
+static WORK_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Subsystem owning a subfolder of the work directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkArea {
+    /// Per-job export temp dirs (concat lists, pre-rendered segments)
+    Export,
+    /// Audio and subtitle streams extracted for captions
+    Captions,
+    /// Rendered preview segments and clip segments, reused while valid
+    Preview,
+}
+
+impl WorkArea {
+    pub const ALL: [WorkArea; 3] = [WorkArea::Export, WorkArea::Captions, WorkArea::Preview];
+
+    fn folder(self) -> &'static str {
+        match self {
+            WorkArea::Export => "export",
+            WorkArea::Captions => "captions",
+            WorkArea::Preview => "preview",
+        }
+    }
+
+    /// Whatever is left here at startup belongs to a job that didn't finish
+    fn is_temporary(self) -> bool {
+        !matches!(self, WorkArea::Preview)
+    }
+}
+
+/// Use `dir` (None = system temp) for work files created from now on
+pub fn install(dir: Option<&Path>) {
+    *WORK_DIR.write().unwrap() = dir.map(Path::to_path_buf);
+}
+
+/// ClipForge's folder in `base` (None = system temp)
+pub fn work_root(base: Option<&Path>) -> PathBuf {
+    base.map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("clipforge")
+}
+
+/// Folder of `area` in the configured work directory, created if needed
+pub fn area_dir(area: WorkArea) -> Result<PathBuf, String> {
+    let base = WORK_DIR.read().unwrap().clone();
+    let dir = work_root(base.as_deref()).join(area.folder());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create work directory {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// A job's own folder in a work area, removed with its contents when dropped
+///
+/// Owned by the job's task, so the folder goes whether the job completes,
+/// fails or is cancelled (its task aborted) rather than waiting for `sweep`.
+#[derive(Debug)]
+pub struct JobDir(PathBuf);
+
+impl JobDir {
+    /// New uniquely named folder in `area`
+    pub fn create(area: WorkArea) -> Result<Self, String> {
+        Self::create_in(&area_dir(area)?)
+    }
+
+    fn create_in(parent: &Path) -> Result<Self, String> {
+        let dir = parent.join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+        Ok(Self(dir))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for JobDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            log::warn!("Failed to remove {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+/// Check that `dir` can hold work files: it exists, is writable and has at
+/// least `min_free_bytes` free (skipped where free space can't be queried)
+pub fn validate_work_directory(dir: &Path, min_free_bytes: u64) -> Result<(), String> {
+    check_writable("Work directory", dir)?;
+    check_free_space(dir, free_space(dir), min_free_bytes)
+}
+
+/// Check that `dir` exists and a file can be created in it; `what` names
+/// the directory in errors
+pub fn check_writable(what: &str, dir: &Path) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Err(format!("{} does not exist: {}", what, dir.display()));
+    }
+
+    let probe = dir.join(format!(".clipforge_write_test_{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"")
+        .map_err(|e| format!("{} is not writable: {}: {}", what, dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_free_space(dir: &Path, free: Option<u64>, min_free_bytes: u64) -> Result<(), String> {
+    match free {
+        Some(free) if free < min_free_bytes => Err(format!(
+            "Work directory {} has only {} MB free ({} MB needed)",
+            dir.display(),
+            free / (1024 * 1024),
+            min_free_bytes / (1024 * 1024)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Bytes available to this user on the filesystem holding `path`
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Remove what unfinished jobs left in the temporary areas of `root`
+/// (modified before `before`); returns the number of entries removed
+pub fn sweep(root: &Path, before: SystemTime) -> usize {
+    let mut removed = 0;
+    for area in WorkArea::ALL.into_iter().filter(|a| a.is_temporary()) {
+        let Ok(entries) = std::fs::read_dir(root.join(area.folder())) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < before);
+            if !stale {
+                continue;
+            }
+            let path = entry.path();
+            let result = if path.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            match result {
+                Ok(()) => removed += 1,
+                Err(e) => log::warn!("Failed to remove {}: {}", path.display(), e),
+            }
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_work_root_defaults_to_system_temp() {
+        assert_eq!(work_root(None), std::env::temp_dir().join("clipforge"));
+        assert_eq!(
+            work_root(Some(Path::new("/media/big"))),
+            PathBuf::from("/media/big/clipforge")
+        );
+    }
+
+    #[test]
+    fn test_validate_work_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(validate_work_directory(temp_dir.path(), 0).is_ok());
+        // Nothing is left behind by the writability probe
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+
+        let missing = temp_dir.path().join("missing");
+        let err = validate_work_directory(&missing, 0).unwrap_err();
+        assert!(err.contains("does not exist"));
+    }
+
+    #[test]
+    fn test_free_space_check() {
+        let dir = Path::new("/work");
+        let gb = 1024 * 1024 * 1024;
+
+        assert!(check_free_space(dir, Some(5 * gb), 2 * gb).is_ok());
+        let err = check_free_space(dir, Some(gb), 2 * gb).unwrap_err();
+        assert!(err.contains("1024 MB free (2048 MB needed)"), "{}", err);
+        // Unknown free space isn't held against the directory
+        assert!(check_free_space(dir, None, 2 * gb).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_free_space_of_existing_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(free_space(temp_dir.path()).is_some());
+        assert!(free_space(&temp_dir.path().join("missing")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_dir_removed_when_task_is_aborted() {
+        let temp_dir = TempDir::new().unwrap();
+        let job_dir = JobDir::create_in(temp_dir.path()).unwrap();
+        let path = job_dir.path().to_path_buf();
+        std::fs::write(path.join("concat.txt"), b"ffconcat version 1.0\n").unwrap();
+
+        let task = tokio::spawn(async move {
+            let _job_dir = job_dir;
+            std::future::pending::<()>().await;
+        });
+        tokio::task::yield_now().await;
+        assert!(path.exists());
+        task.abort();
+        let _ = task.await;
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_sweep_keeps_previews_and_new_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for area in WorkArea::ALL {
+            std::fs::create_dir_all(root.join(area.folder()).join("job")).unwrap();
+        }
+        std::fs::write(root.join("captions").join("clip.wav"), b"").unwrap();
+
+        // Everything is newer than an hour ago
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        assert_eq!(sweep(root, an_hour_ago), 0);
+
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(sweep(root, later), 3);
+        assert!(!root.join("export").join("job").exists());
+        assert!(!root.join("captions").join("clip.wav").exists());
+        assert!(root.join("preview").join("job").exists());
+    }
+}
@@ -0,0 +1,148 @@
+// End-to-end checks of the import -> edit -> export pipeline against the real
+// FFmpeg, on fixtures generated at test time. Run with:
+//   cargo test --features ffmpeg-tests --test pipeline
+// Without FFmpeg installed each test notes it and passes.
+#![cfg(feature = "ffmpeg-tests")]
+
+use clipforge::ffmpeg::export::{write_concat_file, ExportPlan};
+use clipforge::ffmpeg::fixtures::{assert_media, use_system_ffmpeg, ExpectedMedia, Fixture};
+use clipforge::ffmpeg::thumbnails::ThumbnailPaths;
+use clipforge::ffmpeg::{extract_metadata, generate_proxy, generate_thumbnail};
+use clipforge::models::clip::MediaClip;
+use clipforge::models::export::ExportSettings;
+use clipforge::models::project::ProjectSettings;
+use clipforge::models::settings::{ProxySettings, ThumbnailSettings};
+use clipforge::models::timeline::{TimelineClip, Track, TrackType};
+use std::collections::HashMap;
+use std::path::Path;
+use tempfile::TempDir;
+
+async fn import(path: &Path) -> MediaClip {
+    let path = path.to_string_lossy().to_string();
+    let metadata = extract_metadata(&path).await.unwrap();
+    MediaClip::new(
+        path,
+        metadata.duration,
+        metadata.width as i32,
+        metadata.height as i32,
+        metadata.fps,
+        metadata.codec,
+        0,
+    )
+}
+
+#[tokio::test]
+async fn extract_metadata_reads_generated_clips() {
+    if !use_system_ffmpeg() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+
+    let h264 = Fixture::h264(2.0).generate(dir.path(), "h264").unwrap();
+    let metadata = extract_metadata(&h264.to_string_lossy()).await.unwrap();
+    assert!((metadata.duration - 2.0).abs() < 0.1, "{:?}", metadata);
+    assert_eq!((metadata.width, metadata.height), (320, 240));
+    assert!((metadata.fps - 30.0).abs() < 0.01);
+    assert_eq!(metadata.codec, "h264");
+    assert!(metadata.has_audio);
+
+    let silent = Fixture::mpeg4(1.0)
+        .without_audio()
+        .generate(dir.path(), "silent")
+        .unwrap();
+    let metadata = extract_metadata(&silent.to_string_lossy()).await.unwrap();
+    assert_eq!(metadata.codec, "mpeg4");
+    assert!((metadata.fps - 25.0).abs() < 0.01);
+    assert!(!metadata.has_audio);
+}
+
+#[tokio::test]
+async fn generate_proxy_transcodes_to_h264() {
+    if !use_system_ffmpeg() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let source = Fixture::mpeg4(2.0).generate(dir.path(), "source").unwrap();
+    let proxy = dir.path().join("proxy.mp4");
+
+    let mut last_progress = 0.0;
+    generate_proxy(
+        &source.to_string_lossy(),
+        &proxy.to_string_lossy(),
+        25.0,
+        2.0,
+        &ProxySettings::default(),
+        |progress| last_progress = progress,
+    )
+    .await
+    .unwrap();
+
+    assert_media(&proxy, &ExpectedMedia::new(2.0, "h264")).await;
+    assert!(last_progress > 0.0);
+}
+
+#[tokio::test]
+async fn generate_thumbnail_writes_an_image() {
+    if !use_system_ffmpeg() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let source = Fixture::h264(1.0).generate(dir.path(), "source").unwrap();
+    let settings = ThumbnailSettings::default();
+    let paths = ThumbnailPaths::new(dir.path(), "source", &settings);
+
+    let written = generate_thumbnail(&source.to_string_lossy(), &paths, 0.5, &settings)
+        .await
+        .unwrap();
+
+    assert_eq!(written, paths);
+    assert!(std::fs::metadata(&paths.standard).unwrap().len() > 0);
+    assert!(std::fs::metadata(&paths.hidpi).unwrap().len() > 0);
+}
+
+#[tokio::test]
+async fn export_concatenates_trimmed_clips() {
+    if !use_system_ffmpeg() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let first = import(&Fixture::h264(2.0).generate(dir.path(), "first").unwrap()).await;
+    let second = import(&Fixture::h264(2.0).generate(dir.path(), "second").unwrap()).await;
+
+    // 1.5s of the first clip, then the second second of the other
+    let mut track = Track::new("Main".into(), TrackType::Main);
+    track.clips = vec![
+        TimelineClip::new(first.id.clone(), track.id.clone(), 0.0, 0.0, 1.5),
+        TimelineClip::new(second.id.clone(), track.id.clone(), 1.5, 1.0, 2.0),
+    ];
+    let tracks = vec![track];
+    let media_library = vec![first, second];
+
+    let settings = ExportSettings {
+        hardware_acceleration: false,
+        ..ExportSettings::default()
+    };
+    let plan = ExportPlan::build(
+        &tracks,
+        &media_library,
+        &settings,
+        &ProjectSettings::default(),
+        &HashMap::new(),
+    )
+    .unwrap();
+    let concat = write_concat_file(&plan.segments, dir.path()).unwrap();
+    let output = dir.path().join("export.mp4");
+    let result = plan
+        .command_builder(&concat, &output)
+        .build()
+        .output()
+        .unwrap();
+    assert!(
+        result.status.success(),
+        "export failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let probe = assert_media(&output, &ExpectedMedia::new(2.5, "h264")).await;
+    assert!(probe.format_name.unwrap_or_default().contains("mp4"));
+}
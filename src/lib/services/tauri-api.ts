@@ -1,8 +1,33 @@
 // Tauri API wrapper - provides type-safe wrappers for Tauri commands
 import { invoke as tauriInvoke } from '@tauri-apps/api';
-import type { MediaClip } from '$lib/types/clip';
-import type { Project } from '$lib/types/project';
-import type { TimelineClip, Track } from '$lib/types/timeline';
+import type { Caption } from '$lib/types/caption';
+import type { FullProbeResult, MediaClip } from '$lib/types/clip';
+import type { Marker, Project, ProjectSettings } from '$lib/types/project';
+import type {
+  ClipComment,
+  ClipEffect,
+  Comment,
+  CompoundClip,
+  FreezeEdge,
+  Redaction,
+  TimelineClip,
+  Track,
+  Transition,
+  TransitionKind,
+} from '$lib/types/timeline';
+import type {
+  ClipExportManifest,
+  ExportHistoryEntry,
+  ExportJobInfo,
+  ExportJobResponse,
+  ExportPlan,
+  ExportSettings,
+  HardwareEncoders,
+  PreflightReport,
+  SourceFrame,
+} from '$lib/types/export';
+import type { RecordingSession } from '$lib/types/recording';
+import type { AppSettings, AppSettingsPatch } from '$lib/types/settings';
 
 // Re-export invoke for general use
 export { invoke } from '@tauri-apps/api';
@@ -11,6 +36,7 @@ export { invoke } from '@tauri-apps/api';
 export async function importMediaFiles(paths: string[]): Promise<{
   clips: MediaClip[];
   errors: Array<{ path: string; error: string }>;
+  already_imported: string[];
 }> {
   try {
     return await tauriInvoke('import_media_files', { paths });
@@ -20,6 +46,25 @@ export async function importMediaFiles(paths: string[]): Promise<{
   }
 }
 
+// Download a video from an https:// link and import it; progress arrives as
+// `media_download_progress` events. Calling again with the same URL resumes
+// an interrupted download.
+export async function importMediaFromUrl(
+  url: string,
+  destination?: string
+): Promise<{
+  clips: MediaClip[];
+  errors: Array<{ path: string; error: string }>;
+  already_imported: string[];
+}> {
+  try {
+    return await tauriInvoke('import_media_from_url', { url, destination: destination ?? null });
+  } catch (error) {
+    console.error('Failed to import media from URL:', error);
+    throw error;
+  }
+}
+
 export async function getMediaMetadata(clipId: string): Promise<MediaClip> {
   try {
     return await tauriInvoke('get_media_metadata', { clipId });
@@ -29,7 +74,123 @@ export async function getMediaMetadata(clipId: string): Promise<MediaClip> {
   }
 }
 
-export async function generateThumbnailForClip(clipId: string, timestamp: number): Promise<string> {
+// Every stream, chapter and container tag of a clip's source
+export async function getMediaStreams(clipId: string): Promise<FullProbeResult> {
+  try {
+    return await tauriInvoke('get_media_streams', { clipId });
+  } catch (error) {
+    console.error('Failed to probe media streams:', error);
+    throw error;
+  }
+}
+
+// Choose the audio stream used by export and preview (null = default)
+export async function selectAudioStream(
+  clipId: string,
+  streamIndex: number | null
+): Promise<MediaClip> {
+  try {
+    return await tauriInvoke('select_audio_stream', { clipId, streamIndex });
+  } catch (error) {
+    console.error('Failed to select audio stream:', error);
+    throw error;
+  }
+}
+
+// Remove a clip from the project's library (refused while it is on the timeline)
+export async function removeMediaClip(clipId: string): Promise<void> {
+  try {
+    await tauriInvoke('remove_media_clip', { clipId });
+  } catch (error) {
+    console.error('Failed to remove media clip:', error);
+    throw error;
+  }
+}
+
+/** `media_library_changed` event payload */
+export interface MediaLibraryChangedEvent {
+  project_id: string;
+  change: 'added' | 'updated' | 'removed';
+  clip_ids: string[];
+  /** Current state of added or updated clips (empty for removals) */
+  clips: MediaClip[];
+}
+
+/** `media_clip_updated` event payload: one per changed clip, including background changes */
+export interface MediaClipUpdatedEvent {
+  project_id: string;
+  clip: MediaClip;
+}
+
+/** Current state of a clip, to refresh a view that may have missed events */
+export async function getMediaClip(clipId: string): Promise<MediaClip> {
+  try {
+    return await tauriInvoke('get_media_clip', { clipId });
+  } catch (error) {
+    console.error('Failed to get media clip:', error);
+    throw error;
+  }
+}
+
+/** `proxy_progress` event payload (throttled; drop events with a lower sequence) */
+export interface ProxyProgressEvent {
+  clip_id: string;
+  sequence: number;
+  progress: number;
+}
+
+/** `media_download_progress` event payload (throttled; drop events with a lower sequence) */
+export interface MediaDownloadProgressEvent {
+  url: string;
+  sequence: number;
+  downloaded_bytes: number;
+  total_bytes: number | null;
+}
+
+/** `thumbnail_generated` event payload (import thumbnails arrive after the clip) */
+export interface ThumbnailGeneratedEvent {
+  clip_id: string;
+  thumbnail_path: string;
+  url: string;
+  url_2x: string;
+}
+
+// Import an embedded text subtitle stream as the clip's captions
+export async function importSubtitleStream(
+  clipId: string,
+  streamIndex: number,
+  language?: string
+): Promise<Caption[]> {
+  try {
+    return await tauriInvoke('import_subtitle_stream', { clipId, streamIndex, language });
+  } catch (error) {
+    console.error('Failed to import subtitle stream:', error);
+    throw error;
+  }
+}
+
+// Clip ids whose proxies predate the current proxy recipe (re-import to regenerate)
+export async function getOutdatedProxyClips(): Promise<string[]> {
+  try {
+    return await tauriInvoke('get_outdated_proxy_clips');
+  } catch (error) {
+    console.error('Failed to get outdated proxies:', error);
+    throw error;
+  }
+}
+
+/** A clip's thumbnail and its 2x variant for HiDPI displays */
+export interface ClipThumbnail {
+  path: string;
+  url: string;
+  path_2x: string;
+  url_2x: string;
+}
+
+export async function generateThumbnailForClip(
+  clipId: string,
+  timestamp: number
+): Promise<ClipThumbnail> {
   try {
     return await tauriInvoke('generate_thumbnail_for_clip', { clipId, timestamp });
   } catch (error) {
@@ -38,11 +199,54 @@ export async function generateThumbnailForClip(clipId: string, timestamp: number
   }
 }
 
+// A clip's thumbnails at the current thumbnail settings, regenerated if stale
+export async function getClipThumbnail(clipId: string): Promise<ClipThumbnail> {
+  try {
+    return await tauriInvoke('get_clip_thumbnail', { clipId });
+  } catch (error) {
+    console.error('Failed to get thumbnail:', error);
+    throw error;
+  }
+}
+
+export interface HoverSprite {
+  path: string;
+  /** Asset URL the webview can load directly */
+  url: string;
+  frame_count: number;
+  columns: number;
+  rows: number;
+  frame_width: number;
+  frame_height: number;
+  /** Seconds between frames */
+  interval: number;
+  /** Source time of each frame, left to right then top to bottom */
+  timestamps: number[];
+}
+
+export async function getHoverThumbnails(clipId: string, count: number): Promise<HoverSprite> {
+  try {
+    return await tauriInvoke('get_hover_thumbnails', { clipId, count });
+  } catch (error) {
+    console.error('Failed to get hover thumbnails:', error);
+    throw error;
+  }
+}
+
 // Playback Commands
+export interface PlaybackSource {
+  path: string;
+  /** Asset URL the webview can load directly */
+  url: string;
+  is_proxy: boolean;
+  proxy_status: 'ready' | 'pending' | 'unavailable';
+  proxy_pending: boolean;
+}
+
 export async function loadClipForPlayback(
   clipId: string,
   useProxy: boolean = false
-): Promise<string> {
+): Promise<PlaybackSource> {
   try {
     return await tauriInvoke('load_clip_for_playback', { clipId, useProxy });
   } catch (error) {
@@ -51,10 +255,47 @@ export async function loadClipForPlayback(
   }
 }
 
+export type PlaybackSegment =
+  | {
+      kind: 'clip';
+      clip_id: string;
+      media_clip_id: string;
+      file_path: string;
+      is_proxy: boolean;
+      source_in: number;
+      source_out: number;
+      timeline_start: number;
+      timeline_end: number;
+      /** 0 holds the frame at source_in (a freeze extension) */
+      speed: number;
+      volume: number;
+    }
+  | { kind: 'gap'; timeline_start: number; timeline_end: number };
+
+export interface PlaybackSequence {
+  project_id: string;
+  revision: number;
+  duration: number;
+  main: PlaybackSegment[];
+  overlays: { track_id: string; order: number; visible: boolean; segments: PlaybackSegment[] }[];
+}
+
+export async function getPlaybackSequence(useProxies: boolean = true): Promise<PlaybackSequence> {
+  try {
+    return await tauriInvoke('get_playback_sequence', { useProxies });
+  } catch (error) {
+    console.error('Failed to get playback sequence:', error);
+    throw error;
+  }
+}
+
 // Project Commands
-export async function createNewProject(name: string): Promise<Project> {
+export async function createNewProject(
+  name: string,
+  settings?: ProjectSettings
+): Promise<Project> {
   try {
-    return await tauriInvoke('create_new_project', { name });
+    return await tauriInvoke('create_new_project', { name, settings: settings ?? null });
   } catch (error) {
     console.error('Failed to create new project:', error);
     throw error;
@@ -79,13 +320,294 @@ export async function loadProject(path: string): Promise<Project> {
   }
 }
 
+export async function saveProjectAs(
+  newPath: string,
+  newName?: string,
+  keepId?: boolean
+): Promise<Project> {
+  try {
+    return await tauriInvoke('save_project_as', { newPath, newName, keepId });
+  } catch (error) {
+    console.error('Failed to save project as:', error);
+    throw error;
+  }
+}
+
+export async function duplicateProject(sourcePath: string, newPath: string): Promise<Project> {
+  try {
+    return await tauriInvoke('duplicate_project', { sourcePath, newPath });
+  } catch (error) {
+    console.error('Failed to duplicate project:', error);
+    throw error;
+  }
+}
+
+export async function getRecentProjects(): Promise<
+  Array<{ path: string; name: string; opened_at: string }>
+> {
+  try {
+    return await tauriInvoke('get_recent_projects');
+  } catch (error) {
+    console.error('Failed to get recent projects:', error);
+    throw error;
+  }
+}
+
+export interface ImportSectionResult {
+  tracks_added: string[];
+  tracks_extended: string[];
+  clips_imported: number;
+  media_added: MediaClip[];
+  media_reused: number;
+  offset: number;
+  warnings: string[];
+  project: Project;
+}
+
+export async function importProjectSection(
+  path: string,
+  placement: 'new_tracks' | 'append_to_matching' = 'new_tracks'
+): Promise<ImportSectionResult> {
+  try {
+    return await tauriInvoke('import_project_section', { path, options: { placement } });
+  } catch (error) {
+    console.error('Failed to import project section:', error);
+    throw error;
+  }
+}
+
+export interface ArchiveProgressEvent {
+  operation: 'export' | 'import';
+  bytes_done: number;
+  bytes_total: number;
+  current_file: string;
+}
+
+export async function exportProjectArchive(
+  outputPath: string,
+  media: 'originals' | 'proxies_only' = 'originals'
+): Promise<unknown> {
+  try {
+    return await tauriInvoke('export_project_archive', { outputPath, options: { media } });
+  } catch (error) {
+    console.error('Failed to export project archive:', error);
+    throw error;
+  }
+}
+
+export async function importProjectArchive(
+  archivePath: string,
+  destinationDir: string
+): Promise<Project & { missing_media: string[] }> {
+  try {
+    return await tauriInvoke('import_project_archive', { archivePath, destinationDir });
+  } catch (error) {
+    console.error('Failed to import project archive:', error);
+    throw error;
+  }
+}
+
+export type ModificationScope = 'timeline' | 'media_library' | 'settings' | 'captions';
+
+export interface ProjectModifiedEvent {
+  project_id: string;
+  scope: ModificationScope;
+  entity_ids: string[];
+  revision: number;
+}
+
+export interface TimelineLayout {
+  project_id: string;
+  revision: number;
+  duration: number;
+  tracks: Track[];
+  /** Compound clip whose contents `tracks` shows, if one is being edited */
+  editing_compound: string | null;
+}
+
+export async function getTimelineLayout(): Promise<TimelineLayout> {
+  try {
+    return await tauriInvoke('get_timeline_layout');
+  } catch (error) {
+    console.error('Failed to get timeline layout:', error);
+    throw error;
+  }
+}
+
+export type PreviewQuality = 'draft' | 'standard' | 'high';
+
+/** Resolution factor applied to previews (`preview_quality` setting, or chosen in auto mode) */
+export type PreviewScale = 'full' | 'half' | 'quarter';
+
+/** `preview_quality_changed` event payload */
+export interface PreviewQualityChangedEvent {
+  scale: PreviewScale;
+  /** Renders can't keep up with playback ("performance mode") */
+  degraded: boolean;
+}
+
+export interface PreviewSegment {
+  index: number;
+  start: number;
+  end: number;
+  path: string;
+  url: string;
+  cached: boolean;
+}
+
+export async function renderPreviewRange(
+  start: number,
+  end: number,
+  quality: PreviewQuality = 'standard'
+): Promise<PreviewSegment[]> {
+  try {
+    return await tauriInvoke('render_preview_range', { start, end, quality });
+  } catch (error) {
+    console.error('Failed to render preview:', error);
+    throw error;
+  }
+}
+
+export interface AudioPreview {
+  start: number;
+  end: number;
+  path: string;
+  url: string;
+  cached: boolean;
+}
+
+export async function renderAudioPreview(start: number, end: number): Promise<AudioPreview> {
+  try {
+    return await tauriInvoke('render_audio_preview', { start, end });
+  } catch (error) {
+    console.error('Failed to render audio preview:', error);
+    throw error;
+  }
+}
+
+export interface ClipSegment {
+  timeline_clip_id: string;
+  path: string;
+  url: string;
+  /** Cut without re-encoding; null when an existing render was reused */
+  stream_copy: boolean | null;
+  cached: boolean;
+}
+
+export async function renderClipSegment(timelineClipId: string): Promise<ClipSegment> {
+  try {
+    return await tauriInvoke('render_clip_segment', { timelineClipId });
+  } catch (error) {
+    console.error('Failed to render clip segment:', error);
+    throw error;
+  }
+}
+
+export interface TimelineFrame {
+  time: number;
+  path: string;
+  url: string;
+  base64?: string;
+}
+
+export async function getTimelineFrame(
+  time: number,
+  options: { maxWidth?: number; composite?: boolean; base64?: boolean } = {}
+): Promise<TimelineFrame> {
+  try {
+    return await tauriInvoke('get_timeline_frame', { time, ...options });
+  } catch (error) {
+    console.error('Failed to get timeline frame:', error);
+    throw error;
+  }
+}
+
+export async function updateProjectSettings(settings: ProjectSettings): Promise<Project> {
+  try {
+    return await tauriInvoke('update_project_settings', { settings });
+  } catch (error) {
+    console.error('Failed to update project settings:', error);
+    throw error;
+  }
+}
+
+export async function isProjectDirty(): Promise<boolean> {
+  try {
+    return await tauriInvoke('is_project_dirty');
+  } catch (error) {
+    console.error('Failed to query project state:', error);
+    throw error;
+  }
+}
+
+export async function closeProject(saveFirst: boolean): Promise<void> {
+  try {
+    return await tauriInvoke('close_project', { saveFirst });
+  } catch (error) {
+    console.error('Failed to close project:', error);
+    throw error;
+  }
+}
+
+/** `close_requested_unsaved` payload: the window was closed with unsaved changes */
+export interface CloseRequestedUnsavedEvent {
+  project_id: string;
+  name: string;
+  /** Where the project was last saved (null = never saved) */
+  file_path: string | null;
+}
+
+/**
+ * Quit, unless unsaved changes or running jobs still prompt (`close_requested_*` events);
+ * `cancelJobs` cancels the jobs instead (active recordings still prompt)
+ */
+export async function exitApp(cancelJobs = false): Promise<void> {
+  try {
+    await tauriInvoke('exit_app', { cancelJobs });
+  } catch (error) {
+    console.error('Failed to exit:', error);
+    throw error;
+  }
+}
+
+export async function listProjectBackups(
+  path: string
+): Promise<Array<{ path: string; modified_at: string; size: number }>> {
+  try {
+    return await tauriInvoke('list_project_backups', { path });
+  } catch (error) {
+    console.error('Failed to list project backups:', error);
+    throw error;
+  }
+}
+
+export async function restoreProjectBackup(path: string, backupPath?: string): Promise<Project> {
+  try {
+    return await tauriInvoke('restore_project_backup', { path, backupPath });
+  } catch (error) {
+    console.error('Failed to restore project backup:', error);
+    throw error;
+  }
+}
+
+/** Load the latest auto-save of a project; it comes back dirty */
+export async function restoreAutoSave(projectId: string): Promise<Project> {
+  try {
+    return await tauriInvoke('restore_auto_save', { projectId });
+  } catch (error) {
+    console.error('Failed to restore auto-save:', error);
+    throw error;
+  }
+}
+
 // Timeline Commands
 export async function addClipToTimeline(
   mediaClipId: string,
   trackId: string,
   startTime: number,
   inPoint: number,
-  outPoint: number
+  outPoint: number,
+  speed?: number
 ): Promise<TimelineClip> {
   try {
     return await tauriInvoke('add_clip_to_timeline', {
@@ -94,6 +616,7 @@ export async function addClipToTimeline(
       startTime,
       inPoint,
       outPoint,
+      speed,
     });
   } catch (error) {
     console.error('Failed to add clip to timeline:', error);
@@ -108,6 +631,10 @@ export async function updateTimelineClip(
     inPoint?: number;
     outPoint?: number;
     trackId?: string;
+    audioOnly?: boolean;
+    videoOnly?: boolean;
+    /** 0.1 to 10 */
+    speed?: number;
   }
 ): Promise<TimelineClip> {
   try {
@@ -121,7 +648,7 @@ export async function updateTimelineClip(
 export async function splitTimelineClip(
   clipId: string,
   splitTime: number
-): Promise<{ clipBefore: TimelineClip; clipAfter: TimelineClip }> {
+): Promise<{ clip_before: TimelineClip; clip_after: TimelineClip }> {
   try {
     return await tauriInvoke('split_timeline_clip', { clipId, splitTime });
   } catch (error) {
@@ -130,20 +657,760 @@ export async function splitTimelineClip(
   }
 }
 
-export async function deleteTimelineClip(clipId: string): Promise<void> {
+/**
+ * Move a clip by `delta` seconds (negative = earlier) keeping its content;
+ * the neighbors' trims absorb the move. Rejects with a validation error
+ * naming the limiting neighbor when there isn't enough source.
+ */
+export async function slideClip(
+  clipId: string,
+  delta: number
+): Promise<{ previous: TimelineClip; clip: TimelineClip; next: TimelineClip }> {
   try {
-    return await tauriInvoke('delete_timeline_clip', { clipId });
+    return await tauriInvoke('slide_clip', { clipId, delta });
   } catch (error) {
-    console.error('Failed to delete timeline clip:', error);
+    console.error('Failed to slide clip:', error);
     throw error;
   }
 }
 
-export async function createTrack(name: string, type: 'main' | 'overlay'): Promise<Track> {
+/**
+ * Extend a clip past its media by holding its first or last frame for
+ * `extraSeconds` (at most 10, silent); 0 removes the extension
+ */
+export async function extendWithFreeze(
+  clipId: string,
+  extraSeconds: number,
+  edge: FreezeEdge
+): Promise<TimelineClip> {
   try {
-    return await tauriInvoke('create_track', { name, trackType: type });
+    return await tauriInvoke('extend_with_freeze', { clipId, extraSeconds, edge });
   } catch (error) {
-    console.error('Failed to create track:', error);
+    console.error('Failed to extend clip with a freeze frame:', error);
+    throw error;
+  }
+}
+
+/** Set a clip's notes; null or blank clears them */
+export async function setClipNotes(clipId: string, notes: string | null): Promise<TimelineClip> {
+  try {
+    return await tauriInvoke('set_clip_notes', { clipId, notes });
+  } catch (error) {
+    console.error('Failed to set clip notes:', error);
+    throw error;
+  }
+}
+
+/** Comment on a clip `timeOffset` seconds from its start */
+export async function addClipComment(
+  clipId: string,
+  timeOffset: number,
+  text: string,
+  author: string | null = null
+): Promise<Comment> {
+  try {
+    return await tauriInvoke('add_clip_comment', { clipId, timeOffset, text, author });
+  } catch (error) {
+    console.error('Failed to add clip comment:', error);
+    throw error;
+  }
+}
+
+/** Resolve a comment, or reopen it with `resolved = false` */
+export async function resolveClipComment(
+  clipId: string,
+  commentId: string,
+  resolved = true
+): Promise<Comment> {
+  try {
+    return await tauriInvoke('resolve_clip_comment', { clipId, commentId, resolved });
+  } catch (error) {
+    console.error('Failed to resolve clip comment:', error);
+    throw error;
+  }
+}
+
+/** Comments on the timeline in timeline order */
+export async function listComments(includeResolved: boolean): Promise<ClipComment[]> {
+  try {
+    return await tauriInvoke('list_comments', { includeResolved });
+  } catch (error) {
+    console.error('Failed to list comments:', error);
+    throw error;
+  }
+}
+
+/** Unresolved comments across the project */
+export async function getAllOpenComments(): Promise<ClipComment[]> {
+  try {
+    return await tauriInvoke('get_all_open_comments');
+  } catch (error) {
+    console.error('Failed to get open comments:', error);
+    throw error;
+  }
+}
+
+/** Add a marker at timeline `time` */
+export async function addMarker(time: number, label: string, color?: string): Promise<Marker> {
+  try {
+    return await tauriInvoke('add_marker', { time, label, color });
+  } catch (error) {
+    console.error('Failed to add marker:', error);
+    throw error;
+  }
+}
+
+/** Move, rename or recolor a marker; an empty color clears it */
+export async function updateMarker(
+  markerId: string,
+  updates: { time?: number; label?: string; color?: string }
+): Promise<Marker> {
+  try {
+    return await tauriInvoke('update_marker', { markerId, updates });
+  } catch (error) {
+    console.error('Failed to update marker:', error);
+    throw error;
+  }
+}
+
+export async function deleteMarker(markerId: string): Promise<void> {
+  try {
+    await tauriInvoke('delete_marker', { markerId });
+  } catch (error) {
+    console.error('Failed to delete marker:', error);
+    throw error;
+  }
+}
+
+/** YouTube-style chapter lines for an export, with the project's export settings by default */
+export async function exportChaptersText(settings?: ExportSettings): Promise<string> {
+  try {
+    return await tauriInvoke('export_chapters_text', { settings });
+  } catch (error) {
+    console.error('Failed to export chapters text:', error);
+    throw error;
+  }
+}
+
+/** Group timeline clips into a compound clip placed where they were */
+export async function createCompoundClip(
+  clipIds: string[],
+  name: string
+): Promise<{ compound: CompoundClip; clip: TimelineClip }> {
+  try {
+    return await tauriInvoke('create_compound_clip', { clipIds, name });
+  } catch (error) {
+    console.error('Failed to create compound clip:', error);
+    throw error;
+  }
+}
+
+/** Show a compound clip's contents on the timeline for editing */
+export async function enterCompound(compoundId: string): Promise<TimelineLayout> {
+  try {
+    return await tauriInvoke('enter_compound', { compoundId });
+  } catch (error) {
+    console.error('Failed to enter compound clip:', error);
+    throw error;
+  }
+}
+
+/** Store the compound clip being edited and return to the parent timeline */
+export async function exitCompound(): Promise<TimelineLayout> {
+  try {
+    return await tauriInvoke('exit_compound');
+  } catch (error) {
+    console.error('Failed to exit compound clip:', error);
+    throw error;
+  }
+}
+
+export async function setClipEffects(
+  clipId: string,
+  effects: ClipEffect[]
+): Promise<TimelineClip> {
+  try {
+    return await tauriInvoke('set_clip_effects', { clipId, effects });
+  } catch (error) {
+    console.error('Failed to set clip effects:', error);
+    throw error;
+  }
+}
+
+/** Regions must lie inside the clip's media frame */
+export async function setClipRedactions(
+  clipId: string,
+  redactions: Redaction[]
+): Promise<TimelineClip> {
+  try {
+    return await tauriInvoke('set_clip_redactions', { clipId, redactions });
+  } catch (error) {
+    console.error('Failed to set clip redactions:', error);
+    throw error;
+  }
+}
+
+export async function deleteTimelineClip(clipId: string): Promise<void> {
+  try {
+    return await tauriInvoke('delete_timeline_clip', { clipId });
+  } catch (error) {
+    console.error('Failed to delete timeline clip:', error);
+    throw error;
+  }
+}
+
+export async function createTrack(name: string, type: 'main' | 'overlay'): Promise<Track> {
+  try {
+    return await tauriInvoke('create_track', { name, trackType: type });
+  } catch (error) {
+    console.error('Failed to create track:', error);
+    throw error;
+  }
+}
+
+/** Volume is clamped to 0-2; a muted (0) track is left out of exported audio */
+export async function updateTrack(
+  trackId: string,
+  /** `color: ''` clears the track color */
+  updates: { volume?: number; visible?: boolean; locked?: boolean; color?: string }
+): Promise<Track> {
+  try {
+    return await tauriInvoke('update_track', { trackId, updates });
+  } catch (error) {
+    console.error('Failed to update track:', error);
+    throw error;
+  }
+}
+
+/**
+ * Duck a music track by `amountDb` whenever the `againstTrackIds` tracks
+ * sound, in previews and exports alike
+ */
+export async function enableTrackDucking(
+  musicTrackId: string,
+  againstTrackIds: string[],
+  amountDb: number,
+  attackMs: number,
+  releaseMs: number
+): Promise<Track> {
+  try {
+    return await tauriInvoke('enable_track_ducking', {
+      musicTrackId,
+      againstTrackIds,
+      amountDb,
+      attackMs,
+      releaseMs,
+    });
+  } catch (error) {
+    console.error('Failed to enable track ducking:', error);
+    throw error;
+  }
+}
+
+export async function disableTrackDucking(trackId: string): Promise<Track> {
+  try {
+    return await tauriInvoke('disable_track_ducking', { trackId });
+  } catch (error) {
+    console.error('Failed to disable track ducking:', error);
+    throw error;
+  }
+}
+
+/** Add a transition where `fromClipId` ends and `toClipId` starts, replacing any there */
+export async function addTransition(
+  fromClipId: string,
+  toClipId: string,
+  duration: number,
+  kind?: TransitionKind
+): Promise<Transition> {
+  try {
+    return await tauriInvoke('add_transition', { fromClipId, toClipId, duration, kind });
+  } catch (error) {
+    console.error('Failed to add transition:', error);
+    throw error;
+  }
+}
+
+export async function removeTransition(transitionId: string): Promise<void> {
+  try {
+    await tauriInvoke('remove_transition', { transitionId });
+  } catch (error) {
+    console.error('Failed to remove transition:', error);
+    throw error;
+  }
+}
+
+/** A track without its clips */
+export type TrackSummary = Omit<Track, 'clips'> & {
+  clip_count: number;
+  /** End of the track's last clip */
+  duration: number;
+};
+
+/** Tracks of the timeline shown, sorted by order, without their clips */
+export async function listTracks(): Promise<{ revision: number; tracks: TrackSummary[] }> {
+  try {
+    return await tauriInvoke('list_tracks');
+  } catch (error) {
+    console.error('Failed to list tracks:', error);
+    throw error;
+  }
+}
+
+export async function getTrack(trackId: string): Promise<{ revision: number; track: Track }> {
+  try {
+    return await tauriInvoke('get_track', { trackId });
+  } catch (error) {
+    console.error('Failed to get track:', error);
+    throw error;
+  }
+}
+
+export interface TimelineClipDetail {
+  revision: number;
+  clip: TimelineClip;
+  /** Library clip played (media clips only; null if it's gone) */
+  media_clip: MediaClip | null;
+  /** Compound clip played (compound clips only) */
+  compound: CompoundClip | null;
+}
+
+export async function getTimelineClip(clipId: string): Promise<TimelineClipDetail> {
+  try {
+    return await tauriInvoke('get_timeline_clip', { clipId });
+  } catch (error) {
+    console.error('Failed to get timeline clip:', error);
+    throw error;
+  }
+}
+
+export async function renameTrack(trackId: string, name: string): Promise<Track> {
+  try {
+    return await tauriInvoke('rename_track', { trackId, name });
+  } catch (error) {
+    console.error('Failed to rename track:', error);
+    throw error;
+  }
+}
+
+/** Move a track to `order` (0 = top); returns every track by its new order */
+export async function setTrackOrder(trackId: string, order: number): Promise<Track[]> {
+  try {
+    return await tauriInvoke('set_track_order', { trackId, order });
+  } catch (error) {
+    console.error('Failed to reorder track:', error);
+    throw error;
+  }
+}
+
+// Recording Commands
+
+/**
+ * Active recordings, for a page that lost its state (e.g. a reload)
+ *
+ * Also re-emits `recording_started` and `recording_progress` for each session.
+ */
+export async function resyncRecordingState(): Promise<RecordingSession[]> {
+  try {
+    return await tauriInvoke('resync_recording_state');
+  } catch (error) {
+    console.error('Failed to resync recording state:', error);
+    throw error;
+  }
+}
+
+/** Past recording sessions, most recent first (default 50) */
+export async function getRecordingHistory(limit?: number): Promise<RecordingSession[]> {
+  try {
+    return await tauriInvoke('get_recording_history', { limit: limit ?? null });
+  } catch (error) {
+    console.error('Failed to get recording history:', error);
+    throw error;
+  }
+}
+
+/** Recordings an earlier run left unfinished whose file still exists, to offer for recovery */
+export async function getInterruptedRecordings(): Promise<RecordingSession[]> {
+  try {
+    return await tauriInvoke('get_interrupted_recordings');
+  } catch (error) {
+    console.error('Failed to get interrupted recordings:', error);
+    throw error;
+  }
+}
+
+// Settings Commands
+export async function getAppSettings(): Promise<AppSettings> {
+  try {
+    return await tauriInvoke('get_app_settings');
+  } catch (error) {
+    console.error('Failed to get app settings:', error);
+    throw error;
+  }
+}
+
+/** Apply a partial update (nested objects merge, `null` resets a field) and return the result */
+export async function updateAppSettings(patch: AppSettingsPatch): Promise<AppSettings> {
+  try {
+    return await tauriInvoke('update_app_settings', { patch });
+  } catch (error) {
+    console.error('Failed to update app settings:', error);
+    throw error;
+  }
+}
+
+export interface FfmpegStatus {
+  /** Found, new enough and built with every required component */
+  available: boolean;
+  ffmpeg_path: string | null;
+  ffprobe_path: string | null;
+  version: string | null;
+  source: 'setting' | 'managed' | 'path' | 'common_location' | 'bundled' | null;
+  /** Version of the app-downloaded build, if installed (whether or not it is in use) */
+  managed_version: string | null;
+  missing_components: string[];
+  error: string | null;
+}
+
+export async function getFfmpegStatus(): Promise<FfmpegStatus> {
+  try {
+    return await tauriInvoke('get_ffmpeg_status');
+  } catch (error) {
+    console.error('Failed to get FFmpeg status:', error);
+    throw error;
+  }
+}
+
+/** Use the FFmpeg binary (or directory) at `path`; null searches PATH and common locations again */
+export async function setFfmpegPath(path: string | null): Promise<FfmpegStatus> {
+  try {
+    return await tauriInvoke('set_ffmpeg_path', { path });
+  } catch (error) {
+    console.error('Failed to set FFmpeg path:', error);
+    throw error;
+  }
+}
+
+/** `ffmpeg_download_progress` event payload */
+export interface FfmpegDownloadProgress {
+  stage: 'downloading' | 'verifying' | 'unpacking' | 'done';
+  downloaded_bytes: number;
+  total_bytes: number;
+}
+
+export async function downloadFfmpeg(): Promise<FfmpegStatus> {
+  try {
+    return await tauriInvoke('download_ffmpeg');
+  } catch (error) {
+    console.error('Failed to download FFmpeg:', error);
+    throw error;
+  }
+}
+
+export async function updateManagedFfmpeg(): Promise<FfmpegStatus> {
+  try {
+    return await tauriInvoke('update_managed_ffmpeg');
+  } catch (error) {
+    console.error('Failed to update managed FFmpeg:', error);
+    throw error;
+  }
+}
+
+// Background Job Commands
+
+/** Queued or running heavy FFmpeg job */
+export interface BackgroundJob {
+  id: number;
+  kind: 'export' | 'preview' | 'proxy' | 'thumbnail' | 'audio';
+  /** Clip or project the job works on */
+  label: string;
+  state: 'queued' | 'running';
+  /** 0.0 - 1.0, when the job reports it */
+  progress: number | null;
+}
+
+export async function getBackgroundJobs(): Promise<BackgroundJob[]> {
+  try {
+    return await tauriInvoke('get_background_jobs');
+  } catch (error) {
+    console.error('Failed to get background jobs:', error);
+    throw error;
+  }
+}
+
+/** Any background job, as listed in the activity center */
+export interface ActiveJob {
+  /** `<source>:<id>`, e.g. `export:3f2a...` */
+  id: string;
+  kind: 'recording' | 'export' | 'captions' | 'proxy' | 'preview' | 'thumbnail' | 'audio';
+  /** Clip, file or project the job works on */
+  label: string;
+  state: 'queued' | 'running';
+  /** 0.0 - 1.0, when the job reports it */
+  progress: number | null;
+  cancellable: boolean;
+}
+
+/** `jobs_updated` (about once a second, on change) and `close_requested_jobs_running` payload */
+export interface JobsUpdatedEvent {
+  jobs: ActiveJob[];
+}
+
+export async function getActiveJobs(): Promise<ActiveJob[]> {
+  try {
+    return await tauriInvoke('get_active_jobs');
+  } catch (error) {
+    console.error('Failed to get active jobs:', error);
+    throw error;
+  }
+}
+
+export async function cancelJob(id: string): Promise<void> {
+  try {
+    await tauriInvoke('cancel_job', { id });
+  } catch (error) {
+    console.error('Failed to cancel job:', error);
+    throw error;
+  }
+}
+
+// Log Commands
+
+/** Last `lines` lines of the application log (default 200), oldest first */
+export async function getRecentLogs(lines?: number): Promise<string[]> {
+  try {
+    return await tauriInvoke('get_recent_logs', { lines: lines ?? null });
+  } catch (error) {
+    console.error('Failed to get recent logs:', error);
+    throw error;
+  }
+}
+
+export async function openLogFolder(): Promise<void> {
+  try {
+    await tauriInvoke('open_log_folder');
+  } catch (error) {
+    console.error('Failed to open log folder:', error);
+    throw error;
+  }
+}
+
+// Startup Commands
+
+/** Why the cache could not be opened (`initialization_error` event payload) */
+export interface InitializationError {
+  stage: 'home_directory' | 'cache_directory' | 'cache_database';
+  message: string;
+  cache_dir: string | null;
+  recovery_actions: Array<'retry' | 'choose_cache_directory' | 'rebuild_cache'>;
+}
+
+/** Also emitted as `startup_status_changed` after each recovery attempt */
+export interface StartupStatus {
+  /** False while running on the in-memory fallback cache */
+  ready: boolean;
+  cache_dir: string | null;
+  error: InitializationError | null;
+  corrupt_cache_path: string | null;
+}
+
+export async function getStartupStatus(): Promise<StartupStatus> {
+  try {
+    return await tauriInvoke('get_startup_status');
+  } catch (error) {
+    console.error('Failed to get startup status:', error);
+    throw error;
+  }
+}
+
+export async function retryStartup(): Promise<StartupStatus> {
+  try {
+    return await tauriInvoke('retry_startup');
+  } catch (error) {
+    console.error('Failed to retry startup:', error);
+    throw error;
+  }
+}
+
+/** Use (and remember) another cache directory */
+export async function chooseCacheDirectory(path: string): Promise<StartupStatus> {
+  try {
+    return await tauriInvoke('choose_cache_directory', { path });
+  } catch (error) {
+    console.error('Failed to choose cache directory:', error);
+    throw error;
+  }
+}
+
+/** Move the cache database aside and start an empty one */
+export async function rebuildCache(): Promise<StartupStatus> {
+  try {
+    return await tauriInvoke('rebuild_cache');
+  } catch (error) {
+    console.error('Failed to rebuild cache:', error);
+    throw error;
+  }
+}
+
+// Cache Commands
+
+export interface MaintenanceReport {
+  /** Database size in bytes before maintenance */
+  size_before: number;
+  /** Database size in bytes after VACUUM */
+  size_after: number;
+  /** Problems found by the integrity check (empty = healthy) */
+  integrity_errors: string[];
+  /** Proxies, thumbnails and sprites deleted to get under `cache_size_cap_mb` */
+  evicted: { files: number; bytes: number };
+}
+
+/** Evict cache files past the size cap, then check, vacuum and optimize the cache database */
+export async function runCacheMaintenance(): Promise<MaintenanceReport> {
+  try {
+    return await tauriInvoke('run_cache_maintenance');
+  } catch (error) {
+    console.error('Failed to run cache maintenance:', error);
+    throw error;
+  }
+}
+
+/** What exporting the open project with `settings` would render (debugging aid) */
+export async function previewExportPlan(settings: ExportSettings): Promise<ExportPlan> {
+  try {
+    return await tauriInvoke('preview_export_plan', { settings });
+  } catch (error) {
+    console.error('Failed to plan export:', error);
+    throw error;
+  }
+}
+
+/** Check an export can run without starting it; exportTimeline refuses on any error */
+export async function preflightExport(
+  settings: ExportSettings,
+  outputPath: string
+): Promise<PreflightReport> {
+  try {
+    return await tauriInvoke('preflight_export', { settings, outputPath });
+  } catch (error) {
+    console.error('Failed to run export preflight:', error);
+    throw error;
+  }
+}
+
+/**
+ * Save the frame at `timelineTime` as a PNG or JPEG (by the path's extension), at full source
+ * resolution; fails in a gap
+ */
+export async function exportFrame(timelineTime: number, outputPath: string): Promise<SourceFrame> {
+  try {
+    return await tauriInvoke('export_frame', { timelineTime, outputPath });
+  } catch (error) {
+    console.error('Failed to export frame:', error);
+    throw error;
+  }
+}
+
+/**
+ * Export every main track clip to its own file in `directory`, from the sources, as one export
+ * job; cancel_export stops it
+ */
+export async function exportClipsIndividually(
+  directory: string,
+  settings: ExportSettings
+): Promise<ClipExportManifest> {
+  try {
+    return await tauriInvoke('export_clips_individually', { directory, settings });
+  } catch (error) {
+    console.error('Failed to export clips:', error);
+    throw error;
+  }
+}
+
+/** This session's exports: queued, rendering and finished, oldest first */
+export async function listExportJobs(): Promise<ExportJobInfo[]> {
+  try {
+    return await tauriInvoke('list_export_jobs');
+  } catch (error) {
+    console.error('Failed to list export jobs:', error);
+    throw error;
+  }
+}
+
+/** Past exports, most recent first, optionally of one project */
+export async function getExportHistory(
+  limit?: number,
+  projectId?: string
+): Promise<ExportHistoryEntry[]> {
+  try {
+    return await tauriInvoke('get_export_history', { limit, projectId });
+  } catch (error) {
+    console.error('Failed to get export history:', error);
+    throw error;
+  }
+}
+
+/** Delete all export history, returning the number of removed jobs */
+export async function clearExportHistory(): Promise<number> {
+  try {
+    return await tauriInvoke('clear_export_history');
+  } catch (error) {
+    console.error('Failed to clear export history:', error);
+    throw error;
+  }
+}
+
+/** Export a failed or cancelled job again from the current project, as a new linked job */
+export async function retryExport(jobId: string): Promise<ExportJobResponse> {
+  try {
+    return await tauriInvoke('retry_export', { jobId });
+  } catch (error) {
+    console.error('Failed to retry export:', error);
+    throw error;
+  }
+}
+
+/** Hardware H.264 encoders FFmpeg has and the one exports use (probed once) */
+export async function getHardwareEncoders(): Promise<HardwareEncoders> {
+  try {
+    return await tauriInvoke('get_hardware_encoders');
+  } catch (error) {
+    console.error('Failed to detect hardware encoders:', error);
+    throw error;
+  }
+}
+
+export type CheckStatus = 'ok' | 'warn' | 'fail';
+
+/** Fix offered for a check that isn't ok */
+export type CheckAction =
+  | 'download_ffmpeg'
+  | 'configure_whisper'
+  | 'open_permission_settings'
+  | 'choose_cache_directory'
+  | 'choose_recordings_directory'
+  | 'choose_work_directory';
+
+export interface CheckItem {
+  /** e.g. 'ffmpeg', 'whisper_model', 'camera_permission', 'cache_directory' */
+  id: string;
+  status: CheckStatus;
+  detail: string;
+  action: CheckAction | null;
+}
+
+export interface SystemCheckReport {
+  /** Worst status of any item */
+  status: CheckStatus;
+  items: CheckItem[];
+}
+
+/** Check FFmpeg, encoders, whisper, permissions and data directories */
+export async function runSystemCheck(): Promise<SystemCheckReport> {
+  try {
+    return await tauriInvoke('run_system_check');
+  } catch (error) {
+    console.error('Failed to run system check:', error);
     throw error;
   }
 }
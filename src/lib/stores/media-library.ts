@@ -13,7 +13,12 @@ export const sortedMediaLibrary = derived(mediaLibrary, ($clips) => {
 
 // Helper functions
 export function addClipToLibrary(clip: MediaClip) {
-  mediaLibrary.update((clips) => [...clips, clip]);
+  // Re-imported clips keep their id, so replace instead of duplicating
+  mediaLibrary.update((clips) =>
+    clips.some((c) => c.id === clip.id)
+      ? clips.map((c) => (c.id === clip.id ? clip : c))
+      : [...clips, clip]
+  );
 }
 
 export function removeClipFromLibrary(clipId: string) {
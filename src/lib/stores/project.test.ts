@@ -23,6 +23,8 @@ describe('Project Store', () => {
       file_path: '/path/to/project.cfp',
       version: '1.0.0',
       tracks: [],
+      compound_clips: [],
+      markers: [],
       media_library: [],
       export_settings: {
         resolution: '1080p',
@@ -57,6 +59,8 @@ describe('Project Store', () => {
       file_path: '/path/to/project.cfp',
       version: '1.0.0',
       tracks: [],
+      compound_clips: [],
+      markers: [],
       media_library: [],
       export_settings: {
         resolution: '1080p',
@@ -102,6 +106,8 @@ describe('Project Store', () => {
       file_path: '/path/to/project.cfp',
       version: '1.0.0',
       tracks: [],
+      compound_clips: [],
+      markers: [],
       media_library: [],
       export_settings: {
         resolution: '1080p',
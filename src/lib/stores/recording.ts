@@ -1,5 +1,11 @@
 import { writable, get, type Writable } from 'svelte/store';
-import type { RecordingSession, RecordingSources } from '$lib/types/recording';
+import type {
+  AutoAddTarget,
+  ChromaMode,
+  RecordingPostProcess,
+  RecordingSession,
+  RecordingSources,
+} from '$lib/types/recording';
 
 // Recording state interface
 export interface RecordingState {
@@ -19,6 +25,9 @@ export interface RecordingState {
   includeMicrophone: boolean;
   resolution: string;
   fps: number;
+  chroma: ChromaMode;
+  postProcess: RecordingPostProcess;
+  autoAddTarget: AutoAddTarget | null;
 }
 
 // Initial state
@@ -37,6 +46,13 @@ const initialState: RecordingState = {
   includeMicrophone: true,
   resolution: '1920x1080',
   fps: 30,
+  chroma: '420',
+  postProcess: {
+    trim_leading_silence: false,
+    trim_trailing_silence: false,
+    normalize_audio: false,
+  },
+  autoAddTarget: null,
 };
 
 // Create the store
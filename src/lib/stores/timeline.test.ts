@@ -56,6 +56,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     timelineStore.update((state) => ({
@@ -78,6 +79,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     const mockTrack2: Track = {
@@ -89,6 +91,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     timelineStore.update((state) => ({
@@ -112,6 +115,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     const mockClip: TimelineClip = {
@@ -122,6 +126,12 @@ describe('Timeline Store', () => {
       in_point: 0,
       out_point: 10.5,
       layer_order: 0,
+      audio_only: false,
+      video_only: false,
+      effects: [],
+      redactions: [],
+      source_kind: 'media',
+      channel_mode: 'auto',
       transform: null,
     };
 
@@ -160,6 +170,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     const mockClip: TimelineClip = {
@@ -170,6 +181,12 @@ describe('Timeline Store', () => {
       in_point: 0,
       out_point: 10.5,
       layer_order: 0,
+      audio_only: false,
+      video_only: false,
+      effects: [],
+      redactions: [],
+      source_kind: 'media',
+      channel_mode: 'auto',
       transform: null,
     };
 
@@ -208,6 +225,7 @@ describe('Timeline Store', () => {
       visible: true,
       locked: false,
       volume: 1.0,
+      color: null,
     };
 
     const mockClip1: TimelineClip = {
@@ -218,6 +236,12 @@ describe('Timeline Store', () => {
       in_point: 0,
       out_point: 10.5,
       layer_order: 0,
+      audio_only: false,
+      video_only: false,
+      effects: [],
+      redactions: [],
+      source_kind: 'media',
+      channel_mode: 'auto',
       transform: null,
     };
 
@@ -229,6 +253,12 @@ describe('Timeline Store', () => {
       in_point: 0,
       out_point: 15.0,
       layer_order: 0,
+      audio_only: false,
+      video_only: false,
+      effects: [],
+      redactions: [],
+      source_kind: 'media',
+      channel_mode: 'auto',
       transform: null,
     };
 
@@ -296,6 +326,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       const mockClip1: TimelineClip = {
@@ -306,6 +337,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 10.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -317,6 +354,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 15.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -328,6 +371,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 8.5,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -405,6 +454,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       const mockClip1: TimelineClip = {
@@ -415,6 +465,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 10.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -426,6 +482,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 15.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -437,6 +499,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 8.5,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -483,6 +551,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       const mockClip1: TimelineClip = {
@@ -493,6 +562,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 10.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -504,6 +579,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 15.0,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -515,6 +596,12 @@ describe('Timeline Store', () => {
         in_point: 0,
         out_point: 8.5,
         layer_order: 0,
+        audio_only: false,
+        video_only: false,
+        effects: [],
+        redactions: [],
+        source_kind: 'media',
+        channel_mode: 'auto',
         transform: null,
       };
 
@@ -543,6 +630,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       // Initialize with track
@@ -561,6 +649,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 9.0,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         };
 
@@ -626,6 +720,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       timelineStore.update((state) => ({
@@ -643,6 +738,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 10.0,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         })
         .mockResolvedValueOnce({
@@ -653,6 +754,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 15.0,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         })
         .mockResolvedValueOnce({
@@ -663,6 +770,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 8.5,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         });
 
@@ -725,6 +838,7 @@ describe('Timeline Store', () => {
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       timelineStore.update((state) => ({
@@ -742,6 +856,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 5.0,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         })
         .mockResolvedValueOnce({
@@ -752,6 +872,12 @@ describe('Timeline Store', () => {
           in_point: 0,
           out_point: 7.0,
           layer_order: 0,
+          audio_only: false,
+          video_only: false,
+          effects: [],
+          redactions: [],
+          source_kind: 'media',
+          channel_mode: 'auto',
           transform: null,
         });
 
@@ -789,12 +915,19 @@ describe('Timeline Store', () => {
             in_point: 0,
             out_point: 5.0,
             layer_order: 0,
+            audio_only: false,
+            video_only: false,
+            effects: [],
+            redactions: [],
+            source_kind: 'media',
+            channel_mode: 'auto',
             transform: null,
           },
         ],
         visible: true,
         locked: false,
         volume: 1.0,
+        color: null,
       };
 
       timelineStore.update((state) => ({
@@ -1,5 +1,5 @@
 import { writable, derived } from 'svelte/store';
-import type { Track, TimelineClip } from '$lib/types/timeline';
+import type { ChannelMode, Track, TimelineClip } from '$lib/types/timeline';
 import { invoke } from '@tauri-apps/api/tauri';
 
 // Timeline State Interface
@@ -69,6 +69,9 @@ const createTimelineStore = () => {
         inPoint?: number;
         outPoint?: number;
         trackId?: string;
+        audioOnly?: boolean;
+        videoOnly?: boolean;
+        channelMode?: ChannelMode;
       }
     ) => {
       try {
@@ -78,6 +81,9 @@ const createTimelineStore = () => {
           in_point: updates.inPoint,
           out_point: updates.outPoint,
           track_id: updates.trackId,
+          audio_only: updates.audioOnly,
+          video_only: updates.videoOnly,
+          channel_mode: updates.channelMode,
         };
 
         const updatedClip = await invoke<TimelineClip>('update_timeline_clip', {
@@ -186,6 +192,38 @@ const createTimelineStore = () => {
       }
     },
 
+    renameTrack: async (trackId: string, name: string) => {
+      try {
+        const track = await invoke<Track>('rename_track', { trackId, name });
+        update((state) => ({
+          ...state,
+          tracks: state.tracks.map((t) => (t.id === trackId ? { ...t, name: track.name } : t)),
+        }));
+        return track;
+      } catch (error) {
+        console.error('Failed to rename track:', error);
+        throw error;
+      }
+    },
+
+    // Move a track; every track's order may change
+    setTrackOrder: async (trackId: string, order: number) => {
+      try {
+        const reordered = await invoke<Track[]>('set_track_order', { trackId, order });
+        const orders = new Map(reordered.map((t) => [t.id, t.order]));
+        update((state) => ({
+          ...state,
+          tracks: state.tracks
+            .map((t) => ({ ...t, order: orders.get(t.id) ?? t.order }))
+            .sort((a, b) => a.order - b.order),
+        }));
+        return reordered;
+      } catch (error) {
+        console.error('Failed to reorder track:', error);
+        throw error;
+      }
+    },
+
     // Playback controls
     play: () => {
       update((state) => ({ ...state, isPlaying: true }));
@@ -221,7 +259,9 @@ export const timelineDuration = derived(tracks, ($tracks) => {
     ...$tracks.map((track) => {
       if (track.clips.length === 0) return 0;
       return Math.max(
-        ...track.clips.map((clip) => clip.start_time + (clip.out_point - clip.in_point))
+        ...track.clips.map(
+          (clip) => clip.start_time + (clip.out_point - clip.in_point) / (clip.speed ?? 1)
+        )
       );
     })
   );
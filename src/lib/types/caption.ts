@@ -1,5 +1,7 @@
 // Caption types for AI Speech-to-Text
 
+import type { ClipForgeError } from './error';
+
 export interface Caption {
   id: string;
   media_clip_id: string;
@@ -30,9 +32,12 @@ export interface CaptionGenerationRequest {
 
 export interface CaptionGenerationProgress {
   job_id: string;
+  sequence: number; // increases per job; drop events older than the last seen
   progress: number;
   status: 'extracting_audio' | 'transcribing' | 'complete' | 'error';
   message?: string;
+  /** Set on `caption_generation_error` */
+  error?: ClipForgeError;
 }
 
 export interface CaptionGenerationResult {
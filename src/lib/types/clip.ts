@@ -7,7 +7,10 @@ export interface MediaClip {
   name: string;
   source_path: string;
   proxy_path: string | null;
+  proxy_version?: number | null;
   thumbnail_path: string | null;
+  /** 2x thumbnail for HiDPI displays */
+  thumbnail_2x_path?: string | null;
   duration: number;
   resolution: string;
   width: number;
@@ -20,4 +23,51 @@ export interface MediaClip {
   has_audio: boolean;
   imported_at: string; // ISO 8601 datetime
   captions: Caption[]; // Full caption objects
+  audio_streams?: MediaStream[]; // Audio streams found at import
+  audio_stream_index?: number | null; // Selected source stream (null = default)
+  suggested_trim?: SuggestedTrim | null; // From post-recording analysis
+  loudness?: LoudnessMeasurement | null; // From post-recording analysis
+  origin_url?: string | null; // Link it was downloaded from (import_media_from_url)
+}
+
+// Source range worth keeping, with silent lead-in/tail cut off
+export interface SuggestedTrim {
+  in_point: number;
+  out_point: number;
+}
+
+// EBU R128 loudness measured by FFmpeg's loudnorm
+export interface LoudnessMeasurement {
+  integrated: number; // LUFS
+  true_peak: number; // dBTP
+  range: number; // LU
+  threshold: number; // LUFS
+  target_offset: number; // LU
+}
+
+// One stream of a media file, as reported by ffprobe
+export interface MediaStream {
+  index: number;
+  stream_type: string; // 'video' | 'audio' | 'subtitle' | 'data' | ...
+  codec: string | null;
+  language: string | null;
+  title: string | null;
+  channels: number | null;
+  channel_layout: string | null;
+}
+
+export interface ChapterInfo {
+  start: number;
+  end: number;
+  title: string | null;
+}
+
+// Every stream, chapter and container tag of a source file
+export interface FullProbeResult {
+  format_name: string | null;
+  duration: number | null;
+  bitrate: number | null;
+  tags: Record<string, string>;
+  streams: MediaStream[];
+  chapters: ChapterInfo[];
 }
@@ -0,0 +1,31 @@
+// Error object every Tauri command rejects with (and error events embed)
+
+import type { FfmpegErrorKind, PreflightReport } from './export';
+
+interface ErrorBase {
+  /** Human-readable, shown as-is */
+  message: string;
+}
+
+export type ClipForgeError =
+  | (ErrorBase & { code: 'not_found'; kind: string; id: string })
+  | (ErrorBase & { code: 'validation'; field: string })
+  | (ErrorBase & { code: 'ffmpeg_failure'; kind: FfmpegErrorKind; excerpt: string })
+  | (ErrorBase & { code: 'io' })
+  | (ErrorBase & { code: 'missing_dependency'; which: string })
+  | (ErrorBase & { code: 'unsupported' })
+  | (ErrorBase & { code: 'busy' })
+  | (ErrorBase & { code: 'network'; status: number | null })
+  | (ErrorBase & { code: 'preflight_failed'; report: PreflightReport })
+  | (ErrorBase & { code: 'cancelled' })
+  | (ErrorBase & { code: 'other' });
+
+export type ClipForgeErrorCode = ClipForgeError['code'];
+
+/** Message of a rejected `invoke`, whether it carries a ClipForgeError or a plain string */
+export function describeError(error: unknown): string {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return String(error);
+}
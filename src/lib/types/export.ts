@@ -1,8 +1,18 @@
 // Export types matching Rust models
 
+import type { ChapterInfo } from './clip';
+import type { ClipForgeError } from './error';
+
 export type ExportResolution = 'source' | '2160p' | '1440p' | '1080p' | '720p' | '480p';
 
-export type VideoCodec = 'h264' | 'hevc' | 'vp9';
+/**
+ * 'gif' loops, has no audio and is capped to `gif_max_width`; 'prores' (.mov)
+ * and 'dnxhr' (.mxf) are intermediates for finishing in another editor
+ */
+export type VideoCodec = 'h264' | 'hevc' | 'vp9' | 'gif' | 'prores' | 'dnxhr';
+
+/** ProRes profile names; DNxHR uses LB, SQ, HQ and HQX for them */
+export type IntermediateProfile = 'proxy' | 'lt' | 'standard' | 'hq';
 
 export type ExportQuality = 'high' | 'medium' | 'low';
 
@@ -16,6 +26,72 @@ export interface ExportSettings {
   audio_codec: AudioCodec;
   audio_bitrate: number;
   hardware_acceleration: boolean;
+  /** Default 'collapse'; 'black' is not supported by the export yet */
+  gap_handling?: GapHandling;
+  /** Export only this part of the timeline (seconds) */
+  range?: ExportRange | null;
+  /** How other aspect ratios fill the frame (default 'fit_pad'); output is always the exact size */
+  scaling_mode?: ScalingMode;
+  /** Output frame shape, keeping the resolution's short side (null = the resolution's own) */
+  aspect_ratio?: AspectRatio | null;
+  /** Keep clips trimmed between keyframes on the fast concat path; they start early (see warnings) */
+  fast_path?: boolean;
+  /** Widest GIF export in pixels (default 640) */
+  gif_max_width?: number;
+  /** Logo composited into a corner of every frame */
+  watermark?: WatermarkSettings | null;
+  /** ProRes/DNxHR quality tier (default 'hq') */
+  intermediate_profile?: IntermediateProfile;
+  /** Two-pass EBU R128 loudness normalization of the mixed audio */
+  normalize_audio?: boolean;
+  /** Integrated loudness to normalize to, -70 to -5 LUFS (default -16) */
+  loudness_target?: number;
+  /** Write the project's markers as chapters (not for GIF or DNxHR) */
+  chapters?: boolean;
+  /**
+   * Container tags (`title`, `artist`, `comment`, ...); names are letters, digits, _ or -.
+   * Unset title and comment default to the project name and ClipForge version; an empty value
+   * removes the tag
+   */
+  metadata?: Record<string, string>;
+  /** Render the video in parts with several FFmpeg processes and join them (not for GIF) */
+  parallel?: boolean;
+  /** Also write the timeline's captions next to the output as `<output>.srt` or `.vtt` */
+  caption_sidecar?: CaptionFormat | null;
+}
+
+export type WatermarkPosition = 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+
+/** Image (png, jpg, jpeg, webp, bmp) laid over the exported video */
+export interface WatermarkSettings {
+  image_path: string;
+  /** Default 'bottom_right' */
+  position?: WatermarkPosition;
+  /** Distance from the frame edges in output pixels (default 24) */
+  margin?: number;
+  /** 0 to 1 (default 0.8) */
+  opacity?: number;
+  /** Width as a percentage of the output width (default 15) */
+  scale_percent?: number;
+}
+
+export type GapHandling = 'collapse' | 'black';
+
+/**
+ * fit_pad: letterbox with the project background; fill_crop: crop the overflow; stretch: ignore
+ * aspect; fit_blur: fit over a blurred copy filling the frame
+ */
+export type ScalingMode = 'fit_pad' | 'fill_crop' | 'stretch' | 'fit_blur';
+
+/** 9:16 turns a 1920x1080 export into 1080x1920 */
+export type AspectRatio = '16:9' | '9:16' | '1:1';
+
+/** Subtitle format of a caption sidecar: SubRip or WebVTT */
+export type CaptionFormat = 'srt' | 'vtt';
+
+export interface ExportRange {
+  start: number;
+  end: number;
 }
 
 export interface ExportRequest {
@@ -25,10 +101,141 @@ export interface ExportRequest {
 
 export interface ExportJobResponse {
   job_id: string;
+  /** File written; GIF exports always end in .gif */
+  output_path: string;
+  /** Timeline end in seconds, gaps included */
+  timeline_duration: number;
+  /** Length of the exported file; progress is measured against it */
+  render_duration: number;
+  /** Clips the fast path starts early, because they are trimmed between keyframes */
+  warnings: TrimWarning[];
+  /** Media clips exported from their source because the proxy is gone */
+  proxy_fallbacks: string[];
+  /** Markers left out of the chapters */
+  marker_warnings: string[];
+  /** Video parts rendered by separate FFmpeg processes (0 = rendered in one piece) */
+  parallel_parts: number;
+}
+
+export interface TrimWarning {
+  clip_id: string;
+  clip_name: string;
+  /** How early the exported clip starts */
+  shift_ms: number;
+}
+
+/** One piece of an export's main track output, in order */
+export type ExportSegment =
+  | {
+      kind: 'clip';
+      clip_id: string;
+      media_clip_id: string;
+      path: string;
+      in_point: number;
+      out_point: number;
+      timeline_start: number;
+      output_start: number;
+      duration: number;
+    }
+  | { kind: 'filler'; timeline_start: number; output_start: number; duration: number };
+
+/** How an export encodes its output */
+export interface EncodeParams {
+  width: number;
+  height: number;
+  fps: number;
+  scale_filter: string;
+  /** Output pixels per project canvas pixel, for overlay transforms */
+  canvas_scale: number;
+  video_codec: string;
+  crf: number | null;
+  video_bitrate: string | null;
+  preset: string | null;
+  /** ProRes/DNxHR `-profile:v` */
+  profile: string | null;
+  /** `-threads`; null lets FFmpeg decide */
+  threads: number | null;
+  /** null for GIF, which picks its own palette */
+  pixel_format: string | null;
+  /** null when the output has no audio (GIF) */
+  audio_codec: string | null;
+  /** null for uncompressed (PCM) audio */
+  audio_bitrate: number | null;
+  audio_sample_rate: number | null;
+  /** GIF: generate a palette from the frames */
+  palette: boolean;
+  watermark: WatermarkSettings | null;
+  /** Container tags, in name order */
+  metadata: Record<string, string>;
+}
+
+/** Everything an export renders (`preview_export_plan`, for debugging) */
+export interface ExportPlan {
+  gap_handling: GapHandling;
+  range: ExportRange;
+  timeline_duration: number;
+  render_duration: number;
+  frame_accurate: boolean;
+  segments: ExportSegment[];
+  /** Separately read inputs and their filters, as planned by the backend */
+  streams: Record<string, unknown>;
+  encode: EncodeParams;
+  warnings: TrimWarning[];
+  /** Markers written as chapters, in output time */
+  chapters: ChapterInfo[];
+  marker_warnings: string[];
+}
+
+export type FileIssue = 'missing' | 'not_a_file' | 'empty' | 'unreadable';
+
+export type PreflightCheck =
+  | 'ffmpeg'
+  | 'media_reference'
+  | 'source_file'
+  | 'proxy_fallback'
+  | 'output_directory'
+  | 'disk_space';
+
+/** One problem found by an export's preflight */
+export interface PreflightIssue {
+  check: PreflightCheck;
+  /** Timeline clip affected; null for problems with the whole export */
+  clip_id: string | null;
+  media_clip_id: string | null;
+  /** File or directory at fault */
+  path: string | null;
+  /** Why the file can't be used ('source_file') */
+  file_issue: FileIssue | null;
+  message: string;
+}
+
+/** What stops an export (errors) or is worth knowing before it starts (warnings) */
+export interface PreflightReport {
+  errors: PreflightIssue[];
+  warnings: PreflightIssue[];
+  /** Rough size of the output in bytes */
+  estimated_bytes: number;
+  /** Rough render time in seconds, from earlier exports with the codec; null without any */
+  estimated_seconds: number | null;
+  /** Free space where the output goes, when it could be queried */
+  free_bytes: number | null;
+  /** Media clips whose unreadable proxy was replaced by the source */
+  proxy_fallbacks: string[];
+}
+
+/** Source frame saved by exportFrame */
+export interface SourceFrame {
+  clip_id: string;
+  media_clip_id: string;
+  /** The source file, never the proxy */
+  path: string;
+  /** Timestamp within the file */
+  source_time: number;
 }
 
 export interface ExportProgressEvent {
   job_id: string;
+  sequence: number; // increases per job; drop events older than the last seen
   progress: number; // 0.0 - 1.0
   current_frame: number;
   total_frames: number;
@@ -39,17 +246,123 @@ export interface ExportProgressEvent {
 export interface ExportCompleteEvent {
   job_id: string;
   output_path: string;
+  /** Caption file written next to the output, if `caption_sidecar` was set */
+  caption_sidecar: string | null;
+}
+
+export type FfmpegErrorKind =
+  | 'file_not_found'
+  | 'permission_denied'
+  | 'corrupt_input'
+  | 'missing_codec'
+  | 'disk_full'
+  | 'device_busy'
+  | 'odd_dimensions'
+  | 'unsupported_pixel_format'
+  | 'timeout'
+  | 'unknown';
+
+/** Classified FFmpeg failure (export, proxy and recording error events) */
+export interface FfmpegError {
+  kind: FfmpegErrorKind;
+  /** User-facing explanation */
+  message: string;
+  /** Relevant FFmpeg output */
+  excerpt: string;
 }
 
 export interface ExportErrorEvent {
   job_id: string;
+  /** User-facing message */
   error: string;
+  /** `ffmpeg_failure` with the FFmpeg kind and excerpt */
+  details: ClipForgeError;
+}
+
+/** `export_fallback`: the export failed for a known reason and is re-run once */
+export interface ExportFallbackEvent {
+  job_id: string;
+  fallback: {
+    name: 'even_dimensions' | 'ignore_decode_errors' | 'yuv420p';
+    /** What the retry changes */
+    description: string;
+  };
+  /** The failure that triggered the retry */
+  error: FfmpegError;
 }
 
 export interface ExportCancelledEvent {
   job_id: string;
 }
 
+export type ExportStatus = 'queued' | 'rendering' | 'complete' | 'cancelled' | 'failed';
+
+/** An export of this session (`list_export_jobs`) */
+export interface ExportJobInfo {
+  id: string;
+  output_path: string;
+  status: ExportStatus;
+  progress: number | null;
+  /** Exports that will start first, while queued */
+  queue_position: number | null;
+}
+
+/** A past export, kept across sessions (`get_export_history`) */
+export interface ExportHistoryEntry {
+  job_id: string;
+  project_id: string | null;
+  output_path: string;
+  settings: ExportSettings;
+  status: ExportStatus;
+  started_at: string;
+  finished_at: string | null;
+  /** Wall-clock render time in seconds */
+  duration: number | null;
+  timeline_duration: number;
+  output_size: number | null;
+  error: string | null;
+  /** Last lines FFmpeg wrote before failing */
+  error_log: string | null;
+  /** Job this one retried */
+  retry_of: string | null;
+}
+
+export interface ExportQueuedEvent {
+  job_id: string;
+  /** Exports that will start first (0 = next or right away) */
+  position: number;
+}
+
+export interface ExportStartedEvent {
+  job_id: string;
+}
+
+/** A file written by a batch clip export */
+export interface ClipExportFile {
+  clip_id: string;
+  /** Position of the clip on the main track, from 1 */
+  index: number;
+  output_path: string;
+  /** Seconds */
+  duration: number;
+}
+
+/** The job `export_clips_individually` started and the files it writes, in timeline order */
+export interface ClipExportManifest {
+  job_id: string;
+  directory: string;
+  files: ClipExportFile[];
+}
+
+/** `export_clip_progress`: one file of a batch clip export, next to the job's export_progress */
+export interface ExportClipProgressEvent {
+  job_id: string;
+  clip_id: string;
+  index: number;
+  /** 0 - 1 of this clip's file */
+  progress: number;
+}
+
 export const DEFAULT_EXPORT_SETTINGS: ExportSettings = {
   resolution: '1080p',
   codec: 'h264',
@@ -58,3 +371,13 @@ export const DEFAULT_EXPORT_SETTINGS: ExportSettings = {
   audio_bitrate: 192,
   hardware_acceleration: true,
 };
+
+/** Hardware H.264 encoders (`get_hardware_encoders`), in priority order */
+export interface HardwareEncoders {
+  /** In the FFmpeg build */
+  listed: string[];
+  /** Listed encoders that opened on this machine */
+  usable: string[];
+  /** Used with hardware acceleration on; null encodes with libx264 */
+  selected: string | null;
+}
@@ -1,5 +1,6 @@
 import type { MediaClip } from './clip';
-import type { Track } from './timeline';
+import type { ExportSettings } from './export';
+import type { CompoundClip, Track } from './timeline';
 
 export interface Project {
   id: string;
@@ -8,24 +9,35 @@ export interface Project {
   modified_at: string;
   file_path: string | null;
   version: string;
+  /** The compound clip's tracks while one is being edited */
   tracks: Track[];
+  compound_clips: CompoundClip[];
+  /** Compound clip open for editing, with the parent timeline's tracks */
+  editing_compound?: { compound_id: string; parent_tracks: Track[] };
   media_library: MediaClip[];
   export_settings: ExportSettings;
+  settings: ProjectSettings;
+  /** Timeline markers in time order */
+  markers: Marker[];
   auto_save_enabled: boolean;
   last_auto_save: string | null;
 }
 
-export interface ExportSettings {
-  resolution: Resolution;
-  codec: Codec;
-  quality: Quality;
-  fps: number | null;
-  audio_codec: AudioCodec;
-  audio_bitrate: number;
-  hardware_acceleration: boolean;
+/** A named point on the project timeline; exports can write markers as chapters */
+export interface Marker {
+  id: string;
+  /** Timeline time in seconds */
+  time: number;
+  /** Up to 100 characters */
+  label: string;
+  /** "#RRGGBB" (null = the default marker color) */
+  color: string | null;
+}
+
+export interface ProjectSettings {
+  width: number;
+  height: number;
+  fps: number;
+  background_color: string; // "#RRGGBB"
 }
 
-export type Resolution = 'source' | '2160p' | '1440p' | '1080p' | '720p' | '480p';
-export type Codec = 'h264' | 'hevc' | 'vp9';
-export type Quality = 'high' | 'medium' | 'low';
-export type AudioCodec = 'aac' | 'mp3' | 'opus';
@@ -1,5 +1,8 @@
 // Recording-related TypeScript types
 
+import type { ClipForgeError } from './error';
+import type { LoudnessMeasurement, SuggestedTrim } from './clip';
+
 export type RecordingType = 'screen' | 'webcam' | 'screen_webcam';
 
 export type RecordingStatus = 'preparing' | 'recording' | 'paused' | 'stopped' | 'failed';
@@ -17,8 +20,14 @@ export interface RecordingSession {
   audio_sources: string[];
   resolution: string;
   fps: number;
+  /** Frame rate of the finished file, when the device didn't match `fps` exactly */
+  actual_fps?: number;
+  /** Resolution of the finished file */
+  actual_resolution?: string;
   error_message: string | null;
   created_media_clip_id: string | null;
+  post_process: RecordingPostProcess;
+  auto_add_to_timeline?: AutoAddTarget;
 }
 
 export interface RecordingConfig {
@@ -28,22 +37,56 @@ export interface RecordingConfig {
   audio_sources: string[];
   microphone_device_id: string | null; // Add specific microphone selection
   settings: RecordingSettings;
+  post_process?: RecordingPostProcess;
+  auto_add_to_timeline?: AutoAddTarget | null;
+  camera?: CameraOptions;
+}
+
+/** Mirror and center-crop applied to the webcam picture */
+export interface CameraOptions {
+  mirror: boolean;
+  /** "16:9", "1:1" or "4:3"; null keeps the camera's own aspect */
+  crop_aspect: string | null;
+}
+
+/** Where to put the finished recording on the timeline */
+export interface AutoAddTarget {
+  track_id: string | null; // null = the Main track
+  /** At a time (falls back to the track end on overlap), or after the last clip */
+  at: { playhead: number } | 'end';
+}
+
+/** Analysis of the finished file; results are suggestions on the MediaClip */
+export interface RecordingPostProcess {
+  trim_leading_silence: boolean;
+  trim_trailing_silence: boolean;
+  normalize_audio: boolean;
 }
 
 export interface RecordingSettings {
   resolution: string;
   fps: number;
+  chroma?: ChromaMode;
+  /** Hardware H.264 encoding; not available with 4:4:4 */
+  hardware_encoder?: boolean;
 }
 
+/** '444' keeps colored screen text sharp (software encoder only) */
+export type ChromaMode = '420' | '444';
+
 export interface RecordingSource {
   id: string;
   name: string;
+  /** Frame rates the device can capture at; empty if unknown */
+  supported_fps: number[];
 }
 
 export interface ScreenSource {
   id: string;
   name: string;
   resolution: string;
+  /** Frame rates the screen can be captured at; empty if unknown */
+  supported_fps: number[];
 }
 
 export interface WindowSource {
@@ -85,4 +128,30 @@ export interface RecordingProgressEvent {
 export interface RecordingStoppedEvent {
   session_id: string;
   media_clip_id: string;
+  timeline_clip_ids: string[]; // Clips auto-added to the timeline
+}
+
+/** The capture runs (or ran) at a different `fps`/`resolution` than requested */
+export interface RecordingWarningEvent {
+  session_id: string;
+  message: string;
+  requested_fps: number;
+  fps: number;
+  /** Set when the warning comes from the finished file */
+  requested_resolution?: string;
+  resolution?: string;
+}
+
+/** Post-recording analysis finished; the clip has been updated */
+export interface RecordingAnalysisReadyEvent {
+  session_id: string;
+  media_clip_id: string;
+  suggested_trim: SuggestedTrim | null;
+  loudness: LoudnessMeasurement | null;
+}
+
+/** FFmpeg exited while recording; the session is marked failed */
+export interface RecordingFailedEvent {
+  session_id: string;
+  error: ClipForgeError;
 }
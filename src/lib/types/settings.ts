@@ -0,0 +1,97 @@
+// Application settings types matching Rust models
+
+import type { RecordingConfig } from './recording';
+
+/** Resolution of preview renders; 'auto' steps down while playback can't keep up */
+export type PreviewQualityMode = 'full' | 'half' | 'quarter' | 'auto';
+
+export type ThumbnailFormat = 'jpeg' | 'webp';
+
+export interface ProxySettings {
+  /** Generate proxies for non web-compatible codecs */
+  enabled: boolean;
+  max_width: number;
+  max_height: number;
+  /** x264 CRF value (lower = better quality) */
+  crf: number;
+  /** x264 preset */
+  preset: string;
+  /** kbps */
+  audio_bitrate: number;
+  /** Keyframe about every second for instant seeking (roughly 10-20% larger) */
+  scrub_optimized: boolean;
+}
+
+export interface ThumbnailSettings {
+  /** Height of the 1x thumbnail in pixels; a 2x copy is also written */
+  height: number;
+  format: ThumbnailFormat;
+}
+
+export interface WhisperSettings {
+  /** whisper.cpp executable (or a name on PATH) */
+  executable_path: string;
+  /** Model file, e.g. ggml-base.en.bin */
+  model_path: string;
+  /** Language code, 'auto' to detect */
+  default_language: string;
+  threads: number;
+}
+
+/** Application-wide settings */
+export interface AppSettings {
+  /** null = platform default */
+  recordings_dir: string | null;
+  proxy: ProxySettings;
+  /** null = ~/.clipforge/cache */
+  cache_dir: string | null;
+  /** Export temp files, caption audio and preview renders (null = system temp) */
+  work_directory: string | null;
+  /** Thumbnail/proxy cache size in megabytes; the oldest files are evicted past it */
+  cache_size_cap_mb: number;
+  /** Maximum encoder threads used by export (null = let FFmpeg decide) */
+  export_thread_cap: number | null;
+  /** Heavy FFmpeg jobs run at once (null = half the cores) */
+  max_concurrent_jobs: number | null;
+  /** Exports rendered at once; later exports wait in the queue */
+  max_concurrent_exports: number;
+  /** FFmpeg processes a parallel export renders with (null = half the cores, at least 2) */
+  parallel_export_workers: number | null;
+  gif_max_seconds: number;
+  /** Applied on restart */
+  thumbnail_workers: number;
+  thumbnail: ThumbnailSettings;
+  /** 0 = disabled */
+  autosave_interval_secs: number;
+  /** Per-project auto-save space in megabytes (0 = only the count limit) */
+  autosave_max_mb: number;
+  whisper: WhisperSettings;
+  /** Action id -> accelerator, e.g. 'split_clip' -> 'S' */
+  hotkeys: Record<string, string>;
+  preview_quality: PreviewQualityMode;
+  /** FFmpeg binary or its directory (null = search PATH and common locations) */
+  ffmpeg_path: string | null;
+  /** HTTPS URL of the managed FFmpeg manifest (null = downloads disabled) */
+  ffmpeg_manifest_url: string | null;
+  /** Largest file `importMediaFromUrl` downloads, in megabytes */
+  max_download_mb: number;
+  /** Write full FFmpeg stderr to the log file */
+  debug_logging: boolean;
+  /** Cancel jobs on window close instead of asking (active recordings always ask) */
+  cancel_jobs_on_exit: boolean;
+  /** Defaults of the recording panel */
+  last_recording_config: RecordingConfig | null;
+}
+
+/** JSON merge patch: nested objects are merged and `null` resets a field to its default */
+export type MergePatch<T> = {
+  [K in keyof T]?:
+    | (NonNullable<T[K]> extends unknown[]
+        ? T[K]
+        : NonNullable<T[K]> extends object
+          ? MergePatch<NonNullable<T[K]>>
+          : T[K])
+    | null;
+};
+
+export type AppSettingsPatch = MergePatch<AppSettings>;
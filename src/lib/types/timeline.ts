@@ -7,6 +7,36 @@ export interface Track {
   visible: boolean;
   locked: boolean;
   volume: number;
+  /** Header swatch color "#RRGGBB" */
+  color: string | null;
+  /** Automatic gain reduction while other tracks sound */
+  ducking?: TrackDucking | null;
+  /** Transitions where its clips meet */
+  transitions?: Transition[];
+}
+
+export type TransitionKind = 'crossfade';
+
+/**
+ * Transition between two adjacent clips of a track. The later clip starts
+ * `duration` seconds earlier in exports, overlapping the earlier one.
+ */
+export interface Transition {
+  id: string;
+  kind: TransitionKind;
+  /** Overlap in seconds, 0.1 to 10 */
+  duration: number;
+  from_clip_id: string;
+  to_clip_id: string;
+}
+
+/** Ducking of a track (music) under others (narration) */
+export interface TrackDucking {
+  against_track_ids: string[];
+  /** Reduction while they sound, up to 40 dB */
+  amount_db: number;
+  attack_ms: number;
+  release_ms: number;
 }
 
 export type TrackType = 'main' | 'overlay';
@@ -20,8 +50,87 @@ export interface TimelineClip {
   out_point: number;
   layer_order: number;
   transform: Transform | null;
+  /** Only the clip's audio is used; exports show black */
+  audio_only: boolean;
+  /** Only the clip's video is used; its audio is silenced */
+  video_only: boolean;
+  /** Applied in stage order (crop, flips, eq, blur) regardless of list order */
+  effects: ClipEffect[];
+  /** Regions hidden before effects apply */
+  redactions: Redaction[];
+  /** 'compound': media_clip_id holds a compound clip id; in/out trim its contents */
+  source_kind: ClipSourceKind;
+  /** How the audio channels reach the stereo mix (mono always plays on both sides) */
+  channel_mode: ChannelMode;
+  /** First or last frame held (silently) beyond the media; counts toward the duration */
+  freeze_extension?: FreezeExtension | null;
+  notes?: string | null;
+  /** Review comments, anchored to source frames (they follow trims) */
+  comments?: Comment[];
+  /** Playback rate, 0.1 to 10 (default 1); the clip lasts (out - in) / speed */
+  speed?: number;
+}
+
+export interface Comment {
+  id: string;
+  /** Seconds from the clip start */
+  time_offset: number;
+  text: string;
+  author: string | null;
+  created_at: string;
+  resolved: boolean;
+  /** The commented frame was trimmed out of the clip */
+  orphaned: boolean;
+}
+
+/** A comment with the clip it's on (review sidebar) */
+export interface ClipComment {
+  clip_id: string;
+  track_id: string;
+  /** Timeline time the comment points at */
+  time: number;
+  comment: Comment;
+}
+
+export type FreezeEdge = 'start' | 'end';
+
+export interface FreezeExtension {
+  edge: FreezeEdge;
+  /** Seconds, at most 10 */
+  duration: number;
+}
+
+export type ClipSourceKind = 'media' | 'compound';
+
+export type ChannelMode = 'auto' | 'mono_to_stereo' | 'left_only' | 'right_only' | 'swap';
+
+/** Timeline clips grouped into a sub-timeline */
+export interface CompoundClip {
+  id: string;
+  name: string;
+  /** Tracks the clips came from, with times relative to the compound's start */
+  tracks: Track[];
 }
 
+/** Rectangle in source pixels; start/end are seconds from the clip's timeline start */
+export interface Redaction {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  start: number;
+  end: number;
+  kind: 'blur' | 'box';
+}
+
+/** Visual clip effect; crop edges are fractions of the frame (0-1) */
+export type ClipEffect =
+  | { type: 'crop'; left: number; right: number; top: number; bottom: number }
+  | { type: 'flip_h' }
+  | { type: 'flip_v' }
+  | { type: 'gaussian_blur'; sigma: number }
+  | { type: 'eq'; brightness: number; contrast: number; saturation: number };
+
 export interface Transform {
   x: number;
   y: number;